@@ -0,0 +1,150 @@
+//! Benchmarks of the hot paths of editing: updating the world with a keystroke, reading
+//! a cached source, hashing pages to find the changed ones, and rendering pages to SVG.
+//!
+//! Run with `cargo bench --features bench`.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use typst::layout::PagedDocument;
+use typst::syntax::{FileId, VirtualPath};
+use typst::World;
+use typstudio_lib::bench::{IncrementalRenderer, ProjectWorld, TypstEngine};
+
+/// A thesis-like document of `chapters` chapters, each with prose, math, a list and a
+/// table, which lays out to about two pages per chapter.
+fn document(chapters: usize) -> String {
+    let mut source = String::from(
+        "#set page(paper: \"a4\", numbering: \"1\")\n\
+         #set heading(numbering: \"1.1\")\n\
+         #set par(justify: true)\n\n",
+    );
+    for chapter in 1..=chapters {
+        source.push_str(&format!("= Chapter {}\n", chapter));
+        for paragraph in 0..6 {
+            source.push_str(&format!(
+                "Paragraph {} of chapter {} discusses the _results_ at length. {}\n\n",
+                paragraph,
+                chapter,
+                "The measurements agree with the model within the expected error. ".repeat(6)
+            ));
+        }
+        source.push_str(&format!(
+            "$ sum_(k=1)^{} k^2 = ({} ({} + 1) (2 dot {} + 1)) / 6 $\n\n",
+            chapter, chapter, chapter, chapter
+        ));
+        source.push_str("- First finding\n- Second finding\n  - with a detail\n\n");
+        source.push_str("#table(columns: 3, [*Run*], [*Mean*], [*Error*],\n");
+        for run in 0..8 {
+            source.push_str(&format!(
+                "  [{}], [{}.{}], [0.{}],\n",
+                run, chapter, run, run
+            ));
+        }
+        source.push_str(")\n\n");
+    }
+    source
+}
+
+struct Fixture {
+    world: ProjectWorld,
+    main: FileId,
+    content: String,
+    document: PagedDocument,
+    _dir: TempDir,
+}
+
+struct TempDir(PathBuf);
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn fixture(chapters: usize) -> Fixture {
+    let dir = std::env::temp_dir().join(format!(
+        "typstudio-bench-{}-{}",
+        std::process::id(),
+        chapters
+    ));
+    fs::create_dir_all(&dir).unwrap();
+    let content = document(chapters);
+    fs::write(dir.join("main.typ"), &content).unwrap();
+
+    let mut world = ProjectWorld::with_engine(dir.clone(), Arc::new(TypstEngine::embedded()));
+    world.set_main_path(VirtualPath::new("/main.typ"));
+    let document = typst::compile::<PagedDocument>(&world)
+        .output
+        .expect("the benchmark document failed to compile");
+    Fixture {
+        main: world.main(),
+        world,
+        content,
+        document,
+        _dir: TempDir(dir),
+    }
+}
+
+fn bench_world(c: &mut Criterion) {
+    let fixture = fixture(10);
+    let edited = fixture.content.replacen("discusses", "examines", 1);
+
+    c.bench_function("slot_update", |b| {
+        let mut toggle = false;
+        b.iter(|| {
+            toggle = !toggle;
+            let content = if toggle { &edited } else { &fixture.content };
+            fixture
+                .world
+                .slot_update("/main.typ", Some(content.clone()))
+                .unwrap()
+        })
+    });
+
+    fixture.world.source(fixture.main).unwrap();
+    c.bench_function("source_cache_hit", |b| {
+        b.iter(|| fixture.world.source(black_box(fixture.main)).unwrap())
+    });
+}
+
+fn bench_renderer(c: &mut Criterion) {
+    let fixture = fixture(10);
+    let pages = &fixture.document.pages;
+
+    let mut renderer = IncrementalRenderer::new();
+    for (i, page) in pages.iter().enumerate() {
+        renderer.render_page(i, page);
+    }
+    c.bench_function("changed_pages_hashing", |b| {
+        b.iter(|| renderer.get_changed_pages(black_box(&fixture.document)))
+    });
+
+    c.bench_function("render_page_svg", |b| {
+        b.iter_batched(
+            IncrementalRenderer::new,
+            |mut renderer| renderer.render_page(0, &pages[0]),
+            BatchSize::SmallInput,
+        )
+    });
+
+    c.bench_function("render_page_cached", |b| {
+        b.iter(|| renderer.render_page(0, black_box(&pages[0])))
+    });
+
+    c.bench_function("render_document_svg", |b| {
+        b.iter_batched(
+            IncrementalRenderer::new,
+            |mut renderer| {
+                for (i, page) in pages.iter().enumerate() {
+                    renderer.render_page(i, page);
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, bench_world, bench_renderer);
+criterion_main!(benches);
@@ -0,0 +1,285 @@
+use crate::project::Project;
+use crate::snippets::{all_snippets, SnippetEntry};
+use serde::{Deserialize, Serialize};
+
+/// How a document action changes the text around the selection.
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ActionEffect {
+    /// Surrounds the selection, eg. with `*` for bold.
+    Wrap { before: String, after: String },
+    /// Replaces the selection with `text`, placing the cursor `cursor` characters into it
+    /// or after it.
+    Insert {
+        text: String,
+        #[serde(default)]
+        cursor: Option<usize>,
+    },
+    /// Changes the level of the headings on the selected lines, turning lines into
+    /// headings or back into text at the ends.
+    HeadingLevel { delta: i32 },
+}
+
+/// An editing action shown in the toolbar, the Format menu and the command palette.
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
+pub struct DocumentAction {
+    pub id: String,
+    pub title: String,
+    /// Related actions share a group, eg. `format` or `insert`.
+    #[serde(default)]
+    pub group: String,
+    #[serde(default)]
+    pub keybinding: Option<String>,
+    pub effect: ActionEffect,
+}
+
+fn wrap(id: &str, title: &str, before: &str, after: &str) -> DocumentAction {
+    DocumentAction {
+        id: id.into(),
+        title: title.into(),
+        group: "format".into(),
+        keybinding: None,
+        effect: ActionEffect::Wrap {
+            before: before.into(),
+            after: after.into(),
+        },
+    }
+}
+
+fn insert(id: &str, title: &str, text: &str, cursor: usize) -> DocumentAction {
+    DocumentAction {
+        id: id.into(),
+        title: title.into(),
+        group: "insert".into(),
+        keybinding: None,
+        effect: ActionEffect::Insert {
+            text: text.into(),
+            cursor: Some(cursor),
+        },
+    }
+}
+
+fn heading(id: &str, title: &str, delta: i32) -> DocumentAction {
+    DocumentAction {
+        id: id.into(),
+        title: title.into(),
+        group: "structure".into(),
+        keybinding: None,
+        effect: ActionEffect::HeadingLevel { delta },
+    }
+}
+
+fn builtin_actions() -> Vec<DocumentAction> {
+    vec![
+        wrap("bold", "Bold", "*", "*"),
+        wrap("italic", "Italic", "_", "_"),
+        wrap("underline", "Underline", "#underline[", "]"),
+        wrap("strike", "Strikethrough", "#strike[", "]"),
+        wrap("code", "Inline Code", "`", "`"),
+        wrap("math", "Inline Math", "$", "$"),
+        wrap("link", "Link", "#link(\"\")[", "]"),
+        heading("heading_increase", "Increase Heading Level", 1),
+        heading("heading_decrease", "Decrease Heading Level", -1),
+        insert(
+            "insert_figure",
+            "Insert Figure",
+            "#figure(\n  image(\"\"),\n  caption: [],\n)",
+            18,
+        ),
+        insert(
+            "insert_table",
+            "Insert Table",
+            "#table(\n  columns: 2,\n  [], [],\n)",
+            25,
+        ),
+        insert("insert_equation", "Insert Equation", "$ $", 2),
+        insert("insert_list", "Insert List", "- ", 2),
+        insert(
+            "insert_page_break",
+            "Insert Page Break",
+            "#pagebreak()\n",
+            13,
+        ),
+    ]
+}
+
+/// Inserts the snippet's body, its placeholders replaced by their names, with the cursor
+/// at the first placeholder.
+fn snippet_action(entry: &SnippetEntry) -> DocumentAction {
+    let snippet = &entry.snippet;
+    let body: Vec<char> = snippet.body.chars().collect();
+    let mut text = String::new();
+    let mut cursor = None;
+    let mut last = 0;
+    for placeholder in &entry.placeholders {
+        text.extend(&body[last..placeholder.start]);
+        cursor.get_or_insert(char_len(&text));
+        text.push_str(&placeholder.name);
+        last = placeholder.end;
+    }
+    text.extend(&body[last..]);
+    DocumentAction {
+        id: format!("snippet:{}", snippet.id),
+        title: match snippet.description.as_str() {
+            "" => format!("Insert Snippet {}", snippet.prefix),
+            description => format!("Insert Snippet {}: {}", snippet.prefix, description),
+        },
+        group: "snippets".into(),
+        keybinding: None,
+        effect: ActionEffect::Insert { text, cursor },
+    }
+}
+
+/// The built-in actions followed by the open project's own, which replace built-in
+/// actions of the same id, and the snippets.
+pub fn document_actions(project: Option<&Project>) -> Vec<DocumentAction> {
+    let mut actions = builtin_actions();
+    if let Some(project) = project {
+        for action in &project.config.read().unwrap().actions {
+            match actions.iter_mut().find(|a| a.id == action.id) {
+                Some(existing) => *existing = action.clone(),
+                None => actions.push(action.clone()),
+            }
+        }
+    }
+    actions.extend(all_snippets(project).iter().map(snippet_action));
+    actions
+}
+
+/// A replacement of `start..end` with `text`, then selecting `selection`. Offsets are in
+/// characters; the selection is in the edited text.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ActionEdit {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+    pub selection: (usize, usize),
+}
+
+fn char_len(text: &str) -> usize {
+    text.chars().count()
+}
+
+/// The byte index of the `chars`th character of `text`.
+fn byte_index(text: &str, chars: usize) -> usize {
+    text.char_indices()
+        .nth(chars)
+        .map_or(text.len(), |(index, _)| index)
+}
+
+/// Applies `effect` to the selection `start..end` of `text`.
+pub fn apply_action(effect: &ActionEffect, text: &str, start: usize, end: usize) -> ActionEdit {
+    let (start, end) = (start.min(end), start.max(end));
+    let selected = &text[byte_index(text, start)..byte_index(text, end)];
+    match effect {
+        ActionEffect::Wrap { before, after } => {
+            let inner = start + char_len(before);
+            ActionEdit {
+                start,
+                end,
+                text: format!("{}{}{}", before, selected, after),
+                selection: (inner, inner + char_len(selected)),
+            }
+        }
+        ActionEffect::Insert {
+            text: insert,
+            cursor,
+        } => {
+            let cursor = start + cursor.unwrap_or(char_len(insert)).min(char_len(insert));
+            ActionEdit {
+                start,
+                end,
+                text: insert.clone(),
+                selection: (cursor, cursor),
+            }
+        }
+        ActionEffect::HeadingLevel { delta } => {
+            // Whole lines, from the start of the first selected line.
+            let line_start = text[..byte_index(text, start)]
+                .rfind('\n')
+                .map_or(0, |i| i + 1);
+            let end_byte = byte_index(text, end);
+            let line_end = text[end_byte..]
+                .find('\n')
+                .map_or(text.len(), |i| end_byte + i);
+            let lines: Vec<String> = text[line_start..line_end]
+                .split('\n')
+                .map(|line| shift_heading(line, *delta))
+                .collect();
+            let replacement = lines.join("\n");
+            let first = char_len(&text[..line_start]);
+            ActionEdit {
+                start: first,
+                end: first + char_len(&text[line_start..line_end]),
+                selection: (first, first + char_len(&replacement)),
+                text: replacement,
+            }
+        }
+    }
+}
+
+fn shift_heading(line: &str, delta: i32) -> String {
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let rest = line.trim_start();
+    let level = rest.chars().take_while(|&c| c == '=').count();
+    // `==` without a following space isn't a heading.
+    let (level, body) = match rest[level..].strip_prefix(' ') {
+        Some(body) if level > 0 => (level as i32, body),
+        _ if rest.trim().is_empty() => return line.to_string(),
+        _ => (0, rest),
+    };
+    match (level + delta).clamp(0, 6) {
+        0 => format!("{}{}", indent, body),
+        level => format!("{}{} {}", indent, "=".repeat(level as usize), body),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_action, snippet_action, ActionEffect};
+    use crate::snippets::{Snippet, SnippetEntry, SnippetScope};
+
+    #[test]
+    fn test_apply_action() {
+        let bold = ActionEffect::Wrap {
+            before: "*".into(),
+            after: "*".into(),
+        };
+        let edit = apply_action(&bold, "a wörd here", 2, 6);
+        assert_eq!((edit.start, edit.end), (2, 6));
+        assert_eq!(edit.text, "*wörd*");
+        assert_eq!(edit.selection, (3, 7));
+
+        let increase = ActionEffect::HeadingLevel { delta: 1 };
+        let edit = apply_action(&increase, "intro\n= Title\nText", 8, 15);
+        assert_eq!(edit.text, "== Title\n= Text");
+        assert_eq!((edit.start, edit.end), (6, 18));
+
+        let decrease = ActionEffect::HeadingLevel { delta: -1 };
+        let edit = apply_action(&decrease, "= Title", 0, 0);
+        assert_eq!(edit.text, "Title");
+    }
+
+    #[test]
+    fn test_snippet_action() {
+        let entry = SnippetEntry::new(
+            Snippet {
+                id: "fig".into(),
+                prefix: "fig".into(),
+                description: "Figure".into(),
+                body: "#figure(${body}, caption: [${caption}])".into(),
+            },
+            SnippetScope::User,
+        );
+        let action = snippet_action(&entry);
+        assert_eq!(action.id, "snippet:fig");
+        assert_eq!(action.title, "Insert Snippet fig: Figure");
+        assert_eq!(
+            action.effect,
+            ActionEffect::Insert {
+                text: "#figure(body, caption: [caption])".into(),
+                cursor: Some(8),
+            }
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+use typst::syntax::ast::{self, AstNode};
+use typst::syntax::{LinkedNode, Side, Source, SyntaxKind};
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Where the cursor is when a continuation is requested. `offset` is a byte offset.
+pub struct ContinuationRequest {
+    pub path: String,
+    pub source: Source,
+    pub offset: usize,
+}
+
+impl ContinuationRequest {
+    pub fn prefix(&self) -> &str {
+        &self.source.text()[..self.offset]
+    }
+
+    pub fn suffix(&self) -> &str {
+        &self.source.text()[self.offset..]
+    }
+}
+
+/// Suggests text to insert at the cursor, shown as ghost text in the editor.
+pub trait ContinuationProvider: Send + Sync {
+    fn suggest<'a>(&'a self, request: &'a ContinuationRequest) -> BoxFuture<'a, Option<String>>;
+}
+
+/// Suggests structural continuations from the syntax tree: the next table row, the
+/// next list marker, or the delimiters that close the enclosing environments.
+pub struct HeuristicProvider;
+
+impl ContinuationProvider for HeuristicProvider {
+    fn suggest<'a>(&'a self, request: &'a ContinuationRequest) -> BoxFuture<'a, Option<String>> {
+        Box::pin(async move { heuristic_continuation(&request.source, request.offset) })
+    }
+}
+
+pub fn heuristic_continuation(source: &Source, offset: usize) -> Option<String> {
+    let text = source.text();
+    let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line_before = &text[line_start..offset];
+    let at_line_start = line_before.trim().is_empty();
+    let at_line_end = text[offset..].split('\n').next()?.trim().is_empty();
+
+    let root = LinkedNode::new(source.root());
+    let leaf = root.leaf_at(offset, Side::Before);
+
+    if at_line_start && line_start > 0 {
+        let prev_start = text[..line_start - 1].rfind('\n').map_or(0, |i| i + 1);
+        let prev_line = &text[prev_start..line_start - 1];
+
+        if let Some(leaf) = &leaf {
+            if let Some(row) = table_row(leaf, prev_start..line_start - 1) {
+                return Some(row);
+            }
+        }
+        if let Some(marker) = list_marker(prev_line, line_before) {
+            return Some(marker);
+        }
+    }
+
+    if at_line_end {
+        return leaf.and_then(|leaf| closing_delimiters(&leaf));
+    }
+    None
+}
+
+/// Repeats the cell structure of the previous line inside `table(...)` or `grid(...)`.
+fn table_row(leaf: &LinkedNode, prev_line: std::ops::Range<usize>) -> Option<String> {
+    let args = std::iter::successors(Some(leaf.clone()), |n| n.parent().cloned())
+        .find(|n| n.kind() == SyntaxKind::Args)?;
+    let call = args.parent()?.cast::<ast::FuncCall>()?;
+    let name = match call.callee() {
+        ast::Expr::Ident(ident) => ident.get().to_string(),
+        ast::Expr::FieldAccess(access) => access.field().get().to_string(),
+        _ => return None,
+    };
+    if name != "table" && name != "grid" {
+        return None;
+    }
+
+    let cells = args
+        .children()
+        .filter(|child| {
+            child.kind() == SyntaxKind::ContentBlock
+                && child.offset() >= prev_line.start
+                && child.offset() < prev_line.end
+        })
+        .count();
+    (cells > 0).then(|| vec!["[]"; cells].join(", ") + ",")
+}
+
+/// Continues a bullet, numbered, or term list with the previous line's marker.
+fn list_marker(prev_line: &str, line_before: &str) -> Option<String> {
+    let indent_len = prev_line.len() - prev_line.trim_start().len();
+    if line_before.len() != indent_len {
+        return None;
+    }
+    let item = prev_line.trim_start();
+
+    for marker in ["- ", "+ ", "/ "] {
+        if let Some(rest) = item.strip_prefix(marker) {
+            return (!rest.trim().is_empty()).then(|| marker.to_string());
+        }
+    }
+
+    let digits = item.chars().take_while(char::is_ascii_digit).count();
+    let rest = item[digits..].strip_prefix(". ")?;
+    if digits == 0 || rest.trim().is_empty() {
+        return None;
+    }
+    let number: u64 = item[..digits].parse().ok()?;
+    Some(format!("{}. ", number + 1))
+}
+
+/// The delimiters that would close every environment left open at the cursor. The
+/// parser turns an unclosed opening delimiter into an error node, so that's what we
+/// look for among the first children of the ancestors.
+fn closing_delimiters(leaf: &LinkedNode) -> Option<String> {
+    let mut closers = String::new();
+    for node in std::iter::successors(Some(leaf.clone()), |n| n.parent().cloned()) {
+        let Some(first) = node.children().next() else {
+            continue;
+        };
+        if first.kind() != SyntaxKind::Error {
+            continue;
+        }
+        closers.push_str(match first.text().as_str() {
+            "(" => ")",
+            "[" => "]",
+            "{" => "}",
+            "$" => "$",
+            _ => continue,
+        });
+    }
+    (!closers.is_empty()).then_some(closers)
+}
+
+#[derive(Serialize)]
+struct ModelRequest<'a> {
+    path: &'a str,
+    prefix: &'a str,
+    suffix: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ModelResponse {
+    text: String,
+}
+
+/// Asks an external model server for a continuation. The endpoint receives
+/// `{"path", "prefix", "suffix"}` as JSON and answers with `{"text"}`.
+pub struct ModelProvider {
+    endpoint: String,
+    client: reqwest::Client,
+}
+
+impl ModelProvider {
+    pub fn new(endpoint: String) -> Self {
+        Self {
+            endpoint,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn request(&self, request: &ContinuationRequest) -> Result<String, reqwest::Error> {
+        let body = ModelRequest {
+            path: &request.path,
+            prefix: request.prefix(),
+            suffix: request.suffix(),
+        };
+        let bytes = self
+            .client
+            .post(&self.endpoint)
+            .timeout(Duration::from_secs(3))
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_vec(&body).unwrap_or_default())
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        Ok(serde_json::from_slice::<ModelResponse>(&bytes)
+            .map(|r| r.text)
+            .unwrap_or_default())
+    }
+}
+
+impl ContinuationProvider for ModelProvider {
+    fn suggest<'a>(&'a self, request: &'a ContinuationRequest) -> BoxFuture<'a, Option<String>> {
+        Box::pin(async move {
+            match self.request(request).await {
+                Ok(text) if !text.is_empty() => Some(text),
+                Ok(_) => None,
+                Err(e) => {
+                    log::debug!("continuation endpoint {} failed: {}", self.endpoint, e);
+                    None
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::heuristic_continuation;
+    use typst::syntax::Source;
+
+    fn suggest(text: &str) -> Option<String> {
+        let offset = text.find('|').unwrap();
+        let text = text.replacen('|', "", 1);
+        heuristic_continuation(&Source::detached(text), offset)
+    }
+
+    #[test]
+    fn test_heuristic_continuation() {
+        assert_eq!(suggest("- one\n|").as_deref(), Some("- "));
+        assert_eq!(suggest("  3. three\n  |").as_deref(), Some("4. "));
+        assert_eq!(suggest("#table(\n  [a], [b], [c],\n  |\n)").as_deref(), Some("[], [], [],"));
+        assert_eq!(suggest("#figure(caption: [x|").as_deref(), Some("])"));
+        assert_eq!(suggest("#figure(caption: [x]|)"), None);
+    }
+}
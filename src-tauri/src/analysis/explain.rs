@@ -0,0 +1,240 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use typst::syntax::ast::{self, AstNode};
+use typst::syntax::{LinkedNode, Source};
+use typst::World;
+
+/// An edit the editor can apply to fix a diagnostic.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum QuickFix {
+    /// Create the missing file, relative to the project root.
+    CreateFile { path: PathBuf },
+    /// Replace the diagnostic's range with `text`.
+    Replace { text: String },
+}
+
+/// What a diagnostic typst reports often means and how to resolve it.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Explanation {
+    pub summary: String,
+    pub suggestions: Vec<String>,
+    /// Documentation to read on.
+    pub links: Vec<String>,
+    pub fixes: Vec<QuickFix>,
+}
+
+const BINDINGS_DOCS: &str = "https://typst.app/docs/reference/scripting/#bindings";
+const PATHS_DOCS: &str = "https://typst.app/docs/reference/syntax/#paths";
+const FUNCTION_DOCS: &str = "https://typst.app/docs/reference/foundations/function/";
+
+/// Explains a diagnostic by its `message`, or `None` if it isn't one of the common ones.
+/// `root` is the project root missing files are created in and `names` lists the names
+/// in scope, to correct misspelled variables.
+pub fn explain_diagnostic(
+    message: &str,
+    root: &Path,
+    names: impl FnOnce() -> Vec<String>,
+) -> Option<Explanation> {
+    if let Some(name) = message.strip_prefix("unknown variable: ") {
+        let closest = closest_name(name, &names());
+        let mut suggestions = vec![format!(
+            "define it first, eg. `#let {} = ...`, or import it from the file that does",
+            name
+        )];
+        if let Some(closest) = &closest {
+            suggestions.insert(0, format!("did you mean `{}`?", closest));
+        }
+        return Some(Explanation {
+            summary: format!("`{}` is not defined where it is used.", name),
+            suggestions,
+            links: vec![BINDINGS_DOCS.to_string()],
+            fixes: closest
+                .map(|text| QuickFix::Replace { text })
+                .into_iter()
+                .collect(),
+        });
+    }
+
+    if let Some(searched) = message
+        .strip_prefix("file not found (searched at ")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        let missing = Path::new(searched).strip_prefix(root).ok();
+        // Only sources are worth creating empty, not eg. images.
+        let fixes = missing
+            .filter(|path| path.extension().is_some_and(|ext| ext == "typ"))
+            .map(|path| QuickFix::CreateFile {
+                path: path.to_path_buf(),
+            })
+            .into_iter()
+            .collect();
+        return Some(Explanation {
+            summary: match missing {
+                Some(path) => format!("There is no file `{}` in the project.", path.display()),
+                None => format!("There is no file at `{}`.", searched),
+            },
+            suggestions: vec![
+                "paths are relative to the file they are in, or to the root if they start with `/`"
+                    .to_string(),
+                "check the spelling and capitalization of the path".to_string(),
+            ],
+            links: vec![PATHS_DOCS.to_string()],
+            fixes,
+        });
+    }
+
+    if message == "unexpected argument" || message.starts_with("unexpected argument: ") {
+        let suggestion = match message.strip_prefix("unexpected argument: ") {
+            Some(name) => format!(
+                "`{}` is not a parameter of this function, check its spelling",
+                name
+            ),
+            None => "the function takes fewer positional arguments, remove this one or name it"
+                .to_string(),
+        };
+        return Some(Explanation {
+            summary: "The function doesn't accept this argument.".to_string(),
+            suggestions: vec![
+                suggestion,
+                "look up the function's parameters in the documentation".to_string(),
+            ],
+            links: vec![FUNCTION_DOCS.to_string()],
+            fixes: vec![],
+        });
+    }
+
+    None
+}
+
+/// The names of the standard library and those `source` binds with `let`.
+pub fn defined_names(world: &dyn World, source: &Source) -> Vec<String> {
+    let mut names: Vec<String> = world
+        .library()
+        .global
+        .scope()
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .collect();
+    collect_bindings(&LinkedNode::new(source.root()), &mut names);
+    names
+}
+
+fn collect_bindings(node: &LinkedNode, names: &mut Vec<String>) {
+    if let Some(binding) = node.cast::<ast::LetBinding>() {
+        names.extend(
+            binding
+                .kind()
+                .bindings()
+                .iter()
+                .map(|ident| ident.get().to_string()),
+        );
+    }
+    for child in node.children() {
+        collect_bindings(&child, names);
+    }
+}
+
+/// The name in `names` closest to `name`, if it is close enough to be a typo of it.
+fn closest_name(name: &str, names: &[String]) -> Option<String> {
+    let max = (name.chars().count() / 3).max(1);
+    names
+        .iter()
+        .filter(|candidate| candidate.as_str() != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.clone())
+}
+
+/// The edit distance of `a` and `b` in characters, counting swapped neighbours as one
+/// edit like a single typo.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names() -> Vec<String> {
+        ["heading", "figure", "title"].map(String::from).to_vec()
+    }
+
+    #[test]
+    fn test_explain_unknown_variable() {
+        let explanation = explain_diagnostic("unknown variable: titel", Path::new("/p"), names);
+        let explanation = explanation.unwrap();
+        assert_eq!(explanation.suggestions[0], "did you mean `title`?");
+        assert_eq!(
+            explanation.fixes,
+            [QuickFix::Replace {
+                text: "title".to_string()
+            }]
+        );
+
+        let explanation = explain_diagnostic("unknown variable: xyz", Path::new("/p"), names);
+        assert!(explanation.unwrap().fixes.is_empty());
+    }
+
+    #[test]
+    fn test_explain_file_not_found() {
+        let root = Path::new("/project");
+        let explanation = explain_diagnostic(
+            "file not found (searched at /project/chapters/03.typ)",
+            root,
+            Vec::new,
+        );
+        assert_eq!(
+            explanation.unwrap().fixes,
+            [QuickFix::CreateFile {
+                path: PathBuf::from("chapters/03.typ")
+            }]
+        );
+
+        let image = explain_diagnostic(
+            "file not found (searched at /project/a.png)",
+            root,
+            Vec::new,
+        );
+        assert!(image.unwrap().fixes.is_empty());
+        let outside =
+            explain_diagnostic("file not found (searched at /other/a.typ)", root, Vec::new);
+        assert!(outside.unwrap().fixes.is_empty());
+    }
+
+    #[test]
+    fn test_explain_other() {
+        let root = Path::new("/p");
+        assert!(explain_diagnostic("unexpected argument: sise", root, Vec::new).is_some());
+        assert!(explain_diagnostic("unexpected argument", root, Vec::new).is_some());
+        assert!(explain_diagnostic("expected length, found string", root, Vec::new).is_none());
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("titel", "title"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("figure", "figure"), 0);
+    }
+}
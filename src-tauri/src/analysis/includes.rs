@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use typst::syntax::ast::{self, AstNode};
+use typst::syntax::{FileId, LinkedNode, Source, VirtualPath};
+use typst::World;
+
+/// A `#include` or `#import` of another project file.
+#[derive(Clone, Debug)]
+pub struct IncludeEdge {
+    pub from: FileId,
+    pub to: FileId,
+    /// Byte range of the include/import expression in `from`.
+    pub range: Range<usize>,
+}
+
+/// A chain of includes that leads back to its first file.
+#[derive(Clone, Debug)]
+pub struct IncludeCycle {
+    pub edges: Vec<IncludeEdge>,
+}
+
+impl IncludeCycle {
+    /// Renders the cycle as `/a.typ → /b.typ → /a.typ`.
+    pub fn describe(&self) -> String {
+        let mut parts: Vec<String> = self
+            .edges
+            .iter()
+            .map(|e| display_path(e.from))
+            .collect();
+        if let Some(first) = self.edges.first() {
+            parts.push(display_path(first.from));
+        }
+        parts.join(" → ")
+    }
+}
+
+fn display_path(id: FileId) -> String {
+    let path = id.vpath().as_rootless_path().to_string_lossy().to_string();
+    format!("/{}", path)
+}
+
+/// Collects includes and imports with literal project-relative paths. Package imports
+/// and dynamic paths are skipped since they cannot form project cycles.
+pub fn include_edges(source: &Source) -> Vec<IncludeEdge> {
+    let mut edges = vec![];
+    collect_edges(source.id(), &LinkedNode::new(source.root()), &mut edges);
+    edges
+}
+
+fn collect_edges(from: FileId, node: &LinkedNode, edges: &mut Vec<IncludeEdge>) {
+    let target = if let Some(include) = node.cast::<ast::ModuleInclude>() {
+        Some(include.source())
+    } else {
+        node.cast::<ast::ModuleImport>().map(|import| import.source())
+    };
+
+    if let Some(ast::Expr::Str(path)) = target {
+        let path = path.get();
+        if !path.starts_with('@') {
+            let to = FileId::new(None, resolve(from.vpath(), &path));
+            edges.push(IncludeEdge {
+                from,
+                to,
+                range: node.range(),
+            });
+        }
+    }
+
+    for child in node.children() {
+        collect_edges(from, &child, edges);
+    }
+}
+
+fn resolve(base: &VirtualPath, path: &str) -> VirtualPath {
+    if path.starts_with('/') {
+        VirtualPath::new(path)
+    } else {
+        base.join(path)
+    }
+}
+
+/// The `#import` lines at the top level of a file, as written.
+pub fn top_level_imports(source: &Source) -> Vec<String> {
+    source
+        .root()
+        .children()
+        .filter(|node| node.cast::<ast::ModuleImport>().is_some())
+        .map(|node| format!("#{}", node.clone().into_text()))
+        .collect()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Visit {
+    InProgress,
+    Done,
+}
+
+/// Walks the include graph from `main` and returns every cycle found. Files that fail
+/// to load are ignored here; the compiler reports those itself.
+pub fn find_include_cycles(world: &dyn World, main: FileId) -> Vec<IncludeCycle> {
+    let mut state = HashMap::new();
+    let mut stack = vec![];
+    let mut cycles = vec![];
+    visit(world, main, &mut state, &mut stack, &mut cycles);
+    cycles
+}
+
+fn visit(
+    world: &dyn World,
+    id: FileId,
+    state: &mut HashMap<FileId, Visit>,
+    stack: &mut Vec<IncludeEdge>,
+    cycles: &mut Vec<IncludeCycle>,
+) {
+    state.insert(id, Visit::InProgress);
+    let Ok(source) = world.source(id) else {
+        state.insert(id, Visit::Done);
+        return;
+    };
+
+    for edge in include_edges(&source) {
+        match state.get(&edge.to) {
+            Some(Visit::InProgress) => {
+                let start = stack
+                    .iter()
+                    .position(|e| e.from == edge.to)
+                    .unwrap_or(stack.len());
+                let mut edges = stack[start..].to_vec();
+                edges.push(edge);
+                cycles.push(IncludeCycle { edges });
+            }
+            Some(Visit::Done) => {}
+            None => {
+                let to = edge.to;
+                stack.push(edge);
+                visit(world, to, state, stack, cycles);
+                stack.pop();
+            }
+        }
+    }
+
+    state.insert(id, Visit::Done);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::ProjectWorld;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_detects_include_cycle() {
+        let mut world = ProjectWorld::new(PathBuf::from("."), None);
+        world.set_main_path(VirtualPath::new("main.typ"));
+        world
+            .slot_update("main.typ", Some("#include \"a.typ\"".to_string()))
+            .unwrap();
+        world
+            .slot_update("a.typ", Some("#include \"chapters/b.typ\"".to_string()))
+            .unwrap();
+        world
+            .slot_update("chapters/b.typ", Some("#import \"/a.typ\": *".to_string()))
+            .unwrap();
+
+        let cycles = find_include_cycles(&world, world.main());
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].describe(), "/a.typ → /chapters/b.typ → /a.typ");
+    }
+
+    #[test]
+    fn test_top_level_imports() {
+        let source = Source::detached(
+            "#import \"@preview/cetz:0.3.0\"\n#import \"template.typ\": *\n= Title\n#{ import \"x.typ\" }",
+        );
+        assert_eq!(
+            top_level_imports(&source),
+            [
+                "#import \"@preview/cetz:0.3.0\"",
+                "#import \"template.typ\": *"
+            ]
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use serde::Serialize;
+use std::ops::Range;
+use typst::syntax::ast::{self, AstNode};
+use typst::syntax::{LinkedNode, Source};
+
+/// An automated fix the frontend can offer alongside a lint.
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LintFix {
+    /// Download the remote file via `assets_mirror_url` and replace the path.
+    MirrorUrl { url: String },
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Lint {
+    pub range: Range<usize>,
+    pub message: String,
+    pub fix: Option<LintFix>,
+}
+
+/// Runs all source-level lints. Ranges are byte offsets into the source text.
+pub fn lint_source(source: &Source) -> Vec<Lint> {
+    let mut lints = vec![];
+    visit(&LinkedNode::new(source.root()), &mut lints);
+    lints
+}
+
+pub fn is_remote_url(path: &str) -> bool {
+    let lower = path.trim_start().to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+fn visit(node: &LinkedNode, lints: &mut Vec<Lint>) {
+    if let Some(lint) = lint_remote_image(node) {
+        lints.push(lint);
+    }
+    for child in node.children() {
+        visit(&child, lints);
+    }
+}
+
+/// Typst cannot fetch URLs, so `image("https://...")` always fails to load.
+fn lint_remote_image(node: &LinkedNode) -> Option<Lint> {
+    let call = node.cast::<ast::FuncCall>()?;
+    let ast::Expr::Ident(callee) = call.callee() else {
+        return None;
+    };
+    if callee.as_str() != "image" {
+        return None;
+    }
+
+    let path = call.args().items().find_map(|arg| match arg {
+        ast::Arg::Pos(ast::Expr::Str(path)) => Some(path),
+        _ => None,
+    })?;
+    let url = path.get();
+    if !is_remote_url(&url) {
+        return None;
+    }
+
+    let path_node = node.find(path.span())?;
+    Some(Lint {
+        range: path_node.range(),
+        message: "typst cannot load images from URLs".to_string(),
+        fix: Some(LintFix::MirrorUrl {
+            url: url.to_string(),
+        }),
+    })
+}
@@ -0,0 +1,9 @@
+mod continuation;
+mod explain;
+mod includes;
+mod lint;
+
+pub use continuation::*;
+pub use explain::*;
+pub use includes::*;
+pub use lint::*;
@@ -0,0 +1,90 @@
+use super::{project_hash, read_app_json, write_app_json, COMMAND_APPROVALS_FILE};
+use siphasher::sip128::{Hasher128, SipHasher};
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::Hasher;
+use std::io;
+use std::path::Path;
+
+/// The commands of project configs the user allowed to run, by project, as hashes of
+/// the command. Kept in the app config directory, so a project can't approve its own
+/// commands, and a changed command needs a new approval.
+type Approvals = BTreeMap<String, BTreeSet<String>>;
+
+/// The hash of a `kind` of command, eg. `generator` or `export_hook`.
+fn command_hash(kind: &str, command: &str) -> String {
+    let mut hasher = SipHasher::new();
+    hasher.write(kind.as_bytes());
+    hasher.write_u8(0);
+    hasher.write(command.as_bytes());
+    hex::encode(hasher.finish128().as_bytes())
+}
+
+fn is_approved_in(approvals: &Approvals, root: &Path, kind: &str, command: &str) -> bool {
+    approvals
+        .get(&project_hash(root))
+        .is_some_and(|commands| commands.contains(&command_hash(kind, command)))
+}
+
+fn set_approved_in<'a>(
+    approvals: &mut Approvals,
+    root: &Path,
+    kind: &str,
+    commands: impl IntoIterator<Item = &'a str>,
+    approved: bool,
+) {
+    let project = approvals.entry(project_hash(root)).or_default();
+    for command in commands {
+        let hash = command_hash(kind, command);
+        if approved {
+            project.insert(hash);
+        } else {
+            project.remove(&hash);
+        }
+    }
+    approvals.retain(|_, commands| !commands.is_empty());
+}
+
+/// Whether the user allowed the project at `root` to run `command`.
+pub fn is_command_approved(root: &Path, kind: &str, command: &str) -> bool {
+    let approvals: Approvals = read_app_json(COMMAND_APPROVALS_FILE)
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    is_approved_in(&approvals, root, kind, command)
+}
+
+/// Allows or disallows the project at `root` to run `commands`.
+pub fn set_commands_approved<'a>(
+    root: &Path,
+    kind: &str,
+    commands: impl IntoIterator<Item = &'a str>,
+    approved: bool,
+) -> io::Result<()> {
+    let mut approvals: Approvals = read_app_json(COMMAND_APPROVALS_FILE)?.unwrap_or_default();
+    set_approved_in(&mut approvals, root, kind, commands, approved);
+    write_app_json(COMMAND_APPROVALS_FILE, &approvals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_approvals() {
+        let root = Path::new("/projects/thesis");
+        let other = Path::new("/projects/other");
+        let plot = "python3 plot.py";
+        let mut approvals = Approvals::new();
+        set_approved_in(&mut approvals, root, "generator", [plot], true);
+
+        assert!(is_approved_in(&approvals, root, "generator", plot));
+        // A changed command, another kind or another project isn't approved.
+        let changed = "python3 plot.py; rm -rf ~";
+        assert!(!is_approved_in(&approvals, root, "generator", changed));
+        assert!(!is_approved_in(&approvals, root, "export_hook", plot));
+        assert!(!is_approved_in(&approvals, other, "generator", plot));
+
+        set_approved_in(&mut approvals, root, "generator", [plot], false);
+        assert!(approvals.is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+use super::{
+    read_app_json, write_app_json, EXPORT_PROFILES_FILE, KEYBINDINGS_FILE, NOTIFICATIONS_FILE,
+    SETTINGS_FILE, SNIPPETS_FILE, SUBMISSION_PROFILES_FILE, TEMPLATES_FILE,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io;
+
+const BUNDLE_FORMAT: &str = "typstudio-settings";
+const BUNDLE_VERSION: u32 = 1;
+
+/// Sections of a bundle and the app data file each one maps to.
+const SECTIONS: [(&str, &str); 7] = [
+    ("settings", SETTINGS_FILE),
+    ("keybindings", KEYBINDINGS_FILE),
+    ("snippets", SNIPPETS_FILE),
+    ("templates", TEMPLATES_FILE),
+    ("export_profiles", EXPORT_PROFILES_FILE),
+    ("submission_profiles", SUBMISSION_PROFILES_FILE),
+    ("notifications", NOTIFICATIONS_FILE),
+];
+
+/// A portable snapshot of the user's setup, suitable for a dotfiles repository.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SettingsBundle {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: String,
+    #[serde(default)]
+    pub sections: serde_json::Map<String, Value>,
+}
+
+/// Whether an object key likely holds a credential that must not leave the machine.
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    ["token", "password", "secret", "api_key", "apikey", "credential", "private_key"]
+        .iter()
+        .any(|s| key.contains(s))
+}
+
+fn strip_secrets(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|k, _| !is_secret_key(k));
+            map.values_mut().for_each(strip_secrets);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_secrets),
+        _ => {}
+    }
+}
+
+/// Copies secret keys from `existing` into `imported` so importing a bundle does not
+/// wipe credentials stored on this machine.
+fn keep_secrets(imported: &mut Value, existing: &Value) {
+    if let (Value::Object(imported), Value::Object(existing)) = (imported, existing) {
+        for (key, value) in existing {
+            if is_secret_key(key) {
+                imported.insert(key.clone(), value.clone());
+            } else if let Some(target) = imported.get_mut(key) {
+                keep_secrets(target, value);
+            }
+        }
+    }
+}
+
+pub fn export_bundle() -> io::Result<SettingsBundle> {
+    let mut sections = serde_json::Map::new();
+    for (section, file) in SECTIONS {
+        if let Some(mut value) = read_app_json::<Value>(file)? {
+            strip_secrets(&mut value);
+            sections.insert(section.to_string(), value);
+        }
+    }
+    Ok(SettingsBundle {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        sections,
+    })
+}
+
+/// Writes every known section of the bundle to app data and returns the imported
+/// section names. Unknown sections are ignored.
+pub fn import_bundle(bundle: SettingsBundle) -> io::Result<Vec<String>> {
+    if bundle.format != BUNDLE_FORMAT || bundle.version > BUNDLE_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a supported settings bundle",
+        ));
+    }
+
+    let mut imported = vec![];
+    for (section, file) in SECTIONS {
+        let Some(mut value) = bundle.sections.get(section).cloned() else {
+            continue;
+        };
+        strip_secrets(&mut value);
+        if let Some(existing) = read_app_json::<Value>(file)? {
+            keep_secrets(&mut value, &existing);
+        }
+        write_app_json(file, &value)?;
+        imported.push(section.to_string());
+    }
+    Ok(imported)
+}
@@ -0,0 +1,86 @@
+mod approvals;
+mod bundle;
+mod recent;
+
+pub use approvals::*;
+pub use bundle::*;
+pub use recent::*;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use siphasher::sip128::{Hasher128, SipHasher};
+use std::fs;
+use std::hash::Hasher;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Per-user files stored in the app config directory. Each subsystem owns one file.
+pub const SETTINGS_FILE: &str = "settings.json";
+pub const KEYBINDINGS_FILE: &str = "keybindings.json";
+pub const SNIPPETS_FILE: &str = "snippets.json";
+pub const TEMPLATES_FILE: &str = "templates.json";
+pub const EXPORT_PROFILES_FILE: &str = "export_profiles.json";
+pub const SUBMISSION_PROFILES_FILE: &str = "submission_profiles.json";
+pub const NOTIFICATIONS_FILE: &str = "notifications.json";
+pub const RECENT_PROJECTS_FILE: &str = "recent_projects.json";
+pub const COMMAND_APPROVALS_FILE: &str = "command_approvals.json";
+
+/// The directory holding per-user app data, eg. `~/.config/typstudio`.
+#[cfg(not(test))]
+pub fn app_config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|p| p.join("typstudio"))
+}
+
+/// Tests keep their settings, recent projects and journals apart from the user's.
+#[cfg(test)]
+pub fn app_config_dir() -> Option<PathBuf> {
+    Some(std::env::temp_dir().join(format!("typstudio-test-config-{}", std::process::id())))
+}
+
+pub fn app_config_path(name: &str) -> Option<PathBuf> {
+    app_config_dir().map(|p| p.join(name))
+}
+
+/// Reads a JSON file from the app config directory, or `None` if it does not exist.
+pub fn read_app_json<T: DeserializeOwned>(name: &str) -> io::Result<Option<T>> {
+    let Some(path) = app_config_path(name) else {
+        return Ok(None);
+    };
+    match fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Writes a JSON file to the app config directory, replacing it atomically.
+pub fn write_app_json<T: Serialize>(name: &str, value: &T) -> io::Result<()> {
+    let dir = app_config_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+    fs::create_dir_all(&dir)?;
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let tmp = dir.join(format!(".{}.tmp", name));
+    fs::write(&tmp, json)?;
+    fs::rename(&tmp, dir.join(name))
+}
+
+fn project_hash(root: &Path) -> String {
+    let mut hasher = SipHasher::new();
+    hasher.write(root.to_string_lossy().as_bytes());
+    hex::encode(&hasher.finish128().as_bytes()[..8])
+}
+
+/// The name of a per-project file in the app config directory, eg.
+/// `statistics-<hash>.json`, for data that shouldn't end up in the project.
+pub fn project_app_file(kind: &str, root: &Path) -> String {
+    format!("{}-{}.json", kind, project_hash(root))
+}
+
+/// A per-project directory in the app config directory, eg. `recovery/<hash>`, for
+/// data kept as several files.
+pub fn project_app_dir(kind: &str, root: &Path) -> Option<PathBuf> {
+    app_config_dir().map(|dir| dir.join(kind).join(project_hash(root)))
+}
@@ -0,0 +1,173 @@
+use super::{read_app_json, write_app_json, RECENT_PROJECTS_FILE};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Unpinned projects beyond this are forgotten.
+pub const MAX_RECENT_PROJECTS: usize = 10;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RecentProject {
+    pub path: PathBuf,
+    pub name: String,
+    /// Unix time in milliseconds.
+    pub last_opened: i64,
+    /// Pinned projects are listed first and never forgotten.
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+impl RecentProject {
+    fn new(path: &Path, last_opened: i64, pinned: bool) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            name: path
+                .file_name()
+                .map_or_else(|| path.to_string_lossy(), |name| name.to_string_lossy())
+                .to_string(),
+            last_opened,
+            pinned,
+        }
+    }
+}
+
+/// Pinned projects first, then the most recently opened.
+fn sort_recent(projects: &mut [RecentProject]) {
+    projects.sort_by(|a, b| {
+        b.pinned
+            .cmp(&a.pinned)
+            .then(b.last_opened.cmp(&a.last_opened))
+    });
+}
+
+/// Moves `path` to the front of the list, keeping its pin, and forgets the oldest
+/// unpinned projects over the limit.
+fn record_opened(projects: &mut Vec<RecentProject>, path: &Path, now: i64) {
+    let pinned = projects.iter().any(|p| p.path == path && p.pinned);
+    projects.retain(|p| p.path != path);
+    projects.push(RecentProject::new(path, now, pinned));
+    forget_oldest(projects);
+}
+
+/// Adds the projects of an older list that aren't listed yet, keeping when they were
+/// last opened.
+fn merge_imported(projects: &mut Vec<RecentProject>, imported: &[(PathBuf, i64)]) {
+    for (path, last_opened) in imported {
+        if !projects.iter().any(|p| p.path == *path) {
+            projects.push(RecentProject::new(path, *last_opened, false));
+        }
+    }
+    forget_oldest(projects);
+}
+
+fn forget_oldest(projects: &mut Vec<RecentProject>) {
+    sort_recent(projects);
+    let mut unpinned = 0;
+    projects.retain(|p| {
+        unpinned += usize::from(!p.pinned);
+        p.pinned || unpinned <= MAX_RECENT_PROJECTS
+    });
+}
+
+pub fn recent_projects() -> io::Result<Vec<RecentProject>> {
+    let mut projects: Vec<RecentProject> = read_app_json(RECENT_PROJECTS_FILE)?.unwrap_or_default();
+    sort_recent(&mut projects);
+    Ok(projects)
+}
+
+/// Applies `f` to the saved list and returns the updated list.
+fn update_recent_projects(
+    f: impl FnOnce(&mut Vec<RecentProject>),
+) -> io::Result<Vec<RecentProject>> {
+    let mut projects = recent_projects()?;
+    f(&mut projects);
+    sort_recent(&mut projects);
+    write_app_json(RECENT_PROJECTS_FILE, &projects)?;
+    Ok(projects)
+}
+
+/// Whether the user opened the project at `path` before, so it can be reopened without
+/// asking, eg. from a link.
+pub fn is_recent_project(path: &Path) -> bool {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    recent_projects().is_ok_and(|projects| projects.iter().any(|p| p.path == path))
+}
+
+/// The most recently opened project or lone file that still exists, pinned or not.
+pub fn last_opened_project() -> Option<PathBuf> {
+    recent_projects()
+        .ok()?
+        .into_iter()
+        .filter(|p| p.path.exists())
+        .max_by_key(|p| p.last_opened)
+        .map(|p| p.path)
+}
+
+pub fn add_recent_project(path: &Path) -> io::Result<Vec<RecentProject>> {
+    let now = chrono::Utc::now().timestamp_millis();
+    update_recent_projects(|projects| record_opened(projects, path, now))
+}
+
+pub fn remove_recent_project(path: &Path) -> io::Result<Vec<RecentProject>> {
+    update_recent_projects(|projects| projects.retain(|p| p.path != path))
+}
+
+pub fn pin_recent_project(path: &Path, pinned: bool) -> io::Result<Vec<RecentProject>> {
+    update_recent_projects(|projects| {
+        if let Some(project) = projects.iter_mut().find(|p| p.path == path) {
+            project.pinned = pinned;
+        }
+    })
+}
+
+/// Adds the projects of the list the frontend kept before the backend owned it, as
+/// paths with when they were last opened.
+pub fn import_recent_projects(imported: &[(PathBuf, i64)]) -> io::Result<Vec<RecentProject>> {
+    update_recent_projects(|projects| merge_imported(projects, imported))
+}
+
+/// Forgets all projects but the pinned ones.
+pub fn clear_recent_projects() -> io::Result<Vec<RecentProject>> {
+    update_recent_projects(|projects| projects.retain(|p| p.pinned))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge_imported, record_opened, MAX_RECENT_PROJECTS};
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_record_opened() {
+        let mut projects = vec![];
+        record_opened(&mut projects, Path::new("/a/thesis"), 1);
+        projects[0].pinned = true;
+        for i in 0..MAX_RECENT_PROJECTS + 2 {
+            record_opened(
+                &mut projects,
+                &PathBuf::from(format!("/p{}", i)),
+                10 + i as i64,
+            );
+        }
+        assert_eq!(projects.len(), MAX_RECENT_PROJECTS + 1);
+        assert_eq!(projects[0].name, "thesis");
+        assert_eq!(projects[1].path, Path::new("/p11"));
+
+        record_opened(&mut projects, Path::new("/a/thesis"), 100);
+        assert!(projects[0].pinned);
+        assert_eq!(projects.len(), MAX_RECENT_PROJECTS + 1);
+    }
+
+    #[test]
+    fn test_merge_imported() {
+        let mut projects = vec![];
+        record_opened(&mut projects, Path::new("/thesis"), 50);
+        merge_imported(
+            &mut projects,
+            &[(PathBuf::from("/thesis"), 10), (PathBuf::from("/notes"), 20)],
+        );
+        assert_eq!(projects.len(), 2);
+        assert_eq!(projects[0].last_opened, 50);
+        assert_eq!(projects[1].path, Path::new("/notes"));
+        assert_eq!(projects[1].last_opened, 20);
+    }
+}
@@ -0,0 +1,248 @@
+//! The headless mode of the binary, eg. for CI builds of documents written in the app:
+//! `typstudio compile thesis --format pdf -o thesis.pdf`.
+
+use crate::compiler::{toggle_inputs, InputsWorld};
+use crate::export::{output_beside, with_extension, write_document, ExportFormat};
+use crate::project::Project;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use typst::diag::{Severity, SourceDiagnostic};
+use typst::layout::PagedDocument;
+use typst::syntax::VirtualPath;
+use typst::World;
+
+const USAGE: &str = "\
+Usage: typstudio compile <project> [options]
+
+Compiles the main file of a project folder, or a lone .typ file, and exports it.
+
+Options:
+  -f, --format <pdf|png|svg>  The export format; png and svg write a zip of pages [default: pdf]
+  -o, --output <path>         Where to write the export [default: next to the main file]
+      --main <path>           The main file, relative to the project, instead of the configured one
+      --input <key=value>     Sets `sys.inputs.<key>`, overriding the project's toggles
+  -h, --help                  Prints this help";
+
+/// Exit code of a document that failed to compile or export.
+const EXIT_FAILED: i32 = 1;
+/// Exit code of invalid arguments.
+const EXIT_USAGE: i32 = 2;
+
+#[derive(Debug, PartialEq)]
+struct CompileArgs {
+    project: PathBuf,
+    format: ExportFormat,
+    output: Option<PathBuf>,
+    main: Option<PathBuf>,
+    inputs: BTreeMap<String, String>,
+}
+
+fn parse_format(value: &str) -> Result<ExportFormat, String> {
+    match value {
+        "pdf" => Ok(ExportFormat::Pdf),
+        "png" => Ok(ExportFormat::Png),
+        "svg" => Ok(ExportFormat::Svg),
+        _ => Err(format!("unknown format {:?}", value)),
+    }
+}
+
+/// Parses the arguments after `compile`. `None` asks for the usage.
+fn parse_compile_args(args: &[String]) -> Result<Option<CompileArgs>, String> {
+    let mut project = None;
+    let mut parsed = CompileArgs {
+        project: PathBuf::new(),
+        format: ExportFormat::Pdf,
+        output: None,
+        main: None,
+        inputs: BTreeMap::new(),
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .cloned()
+                .ok_or_else(|| format!("{} needs a value", name))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-f" | "--format" => parsed.format = parse_format(&value(arg)?)?,
+            "-o" | "--output" => parsed.output = Some(PathBuf::from(value(arg)?)),
+            "--main" => parsed.main = Some(PathBuf::from(value(arg)?)),
+            "--input" => {
+                let input = value(arg)?;
+                let (key, value) = input
+                    .split_once('=')
+                    .ok_or_else(|| format!("--input {:?} is not key=value", input))?;
+                parsed.inputs.insert(key.to_string(), value.to_string());
+            }
+            flag if flag.starts_with('-') => return Err(format!("unknown option {}", flag)),
+            path if project.is_none() => project = Some(PathBuf::from(path)),
+            extra => return Err(format!("unexpected argument {:?}", extra)),
+        }
+    }
+    parsed.project = project.ok_or("missing the project")?;
+    Ok(Some(parsed))
+}
+
+/// Runs the command line `args`, without the program name, if they name a command.
+/// Returns the exit code then, or `None` to start the app.
+pub fn run(args: &[String]) -> Option<i32> {
+    if args.first().map(String::as_str) != Some("compile") {
+        return None;
+    }
+    attach_parent_console();
+    let code = match parse_compile_args(&args[1..]) {
+        Ok(Some(args)) => compile(args),
+        Ok(None) => {
+            println!("{}", USAGE);
+            0
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            EXIT_USAGE
+        }
+    };
+    Some(code)
+}
+
+/// Release builds on Windows are GUI programs without a console, so their output would go
+/// nowhere. Writes it to the console of the shell that started the command instead.
+#[cfg(windows)]
+fn attach_parent_console() {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    // Fails without a parent console, or with a console of our own in debug builds, which
+    // is fine either way.
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
+
+fn compile(args: CompileArgs) -> i32 {
+    if !args.project.exists() {
+        eprintln!("error: {:?} does not exist", args.project);
+        return EXIT_USAGE;
+    }
+    let project = Project::open(args.project, None);
+    let config = project.config.read().unwrap().clone();
+    config.apply(&project);
+    let mut world = project.world.lock().unwrap();
+    if let Some(main) = &args.main {
+        world.set_main_path(VirtualPath::new(main));
+    }
+    if !world.is_main_set() {
+        eprintln!("error: the project has no main file; pass one with --main");
+        return EXIT_USAGE;
+    }
+    let main = world.main();
+    let main_path = project.root.join(main.vpath().as_rootless_path());
+
+    let mut inputs = toggle_inputs(&config.toggles, &BTreeMap::new());
+    inputs.extend(args.inputs);
+    let inputs_world = InputsWorld::new(&*world, &inputs);
+    let result = typst::compile::<PagedDocument>(&inputs_world);
+    for warning in &result.warnings {
+        eprintln!("{}", format_diagnostic(&inputs_world, warning));
+    }
+    let document = match result.output {
+        Ok(document) => document,
+        Err(errors) => {
+            for error in &errors {
+                eprintln!("{}", format_diagnostic(&inputs_world, error));
+            }
+            eprintln!("error: compiling {:?} failed", main_path);
+            return EXIT_FAILED;
+        }
+    };
+
+    let output = match args.output {
+        Some(output) => with_extension(&output, args.format),
+        None => output_beside(&main_path, args.format),
+    };
+    match write_document(&document, args.format, &output) {
+        Ok(path) => {
+            eprintln!(
+                "exported {} pages to {}",
+                document.pages.len(),
+                path.display()
+            );
+            0
+        }
+        Err(e) => {
+            eprintln!("error: exporting to {:?} failed: {}", output, e);
+            EXIT_FAILED
+        }
+    }
+}
+
+/// Eg. `error: chapters/one.typ:12:5: unknown variable: foo`, then its hints, like the
+/// typst CLI.
+fn format_diagnostic(world: &dyn World, diagnostic: &SourceDiagnostic) -> String {
+    let severity = match diagnostic.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    };
+    let location = diagnostic.span.id().and_then(|id| {
+        let source = world.source(id).ok()?;
+        let offset = source.find(diagnostic.span)?.range().start;
+        let lines = source.lines();
+        let (line, column) = (lines.byte_to_line(offset)?, lines.byte_to_column(offset)?);
+        let path = id.vpath().as_rootless_path().display();
+        Some(format!("{}:{}:{}: ", path, line + 1, column + 1))
+    });
+    let mut out = format!(
+        "{}: {}{}",
+        severity,
+        location.unwrap_or_default(),
+        diagnostic.message
+    );
+    for hint in &diagnostic.hints {
+        out.push_str(&format!("\n  hint: {}", hint));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_compile_args() {
+        let parsed = parse_compile_args(&args(&[
+            "thesis",
+            "-f",
+            "png",
+            "-o",
+            "out",
+            "--input",
+            "lang=de",
+            "--main",
+            "draft.typ",
+        ]))
+        .unwrap()
+        .unwrap();
+        assert_eq!(parsed.project, PathBuf::from("thesis"));
+        assert_eq!(parsed.format, ExportFormat::Png);
+        assert_eq!(parsed.output, Some(PathBuf::from("out")));
+        assert_eq!(parsed.main, Some(PathBuf::from("draft.typ")));
+        assert_eq!(parsed.inputs["lang"], "de");
+
+        assert_eq!(parse_compile_args(&args(&["--help"])), Ok(None));
+        assert!(parse_compile_args(&args(&[])).is_err());
+        assert!(parse_compile_args(&args(&["a", "b"])).is_err());
+        assert!(parse_compile_args(&args(&["a", "-f", "docx"])).is_err());
+        assert!(parse_compile_args(&args(&["a", "--input", "lang"])).is_err());
+        assert!(parse_compile_args(&args(&["a", "-o"])).is_err());
+        assert_eq!(run(&args(&["/home/me/thesis"])), None);
+    }
+}
@@ -0,0 +1,161 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use yrs::updates::decoder::Decode;
+use yrs::updates::encoder::Encode;
+use yrs::{Doc, GetString, OffsetKind, Options, ReadTxn, StateVector, Text, Transact, Update};
+
+/// A replacement of a range of a shared file. Offsets count UTF-16 code units, like the
+/// editor's.
+#[derive(Deserialize, Clone, Debug)]
+pub struct CollabEdit {
+    pub start: u32,
+    pub end: u32,
+    pub text: String,
+}
+
+/// The files of a collaboration session in one CRDT document, a text per project path
+/// such as `/main.typ`. Edits of any peer merge without conflicts in any order.
+pub struct CollabDocument {
+    doc: Doc,
+    /// The content of every file as of the last change, to tell which files an update
+    /// from a peer touched.
+    known: HashMap<String, String>,
+}
+
+impl CollabDocument {
+    pub fn new() -> Self {
+        let doc = Doc::with_options(Options {
+            offset_kind: OffsetKind::Utf16,
+            ..Options::default()
+        });
+        Self {
+            doc,
+            known: HashMap::new(),
+        }
+    }
+
+    /// Shares the file at `path` with `content` unless a peer shared it already. Returns
+    /// its content in the session, and the update to send to peers if it was added.
+    pub fn open(&mut self, path: &str, content: &str) -> (String, Option<Vec<u8>>) {
+        let text = self.doc.get_or_insert_text(path);
+        let mut txn = self.doc.transact_mut();
+        if text.len(&txn) > 0 {
+            let current = text.get_string(&txn);
+            self.known.insert(path.to_string(), current.clone());
+            return (current, None);
+        }
+        text.insert(&mut txn, 0, content);
+        let update = txn.encode_update_v1();
+        self.known.insert(path.to_string(), content.to_string());
+        (content.to_string(), Some(update))
+    }
+
+    /// Applies edits made in this editor. Returns the new content and the update to send
+    /// to peers. Edits apply in order, each to the result of the previous one.
+    pub fn apply_local(&mut self, path: &str, edits: &[CollabEdit]) -> (String, Vec<u8>) {
+        let text = self.doc.get_or_insert_text(path);
+        let mut txn = self.doc.transact_mut();
+        for edit in edits {
+            let len = text.len(&txn);
+            let start = edit.start.min(len);
+            let end = edit.end.clamp(start, len);
+            if end > start {
+                text.remove_range(&mut txn, start, end - start);
+            }
+            if !edit.text.is_empty() {
+                text.insert(&mut txn, start, &edit.text);
+            }
+        }
+        let update = txn.encode_update_v1();
+        let content = text.get_string(&txn);
+        self.known.insert(path.to_string(), content.clone());
+        (content, update)
+    }
+
+    /// Merges an update of a peer. Returns the files it changed, with their new content.
+    pub fn apply_remote(&mut self, update: &[u8]) -> Result<Vec<(String, String)>, String> {
+        let update = Update::decode_v1(update).map_err(|e| e.to_string())?;
+        let mut txn = self.doc.transact_mut();
+        txn.apply_update(update).map_err(|e| e.to_string())?;
+        let paths: Vec<String> = txn.root_refs().map(|(name, _)| name.to_string()).collect();
+        drop(txn);
+
+        let mut changed = vec![];
+        for path in paths {
+            let content = self.content(&path);
+            if self.known.get(&path) != Some(&content) {
+                self.known.insert(path.clone(), content.clone());
+                changed.push((path, content));
+            }
+        }
+        Ok(changed)
+    }
+
+    pub fn content(&self, path: &str) -> String {
+        let text = self.doc.get_or_insert_text(path);
+        let txn = self.doc.transact();
+        text.get_string(&txn)
+    }
+
+    /// What this document has seen, for a peer to send what is missing.
+    pub fn state_vector(&self) -> Vec<u8> {
+        self.doc.transact().state_vector().encode_v1()
+    }
+
+    /// The changes a peer with the state vector `state` is missing.
+    pub fn diff(&self, state: &[u8]) -> Result<Vec<u8>, String> {
+        let state = StateVector::decode_v1(state).map_err(|e| e.to_string())?;
+        Ok(self.doc.transact().encode_diff_v1(&state))
+    }
+}
+
+impl Default for CollabDocument {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(start: u32, end: u32, text: &str) -> CollabEdit {
+        CollabEdit {
+            start,
+            end,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_concurrent_edits_merge() {
+        let mut host = CollabDocument::new();
+        let mut guest = CollabDocument::new();
+        let (_, shared) = host.open("/main.typ", "= Results\nThe effect is small.");
+        guest.apply_remote(&shared.unwrap()).unwrap();
+        assert_eq!(
+            guest.open("/main.typ", "ignored").0,
+            "= Results\nThe effect is small."
+        );
+
+        let (_, from_host) = host.apply_local("/main.typ", &[edit(2, 9, "Findings")]);
+        let (_, from_guest) = guest.apply_local("/main.typ", &[edit(24, 29, "large")]);
+        let changed = host.apply_remote(&from_guest).unwrap();
+        guest.apply_remote(&from_host).unwrap();
+
+        let merged = "= Findings\nThe effect is large.";
+        assert_eq!(changed, [("/main.typ".to_string(), merged.to_string())]);
+        assert_eq!(host.content("/main.typ"), merged);
+        assert_eq!(guest.content("/main.typ"), merged);
+    }
+
+    #[test]
+    fn test_sync_from_state_vector() {
+        let mut host = CollabDocument::new();
+        host.open("/refs.bib", "@book{a}");
+        let mut guest = CollabDocument::new();
+        let missing = host.diff(&guest.state_vector()).unwrap();
+        let changed = guest.apply_remote(&missing).unwrap();
+        assert_eq!(changed, [("/refs.bib".to_string(), "@book{a}".to_string())]);
+    }
+}
@@ -0,0 +1,5 @@
+mod document;
+mod session;
+
+pub use document::*;
+pub use session::*;
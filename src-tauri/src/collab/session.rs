@@ -0,0 +1,255 @@
+use super::{CollabDocument, CollabEdit};
+use futures_util::{SinkExt, StreamExt};
+use log::{info, warn};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio::sync::broadcast;
+use tokio::task::{JoinHandle, JoinSet};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+/// The first byte of a message between peers.
+const SYNC_STATE: u8 = 0;
+/// The changes the receiver of a `SYNC_STATE` message was missing.
+const SYNC_DIFF: u8 = 1;
+const UPDATE: u8 = 2;
+
+/// How long a peer may take to open its connection before it is dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The origin of updates made in this editor.
+const LOCAL_PEER: u64 = 0;
+
+/// How many updates may wait for a slow peer before it is sent the whole state again.
+const OUTGOING_CAPACITY: usize = 256;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CollabSessionInfo {
+    /// The link peers join with, eg. `ws://192.168.1.20:4321/3f2a…`.
+    pub url: String,
+    pub hosting: bool,
+}
+
+/// Called with the files peers changed and their new content.
+pub type RemoteChanges = Box<dyn Fn(Vec<(String, String)>) + Send + Sync>;
+
+/// A collaboration session, hosted by this editor or joined. The host relays every
+/// update to its other peers, so peers only connect to the host.
+pub struct CollabSession {
+    document: Mutex<CollabDocument>,
+    /// Updates to send, with the peer they came from, which doesn't get them back.
+    outgoing: broadcast::Sender<(u64, Vec<u8>)>,
+    next_peer: AtomicU64,
+    on_remote: RemoteChanges,
+    /// The files opened in this editor. Peers' changes to other files stay in the
+    /// session's document and aren't reported, so a peer can't create or overwrite files.
+    opened: Mutex<HashSet<String>>,
+    tasks: Mutex<Vec<JoinHandle<()>>>,
+    info: CollabSessionInfo,
+}
+
+impl CollabSession {
+    fn new(info: CollabSessionInfo, on_remote: RemoteChanges) -> Arc<Self> {
+        Arc::new(Self {
+            document: Mutex::new(CollabDocument::new()),
+            outgoing: broadcast::channel(OUTGOING_CAPACITY).0,
+            next_peer: AtomicU64::new(LOCAL_PEER + 1),
+            on_remote,
+            opened: Mutex::default(),
+            tasks: Mutex::default(),
+            info,
+        })
+    }
+
+    /// Hosts a session on `port` of every network interface, or any free port.
+    pub async fn host(port: Option<u16>, on_remote: RemoteChanges) -> std::io::Result<Arc<Self>> {
+        let listener =
+            TcpListener::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, port.unwrap_or(0)))).await?;
+        let port = listener.local_addr()?.port();
+        // The host accepts anyone on the network who knows the link.
+        let token = crate::net::random_token()?;
+        let info = CollabSessionInfo {
+            url: format!("ws://{}:{}/{}", crate::net::lan_address(), port, token),
+            hosting: true,
+        };
+        let session = Self::new(info, on_remote);
+        info!("hosting a collaboration session on port {}", port);
+
+        let accepting = session.clone();
+        let task = tokio::spawn(async move {
+            let expected: Arc<str> = format!("/{}", token).into();
+            // Aborted with the accepting task, which stopping the session does.
+            let mut handshakes = JoinSet::new();
+            while let Ok((stream, address)) = listener.accept().await {
+                while handshakes.try_join_next().is_some() {}
+                let (session, expected) = (accepting.clone(), expected.clone());
+                // Each on its own, so a client that never completes its handshake doesn't
+                // keep others from joining.
+                handshakes.spawn(async move {
+                    let check = |request: &Request, response: Response| {
+                        if request.uri().path() == &*expected {
+                            Ok(response)
+                        } else {
+                            let mut refused = ErrorResponse::new(None);
+                            *refused.status_mut() = StatusCode::FORBIDDEN;
+                            Err(refused)
+                        }
+                    };
+                    let handshake = tokio_tungstenite::accept_hdr_async(stream, check);
+                    match tokio::time::timeout(HANDSHAKE_TIMEOUT, handshake).await {
+                        Ok(Ok(socket)) => {
+                            info!("{} joined the collaboration session", address);
+                            session.spawn_peer(socket);
+                        }
+                        Ok(Err(e)) => warn!("refused collaboration peer {}: {}", address, e),
+                        Err(_) => warn!("collaboration peer {} timed out joining", address),
+                    }
+                });
+            }
+        });
+        session.tasks.lock().unwrap().push(task);
+        Ok(session)
+    }
+
+    /// Joins the session hosted at `url`, a link of [`CollabSession::host`].
+    pub async fn join(url: &str, on_remote: RemoteChanges) -> Result<Arc<Self>, String> {
+        let (socket, _) = tokio_tungstenite::connect_async(url)
+            .await
+            .map_err(|e| e.to_string())?;
+        let info = CollabSessionInfo {
+            url: url.to_string(),
+            hosting: false,
+        };
+        let session = Self::new(info, on_remote);
+        info!("joined the collaboration session at {}", url);
+        session.spawn_peer(socket);
+        Ok(session)
+    }
+
+    pub fn info(&self) -> CollabSessionInfo {
+        self.info.clone()
+    }
+
+    /// Shares a file opened in the editor. Returns its content in the session, which is a
+    /// peer's if they shared it first.
+    pub fn open(&self, path: &str, content: &str) -> String {
+        self.opened.lock().unwrap().insert(path.to_string());
+        let (content, update) = self.document.lock().unwrap().open(path, content);
+        if let Some(update) = update {
+            let _ = self.outgoing.send((LOCAL_PEER, update));
+        }
+        content
+    }
+
+    /// Applies and sends edits made in this editor. Returns the new content of the file.
+    pub fn apply_local(&self, path: &str, edits: &[CollabEdit]) -> String {
+        let (content, update) = self.document.lock().unwrap().apply_local(path, edits);
+        let _ = self.outgoing.send((LOCAL_PEER, update));
+        content
+    }
+
+    /// Disconnects from all peers. The session is unusable afterwards.
+    pub fn stop(&self) {
+        for task in self.tasks.lock().unwrap().drain(..) {
+            task.abort();
+        }
+    }
+
+    fn spawn_peer<S>(self: &Arc<Self>, socket: WebSocketStream<S>)
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let peer = self.next_peer.fetch_add(1, Ordering::SeqCst);
+        let session = self.clone();
+        let task = tokio::spawn(async move {
+            if let Err(e) = session.run_peer(peer, socket).await {
+                warn!("collaboration peer {} disconnected: {}", peer, e);
+            }
+        });
+        let mut tasks = self.tasks.lock().unwrap();
+        tasks.retain(|task| !task.is_finished());
+        tasks.push(task);
+    }
+
+    /// Exchanges updates with a peer until either side disconnects. Both sides start by
+    /// sending their state, so each receives what it missed.
+    async fn run_peer<S>(&self, peer: u64, socket: WebSocketStream<S>) -> Result<(), String>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let (mut sink, mut stream) = socket.split();
+        let mut outgoing = self.outgoing.subscribe();
+        let state = self.document.lock().unwrap().state_vector();
+        sink.send(message(SYNC_STATE, state))
+            .await
+            .map_err(|e| e.to_string())?;
+
+        loop {
+            tokio::select! {
+                received = stream.next() => match received {
+                    Some(Ok(Message::Binary(data))) => {
+                        if let Some(reply) = self.receive(peer, &data)? {
+                            sink.send(reply).await.map_err(|e| e.to_string())?;
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | None => return Ok(()),
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return Err(e.to_string()),
+                },
+                update = outgoing.recv() => match update {
+                    Ok((origin, update)) if origin != peer => {
+                        sink.send(message(UPDATE, update)).await.map_err(|e| e.to_string())?;
+                    }
+                    Ok(_) => {}
+                    // Missed updates are recovered by syncing the whole state again.
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        let state = self.document.lock().unwrap().state_vector();
+                        sink.send(message(SYNC_STATE, state)).await.map_err(|e| e.to_string())?;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return Ok(()),
+                },
+            }
+        }
+    }
+
+    /// Handles a message of `peer`. Returns the reply, if any.
+    fn receive(&self, peer: u64, data: &[u8]) -> Result<Option<Message>, String> {
+        let (&kind, payload) = data.split_first().ok_or("empty message")?;
+        match kind {
+            SYNC_STATE => {
+                let diff = self.document.lock().unwrap().diff(payload)?;
+                Ok(Some(message(SYNC_DIFF, diff)))
+            }
+            SYNC_DIFF | UPDATE => {
+                let mut changed = self.document.lock().unwrap().apply_remote(payload)?;
+                if !changed.is_empty() {
+                    // Relays the update to the host's other peers.
+                    let _ = self.outgoing.send((peer, payload.to_vec()));
+                    let opened = self.opened.lock().unwrap();
+                    changed.retain(|(path, _)| opened.contains(path));
+                    drop(opened);
+                    if !changed.is_empty() {
+                        (self.on_remote)(changed);
+                    }
+                }
+                Ok(None)
+            }
+            kind => Err(format!("unknown message {}", kind)),
+        }
+    }
+}
+
+fn message(kind: u8, payload: Vec<u8>) -> Message {
+    let mut data = Vec::with_capacity(payload.len() + 1);
+    data.push(kind);
+    data.extend(payload);
+    Message::Binary(data)
+}
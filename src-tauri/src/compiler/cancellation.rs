@@ -1,6 +1,7 @@
 use crate::project::ProjectWorld;
+use std::collections::{BTreeMap, HashSet};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use typst::diag::{FileError, FileResult};
 use typst::foundations::{Bytes, Datetime};
 use typst::syntax::{FileId, Source};
@@ -9,15 +10,40 @@ use typst::utils::LazyHash;
 use typst::{Library, World};
 
 /// A wrapper around `ProjectWorld` that checks for cancellation
-/// before performing expensive operations.
+/// before performing expensive operations. It also logs every file the
+/// compilation accesses, which is used to track the dependencies of a target.
 pub struct CancellableWorld<'a> {
     pub world: &'a ProjectWorld,
     pub token: Arc<AtomicBool>,
+    accessed: Mutex<HashSet<FileId>>,
+    library: Option<Arc<LazyHash<Library>>>,
 }
 
 impl<'a> CancellableWorld<'a> {
     pub fn new(world: &'a ProjectWorld, token: Arc<AtomicBool>) -> Self {
-        Self { world, token }
+        Self {
+            world,
+            token,
+            accessed: Mutex::new(HashSet::new()),
+            library: None,
+        }
+    }
+
+    /// Exposes `inputs` as `sys.inputs` instead of the world's own (empty) inputs.
+    pub fn with_inputs(mut self, inputs: &BTreeMap<String, String>) -> Self {
+        if !inputs.is_empty() {
+            self.library = Some(self.world.engine().library_for_inputs(inputs));
+        }
+        self
+    }
+
+    /// Files read through this world so far, including the main file.
+    pub fn accessed(&self) -> HashSet<FileId> {
+        self.accessed.lock().unwrap().clone()
+    }
+
+    fn record(&self, id: FileId) {
+        self.accessed.lock().unwrap().insert(id);
     }
 
     fn check_cancellation(&self) -> FileResult<()> {
@@ -30,7 +56,7 @@ impl<'a> CancellableWorld<'a> {
 
 impl<'a> World for CancellableWorld<'a> {
     fn library(&self) -> &LazyHash<Library> {
-        self.world.library()
+        self.library.as_deref().unwrap_or_else(|| self.world.library())
     }
 
     fn book(&self) -> &LazyHash<FontBook> {
@@ -42,10 +68,12 @@ impl<'a> World for CancellableWorld<'a> {
     }
 
     fn source(&self, id: FileId) -> FileResult<Source> {
+        self.record(id);
         self.world.source(id)
     }
 
     fn file(&self, id: FileId) -> FileResult<Bytes> {
+        self.record(id);
         self.world.file(id)
     }
 
@@ -0,0 +1,242 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use typst::layout::{Frame, FrameItem};
+
+const PT_PER_MM: f64 = 72.0 / 25.4;
+const DEFAULT_COLOR: &str = "#3b82f6";
+
+/// Guides drawn over the preview pages only, never part of the document or exports.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct PreviewDecorations {
+    /// Spacing of grid paper lines in millimeters, or no grid if unset.
+    pub grid_mm: Option<f64>,
+    /// Millimeter marks along the top and left edges, with the margins around the
+    /// page's content shaded.
+    pub margin_ruler: bool,
+    /// Splits the content area into this many column guides. 0 and 1 draw none.
+    pub columns: u32,
+    pub column_gutter_mm: f64,
+    /// CSS hex color of the guides, eg. `#3b82f6`.
+    pub color: String,
+}
+
+impl Default for PreviewDecorations {
+    fn default() -> Self {
+        Self {
+            grid_mm: None,
+            margin_ruler: false,
+            columns: 0,
+            column_gutter_mm: 5.0,
+            color: DEFAULT_COLOR.into(),
+        }
+    }
+}
+
+/// A rectangle in points, relative to the page's top left corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub x0: f64,
+    pub y0: f64,
+    pub x1: f64,
+    pub y1: f64,
+}
+
+impl Bounds {
+    fn area(&self) -> f64 {
+        (self.x1 - self.x0) * (self.y1 - self.y0)
+    }
+}
+
+/// Typst's margins when the page sets none: 2.5/21 of the shorter side.
+fn default_margin(width: f64, height: f64) -> f64 {
+    2.5 / 21.0 * width.min(height)
+}
+
+/// The area within the page's margins. Typst places the page's body as a group at the
+/// top left margin, sized to the area within the margins, however little of it the body
+/// fills; headers and footers are groups as wide but lower. A body merged into the page's
+/// frame, or one without margins, leaves no such group, and the default margins are
+/// assumed.
+pub fn content_bounds(frame: &Frame) -> Bounds {
+    let (width, height) = (frame.width().to_pt(), frame.height().to_pt());
+    let body = frame
+        .items()
+        .filter_map(|(pos, item)| match item {
+            FrameItem::Group(group) => Some(Bounds {
+                x0: pos.x.to_pt(),
+                y0: pos.y.to_pt(),
+                x1: (pos.x + group.frame.width()).to_pt(),
+                y1: (pos.y + group.frame.height()).to_pt(),
+            }),
+            _ => None,
+        })
+        .filter(|b| (b.x0 > 0.0 || b.y0 > 0.0) && b.x1 <= width + 0.01 && b.y1 <= height + 0.01)
+        .max_by(|a, b| a.area().total_cmp(&b.area()));
+    body.unwrap_or_else(|| {
+        let margin = default_margin(width, height);
+        Bounds {
+            x0: margin,
+            y0: margin,
+            x1: width - margin,
+            y1: height - margin,
+        }
+    })
+}
+
+/// Only hex colors are used, as the value ends up in SVG markup.
+fn is_hex_color(color: &str) -> bool {
+    color.strip_prefix('#').is_some_and(|hex| {
+        matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit())
+    })
+}
+
+impl PreviewDecorations {
+    pub fn is_empty(&self) -> bool {
+        self.grid_mm.is_none() && !self.margin_ruler && self.columns < 2
+    }
+
+    /// An SVG of the page's size holding the decorations, to be laid over the page.
+    pub fn overlay(&self, frame: &Frame) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+        let size = frame.size();
+        Some(self.overlay_svg(size.x.to_pt(), size.y.to_pt(), content_bounds(frame)))
+    }
+
+    fn overlay_svg(&self, width: f64, height: f64, content: Bounds) -> String {
+        let color = if is_hex_color(&self.color) {
+            self.color.as_str()
+        } else {
+            DEFAULT_COLOR
+        };
+
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}pt" height="{h}pt" viewBox="0 0 {w} {h}" fill="none" stroke="{color}">"#,
+            w = width,
+            h = height,
+        );
+
+        if let Some(spacing) = self.grid_mm.filter(|mm| *mm >= 1.0) {
+            let step = spacing * PT_PER_MM;
+            let _ = write!(
+                svg,
+                r#"<defs><pattern id="grid" width="{s}" height="{s}" patternUnits="userSpaceOnUse"><path d="M {s} 0 L 0 0 0 {s}" stroke-width="0.3"/></pattern></defs><rect width="{w}" height="{h}" fill="url(#grid)" stroke="none" opacity="0.35"/>"#,
+                s = step,
+                w = width,
+                h = height,
+            );
+        }
+
+        if self.columns >= 2 {
+            let gutter = self.column_gutter_mm.max(0.0) * PT_PER_MM;
+            let columns = self.columns as f64;
+            let column = ((content.x1 - content.x0) - gutter * (columns - 1.0)) / columns;
+            if column > 0.0 {
+                for i in 0..self.columns {
+                    let x = content.x0 + i as f64 * (column + gutter);
+                    let _ = write!(
+                        svg,
+                        r#"<rect x="{x}" y="{y}" width="{column}" height="{h}" stroke-width="0.5" stroke-dasharray="4 2" fill="{color}" fill-opacity="0.04"/>"#,
+                        y = content.y0,
+                        h = content.y1 - content.y0,
+                    );
+                }
+            }
+        }
+
+        if self.margin_ruler {
+            let ruler = 12.0;
+            let _ = write!(
+                svg,
+                r#"<g fill="{color}" stroke="none" opacity="0.15"><rect width="{left}" height="{ruler}"/><rect x="{right}" width="{rw}" height="{ruler}"/><rect width="{ruler}" height="{top}"/><rect y="{bottom}" width="{ruler}" height="{bh}"/></g>"#,
+                left = content.x0,
+                right = content.x1,
+                rw = (width - content.x1).max(0.0),
+                top = content.y0,
+                bottom = content.y1,
+                bh = (height - content.y1).max(0.0),
+            );
+            let mut ticks = String::new();
+            let tick = |mm: u32| {
+                if mm % 10 == 0 {
+                    8.0
+                } else if mm % 5 == 0 {
+                    5.0
+                } else {
+                    2.5
+                }
+            };
+            for mm in 1..=(width / PT_PER_MM) as u32 {
+                let x = mm as f64 * PT_PER_MM;
+                let _ = write!(ticks, "M {x} 0 V {} ", tick(mm));
+            }
+            for mm in 1..=(height / PT_PER_MM) as u32 {
+                let y = mm as f64 * PT_PER_MM;
+                let _ = write!(ticks, "M 0 {y} H {} ", tick(mm));
+            }
+            let _ = write!(
+                svg,
+                r#"<path d="{}" stroke-width="0.4"/><path d="M {x0} 0 V {h} M {x1} 0 V {h} M 0 {y0} H {w} M 0 {y1} H {w}" stroke-width="0.5" stroke-dasharray="2 2" opacity="0.6"/>"#,
+                ticks.trim_end(),
+                x0 = content.x0,
+                x1 = content.x1,
+                y0 = content.y0,
+                y1 = content.y1,
+                w = width,
+                h = height,
+            );
+        }
+
+        svg.push_str("</svg>");
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{content_bounds, default_margin, Bounds, PreviewDecorations};
+    use typst::layout::{Abs, Frame, Point, Size};
+
+    #[test]
+    fn test_overlay_svg() {
+        let decorations = PreviewDecorations {
+            grid_mm: Some(5.0),
+            margin_ruler: true,
+            columns: 2,
+            color: "\"/><script>".into(),
+            ..Default::default()
+        };
+        let content = Bounds {
+            x0: 72.0,
+            y0: 72.0,
+            x1: 523.0,
+            y1: 770.0,
+        };
+        let svg = decorations.overlay_svg(595.0, 842.0, content);
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>"));
+        assert!(!svg.contains("<script>"));
+        assert!(svg.contains(r##"stroke="#3b82f6""##));
+        assert!(svg.contains(r#"<pattern id="grid""#));
+        assert_eq!(svg.matches("stroke-dasharray=\"4 2\"").count(), 2);
+
+        assert!(PreviewDecorations::default().is_empty());
+    }
+
+    #[test]
+    fn test_content_bounds() {
+        let size = |w, h| Size::new(Abs::pt(w), Abs::pt(h));
+        let mut page = Frame::hard(size(595.0, 842.0));
+        page.push_frame(Point::zero(), Frame::hard(size(595.0, 842.0)));
+        page.push_frame(Point::new(Abs::pt(50.0), Abs::pt(60.0)), Frame::hard(size(495.0, 722.0)));
+        page.push_frame(Point::new(Abs::pt(50.0), Abs::pt(800.0)), Frame::hard(size(495.0, 20.0)));
+        let bounds = content_bounds(&page);
+        assert_eq!((bounds.x0, bounds.y0, bounds.x1, bounds.y1), (50.0, 60.0, 545.0, 782.0));
+
+        // Without the body's group, the default margins are assumed.
+        let margin = default_margin(595.0, 842.0);
+        let bounds = content_bounds(&Frame::hard(size(595.0, 842.0)));
+        assert_eq!((bounds.x0, bounds.y1), (margin, 842.0 - margin));
+    }
+}
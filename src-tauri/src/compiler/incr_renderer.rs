@@ -11,9 +11,14 @@ pub struct PageRenderCache {
     pub data_tid: String,
 }
 
+/// Inverts the colors of what it's applied to. Inverting twice restores them exactly.
+const DARK_FILTER: &str = r#"<filter id="typstudio-dark" color-interpolation-filters="sRGB"><feComponentTransfer><feFuncR type="table" tableValues="1 0"/><feFuncG type="table" tableValues="1 0"/><feFuncB type="table" tableValues="1 0"/></feComponentTransfer></filter>"#;
+
 pub struct IncrementalRenderer {
     page_cache: HashMap<usize, PageRenderCache>,
     render_version: u64,
+    /// Renders pages with inverted colors, see [`dark_svg`].
+    dark: bool,
 }
 
 impl Default for IncrementalRenderer {
@@ -27,6 +32,7 @@ impl IncrementalRenderer {
         Self {
             page_cache: HashMap::new(),
             render_version: 0,
+            dark: false,
         }
     }
 
@@ -35,6 +41,14 @@ impl IncrementalRenderer {
         self.render_version = 0;
     }
 
+    /// Switches between light and dark pages. Changing it drops the cached pages.
+    pub fn set_dark(&mut self, dark: bool) {
+        if self.dark != dark {
+            self.dark = dark;
+            self.page_cache.clear();
+        }
+    }
+
     fn compute_page_hash(page: &Page) -> u128 {
         let mut hasher = SipHasher::new();
         page.frame.hash(&mut hasher);
@@ -65,8 +79,12 @@ impl IncrementalRenderer {
             }
         }
         
-        let svg = typst_svg::svg(page);
-        let data_tid = Self::generate_data_tid(frame_hash, page_index);
+        let mut svg = typst_svg::svg(page);
+        let mut data_tid = Self::generate_data_tid(frame_hash, page_index);
+        if self.dark {
+            svg = dark_svg(&svg);
+            data_tid.push_str("-dark");
+        }
         let svg_with_tid = Self::add_data_tid_to_svg(&svg, &data_tid);
         
         self.page_cache.insert(page_index, PageRenderCache {
@@ -122,3 +140,40 @@ impl IncrementalRenderer {
         self.render_version
     }
 }
+
+/// A dark version of a page's SVG: every color is inverted, so the page turns black and
+/// its text white, except in images, which are inverted back to their own colors.
+pub fn dark_svg(svg: &str) -> String {
+    let (Some(start), Some(end)) = (svg.find("<svg"), svg.rfind("</svg>")) else {
+        return svg.to_string();
+    };
+    let Some(open_end) = svg[start..].find('>').map(|i| start + i + 1) else {
+        return svg.to_string();
+    };
+    let body = svg[open_end..end].replace("<image ", "<image filter=\"url(#typstudio-dark)\" ");
+    format!(
+        "{}<defs>{}</defs><g filter=\"url(#typstudio-dark)\">{}</g>{}",
+        &svg[..open_end],
+        DARK_FILTER,
+        body,
+        &svg[end..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dark_svg() {
+        let svg = r#"<svg class="typst-doc" viewBox="0 0 10 10"><path d="M0 0"/><image width="5" href="a.png"/></svg>"#;
+        assert_eq!(
+            dark_svg(svg),
+            format!(
+                r#"<svg class="typst-doc" viewBox="0 0 10 10"><defs>{}</defs><g filter="url(#typstudio-dark)"><path d="M0 0"/><image filter="url(#typstudio-dark)" width="5" href="a.png"/></g></svg>"#,
+                DARK_FILTER
+            )
+        );
+        assert_eq!(dark_svg("not svg"), "not svg");
+    }
+}
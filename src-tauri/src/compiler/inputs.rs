@@ -0,0 +1,182 @@
+use crate::engine::TypstEngine;
+use crate::project::Project;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use typst::diag::FileResult;
+use typst::foundations::{Bytes, Datetime};
+use typst::layout::PagedDocument;
+use typst::syntax::{FileId, Source};
+use typst::text::{Font, FontBook};
+use typst::utils::LazyHash;
+use typst::{Library, World};
+
+/// The app's color theme, for documents that opt into a matching preview.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct PreviewTheme {
+    pub dark: bool,
+    /// Accent color as a CSS hex string, eg. `#3b82f6`.
+    pub accent: Option<String>,
+}
+
+/// The `sys.inputs` key holding the seed of randomized documents such as exam variants.
+pub const SEED_INPUT: &str = "seed";
+
+/// Inputs that only apply to preview compiles, never to exports.
+#[derive(Default, Debug, Clone)]
+pub struct PreviewInputs {
+    pub theme: Option<PreviewTheme>,
+    /// Preview overrides of the project's toggles.
+    pub toggles: BTreeMap<String, bool>,
+    /// The seed shown in the preview. Unlike the theme, exports of the preview keep it.
+    pub seed: Option<u64>,
+}
+
+impl PreviewInputs {
+    /// The `sys.inputs` entries for a preview compile. Documents read them with
+    /// eg. `sys.inputs.at("typstudio-theme", default: "light")`.
+    pub fn to_inputs(&self, defaults: &BTreeMap<String, bool>) -> BTreeMap<String, String> {
+        let mut inputs = toggle_inputs(defaults, &self.toggles);
+        if let Some(seed) = self.seed {
+            inputs.insert(SEED_INPUT.to_string(), seed.to_string());
+        }
+        if let Some(theme) = &self.theme {
+            let mode = if theme.dark { "dark" } else { "light" };
+            inputs.insert("typstudio-theme".to_string(), mode.to_string());
+            if let Some(accent) = &theme.accent {
+                inputs.insert("typstudio-accent".to_string(), accent.clone());
+            }
+        }
+        inputs
+    }
+}
+
+/// The project's toggles as `"true"`/`"false"` inputs, with `overrides` taking
+/// precedence over the configured defaults. Unknown overrides are ignored.
+pub fn toggle_inputs(
+    defaults: &BTreeMap<String, bool>,
+    overrides: &BTreeMap<String, bool>,
+) -> BTreeMap<String, String> {
+    defaults
+        .iter()
+        .map(|(name, default)| {
+            let value = overrides.get(name).unwrap_or(default);
+            (name.clone(), value.to_string())
+        })
+        .collect()
+}
+
+/// A world that exposes `inputs` as `sys.inputs` while reading everything else
+/// from the wrapped world.
+pub struct InputsWorld<'a> {
+    world: &'a dyn World,
+    library: LazyHash<Library>,
+    prelude: Option<String>,
+}
+
+impl<'a> InputsWorld<'a> {
+    pub fn new(world: &'a dyn World, inputs: &BTreeMap<String, String>) -> Self {
+        Self {
+            world,
+            library: LazyHash::new(TypstEngine::library_with_inputs(inputs)),
+            prelude: None,
+        }
+    }
+
+    /// Replaces the standard library, eg. with one that has extra features enabled. It
+    /// should hold the same inputs.
+    pub fn with_library(mut self, library: Library) -> Self {
+        self.library = LazyHash::new(library);
+        self
+    }
+
+    /// Prepends markup to the main file, eg. show rules that apply to the whole document.
+    pub fn with_prelude(mut self, prelude: String) -> Self {
+        self.prelude = Some(prelude);
+        self
+    }
+}
+
+impl<'a> World for InputsWorld<'a> {
+    fn library(&self) -> &LazyHash<Library> {
+        &self.library
+    }
+
+    fn book(&self) -> &LazyHash<FontBook> {
+        self.world.book()
+    }
+
+    fn main(&self) -> FileId {
+        self.world.main()
+    }
+
+    fn source(&self, id: FileId) -> FileResult<Source> {
+        let source = self.world.source(id)?;
+        match &self.prelude {
+            Some(prelude) if id == self.main() => {
+                Ok(Source::new(id, format!("{}{}", prelude, source.text())))
+            }
+            _ => Ok(source),
+        }
+    }
+
+    fn file(&self, id: FileId) -> FileResult<Bytes> {
+        self.world.file(id)
+    }
+
+    fn font(&self, id: usize) -> Option<Font> {
+        self.world.font(id)
+    }
+
+    fn today(&self, offset: Option<i64>) -> Option<Datetime> {
+        self.world.today(offset)
+    }
+}
+
+/// Compiles the project's main file with the given `sys.inputs`, independently of the
+/// preview document. Errors are flattened into a single message.
+pub fn compile_with_inputs(
+    project: &Project,
+    inputs: &BTreeMap<String, String>,
+) -> Result<PagedDocument, String> {
+    compile_with_prelude(project, inputs, None)
+}
+
+/// Like [`compile_with_inputs`], additionally prepending `prelude` to the main file.
+pub fn compile_with_prelude(
+    project: &Project,
+    inputs: &BTreeMap<String, String>,
+    prelude: Option<String>,
+) -> Result<PagedDocument, String> {
+    // Compiled from a snapshot, so the preview isn't held up by a long export.
+    let world = {
+        let mut world = project.world.lock().unwrap_or_else(|e| e.into_inner());
+        if !world.is_main_set() {
+            let config = project.config.read().unwrap();
+            config
+                .apply_main(project, &mut world)
+                .map_err(|_| "no main file is configured".to_string())?;
+        }
+        world.snapshot()
+    };
+
+    let mut inputs_world = InputsWorld::new(&world, inputs);
+    if let Some(prelude) = prelude {
+        inputs_world = inputs_world.with_prelude(prelude);
+    }
+    let result = typst::compile::<PagedDocument>(&inputs_world);
+    drop(inputs_world);
+    project
+        .world
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .absorb(&world);
+    result
+        .output
+        .map_err(|diagnostics| {
+            diagnostics
+                .iter()
+                .map(|d| d.message.to_string())
+                .collect::<Vec<_>>()
+                .join("; ")
+        })
+}
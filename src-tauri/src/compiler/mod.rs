@@ -1,6 +1,26 @@
 mod cancellation;
+mod decorations;
 mod incr_renderer;
+mod inputs;
+mod pipeline;
+mod problems;
+mod profile;
+mod render_queue;
+mod revision;
 mod service;
+mod sink;
+mod snippet;
+mod tile;
 
+pub use decorations::*;
 pub use incr_renderer::*;
+pub use inputs::*;
+pub use pipeline::*;
+pub use problems::*;
+pub use profile::*;
+pub use render_queue::*;
+pub use revision::*;
 pub use service::*;
+pub use sink::*;
+pub use snippet::*;
+pub use tile::*;
@@ -0,0 +1,464 @@
+use crate::analysis::{defined_names, explain_diagnostic, find_include_cycles, IncludeCycle};
+use crate::compiler::cancellation::CancellableWorld;
+use crate::compiler::{
+    emit_event, project_diagnostics, send_event, BackendEvent, CompileProfiler, EventSink,
+    Hotspot, StageTiming,
+};
+use crate::document::{changed_regions, check_page_budget, document_word_count, resolve_bookmarks};
+use crate::ipc::{
+    CompileCacheInfo, CompileProfileEvent, PageBudgetEvent, PreviewBookmarksEvent, PreviewChangesEvent,
+    ProjectDiagnosticsEvent, TypstCompileEvent,
+    TypstDiagnosticSeverity, TypstDocument, TypstSourceDiagnostic,
+};
+use crate::project::Project;
+use log::error;
+use siphasher::sip128::{Hasher128, SipHasher};
+use std::hash::Hash;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use typst::diag::Severity;
+use typst::syntax::{FileId, VirtualPath};
+use typst::World;
+
+/// Memoized results unused for this many compiles are evicted, like `typst watch` does.
+const MEMO_MAX_AGE: usize = 10;
+
+#[derive(Clone, Debug)]
+pub struct CompileRequest {
+    pub path: PathBuf,
+    pub content: String,
+    pub main_path: Option<PathBuf>,
+    pub request_id: u64,
+    pub window_label: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompileOutcome {
+    /// Cancelled or superseded by a newer request, so nothing was reported.
+    Skipped,
+    /// The includes form a cycle, reported as diagnostics without compiling.
+    Cycle,
+    Compiled,
+    Failed,
+    /// Took longer than the timeout, reported as a diagnostic without waiting for it.
+    TimedOut,
+}
+
+/// The compiled target and the edited file of a request.
+pub fn compile_target(req: &CompileRequest) -> (FileId, FileId) {
+    let main = req.main_path.as_ref().unwrap_or(&req.path);
+    (
+        FileId::new(None, VirtualPath::new(main)),
+        FileId::new(None, VirtualPath::new(&req.path)),
+    )
+}
+
+/// Applies the edit of `req` to the project's world, compiles it and reports the
+/// document or diagnostics to `sink`. Uses no Tauri types, so it can later run in a
+/// server or WASM host too; it isn't yet split into a crate of its own that builds
+/// without the app.
+///
+/// The compile runs on a snapshot of the world, so the project's lock is only held to
+/// apply the edit. A compile taking longer than `timeout` is cancelled and reported as
+/// timed out right away. Typst can't be interrupted mid-computation, so it is left to
+/// stop when it next reads a file, which then fails, or to finish on its own. The next
+/// compile of the project waits for it to exit, unless a newer request comes first.
+pub fn compile_project(
+    project: &Project,
+    req: &CompileRequest,
+    token: Arc<AtomicBool>,
+    timeout: Option<Duration>,
+    sink: &dyn EventSink,
+) -> CompileOutcome {
+    let started = Instant::now();
+    if !wait_for_abandoned(project, &token) {
+        return CompileOutcome::Skipped;
+    }
+    let mut world_guard = project.world.lock().unwrap_or_else(|e| {
+        log::warn!("Project world mutex poisoned, recovering: {}", e);
+        e.into_inner()
+    });
+
+    if token.load(Ordering::Relaxed) {
+        return CompileOutcome::Skipped;
+    }
+
+    let update_res = world_guard.slot_update(&req.path, Some(req.content.clone()));
+    if let Err(e) = update_res {
+        error!("Failed to update slot: {:?}", e);
+        return CompileOutcome::Skipped;
+    }
+
+    let main_to_set = req.main_path.as_ref().unwrap_or(&req.path);
+    world_guard.set_main_path(VirtualPath::new(main_to_set));
+
+    if !world_guard.is_main_set() {
+        let config = project.config.read().unwrap();
+        if config.apply_main(project, &mut world_guard).is_err() {
+            return CompileOutcome::Skipped;
+        }
+    }
+
+    let version = world_guard.revision();
+    let cycles = find_include_cycles(&*world_guard, world_guard.main());
+    if !cycles.is_empty() {
+        drop(world_guard);
+        if is_superseded(project, req) {
+            return CompileOutcome::Skipped;
+        }
+        emit_event(
+            sink,
+            BackendEvent::Compile(TypstCompileEvent {
+                version,
+                document: None,
+                diagnostics: Some(cycle_diagnostics(&cycles, req)),
+            }),
+        );
+        return CompileOutcome::Cycle;
+    }
+
+    let world = world_guard.snapshot();
+    drop(world_guard);
+
+    let toggles = project.config.read().unwrap().toggles.clone();
+    let inputs = project.preview_inputs.read().unwrap().to_inputs(&toggles);
+    let profile = project.profile_compiles.load(Ordering::Relaxed);
+    let compile_token = token.clone();
+    let compiled = with_watchdog(timeout, &project.abandoned_compile, move || {
+        let profiler = profile.then(CompileProfiler::start);
+        let cancellable_world = CancellableWorld::new(&world, compile_token).with_inputs(&inputs);
+        let result = typst::compile::<typst::layout::PagedDocument>(&cancellable_world);
+        let timings = profiler.map(|profiler| profiler.finish(&cancellable_world));
+        let accessed = cancellable_world.accessed();
+        drop(cancellable_world);
+        (result, timings, accessed, world)
+    });
+    let Some((result, timings, accessed, world)) = compiled else {
+        token.store(true, Ordering::Relaxed);
+        if !is_superseded(project, req) {
+            let seconds = timeout.unwrap_or_default().as_secs_f64();
+            emit_event(
+                sink,
+                BackendEvent::Compile(TypstCompileEvent {
+                    version,
+                    document: None,
+                    diagnostics: Some(vec![timeout_diagnostic(seconds)]),
+                }),
+            );
+        }
+        return CompileOutcome::TimedOut;
+    };
+
+    let (target, _) = compile_target(req);
+    let mut cache_info = CompileCacheInfo {
+        files_accessed: accessed.len(),
+        ..CompileCacheInfo::default()
+    };
+    if result.output.is_ok() {
+        project.dependencies.set(target, accessed);
+    } else {
+        project.dependencies.invalidate(target);
+    }
+    let errors = result.output.as_ref().err().map_or(&[][..], |errors| errors.as_slice());
+    let problems = ProjectDiagnosticsEvent::new(
+        version,
+        project_diagnostics(&world, errors.iter().chain(&result.warnings)),
+    );
+    project
+        .world
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .absorb(&world);
+    comemo::evict(MEMO_MAX_AGE);
+
+    if is_superseded(project, req) {
+        return CompileOutcome::Skipped;
+    }
+    // Every file's problems, not only the edited file's like the compile event.
+    project.cache.write().unwrap().problems = Some(problems.clone());
+    send_event(sink, "project_diagnostics", problems);
+
+    match result.output {
+        Ok(doc) => {
+            let pages = doc.pages.len();
+            let mut hasher = SipHasher::new();
+            for page in &doc.pages {
+                page.frame.hash(&mut hasher);
+            }
+            let hash = hex::encode(hasher.finish128().as_bytes());
+
+            let first_page = &doc.pages[0];
+            let width = first_page.frame.width();
+            let height = first_page.frame.height();
+
+            let max_prerender = std::cmp::min(pages, 10);
+            let page_svgs: Vec<String> = (0..max_prerender)
+                .map(|i| {
+                    let page = &doc.pages[i];
+                    let mut renderer = project.renderer.lock().unwrap_or_else(|e| e.into_inner());
+                    let (svg, rendered) = renderer.render_page(i, page);
+                    if rendered {
+                        cache_info.pages_rendered += 1;
+                    } else {
+                        cache_info.pages_reused += 1;
+                    }
+                    svg
+                })
+                .collect();
+
+            let budget = project.config.read().unwrap().page_budget.clone();
+            if !budget.is_empty() {
+                send_event(
+                    sink,
+                    "page_budget",
+                    PageBudgetEvent {
+                        pages,
+                        overruns: check_page_budget(&doc, &budget),
+                    },
+                );
+            }
+
+            let bookmarks = project.config.read().unwrap().bookmarks.clone();
+            if !bookmarks.is_empty() {
+                send_event(
+                    sink,
+                    "preview_bookmarks",
+                    PreviewBookmarksEvent {
+                        bookmarks: resolve_bookmarks(&doc, &bookmarks),
+                    },
+                );
+            }
+
+            let changes = project
+                .cache
+                .read()
+                .unwrap()
+                .document
+                .as_ref()
+                .map(|previous| changed_regions(previous, &doc));
+            project.statistics.record_compile(|| document_word_count(&doc));
+            {
+                let mut cache = project.cache.write().unwrap();
+                cache.document = Some(doc);
+                cache.version = version;
+            }
+            project.document_versions.send_replace(version);
+
+            emit_event(
+                sink,
+                BackendEvent::Compile(TypstCompileEvent {
+                    version,
+                    document: Some(TypstDocument {
+                        pages,
+                        hash,
+                        width: width.to_pt(),
+                        height: height.to_pt(),
+                        page_svgs,
+                    }),
+                    diagnostics: None,
+                }),
+            );
+
+            // After the compile event, so the preview already shows the changed pages.
+            if let Some(pages) = changes.filter(|pages| !pages.is_empty()) {
+                send_event(sink, "preview_changes", PreviewChangesEvent { version, pages });
+            }
+            if let Some(timings) = timings {
+                send_profile(sink, version, started, timings, cache_info);
+            }
+            CompileOutcome::Compiled
+        }
+        Err(diagnostics) => {
+            let id = FileId::new(None, VirtualPath::new(&req.path));
+
+            let source_res = world.source(id);
+            let mapped_diagnostics = if let Ok(source) = source_res {
+                diagnostics
+                    .iter()
+                    .filter(|d| d.span.id() == Some(id))
+                    .filter_map(|d| {
+                        let span = source.find(d.span)?;
+                        let range = span.range();
+                        let start = req.content[..range.start].chars().count();
+                        let size = req.content[range.start..range.end].chars().count();
+
+                        Some(TypstSourceDiagnostic {
+                            range: start..start + size,
+                            severity: match d.severity {
+                                Severity::Error => TypstDiagnosticSeverity::Error,
+                                Severity::Warning => TypstDiagnosticSeverity::Warning,
+                            },
+                            message: d.message.to_string(),
+                            hints: d.hints.iter().map(|h| h.to_string()).collect(),
+                            explanation: explain_diagnostic(&d.message, &project.root, || {
+                                defined_names(&world, &source)
+                            }),
+                        })
+                    })
+                    .collect()
+            } else {
+                vec![]
+            };
+
+            emit_event(
+                sink,
+                BackendEvent::Compile(TypstCompileEvent {
+                    version,
+                    document: None,
+                    diagnostics: Some(mapped_diagnostics),
+                }),
+            );
+            if let Some(timings) = timings {
+                send_profile(sink, version, started, timings, cache_info);
+            }
+            CompileOutcome::Failed
+        }
+    }
+}
+
+/// Waits for the last timed out compile of `project` to exit. Returns `false` if `token`
+/// is cancelled first.
+fn wait_for_abandoned(project: &Project, token: &AtomicBool) -> bool {
+    loop {
+        if token.load(Ordering::Relaxed) {
+            return false;
+        }
+        let mut abandoned = project.abandoned_compile.lock().unwrap();
+        if abandoned.as_ref().map_or(true, |thread| thread.is_finished()) {
+            *abandoned = None;
+            return true;
+        }
+        drop(abandoned);
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Runs `compile` on its own thread and waits at most `timeout` for it, returning `None`
+/// once it runs longer. The compile itself carries on in the background until it returns,
+/// and its thread is kept in `abandoned`.
+fn with_watchdog<T: Send + 'static>(
+    timeout: Option<Duration>,
+    abandoned: &Mutex<Option<JoinHandle<()>>>,
+    compile: impl FnOnce() -> T + Send + 'static,
+) -> Option<T> {
+    let Some(timeout) = timeout else {
+        return Some(compile());
+    };
+    let (done, finished) = mpsc::channel();
+    let handle = std::thread::spawn(move || {
+        let _ = done.send(compile());
+    });
+    match finished.recv_timeout(timeout) {
+        Ok(output) => Some(output),
+        Err(RecvTimeoutError::Timeout) => {
+            *abandoned.lock().unwrap() = Some(handle);
+            None
+        }
+        Err(RecvTimeoutError::Disconnected) => match handle.join() {
+            Err(panic) => std::panic::resume_unwind(panic),
+            Ok(()) => None,
+        },
+    }
+}
+
+fn timeout_diagnostic(seconds: f64) -> TypstSourceDiagnostic {
+    TypstSourceDiagnostic {
+        range: 0..0,
+        severity: TypstDiagnosticSeverity::Error,
+        message: format!("compilation timed out after {} seconds", seconds),
+        hints: vec![
+            "look for an endless loop or recursion, or very expensive layout".to_string(),
+            "the timeout can be changed in the preview settings".to_string(),
+        ],
+        explanation: None,
+    }
+}
+
+fn send_profile(
+    sink: &dyn EventSink,
+    version: u64,
+    started: Instant,
+    (compile_ms, stages, hotspots): (f64, Vec<StageTiming>, Vec<Hotspot>),
+    cache: CompileCacheInfo,
+) {
+    send_event(
+        sink,
+        "compile_profile",
+        CompileProfileEvent {
+            version,
+            total_ms: started.elapsed().as_secs_f64() * 1000.0,
+            compile_ms,
+            stages,
+            hotspots,
+            cache,
+        },
+    );
+}
+
+/// Whether a newer request of the project already reported its result.
+fn is_superseded(project: &Project, req: &CompileRequest) -> bool {
+    let old_id = project
+        .current_compile_request_id
+        .fetch_max(req.request_id, Ordering::SeqCst);
+    req.request_id < old_id
+}
+
+/// Maps include cycles to diagnostics on the requested file. If the file is only
+/// reachable from a cycle without being part of it, the cycle is reported at its start.
+fn cycle_diagnostics(cycles: &[IncludeCycle], req: &CompileRequest) -> Vec<TypstSourceDiagnostic> {
+    let id = FileId::new(None, VirtualPath::new(&req.path));
+    cycles
+        .iter()
+        .flat_map(|cycle| {
+            let message = format!("cyclic include: {}", cycle.describe());
+            let ranges: Vec<_> = cycle
+                .edges
+                .iter()
+                .filter(|e| e.from == id)
+                .filter_map(|e| {
+                    let start = req.content.get(..e.range.start)?.chars().count();
+                    let size = req.content.get(e.range.clone())?.chars().count();
+                    Some(start..start + size)
+                })
+                .collect();
+            let ranges = if ranges.is_empty() { vec![0..0] } else { ranges };
+            ranges.into_iter().map(move |range| TypstSourceDiagnostic {
+                range,
+                severity: TypstDiagnosticSeverity::Error,
+                message: message.clone(),
+                hints: vec!["remove one of the includes to break the cycle".to_string()],
+                explanation: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_watchdog() {
+        let started = Instant::now();
+        let abandoned = Mutex::new(None);
+        let slow = with_watchdog(Some(Duration::from_millis(10)), &abandoned, || {
+            std::thread::sleep(Duration::from_millis(500));
+            0
+        });
+        assert_eq!(slow, None);
+        // The slow compile is left running instead of waited for, and kept track of.
+        assert!(started.elapsed() < Duration::from_millis(400));
+        let thread = abandoned.lock().unwrap().take().unwrap();
+        assert!(!thread.is_finished());
+        thread.join().unwrap();
+
+        let watchdog = Some(Duration::from_secs(5));
+        assert_eq!(with_watchdog(watchdog, &abandoned, || 1), Some(1));
+        assert_eq!(with_watchdog(None, &abandoned, || 2), Some(2));
+        assert!(abandoned.lock().unwrap().is_none());
+    }
+}
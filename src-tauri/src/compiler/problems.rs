@@ -0,0 +1,131 @@
+use crate::ipc::TypstDiagnosticSeverity;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use typst::diag::{Severity, SourceDiagnostic};
+use typst::World;
+
+/// A diagnostic of a compile, located by line so it can be listed without its file open.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ProjectDiagnostic {
+    pub severity: TypstDiagnosticSeverity,
+    pub message: String,
+    pub hints: Vec<String>,
+    /// 1-based line and column, `None` if the diagnostic has no place in the file.
+    pub start: Option<(usize, usize)>,
+    pub end: Option<(usize, usize)>,
+}
+
+/// The diagnostics of one file, in the order they appear in it.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct FileDiagnostics {
+    /// Relative to the project root, prefixed with the package for files of packages, eg.
+    /// `@preview/cetz:0.3.0/src/lib.typ`. `None` for diagnostics without a file.
+    pub path: Option<PathBuf>,
+    pub errors: usize,
+    pub warnings: usize,
+    pub diagnostics: Vec<ProjectDiagnostic>,
+}
+
+/// Groups `diagnostics` by the file they are in, sorted by path.
+pub fn project_diagnostics<'a>(
+    world: &dyn World,
+    diagnostics: impl IntoIterator<Item = &'a SourceDiagnostic>,
+) -> Vec<FileDiagnostics> {
+    let located = diagnostics.into_iter().map(|diagnostic| {
+        let id = diagnostic.span.id();
+        let path = id.map(|id| {
+            let path = id.vpath().as_rootless_path();
+            match id.package() {
+                Some(package) => PathBuf::from(package.to_string()).join(path),
+                None => path.to_path_buf(),
+            }
+        });
+        let range = id.and_then(|id| world.source(id).ok()).and_then(|source| {
+            let range = source.range(diagnostic.span)?;
+            let lines = source.lines();
+            let position = |offset: usize| -> Option<(usize, usize)> {
+                Some((
+                    lines.byte_to_line(offset)? + 1,
+                    lines.byte_to_column(offset)? + 1,
+                ))
+            };
+            Some((position(range.start), position(range.end)))
+        });
+        let (start, end) = range.unwrap_or_default();
+        let diagnostic = ProjectDiagnostic {
+            severity: match diagnostic.severity {
+                Severity::Error => TypstDiagnosticSeverity::Error,
+                Severity::Warning => TypstDiagnosticSeverity::Warning,
+            },
+            message: diagnostic.message.to_string(),
+            hints: diagnostic.hints.iter().map(|h| h.to_string()).collect(),
+            start,
+            end,
+        };
+        (path, diagnostic)
+    });
+    group_diagnostics(located)
+}
+
+fn group_diagnostics(
+    diagnostics: impl IntoIterator<Item = (Option<PathBuf>, ProjectDiagnostic)>,
+) -> Vec<FileDiagnostics> {
+    let mut files: BTreeMap<Option<PathBuf>, Vec<ProjectDiagnostic>> = BTreeMap::new();
+    for (path, diagnostic) in diagnostics {
+        files.entry(path).or_default().push(diagnostic);
+    }
+    files
+        .into_iter()
+        .map(|(path, mut diagnostics)| {
+            // Stable, so diagnostics at the same place keep typst's order.
+            diagnostics.sort_by_key(|d| d.start);
+            let errors = diagnostics
+                .iter()
+                .filter(|d| d.severity == TypstDiagnosticSeverity::Error)
+                .count();
+            FileDiagnostics {
+                path,
+                errors,
+                warnings: diagnostics.len() - errors,
+                diagnostics,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(severity: TypstDiagnosticSeverity, line: usize) -> ProjectDiagnostic {
+        ProjectDiagnostic {
+            severity,
+            message: format!("line {}", line),
+            hints: vec![],
+            start: Some((line, 1)),
+            end: Some((line, 5)),
+        }
+    }
+
+    #[test]
+    fn test_group_diagnostics() {
+        use TypstDiagnosticSeverity::{Error, Warning};
+        let chapter = Some(PathBuf::from("chapters/03.typ"));
+        let intro = Some(PathBuf::from("intro.typ"));
+        let files = group_diagnostics([
+            (intro.clone(), diagnostic(Warning, 2)),
+            (chapter.clone(), diagnostic(Error, 9)),
+            (chapter.clone(), diagnostic(Warning, 4)),
+            (chapter.clone(), diagnostic(Error, 1)),
+        ]);
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, chapter);
+        assert_eq!((files[0].errors, files[0].warnings), (2, 1));
+        let lines: Vec<_> = files[0].diagnostics.iter().map(|d| d.start).collect();
+        assert_eq!(lines, [Some((1, 1)), Some((4, 1)), Some((9, 1))]);
+        assert_eq!(files[1].path, intro);
+        assert_eq!((files[1].errors, files[1].warnings), (0, 1));
+    }
+}
@@ -0,0 +1,210 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Instant;
+use typst::syntax::Span;
+use typst::World;
+
+/// Hotspots beyond these are left out of a profile.
+const MAX_HOTSPOTS: usize = 15;
+
+/// Held while a compile is profiled, as Typst's timing is global and profiles of
+/// compiles running at the same time would clear and mix each other's scopes.
+static PROFILING: Mutex<()> = Mutex::new(());
+
+/// The time spent in one kind of Typst timing scope, eg. `eval` or `layout`, without
+/// counting scopes nested in a scope of the same kind twice.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct StageTiming {
+    pub name: String,
+    pub duration_ms: f64,
+    pub count: usize,
+}
+
+/// A timing scope at a place in the sources, eg. the calls of a slow function.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Hotspot {
+    pub name: String,
+    /// Project path of the file, eg. `/chapters/one.typ`.
+    pub path: String,
+    /// 1-based.
+    pub line: u32,
+    pub duration_ms: f64,
+    pub count: usize,
+}
+
+/// An event of the Chrome trace format that `typst_timing` exports.
+#[derive(Deserialize, Debug)]
+struct TraceEvent {
+    name: String,
+    ph: String,
+    /// Microseconds.
+    ts: f64,
+    #[serde(default)]
+    tid: u64,
+    #[serde(default)]
+    args: Option<TraceArgs>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct TraceArgs {
+    #[serde(default)]
+    file: Option<String>,
+    #[serde(default)]
+    line: Option<u32>,
+}
+
+/// Records the timing scopes of one compile. Profiled compiles wait for each other, but
+/// Typst's timing is global, so scopes of unprofiled compiles running at the same time,
+/// eg. an export, are recorded too.
+pub struct CompileProfiler {
+    started: Instant,
+    _exclusive: MutexGuard<'static, ()>,
+}
+
+impl CompileProfiler {
+    /// Waits for other profiled compiles to finish, then starts recording.
+    pub fn start() -> Self {
+        let exclusive = PROFILING.lock().unwrap_or_else(PoisonError::into_inner);
+        typst_timing::clear();
+        typst_timing::enable();
+        Self {
+            started: Instant::now(),
+            _exclusive: exclusive,
+        }
+    }
+
+    /// Stops recording. Returns the elapsed time in milliseconds, with the stages and
+    /// hotspots of the recorded scopes, whose spans are resolved in `world`.
+    pub fn finish(self, world: &dyn World) -> (f64, Vec<StageTiming>, Vec<Hotspot>) {
+        let elapsed = self.started.elapsed().as_secs_f64() * 1000.0;
+        typst_timing::disable();
+        let mut trace = vec![];
+        let exported = typst_timing::export_json(&mut trace, |raw| {
+            let span = Span::from_raw(raw);
+            span.id()
+                .and_then(|id| {
+                    let source = world.source(id).ok()?;
+                    let range = source.find(span)?.range();
+                    let line = source.lines().byte_to_line(range.start)?;
+                    let path = id.vpath().as_rootless_path().to_string_lossy();
+                    Some((format!("/{}", path), line as u32 + 1))
+                })
+                .unwrap_or_default()
+        });
+        typst_timing::clear();
+        let (stages, hotspots) = match exported {
+            Ok(()) => summarize_trace(&trace).unwrap_or_default(),
+            Err(e) => {
+                log::warn!("failed to export the compile timings: {}", e);
+                Default::default()
+            }
+        };
+        (elapsed, stages, hotspots)
+    }
+}
+
+/// Adds up the scopes of a trace by name, and by name and place for the hotspots.
+/// Scopes are matched per thread, as each thread's begin and end events nest.
+fn summarize_trace(json: &[u8]) -> serde_json::Result<(Vec<StageTiming>, Vec<Hotspot>)> {
+    let events: Vec<TraceEvent> = serde_json::from_slice(json)?;
+    let mut open: HashMap<u64, Vec<TraceEvent>> = HashMap::new();
+    let mut stages: HashMap<String, (f64, usize)> = HashMap::new();
+    let mut hotspots: HashMap<(String, String, u32), (f64, usize)> = HashMap::new();
+
+    for event in events {
+        let stack = open.entry(event.tid).or_default();
+        match event.ph.as_str() {
+            "B" => stack.push(event),
+            "E" => {
+                let Some(begin) = stack.pop() else { continue };
+                let duration = (event.ts - begin.ts).max(0.0) / 1000.0;
+                if !stack.iter().any(|outer| outer.name == begin.name) {
+                    let stage = stages.entry(begin.name.clone()).or_default();
+                    stage.0 += duration;
+                    stage.1 += 1;
+                }
+                let place = begin.args.as_ref().and_then(|args| {
+                    Some((args.file.clone().filter(|f| !f.is_empty())?, args.line?))
+                });
+                if let Some((path, line)) = place {
+                    let nested = stack.iter().any(|outer| {
+                        outer.name == begin.name
+                            && outer.args.as_ref().is_some_and(|args| {
+                                args.file.as_ref() == Some(&path) && args.line == Some(line)
+                            })
+                    });
+                    if !nested {
+                        let hotspot = hotspots.entry((begin.name, path, line)).or_default();
+                        hotspot.0 += duration;
+                        hotspot.1 += 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut stages: Vec<StageTiming> = stages
+        .into_iter()
+        .map(|(name, (duration_ms, count))| StageTiming {
+            name,
+            duration_ms,
+            count,
+        })
+        .collect();
+    stages.sort_by(|a, b| b.duration_ms.total_cmp(&a.duration_ms));
+    let mut hotspots: Vec<Hotspot> = hotspots
+        .into_iter()
+        .map(|((name, path, line), (duration_ms, count))| Hotspot {
+            name,
+            path,
+            line,
+            duration_ms,
+            count,
+        })
+        .collect();
+    hotspots.sort_by(|a, b| b.duration_ms.total_cmp(&a.duration_ms));
+    hotspots.truncate(MAX_HOTSPOTS);
+    Ok((stages, hotspots))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_trace() {
+        let trace = r#"[
+            {"name": "eval", "cat": "typst", "ph": "B", "ts": 0, "pid": 1, "tid": 1},
+            {"name": "func call", "ph": "B", "ts": 100, "pid": 1, "tid": 1, "args": {"file": "/lib.typ", "line": 3}},
+            {"name": "func call", "ph": "B", "ts": 200, "pid": 1, "tid": 1, "args": {"file": "/lib.typ", "line": 3}},
+            {"name": "func call", "ph": "E", "ts": 900, "pid": 1, "tid": 1},
+            {"name": "func call", "ph": "E", "ts": 1100, "pid": 1, "tid": 1},
+            {"name": "layout", "ph": "B", "ts": 500, "pid": 1, "tid": 2},
+            {"name": "layout", "ph": "E", "ts": 3500, "pid": 1, "tid": 2},
+            {"name": "eval", "ph": "E", "ts": 2000, "pid": 1, "tid": 1}
+        ]"#;
+        let (stages, hotspots) = summarize_trace(trace.as_bytes()).unwrap();
+        let stage = |name: &str| stages.iter().find(|s| s.name == name).unwrap();
+        assert_eq!(stages[0].name, "layout");
+        assert_eq!(stage("layout").duration_ms, 3.0);
+        assert_eq!(stage("eval").duration_ms, 2.0);
+        // The recursive call is only counted once.
+        assert_eq!(
+            (stage("func call").duration_ms, stage("func call").count),
+            (1.0, 1)
+        );
+        assert_eq!(
+            hotspots,
+            [Hotspot {
+                name: "func call".to_string(),
+                path: "/lib.typ".to_string(),
+                line: 3,
+                duration_ms: 1.0,
+                count: 1,
+            }]
+        );
+        assert!(summarize_trace(b"{").is_err());
+    }
+}
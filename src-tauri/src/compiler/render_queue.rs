@@ -0,0 +1,128 @@
+use crate::compiler::{send_event, EventSink};
+use crate::ipc::{TypstPageRenderedEvent, TypstRenderResponse};
+use crate::project::Project;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Pages rendered ahead on either side of the visible ones, so scrolling a little
+/// doesn't show blank pages.
+pub const PRERENDER_NEIGHBORS: usize = 2;
+
+/// The pages the preview shows, at the scale it shows them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VisiblePages {
+    pub first: usize,
+    /// Inclusive.
+    pub last: usize,
+    pub scale: f32,
+}
+
+/// Renders the pages of a project's preview, the visible ones first and then their
+/// neighbors. Scheduling new pages, eg. after a scroll, cancels the pages still waiting
+/// to be rendered for the old ones.
+#[derive(Default)]
+pub struct RenderQueue {
+    generation: AtomicU64,
+    visible: Mutex<Option<VisiblePages>>,
+}
+
+impl RenderQueue {
+    /// Renders `visible` and its neighbors in the background, sending each page to
+    /// `sink` as a `page_rendered` event.
+    pub fn schedule(
+        &self,
+        project: &Arc<Project>,
+        sink: Arc<dyn EventSink>,
+        visible: VisiblePages,
+    ) {
+        *self.visible.lock().unwrap() = Some(visible);
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let project = project.clone();
+        tokio::task::spawn_blocking(move || {
+            let queue = &project.render_queue;
+            let pages = match &project.cache.read().unwrap().document {
+                Some(doc) => doc.pages.len(),
+                None => return,
+            };
+            for page in render_order(visible.first, visible.last, pages, PRERENDER_NEIGHBORS) {
+                if queue.generation.load(Ordering::SeqCst) != generation {
+                    return;
+                }
+                if let Some(render) = render_page(&project, page, visible.scale) {
+                    send_event(
+                        &*sink,
+                        "page_rendered",
+                        TypstPageRenderedEvent { page, render },
+                    );
+                }
+            }
+        });
+    }
+
+    /// Renders the last scheduled pages again, eg. once a compile changed them.
+    pub fn reschedule(&self, project: &Arc<Project>, sink: Arc<dyn EventSink>) {
+        let visible = *self.visible.lock().unwrap();
+        if let Some(visible) = visible {
+            self.schedule(project, sink, visible);
+        }
+    }
+}
+
+/// The order to render pages `first..=last` and `neighbors` pages around them in, out
+/// of `pages`: the visible pages from the top, then the neighbors nearest first, those
+/// below before those above as reading goes down.
+pub fn render_order(first: usize, last: usize, pages: usize, neighbors: usize) -> Vec<usize> {
+    if pages == 0 {
+        return vec![];
+    }
+    let last = last.min(pages - 1);
+    let first = first.min(last);
+    let mut order: Vec<usize> = (first..=last).collect();
+    for distance in 1..=neighbors {
+        if last + distance < pages {
+            order.push(last + distance);
+        }
+        if let Some(before) = first.checked_sub(distance) {
+            order.push(before);
+        }
+    }
+    order
+}
+
+/// Renders `page` of the project's last document to SVG, `scale` giving the size in
+/// pixels. Returns `None` without a document or if it has no such page.
+pub fn render_page(project: &Project, page: usize, scale: f32) -> Option<TypstRenderResponse> {
+    let cache = project.cache.read().unwrap();
+    let p = cache.document.as_ref()?.pages.get(page)?;
+    let width = (p.frame.width().to_pt() * scale as f64) as u32;
+    let height = (p.frame.height().to_pt() * scale as f64) as u32;
+
+    let mut renderer = project.renderer.lock().unwrap_or_else(|e| e.into_inner());
+    let (svg, _was_changed) = renderer.render_page(page, p);
+    let overlay = project
+        .preview_decorations
+        .read()
+        .unwrap()
+        .overlay(&p.frame);
+    Some(TypstRenderResponse {
+        image: svg,
+        width,
+        height,
+        version: cache.version,
+        overlay,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_order() {
+        assert_eq!(render_order(3, 4, 10, 2), [3, 4, 5, 2, 6, 1]);
+        assert_eq!(render_order(0, 1, 3, 2), [0, 1, 2]);
+        // The range is clamped to the document, eg. after pages were removed.
+        assert_eq!(render_order(7, 9, 5, 1), [4, 3]);
+        assert!(render_order(0, 0, 0, 2).is_empty());
+    }
+}
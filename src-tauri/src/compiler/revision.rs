@@ -0,0 +1,113 @@
+use crate::git::repository_of;
+use git2::{Oid, Repository};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use typst::diag::{FileError, FileResult};
+use typst::foundations::{Bytes, Datetime};
+use typst::syntax::{FileId, Source};
+use typst::text::{Font, FontBook};
+use typst::utils::LazyHash;
+use typst::{Library, World};
+
+/// A world that reads the project's files as they were at a git revision, and fonts,
+/// packages and the main file from the wrapped world.
+///
+/// Files the revision lacks fall back to the wrapped world only if the project folder
+/// lacks them too, ie. if they come from an attached folder, which isn't versioned.
+pub struct RevisionWorld<'a> {
+    world: &'a dyn World,
+    root: PathBuf,
+    repo: Mutex<Repository>,
+    tree: Oid,
+    /// The project folder in the repository's working directory.
+    prefix: PathBuf,
+    files: Mutex<HashMap<FileId, FileResult<Bytes>>>,
+    sources: Mutex<HashMap<FileId, FileResult<Source>>>,
+}
+
+impl<'a> RevisionWorld<'a> {
+    /// The project at `root` at `rev`, eg. `HEAD~2` or a commit hash.
+    pub fn new(world: &'a dyn World, root: &Path, rev: &str) -> Result<Self, git2::Error> {
+        let (repo, prefix) = repository_of(root)
+            .ok_or_else(|| git2::Error::from_str("the project is not in a git repository"))?;
+        let tree = repo.revparse_single(rev)?.peel_to_tree()?.id();
+        Ok(Self {
+            world,
+            root: root.to_path_buf(),
+            repo: Mutex::new(repo),
+            tree,
+            prefix,
+            files: Mutex::default(),
+            sources: Mutex::default(),
+        })
+    }
+
+    fn read(&self, id: FileId) -> FileResult<Bytes> {
+        let path = id.vpath().as_rootless_path();
+        let repo = self.repo.lock().unwrap();
+        let relative = self.prefix.join(path);
+        let tree = repo
+            .find_tree(self.tree)
+            .map_err(|_| FileError::Other(None))?;
+        let blob = tree
+            .get_path(&relative)
+            .and_then(|entry| entry.to_object(&repo))
+            .ok()
+            .and_then(|object| object.into_blob().ok());
+        match blob {
+            Some(blob) => Ok(Bytes::new(blob.content().to_vec())),
+            None if !self.root.join(path).exists() => self.world.file(id),
+            None => Err(FileError::NotFound(self.root.join(path))),
+        }
+    }
+}
+
+impl<'a> World for RevisionWorld<'a> {
+    fn library(&self) -> &LazyHash<Library> {
+        self.world.library()
+    }
+
+    fn book(&self) -> &LazyHash<FontBook> {
+        self.world.book()
+    }
+
+    fn main(&self) -> FileId {
+        self.world.main()
+    }
+
+    fn source(&self, id: FileId) -> FileResult<Source> {
+        if id.package().is_some() {
+            return self.world.source(id);
+        }
+        if let Some(source) = self.sources.lock().unwrap().get(&id) {
+            return source.clone();
+        }
+        let source = self.file(id).and_then(|bytes| {
+            let text = std::str::from_utf8(&bytes).map_err(|_| FileError::InvalidUtf8)?;
+            Ok(Source::new(id, text.to_string()))
+        });
+        self.sources.lock().unwrap().insert(id, source.clone());
+        source
+    }
+
+    fn file(&self, id: FileId) -> FileResult<Bytes> {
+        if id.package().is_some() {
+            return self.world.file(id);
+        }
+        if let Some(file) = self.files.lock().unwrap().get(&id) {
+            return file.clone();
+        }
+        let file = self.read(id);
+        self.files.lock().unwrap().insert(id, file.clone());
+        file
+    }
+
+    fn font(&self, id: usize) -> Option<Font> {
+        self.world.font(id)
+    }
+
+    fn today(&self, offset: Option<i64>) -> Option<Datetime> {
+        self.world.today(offset)
+    }
+}
@@ -1,33 +1,22 @@
-use crate::compiler::cancellation::CancellableWorld;
-use crate::ipc::events::{emit_event, BackendEvent};
-use crate::ipc::{TypstCompileEvent, TypstDiagnosticSeverity, TypstDocument, TypstSourceDiagnostic};
+use crate::compiler::{compile_project, compile_target, CompileOutcome, CompileRequest};
+use crate::ipc::long_operations::report_long_operation;
+use crate::menu::update_menu_context;
 use crate::project::ProjectManager;
+use crate::settings::app_settings;
 use log::{debug, error};
-#[allow(unused_imports)]
-use serde::Serialize;
-use siphasher::sip128::{Hasher128, SipHasher};
-use std::hash::Hash;
-use std::path::PathBuf;
-use std::sync::{atomic::{AtomicBool, Ordering}, Arc};
+use std::collections::HashMap;
+use std::sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex};
+use std::time::{Duration, Instant};
 use tauri::{Manager, Runtime};
 use tokio::sync::watch;
-use tokio::task::JoinHandle;
-use typst::diag::Severity;
-use typst::World;
-
-#[derive(Clone, Debug)]
-pub struct CompileRequest {
-    pub path: PathBuf,
-    pub content: String,
-    pub main_path: Option<PathBuf>,
-    pub request_id: u64,
-    pub window_label: String,
-}
 
+/// Compiles the latest request of each window, so edits in one window never supersede
+/// or cancel the compiles of another.
 pub struct Compiler<R: Runtime> {
-    tx: watch::Sender<Option<CompileRequest>>,
-    _handle: JoinHandle<()>,
-    _marker: std::marker::PhantomData<R>,
+    project_manager: Arc<ProjectManager<R>>,
+    app: tauri::AppHandle<R>,
+    /// The pending request of each window by label, taken by the window's compile task.
+    windows: Mutex<HashMap<String, watch::Sender<Option<CompileRequest>>>>,
 }
 
 unsafe impl<R: Runtime> Send for Compiler<R> {}
@@ -35,174 +24,134 @@ unsafe impl<R: Runtime> Sync for Compiler<R> {}
 
 impl<R: Runtime> Compiler<R> {
     pub fn new(project_manager: Arc<ProjectManager<R>>, app: tauri::AppHandle<R>) -> Self {
-        let (tx, mut rx) = watch::channel::<Option<CompileRequest>>(None);
-
-        let handle = tokio::spawn(async move {
-            let mut current_cancel_token: Option<Arc<AtomicBool>> = None;
-            let mut _current_job: Option<JoinHandle<()>> = None;
-
-            while rx.changed().await.is_ok() {
-                if let Some(token) = &current_cancel_token {
-                    token.store(true, Ordering::Relaxed);
-                }
-
-                let request = {
-                    let borrow = rx.borrow_and_update();
-                    borrow.clone()
-                };
-
-                if let Some(req) = request {
-                    let token = Arc::new(AtomicBool::new(false));
-                    current_cancel_token = Some(token.clone());
-                    
-                    let pm = project_manager.clone();
-                    let window = app.get_webview_window(&req.window_label);
-
-                    if let Some(window) = window {
-                        let inner_token = token.clone();
-                        _current_job = Some(tokio::task::spawn_blocking(move || {
-                             compile_job(pm, window, req, inner_token);
-                        }));
-                    }
-                }
-            }
-        });
-
         Self {
-            tx,
-            _handle: handle,
-            _marker: std::marker::PhantomData,
+            project_manager,
+            app,
+            windows: Mutex::new(HashMap::new()),
         }
     }
 
     pub fn update(&self, req: CompileRequest) {
-        let _ = self.tx.send(Some(req));
+        let mut windows = self.windows.lock().unwrap();
+        let tx = windows.entry(req.window_label.clone()).or_insert_with(|| {
+            let (tx, rx) = watch::channel(None);
+            tokio::spawn(compile_loop(self.project_manager.clone(), self.app.clone(), rx));
+            tx
+        });
+        let _ = tx.send(Some(req));
+    }
+
+    /// Stops compiling for a closed window.
+    pub fn remove_window(&self, label: &str) {
+        // Dropping the sender ends the window's compile task.
+        self.windows.lock().unwrap().remove(label);
     }
 }
 
-fn compile_job<R: Runtime>(
+/// Compiles the requests of one window until its sender is dropped. A new request cancels
+/// the compile of the previous one.
+async fn compile_loop<R: Runtime>(
     project_manager: Arc<ProjectManager<R>>,
-    window: tauri::WebviewWindow<R>,
-    req: CompileRequest,
-    token: Arc<AtomicBool>,
+    app: tauri::AppHandle<R>,
+    mut rx: watch::Receiver<Option<CompileRequest>>,
 ) {
-    if token.load(Ordering::Relaxed) { return; }
-
-    let project_opt = project_manager.get_project(&window);
-    if project_opt.is_none() {
-        return;
-    }
-    let project = project_opt.unwrap();
-
-    if token.load(Ordering::Relaxed) { return; }
-    let mut world_guard = project.world.lock().unwrap_or_else(|e| {
-        log::warn!("Project world mutex poisoned, recovering: {}", e);
-        e.into_inner()
-    });
-
-    if token.load(Ordering::Relaxed) { return; }
+    let mut current_cancel_token: Option<Arc<AtomicBool>> = None;
 
-    let update_res = world_guard.slot_update(&req.path, Some(req.content.clone()));
-    if let Err(e) = update_res {
-        error!("Failed to update slot: {:?}", e);
-        return;
-    }
+    while rx.changed().await.is_ok() {
+        // Edits arriving while waiting replace the request, so only the last compiles.
+        let debounce = app_settings().preview.compile_debounce_ms;
+        if debounce > 0 {
+            tokio::time::sleep(Duration::from_millis(debounce)).await;
+        }
+        let Some(req) = rx.borrow_and_update().clone() else {
+            continue;
+        };
+        let Some(window) = app.get_webview_window(&req.window_label) else {
+            continue;
+        };
+
+        if !affects_target(&project_manager, &app, &req) {
+            debug!(
+                "{:?} is not a dependency of the current target, skipping compile",
+                req.path
+            );
+            let pm = project_manager.clone();
+            tokio::task::spawn_blocking(move || update_slot(pm, window, req));
+            continue;
+        }
 
-    let main_to_set = req.main_path.as_ref().unwrap_or(&req.path);
-    world_guard.set_main_path(typst::syntax::VirtualPath::new(main_to_set));
-    
-    if !world_guard.is_main_set() {
-        let config = project.config.read().unwrap();
-        if config.apply_main(&project, &mut world_guard).is_err() {
-            return;
+        if let Some(token) = &current_cancel_token {
+            token.store(true, Ordering::Relaxed);
         }
+        let token = Arc::new(AtomicBool::new(false));
+        current_cancel_token = Some(token.clone());
+        let pm = project_manager.clone();
+        tokio::task::spawn_blocking(move || compile_job(pm, window, req, token));
     }
+    if let Some(token) = current_cancel_token {
+        token.store(true, Ordering::Relaxed);
+    }
+}
 
-    let cancellable_world = CancellableWorld::new(&world_guard, token.clone());
-
-    let result = typst::compile::<typst::layout::PagedDocument>(&cancellable_world);
-    
-    drop(world_guard);
+/// Whether the edited file is a dependency of the requested target, as recorded by
+/// the target's last compile.
+fn affects_target<R: Runtime>(
+    project_manager: &ProjectManager<R>,
+    app: &tauri::AppHandle<R>,
+    req: &CompileRequest,
+) -> bool {
+    let Some(window) = app.get_webview_window(&req.window_label) else {
+        return true;
+    };
+    let Some(project) = project_manager.get_project(&window) else {
+        return true;
+    };
+    let (target, file) = compile_target(req);
+    project.dependencies.affects(target, file)
+}
 
-    let old_id = project.current_compile_request_id.fetch_max(req.request_id, Ordering::SeqCst);
-    if req.request_id < old_id {
+/// Applies an edit to the world without compiling, so that later compiles see it.
+fn update_slot<R: Runtime>(
+    project_manager: Arc<ProjectManager<R>>,
+    window: tauri::WebviewWindow<R>,
+    req: CompileRequest,
+) {
+    let Some(project) = project_manager.get_project(&window) else {
         return;
+    };
+    let world = project.world.lock().unwrap_or_else(|e| e.into_inner());
+    if let Err(e) = world.slot_update(&req.path, Some(req.content)) {
+        error!("Failed to update slot: {:?}", e);
     }
+}
 
-    match result.output {
-        Ok(doc) => {
-             let pages = doc.pages.len();
-             let mut hasher = SipHasher::new();
-             for page in &doc.pages {
-                 page.frame.hash(&mut hasher);
-             }
-             let hash = hex::encode(hasher.finish128().as_bytes());
-
-             let first_page = &doc.pages[0];
-             let width = first_page.frame.width();
-             let height = first_page.frame.height();
-             
-             let max_prerender = std::cmp::min(pages, 10);
-             let page_svgs: Vec<String> = (0..max_prerender)
-                 .map(|i| {
-                     let page = &doc.pages[i];
-                     let mut renderer = project.renderer.lock().unwrap_or_else(|e| e.into_inner());
-                     let (svg, _) = renderer.render_page(i, page);
-                     svg
-                 })
-                 .collect();
+fn compile_job<R: Runtime>(
+    project_manager: Arc<ProjectManager<R>>,
+    window: tauri::WebviewWindow<R>,
+    req: CompileRequest,
+    token: Arc<AtomicBool>,
+) {
+    let started = Instant::now();
+    if token.load(Ordering::Relaxed) { return; }
 
-             project.cache.write().unwrap().document = Some(doc);
-            
-             emit_event(&window, BackendEvent::Compile(TypstCompileEvent {
-                 document: Some(TypstDocument {
-                     pages,
-                     hash,
-                     width: width.to_pt(),
-                     height: height.to_pt(),
-                     page_svgs,
-                 }),
-                 diagnostics: None,
-             }));
+    let Some(project) = project_manager.get_project(&window) else {
+        return;
+    };
+
+    let timeout = app_settings()
+        .preview
+        .compile_timeout_secs
+        .map(Duration::from_secs);
+    match compile_project(&project, &req, token, timeout, &window) {
+        CompileOutcome::Compiled => {
+            update_menu_context(&window, |context| context.compiled = true);
+            project.render_queue.reschedule(&project, Arc::new(window.clone()));
+            project.auto_exporter.compiled(&window, &project);
+            report_long_operation(&window, "compile", started, true);
         }
-        Err(diagnostics) => {
-            let world_guard = project.world.lock().unwrap_or_else(|e| {
-                log::warn!("Project world mutex poisoned, recovering: {}", e);
-                e.into_inner()
-            });
-            
-            let vpath = typst::syntax::VirtualPath::new(&req.path);
-            let id = typst::syntax::FileId::new(None, vpath);
-            
-            let source_res = world_guard.source(id);
-            let mapped_diagnostics = if let Ok(source) = source_res {
-                diagnostics.iter()
-                    .filter(|d| d.span.id() == Some(id))
-                    .filter_map(|d| {
-                         let span = source.find(d.span)?;
-                         let range = span.range();
-                         let start = req.content[..range.start].chars().count();
-                         let size = req.content[range.start..range.end].chars().count();
-                         
-                         Some(TypstSourceDiagnostic {
-                             range: start..start + size,
-                             severity: match d.severity {
-                                 Severity::Error => TypstDiagnosticSeverity::Error,
-                                 Severity::Warning => TypstDiagnosticSeverity::Warning,
-                             },
-                             message: d.message.to_string(),
-                             hints: d.hints.iter().map(|h| h.to_string()).collect(),
-                         })
-                    })
-                    .collect()
-            } else {
-                vec![]
-            };
-
-            emit_event(&window, BackendEvent::Compile(TypstCompileEvent {
-                document: None,
-                diagnostics: Some(mapped_diagnostics),
-            }));
+        CompileOutcome::Failed | CompileOutcome::TimedOut => {
+            report_long_operation(&window, "compile", started, false)
         }
+        CompileOutcome::Skipped | CompileOutcome::Cycle => {}
     }
 }
@@ -0,0 +1,47 @@
+use crate::ipc::TypstCompileEvent;
+use log::error;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Mutex;
+
+/// Where the compile pipeline reports to, independent of the transport: a window of the
+/// app, or later a server connection or WASM host without any Tauri types.
+pub trait EventSink: Send + Sync {
+    /// Delivers `payload` as `event` to the client the compile is for.
+    fn send(&self, event: &str, payload: Value);
+}
+
+/// Serializes `payload` and sends it through `sink`.
+pub fn send_event<S: EventSink + ?Sized, T: Serialize>(sink: &S, event: &str, payload: T) {
+    match serde_json::to_value(payload) {
+        Ok(payload) => sink.send(event, payload),
+        Err(e) => error!("unable to serialize {} event: {}", event, e),
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "event", content = "payload")]
+pub enum BackendEvent {
+    #[serde(rename = "typst_compile")]
+    Compile(TypstCompileEvent),
+}
+
+pub fn emit_event<S: EventSink + ?Sized>(sink: &S, event: BackendEvent) {
+    match &event {
+        BackendEvent::Compile(payload) => send_event(sink, "typst_compile", payload),
+    };
+    // Also emit a generic "backend_event" for single-listener setups if needed
+    send_event(sink, "backend_event", event);
+}
+
+/// Keeps the events sent to it, eg. for a compile without a window.
+#[derive(Default)]
+pub struct RecordingSink {
+    pub events: Mutex<Vec<(String, Value)>>,
+}
+
+impl EventSink for RecordingSink {
+    fn send(&self, event: &str, payload: Value) {
+        self.events.lock().unwrap().push((event.to_string(), payload));
+    }
+}
@@ -0,0 +1,105 @@
+use crate::engine::{today, TypstEngine};
+use once_cell::sync::Lazy;
+use std::sync::Arc;
+use typst::diag::{FileError, FileResult};
+use typst::foundations::{Bytes, Datetime};
+use typst::layout::PagedDocument;
+use typst::syntax::{FileId, Source, VirtualPath};
+use typst::text::{Font, FontBook};
+use typst::utils::LazyHash;
+use typst::{Library, LibraryExt, World};
+
+/// Fonts for snippets compiled while no project is open, searched on first use.
+static DETACHED_ENGINE: Lazy<Arc<TypstEngine>> = Lazy::new(|| Arc::new(TypstEngine::new(None)));
+
+pub fn detached_engine() -> Arc<TypstEngine> {
+    DETACHED_ENGINE.clone()
+}
+
+/// A world whose main file is a snippet held in memory, for compiling code apart from
+/// the project's document.
+pub struct SnippetWorld<'a> {
+    engine: Arc<TypstEngine>,
+    library: LazyHash<Library>,
+    main: Source,
+    /// Resolves the files the snippet reads, such as the project's imports. Without it,
+    /// all file access is denied.
+    files: Option<&'a dyn World>,
+}
+
+impl<'a> SnippetWorld<'a> {
+    /// A sandboxed world that can't read any files.
+    pub fn new(engine: Arc<TypstEngine>, code: String) -> Self {
+        Self {
+            engine,
+            library: LazyHash::new(Library::default()),
+            main: Source::new(FileId::new_fake(VirtualPath::new("/snippet.typ")), code),
+            files: None,
+        }
+    }
+
+    /// Resolves files through `world`. The snippet is placed at `path`, so relative
+    /// imports resolve like they would in a file there.
+    pub fn with_files(mut self, world: &'a dyn World, path: VirtualPath) -> Self {
+        self.main = Source::new(FileId::new_fake(path), self.main.text().to_string());
+        self.files = Some(world);
+        self
+    }
+
+    pub fn with_library(mut self, library: Library) -> Self {
+        self.library = LazyHash::new(library);
+        self
+    }
+
+    /// Compiles the snippet. Errors are flattened into a single message.
+    pub fn compile(&self) -> Result<PagedDocument, String> {
+        typst::compile::<PagedDocument>(self)
+            .output
+            .map_err(|diagnostics| {
+                diagnostics
+                    .iter()
+                    .map(|d| d.message.to_string())
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            })
+    }
+}
+
+impl World for SnippetWorld<'_> {
+    fn library(&self) -> &LazyHash<Library> {
+        &self.library
+    }
+
+    fn book(&self) -> &LazyHash<FontBook> {
+        &self.engine.fontbook
+    }
+
+    fn main(&self) -> FileId {
+        self.main.id()
+    }
+
+    fn source(&self, id: FileId) -> FileResult<Source> {
+        if id == self.main.id() {
+            return Ok(self.main.clone());
+        }
+        match self.files {
+            Some(world) => world.source(id),
+            None => Err(FileError::AccessDenied),
+        }
+    }
+
+    fn file(&self, id: FileId) -> FileResult<Bytes> {
+        match self.files {
+            Some(world) => world.file(id),
+            None => Err(FileError::AccessDenied),
+        }
+    }
+
+    fn font(&self, id: usize) -> Option<Font> {
+        self.engine.font(id)
+    }
+
+    fn today(&self, offset: Option<i64>) -> Option<Datetime> {
+        today(offset)
+    }
+}
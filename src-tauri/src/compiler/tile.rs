@@ -0,0 +1,103 @@
+use crate::document::Rect;
+use typst::layout::{Abs, Frame, Page, Point, Size};
+
+/// Tiles are refused beyond this many pixels on a side, so a request can't allocate an
+/// image as large as the whole page at a high zoom.
+pub const MAX_TILE_SIDE: u32 = 4096;
+
+/// The part of `rect` on a page of `width` by `height` points and its size in pixels at
+/// `scale`. `None` if it misses the page or is larger than [`MAX_TILE_SIDE`].
+pub fn tile_bounds(rect: Rect, width: f64, height: f64, scale: f32) -> Option<(Rect, u32, u32)> {
+    let (x0, y0) = (rect.x.max(0.0), rect.y.max(0.0));
+    let (x1, y1) = (
+        (rect.x + rect.width).min(width),
+        (rect.y + rect.height).min(height),
+    );
+    if !(x1 > x0 && y1 > y0 && scale > 0.0) {
+        return None;
+    }
+    let pixels = |points: f64| (points * scale as f64).ceil() as u32;
+    let (w, h) = (pixels(x1 - x0), pixels(y1 - y0));
+    let rect = Rect {
+        x: x0,
+        y: y0,
+        width: x1 - x0,
+        height: y1 - y0,
+    };
+    (w <= MAX_TILE_SIDE && h <= MAX_TILE_SIDE).then_some((rect, w, h))
+}
+
+/// The largest scale at most `scale` at which a page of `width` by `height` points is
+/// no more than [`MAX_TILE_SIDE`] pixels on a side.
+pub fn capped_tile_scale(width: f64, height: f64, scale: f32) -> f32 {
+    // One pixel short of the limit, as tile sizes are rounded up.
+    let limit = (MAX_TILE_SIDE - 1) as f64 / width.max(height).max(1.0);
+    (scale as f64).min(limit) as f32
+}
+
+/// A page of only the part of `page` in `rect`, in points from its top left corner, so
+/// rendering it rasterizes nothing else.
+pub fn tile_page(page: &Page, rect: Rect) -> Page {
+    let mut frame = Frame::hard(Size::new(Abs::pt(rect.width), Abs::pt(rect.height)));
+    frame.push_frame(
+        Point::new(Abs::pt(-rect.x), Abs::pt(-rect.y)),
+        page.frame.clone(),
+    );
+    let mut tile = page.clone();
+    tile.frame = frame;
+    tile
+}
+
+/// Rasterizes only the part of `page` in `rect` at `scale` pixels per point, and encodes
+/// it as PNG.
+pub fn render_tile_png(page: &Page, rect: Rect, scale: f32) -> Result<Vec<u8>, String> {
+    typst_render::render(&tile_page(page, rect), scale)
+        .encode_png()
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_bounds() {
+        let rect = Rect {
+            x: 100.0,
+            y: -20.0,
+            width: 100.0,
+            height: 50.0,
+        };
+        let (clamped, w, h) = tile_bounds(rect, 150.0, 1000.0, 4.0).unwrap();
+        assert_eq!(
+            clamped,
+            Rect {
+                x: 100.0,
+                y: 0.0,
+                width: 50.0,
+                height: 30.0
+            }
+        );
+        assert_eq!((w, h), (200, 120));
+        // Off the page, or too large at this scale.
+        assert!(tile_bounds(Rect { x: 200.0, ..rect }, 150.0, 1000.0, 4.0).is_none());
+        assert!(tile_bounds(rect, 150.0, 1000.0, 100.0).is_none());
+    }
+
+    #[test]
+    fn test_capped_tile_scale() {
+        let (width, height) = (595.0, 842.0);
+        assert_eq!(capped_tile_scale(width, height, 2.0), 2.0);
+        let scale = capped_tile_scale(width, height, 16.0);
+        assert!(scale < 16.0);
+        let page = Rect {
+            x: 0.0,
+            y: 0.0,
+            width,
+            height,
+        };
+        let (_, w, h) = tile_bounds(page, width, height, scale).unwrap();
+        assert!(h >= MAX_TILE_SIDE - 1);
+        assert!(w < h);
+    }
+}
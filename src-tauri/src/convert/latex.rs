@@ -0,0 +1,1199 @@
+use std::collections::BTreeSet;
+
+/// Converts LaTeX, such as a snippet pasted from Overleaf, to Typst markup. Handles inline
+/// and display math, sectioning, text styles, citations and references, lists, `tabular`
+/// and `figure`. Unknown commands are dropped but their arguments are kept.
+pub fn latex_to_typst(latex: &str) -> String {
+    convert_latex(latex).typst
+}
+
+/// A LaTeX document converted to Typst.
+pub struct LatexConversion {
+    pub typst: String,
+    /// Commands and environments that were dropped or only partially converted, eg.
+    /// `\newcommand` or `tikzpicture`, and need manual attention.
+    pub unsupported: BTreeSet<String>,
+}
+
+/// Like [`latex_to_typst`], but also reports what the conversion couldn't handle.
+pub fn convert_latex(latex: &str) -> LatexConversion {
+    let mut converter = Converter::default();
+    let typst = tidy(&converter.text(&latex.replace("\r\n", "\n")));
+    LatexConversion {
+        typst,
+        unsupported: converter.unsupported,
+    }
+}
+
+#[derive(Default)]
+struct Converter {
+    unsupported: BTreeSet<String>,
+}
+
+struct Scanner<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(src: &'a str) -> Self {
+        Self { src, pos: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        let matches = self.rest().starts_with(s);
+        if matches {
+            self.pos += s.len();
+        }
+        matches
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.next();
+        }
+    }
+
+    /// Skips the rest of the line if it's blank, after block content that already ended
+    /// its line.
+    fn eat_line_end(&mut self) {
+        let save = self.pos;
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.next();
+        }
+        if !self.eat("\n") && self.pos < self.src.len() {
+            self.pos = save;
+        }
+    }
+
+    /// Skips a `%` comment. A comment on its own line takes its line break with it.
+    fn skip_comment(&mut self, whole_line: bool) {
+        while let Some(c) = self.peek() {
+            if c == '\n' {
+                if whole_line {
+                    self.next();
+                }
+                break;
+            }
+            self.next();
+        }
+    }
+
+    /// Reads a command name after a backslash: a run of letters, or one other character.
+    fn command(&mut self) -> &'a str {
+        let start = self.pos;
+        let letters = self.rest().bytes().take_while(u8::is_ascii_alphabetic).count();
+        if letters > 0 {
+            self.pos += letters;
+        } else {
+            self.next();
+        }
+        &self.src[start..self.pos]
+    }
+
+    /// Reads up to the `close` matching an already consumed `open`.
+    fn balanced(&mut self, open: char, close: char) -> &'a str {
+        let start = self.pos;
+        let mut depth = 0;
+        while let Some(c) = self.next() {
+            if c == '\\' {
+                self.next();
+            } else if c == open {
+                depth += 1;
+            } else if c == close {
+                if depth == 0 {
+                    return &self.src[start..self.pos - close.len_utf8()];
+                }
+                depth -= 1;
+            }
+        }
+        &self.src[start..]
+    }
+
+    fn delimited(&mut self, open: char, close: char) -> Option<&'a str> {
+        let save = self.pos;
+        self.skip_spaces();
+        if self.peek() == Some(open) {
+            self.next();
+            Some(self.balanced(open, close))
+        } else {
+            self.pos = save;
+            None
+        }
+    }
+
+    fn group(&mut self) -> Option<&'a str> {
+        self.delimited('{', '}')
+    }
+
+    fn optional(&mut self) -> Option<&'a str> {
+        self.delimited('[', ']')
+    }
+
+    /// Reads a command argument: a group, or else a single token.
+    fn argument(&mut self) -> &'a str {
+        if let Some(group) = self.group() {
+            return group;
+        }
+        self.skip_spaces();
+        let start = self.pos;
+        if self.next() == Some('\\') {
+            self.command();
+        }
+        &self.src[start..self.pos]
+    }
+
+    /// Reads the body of an environment whose `\begin{name}` was consumed, up to and
+    /// including the matching `\end{name}`.
+    fn environment(&mut self, name: &str) -> &'a str {
+        let begin = format!("\\begin{{{}}}", name);
+        let end = format!("\\end{{{}}}", name);
+        let start = self.pos;
+        let mut depth = 0;
+        while self.pos < self.src.len() {
+            if self.rest().starts_with(&end) {
+                if depth == 0 {
+                    let body = &self.src[start..self.pos];
+                    self.pos += end.len();
+                    return body;
+                }
+                depth -= 1;
+                self.pos += end.len();
+            } else if self.eat(&begin) {
+                depth += 1;
+            } else if self.next() == Some('\\') {
+                self.next();
+            }
+        }
+        &self.src[start..]
+    }
+
+    /// Reads up to an unescaped `delim`, consuming it.
+    fn until(&mut self, delim: &str) -> &'a str {
+        let start = self.pos;
+        while self.pos < self.src.len() {
+            if self.rest().starts_with(delim) {
+                let body = &self.src[start..self.pos];
+                self.pos += delim.len();
+                return body;
+            }
+            if self.next() == Some('\\') {
+                self.next();
+            }
+        }
+        &self.src[start..]
+    }
+}
+
+/// Splits at every `sep` outside of groups and environments.
+fn split_top_level<'a>(src: &'a str, sep: &str) -> Vec<&'a str> {
+    let word = sep.ends_with(|c: char| c.is_ascii_alphabetic());
+    let mut s = Scanner::new(src);
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut depth = 0;
+    while s.pos < src.len() {
+        let rest = s.rest();
+        let at_sep = rest.starts_with(sep)
+            && !(word && rest[sep.len()..].starts_with(|c: char| c.is_ascii_alphabetic()));
+        if depth == 0 && at_sep {
+            parts.push(&src[start..s.pos]);
+            s.pos += sep.len();
+            start = s.pos;
+        } else if s.eat("\\begin") {
+            depth += 1;
+        } else if s.eat("\\end") {
+            depth -= 1;
+        } else {
+            match s.next() {
+                Some('\\') => {
+                    s.next();
+                }
+                Some('{') => depth += 1,
+                Some('}') => depth -= 1,
+                Some('%') => s.skip_comment(false),
+                _ => {}
+            }
+        }
+    }
+    parts.push(&src[start..]);
+    parts
+}
+
+pub(super) fn string_literal(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Drops the backslash of escaped special characters, eg. in URLs.
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' && chars.peek().is_some_and(|c| "%&#$_{}".contains(*c)) {
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn at_line_start(out: &str) -> bool {
+    out.rsplit('\n').next().unwrap_or("").trim().is_empty()
+}
+
+/// Starts a new line for block content such as headings and display math.
+fn start_line(out: &mut String) {
+    out.truncate(out.trim_end_matches([' ', '\t']).len());
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+/// Attaches a label to the preceding element, keeping a trailing line break.
+fn push_label(out: &mut String, label: &str) {
+    let end = out.trim_end().len();
+    let newline = out[end..].contains('\n');
+    out.truncate(end);
+    out.push_str(&format!(" <{}>", label));
+    if newline {
+        out.push('\n');
+    }
+}
+
+/// Wraps `body` in `delim`, or calls `func` where the delimiters would touch a word.
+pub(super) fn push_styled(out: &mut String, func: &str, delim: char, body: &str, next: Option<char>) {
+    let body = body.trim();
+    if body.is_empty() {
+        return;
+    }
+    let touches_word = out.ends_with(char::is_alphanumeric) || next.is_some_and(char::is_alphanumeric);
+    if touches_word || body.contains(delim) {
+        out.push_str(&format!("#{}[{}]", func, body));
+    } else {
+        out.push_str(&format!("{d}{}{d}", body, d = delim));
+    }
+}
+
+fn push_linebreak(out: &mut String, next: Option<char>) {
+    if !out.is_empty() && !out.ends_with(char::is_whitespace) {
+        out.push(' ');
+    }
+    out.push('\\');
+    if !next.is_some_and(char::is_whitespace) {
+        out.push(' ');
+    }
+}
+
+fn push_display_math(out: &mut String, math: &str, labels: &[String]) {
+    start_line(out);
+    out.push_str(&format!("$ {} $", math));
+    if let Some(label) = labels.first() {
+        out.push_str(&format!(" <{}>", label));
+    }
+    out.push('\n');
+}
+
+/// Collapses runs of blank lines and trailing whitespace.
+fn tidy(text: &str) -> String {
+    let mut out = String::new();
+    let mut blank = false;
+    for line in text.lines() {
+        let line = line.trim_end();
+        if line.trim_start().is_empty() {
+            blank = !out.is_empty();
+            continue;
+        }
+        if !out.is_empty() {
+            out.push('\n');
+            if blank {
+                out.push('\n');
+            }
+        }
+        blank = false;
+        out.push_str(line);
+    }
+    out
+}
+
+/// Alignments from a `tabular` column spec such as `|l|c|p{3cm}|`.
+fn column_alignments(spec: &str) -> Vec<&'static str> {
+    let mut s = Scanner::new(spec);
+    let mut aligns = vec![];
+    while let Some(c) = s.next() {
+        match c {
+            'l' | 'X' => aligns.push("left"),
+            'c' => aligns.push("center"),
+            'r' => aligns.push("right"),
+            'p' | 'm' | 'b' => {
+                s.group();
+                aligns.push("left");
+            }
+            '@' | '!' | '>' | '<' => {
+                s.group();
+            }
+            '*' => {
+                let count = s.argument().trim().parse().unwrap_or(1);
+                let repeated = column_alignments(s.argument());
+                for _ in 0..count {
+                    aligns.extend(&repeated);
+                }
+            }
+            _ => {}
+        }
+    }
+    aligns
+}
+
+fn image_width(options: &str) -> Option<String> {
+    let value = options
+        .split(',')
+        .find_map(|option| option.trim().strip_prefix("width="))?
+        .trim();
+    let relative = ["\\textwidth", "\\linewidth", "\\columnwidth"]
+        .iter()
+        .find_map(|unit| value.strip_suffix(unit));
+    if let Some(fraction) = relative {
+        let fraction: f64 = match fraction.trim() {
+            "" => 1.0,
+            fraction => fraction.parse().ok()?,
+        };
+        return Some(format!("{}%", (fraction * 100.0).round()));
+    }
+    ["pt", "mm", "cm", "in", "em"]
+        .iter()
+        .find_map(|unit| value.strip_suffix(unit)?.trim().parse::<f64>().ok())
+        .map(|_| value.to_string())
+}
+
+fn image_call(path: &str, options: Option<&str>) -> String {
+    let mut call = format!("image({}", string_literal(path.trim()));
+    if let Some(width) = options.and_then(image_width) {
+        call.push_str(&format!(", width: {}", width));
+    }
+    call.push(')');
+    call
+}
+
+impl Converter {
+    fn text(&mut self, latex: &str) -> String {
+        let mut s = Scanner::new(latex);
+        let mut out = String::new();
+        while let Some(c) = s.next() {
+            match c {
+                '%' => s.skip_comment(at_line_start(&out)),
+                '\\' => self.text_command(&mut s, &mut out),
+                '$' => {
+                    let mut labels = vec![];
+                    if s.eat("$") {
+                        let body = math(s.until("$$"), &mut labels);
+                        push_display_math(&mut out, &body, &labels);
+                        s.eat_line_end();
+                    } else {
+                        let body = math(s.until("$"), &mut labels);
+                        out.push_str(&format!("${}$", body));
+                    }
+                }
+                '{' => out.push_str(&self.text(s.balanced('{', '}'))),
+                '}' => {}
+                '`' => out.push(if s.eat("`") { '"' } else { '\'' }),
+                '\'' if s.eat("'") => out.push('"'),
+                '*' | '_' | '#' | '@' | '<' | '>' => {
+                    out.push('\\');
+                    out.push(c);
+                }
+                '/' if matches!(s.peek(), Some('/' | '*')) => out.push_str("\\/"),
+                '[' if out.ends_with([')', ']']) => out.push_str("\\["),
+                '-' | '+' | '=' if at_line_start(&out) && s.peek() == Some(' ') => {
+                    out.push('\\');
+                    out.push(c);
+                }
+                c => out.push(c),
+            }
+        }
+        out
+    }
+
+    fn text_command(&mut self, s: &mut Scanner, out: &mut String) {
+        let name = s.command();
+        match name {
+            "part" | "chapter" | "section" | "subsection" | "subsubsection" | "paragraph"
+            | "subparagraph" => {
+                s.eat("*");
+                s.optional();
+                let level = match name {
+                    "part" | "chapter" | "section" => 1,
+                    "subsection" => 2,
+                    "subsubsection" => 3,
+                    "paragraph" => 4,
+                    _ => 5,
+                };
+                let title = self.text(s.argument());
+                start_line(out);
+                out.push_str(&format!("{} {}\n", "=".repeat(level), title.trim()));
+                s.eat_line_end();
+            }
+            "textbf" => {
+                let body = self.text(s.argument());
+                push_styled(out, "strong", '*', &body, s.peek());
+            }
+            "textit" | "emph" | "textsl" => {
+                let body = self.text(s.argument());
+                push_styled(out, "emph", '_', &body, s.peek());
+            }
+            "texttt" => out.push_str(&format!("`{}`", unescape(s.argument()))),
+            "verb" => {
+                s.eat("*");
+                if let Some(delim) = s.next() {
+                    out.push_str(&format!("`{}`", s.until(delim.encode_utf8(&mut [0; 4]))));
+                }
+            }
+            "underline" | "uline" | "textsc" | "footnote" | "textsuperscript" | "textsubscript" => {
+                let func = match name {
+                    "textsc" => "smallcaps",
+                    "footnote" => "footnote",
+                    "textsuperscript" => "super",
+                    "textsubscript" => "sub",
+                    _ => "underline",
+                };
+                out.push_str(&format!("#{}[{}]", func, self.text(s.argument()).trim()));
+            }
+            "textcolor" => {
+                let model = s.optional();
+                let color = s.argument().trim();
+                let fill = if model == Some("HTML") {
+                    format!("rgb(\"#{}\")", color)
+                } else {
+                    color.to_string()
+                };
+                out.push_str(&format!("#text(fill: {})[{}]", fill, self.text(s.argument()).trim()));
+            }
+            "cite" | "citep" | "citet" | "autocite" | "parencite" | "textcite" | "footcite" => {
+                s.eat("*");
+                s.optional();
+                s.optional();
+                let keys: Vec<String> = s
+                    .argument()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|key| !key.is_empty())
+                    .map(|key| format!("@{}", key))
+                    .collect();
+                out.push_str(&keys.join(" "));
+            }
+            "ref" | "eqref" | "autoref" | "cref" | "Cref" | "vref" => {
+                out.push_str(&format!("@{}", s.argument().trim()))
+            }
+            "label" => {
+                push_label(out, s.argument().trim());
+                if out.ends_with('\n') {
+                    s.eat_line_end();
+                }
+            }
+            "url" => out.push_str(&format!("#link({})", string_literal(&unescape(s.argument())))),
+            "href" => {
+                let url = unescape(s.argument());
+                let body = self.text(s.argument());
+                out.push_str(&format!("#link({})[{}]", string_literal(&url), body.trim()));
+            }
+            "includegraphics" => {
+                let options = s.optional();
+                out.push_str(&format!("#{}", image_call(s.argument(), options)));
+            }
+            "input" | "include" => {
+                let path = s.argument().trim();
+                let path = path.strip_suffix(".tex").unwrap_or(path);
+                out.push_str(&format!("#include {}", string_literal(&format!("{}.typ", path))));
+            }
+            "bibliography" => {
+                let files: Vec<String> = s
+                    .argument()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|file| !file.is_empty())
+                    .map(|file| match file.ends_with(".bib") {
+                        true => string_literal(file),
+                        false => string_literal(&format!("{}.bib", file)),
+                    })
+                    .collect();
+                start_line(out);
+                match files.as_slice() {
+                    [file] => out.push_str(&format!("#bibliography({})\n", file)),
+                    files => out.push_str(&format!("#bibliography(({}))\n", files.join(", "))),
+                }
+            }
+            "item" => {
+                s.optional();
+                start_line(out);
+                out.push_str("- ");
+            }
+            "\\" | "newline" | "linebreak" => {
+                if name == "\\" {
+                    s.eat("*");
+                    s.optional();
+                }
+                push_linebreak(out, s.peek());
+            }
+            "par" => out.push_str("\n\n"),
+            "newpage" | "clearpage" | "cleardoublepage" | "pagebreak" => {
+                start_line(out);
+                out.push_str("#pagebreak()\n");
+            }
+            "tableofcontents" => {
+                start_line(out);
+                out.push_str("#outline()\n");
+            }
+            "ldots" | "dots" | "textellipsis" => out.push_str("..."),
+            "LaTeX" | "TeX" => out.push_str(name),
+            "today" => out.push_str("#datetime.today().display()"),
+            "textbackslash" => out.push_str("\\\\"),
+            "textendash" => out.push_str("--"),
+            "textemdash" => out.push_str("---"),
+            "S" => out.push('§'),
+            "copyright" => out.push('©'),
+            "i" => out.push('ı'),
+            "%" | "&" | "{" | "}" => out.push_str(name),
+            "$" | "#" | "_" => {
+                out.push('\\');
+                out.push_str(name);
+            }
+            " " | "\n" | "\t" | "," | ";" | "quad" | "qquad" => out.push(' '),
+            "'" | "`" | "^" | "\"" | "~" | "=" | "." | "u" | "v" | "H" | "c" | "r" => {
+                let mark = match name {
+                    "'" => '\u{301}',
+                    "`" => '\u{300}',
+                    "^" => '\u{302}',
+                    "\"" => '\u{308}',
+                    "~" => '\u{303}',
+                    "=" => '\u{304}',
+                    "." => '\u{307}',
+                    "u" => '\u{306}',
+                    "v" => '\u{30C}',
+                    "H" => '\u{30B}',
+                    "c" => '\u{327}',
+                    _ => '\u{30A}',
+                };
+                out.push_str(&self.text(s.argument()));
+                out.push(mark);
+            }
+            "(" => out.push_str(&format!("${}$", math(s.until("\\)"), &mut vec![]))),
+            "[" => {
+                let mut labels = vec![];
+                let body = math(s.until("\\]"), &mut labels);
+                push_display_math(out, &body, &labels);
+                s.eat_line_end();
+            }
+            "begin" => {
+                let env = s.argument().trim();
+                self.text_environment(s, env, out);
+            }
+            "end" => {
+                s.argument();
+            }
+            "cmidrule" => {
+                s.delimited('(', ')');
+                s.group();
+            }
+            "newcommand" | "renewcommand" | "providecommand" | "DeclareMathOperator"
+            | "newenvironment" | "renewenvironment" => {
+                self.unsupported.insert(format!("\\{}", name));
+                s.eat("*");
+                while s.optional().is_some() || s.group().is_some() {}
+            }
+            "documentclass" | "usepackage" | "RequirePackage" | "bibliographystyle" | "nocite"
+            | "vspace" | "hspace" | "setlength" | "addtolength" | "setcounter" | "pagestyle"
+            | "thispagestyle" | "graphicspath" | "cline" => {
+                s.eat("*");
+                while s.optional().is_some() || s.group().is_some() {}
+            }
+            "-" | "/" | "@" | "noindent" | "centering" | "raggedright" | "raggedleft" | "maketitle"
+            | "hline" | "toprule" | "midrule" | "bottomrule" | "protect" | "small" | "footnotesize"
+            | "large" | "Large" | "normalsize" | "bfseries" | "itshape" | "normalfont" => {}
+            _ => {
+                if name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                    self.unsupported.insert(format!("\\{}", name));
+                }
+                s.eat("*");
+                while s.optional().is_some() {}
+                while let Some(arg) = s.group() {
+                    out.push_str(&self.text(arg));
+                }
+            }
+        }
+    }
+
+    fn text_environment(&mut self, s: &mut Scanner, name: &str, out: &mut String) {
+        let body = s.environment(name);
+        match name.trim_end_matches('*') {
+            "itemize" | "enumerate" | "description" => self.push_list(out, name, body),
+            "equation" | "align" | "gather" | "multline" | "flalign" | "eqnarray" | "displaymath" => {
+                let mut labels = vec![];
+                let math = math(body, &mut labels);
+                push_display_math(out, &math, &labels);
+            }
+            "tabular" | "tabularx" | "longtable" => {
+                start_line(out);
+                out.push_str(&format!("#{}\n", self.tabular(name, body)));
+            }
+            "figure" | "table" | "wrapfigure" => {
+                start_line(out);
+                out.push_str(&format!("{}\n", self.figure(body)));
+            }
+            "center" | "quote" | "quotation" => {
+                let func = match name {
+                    "center" => "align(center)",
+                    _ => "quote(block: true)",
+                };
+                start_line(out);
+                out.push_str(&format!("#{}[\n{}\n]\n", func, tidy(&self.text(body))));
+            }
+            "verbatim" | "lstlisting" | "minted" => {
+                let mut b = Scanner::new(body);
+                let lang = match name {
+                    "minted" => {
+                        b.optional();
+                        b.group()
+                    }
+                    "lstlisting" => b.optional().and_then(|options| {
+                        options.split(',').find_map(|o| o.trim().strip_prefix("language="))
+                    }),
+                    _ => None,
+                };
+                start_line(out);
+                out.push_str(&format!(
+                    "```{}\n{}\n```\n",
+                    lang.unwrap_or("").trim().to_lowercase(),
+                    b.rest().trim_matches('\n')
+                ));
+            }
+            base => {
+                if !matches!(base, "document" | "abstract" | "minipage") {
+                    self.unsupported.insert(format!("{} environment", name));
+                }
+                out.push_str(&self.text(body));
+            }
+        }
+        if out.ends_with('\n') {
+            s.eat_line_end();
+        }
+    }
+
+    fn push_list(&mut self, out: &mut String, name: &str, body: &str) {
+        let marker = if name == "enumerate" { "+" } else { "-" };
+        start_line(out);
+        for item in split_top_level(body, "\\item").into_iter().skip(1) {
+            let mut s = Scanner::new(item);
+            let term = s.optional().map(|term| tidy(&self.text(term)));
+            let content = tidy(&self.text(s.rest().trim_start()));
+            let item = match term {
+                Some(term) if name == "description" => format!("/ {}: {}", term, content),
+                Some(term) => format!("{} {} {}", marker, term, content),
+                None => format!("{} {}", marker, content),
+            };
+            for (i, line) in item.lines().enumerate() {
+                if i > 0 && !line.is_empty() {
+                    out.push_str("  ");
+                }
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+    }
+
+    /// Renders a `tabular` body as a `table(...)` call.
+    fn tabular(&mut self, name: &str, body: &str) -> String {
+        let mut s = Scanner::new(body);
+        if name == "tabularx" || name == "tabular*" {
+            s.group();
+        }
+        let aligns = column_alignments(s.group().unwrap_or(""));
+
+        let rows: Vec<Vec<(usize, String)>> = split_top_level(s.rest(), "\\\\")
+            .into_iter()
+            .map(|row| {
+                let mut row = Scanner::new(row);
+                row.optional();
+                split_top_level(row.rest(), "&")
+                    .into_iter()
+                    .map(|cell| self.table_cell(cell))
+                    .collect::<Vec<_>>()
+            })
+            .filter(|cells| cells.iter().any(|(_, cell)| !cell.is_empty()))
+            .collect();
+
+        let widest = rows
+            .iter()
+            .map(|cells| cells.iter().map(|(span, _)| span).sum())
+            .max()
+            .unwrap_or(0);
+        let columns = aligns.len().max(widest);
+
+        let mut out = format!("table(\n  columns: {},\n", columns);
+        if aligns.iter().any(|align| *align != "left") {
+            out.push_str(&format!("  align: ({}),\n", aligns.join(", ")));
+        }
+        for cells in rows {
+            let cells: Vec<String> = cells
+                .into_iter()
+                .map(|(span, cell)| match span {
+                    1 => format!("[{}]", cell),
+                    span => format!("table.cell(colspan: {})[{}]", span, cell),
+                })
+                .collect();
+            out.push_str(&format!("  {},\n", cells.join(", ")));
+        }
+        out.push(')');
+        out
+    }
+
+    fn table_cell(&mut self, cell: &str) -> (usize, String) {
+        let mut s = Scanner::new(cell.trim());
+        if s.eat("\\multicolumn") {
+            let span = s.argument().trim().parse().unwrap_or(1);
+            s.argument();
+            return (span, tidy(self.text(s.argument()).trim()));
+        }
+        (1, tidy(self.text(cell).trim()))
+    }
+
+    /// Renders a `figure` or `table` float as a `#figure(...)` with its caption and label.
+    fn figure(&mut self, body: &str) -> String {
+        let mut s = Scanner::new(body);
+        let mut content = vec![];
+        let mut caption = None;
+        let mut label = None;
+        while let Some(c) = s.next() {
+            match c {
+                '%' => s.skip_comment(true),
+                '\\' => match s.command() {
+                    "includegraphics" => {
+                        let options = s.optional();
+                        content.push(image_call(s.argument(), options));
+                    }
+                    "caption" => {
+                        s.optional();
+                        let mut text_src = s.argument().to_string();
+                        if let Some(i) = text_src.find("\\label") {
+                            let mut rest = Scanner::new(&text_src[i + "\\label".len()..]);
+                            label = Some(rest.argument().trim().to_string());
+                            text_src = format!("{}{}", &text_src[..i], rest.rest());
+                        }
+                        caption = Some(tidy(&self.text(&text_src)));
+                    }
+                    "label" => label = Some(s.argument().trim().to_string()),
+                    "begin" => {
+                        let name = s.argument().trim();
+                        if name.starts_with("tabular") {
+                            content.push(self.tabular(name, s.environment(name)));
+                        }
+                    }
+                    "end" => {
+                        s.argument();
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+
+        let body = match content.len() {
+            0 => "[]".to_string(),
+            1 => content.remove(0),
+            n => format!("grid(columns: {}, gutter: 1em, {})", n, content.join(", ")),
+        };
+        let mut out = format!("#figure(\n  {},\n", body.replace('\n', "\n  "));
+        if let Some(caption) = caption {
+            out.push_str(&format!("  caption: [{}],\n", caption));
+        }
+        out.push(')');
+        if let Some(label) = label {
+            out.push_str(&format!(" <{}>", label));
+        }
+        out
+    }
+}
+
+/// LaTeX commands that map to a differently named Typst symbol. Any other command name
+/// (Greek letters, `sin`, `sum`, ...) is used as is.
+const SYMBOLS: &[(&str, &str)] = &[
+    ("cdot", "dot.op"),
+    ("cdots", "dots.h.c"),
+    ("ldots", "dots.h"),
+    ("dots", "dots.h"),
+    ("vdots", "dots.v"),
+    ("ddots", "dots.down"),
+    ("pm", "plus.minus"),
+    ("mp", "minus.plus"),
+    ("leq", "<="),
+    ("le", "<="),
+    ("geq", ">="),
+    ("ge", ">="),
+    ("neq", "!="),
+    ("ne", "!="),
+    ("ll", "<<"),
+    ("gg", ">>"),
+    ("sim", "tilde.op"),
+    ("simeq", "tilde.eq"),
+    ("cong", "tilde.equiv"),
+    ("propto", "prop"),
+    ("to", "->"),
+    ("rightarrow", "->"),
+    ("leftarrow", "<-"),
+    ("Rightarrow", "=>"),
+    ("Leftarrow", "arrow.l.double"),
+    ("leftrightarrow", "<->"),
+    ("Leftrightarrow", "<=>"),
+    ("implies", "==>"),
+    ("iff", "<==>"),
+    ("mapsto", "|->"),
+    ("longrightarrow", "-->"),
+    ("uparrow", "arrow.t"),
+    ("downarrow", "arrow.b"),
+    ("infty", "infinity"),
+    ("int", "integral"),
+    ("iint", "integral.double"),
+    ("iiint", "integral.triple"),
+    ("oint", "integral.cont"),
+    ("prod", "product"),
+    ("coprod", "product.co"),
+    ("cup", "union"),
+    ("cap", "inter"),
+    ("bigcup", "union.big"),
+    ("bigcap", "inter.big"),
+    ("setminus", "without"),
+    ("subseteq", "subset.eq"),
+    ("supseteq", "supset.eq"),
+    ("notin", "in.not"),
+    ("ni", "in.rev"),
+    ("varnothing", "emptyset"),
+    ("nexists", "exists.not"),
+    ("neg", "not"),
+    ("lnot", "not"),
+    ("land", "and"),
+    ("wedge", "and"),
+    ("lor", "or"),
+    ("vee", "or"),
+    ("oplus", "plus.circle"),
+    ("otimes", "times.circle"),
+    ("circ", "compose"),
+    ("star", "star.op"),
+    ("ast", "ast.op"),
+    ("langle", "chevron.l"),
+    ("rangle", "chevron.r"),
+    ("lfloor", "floor.l"),
+    ("rfloor", "floor.r"),
+    ("lceil", "ceil.l"),
+    ("rceil", "ceil.r"),
+    ("lvert", "|"),
+    ("rvert", "|"),
+    ("vert", "|"),
+    ("mid", "|"),
+    ("Vert", "||"),
+    ("lbrace", "{"),
+    ("rbrace", "}"),
+    ("colon", ":"),
+    ("hbar", "planck.reduce"),
+    ("varepsilon", "epsilon"),
+    ("epsilon", "epsilon.alt"),
+    ("varphi", "phi"),
+    ("phi", "phi.alt"),
+    ("vartheta", "theta.alt"),
+    ("varrho", "rho.alt"),
+    ("varsigma", "sigma.alt"),
+    ("varpi", "pi.alt"),
+    ("varkappa", "kappa.alt"),
+];
+
+/// LaTeX commands that style or decorate their argument, with the Typst function.
+const STYLES: &[(&str, &str)] = &[
+    ("mathbf", "bold"),
+    ("boldsymbol", "bold"),
+    ("bm", "bold"),
+    ("mathit", "italic"),
+    ("mathrm", "upright"),
+    ("mathsf", "sans"),
+    ("mathtt", "mono"),
+    ("mathcal", "cal"),
+    ("mathscr", "scr"),
+    ("mathfrak", "frak"),
+    ("mathbb", "bb"),
+    ("hat", "hat"),
+    ("widehat", "hat"),
+    ("tilde", "tilde"),
+    ("widetilde", "tilde"),
+    ("bar", "overline"),
+    ("overline", "overline"),
+    ("underline", "underline"),
+    ("vec", "arrow"),
+    ("dot", "dot"),
+    ("ddot", "dot.double"),
+    ("overbrace", "overbrace"),
+    ("underbrace", "underbrace"),
+    ("cancel", "cancel"),
+];
+
+/// Appends a token, separating it from a preceding word so that eg. `a` `b` doesn't
+/// turn into the identifier `ab`.
+fn push_word(out: &mut String, word: &str) {
+    let separate = match (out.chars().last(), word.chars().next()) {
+        (Some(a), Some(b)) => {
+            (a.is_alphanumeric() && b.is_alphabetic()) || (a.is_alphabetic() && b.is_numeric())
+        }
+        _ => false,
+    };
+    if separate {
+        out.push(' ');
+    }
+    out.push_str(word);
+}
+
+/// Escapes top-level commas and semicolons so that math stays one function argument.
+fn call_arg(math: &str) -> String {
+    let mut out = String::with_capacity(math.len());
+    let mut depth = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for c in math.trim().chars() {
+        if escaped {
+            escaped = false;
+        } else {
+            match c {
+                '\\' => escaped = true,
+                '"' => quoted = !quoted,
+                '(' | '[' | '{' if !quoted => depth += 1,
+                ')' | ']' | '}' if !quoted => depth -= 1,
+                ',' | ';' if !quoted && depth == 0 => out.push('\\'),
+                _ => {}
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Whether a script can follow `^` or `_` without parentheses.
+fn is_atom(math: &str) -> bool {
+    math.chars().count() == 1 || (!math.is_empty() && math.chars().all(|c| c.is_alphanumeric() || c == '.'))
+}
+
+fn math(latex: &str, labels: &mut Vec<String>) -> String {
+    let mut s = Scanner::new(latex);
+    let mut out = String::new();
+    while let Some(c) = s.next() {
+        match c {
+            '%' => s.skip_comment(true),
+            '\\' => math_command(&mut s, &mut out, labels),
+            '^' | '_' => {
+                let script = math(s.argument(), labels);
+                out.truncate(out.trim_end().len());
+                out.push(c);
+                if is_atom(&script) {
+                    out.push_str(&script);
+                } else {
+                    out.push_str(&format!("({})", script));
+                }
+            }
+            '{' => {
+                let group = math(s.balanced('{', '}'), labels);
+                push_word(&mut out, &group);
+            }
+            '}' => {}
+            '/' => out.push_str("\\/"),
+            '"' => out.push_str("\\\""),
+            '~' => out.push(' '),
+            c if c.is_alphanumeric() => push_word(&mut out, c.encode_utf8(&mut [0; 4])),
+            c if c.is_whitespace() => out.push(' '),
+            c => out.push(c),
+        }
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn math_command(s: &mut Scanner, out: &mut String, labels: &mut Vec<String>) {
+    let name = s.command();
+    match name {
+        "frac" | "dfrac" | "tfrac" | "cfrac" | "binom" | "dbinom" | "tbinom" => {
+            let func = if name.ends_with("frac") { "frac" } else { "binom" };
+            let upper = math(s.argument(), labels);
+            let lower = math(s.argument(), labels);
+            push_word(out, &format!("{}({}, {})", func, call_arg(&upper), call_arg(&lower)));
+        }
+        "sqrt" => {
+            let index = s.optional().map(|index| math(index, labels));
+            let radicand = call_arg(&math(s.argument(), labels));
+            match index {
+                Some(index) => push_word(out, &format!("root({}, {})", call_arg(&index), radicand)),
+                None => push_word(out, &format!("sqrt({})", radicand)),
+            }
+        }
+        "text" | "textrm" | "textnormal" | "textit" | "textbf" | "mbox" => {
+            push_word(out, &string_literal(&unescape(s.argument())))
+        }
+        "operatorname" => {
+            s.eat("*");
+            push_word(out, &format!("op({})", string_literal(s.argument().trim())));
+        }
+        "left" | "right" | "middle" | "big" | "Big" | "bigg" | "Bigg" | "bigl" | "bigr"
+        | "Bigl" | "Bigr" | "biggl" | "biggr" | "Biggl" | "Biggr" => {
+            s.skip_spaces();
+            s.eat(".");
+        }
+        "label" => labels.push(s.argument().trim().to_string()),
+        "nonumber" | "notag" | "displaystyle" | "textstyle" | "scriptstyle" | "limits"
+        | "nolimits" | "!" => {}
+        "," | ":" | ">" | ";" | "quad" | "qquad" => {
+            let space = match name {
+                "," => "thin",
+                ":" | ">" => "med",
+                ";" => "thick",
+                "quad" => "quad",
+                _ => "wide",
+            };
+            push_word(out, space);
+            out.push(' ');
+        }
+        " " => out.push(' '),
+        "\\" => out.push_str(" \\ "),
+        "{" | "}" | "%" => out.push_str(name),
+        "|" => out.push_str("||"),
+        "&" | "#" | "_" | "$" => {
+            out.push('\\');
+            out.push_str(name);
+        }
+        "begin" => {
+            let env = s.argument().trim();
+            let body = s.environment(env);
+            push_word(out, &math_environment(env, body, labels));
+        }
+        "end" => {
+            s.argument();
+        }
+        _ => {
+            if let Some(&(_, func)) = STYLES.iter().find(|(latex, _)| *latex == name) {
+                let arg = s.argument().trim();
+                let styled = if func == "bb" && arg.len() == 1 && arg.chars().all(|c| c.is_ascii_uppercase()) {
+                    arg.repeat(2)
+                } else if arg.len() > 1 && arg.chars().all(|c| c.is_ascii_alphabetic()) {
+                    format!("{}({})", func, string_literal(arg))
+                } else {
+                    format!("{}({})", func, call_arg(&math(arg, labels)))
+                };
+                push_word(out, &styled);
+            } else if let Some(&(_, symbol)) = SYMBOLS.iter().find(|(latex, _)| *latex == name) {
+                push_word(out, symbol);
+            } else {
+                push_word(out, name);
+            }
+        }
+    }
+}
+
+fn math_environment(name: &str, body: &str, labels: &mut Vec<String>) -> String {
+    let rows = |body: &str, labels: &mut Vec<String>| -> Vec<Vec<String>> {
+        split_top_level(body, "\\\\")
+            .into_iter()
+            .filter(|row| !row.trim().is_empty())
+            .map(|row| {
+                split_top_level(row, "&")
+                    .into_iter()
+                    .map(|cell| call_arg(&math(cell, labels)))
+                    .collect()
+            })
+            .collect()
+    };
+    let matrix = |delim: &str, body: &str, labels: &mut Vec<String>| {
+        let rows: Vec<String> = rows(body, labels).into_iter().map(|cells| cells.join(", ")).collect();
+        format!("mat(delim: {}, {})", delim, rows.join("; "))
+    };
+
+    match name.trim_end_matches('*') {
+        "pmatrix" => matrix("\"(\"", body, labels),
+        "bmatrix" => matrix("\"[\"", body, labels),
+        "Bmatrix" => matrix("\"{\"", body, labels),
+        "vmatrix" => matrix("\"|\"", body, labels),
+        "Vmatrix" => matrix("\"||\"", body, labels),
+        "matrix" | "smallmatrix" => matrix("#none", body, labels),
+        "array" => {
+            let mut s = Scanner::new(body);
+            s.group();
+            matrix("#none", s.rest(), labels)
+        }
+        "cases" | "dcases" => {
+            let rows: Vec<String> = split_top_level(body, "\\\\")
+                .into_iter()
+                .filter(|row| !row.trim().is_empty())
+                .map(|row| call_arg(&math(row, labels)))
+                .collect();
+            format!("cases({})", rows.join(", "))
+        }
+        _ => math(body, labels),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::latex_to_typst;
+
+    #[test]
+    fn test_latex_math() {
+        assert_eq!(
+            latex_to_typst(r"$\frac{a+b}{2} \leq \sqrt{x^{2n}}$ and $\mathbb{R}^n$"),
+            "$frac(a+b, 2) <= sqrt(x^(2 n))$ and $RR^n$"
+        );
+        assert_eq!(
+            latex_to_typst(
+                "\\begin{equation}\\label{eq:m}\n  A = \\begin{pmatrix} 1 & 0 \\\\ 0 & 1 \\end{pmatrix}\n\\end{equation}"
+            ),
+            "$ A = mat(delim: \"(\", 1, 0; 0, 1) $ <eq:m>"
+        );
+    }
+
+    #[test]
+    fn test_latex_structure() {
+        let latex = r"\section{Results}\label{sec:res}
+As shown in \cite{knuth84,lamport94}, \textbf{bold} and \emph{this}.
+\begin{itemize}
+  \item First
+  \item Second
+\end{itemize}
+\begin{figure}[h]
+  \centering
+  \includegraphics[width=0.5\textwidth]{plot.png}
+  \caption{A plot.}\label{fig:plot}
+\end{figure}
+\begin{tabular}{l|c}
+  a & b \\ \hline
+  1 & 2 \\
+\end{tabular}";
+        assert_eq!(
+            latex_to_typst(latex),
+            "= Results <sec:res>
+As shown in @knuth84 @lamport94, *bold* and _this_.
+- First
+- Second
+#figure(
+  image(\"plot.png\", width: 50%),
+  caption: [A plot.],
+) <fig:plot>
+#table(
+  columns: 2,
+  align: (left, center),
+  [a], [b],
+  [1], [2],
+)"
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use crate::convert::convert_latex;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Files imported as they are: figures typst can load, bibliographies and data.
+const COPIED: [&str; 11] = [
+    "pdf", "png", "jpg", "jpeg", "gif", "svg", "webp", "bib", "csv", "json", "yaml",
+];
+
+/// Build output that is never imported.
+const IGNORED: [&str; 12] = [
+    "aux", "log", "out", "toc", "lof", "lot", "bbl", "blg", "fls", "fdb_latexmk", "synctex", "gz",
+];
+
+/// Extensions tried, in order, for `\includegraphics` paths without one.
+const IMAGE_EXTENSIONS: [&str; 7] = ["pdf", "png", "jpg", "jpeg", "svg", "gif", "webp"];
+
+#[derive(Serialize, Debug)]
+pub struct ConvertedFile {
+    /// Relative to the imported directory.
+    pub source: PathBuf,
+    /// Relative to the project root.
+    pub output: PathBuf,
+    /// Commands and environments that need manual attention.
+    pub unsupported: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct LatexImportReport {
+    /// The converted file that had the `\documentclass`.
+    pub main: Option<PathBuf>,
+    pub converted: Vec<ConvertedFile>,
+    pub copied: Vec<PathBuf>,
+    /// Files that weren't imported, eg. EPS figures or custom classes and styles.
+    pub skipped: Vec<PathBuf>,
+    /// Files that already existed in the project and were left untouched.
+    pub conflicts: Vec<PathBuf>,
+}
+
+static PATH_CALL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(image\(|#include |bibliography\()"([^"]*)""#).unwrap());
+
+/// Roots the paths of converted `#include`, `image` and `bibliography` calls at `prefix`.
+/// LaTeX resolves them against the main file, typst against the file containing them.
+fn root_paths(typst: &str, source: &Path, prefix: &str) -> String {
+    PATH_CALL
+        .replace_all(typst, |caps: &Captures| {
+            let path = &caps[2];
+            if path.starts_with('/') || path.contains("://") {
+                return caps[0].to_string();
+            }
+            let path = path.trim_start_matches("./");
+            let path = match &caps[1] {
+                "image(" if Path::new(path).extension().is_none() => IMAGE_EXTENSIONS
+                    .iter()
+                    .map(|extension| format!("{}.{}", path, extension))
+                    .find(|candidate| source.join(candidate).is_file())
+                    .unwrap_or_else(|| path.to_string()),
+                _ => path.to_string(),
+            };
+            format!("{}\"{}/{}\"", &caps[1], prefix, path)
+        })
+        .into_owned()
+}
+
+fn project_relative(root: &Path, path: &Path) -> io::Result<PathBuf> {
+    path.strip_prefix(root)
+        .map(Path::to_path_buf)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "path outside of the project"))
+}
+
+/// Imports the LaTeX tree at `source` into `target`, a directory of the project at `root`.
+/// `.tex` files are converted to `.typ`, figures, bibliographies and data are copied, and
+/// existing files are never overwritten.
+pub fn import_latex_tree(source: &Path, target: &Path, root: &Path) -> io::Result<LatexImportReport> {
+    let source = &fs::canonicalize(source)?;
+    if target.starts_with(source) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "cannot import a directory into itself",
+        ));
+    }
+    let prefix = project_relative(root, target)?
+        .to_string_lossy()
+        .replace('\\', "/");
+    let prefix = if prefix.is_empty() {
+        prefix
+    } else {
+        format!("/{}", prefix)
+    };
+
+    let mut report = LatexImportReport::default();
+    let entries = WalkDir::new(source)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'));
+    for entry in entries {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Ok(relative) = entry.path().strip_prefix(source) else {
+            continue;
+        };
+        let extension = relative
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+
+        let output = match extension.as_str() {
+            "tex" => target.join(relative).with_extension("typ"),
+            e if COPIED.contains(&e) => target.join(relative),
+            e if IGNORED.contains(&e) => continue,
+            _ => {
+                report.skipped.push(relative.to_path_buf());
+                continue;
+            }
+        };
+        let project_path = project_relative(root, &output)?;
+        if output.exists() {
+            report.conflicts.push(project_path);
+            continue;
+        }
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        if extension == "tex" {
+            let latex = String::from_utf8_lossy(&fs::read(entry.path())?).into_owned();
+            let conversion = convert_latex(&latex);
+            fs::write(&output, root_paths(&conversion.typst, source, &prefix) + "\n")?;
+            if report.main.is_none() && latex.contains("\\documentclass") {
+                report.main = Some(project_path.clone());
+            }
+            report.converted.push(ConvertedFile {
+                source: relative.to_path_buf(),
+                output: project_path,
+                unsupported: conversion.unsupported.into_iter().collect(),
+            });
+        } else {
+            fs::copy(entry.path(), &output)?;
+            report.copied.push(project_path);
+        }
+    }
+    Ok(report)
+}
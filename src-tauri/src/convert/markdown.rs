@@ -0,0 +1,401 @@
+use super::latex::{push_styled, string_literal};
+
+/// Converts Markdown to Typst markup: headings, emphasis, lists, links, images, code
+/// fences, block quotes and pipe tables. Inline HTML other than `<br>` is kept as text.
+pub fn markdown_to_typst(markdown: &str) -> String {
+    let markdown = markdown.replace("\r\n", "\n");
+    let lines: Vec<&str> = markdown.lines().collect();
+    let mut out: Vec<String> = vec![];
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim_start();
+        let indent = &line[..line.len() - trimmed.len()];
+        i += 1;
+
+        if let Some(fence) = code_fence(trimmed) {
+            let ticks = "`".repeat(fence.len());
+            out.push(format!("{}{}{}", indent, ticks, trimmed[fence.len()..].trim()));
+            while i < lines.len() && !lines[i].trim_start().starts_with(fence) {
+                out.push(lines[i].to_string());
+                i += 1;
+            }
+            out.push(format!("{}{}", indent, ticks));
+            i += 1;
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            out.push(String::new());
+            continue;
+        }
+
+        if let Some((level, title)) = atx_heading(trimmed) {
+            out.push(format!("{} {}", "=".repeat(level), inline(title)));
+            continue;
+        }
+
+        let underline = lines.get(i).map(|l| l.trim()).unwrap_or("");
+        if indent.is_empty() && !underline.is_empty() && list_item(trimmed).is_none() {
+            let level = match underline {
+                u if u.chars().all(|c| c == '=') => Some(1),
+                u if u.chars().all(|c| c == '-') => Some(2),
+                _ => None,
+            };
+            if let Some(level) = level {
+                out.push(format!("{} {}", "=".repeat(level), inline(trimmed)));
+                i += 1;
+                continue;
+            }
+        }
+
+        if is_rule(trimmed) {
+            out.push("#line(length: 100%)".to_string());
+            continue;
+        }
+
+        if trimmed.starts_with('>') {
+            let mut quoted = vec![quote_line(trimmed)];
+            while i < lines.len() && lines[i].trim_start().starts_with('>') {
+                quoted.push(quote_line(lines[i].trim_start()));
+                i += 1;
+            }
+            out.push(format!("#quote(block: true)[\n{}\n]", markdown_to_typst(&quoted.join("\n"))));
+            continue;
+        }
+
+        if let Some(aligns) = lines.get(i).and_then(|l| table_separator(l)) {
+            if trimmed.contains('|') {
+                let mut rows = vec![table_cells(trimmed)];
+                i += 1;
+                while i < lines.len() && lines[i].contains('|') && !lines[i].trim().is_empty() {
+                    rows.push(table_cells(lines[i]));
+                    i += 1;
+                }
+                out.push(table(&rows, &aligns));
+                continue;
+            }
+        }
+
+        if let Some((marker, item)) = list_item(trimmed) {
+            let item = match item {
+                item if item.starts_with("[ ] ") => format!("☐ {}", &item[4..]),
+                item if item.starts_with("[x] ") || item.starts_with("[X] ") => {
+                    format!("☒ {}", &item[4..])
+                }
+                item => item.to_string(),
+            };
+            out.push(format!("{}{} {}", indent, marker, inline(&item)));
+            continue;
+        }
+
+        let mut paragraph = inline(trimmed.trim_end());
+        if trimmed.starts_with("= ") || trimmed.starts_with("/ ") {
+            paragraph.insert(0, '\\');
+        }
+        if line.ends_with("  ") || line.ends_with('\\') {
+            paragraph = paragraph.trim_end_matches('\\').trim_end().to_string();
+            paragraph.push_str(" \\");
+        }
+        out.push(format!("{}{}", indent, paragraph));
+    }
+
+    out.join("\n").trim().to_string()
+}
+
+/// The opening fence of a fenced code block, eg. "```" or "~~~~".
+fn code_fence(line: &str) -> Option<&str> {
+    let c = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = line.chars().take_while(|d| *d == c).count();
+    (len >= 3).then(|| &line[..len])
+}
+
+fn atx_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let title = line[level..].strip_prefix(' ').or_else(|| (line.len() == level).then_some(""))?;
+    let title = title.trim_end().trim_end_matches('#').trim_end();
+    Some((level, title))
+}
+
+fn is_rule(line: &str) -> bool {
+    let chars: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    chars.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|c| chars.chars().all(|d| d == *c))
+}
+
+fn quote_line(line: &str) -> &str {
+    let line = &line[1..];
+    line.strip_prefix(' ').unwrap_or(line)
+}
+
+/// Splits a list item into its Typst marker and text.
+fn list_item(line: &str) -> Option<(&'static str, &str)> {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(item) = line.strip_prefix(bullet) {
+            return Some(("-", item));
+        }
+    }
+    let digits = line.bytes().take_while(u8::is_ascii_digit).count();
+    if (1..10).contains(&digits) {
+        let rest = &line[digits..];
+        if let Some(item) = rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") ")) {
+            return Some(("+", item));
+        }
+    }
+    None
+}
+
+/// Splits a pipe table row into cells. Pipes in code spans or escaped as `\|` don't split.
+fn table_cells(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line.strip_suffix('|').unwrap_or(line);
+
+    let mut cells = vec![];
+    let mut cell = String::new();
+    let mut code = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cell.push('|');
+                chars.next();
+            }
+            '`' => {
+                code = !code;
+                cell.push(c);
+            }
+            '|' if !code => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            c => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+/// Parses a table's delimiter row such as `|:---|:---:|---:|` into column alignments.
+fn table_separator(line: &str) -> Option<Vec<&'static str>> {
+    let cells = table_cells(line);
+    cells
+        .iter()
+        .map(|cell| {
+            let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+            if dashes.is_empty() || !dashes.chars().all(|c| c == '-') {
+                return None;
+            }
+            Some(match (cell.starts_with(':'), cell.ends_with(':')) {
+                (true, true) => "center",
+                (false, true) => "right",
+                (true, false) => "left",
+                (false, false) => "auto",
+            })
+        })
+        .collect()
+}
+
+fn table(rows: &[Vec<String>], aligns: &[&str]) -> String {
+    let columns = aligns.len().max(rows.iter().map(Vec::len).max().unwrap_or(0));
+    let row_markup = |row: &Vec<String>| {
+        (0..columns)
+            .map(|i| format!("[{}]", inline(row.get(i).map_or("", String::as_str))))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let mut out = format!("#table(\n  columns: {},\n", columns);
+    if aligns.iter().any(|align| *align != "auto") {
+        out.push_str(&format!("  align: ({}),\n", aligns.join(", ")));
+    }
+    let mut rows = rows.iter();
+    if let Some(header) = rows.next() {
+        out.push_str(&format!("  table.header({}),\n", row_markup(header)));
+    }
+    for row in rows {
+        out.push_str(&format!("  {},\n", row_markup(row)));
+    }
+    out.push(')');
+    out
+}
+
+/// Parses `[label](url "title")` at the start of `text`, returning the label, the URL
+/// and the length of the link.
+fn link(text: &str) -> Option<(&str, &str, usize)> {
+    let mut depth = 0;
+    let mut label_end = None;
+    for (i, c) in text.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    label_end = Some(i);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let label_end = label_end?;
+    let target = text[label_end + 1..].strip_prefix('(')?;
+    let target_end = target.find(')')?;
+    let url = target[..target_end].split_whitespace().next().unwrap_or("");
+    let url = url.trim_start_matches('<').trim_end_matches('>');
+    Some((&text[1..label_end], url, label_end + 2 + target_end + 1))
+}
+
+/// Finds the closing `delim` of an emphasis run opened at the start of `text`.
+fn closing(text: &str, delim: &str) -> Option<usize> {
+    let c = delim.chars().next()?;
+    let mut from = delim.len();
+    while let Some(offset) = text[from..].find(delim) {
+        let at = from + offset;
+        let before = text[..at].chars().last();
+        let after = text[at + delim.len()..].chars().next();
+        let closes = at > delim.len()
+            && before.is_some_and(|b| !b.is_whitespace() && b != c)
+            && after != Some(c)
+            && !(c == '_' && after.is_some_and(char::is_alphanumeric));
+        if closes {
+            return Some(at);
+        }
+        from = at + 1;
+    }
+    None
+}
+
+fn inline(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while let Some(c) = text[i..].chars().next() {
+        let rest = &text[i..];
+
+        if c == '`' {
+            let ticks = rest.chars().take_while(|c| *c == '`').count();
+            let fence = &rest[..ticks];
+            if let Some(end) = rest[ticks..].find(fence) {
+                let code = rest[ticks..ticks + end].trim();
+                if code.contains('`') {
+                    out.push_str(&format!("#raw({})", string_literal(code)));
+                } else {
+                    out.push_str(&format!("`{}`", code));
+                }
+                i += 2 * ticks + end;
+            } else {
+                out.push_str(&"\\`".repeat(ticks));
+                i += ticks;
+            }
+            continue;
+        }
+
+        if c == '\\' {
+            match rest[1..].chars().next() {
+                Some(escaped) if escaped.is_ascii_punctuation() => {
+                    if "\\*_`#$@<>[]~/=+-".contains(escaped) {
+                        out.push('\\');
+                    }
+                    out.push(escaped);
+                    i += 2;
+                }
+                _ => {
+                    out.push_str("\\\\");
+                    i += 1;
+                }
+            }
+            continue;
+        }
+
+        if rest.starts_with("![") {
+            if let Some((alt, url, len)) = link(&rest[1..]) {
+                out.push_str(&format!("#image({}, alt: {})", string_literal(url), string_literal(alt)));
+                i += 1 + len;
+                continue;
+            }
+        }
+
+        if c == '[' {
+            if let Some((label, url, len)) = link(rest) {
+                out.push_str(&format!("#link({})[{}]", string_literal(url), inline(label)));
+                i += len;
+                continue;
+            }
+        }
+
+        if c == '<' {
+            let tag = rest.find('>').map(|end| &rest[..=end]);
+            match tag {
+                Some(tag) if matches!(tag.to_ascii_lowercase().as_str(), "<br>" | "<br/>" | "<br />") => {
+                    out.push_str(" \\ ");
+                    i += tag.len();
+                    continue;
+                }
+                Some(tag) if tag.contains("://") && !tag.contains(char::is_whitespace) => {
+                    out.push_str(&format!("#link({})", string_literal(&tag[1..tag.len() - 1])));
+                    i += tag.len();
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(struck) = rest.strip_prefix("~~") {
+            if let Some(end) = struck.find("~~") {
+                out.push_str(&format!("#strike[{}]", inline(&struck[..end])));
+                i += end + 4;
+                continue;
+            }
+        }
+
+        if c == '*' || c == '_' {
+            let run = rest.chars().take_while(|d| *d == c).count();
+            let opens = run <= 3
+                && rest[run..].chars().next().is_some_and(|n| !n.is_whitespace())
+                && !(c == '_' && out.ends_with(char::is_alphanumeric));
+            if let Some(end) = opens.then(|| closing(rest, &rest[..run])).flatten() {
+                let body = inline(&rest[run..end]);
+                let next = rest[end + run..].chars().next();
+                match run {
+                    1 => push_styled(&mut out, "emph", '_', &body, next),
+                    2 => push_styled(&mut out, "strong", '*', &body, next),
+                    _ => push_styled(&mut out, "strong", '*', &format!("_{}_", body), next),
+                }
+                i += end + run;
+            } else {
+                for _ in 0..run {
+                    out.push('\\');
+                    out.push(c);
+                }
+                i += run;
+            }
+            continue;
+        }
+
+        match c {
+            '#' | '$' | '@' | '<' | '~' => out.push('\\'),
+            '/' if rest.starts_with("//") && !out.ends_with(':') => out.push('\\'),
+            _ => {}
+        }
+        out.push(c);
+        i += c.len_utf8();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::markdown_to_typst;
+
+    #[test]
+    fn test_markdown() {
+        let markdown = "# Notes\n\nSome **bold**, *emphasis* and `code` in snake_case.\nSee [the docs](https://typst.app/docs \"Docs\").\n\n- one\n  - nested\n1. first\n\n```rust\nfn main() {}\n```\n\n| Name | Price |\n|:-----|------:|\n| Fish | $5 |";
+        assert_eq!(
+            markdown_to_typst(markdown),
+            "= Notes\n\nSome *bold*, _emphasis_ and `code` in snake\\_case.\nSee #link(\"https://typst.app/docs\")[the docs].\n\n- one\n  - nested\n+ first\n\n```rust\nfn main() {}\n```\n\n#table(\n  columns: 2,\n  align: (left, right),\n  table.header([Name], [Price]),\n  [Fish], [\\$5],\n)"
+        );
+    }
+}
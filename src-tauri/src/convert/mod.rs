@@ -0,0 +1,13 @@
+mod latex;
+mod latex_project;
+mod markdown;
+mod pandoc;
+mod pasted;
+mod table;
+
+pub use latex::*;
+pub use latex_project::*;
+pub use markdown::*;
+pub use pandoc::*;
+pub use pasted::*;
+pub use table::*;
@@ -0,0 +1,55 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Where package managers install pandoc. Apps started from the Finder or a desktop
+/// launcher don't see the PATH of the user's shell.
+const PANDOC_LOCATIONS: [&str; 3] = [
+    "/opt/homebrew/bin/pandoc",
+    "/usr/local/bin/pandoc",
+    "/usr/bin/pandoc",
+];
+
+fn pandoc_program() -> PathBuf {
+    PANDOC_LOCATIONS
+        .iter()
+        .map(PathBuf::from)
+        .find(|path| path.is_file())
+        .unwrap_or_else(|| PathBuf::from("pandoc"))
+}
+
+pub struct PandocRun {
+    pub success: bool,
+    pub log: String,
+}
+
+/// Converts `input` to typst with pandoc and writes it to `output`. Images are extracted
+/// into `media_dir`, relative to the directory of `output`, so the converted file can
+/// refer to them with relative paths. Fails with `NotFound` if pandoc isn't installed.
+pub fn pandoc_to_typst(input: &Path, output: &Path, media_dir: &Path) -> io::Result<PandocRun> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "output has no parent directory",
+        )
+    };
+    let dir = output.parent().ok_or_else(invalid)?;
+    let file_name = output.file_name().ok_or_else(invalid)?;
+
+    let result = Command::new(pandoc_program())
+        .arg(input)
+        .args(["--to", "typst", "--wrap", "preserve"])
+        .arg("--extract-media")
+        .arg(media_dir)
+        .arg("--output")
+        .arg(file_name)
+        .current_dir(dir)
+        .output()?;
+
+    let mut log = String::from_utf8_lossy(&result.stdout).into_owned();
+    log.push_str(&String::from_utf8_lossy(&result.stderr));
+    Ok(PandocRun {
+        success: result.status.success(),
+        log,
+    })
+}
@@ -0,0 +1,118 @@
+/// Repairs text copied out of a PDF: joins hard-wrapped lines into paragraphs, merges
+/// words hyphenated across lines, drops soft hyphens, expands ligatures, and turns
+/// typographic quotes and dashes into their Typst spelling.
+pub fn clean_pasted_text(text: &str) -> String {
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+
+    let mut paragraphs: Vec<String> = vec![];
+    let mut current = String::new();
+    for line in text.lines() {
+        let line = normalize_line(line);
+        if line.is_empty() {
+            if !current.is_empty() {
+                paragraphs.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+
+        if let Some(item) = strip_bullet(&line) {
+            if !current.is_empty() {
+                paragraphs.push(std::mem::take(&mut current));
+            }
+            current = format!("- {}", item);
+            continue;
+        }
+
+        join_line(&mut current, &line);
+    }
+    if !current.is_empty() {
+        paragraphs.push(current);
+    }
+
+    // Consecutive list items belong to one list, everything else is a paragraph.
+    let mut out = String::new();
+    for (i, paragraph) in paragraphs.iter().enumerate() {
+        if i > 0 {
+            let list = paragraph.starts_with("- ") && paragraphs[i - 1].starts_with("- ");
+            out.push_str(if list { "\n" } else { "\n\n" });
+        }
+        out.push_str(paragraph);
+    }
+    out
+}
+
+fn normalize_line(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    for c in line.trim().chars() {
+        match c {
+            '\u{00AD}' | '\u{200B}' | '\u{FEFF}' => {}
+            '\u{FB00}' => out.push_str("ff"),
+            '\u{FB01}' => out.push_str("fi"),
+            '\u{FB02}' => out.push_str("fl"),
+            '\u{FB03}' => out.push_str("ffi"),
+            '\u{FB04}' => out.push_str("ffl"),
+            '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{00AB}' | '\u{00BB}' => out.push('"'),
+            '\u{2018}' | '\u{2019}' | '\u{201A}' => out.push('\''),
+            '\u{2013}' => out.push_str("--"),
+            '\u{2014}' => out.push_str("---"),
+            '\u{2010}' | '\u{2011}' => out.push('-'),
+            c if c.is_whitespace() => {
+                if !out.ends_with(' ') {
+                    out.push(' ');
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn strip_bullet(line: &str) -> Option<&str> {
+    ["\u{2022} ", "\u{25AA} ", "\u{25E6} ", "\u{2023} "]
+        .iter()
+        .find_map(|bullet| line.strip_prefix(bullet))
+        .map(str::trim_start)
+}
+
+fn join_line(current: &mut String, line: &str) {
+    if current.is_empty() {
+        current.push_str(line);
+        return;
+    }
+
+    // "hyphen-\nated" becomes "hyphenated", but "self-\nAware" and "--" stay.
+    let hyphenated = current.ends_with('-')
+        && !current.ends_with("--")
+        && current[..current.len() - 1]
+            .chars()
+            .next_back()
+            .is_some_and(char::is_alphabetic)
+        && line.chars().next().is_some_and(char::is_lowercase);
+    if hyphenated {
+        current.pop();
+    } else {
+        current.push(' ');
+    }
+    current.push_str(line);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::clean_pasted_text;
+
+    #[test]
+    fn test_clean_pasted_text() {
+        assert_eq!(
+            clean_pasted_text("The quick brown fox jum-\nped over the\r\nlazy dog.\n\nNext para\u{00AD}graph."),
+            "The quick brown fox jumped over the lazy dog.\n\nNext paragraph."
+        );
+        assert_eq!(
+            clean_pasted_text("\u{201C}E\u{FB03}cient\u{201D} \u{2014} see pp. 3\u{2013}5"),
+            "\"Efficient\" --- see pp. 3--5"
+        );
+        assert_eq!(
+            clean_pasted_text("Items:\n\u{2022} one\n\u{2022} two\ncontinued"),
+            "Items:\n\n- one\n- two continued"
+        );
+    }
+}
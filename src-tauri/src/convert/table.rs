@@ -0,0 +1,183 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Rows of cells, with whether the first row is a header.
+pub struct Table {
+    pub rows: Vec<Vec<String>>,
+    pub header: bool,
+}
+
+/// Parses text with cells separated by `delimiter` and rows by newlines. Cells may be
+/// quoted to contain delimiters, newlines and doubled quotes.
+pub fn parse_delimited(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let text = text.replace("\r\n", "\n");
+    let text = text.trim_end_matches('\n');
+
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut cell = String::new();
+    let mut chars = text.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                cell.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if cell.is_empty() => quoted = true,
+            c if c == delimiter && !quoted => row.push(std::mem::take(&mut cell)),
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut cell));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => cell.push(c),
+        }
+    }
+    row.push(cell);
+    rows.push(row);
+    rows
+}
+
+/// Parses tab-separated text as copied from Excel, Numbers or Sheets. Cells may be
+/// quoted to contain tabs, newlines and doubled quotes. Returns `None` unless the text
+/// has at least two columns.
+pub fn parse_tsv(text: &str) -> Option<Table> {
+    let rows = parse_delimited(text, '\t');
+    if rows.iter().map(Vec::len).max()? < 2 {
+        return None;
+    }
+    Some(Table {
+        rows,
+        header: false,
+    })
+}
+
+static TABLE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<table\b.*?</table>").unwrap());
+static ROW: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<tr\b[^>]*>(.*?)</tr>").unwrap());
+static CELL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<t([dh])\b[^>]*>(.*?)</t[dh]>").unwrap());
+static BREAK: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)<br\s*/?>|</p>").unwrap());
+static TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<[^>]*>").unwrap());
+static ENTITY: Lazy<Regex> = Lazy::new(|| Regex::new(r"&(#x?[0-9a-fA-F]+|[a-zA-Z]+);").unwrap());
+
+fn decode_entity(entity: &str) -> Option<char> {
+    if let Some(hex) = entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+        return char::from_u32(u32::from_str_radix(hex, 16).ok()?);
+    }
+    if let Some(dec) = entity.strip_prefix('#') {
+        return char::from_u32(dec.parse().ok()?);
+    }
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => None,
+    }
+}
+
+fn html_text(html: &str) -> String {
+    let text = BREAK.replace_all(html, "\n");
+    let text = TAG.replace_all(&text, "");
+    let text = ENTITY.replace_all(&text, |caps: &regex::Captures| {
+        decode_entity(&caps[1]).map_or_else(|| caps[0].to_string(), String::from)
+    });
+    let lines: Vec<&str> = text.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    lines.join("\n")
+}
+
+/// Parses the first `<table>` of an HTML fragment, as put on the clipboard by
+/// spreadsheets and browsers. A first row made of `<th>` cells becomes the header.
+pub fn parse_html_table(html: &str) -> Option<Table> {
+    let table = TABLE.find(html)?.as_str();
+    let mut header = false;
+    let mut rows = vec![];
+    for (i, row) in ROW.captures_iter(table).enumerate() {
+        let cells: Vec<(bool, String)> = CELL
+            .captures_iter(&row[1])
+            .map(|cell| (&cell[1] == "h" || &cell[1] == "H", html_text(&cell[2])))
+            .collect();
+        if i == 0 {
+            header = !cells.is_empty() && cells.iter().all(|(th, _)| *th);
+        }
+        rows.push(cells.into_iter().map(|(_, text)| text).collect());
+    }
+    (!rows.is_empty()).then_some(Table { rows, header })
+}
+
+/// Escapes text so it renders literally inside a content block.
+fn escape_markup(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.trim().chars().peekable();
+    let mut line_start = true;
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => {
+                out.push_str(" \\ ");
+                line_start = true;
+                continue;
+            }
+            '\\' | '#' | '[' | ']' | '*' | '_' | '`' | '$' | '<' | '>' | '@' | '~' => {
+                out.push('\\');
+            }
+            '/' if matches!(chars.peek(), Some('/') | Some('*')) => out.push('\\'),
+            '-' | '+' | '=' | '/' if line_start => out.push('\\'),
+            _ => {}
+        }
+        out.push(c);
+        line_start = false;
+    }
+    out
+}
+
+/// Renders the table as a `#table(...)` call with one source line per row.
+pub fn table_markup(table: &Table) -> String {
+    let columns = table.rows.iter().map(Vec::len).max().unwrap_or(0);
+    let row_markup = |row: &Vec<String>| {
+        (0..columns)
+            .map(|i| format!("[{}]", escape_markup(row.get(i).map_or("", String::as_str))))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let mut out = format!("#table(\n  columns: {},\n", columns);
+    let mut rows = table.rows.iter();
+    if table.header {
+        if let Some(header) = rows.next() {
+            out.push_str(&format!("  table.header({}),\n", row_markup(header)));
+        }
+    }
+    for row in rows {
+        out.push_str(&format!("  {},\n", row_markup(row)));
+    }
+    out.push(')');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_html_table, parse_tsv, table_markup};
+
+    #[test]
+    fn test_tsv_table() {
+        let table = parse_tsv("Name\tPrice\n\"Fish, \"\"fresh\"\"\"\t$5\n").unwrap();
+        assert_eq!(
+            table_markup(&table),
+            "#table(\n  columns: 2,\n  [Name], [Price],\n  [Fish, \"fresh\"], [\\$5],\n)"
+        );
+        assert!(parse_tsv("just a line of text").is_none());
+    }
+
+    #[test]
+    fn test_html_table() {
+        let html = "<table><tr><th>A</th><th>B</th></tr><tr><td>1 &amp; 2</td><td><b>#3</b></td></tr></table>";
+        let table = parse_html_table(html).unwrap();
+        assert_eq!(
+            table_markup(&table),
+            "#table(\n  columns: 2,\n  table.header([A], [B]),\n  [1 & 2], [\\#3],\n)"
+        );
+    }
+}
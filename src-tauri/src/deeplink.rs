@@ -0,0 +1,216 @@
+//! `typstudio://` links, which open a project at a location from citation managers,
+//! terminals and scripts, eg. `typstudio://open?path=/thesis&file=chapter2.typ&line=120`.
+
+use crate::appdata::is_recent_project;
+use crate::ipc::commands::TypstJump;
+use crate::ipc::events::emit_to_window;
+use crate::window;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Runtime, Url};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+pub const SCHEME: &str = "typstudio";
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct DeepLink {
+    /// The project folder, or a lone `.typ` file.
+    pub path: PathBuf,
+    /// The file to show, relative to the project or inside it.
+    pub file: Option<PathBuf>,
+    /// 1-based, like the line numbers editors show.
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl DeepLink {
+    /// Where the editor goes once the project is open, if the link names a location.
+    pub fn location(&self) -> Option<TypstJump> {
+        let file = match &self.file {
+            Some(file) => file.strip_prefix(&self.path).unwrap_or(file).to_path_buf(),
+            None if self.line.is_some()
+                && self.path.extension().is_some_and(|ext| ext == "typ") =>
+            {
+                PathBuf::from(self.path.file_name()?)
+            }
+            None => return None,
+        };
+        let filepath = Path::new("/").join(file);
+        Some(TypstJump::at(
+            filepath.to_string_lossy().to_string(),
+            self.line.unwrap_or(1),
+            self.column.unwrap_or(1),
+        ))
+    }
+}
+
+/// Parses a `typstudio://open` link. The path must be absolute, as the app has no
+/// working directory of the caller to resolve it against.
+pub fn parse_deep_link(url: &Url) -> Result<DeepLink, String> {
+    if url.scheme() != SCHEME {
+        return Err(format!("not a {} link", SCHEME));
+    }
+    let action = url
+        .host_str()
+        .unwrap_or_else(|| url.path().trim_matches('/'));
+    if action != "open" {
+        return Err(format!("unknown action {:?}", action));
+    }
+
+    let mut link = DeepLink {
+        path: PathBuf::new(),
+        file: None,
+        line: None,
+        column: None,
+    };
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "path" => link.path = PathBuf::from(value.as_ref()),
+            "file" => link.file = Some(PathBuf::from(value.as_ref())),
+            "line" => link.line = Some(parse_position("line", &value)?),
+            "column" => link.column = Some(parse_position("column", &value)?),
+            _ => {}
+        }
+    }
+    if !link.path.is_absolute() {
+        return Err("path must be absolute".to_string());
+    }
+    if let Some(file) = &link.file {
+        let escapes = file
+            .components()
+            .any(|c| c == std::path::Component::ParentDir);
+        if escapes || (file.is_absolute() && !file.starts_with(&link.path)) {
+            return Err("file must be inside the project".to_string());
+        }
+    }
+    Ok(link)
+}
+
+fn parse_position(name: &str, value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|position| *position > 0)
+        .ok_or_else(|| format!("invalid {} {:?}", name, value))
+}
+
+/// Locations of links waiting for their window to load the project, by window label.
+#[derive(Default)]
+pub struct PendingLocations(Mutex<HashMap<String, TypstJump>>);
+
+impl PendingLocations {
+    pub fn insert(&self, label: &str, location: TypstJump) {
+        self.0.lock().unwrap().insert(label.to_string(), location);
+    }
+
+    pub fn take(&self, label: &str) -> Option<TypstJump> {
+        self.0.lock().unwrap().remove(label)
+    }
+}
+
+/// Opens the project of a link, or focuses the window showing it, and moves its editor
+/// to the linked location. Any program or web page can open a link, so projects that
+/// are neither open nor recent are only opened once the user confirms.
+pub fn handle_deep_link<R: Runtime>(app: &AppHandle<R>, url: &Url) {
+    let link = match parse_deep_link(url) {
+        Ok(link) => link,
+        Err(e) => {
+            log::warn!("Ignoring link {}: {}", url, e);
+            return;
+        }
+    };
+
+    if let Some(window) = window::project_window(app, &link.path) {
+        let _ = window.set_focus();
+        if let Some(location) = link.location() {
+            emit_to_window(&window, "editor_goto_location", location);
+        }
+        return;
+    }
+    if is_recent_project(&link.path) {
+        open_link(app, link);
+        return;
+    }
+
+    let handle = app.clone();
+    app.dialog()
+        .message(format!(
+            "A link asks to open {}, which you haven't opened in Typstudio before.\n\nOnly open projects you trust.",
+            link.path.display()
+        ))
+        .title("Open Project from Link")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Open".to_string(),
+            "Cancel".to_string(),
+        ))
+        .show(move |confirmed| {
+            if confirmed {
+                open_link(&handle, link);
+            } else {
+                log::info!("Not opening {:?} from a link", link.path);
+            }
+        });
+}
+
+fn open_link<R: Runtime>(app: &AppHandle<R>, link: DeepLink) {
+    log::info!("opening {:?} from a link", link.path);
+    let location = link.location();
+    if let Some(window) = window::open_path(app, link.path) {
+        // The page asks for it once `project_changed` arrived.
+        if let Some(location) = location {
+            app.state::<PendingLocations>()
+                .insert(window.label(), location);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(url: &str) -> Result<DeepLink, String> {
+        parse_deep_link(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn test_parse_deep_link() {
+        let link =
+            parse("typstudio://open?path=/home/me/thesis&file=chapter2.typ&line=120").unwrap();
+        assert_eq!(link.path, PathBuf::from("/home/me/thesis"));
+        assert_eq!(link.file, Some(PathBuf::from("chapter2.typ")));
+        assert_eq!(link.line, Some(120));
+        assert_eq!(link.column, None);
+
+        let link = parse("typstudio://open?path=%2Fhome%2Fme%2Fmy%20thesis").unwrap();
+        assert_eq!(link.path, PathBuf::from("/home/me/my thesis"));
+        assert!(link.location().is_none());
+
+        assert!(parse("typstudio://open?path=thesis").is_err());
+        assert!(parse("typstudio://open?path=/thesis&line=0").is_err());
+        assert!(parse("typstudio://open?path=/thesis&file=/etc/passwd").is_err());
+        assert!(parse("typstudio://open?path=/thesis&file=../secret.typ").is_err());
+        assert!(parse("typstudio://delete?path=/thesis").is_err());
+        assert!(parse("https://open?path=/thesis").is_err());
+    }
+
+    #[test]
+    fn test_location() {
+        let location = |url: &str| {
+            let jump = parse(url).unwrap().location().unwrap();
+            serde_json::to_value(jump).unwrap()
+        };
+
+        let jump = location("typstudio://open?path=/thesis&file=chapters/two.typ&line=12&column=4");
+        assert_eq!(jump["filepath"], "/chapters/two.typ");
+        assert_eq!(jump["start"], serde_json::json!([12, 4]));
+
+        let jump = location("typstudio://open?path=/thesis&file=/thesis/two.typ");
+        assert_eq!(jump["filepath"], "/two.typ");
+        assert_eq!(jump["start"], serde_json::json!([1, 1]));
+
+        let jump = location("typstudio://open?path=/notes/todo.typ&line=3");
+        assert_eq!(jump["filepath"], "/todo.typ");
+    }
+}
@@ -0,0 +1,322 @@
+use crate::search::fuzzy_match;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use typst::foundations::{CastInfo, Func, Repr, Scope, Value};
+use typst::{Library, LibraryExt};
+
+/// Search results beyond this are dropped.
+const MAX_RESULTS: usize = 50;
+
+/// Modules are not descended into deeper than this; `std` refers back to the root.
+const MAX_DEPTH: usize = 4;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DocKind {
+    Function,
+    Type,
+    Module,
+    Constant,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DocParam {
+    pub name: String,
+    /// The accepted types or values, eg. `["length", "auto"]`.
+    pub types: Vec<String>,
+    pub docs: String,
+    pub default: Option<String>,
+    pub positional: bool,
+    pub named: bool,
+    pub required: bool,
+    pub variadic: bool,
+}
+
+/// The documentation of a definition of the standard library.
+#[derive(Serialize, Clone, Debug)]
+pub struct DocEntry {
+    /// The path under which it is reachable, eg. `calc.pow` or `str.len`.
+    pub path: String,
+    pub kind: DocKind,
+    pub signature: Option<String>,
+    /// The first paragraph of `docs`.
+    pub summary: String,
+    /// Markdown, as in the official documentation.
+    pub docs: String,
+    pub params: Vec<DocParam>,
+    pub returns: Vec<String>,
+    /// The sources of the documentation's example blocks.
+    pub examples: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DocSearchHit {
+    pub path: String,
+    pub kind: DocKind,
+    pub summary: String,
+}
+
+/// The documentation of the bundled typst version, built from the standard library
+/// itself so it is available offline and always matches the compiler.
+static INDEX: Lazy<BTreeMap<String, DocEntry>> = Lazy::new(|| {
+    let library = Library::default();
+    let mut index = BTreeMap::new();
+    let mut seen = HashSet::new();
+    index_scope(library.global.scope(), "", 0, &mut seen, &mut index);
+    index
+});
+
+fn cast_names(info: &CastInfo, names: &mut Vec<String>) {
+    match info {
+        CastInfo::Any => names.push("any".into()),
+        CastInfo::Value(value, _) => names.push(value.repr().to_string()),
+        CastInfo::Type(ty) => names.push(ty.short_name().into()),
+        CastInfo::Union(infos) => infos.iter().for_each(|info| cast_names(info, names)),
+    }
+}
+
+fn dedup(mut names: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    names.retain(|name| seen.insert(name.clone()));
+    names
+}
+
+/// The first paragraph of markdown documentation.
+pub fn doc_summary(docs: &str) -> String {
+    docs.trim()
+        .split("\n\n")
+        .next()
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The page setup the official documentation renders examples with.
+const EXAMPLE_PREAMBLE: &str = "#set page(width: 240pt, height: auto, margin: 15pt)\n";
+
+/// The lines of the ```` ```example ```` blocks of markdown documentation.
+fn example_blocks(docs: &str) -> Vec<Vec<&str>> {
+    let mut blocks = vec![];
+    let mut current: Option<Vec<&str>> = None;
+    for line in docs.lines() {
+        let trimmed = line.trim();
+        match &mut current {
+            None if trimmed.starts_with("```example") => current = Some(vec![]),
+            Some(lines) if trimmed.starts_with("```") => {
+                blocks.push(std::mem::take(lines));
+                current = None;
+            }
+            Some(lines) => lines.push(line),
+            None => {}
+        }
+    }
+    blocks
+}
+
+/// Lines starting with `>>>` are only compiled, lines starting with `<<<` only shown.
+fn example_text(lines: &[&str], compiled: bool) -> String {
+    let (keep, drop) = if compiled {
+        (">>>", "<<<")
+    } else {
+        ("<<<", ">>>")
+    };
+    lines
+        .iter()
+        .filter(|line| !line.trim_start().starts_with(drop))
+        .map(|line| match line.trim_start().strip_prefix(keep) {
+            Some(rest) => rest.strip_prefix(' ').unwrap_or(rest),
+            None => line,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The examples of markdown documentation, as shown to the reader.
+pub fn doc_examples(docs: &str) -> Vec<String> {
+    example_blocks(docs)
+        .iter()
+        .map(|lines| example_text(lines, false))
+        .collect()
+}
+
+/// The code to compile for the `index`th example of `path`, set up like the official
+/// documentation's examples.
+pub fn doc_example_source(path: &str, index: usize) -> Option<String> {
+    let entry = INDEX.get(path.trim().trim_start_matches('#'))?;
+    let blocks = example_blocks(&entry.docs);
+    let lines = blocks.get(index)?;
+    Some(format!("{}{}", EXAMPLE_PREAMBLE, example_text(lines, true)))
+}
+
+fn function_entry(path: &str, func: &Func) -> DocEntry {
+    let docs = func.docs().unwrap_or_default().to_string();
+    let params: Vec<DocParam> = func
+        .params()
+        .unwrap_or_default()
+        .iter()
+        .map(|param| {
+            let mut types = vec![];
+            cast_names(&param.input, &mut types);
+            DocParam {
+                name: param.name.into(),
+                types: dedup(types),
+                docs: param.docs.into(),
+                default: param.default.map(|default| default().repr().to_string()),
+                positional: param.positional,
+                named: param.named,
+                required: param.required,
+                variadic: param.variadic,
+            }
+        })
+        .collect();
+    let mut returns = vec![];
+    if let Some(info) = func.returns() {
+        cast_names(info, &mut returns);
+    }
+    let returns = dedup(returns);
+
+    let args: Vec<String> = params
+        .iter()
+        .map(|param| {
+            let dots = if param.variadic { ".." } else { "" };
+            format!("{}{}: {}", dots, param.name, param.types.join(" | "))
+        })
+        .collect();
+    let mut signature = format!("{}({})", path, args.join(", "));
+    if !returns.is_empty() {
+        signature.push_str(" -> ");
+        signature.push_str(&returns.join(" | "));
+    }
+
+    DocEntry {
+        path: path.into(),
+        kind: DocKind::Function,
+        signature: Some(signature),
+        summary: doc_summary(&docs),
+        examples: doc_examples(&docs),
+        docs,
+        params,
+        returns,
+    }
+}
+
+fn plain_entry(path: &str, kind: DocKind, docs: &str) -> DocEntry {
+    DocEntry {
+        path: path.into(),
+        kind,
+        signature: None,
+        summary: doc_summary(docs),
+        docs: docs.into(),
+        params: vec![],
+        returns: vec![],
+        examples: doc_examples(docs),
+    }
+}
+
+fn index_scope(
+    scope: &Scope,
+    prefix: &str,
+    depth: usize,
+    seen: &mut HashSet<String>,
+    index: &mut BTreeMap<String, DocEntry>,
+) {
+    for (name, binding) in scope.iter() {
+        let path = format!("{}{}", prefix, name);
+        if !seen.insert(path.clone()) {
+            continue;
+        }
+        match binding.read() {
+            Value::Func(func) => {
+                index.insert(path.clone(), function_entry(&path, func));
+                if let Some(scope) = func.scope() {
+                    index_scope(scope, &format!("{}.", path), depth + 1, seen, index);
+                }
+            }
+            Value::Type(ty) => {
+                let mut entry = plain_entry(&path, DocKind::Type, ty.docs());
+                if let Ok(constructor) = ty.constructor() {
+                    let constructor = function_entry(&path, &constructor);
+                    entry.signature = constructor.signature;
+                    entry.params = constructor.params;
+                    entry.returns = constructor.returns;
+                }
+                index.insert(path.clone(), entry);
+                index_scope(ty.scope(), &format!("{}.", path), depth + 1, seen, index);
+            }
+            Value::Module(module) => {
+                if depth >= MAX_DEPTH || name.as_str() == "std" {
+                    continue;
+                }
+                index.insert(path.clone(), plain_entry(&path, DocKind::Module, ""));
+                index_scope(
+                    module.scope(),
+                    &format!("{}.", path),
+                    depth + 1,
+                    seen,
+                    index,
+                );
+            }
+            value => {
+                let docs = format!("`{}`", value.repr());
+                index.insert(path.clone(), plain_entry(&path, DocKind::Constant, &docs));
+            }
+        }
+    }
+}
+
+/// Searches the documentation by name, then by the text of the documentation.
+pub fn search_docs(query: &str) -> Vec<DocSearchHit> {
+    let query = query.trim();
+    let lowercase = query.to_lowercase();
+    let mut hits: Vec<(i64, &DocEntry)> = INDEX
+        .values()
+        .filter_map(|entry| {
+            let score = match fuzzy_match(query, &entry.path) {
+                Some(found) => found.score,
+                None if !lowercase.is_empty() && entry.docs.to_lowercase().contains(&lowercase) => {
+                    i64::MIN / 2
+                }
+                None => return None,
+            };
+            Some((score, entry))
+        })
+        .collect();
+    hits.sort_by(|(a, x), (b, y)| {
+        b.cmp(a)
+            .then(x.path.len().cmp(&y.path.len()))
+            .then(x.path.cmp(&y.path))
+    });
+    hits.into_iter()
+        .take(MAX_RESULTS)
+        .map(|(_, entry)| DocSearchHit {
+            path: entry.path.clone(),
+            kind: entry.kind,
+            summary: entry.summary.clone(),
+        })
+        .collect()
+}
+
+/// The documentation of `path`, eg. `text` or `calc.pow`.
+pub fn doc_entry(path: &str) -> Option<DocEntry> {
+    INDEX.get(path.trim().trim_start_matches('#')).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{doc_examples, doc_summary, example_blocks, example_text};
+
+    #[test]
+    fn test_doc_text() {
+        let docs = "Raises a value to\nsome exponent.\n\n```example\n>>> #set text(8pt)\n#calc.pow(2, 3)\n```\n\nMore.";
+        assert_eq!(doc_summary(docs), "Raises a value to some exponent.");
+        assert_eq!(doc_examples(docs), ["#calc.pow(2, 3)"]);
+        let blocks = example_blocks(docs);
+        assert_eq!(
+            example_text(&blocks[0], true),
+            "#set text(8pt)\n#calc.pow(2, 3)"
+        );
+    }
+}
@@ -0,0 +1,5 @@
+mod index;
+mod symbols;
+
+pub use index::*;
+pub use symbols::*;
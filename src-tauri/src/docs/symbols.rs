@@ -0,0 +1,136 @@
+use crate::search::fuzzy_match;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use typst::foundations::Value;
+use typst::{Library, LibraryExt};
+
+/// Markup and math shorthands, as `(symbol, shorthand)`.
+const SHORTHANDS: &[(&str, &str)] = &[
+    ("\u{a0}", "~"),
+    ("\u{ad}", "-?"),
+    ("–", "--"),
+    ("—", "---"),
+    ("…", "..."),
+    ("→", "->"),
+    ("←", "<-"),
+    ("↔", "<->"),
+    ("⇒", "=>"),
+    ("⇐", "<=="),
+    ("⇔", "<=>"),
+    ("⟶", "-->"),
+    ("⟹", "==>"),
+    ("↦", "|->"),
+    ("≠", "!="),
+    ("≤", "<="),
+    ("≥", ">="),
+    ("≪", "<<"),
+    ("≫", ">>"),
+    ("∗", "*"),
+    ("′", "'"),
+    ("∥", "||"),
+    ("≔", ":="),
+    ("⩴", "::="),
+    ("⟦", "[|"),
+    ("⟧", "|]"),
+];
+
+/// A symbol of the `sym` or `emoji` module.
+#[derive(Serialize, Clone, Debug)]
+pub struct SymbolEntry {
+    /// The name within its module, eg. `arrow.r.double`.
+    pub name: String,
+    pub module: String,
+    pub value: String,
+    /// Eg. `U+21D2`.
+    pub codepoints: Vec<String>,
+    /// What to insert in markup, eg. `#sym.arrow.r.double`.
+    pub markup: String,
+    /// What to insert in math mode, eg. `arrow.r.double`.
+    pub math: String,
+    pub shorthand: Option<String>,
+}
+
+static SYMBOLS: Lazy<Vec<SymbolEntry>> = Lazy::new(|| {
+    let library = Library::default();
+    let scope = library.global.scope();
+    let mut symbols = vec![];
+    for module in ["sym", "emoji"] {
+        let Some(Value::Module(module_value)) = scope.get(module).map(|b| b.read()) else {
+            continue;
+        };
+        for (name, binding) in module_value.scope().iter() {
+            let Value::Symbol(symbol) = binding.read() else {
+                continue;
+            };
+            for (modifiers, value, deprecation) in symbol.variants() {
+                if deprecation.is_some() {
+                    continue;
+                }
+                let name = match modifiers.as_str() {
+                    "" => name.to_string(),
+                    modifiers => format!("{}.{}", name, modifiers),
+                };
+                symbols.push(symbol_entry(module, name, value));
+            }
+        }
+    }
+    symbols
+});
+
+fn symbol_entry(module: &str, name: String, value: &str) -> SymbolEntry {
+    // Math mode resolves `sym` names directly; emoji need their module.
+    let math = match module {
+        "sym" => name.clone(),
+        _ => format!("{}.{}", module, name),
+    };
+    SymbolEntry {
+        markup: format!("#{}.{}", module, name),
+        math,
+        module: module.into(),
+        codepoints: value
+            .chars()
+            .map(|c| format!("U+{:04X}", c as u32))
+            .collect(),
+        shorthand: SHORTHANDS
+            .iter()
+            .find(|(symbol, _)| *symbol == value)
+            .map(|(_, shorthand)| shorthand.to_string()),
+        value: value.into(),
+        name,
+    }
+}
+
+/// The symbols whose name matches `query`, best matches first, or all of them for an
+/// empty query. A query that is a symbol itself finds it by value.
+pub fn list_symbols(query: &str) -> Vec<SymbolEntry> {
+    let query = query.trim();
+    if query.is_empty() {
+        return SYMBOLS.clone();
+    }
+    let mut hits: Vec<(i64, &SymbolEntry)> = SYMBOLS
+        .iter()
+        .filter_map(|entry| {
+            if entry.value == query {
+                return Some((i64::MAX, entry));
+            }
+            let full = format!("{}.{}", entry.module, entry.name);
+            fuzzy_match(query, &full).map(|found| (found.score, entry))
+        })
+        .collect();
+    hits.sort_by(|(a, x), (b, y)| b.cmp(a).then(x.name.len().cmp(&y.name.len())));
+    hits.into_iter().map(|(_, entry)| entry.clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::symbol_entry;
+
+    #[test]
+    fn test_symbol_entry() {
+        let entry = symbol_entry("sym", "arrow.r".into(), "→");
+        assert_eq!(entry.markup, "#sym.arrow.r");
+        assert_eq!(entry.math, "arrow.r");
+        assert_eq!(entry.codepoints, ["U+2192"]);
+        assert_eq!(entry.shorthand.as_deref(), Some("->"));
+    }
+}
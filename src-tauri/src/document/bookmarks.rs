@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use typst::foundations::{Content, Element, Label, Selector, Value};
+use typst::layout::PagedDocument;
+use typst::model::HeadingElem;
+use typst::utils::PicoStr;
+
+/// What a bookmark points at. Anchoring to the document structure instead of a page
+/// number keeps bookmarks in place when content above them grows or shrinks.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum BookmarkAnchor {
+    /// A label such as `intro` for `<intro>`.
+    Label(String),
+    /// The text of a heading, eg. `Related work`.
+    Heading(String),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Bookmark {
+    pub name: String,
+    pub anchor: BookmarkAnchor,
+}
+
+/// Where a bookmark is in the document: a page index and a point in pt, like the
+/// preview's jump positions.
+#[derive(Serialize, Clone, Debug)]
+pub struct BookmarkPosition {
+    pub page: usize,
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ResolvedBookmark {
+    #[serde(flatten)]
+    pub bookmark: Bookmark,
+    /// `None` if the label or heading is no longer in the document.
+    pub position: Option<BookmarkPosition>,
+}
+
+fn heading_text(heading: &Content) -> Option<String> {
+    match heading.get_by_name("body").ok()? {
+        Value::Content(body) => Some(body.plain_text().trim().to_string()),
+        _ => None,
+    }
+}
+
+fn find_anchor(document: &PagedDocument, anchor: &BookmarkAnchor) -> Option<Content> {
+    let introspector = &document.introspector;
+    match anchor {
+        BookmarkAnchor::Label(label) => {
+            let label = Label::new(PicoStr::intern(label.trim_matches(['<', '>'])))?;
+            introspector.query_first(&Selector::Label(label))
+        }
+        BookmarkAnchor::Heading(text) => introspector
+            .query(&Selector::Elem(Element::of::<HeadingElem>(), None))
+            .into_iter()
+            .find(|heading| heading_text(heading).as_deref() == Some(text.trim())),
+    }
+}
+
+/// Finds the current position of every bookmark in the document.
+pub fn resolve_bookmarks(
+    document: &PagedDocument,
+    bookmarks: &[Bookmark],
+) -> Vec<ResolvedBookmark> {
+    bookmarks
+        .iter()
+        .map(|bookmark| {
+            let position = find_anchor(document, &bookmark.anchor)
+                .and_then(|content| content.location())
+                .map(|location| {
+                    let position = document.introspector.position(location);
+                    BookmarkPosition {
+                        page: position.page.get() - 1,
+                        x: position.point.x.to_pt(),
+                        y: position.point.y.to_pt(),
+                    }
+                });
+            ResolvedBookmark {
+                bookmark: bookmark.clone(),
+                position,
+            }
+        })
+        .collect()
+}
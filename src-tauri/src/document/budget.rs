@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use typst::foundations::{Element, Selector, Value};
+use typst::layout::PagedDocument;
+use typst::model::HeadingElem;
+
+/// Page limits for the whole document and for top-level sections.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Hash)]
+#[serde(default)]
+pub struct PageBudget {
+    pub total: Option<usize>,
+    /// Limits keyed by the text of a top-level heading, eg. `{"Introduction": 2}`.
+    pub sections: BTreeMap<String, usize>,
+}
+
+impl PageBudget {
+    pub fn is_empty(&self) -> bool {
+        self.total.is_none() && self.sections.is_empty()
+    }
+}
+
+/// A top-level section and the pages it occupies (1-based, inclusive).
+#[derive(Serialize, Debug, Clone)]
+pub struct SectionPages {
+    pub title: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl SectionPages {
+    /// Pages touched by the section. A page shared with the neighbouring section counts
+    /// for both.
+    pub fn pages(&self) -> usize {
+        self.end - self.start + 1
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BudgetOverrun {
+    /// The section over budget, or `None` for the whole document.
+    pub section: Option<String>,
+    pub pages: usize,
+    pub limit: usize,
+}
+
+/// The top-level sections of the document in order, based on level 1 headings.
+pub fn top_level_sections(document: &PagedDocument) -> Vec<SectionPages> {
+    let introspector = &document.introspector;
+    let headings = introspector.query(&Selector::Elem(Element::of::<HeadingElem>(), None));
+
+    let starts: Vec<(String, usize)> = headings
+        .iter()
+        .filter(|heading| matches!(heading.get_by_name("level").ok(), Some(Value::Int(1))))
+        .filter_map(|heading| {
+            let title = match heading.get_by_name("body").ok()? {
+                Value::Content(body) => body.plain_text().trim().to_string(),
+                _ => return None,
+            };
+            Some((title, introspector.page(heading.location()?).get()))
+        })
+        .collect();
+
+    let last_page = document.pages.len();
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, (title, start))| {
+            let end = starts.get(i + 1).map_or(last_page, |(_, next)| *next);
+            SectionPages {
+                title: title.clone(),
+                start: *start,
+                end: end.max(*start),
+            }
+        })
+        .collect()
+}
+
+/// Compares the document against the budget and returns every limit that is exceeded.
+pub fn check_page_budget(document: &PagedDocument, budget: &PageBudget) -> Vec<BudgetOverrun> {
+    let mut overruns = vec![];
+    if let Some(limit) = budget.total {
+        let pages = document.pages.len();
+        if pages > limit {
+            overruns.push(BudgetOverrun {
+                section: None,
+                pages,
+                limit,
+            });
+        }
+    }
+
+    if !budget.sections.is_empty() {
+        for section in top_level_sections(document) {
+            let Some(&limit) = budget.sections.get(&section.title) else {
+                continue;
+            };
+            if section.pages() > limit {
+                overruns.push(BudgetOverrun {
+                    pages: section.pages(),
+                    section: Some(section.title),
+                    limit,
+                });
+            }
+        }
+    }
+    overruns
+}
@@ -0,0 +1,185 @@
+use super::text::transform_rect;
+use crate::document::Rect;
+use serde::Serialize;
+use siphasher::sip128::{Hasher128, SipHasher};
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use typst::layout::{Abs, Frame, FrameItem, Page, PagedDocument, Transform};
+
+/// Pages with more changed items than this are reported as changed as a whole.
+const MAX_ITEMS: usize = 400;
+
+/// Changed items closer than this, in points, are merged into one region.
+const MERGE_DISTANCE: f64 = 6.0;
+
+/// The regions of a page whose content changed since the previous compile.
+#[derive(Serialize, Clone, Debug)]
+pub struct PageChanges {
+    pub page: usize,
+    pub rects: Vec<Rect>,
+}
+
+/// A visible item of a page: what it shows and where, and its box in page coordinates.
+struct PageItem {
+    key: u128,
+    rect: Rect,
+}
+
+fn rect_key(hasher: &mut SipHasher, rect: Rect) {
+    for value in [rect.x, rect.y, rect.width, rect.height] {
+        ((value * 100.0).round() as i64).hash(hasher);
+    }
+}
+
+fn collect_items(frame: &Frame, ts: Transform, items: &mut Vec<PageItem>) {
+    for (pos, item) in frame.items() {
+        let mut hasher = SipHasher::new();
+        let rect = match item {
+            FrameItem::Group(group) => {
+                let ts = ts
+                    .pre_concat(Transform::translate(pos.x, pos.y))
+                    .pre_concat(group.transform);
+                collect_items(&group.frame, ts, items);
+                continue;
+            }
+            // Spans are renumbered by edits elsewhere in a file, so text is compared by
+            // the file it came from rather than its exact span.
+            FrameItem::Text(text) => {
+                text.text.hash(&mut hasher);
+                text.size.hash(&mut hasher);
+                text.fill.hash(&mut hasher);
+                text.glyphs
+                    .first()
+                    .and_then(|glyph| glyph.span.0.id())
+                    .hash(&mut hasher);
+                let width: Abs = text.glyphs.iter().map(|g| g.x_advance.at(text.size)).sum();
+                let ascent = text.size * 0.8;
+                transform_rect(ts, pos.x, pos.y - ascent, width, text.size)
+            }
+            FrameItem::Shape(shape, _) => {
+                shape.hash(&mut hasher);
+                let size = shape.geometry.bbox_size();
+                transform_rect(ts, pos.x, pos.y, size.x, size.y)
+            }
+            FrameItem::Image(image, size, _) => {
+                image.hash(&mut hasher);
+                transform_rect(ts, pos.x, pos.y, size.x, size.y)
+            }
+            _ => continue,
+        };
+        rect_key(&mut hasher, rect);
+        items.push(PageItem {
+            key: hasher.finish128().as_u128(),
+            rect,
+        });
+    }
+}
+
+fn page_items(page: &Page) -> Vec<PageItem> {
+    let mut items = vec![];
+    collect_items(&page.frame, Transform::identity(), &mut items);
+    items
+}
+
+fn page_rect(page: &Page) -> Rect {
+    Rect {
+        x: 0.0,
+        y: 0.0,
+        width: page.frame.width().to_pt(),
+        height: page.frame.height().to_pt(),
+    }
+}
+
+fn near(a: Rect, b: Rect) -> bool {
+    a.x - MERGE_DISTANCE <= b.x + b.width
+        && b.x - MERGE_DISTANCE <= a.x + a.width
+        && a.y - MERGE_DISTANCE <= b.y + b.height
+        && b.y - MERGE_DISTANCE <= a.y + a.height
+}
+
+/// Merges overlapping and nearby rectangles until none are close to each other.
+pub fn merge_rects(mut rects: Vec<Rect>) -> Vec<Rect> {
+    let mut merged: Vec<Rect> = vec![];
+    while let Some(mut rect) = rects.pop() {
+        while let Some(i) = merged.iter().position(|&other| near(rect, other)) {
+            rect = rect.union(merged.swap_remove(i));
+        }
+        merged.push(rect);
+    }
+    merged.sort_by(|a, b| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)));
+    merged
+}
+
+/// Compares two pages item by item. Items that appeared, disappeared or moved are
+/// changed; both their old and new places are reported.
+pub(super) fn page_changes(old: &Page, new: &Page) -> Vec<Rect> {
+    let old_items = page_items(old);
+    let new_items = page_items(new);
+    let old_keys: HashSet<u128> = old_items.iter().map(|item| item.key).collect();
+    let new_keys: HashSet<u128> = new_items.iter().map(|item| item.key).collect();
+
+    let changed: Vec<Rect> = new_items
+        .iter()
+        .filter(|item| !old_keys.contains(&item.key))
+        .chain(
+            old_items
+                .iter()
+                .filter(|item| !new_keys.contains(&item.key)),
+        )
+        .map(|item| item.rect)
+        .collect();
+    if changed.len() > MAX_ITEMS {
+        return vec![page_rect(new)];
+    }
+    merge_rects(changed)
+}
+
+fn frame_hash(frame: &Frame) -> u128 {
+    let mut hasher = SipHasher::new();
+    frame.hash(&mut hasher);
+    hasher.finish128().as_u128()
+}
+
+/// The regions of `new` that differ from `old`, per page. Pages added at the end are
+/// changed as a whole.
+pub fn changed_regions(old: &PagedDocument, new: &PagedDocument) -> Vec<PageChanges> {
+    new.pages
+        .iter()
+        .enumerate()
+        .filter_map(|(i, page)| {
+            let rects = match old.pages.get(i) {
+                Some(old) if frame_hash(&old.frame) == frame_hash(&page.frame) => return None,
+                Some(old) => page_changes(old, page),
+                None => vec![page_rect(page)],
+            };
+            (!rects.is_empty()).then_some(PageChanges { page: i, rects })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge_rects, Rect};
+
+    fn rect(x: f64, y: f64, width: f64, height: f64) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn test_merge_rects() {
+        let rects = vec![
+            rect(10.0, 100.0, 50.0, 10.0),
+            rect(62.0, 100.0, 30.0, 10.0),
+            rect(10.0, 400.0, 20.0, 10.0),
+        ];
+        assert_eq!(
+            merge_rects(rects),
+            [rect(10.0, 100.0, 82.0, 10.0), rect(10.0, 400.0, 20.0, 10.0)]
+        );
+    }
+}
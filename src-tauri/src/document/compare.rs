@@ -0,0 +1,145 @@
+use super::changes::page_changes;
+use crate::document::Rect;
+use serde::Serialize;
+use typst::layout::{Page, PagedDocument};
+
+/// Pixels per point the pages are rasterized at to count changed pixels.
+const COMPARE_SCALE: f32 = 1.0;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PageCompareStatus {
+    Unchanged,
+    Changed,
+    /// Only in the newer document.
+    Added,
+    /// Only in the older document.
+    Removed,
+}
+
+/// How a page of two compiled revisions differs.
+#[derive(Serialize, Clone, Debug)]
+pub struct PageComparison {
+    pub page: usize,
+    pub status: PageCompareStatus,
+    /// The changed regions of the newer page, from its layout, in points.
+    pub rects: Vec<Rect>,
+    /// The share of pixels that differ when both pages are rasterized, from 0 to 1.
+    pub pixel_difference: f64,
+    /// The box around the differing pixels, in points.
+    pub pixel_bounds: Option<Rect>,
+}
+
+/// Compares the pages of `old` and `new` by index.
+pub fn compare_documents(old: &PagedDocument, new: &PagedDocument) -> Vec<PageComparison> {
+    let count = old.pages.len().max(new.pages.len());
+    (0..count)
+        .map(|i| match (old.pages.get(i), new.pages.get(i)) {
+            (Some(old), Some(new)) => compare_pages(i, old, new),
+            (None, Some(page)) => whole_page(i, PageCompareStatus::Added, page),
+            (Some(page), _) => whole_page(i, PageCompareStatus::Removed, page),
+            (None, None) => unreachable!(),
+        })
+        .collect()
+}
+
+fn page_rect(page: &Page) -> Rect {
+    Rect {
+        x: 0.0,
+        y: 0.0,
+        width: page.frame.width().to_pt(),
+        height: page.frame.height().to_pt(),
+    }
+}
+
+fn whole_page(page: usize, status: PageCompareStatus, content: &Page) -> PageComparison {
+    PageComparison {
+        page,
+        status,
+        rects: vec![page_rect(content)],
+        pixel_difference: 1.0,
+        pixel_bounds: Some(page_rect(content)),
+    }
+}
+
+fn compare_pages(page: usize, old: &Page, new: &Page) -> PageComparison {
+    let rects = page_changes(old, new);
+    let old_pixmap = typst_render::render(old, COMPARE_SCALE);
+    let new_pixmap = typst_render::render(new, COMPARE_SCALE);
+    let (pixel_difference, pixel_bounds) =
+        if (old_pixmap.width(), old_pixmap.height()) != (new_pixmap.width(), new_pixmap.height()) {
+            (1.0, Some(page_rect(new)))
+        } else {
+            pixel_diff(
+                old_pixmap.data(),
+                new_pixmap.data(),
+                new_pixmap.width() as usize,
+                COMPARE_SCALE as f64,
+            )
+        };
+    let status = if rects.is_empty() && pixel_bounds.is_none() {
+        PageCompareStatus::Unchanged
+    } else {
+        PageCompareStatus::Changed
+    };
+    PageComparison {
+        page,
+        status,
+        rects,
+        pixel_difference,
+        pixel_bounds,
+    }
+}
+
+/// The share of differing pixels of two RGBA images of the same size and the box around
+/// them, scaled from pixels to points.
+fn pixel_diff(old: &[u8], new: &[u8], width: usize, scale: f64) -> (f64, Option<Rect>) {
+    let pixels = old.len() / 4;
+    if pixels == 0 || width == 0 {
+        return (0.0, None);
+    }
+    let mut changed = 0;
+    let (mut x0, mut y0, mut x1, mut y1) = (usize::MAX, usize::MAX, 0, 0);
+    for (i, (a, b)) in old.chunks_exact(4).zip(new.chunks_exact(4)).enumerate() {
+        if a != b {
+            changed += 1;
+            let (x, y) = (i % width, i / width);
+            x0 = x0.min(x);
+            y0 = y0.min(y);
+            x1 = x1.max(x + 1);
+            y1 = y1.max(y + 1);
+        }
+    }
+    let bounds = (changed > 0).then(|| Rect {
+        x: x0 as f64 / scale,
+        y: y0 as f64 / scale,
+        width: (x1 - x0) as f64 / scale,
+        height: (y1 - y0) as f64 / scale,
+    });
+    (changed as f64 / pixels as f64, bounds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pixel_diff() {
+        let white = [255u8; 4 * 4 * 2];
+        assert_eq!(pixel_diff(&white, &white, 4, 1.0), (0.0, None));
+
+        let mut changed = white;
+        changed[4 * 5..4 * 7].fill(0);
+        let (difference, bounds) = pixel_diff(&white, &changed, 4, 2.0);
+        assert_eq!(difference, 0.25);
+        assert_eq!(
+            bounds,
+            Some(Rect {
+                x: 0.5,
+                y: 0.5,
+                width: 1.0,
+                height: 0.5,
+            })
+        );
+    }
+}
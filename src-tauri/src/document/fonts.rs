@@ -0,0 +1,208 @@
+use crate::engine::TypstEngine;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use typst::foundations::Bytes;
+use typst::layout::{Frame, FrameItem, PagedDocument};
+use typst::text::{Font, FontInfo, FontStyle};
+
+/// Extensions of font files looked for in the project.
+const FONT_EXTENSIONS: [&str; 4] = ["ttf", "otf", "ttc", "otc"];
+
+/// A font face as shown in the report, eg. `Libertinus Serif` at weight 700, italic.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FontFace {
+    pub family: String,
+    pub weight: u16,
+    pub style: &'static str,
+}
+
+impl FontFace {
+    fn new(info: &FontInfo) -> Self {
+        Self {
+            family: info.family.clone(),
+            weight: info.variant.weight.to_number(),
+            style: match info.variant.style {
+                FontStyle::Normal => "normal",
+                FontStyle::Italic => "italic",
+                FontStyle::Oblique => "oblique",
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FontUsage {
+    #[serde(flatten)]
+    pub face: FontFace,
+    /// The file the face was loaded from, or `None` for the fonts built into the app.
+    pub path: Option<PathBuf>,
+    /// Distinct characters set in this face.
+    pub characters: usize,
+    /// Distinct glyphs, which differ from the characters for ligatures and shaping.
+    pub glyphs: usize,
+    /// The characters as inclusive code point ranges.
+    pub ranges: Vec<(u32, u32)>,
+    /// The ranges in the syntax of font subsetters, eg. `U+0020-007E,U+00E9`.
+    pub unicodes: String,
+    /// Pages (0-based) using the face.
+    pub pages: Vec<usize>,
+}
+
+/// A face in one of the project's font files.
+#[derive(Serialize, Debug, Clone)]
+pub struct ProjectFontFile {
+    /// Project-relative path, eg. `/fonts/Libertinus-Bold.otf`.
+    pub path: PathBuf,
+    #[serde(flatten)]
+    pub face: FontFace,
+    pub used: bool,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct FontReport {
+    pub fonts: Vec<FontUsage>,
+    pub project_fonts: Vec<ProjectFontFile>,
+}
+
+#[derive(Default)]
+struct FaceUsage {
+    font: Option<Font>,
+    chars: BTreeSet<char>,
+    glyphs: BTreeSet<u16>,
+    pages: BTreeSet<usize>,
+}
+
+fn collect_usage(frame: &Frame, page: usize, usage: &mut BTreeMap<FontFace, FaceUsage>) {
+    for (_, item) in frame.items() {
+        match item {
+            FrameItem::Group(group) => collect_usage(&group.frame, page, usage),
+            FrameItem::Text(text) => {
+                let face = usage.entry(FontFace::new(text.font.info())).or_default();
+                face.font.get_or_insert_with(|| text.font.clone());
+                face.chars
+                    .extend(text.text.chars().filter(|c| !c.is_control()));
+                face.glyphs.extend(text.glyphs.iter().map(|glyph| glyph.id));
+                face.pages.insert(page);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Merges characters into inclusive ranges of consecutive code points.
+fn unicode_ranges(chars: &BTreeSet<char>) -> Vec<(u32, u32)> {
+    let mut ranges: Vec<(u32, u32)> = vec![];
+    for c in chars.iter().map(|&c| c as u32) {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == c => *end = c,
+            _ => ranges.push((c, c)),
+        }
+    }
+    ranges
+}
+
+fn format_unicodes(ranges: &[(u32, u32)]) -> String {
+    ranges
+        .iter()
+        .map(|&(start, end)| {
+            if start == end {
+                format!("U+{:04X}", start)
+            } else {
+                format!("U+{:04X}-{:04X}", start, end)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// The file `font` was loaded from, found among the engine's fonts.
+fn font_path(engine: &TypstEngine, font: &Font) -> Option<PathBuf> {
+    engine
+        .fonts
+        .iter()
+        .find(|slot| {
+            slot.font
+                .get()
+                .is_some_and(|loaded| loaded.as_ref() == Some(font))
+        })
+        .map(|slot| slot.path.clone())
+        .filter(|path| !path.as_os_str().is_empty())
+}
+
+/// Reports the faces and characters the document uses, and which faces of the font
+/// files in the project (given as project-relative paths) it doesn't use.
+pub fn font_report(
+    document: &PagedDocument,
+    engine: &TypstEngine,
+    root: &Path,
+    files: &[String],
+) -> FontReport {
+    let mut usage = BTreeMap::new();
+    for (i, page) in document.pages.iter().enumerate() {
+        collect_usage(&page.frame, i, &mut usage);
+    }
+
+    let mut project_fonts = vec![];
+    for file in files {
+        let relative = Path::new(file);
+        let is_font = relative
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| FONT_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()));
+        if !is_font {
+            continue;
+        }
+        let Ok(data) = std::fs::read(root.join(relative)) else {
+            continue;
+        };
+        for font in Font::iter(Bytes::new(data)) {
+            let face = FontFace::new(font.info());
+            project_fonts.push(ProjectFontFile {
+                path: Path::new("/").join(relative),
+                used: usage.contains_key(&face),
+                face,
+            });
+        }
+    }
+
+    let fonts = usage
+        .into_iter()
+        .map(|(face, usage)| {
+            let ranges = unicode_ranges(&usage.chars);
+            FontUsage {
+                path: usage.font.as_ref().and_then(|font| font_path(engine, font)),
+                characters: usage.chars.len(),
+                glyphs: usage.glyphs.len(),
+                unicodes: format_unicodes(&ranges),
+                ranges,
+                pages: usage.pages.into_iter().collect(),
+                face,
+            }
+        })
+        .collect();
+
+    FontReport {
+        fonts,
+        project_fonts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_unicodes, unicode_ranges};
+
+    #[test]
+    fn test_unicode_ranges() {
+        let chars = "abcé xyz".chars().collect();
+        let ranges = unicode_ranges(&chars);
+        assert_eq!(
+            ranges,
+            vec![(0x20, 0x20), (0x61, 0x63), (0x78, 0x7A), (0xE9, 0xE9)]
+        );
+        assert_eq!(
+            format_unicodes(&ranges),
+            "U+0020,U+0061-0063,U+0078-007A,U+00E9"
+        );
+    }
+}
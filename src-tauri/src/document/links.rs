@@ -0,0 +1,80 @@
+use super::text::transform_rect;
+use super::Rect;
+use serde::Serialize;
+use typst::layout::{Frame, FrameItem, PagedDocument, Position, Transform};
+use typst::model::Destination;
+
+/// Where a link on a page leads.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LinkTarget {
+    Url {
+        url: String,
+    },
+    /// A place in the document: a page index and a point in pt, like the preview's
+    /// jump positions.
+    Position {
+        page: usize,
+        x: f64,
+        y: f64,
+    },
+}
+
+/// The clickable area of a link, in points on its page.
+#[derive(Serialize, Clone, Debug)]
+pub struct PageLink {
+    pub rect: Rect,
+    pub target: LinkTarget,
+}
+
+/// The links of page `page`, with internal links such as references resolved to the
+/// position they lead to. Empty if there is no such page.
+pub fn page_links(document: &PagedDocument, page: usize) -> Vec<PageLink> {
+    let mut links = vec![];
+    if let Some(page) = document.pages.get(page) {
+        collect_links(document, &page.frame, Transform::identity(), &mut links);
+    }
+    links
+}
+
+fn collect_links(
+    document: &PagedDocument,
+    frame: &Frame,
+    ts: Transform,
+    links: &mut Vec<PageLink>,
+) {
+    for (pos, item) in frame.items() {
+        match item {
+            FrameItem::Group(group) => {
+                let ts = ts
+                    .pre_concat(Transform::translate(pos.x, pos.y))
+                    .pre_concat(group.transform);
+                collect_links(document, &group.frame, ts, links);
+            }
+            FrameItem::Link(destination, size) => {
+                let target = match destination {
+                    Destination::Url(url) => LinkTarget::Url {
+                        url: url.as_str().to_string(),
+                    },
+                    Destination::Position(position) => position_target(*position),
+                    Destination::Location(location) => {
+                        position_target(document.introspector.position(*location))
+                    }
+                };
+                links.push(PageLink {
+                    rect: transform_rect(ts, pos.x, pos.y, size.x, size.y),
+                    target,
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+fn position_target(position: Position) -> LinkTarget {
+    LinkTarget::Position {
+        page: position.page.get() - 1,
+        x: position.point.x.to_pt(),
+        y: position.point.y.to_pt(),
+    }
+}
@@ -0,0 +1,21 @@
+mod bookmarks;
+mod budget;
+mod changes;
+mod compare;
+mod fonts;
+mod links;
+mod plain;
+mod submission;
+mod sync;
+mod text;
+
+pub use bookmarks::*;
+pub use budget::*;
+pub use changes::*;
+pub use compare::*;
+pub use fonts::*;
+pub use links::*;
+pub use plain::*;
+pub use submission::*;
+pub use sync::*;
+pub use text::*;
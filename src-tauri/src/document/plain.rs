@@ -0,0 +1,254 @@
+use super::text::page_text_runs;
+use serde::Deserialize;
+use typst::foundations::{Element, Selector, Value};
+use typst::layout::PagedDocument;
+use typst::model::HeadingElem;
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TextFormat {
+    Plain,
+    /// Headings become `#` lines and list items `-` items, nested by their indent.
+    Markdown,
+}
+
+/// A line of text as laid out on a page, in points.
+#[derive(Debug, Clone)]
+struct Line {
+    text: String,
+    x: f64,
+    y: f64,
+    size: f64,
+}
+
+fn page_lines(page: &typst::layout::Page) -> Vec<Line> {
+    let mut lines: Vec<Line> = vec![];
+    let mut right = 0.0;
+    for run in page_text_runs(page) {
+        match lines.last_mut() {
+            Some(line) if (run.bounds.y - line.y).abs() < line.size * 0.5 => {
+                if run.bounds.x - right > run.size * 0.15 {
+                    line.text.push(' ');
+                }
+                line.text.push_str(&run.text);
+            }
+            _ => lines.push(Line {
+                text: run.text.clone(),
+                x: run.bounds.x,
+                y: run.bounds.y,
+                size: run.size,
+            }),
+        }
+        right = run.bounds.x + run.bounds.width;
+    }
+    lines.retain(|line| !line.text.trim().is_empty());
+
+    // Drop page numbers in the header or footer.
+    let is_number = |line: &Line| line.text.trim().chars().all(|c| c.is_ascii_digit());
+    if lines.last().is_some_and(is_number) {
+        lines.pop();
+    }
+    if lines.len() > 1 && lines.first().is_some_and(is_number) {
+        lines.remove(0);
+    }
+    lines
+}
+
+/// Bullets typst and common templates draw for list items.
+const BULLETS: [char; 6] = ['•', '‣', '–', '◦', '▪', '-'];
+
+/// Whether `text` numbers a list item: up to three digits, a letter or a lowercase roman
+/// numeral, as in `12.`, `b)` or `iv.`.
+fn is_enumerator(text: &str) -> bool {
+    let digits = (1..=3).contains(&text.len()) && text.chars().all(|c| c.is_ascii_digit());
+    let letter = text.len() == 1 && text.chars().all(|c| c.is_ascii_alphabetic());
+    let roman = (1..=4).contains(&text.len()) && text.chars().all(|c| "ivxl".contains(c));
+    digits || letter || roman
+}
+
+/// Splits a list marker such as `•` or `2.` off the start of a line. The marker has to be
+/// followed by a space and a word, so a line starting with eg. `etc.` stays text.
+fn list_marker(text: &str) -> Option<(&str, &str)> {
+    let (marker, body) = text.split_once(' ')?;
+    let body = body.trim_start();
+    if !body.starts_with(char::is_alphanumeric) {
+        return None;
+    }
+    let mut chars = marker.chars();
+    let is_bullet = chars.next().is_some_and(|c| BULLETS.contains(&c)) && chars.next().is_none();
+    let is_number = marker.strip_suffix(['.', ')']).is_some_and(is_enumerator);
+    (is_bullet || is_number).then_some((marker, body))
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    if escaped.starts_with(['#', '>', '+']) {
+        escaped.insert(0, '\\');
+    }
+    escaped
+}
+
+/// Appends a wrapped line to a paragraph, rejoining words hyphenated at the line end.
+fn join_line(paragraph: &mut String, text: &str) {
+    if paragraph.is_empty() {
+        paragraph.push_str(text);
+        return;
+    }
+    let hyphenated = paragraph.ends_with(['-', '\u{2010}', '\u{ad}'])
+        && text.starts_with(|c: char| c.is_lowercase());
+    if hyphenated {
+        paragraph.pop();
+    } else {
+        paragraph.push(' ');
+    }
+    paragraph.push_str(text);
+}
+
+/// A heading of the document: its level, page index, vertical position and text.
+fn headings(document: &PagedDocument) -> Vec<(usize, usize, f64, String)> {
+    let introspector = &document.introspector;
+    introspector
+        .query(&Selector::Elem(Element::of::<HeadingElem>(), None))
+        .iter()
+        .filter_map(|heading| {
+            let level = match heading.get_by_name("level").ok()? {
+                Value::Int(level) => level.max(1) as usize,
+                _ => 1,
+            };
+            let text = match heading.get_by_name("body").ok()? {
+                Value::Content(body) => body.plain_text().trim().to_string(),
+                _ => return None,
+            };
+            let position = introspector.position(heading.location()?);
+            Some((
+                level,
+                position.page.get() - 1,
+                position.point.y.to_pt(),
+                text,
+            ))
+        })
+        .collect()
+}
+
+fn flush(blocks: &mut Vec<String>, paragraph: &mut String) {
+    if !paragraph.is_empty() {
+        blocks.push(std::mem::take(paragraph));
+    }
+}
+
+/// The text of the document as plain text or best-effort Markdown. Paragraphs are
+/// rejoined from their lines, headings are recognized through the document's
+/// introspector, and list nesting is inferred from the indent of the items.
+pub fn document_text(document: &PagedDocument, format: TextFormat) -> String {
+    let markdown = format == TextFormat::Markdown;
+    let mut headings = headings(document).into_iter().peekable();
+    let mut blocks: Vec<String> = vec![];
+    let mut paragraph = String::new();
+    let mut last: Option<Line> = None;
+    let mut in_list = false;
+
+    for (index, page) in document.pages.iter().enumerate() {
+        while headings.next_if(|(_, page, ..)| *page < index).is_some() {}
+
+        let lines = page_lines(page);
+        let left = lines.iter().map(|l| l.x).fold(f64::INFINITY, f64::min);
+        for line in lines {
+            let text = line.text.trim();
+            // A larger gap than the usual line spacing starts a new block. Lines at the
+            // top of a page continue the block of the previous page.
+            let gap = match &last {
+                Some(last) if line.y > last.y => line.y - last.y > last.size * 1.9,
+                Some(_) => false,
+                None => true,
+            };
+
+            let heading = headings.next_if(|(_, page, y, heading)| {
+                *page == index && *y <= line.y + line.size && text.ends_with(heading.as_str())
+            });
+            if let Some((level, ..)) = heading {
+                flush(&mut blocks, &mut paragraph);
+                blocks.push(if markdown {
+                    format!("{} {}", "#".repeat(level), escape_markdown(text))
+                } else {
+                    text.to_string()
+                });
+                last = None;
+                in_list = false;
+                continue;
+            }
+
+            if let Some((marker, body)) = list_marker(text) {
+                flush(&mut blocks, &mut paragraph);
+                let item = if markdown {
+                    let depth = ((line.x - left) / (line.size * 1.2)).round().max(0.0) as usize;
+                    let marker = if marker.chars().count() == 1 {
+                        "-"
+                    } else {
+                        marker
+                    };
+                    format!("{}{} {}", "  ".repeat(depth), marker, escape_markdown(body))
+                } else {
+                    text.to_string()
+                };
+                // Consecutive items form one block, so they stay on adjacent lines.
+                match blocks.last_mut() {
+                    Some(list) if in_list && !gap => {
+                        list.push('\n');
+                        list.push_str(&item);
+                    }
+                    _ => blocks.push(item),
+                }
+                in_list = true;
+                last = Some(line);
+                continue;
+            }
+
+            let text = if markdown {
+                escape_markdown(text)
+            } else {
+                text.to_string()
+            };
+            match blocks.last_mut() {
+                // The wrapped continuation of a list item.
+                Some(list) if in_list && !gap && line.x > left + 1.0 => join_line(list, &text),
+                _ => {
+                    if gap || in_list {
+                        flush(&mut blocks, &mut paragraph);
+                    }
+                    in_list = false;
+                    join_line(&mut paragraph, &text);
+                }
+            }
+            last = Some(line);
+        }
+    }
+    flush(&mut blocks, &mut paragraph);
+    blocks.join("\n\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{join_line, list_marker};
+
+    #[test]
+    fn test_lines() {
+        assert_eq!(list_marker("• First item"), Some(("•", "First item")));
+        assert_eq!(list_marker("12. Twelfth"), Some(("12.", "Twelfth")));
+        assert_eq!(list_marker("iv) Fourth"), Some(("iv)", "Fourth")));
+        assert_eq!(list_marker("Plain words"), None);
+        assert_eq!(list_marker("etc. and more"), None);
+        assert_eq!(list_marker("- "), None);
+
+        let mut paragraph = String::new();
+        join_line(&mut paragraph, "A hyphen-");
+        join_line(&mut paragraph, "ated word");
+        join_line(&mut paragraph, "and more.");
+        assert_eq!(paragraph, "A hyphenated word and more.");
+    }
+}
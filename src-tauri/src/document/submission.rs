@@ -0,0 +1,180 @@
+use crate::document::find_text;
+use serde::{Deserialize, Serialize};
+use typst::layout::{Frame, FrameItem, PagedDocument};
+use typst::text::Font;
+
+/// Formatting rules of a journal or conference, checked before submitting.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct SubmissionProfile {
+    pub max_pages: Option<usize>,
+    /// Required paper size, eg. `"a4"` or `"us-letter"`.
+    pub paper: Option<String>,
+    /// Smallest allowed font size in points.
+    pub min_font_size: Option<f64>,
+    /// Author names that must not appear in a double-blind submission.
+    pub anonymous_names: Vec<String>,
+    /// Require every font to allow embedding, as most submission systems do.
+    pub embedded_fonts: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SubmissionCheck {
+    pub rule: &'static str,
+    pub passed: bool,
+    pub message: String,
+    /// Pages (0-based) that violate the rule.
+    pub pages: Vec<usize>,
+}
+
+impl SubmissionCheck {
+    fn new(rule: &'static str, pages: Vec<usize>, message: String) -> Self {
+        Self {
+            rule,
+            passed: pages.is_empty(),
+            message,
+            pages,
+        }
+    }
+}
+
+/// Paper sizes in millimeters.
+const PAPER_SIZES: [(&str, f64, f64); 7] = [
+    ("a3", 297.0, 420.0),
+    ("a4", 210.0, 297.0),
+    ("a5", 148.0, 210.0),
+    ("b5", 176.0, 250.0),
+    ("us-letter", 215.9, 279.4),
+    ("us-legal", 215.9, 355.6),
+    ("us-executive", 184.15, 266.7),
+];
+
+fn collect_text(frame: &Frame, sizes: &mut Vec<f64>, fonts: &mut Vec<Font>) {
+    for (_, item) in frame.items() {
+        match item {
+            FrameItem::Group(group) => collect_text(&group.frame, sizes, fonts),
+            FrameItem::Text(text) => {
+                sizes.push(text.size.to_pt());
+                if !fonts.contains(&text.font) {
+                    fonts.push(text.font.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Evaluates the profile against the compiled document. Only the rules the profile
+/// sets produce a check.
+pub fn check_submission(document: &PagedDocument, profile: &SubmissionProfile) -> Vec<SubmissionCheck> {
+    let mut checks = vec![];
+
+    if let Some(max) = profile.max_pages {
+        let pages = document.pages.len();
+        checks.push(SubmissionCheck::new(
+            "max_pages",
+            (max..pages).collect(),
+            format!("{} of at most {} pages", pages, max),
+        ));
+    }
+
+    if let Some(paper) = &profile.paper {
+        let check = match PAPER_SIZES.iter().find(|(name, ..)| *name == paper.as_str()) {
+            Some(&(_, width, height)) => {
+                let pages = document
+                    .pages
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, page)| {
+                        let size = page.frame.size();
+                        (size.x.to_mm() - width).abs() > 1.0 || (size.y.to_mm() - height).abs() > 1.0
+                    })
+                    .map(|(i, _)| i)
+                    .collect();
+                SubmissionCheck::new("paper", pages, format!("pages must be {}", paper))
+            }
+            None => SubmissionCheck {
+                rule: "paper",
+                passed: false,
+                message: format!("unknown paper size {:?}", paper),
+                pages: vec![],
+            },
+        };
+        checks.push(check);
+    }
+
+    let mut fonts = vec![];
+    if let Some(min) = profile.min_font_size {
+        let mut smallest = f64::INFINITY;
+        let mut pages = vec![];
+        for (i, page) in document.pages.iter().enumerate() {
+            let mut sizes = vec![];
+            collect_text(&page.frame, &mut sizes, &mut fonts);
+            let page_min = sizes.into_iter().fold(f64::INFINITY, f64::min);
+            smallest = smallest.min(page_min);
+            if page_min < min {
+                pages.push(i);
+            }
+        }
+        let message = if pages.is_empty() {
+            format!("all text is at least {}pt", min)
+        } else {
+            format!("text as small as {:.1}pt, at least {}pt required", smallest, min)
+        };
+        checks.push(SubmissionCheck::new("min_font_size", pages, message));
+    }
+
+    if !profile.anonymous_names.is_empty() {
+        let mut pages: Vec<usize> = profile
+            .anonymous_names
+            .iter()
+            .flat_map(|name| find_text(document, name, false))
+            .map(|hit| hit.page)
+            .collect();
+        pages.sort_unstable();
+        pages.dedup();
+
+        let in_metadata = document.info.author.iter().any(|author| {
+            profile
+                .anonymous_names
+                .iter()
+                .any(|name| author.to_lowercase().contains(&name.to_lowercase()))
+        });
+        let message = match (pages.is_empty(), in_metadata) {
+            (true, false) => "no author names found".to_string(),
+            (_, true) => "author names appear in the document metadata".to_string(),
+            (false, false) => "author names appear in the text".to_string(),
+        };
+        checks.push(SubmissionCheck {
+            rule: "anonymity",
+            passed: pages.is_empty() && !in_metadata,
+            message,
+            pages,
+        });
+    }
+
+    if profile.embedded_fonts {
+        if fonts.is_empty() {
+            for page in &document.pages {
+                collect_text(&page.frame, &mut vec![], &mut fonts);
+            }
+        }
+        let restricted: Vec<String> = fonts
+            .iter()
+            .filter(|font| font.ttf().permissions() == Some(ttf_parser::Permissions::Restricted))
+            .map(|font| font.info().family.clone())
+            .collect();
+        checks.push(SubmissionCheck {
+            rule: "embedded_fonts",
+            passed: restricted.is_empty(),
+            message: if restricted.is_empty() {
+                "all fonts can be embedded".to_string()
+            } else {
+                format!("fonts that forbid embedding: {}", restricted.join(", "))
+            },
+            pages: vec![],
+        });
+    }
+
+    checks
+}
@@ -0,0 +1,102 @@
+use super::page_text_runs;
+use super::text::spans_range;
+use serde::Serialize;
+use std::ops::Range;
+use typst::layout::PagedDocument;
+use typst::syntax::FileId;
+use typst::World;
+
+/// Where the text of some lines of a file starts in the document.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SyncAnchor {
+    /// 1-based, inclusive.
+    pub start_line: usize,
+    /// 1-based, inclusive.
+    pub end_line: usize,
+    pub page: usize,
+    /// In points from the top of the page.
+    pub y: f64,
+}
+
+/// Maps the lines of the file `id` to where their text is laid out in `document`, sorted
+/// by line, so editor and preview can scroll together without asking the backend on
+/// every scroll. Lines without text of their own, eg. of a `#show` rule, have no anchor.
+pub fn sync_map(document: &PagedDocument, world: &dyn World, id: FileId) -> Vec<SyncAnchor> {
+    let Ok(source) = world.source(id) else {
+        return vec![];
+    };
+    let lines = source.lines();
+    let mut anchors = vec![];
+    for (index, page) in document.pages.iter().enumerate() {
+        for run in page_text_runs(page) {
+            let Some(range) = spans_range(&run.spans, &source) else {
+                continue;
+            };
+            let (Some(start), Some(end)) = (
+                lines.byte_to_line(range.start),
+                lines.byte_to_line(range.end.saturating_sub(1).max(range.start)),
+            ) else {
+                continue;
+            };
+            anchors.push((start + 1..end + 2, index, run.bounds.y));
+        }
+    }
+    merge_anchors(anchors)
+}
+
+/// Sorts anchors by line and keeps only the first place each line shows up at, as
+/// text such as a running header repeats lines further down the document.
+fn merge_anchors(mut anchors: Vec<(Range<usize>, usize, f64)>) -> Vec<SyncAnchor> {
+    anchors.sort_by(|a, b| {
+        (a.0.start, a.1)
+            .cmp(&(b.0.start, b.1))
+            .then(a.2.total_cmp(&b.2))
+    });
+    let mut merged: Vec<SyncAnchor> = vec![];
+    for (lines, page, y) in anchors {
+        match merged.last_mut() {
+            Some(last) if last.start_line == lines.start => {
+                last.end_line = last.end_line.max(lines.end - 1);
+            }
+            _ => merged.push(SyncAnchor {
+                start_line: lines.start,
+                end_line: lines.end - 1,
+                page,
+                y,
+            }),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_anchors() {
+        let anchors = vec![
+            (3..4, 0, 300.0),
+            (1..3, 1, 10.0),
+            (1..2, 0, 80.0),
+            (1..2, 0, 70.0),
+        ];
+        assert_eq!(
+            merge_anchors(anchors),
+            [
+                SyncAnchor {
+                    start_line: 1,
+                    end_line: 2,
+                    page: 0,
+                    y: 70.0,
+                },
+                SyncAnchor {
+                    start_line: 3,
+                    end_line: 3,
+                    page: 0,
+                    y: 300.0,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,268 @@
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use typst::layout::{Abs, Frame, FrameItem, Page, PagedDocument, Point, Transform};
+use typst::syntax::{Source, Span};
+use typst::World;
+
+/// An axis-aligned rectangle on a page, in points.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub(super) fn union(self, other: Rect) -> Rect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Rect {
+            x,
+            y,
+            width: (self.x + self.width).max(other.x + other.width) - x,
+            height: (self.y + self.height).max(other.y + other.height) - y,
+        }
+    }
+}
+
+/// A run of text as laid out on a page, with the box of each character.
+#[derive(Clone, Debug)]
+pub struct TextRun {
+    pub text: String,
+    /// One box per `char` of `text`.
+    pub boxes: Vec<Rect>,
+    pub bounds: Rect,
+    pub size: f64,
+    /// The source spans of the glyphs, in order and without repeats.
+    pub spans: Vec<Span>,
+}
+
+/// Collects all text runs of a page in layout order, with boxes in page coordinates.
+pub fn page_text_runs(page: &Page) -> Vec<TextRun> {
+    let mut runs = vec![];
+    collect_runs(&page.frame, Transform::identity(), &mut runs);
+    runs
+}
+
+fn collect_runs(frame: &Frame, ts: Transform, runs: &mut Vec<TextRun>) {
+    for (pos, item) in frame.items() {
+        match item {
+            FrameItem::Group(group) => {
+                let ts = ts
+                    .pre_concat(Transform::translate(pos.x, pos.y))
+                    .pre_concat(group.transform);
+                collect_runs(&group.frame, ts, runs);
+            }
+            FrameItem::Text(text) => {
+                let ascent = text.size * 0.8;
+                let descent = text.size * 0.2;
+                let mut boxes = vec![];
+                let mut spans: Vec<Span> = vec![];
+                let mut run = String::new();
+                let mut x = pos.x;
+                for glyph in &text.glyphs {
+                    let width = glyph.x_advance.at(text.size);
+                    let span = glyph.span.0;
+                    if !span.is_detached() && spans.last() != Some(&span) {
+                        spans.push(span);
+                    }
+                    let Some(slice) = text.text.get(glyph.range()) else {
+                        x += width;
+                        continue;
+                    };
+                    let rect = transform_rect(ts, x, pos.y - ascent, width, ascent + descent);
+                    // Ligatures map several characters onto one glyph; split its box evenly.
+                    let count = slice.chars().count().max(1);
+                    for (i, c) in slice.chars().enumerate() {
+                        run.push(c);
+                        boxes.push(Rect {
+                            x: rect.x + rect.width * i as f64 / count as f64,
+                            width: rect.width / count as f64,
+                            ..rect
+                        });
+                    }
+                    x += width;
+                }
+                let Some(bounds) = boxes.iter().copied().reduce(Rect::union) else {
+                    continue;
+                };
+                runs.push(TextRun {
+                    text: run,
+                    boxes,
+                    bounds,
+                    size: text.size.to_pt(),
+                    spans,
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+pub(super) fn transform_rect(ts: Transform, x: Abs, y: Abs, width: Abs, height: Abs) -> Rect {
+    let a = Point::new(x, y).transform(ts);
+    let b = Point::new(x + width, y + height).transform(ts);
+    Rect {
+        x: a.x.min(b.x).to_pt(),
+        y: a.y.min(b.y).to_pt(),
+        width: (a.x - b.x).abs().to_pt(),
+        height: (a.y - b.y).abs().to_pt(),
+    }
+}
+
+/// A run of text as laid out on a page, eg. for selecting and copying text in the
+/// preview. Positions are in points.
+#[derive(Serialize, Clone, Debug)]
+pub struct PositionedText {
+    pub text: String,
+    pub bounds: Rect,
+    /// One box per `char` of `text`.
+    pub boxes: Vec<Rect>,
+    pub size: f64,
+    /// Where the text comes from, if it's from a file of the project.
+    pub source: Option<TextSource>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct TextSource {
+    /// Project path of the file, eg. `/chapters/one.typ`.
+    pub path: String,
+    /// Byte range in the file.
+    pub range: Range<usize>,
+}
+
+/// The text runs of a page in layout order, with the sources they come from, which are
+/// looked up in `world`.
+pub fn positioned_text(page: &Page, world: &dyn World) -> Vec<PositionedText> {
+    page_text_runs(page)
+        .into_iter()
+        .map(|run| PositionedText {
+            source: text_source(&run.spans, world),
+            text: run.text,
+            bounds: run.bounds,
+            boxes: run.boxes,
+            size: run.size,
+        })
+        .collect()
+}
+
+/// The range covering `spans` in the file of the first. Spans in other files, eg. text
+/// passed into a template, are left out.
+fn text_source(spans: &[Span], world: &dyn World) -> Option<TextSource> {
+    let id = spans.first()?.id()?;
+    let source = world.source(id).ok()?;
+    let range = spans_range(spans, &source)?;
+    let path = id.vpath().as_rootless_path().to_string_lossy();
+    Some(TextSource {
+        path: format!("/{}", path),
+        range,
+    })
+}
+
+/// The range of `source` covered by those of `spans` that are in it.
+pub(super) fn spans_range(spans: &[Span], source: &Source) -> Option<Range<usize>> {
+    spans
+        .iter()
+        .filter(|span| span.id() == Some(source.id()))
+        .filter_map(|&span| source.range(span))
+        .reduce(|a, b| a.start.min(b.start)..a.end.max(b.end))
+}
+
+/// The text of a page as one string, with a box per character. Runs on different
+/// lines are separated by a newline and runs on the same line by a space if there is
+/// a visible gap between them.
+pub struct PageText {
+    pub text: Vec<char>,
+    pub boxes: Vec<Option<Rect>>,
+}
+
+pub fn page_text(page: &Page) -> PageText {
+    let mut text = vec![];
+    let mut boxes = vec![];
+    let mut last: Option<Rect> = None;
+    for run in page_text_runs(page) {
+        if let Some(last) = last {
+            let same_line = (run.bounds.y - last.y).abs() < last.height * 0.5;
+            if !same_line {
+                text.push('\n');
+                boxes.push(None);
+            } else if run.bounds.x - (last.x + last.width) > run.size * 0.15 {
+                text.push(' ');
+                boxes.push(None);
+            }
+        }
+        last = run.boxes.last().copied();
+        text.extend(run.text.chars());
+        boxes.extend(run.boxes.into_iter().map(Some));
+    }
+    PageText { text, boxes }
+}
+
+/// Counts the words of the rendered document, as a word processor would.
+pub fn document_word_count(document: &PagedDocument) -> usize {
+    document
+        .pages
+        .iter()
+        .map(|page| {
+            let text: String = page_text(page).text.into_iter().collect();
+            text.split_whitespace().count()
+        })
+        .sum()
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct TextHit {
+    pub page: usize,
+    /// One rectangle per line the hit spans.
+    pub rects: Vec<Rect>,
+}
+
+fn normalize(c: char, case_sensitive: bool) -> char {
+    let c = if c.is_whitespace() { ' ' } else { c };
+    if case_sensitive {
+        c
+    } else {
+        c.to_lowercase().next().unwrap_or(c)
+    }
+}
+
+/// Finds all occurrences of `query` in the rendered text of the document.
+/// Whitespace, including line breaks, matches any whitespace.
+pub fn find_text(document: &PagedDocument, query: &str, case_sensitive: bool) -> Vec<TextHit> {
+    let query: Vec<char> = query.chars().map(|c| normalize(c, case_sensitive)).collect();
+    if query.is_empty() {
+        return vec![];
+    }
+
+    let mut hits = vec![];
+    for (index, page) in document.pages.iter().enumerate() {
+        let page_text = page_text(page);
+        let haystack: Vec<char> = page_text
+            .text
+            .iter()
+            .map(|&c| normalize(c, case_sensitive))
+            .collect();
+
+        let mut start = 0;
+        while start + query.len() <= haystack.len() {
+            if haystack[start..start + query.len()] != query[..] {
+                start += 1;
+                continue;
+            }
+
+            let mut rects: Vec<Rect> = vec![];
+            for rect in page_text.boxes[start..start + query.len()].iter().flatten() {
+                match rects.last_mut() {
+                    Some(last) if (rect.y - last.y).abs() < last.height * 0.5 => {
+                        *last = last.union(*rect);
+                    }
+                    _ => rects.push(*rect),
+                }
+            }
+            hits.push(TextHit { page: index, rects });
+            start += query.len();
+        }
+    }
+    hits
+}
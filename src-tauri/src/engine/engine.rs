@@ -1,12 +1,19 @@
 use crate::engine::{FontSearcher, FontSlot};
+use chrono::Datelike;
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use typst::foundations::{Bytes, Datetime, Dict, Str, Value};
 use typst::utils::LazyHash;
-use typst::text::FontBook;
-use typst::{Library, LibraryExt};
+use typst::text::{Font, FontBook};
+use typst::{Feature, Library, LibraryExt};
 
 pub struct TypstEngine {
     pub library: LazyHash<Library>,
     pub fontbook: LazyHash<FontBook>,
     pub fonts: Vec<FontSlot>,
+    /// The library of the latest inputs, see [`Self::library_for_inputs`].
+    input_library: Mutex<Option<(BTreeMap<String, String>, Arc<LazyHash<Library>>)>>,
 }
 
 impl TypstEngine {
@@ -20,6 +27,80 @@ impl TypstEngine {
             library: LazyHash::new(Library::default()),
             fontbook: LazyHash::new(searcher.book),
             fonts: searcher.fonts,
+            input_library: Mutex::new(None),
         }
     }
+
+    /// An engine with only the fonts built into the app, so documents lay out the same on
+    /// every machine.
+    #[cfg(any(test, feature = "bench"))]
+    pub fn embedded() -> Self {
+        let mut searcher = FontSearcher::new();
+        searcher.search_embedded();
+        Self {
+            library: LazyHash::new(Library::default()),
+            fontbook: LazyHash::new(searcher.book),
+            fonts: searcher.fonts,
+            input_library: Mutex::new(None),
+        }
+    }
+
+    /// Builds a standard library whose `sys.inputs` holds the given string values.
+    pub fn library_with_inputs(inputs: &BTreeMap<String, String>) -> Library {
+        Library::builder().with_inputs(Self::inputs_dict(inputs)).build()
+    }
+
+    /// Like [`Self::library_with_inputs`], but reuses the library while the inputs stay
+    /// the same, as they do between most preview compiles. A new library would have to
+    /// be hashed again before memoized results could be reused.
+    pub fn library_for_inputs(&self, inputs: &BTreeMap<String, String>) -> Arc<LazyHash<Library>> {
+        let mut cached = self.input_library.lock().unwrap();
+        match &*cached {
+            Some((cached_inputs, library)) if cached_inputs == inputs => library.clone(),
+            _ => {
+                let library = Arc::new(LazyHash::new(Self::library_with_inputs(inputs)));
+                *cached = Some((inputs.clone(), library.clone()));
+                library
+            }
+        }
+    }
+
+    /// Like [`Self::library_with_inputs`], with the experimental HTML features enabled.
+    pub fn html_library_with_inputs(inputs: &BTreeMap<String, String>) -> Library {
+        Library::builder()
+            .with_inputs(Self::inputs_dict(inputs))
+            .with_features([Feature::Html].into_iter().collect())
+            .build()
+    }
+
+    /// Loads the font with the given index in the font book on first use.
+    pub fn font(&self, id: usize) -> Option<Font> {
+        let slot = self.fonts.get(id)?;
+        slot.font
+            .get_or_init(|| {
+                let data = fs::read(&slot.path).map(Bytes::new).ok()?;
+                Font::new(data, slot.index)
+            })
+            .clone()
+    }
+
+    fn inputs_dict(inputs: &BTreeMap<String, String>) -> Dict {
+        inputs
+            .iter()
+            .map(|(k, v)| (Str::from(k.as_str()), Value::Str(Str::from(v.as_str()))))
+            .collect()
+    }
+}
+
+/// The current date for `datetime.today`, in local time or at a UTC offset in hours.
+pub fn today(offset: Option<i64>) -> Option<Datetime> {
+    let dt = match offset {
+        None => chrono::Local::now().naive_local(),
+        Some(o) => (chrono::Utc::now() + chrono::Duration::try_hours(o)?).naive_utc(),
+    };
+    Datetime::from_ymd(
+        dt.year(),
+        dt.month().try_into().ok()?,
+        dt.day().try_into().ok()?,
+    )
 }
@@ -47,8 +47,7 @@ impl FontSearcher {
     }
 
     /// Add fonts that are embedded in the binary.
-    /// Add fonts that are embedded in the binary.
-    fn search_embedded(&mut self) {
+    pub(crate) fn search_embedded(&mut self) {
         use typst::foundations::Bytes;
 
         log::info!("searching embedded fonts...");
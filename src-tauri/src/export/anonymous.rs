@@ -0,0 +1,122 @@
+use crate::document::page_text;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use typst::layout::PagedDocument;
+
+/// How to turn the document into a double-blind submission.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Hash)]
+#[serde(default)]
+pub struct AnonymizeConfig {
+    /// Extra `sys.inputs` for templates with a blind mode, eg. `{"anonymous": "true"}`.
+    pub inputs: BTreeMap<String, String>,
+    /// Labels whose elements are replaced by `placeholder`, eg. `["author"]`.
+    pub labels: Vec<String>,
+    pub placeholder: String,
+    /// Regexes that must not match anywhere in the exported text.
+    pub forbidden: Vec<String>,
+}
+
+impl Default for AnonymizeConfig {
+    fn default() -> Self {
+        Self {
+            inputs: BTreeMap::from([("anonymous".to_string(), "true".to_string())]),
+            labels: vec!["author".to_string()],
+            placeholder: "Anonymous Author(s)".to_string(),
+            forbidden: vec![
+                r"(?i)\backnowledge?ments?\b".to_string(),
+                r"(?i)\bfunded by\b".to_string(),
+                // Grant IDs are upper case and contain a digit, eg. `EP/K012345/1`, so
+                // "grant proposal" or "grant 5" don't match.
+                r"(?i:\bgrant(\s+(no\.?|number|agreement))?)\s+[A-Z0-9/-]*[0-9][A-Z0-9/-]{2,}"
+                    .to_string(),
+            ],
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Violation {
+    pub pattern: String,
+    pub page: usize,
+    pub text: String,
+}
+
+fn is_label(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
+
+impl AnonymizeConfig {
+    /// Show rules that replace every labelled author block with the placeholder.
+    pub fn prelude(&self) -> String {
+        let placeholder = self.placeholder.replace('\\', "\\\\").replace('"', "\\\"");
+        self.labels
+            .iter()
+            .filter(|label| is_label(label))
+            .map(|label| format!("#show <{}>: \"{}\"\n", label, placeholder))
+            .collect()
+    }
+
+    /// Matches of the forbidden patterns in the document text. Invalid patterns are
+    /// skipped with a warning.
+    pub fn violations(&self, document: &PagedDocument) -> Vec<Violation> {
+        let patterns = self.patterns();
+        let mut violations = vec![];
+        for (i, page) in document.pages.iter().enumerate() {
+            let text: String = page_text(page).text.into_iter().collect();
+            for (pattern, regex) in &patterns {
+                violations.extend(regex.find_iter(&text).map(|m| Violation {
+                    pattern: pattern.to_string(),
+                    page: i,
+                    text: m.as_str().to_string(),
+                }));
+            }
+        }
+        violations
+    }
+
+    fn patterns(&self) -> Vec<(&String, Regex)> {
+        self.forbidden
+            .iter()
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(regex) => Some((pattern, regex)),
+                Err(e) => {
+                    log::warn!("invalid anonymity pattern {:?}: {}", pattern, e);
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(text: &str) -> Vec<String> {
+        AnonymizeConfig::default()
+            .patterns()
+            .iter()
+            .flat_map(|(_, regex)| regex.find_iter(text).map(|m| m.as_str().to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_default_patterns() {
+        assert_eq!(
+            matches("Supported by grant EP/K012345/1."),
+            ["grant EP/K012345/1"]
+        );
+        assert_eq!(matches("Grant No. ABC-2021-77"), ["Grant No. ABC-2021-77"]);
+        assert_eq!(matches("Acknowledgements"), ["Acknowledgements"]);
+        assert_eq!(matches("This work was funded by"), ["funded by"]);
+
+        assert!(matches("We wrote a grant proposal.").is_empty());
+        assert!(matches("The grant application was declined.").is_empty());
+        assert!(matches("Grant TODO").is_empty());
+        assert!(matches("A grant of 5 euros.").is_empty());
+    }
+}
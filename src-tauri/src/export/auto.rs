@@ -0,0 +1,234 @@
+use crate::export::{
+    output_beside, with_extension, write_document, ExportFormat,
+};
+use crate::ipc::events::emit_to_window;
+use crate::ipc::{AutoExportEvent, AutoExportStatus};
+use crate::project::{resolve_project_path, Project};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{Runtime, WebviewWindow};
+
+/// Exports of the document the preview shows, written whenever a save leaves it
+/// compiling, like `typst watch`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Hash)]
+#[serde(default)]
+pub struct AutoExportConfig {
+    pub enabled: bool,
+    /// The files to write, a PDF next to the main file if empty.
+    pub outputs: Vec<AutoExportOutput>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Hash)]
+pub struct AutoExportOutput {
+    pub format: ExportFormat,
+    /// Relative to the project, which it can't leave. Next to the main file if not given.
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+}
+
+impl AutoExportConfig {
+    /// The absolute path of every output, given the main file of a project with `roots`.
+    /// Fails if an output would be written outside the project, as `project.json` comes
+    /// with the project and mustn't overwrite the user's other files.
+    pub fn targets(
+        &self,
+        roots: &[PathBuf],
+        main: &Path,
+    ) -> Result<Vec<(ExportFormat, PathBuf)>, String> {
+        if self.outputs.is_empty() {
+            return Ok(vec![(
+                ExportFormat::Pdf,
+                output_beside(main, ExportFormat::Pdf),
+            )]);
+        }
+        self.outputs
+            .iter()
+            .map(|output| {
+                let path = match &output.path {
+                    Some(path) => {
+                        let path = with_extension(path, output.format);
+                        resolve_project_path(roots, &path)
+                            .ok_or_else(|| format!("{} is outside the project", path.display()))?
+                    }
+                    None => output_beside(main, output.format),
+                };
+                Ok((output.format, path))
+            })
+            .collect()
+    }
+}
+
+/// Runs a project's automatic exports one at a time, once the preview compiled the saved
+/// files. Saves made while an export runs only lead to one more export, of the latest
+/// files.
+#[derive(Default)]
+pub struct AutoExporter {
+    /// The world revision with the latest save, exported once a document compiled from it
+    /// or a later revision.
+    pending: Mutex<Option<u64>>,
+    requested: AtomicU64,
+    running: Mutex<()>,
+    /// The version of the document exported last, which isn't exported again.
+    exported: AtomicU64,
+}
+
+impl AutoExporter {
+    /// Exports `project` after the next successful compile of its files as they are now,
+    /// right away if the preview's document is already up to date. Called after saves,
+    /// once the saved files were applied to the world.
+    pub fn saved<R: Runtime>(&self, window: &WebviewWindow<R>, project: &Arc<Project>) {
+        if !project.config.read().unwrap().auto_export.enabled {
+            return;
+        }
+        let revision = project
+            .world
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .revision();
+        *self.pending.lock().unwrap() = Some(revision);
+        self.compiled(window, project);
+    }
+
+    /// Starts the export waiting for the document the preview just compiled, if any.
+    pub fn compiled<R: Runtime>(&self, window: &WebviewWindow<R>, project: &Arc<Project>) {
+        let version = project.cache.read().unwrap().version;
+        {
+            let mut pending = self.pending.lock().unwrap();
+            if pending.map_or(true, |revision| version < revision) {
+                return;
+            }
+            *pending = None;
+        }
+        self.spawn(window, project);
+    }
+
+    /// Exports `project` in the background if it enabled automatic exports, reporting
+    /// the progress to `window` through `auto_export` events.
+    pub fn spawn<R: Runtime>(&self, window: &WebviewWindow<R>, project: &Arc<Project>) {
+        if !project.config.read().unwrap().auto_export.enabled {
+            return;
+        }
+        let request = self.requested.fetch_add(1, Ordering::SeqCst) + 1;
+        let window = window.clone();
+        let project = project.clone();
+        tokio::task::spawn_blocking(move || {
+            let exporter = &project.auto_exporter;
+            let _running = exporter.running.lock().unwrap_or_else(|e| e.into_inner());
+            if exporter.requested.load(Ordering::SeqCst) != request {
+                return;
+            }
+            let version = project.cache.read().unwrap().version;
+            if version != 0 && exporter.exported.load(Ordering::SeqCst) == version {
+                return;
+            }
+            emit_to_window(
+                &window,
+                "auto_export",
+                AutoExportEvent::new(AutoExportStatus::Running),
+            );
+            let event = match auto_export(&project) {
+                Ok((version, outputs)) => {
+                    exporter.exported.store(version, Ordering::SeqCst);
+                    info!("auto-exported {:?}", outputs);
+                    let outputs = outputs.into_iter().map(|(_, path)| path).collect();
+                    AutoExportEvent {
+                        outputs,
+                        ..AutoExportEvent::new(AutoExportStatus::Exported)
+                    }
+                }
+                Err(e) => {
+                    warn!("auto-export of {:?} failed: {}", project.root, e);
+                    AutoExportEvent {
+                        error: Some(e),
+                        ..AutoExportEvent::new(AutoExportStatus::Failed)
+                    }
+                }
+            };
+            emit_to_window(&window, "auto_export", event);
+        });
+    }
+}
+
+/// The main file of `project` on disk, if one is configured.
+pub fn project_main_path(project: &Project) -> Option<PathBuf> {
+    let main = project.config.read().unwrap().main.clone()?;
+    Some(
+        project
+            .resolve(&main)
+            .unwrap_or_else(|| project.root.join(main.strip_prefix("/").unwrap_or(&main))),
+    )
+}
+
+/// Writes every configured output of the document the preview last compiled, rather
+/// than compiling it once more. Returns the version of the exported document.
+fn auto_export(project: &Project) -> Result<(u64, Vec<(ExportFormat, PathBuf)>), String> {
+    let main = project_main_path(project).ok_or("no main file is configured")?;
+    let roots = project.roots.read().unwrap().clone();
+    let targets = project
+        .config
+        .read()
+        .unwrap()
+        .auto_export
+        .targets(&roots, &main)?;
+    let (document, version) = {
+        let cache = project.cache.read().unwrap();
+        (cache.document.clone(), cache.version)
+    };
+    let document = document.ok_or("the document didn't compile yet")?;
+    let outputs = targets
+        .into_iter()
+        .map(|(format, path)| {
+            let path = write_document(&document, format, &path).map_err(|e| e.to_string())?;
+            Ok((format, path))
+        })
+        .collect::<Result<_, String>>()?;
+    Ok((version, outputs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_targets() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let roots = [root.clone()];
+        let main = root.join("main.typ");
+        let mut config = AutoExportConfig::default();
+        assert_eq!(
+            config.targets(&roots, &main).unwrap(),
+            [(ExportFormat::Pdf, root.join("main.pdf"))]
+        );
+
+        config.outputs = vec![
+            AutoExportOutput {
+                format: ExportFormat::Pdf,
+                path: Some(PathBuf::from("out/thesis")),
+            },
+            AutoExportOutput {
+                format: ExportFormat::Png,
+                path: None,
+            },
+            AutoExportOutput {
+                format: ExportFormat::Svg,
+                path: Some(PathBuf::from("/srv/www/pages.zip")),
+            },
+        ];
+        assert_eq!(
+            config.targets(&roots, &main).unwrap(),
+            [
+                (ExportFormat::Pdf, root.join("out/thesis.pdf")),
+                (ExportFormat::Png, root.join("main.png.zip")),
+                (ExportFormat::Svg, root.join("srv/www/pages.zip")),
+            ]
+        );
+
+        config.outputs[0].path = Some(PathBuf::from("../../home/me/.bashrc"));
+        assert!(config.targets(&roots, &main).is_err());
+    }
+}
@@ -0,0 +1,336 @@
+use super::writer::zip_error;
+use crate::export::extract_data_urls;
+use crate::ipc::commands::{ensure_disk_space, Error, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use siphasher::sip128::{Hasher128, SipHasher};
+use std::collections::BTreeMap;
+use std::fs;
+use std::hash::Hasher;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+/// Book metadata for `export_epub`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Hash)]
+#[serde(default)]
+pub struct EpubConfig {
+    /// Defaults to the first top-level heading.
+    pub title: Option<String>,
+    pub authors: Vec<String>,
+    /// A BCP 47 language tag, eg. `en` or `de-CH`.
+    pub language: Option<String>,
+    /// A unique identifier such as an ISBN or URN. Derived from the title if not set.
+    pub identifier: Option<String>,
+    /// Project paths of font files to embed, with the family name each one provides,
+    /// eg. `{"fonts/Libertinus.otf": "Libertinus Serif"}`.
+    pub fonts: BTreeMap<PathBuf, String>,
+}
+
+/// A chapter of the book: the title of its heading and its XHTML body.
+#[derive(Debug, PartialEq)]
+pub struct Chapter {
+    pub title: String,
+    pub body: String,
+}
+
+static BODY: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<body[^>]*>(.*)</body>").unwrap());
+static HEADING: Lazy<Regex> = Lazy::new(|| Regex::new(r"<h([1-6])[\s>]").unwrap());
+static TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<[^>]*>").unwrap());
+static VOID: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"<(area|base|br|col|embed|hr|img|input|link|meta|source|track|wbr)\b([^>]*?)/?>")
+        .unwrap()
+});
+
+/// Makes HTML well-formed XHTML, as EPUB requires: void elements are closed.
+fn xhtml(html: &str) -> String {
+    VOID.replace_all(html, "<$1$2 />").into_owned()
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The text of the heading `html` starts with, unescaped.
+fn heading_title(html: &str) -> String {
+    let end = html.find("</h").unwrap_or(html.len());
+    TAG.replace_all(&html[..end], "")
+        .trim()
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
+/// Splits the body of an HTML export at its top-level headings. Content before the first
+/// heading becomes a chapter of its own if it has any text.
+pub fn split_chapters(html: &str) -> Vec<Chapter> {
+    let body = BODY
+        .captures(html)
+        .and_then(|caps| caps.get(1))
+        .map_or(html, |m| m.as_str());
+    let Some(level) = HEADING
+        .captures_iter(body)
+        .map(|caps| caps[1].to_string())
+        .min()
+    else {
+        return vec![Chapter {
+            title: String::new(),
+            body: body.trim().to_string(),
+        }];
+    };
+
+    let open = format!("<h{}", level);
+    let starts: Vec<usize> = HEADING
+        .find_iter(body)
+        .filter(|m| m.as_str().starts_with(&open))
+        .map(|m| m.start())
+        .collect();
+
+    let mut chapters = vec![];
+    let front = &body[..starts[0]];
+    if !TAG.replace_all(front, "").trim().is_empty() {
+        chapters.push(Chapter {
+            title: String::new(),
+            body: front.trim().to_string(),
+        });
+    }
+    for (i, &start) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).copied().unwrap_or(body.len());
+        let html = &body[start..end];
+        chapters.push(Chapter {
+            title: heading_title(html),
+            body: html.trim().to_string(),
+        });
+    }
+    chapters
+}
+
+fn chapter_xhtml(title: &str, language: &str, body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <!DOCTYPE html>\n\
+         <html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" xml:lang=\"{lang}\" lang=\"{lang}\">\n\
+         <head>\n<meta charset=\"utf-8\" />\n<title>{title}</title>\n\
+         <link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\" />\n</head>\n\
+         <body>\n{body}\n</body>\n</html>\n",
+        lang = escape_xml(language),
+        title = escape_xml(title),
+        body = body,
+    )
+}
+
+fn media_type(path: &str) -> &'static str {
+    match path.rsplit('.').next().unwrap_or_default() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "otf" => "font/otf",
+        "ttf" => "font/ttf",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
+const CONTAINER: &str = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+<container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">\n\
+<rootfiles>\n\
+<rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\" />\n\
+</rootfiles>\n\
+</container>\n";
+
+const STYLE: &str = "body { font-family: serif; line-height: 1.4; }\n\
+img { max-width: 100%; }\n\
+pre { white-space: pre-wrap; }\n";
+
+/// Builds an EPUB 3 book from the HTML export of a document, with one chapter per
+/// top-level heading. Images embedded in the HTML and the configured fonts, read
+/// relative to `root`, are stored in the book.
+pub fn write_epub(html: &str, config: &EpubConfig, root: &Path, path: &Path) -> Result<PathBuf> {
+    let mut path = path.to_path_buf();
+    if path.extension().is_none() {
+        path.set_extension("epub");
+    }
+
+    let (html, images) = extract_data_urls(html, "images");
+    let chapters = split_chapters(&xhtml(&html));
+    let title = config
+        .title
+        .clone()
+        .or_else(|| {
+            chapters
+                .iter()
+                .map(|c| c.title.clone())
+                .find(|t| !t.is_empty())
+        })
+        .unwrap_or_else(|| "Untitled".to_string());
+    let language = config.language.clone().unwrap_or_else(|| "en".to_string());
+    let identifier = config.identifier.clone().unwrap_or_else(|| {
+        let mut hasher = SipHasher::new();
+        hasher.write(title.as_bytes());
+        format!(
+            "urn:typstudio:{}",
+            hex::encode(hasher.finish128().as_bytes())
+        )
+    });
+
+    let mut fonts = vec![];
+    let mut style = STYLE.to_string();
+    for (i, (font, family)) in config.fonts.iter().enumerate() {
+        let extension = font.extension().and_then(|e| e.to_str()).unwrap_or("otf");
+        let name = format!("fonts/font-{}.{}", i + 1, extension);
+        let relative = font.strip_prefix("/").unwrap_or(font);
+        if relative.components().any(|c| c == Component::ParentDir) {
+            return Err(Error::UnrelatedPath);
+        }
+        let data = fs::read(root.join(relative)).map_err(Into::<Error>::into)?;
+        style.push_str(&format!(
+            "@font-face {{ font-family: \"{}\"; src: url(\"{}\"); }}\n",
+            family.replace('"', ""),
+            name
+        ));
+        fonts.push((name, data));
+    }
+    if let Some(family) = config.fonts.values().next() {
+        style.push_str(&format!(
+            "body {{ font-family: \"{}\", serif; }}\n",
+            family.replace('"', "")
+        ));
+    }
+
+    let mut files: Vec<(String, Vec<u8>)> = vec![];
+    let mut manifest = String::new();
+    let mut spine = String::new();
+    let mut nav = String::new();
+    for (i, chapter) in chapters.iter().enumerate() {
+        let name = format!("chapter-{}.xhtml", i + 1);
+        let chapter_title = if chapter.title.is_empty() {
+            &title
+        } else {
+            &chapter.title
+        };
+        files.push((
+            name.clone(),
+            chapter_xhtml(chapter_title, &language, &chapter.body).into_bytes(),
+        ));
+        manifest.push_str(&format!(
+            "<item id=\"chapter-{}\" href=\"{}\" media-type=\"application/xhtml+xml\" />\n",
+            i + 1,
+            name
+        ));
+        spine.push_str(&format!("<itemref idref=\"chapter-{}\" />\n", i + 1));
+        nav.push_str(&format!(
+            "<li><a href=\"{}\">{}</a></li>\n",
+            name,
+            escape_xml(chapter_title)
+        ));
+    }
+    for (i, (name, _)) in images.iter().chain(&fonts).enumerate() {
+        manifest.push_str(&format!(
+            "<item id=\"resource-{}\" href=\"{}\" media-type=\"{}\" />\n",
+            i + 1,
+            name,
+            media_type(name)
+        ));
+    }
+
+    let nav = chapter_xhtml(
+        &title,
+        &language,
+        &format!(
+            "<nav epub:type=\"toc\" id=\"toc\">\n<ol>\n{}</ol>\n</nav>",
+            nav
+        ),
+    );
+    let authors: String = config
+        .authors
+        .iter()
+        .map(|author| format!("<dc:creator>{}</dc:creator>\n", escape_xml(author)))
+        .collect();
+    let opf = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"book-id\">\n\
+         <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n\
+         <dc:identifier id=\"book-id\">{}</dc:identifier>\n\
+         <dc:title>{}</dc:title>\n\
+         <dc:language>{}</dc:language>\n\
+         {}<meta property=\"dcterms:modified\">{}</meta>\n\
+         </metadata>\n\
+         <manifest>\n\
+         <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\" />\n\
+         <item id=\"style\" href=\"style.css\" media-type=\"text/css\" />\n\
+         {}</manifest>\n\
+         <spine>\n{}</spine>\n\
+         </package>\n",
+        escape_xml(&identifier),
+        escape_xml(&title),
+        escape_xml(&language),
+        authors,
+        chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+        manifest,
+        spine,
+    );
+
+    files.push(("nav.xhtml".to_string(), nav.into_bytes()));
+    files.push(("style.css".to_string(), style.into_bytes()));
+    files.push(("content.opf".to_string(), opf.into_bytes()));
+    files.extend(images);
+    files.extend(fonts);
+    ensure_disk_space(&path, files.iter().map(|(_, data)| data.len() as u64).sum())?;
+
+    let file = fs::File::create(&path).map_err(Into::<Error>::into)?;
+    let mut zip = zip::ZipWriter::new(file);
+    // The mimetype must come first and uncompressed so readers can sniff the format.
+    let stored =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    let deflated = zip::write::FileOptions::default();
+    zip.start_file("mimetype", stored).map_err(zip_error)?;
+    zip.write_all(b"application/epub+zip")
+        .map_err(Into::<Error>::into)?;
+    zip.start_file("META-INF/container.xml", deflated)
+        .map_err(zip_error)?;
+    zip.write_all(CONTAINER.as_bytes())
+        .map_err(Into::<Error>::into)?;
+    for (name, data) in files {
+        zip.start_file(format!("OEBPS/{}", name), deflated)
+            .map_err(zip_error)?;
+        zip.write_all(&data).map_err(Into::<Error>::into)?;
+    }
+    zip.finish().map_err(zip_error)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{split_chapters, xhtml, Chapter};
+
+    #[test]
+    fn test_split_chapters() {
+        let html = "<html><body><p>Preface<br></p><h2>One</h2><p>A</p><h3>Sub</h3>\
+                    <h2 id=\"two\">Two <em>B</em></h2><p>C</p></body></html>";
+        let chapters = split_chapters(&xhtml(html));
+        assert_eq!(
+            chapters,
+            [
+                Chapter {
+                    title: String::new(),
+                    body: "<p>Preface<br /></p>".to_string()
+                },
+                Chapter {
+                    title: "One".to_string(),
+                    body: "<h2>One</h2><p>A</p><h3>Sub</h3>".to_string()
+                },
+                Chapter {
+                    title: "Two B".to_string(),
+                    body: "<h2 id=\"two\">Two <em>B</em></h2><p>C</p>".to_string()
+                },
+            ]
+        );
+    }
+}
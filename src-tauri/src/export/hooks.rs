@@ -0,0 +1,294 @@
+use crate::appdata::is_command_approved;
+use crate::ipc::events::emit_to_window;
+use crate::ipc::{ExportHookFinishedEvent, ExportHookOutputEvent, ExportHookStream};
+use crate::project::Project;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+use tauri::{Runtime, WebviewWindow};
+
+static NEXT_RUN_ID: AtomicU64 = AtomicU64::new(1);
+
+/// The kind of the commands of export hooks, for [`is_command_approved`].
+pub const EXPORT_HOOK_COMMANDS: &str = "export_hook";
+
+/// How long a hook may run before it is killed, eg. an upload hanging on a prompt.
+const HOOK_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// A shell command run in the project root after a successful export, eg.
+/// `scp "$TYPSTUDIO_EXPORT" server:`. The exported file is passed in `TYPSTUDIO_EXPORT`
+/// and its format, such as `pdf` or `epub`, in `TYPSTUDIO_EXPORT_FORMAT`.
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
+pub struct ExportHook {
+    pub command: String,
+    /// Only runs after exports of these formats, or after every export if empty.
+    #[serde(default)]
+    pub formats: Vec<String>,
+}
+
+impl ExportHook {
+    pub fn applies_to(&self, format: &str) -> bool {
+        self.formats.is_empty() || self.formats.iter().any(|f| f == format)
+    }
+
+    /// Whether the user allowed the project at `root` to run this hook's command.
+    pub fn is_approved(&self, root: &Path) -> bool {
+        is_command_approved(root, EXPORT_HOOK_COMMANDS, &self.command)
+    }
+
+    /// Runs the command to completion, passing each line it prints to `on_line` as it
+    /// arrives. The command is killed if it is still running after `timeout`.
+    pub fn run(
+        &self,
+        root: &Path,
+        export: &Path,
+        format: &str,
+        timeout: Duration,
+        on_line: impl FnMut(ExportHookStream, String),
+    ) -> io::Result<ExitStatus> {
+        run_with_timeout(
+            shell(&self.command)
+                .current_dir(root)
+                .env("TYPSTUDIO_EXPORT", export)
+                .env("TYPSTUDIO_EXPORT_FORMAT", format),
+            timeout,
+            on_line,
+        )
+    }
+}
+
+/// Runs `command` to completion, passing each line it prints to `on_line` as it arrives.
+/// The command is killed if it is still running after `timeout`, which fails with
+/// [`io::ErrorKind::TimedOut`].
+pub fn run_with_timeout(
+    command: &mut Command,
+    timeout: Duration,
+    mut on_line: impl FnMut(ExportHookStream, String),
+) -> io::Result<ExitStatus> {
+    let deadline = Instant::now() + timeout;
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let (sender, lines) = mpsc::channel();
+    let readers = [
+        child
+            .stdout
+            .take()
+            .map(|out| forward_lines(out, ExportHookStream::Stdout, &sender)),
+        child
+            .stderr
+            .take()
+            .map(|err| forward_lines(err, ExportHookStream::Stderr, &sender)),
+    ];
+    drop(sender);
+    let timed_out = || {
+        io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("killed after {} seconds", timeout.as_secs()),
+        )
+    };
+    loop {
+        match lines.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok((stream, line)) => on_line(stream, line),
+            Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => {
+                // The readers are left to finish once whatever the command started
+                // closes its output.
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(timed_out());
+            }
+        }
+    }
+    for reader in readers.into_iter().flatten() {
+        let _ = reader.join();
+    }
+    // The command may have closed its output but still be running.
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(timed_out());
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    }
+}
+
+/// Sends the lines of `output` to `sender` from a thread of its own, so stdout and stderr
+/// are both drained while the command runs.
+fn forward_lines<T: Read + Send + 'static>(
+    output: T,
+    stream: ExportHookStream,
+    sender: &mpsc::Sender<(ExportHookStream, String)>,
+) -> std::thread::JoinHandle<()> {
+    let sender = sender.clone();
+    std::thread::spawn(move || {
+        for line in BufReader::new(output).lines() {
+            let Ok(line) = line else { break };
+            if sender.send((stream, line)).is_err() {
+                break;
+            }
+        }
+    })
+}
+
+/// Runs the project's export hooks for the `format` file at `export` in the background,
+/// those whose command the user approved. Their output is streamed to `window` through
+/// `export_hook_output` events, and each ends with an `export_hook_finished` event. Only
+/// explicit exports run hooks, not auto-export.
+pub fn run_export_hooks<R: Runtime>(
+    window: &WebviewWindow<R>,
+    project: &Project,
+    export: &Path,
+    format: &str,
+) {
+    let hooks: Vec<ExportHook> = {
+        let config = project.config.read().unwrap();
+        config
+            .export_hooks
+            .iter()
+            .filter(|hook| hook.applies_to(format) && hook.is_approved(&project.root))
+            .cloned()
+            .collect()
+    };
+    if hooks.is_empty() {
+        return;
+    }
+
+    let window = window.clone();
+    let root = project.root.clone();
+    let export = export.to_path_buf();
+    let format = format.to_string();
+    tokio::task::spawn_blocking(move || {
+        for hook in hooks {
+            let run_id = NEXT_RUN_ID.fetch_add(1, Ordering::Relaxed);
+            info!("running export hook {:?} for {:?}", hook.command, export);
+            let result = hook.run(&root, &export, &format, HOOK_TIMEOUT, |stream, line| {
+                emit_to_window(
+                    &window,
+                    "export_hook_output",
+                    ExportHookOutputEvent {
+                        run_id,
+                        command: hook.command.clone(),
+                        stream,
+                        line,
+                    },
+                );
+            });
+            let event = finished_event(run_id, &hook, &export, result);
+            if !event.success {
+                warn!("export hook {:?} failed: {:?}", hook.command, event);
+            }
+            emit_to_window(&window, "export_hook_finished", event);
+        }
+    });
+}
+
+fn finished_event(
+    run_id: u64,
+    hook: &ExportHook,
+    export: &Path,
+    result: io::Result<ExitStatus>,
+) -> ExportHookFinishedEvent {
+    let (success, code, error) = match result {
+        Ok(status) => (status.success(), status.code(), None),
+        Err(e) => (false, None, Some(e.to_string())),
+    };
+    ExportHookFinishedEvent {
+        run_id,
+        command: hook.command.clone(),
+        export: PathBuf::from(export),
+        success,
+        code,
+        error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_applies_to() {
+        let mut hook = ExportHook {
+            command: "true".to_string(),
+            formats: vec![],
+        };
+        assert!(hook.applies_to("epub"));
+        hook.formats = vec!["pdf".to_string()];
+        assert!(hook.applies_to("pdf"));
+        assert!(!hook.applies_to("png"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_streams_output() {
+        let hook = ExportHook {
+            command: "echo \"$TYPSTUDIO_EXPORT_FORMAT\"; echo oops >&2; exit 3".to_string(),
+            formats: vec![],
+        };
+        let mut lines = vec![];
+        let status = hook
+            .run(
+                Path::new("."),
+                Path::new("out.pdf"),
+                "pdf",
+                HOOK_TIMEOUT,
+                |stream, line| lines.push((stream, line)),
+            )
+            .unwrap();
+        assert_eq!(status.code(), Some(3));
+        lines.sort_by_key(|(stream, _)| *stream as u8);
+        assert_eq!(
+            lines,
+            [
+                (ExportHookStream::Stdout, "pdf".to_string()),
+                (ExportHookStream::Stderr, "oops".to_string()),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_kills_after_timeout() {
+        let hook = ExportHook {
+            command: "echo started; sleep 10".to_string(),
+            formats: vec![],
+        };
+        let started = Instant::now();
+        let error = hook
+            .run(
+                Path::new("."),
+                Path::new("out.pdf"),
+                "pdf",
+                Duration::from_millis(200),
+                |_, _| {},
+            )
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,160 @@
+use crate::compiler::InputsWorld;
+use crate::engine::TypstEngine;
+use crate::ipc::commands::{ensure_disk_space, Error, Result};
+use crate::ipc::TypstDiagnosticSeverity;
+use crate::project::Project;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use typst::diag::{Severity, SourceDiagnostic};
+use typst_html::HtmlDocument;
+
+/// A diagnostic of the HTML backend. Unlike preview diagnostics these span the whole
+/// project, since HTML export fails on elements the PDF export handles fine.
+#[derive(Serialize, Debug, Clone)]
+pub struct ExportDiagnostic {
+    pub severity: TypstDiagnosticSeverity,
+    pub message: String,
+    pub hints: Vec<String>,
+    /// The file the diagnostic is in, relative to the project root.
+    pub path: Option<PathBuf>,
+}
+
+impl From<&SourceDiagnostic> for ExportDiagnostic {
+    fn from(diagnostic: &SourceDiagnostic) -> Self {
+        Self {
+            severity: match diagnostic.severity {
+                Severity::Error => TypstDiagnosticSeverity::Error,
+                Severity::Warning => TypstDiagnosticSeverity::Warning,
+            },
+            message: diagnostic.message.to_string(),
+            hints: diagnostic.hints.iter().map(|h| h.to_string()).collect(),
+            path: diagnostic
+                .span
+                .id()
+                .map(|id| id.vpath().as_rootless_path().to_path_buf()),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct HtmlExport {
+    /// The written file, or `None` if the document failed to compile.
+    pub path: Option<PathBuf>,
+    pub diagnostics: Vec<ExportDiagnostic>,
+}
+
+/// Compiles the project's main file with typst's HTML backend and the given `sys.inputs`.
+pub fn compile_html(
+    project: &Project,
+    inputs: &BTreeMap<String, String>,
+) -> (Option<String>, Vec<ExportDiagnostic>) {
+    let mut world = project.world.lock().unwrap_or_else(|e| e.into_inner());
+    if !world.is_main_set() {
+        let config = project.config.read().unwrap();
+        if config.apply_main(project, &mut world).is_err() {
+            return (None, vec![]);
+        }
+    }
+
+    let world = InputsWorld::new(&*world, inputs)
+        .with_library(TypstEngine::html_library_with_inputs(inputs));
+    let compiled = typst::compile::<HtmlDocument>(&world);
+    let mut diagnostics: Vec<_> = compiled.warnings.iter().map(Into::into).collect();
+    let html = compiled
+        .output
+        .and_then(|document| typst_html::html(&document));
+    match html {
+        Ok(html) => (Some(html), diagnostics),
+        Err(errors) => {
+            diagnostics.extend(errors.iter().map(Into::into));
+            (None, diagnostics)
+        }
+    }
+}
+
+static DATA_URL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(src|href)="data:([a-z]+/[a-zA-Z0-9.+-]+);base64,([A-Za-z0-9+/=]+)""#).unwrap()
+});
+
+fn mime_extension(mime: &str) -> &'static str {
+    match mime {
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/svg+xml" => "svg",
+        "application/pdf" => "pdf",
+        _ => "bin",
+    }
+}
+
+/// Moves the data URLs typst embeds images as into separate files under `dir`, a path
+/// relative to the HTML file. Returns the rewritten HTML and the files to write.
+pub fn extract_data_urls(html: &str, dir: &str) -> (String, Vec<(String, Vec<u8>)>) {
+    let mut files = vec![];
+    let html = DATA_URL.replace_all(html, |caps: &Captures| {
+        let Ok(data) = STANDARD.decode(&caps[3]) else {
+            return caps[0].to_string();
+        };
+        let name = format!(
+            "{}/asset-{}.{}",
+            dir,
+            files.len() + 1,
+            mime_extension(&caps[2])
+        );
+        files.push((name.clone(), data));
+        format!("{}=\"{}\"", &caps[1], name)
+    });
+    (html.into_owned(), files)
+}
+
+/// Writes the HTML to `path`. Unless `inline_assets` is set, embedded images are written
+/// to a `<name>_assets` directory next to it.
+pub fn write_html(html: &str, path: &Path, inline_assets: bool) -> Result<PathBuf> {
+    let mut path = path.to_path_buf();
+    if path.extension().is_none() {
+        path.set_extension("html");
+    }
+    let dir = path.parent().ok_or(Error::UnrelatedPath)?;
+
+    let (html, files) = match path.file_stem() {
+        Some(stem) if !inline_assets => {
+            extract_data_urls(html, &format!("{}_assets", stem.to_string_lossy()))
+        }
+        _ => (html.to_string(), vec![]),
+    };
+    let size = html.len() + files.iter().map(|(_, data)| data.len()).sum::<usize>();
+    ensure_disk_space(dir, size as u64)?;
+
+    for (name, data) in files {
+        let file = dir.join(name);
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent).map_err(Into::<Error>::into)?;
+        }
+        fs::write(&file, data).map_err(Into::<Error>::into)?;
+    }
+    fs::write(&path, html).map_err(Into::<Error>::into)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::extract_data_urls;
+
+    #[test]
+    fn test_extract_data_urls() {
+        let html = r#"<img src="data:image/png;base64,iVBORw0KGgo="><img src="logo.png">"#;
+        let (html, files) = extract_data_urls(html, "report_assets");
+        assert_eq!(
+            html,
+            r#"<img src="report_assets/asset-1.png"><img src="logo.png">"#
+        );
+        assert_eq!(files[0].1, b"\x89PNG\r\n\x1a\n");
+    }
+}
@@ -0,0 +1,131 @@
+use crate::compiler::compile_with_inputs;
+use crate::export::{write_document, ExportFormat};
+use crate::ipc::events::emit_to_window;
+use crate::ipc::long_operations::report_long_operation;
+use crate::ipc::{ExportFinishedEvent, ExportProgressEvent};
+use crate::project::Project;
+use log::{info, warn};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tauri::{Runtime, WebviewWindow};
+
+/// One output of an export job: the document compiled with `inputs` and written to `output`.
+#[derive(Clone, Debug)]
+pub struct ExportTask {
+    pub name: String,
+    pub inputs: BTreeMap<String, String>,
+    pub format: ExportFormat,
+    pub output: PathBuf,
+}
+
+/// Runs batches of exports in the background. Each task compiles separately, so a
+/// failing task does not stop the rest of the job.
+#[derive(Default)]
+pub struct ExportJobs {
+    next_id: AtomicU64,
+    /// Whether each running job was cancelled. Finished jobs are removed, and can't be
+    /// cancelled anymore.
+    running: Mutex<HashMap<u64, bool>>,
+}
+
+impl ExportJobs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self, job_id: u64) {
+        if let Some(cancelled) = self.running.lock().unwrap().get_mut(&job_id) {
+            *cancelled = true;
+        }
+    }
+
+    fn is_cancelled(&self, job_id: u64) -> bool {
+        self.running.lock().unwrap().get(&job_id) == Some(&true)
+    }
+
+    /// Starts a job and returns its id. Progress is reported through `export_progress`
+    /// events and completion through `export_finished`.
+    pub fn spawn<R: Runtime>(
+        self: &Arc<Self>,
+        window: WebviewWindow<R>,
+        project: Arc<Project>,
+        tasks: Vec<ExportTask>,
+    ) -> u64 {
+        let job_id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        self.running.lock().unwrap().insert(job_id, false);
+        let jobs = self.clone();
+        tokio::task::spawn_blocking(move || jobs.run(job_id, window, project, tasks));
+        job_id
+    }
+
+    fn run<R: Runtime>(
+        &self,
+        job_id: u64,
+        window: WebviewWindow<R>,
+        project: Arc<Project>,
+        tasks: Vec<ExportTask>,
+    ) {
+        let started = Instant::now();
+        let total = tasks.len();
+        let mut succeeded = 0;
+        let mut failed = 0;
+        let mut cancelled = false;
+
+        for (index, task) in tasks.into_iter().enumerate() {
+            if self.is_cancelled(job_id) {
+                cancelled = true;
+                break;
+            }
+
+            let result = compile_with_inputs(&project, &task.inputs).and_then(|doc| {
+                write_document(&doc, task.format, &task.output).map_err(|e| e.to_string())
+            });
+            let (output, error) = match result {
+                Ok(path) => {
+                    succeeded += 1;
+                    (Some(path), None)
+                }
+                Err(e) => {
+                    warn!("export task {:?} of job {} failed: {}", task.name, job_id, e);
+                    failed += 1;
+                    (None, Some(e))
+                }
+            };
+
+            emit_to_window(
+                &window,
+                "export_progress",
+                ExportProgressEvent {
+                    job_id,
+                    index,
+                    total,
+                    name: task.name,
+                    output,
+                    error,
+                },
+            );
+        }
+
+        self.running.lock().unwrap().remove(&job_id);
+        info!(
+            "export job {} finished: {} succeeded, {} failed",
+            job_id, succeeded, failed
+        );
+        emit_to_window(
+            &window,
+            "export_finished",
+            ExportFinishedEvent {
+                job_id,
+                succeeded,
+                failed,
+                cancelled,
+            },
+        );
+        if !cancelled {
+            report_long_operation(&window, "export", started, failed == 0);
+        }
+    }
+}
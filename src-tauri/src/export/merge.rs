@@ -0,0 +1,186 @@
+use crate::convert::parse_delimited;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// One row of a mail-merge data source, keyed by column name.
+pub type MergeRecord = BTreeMap<String, String>;
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Parses CSV with a header row. Rows shorter than the header leave the missing
+/// columns out, and blank rows are skipped.
+pub fn parse_csv_records(text: &str) -> Vec<MergeRecord> {
+    let text = text.trim_start_matches('\u{feff}');
+    let mut rows = parse_delimited(text, ',').into_iter();
+    let Some(header) = rows.next() else {
+        return vec![];
+    };
+    let header: Vec<String> = header.into_iter().map(|h| h.trim().to_string()).collect();
+    rows.filter(|row| row.iter().any(|cell| !cell.trim().is_empty()))
+        .map(|row| header.iter().cloned().zip(row).collect())
+        .collect()
+}
+
+fn value_text(value: Value) -> String {
+    match value {
+        Value::String(s) => s,
+        Value::Null => String::new(),
+        value => value.to_string(),
+    }
+}
+
+/// Parses a JSON array of flat objects. Nested values are passed on as JSON text.
+pub fn parse_json_records(text: &str) -> io::Result<Vec<MergeRecord>> {
+    let value: Value = serde_json::from_str(text)?;
+    let Value::Array(items) = value else {
+        return Err(invalid_data("expected an array of records"));
+    };
+    items
+        .into_iter()
+        .map(|item| match item {
+            Value::Object(fields) => Ok(fields
+                .into_iter()
+                .map(|(key, value)| (key, value_text(value)))
+                .collect()),
+            _ => Err(invalid_data("expected every record to be an object")),
+        })
+        .collect()
+}
+
+/// Reads the records of a `.csv` or `.json` data source.
+pub fn read_merge_records(path: &Path) -> io::Result<Vec<MergeRecord>> {
+    let text = fs::read_to_string(path)?;
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "json" => parse_json_records(&text),
+        "csv" => Ok(parse_csv_records(&text)),
+        _ => Err(invalid_data("expected a .csv or .json data source")),
+    }
+}
+
+/// Maps a record to `sys.inputs`. `mapping` goes from input name to column name;
+/// without one, every column is passed under its own name.
+pub fn merge_inputs(record: &MergeRecord, mapping: &BTreeMap<String, String>) -> MergeRecord {
+    if mapping.is_empty() {
+        return record.clone();
+    }
+    mapping
+        .iter()
+        .filter_map(|(input, column)| Some((input.clone(), record.get(column)?.clone())))
+        .collect()
+}
+
+fn file_name_part(value: &str) -> String {
+    value
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Fills the `{column}` placeholders of a file name template, eg. `invoice-{number}`.
+/// Without a template, or if it comes out empty, records are numbered from 1 after `stem`.
+pub fn merge_name(
+    template: Option<&str>,
+    record: &MergeRecord,
+    stem: &str,
+    index: usize,
+) -> String {
+    let mut name = String::new();
+    if let Some(template) = template {
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            let Some(end) = rest[start..].find('}') else {
+                break;
+            };
+            name.push_str(&file_name_part(&rest[..start]));
+            let column = &rest[start + 1..start + end];
+            name.push_str(&file_name_part(
+                record.get(column).map_or("", String::as_str),
+            ));
+            rest = &rest[start + end + 1..];
+        }
+        name.push_str(&file_name_part(rest));
+    }
+    let name = name.trim_matches(|c| c == '_' || c == '.').to_string();
+    if name.is_empty() {
+        format!("{}-{}", stem, index + 1)
+    } else {
+        name
+    }
+}
+
+/// Makes `name` unique among `taken` by appending `-2`, `-3`, and so on.
+pub fn unique_name(name: String, taken: &mut HashSet<String>) -> String {
+    let mut unique = name.clone();
+    let mut n = 2;
+    while !taken.insert(unique.clone()) {
+        unique = format!("{}-{}", name, n);
+        n += 1;
+    }
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_records() {
+        let records = parse_csv_records("number,customer\n1001,\"Doe, Jane\"\n\n1002,Acme/Co\n");
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["customer"], "Doe, Jane");
+
+        let mut mapping = BTreeMap::new();
+        mapping.insert("client".to_string(), "customer".to_string());
+        let inputs = merge_inputs(&records[1], &mapping);
+        assert_eq!(
+            inputs.into_iter().collect::<Vec<_>>(),
+            [("client".to_string(), "Acme/Co".to_string())]
+        );
+
+        let mut taken = HashSet::new();
+        let names: Vec<_> = [&records[0], &records[1], &records[1]]
+            .iter()
+            .enumerate()
+            .map(|(i, r)| {
+                unique_name(
+                    merge_name(Some("invoice-{number}-{customer}"), r, "main", i),
+                    &mut taken,
+                )
+            })
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "invoice-1001-Doe__Jane",
+                "invoice-1002-Acme_Co",
+                "invoice-1002-Acme_Co-2"
+            ]
+        );
+        assert_eq!(
+            merge_name(Some("{missing}"), &records[0], "main", 4),
+            "main-5"
+        );
+
+        let records =
+            parse_json_records(r#"[{"name": "Ada", "score": 9.5, "tags": null}]"#).unwrap();
+        assert_eq!(records[0]["score"], "9.5");
+        assert_eq!(records[0]["tags"], "");
+        assert!(parse_json_records(r#"{"name": "Ada"}"#).is_err());
+    }
+}
@@ -0,0 +1,21 @@
+mod anonymous;
+mod auto;
+mod epub;
+mod hooks;
+mod html;
+mod jobs;
+mod merge;
+mod print;
+mod variants;
+mod writer;
+
+pub use anonymous::*;
+pub use auto::*;
+pub use epub::*;
+pub use hooks::*;
+pub use html::*;
+pub use jobs::*;
+pub use merge::*;
+pub use print::*;
+pub use variants::*;
+pub use writer::*;
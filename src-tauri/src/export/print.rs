@@ -0,0 +1,89 @@
+use std::io;
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::process::Command;
+use typst::layout::{PageRange, PageRanges};
+
+/// Parses a print dialog style page selection such as `1-3, 5, 8-`. Pages are 1-based
+/// and open ends extend to the first or last page.
+pub fn parse_page_ranges(text: &str) -> Option<PageRanges> {
+    let page = |text: &str| -> Option<Option<NonZeroUsize>> {
+        match text.trim() {
+            "" => Some(None),
+            text => text.parse().ok().map(Some),
+        }
+    };
+    let ranges = text
+        .split(',')
+        .filter(|part| !part.trim().is_empty())
+        .map(|part| -> Option<PageRange> {
+            match part.split_once('-') {
+                Some((start, end)) => {
+                    let (start, end) = (page(start)?, page(end)?);
+                    if start.zip(end).is_some_and(|(start, end)| start > end) {
+                        return None;
+                    }
+                    Some(start..=end)
+                }
+                None => {
+                    let page = page(part)?;
+                    page.is_some().then_some(page..=page)
+                }
+            }
+        })
+        .collect::<Option<Vec<_>>>()?;
+    (!ranges.is_empty()).then(|| PageRanges::new(ranges))
+}
+
+/// Hands a PDF to the platform's print dialog.
+pub fn open_print_dialog(path: &Path) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        let script = format!(
+            "tell application \"Preview\" to print POSIX file {:?} with print dialog",
+            path.to_string_lossy()
+        );
+        let mut command = Command::new("osascript");
+        command.args(["-e", &script]);
+        command
+    } else if cfg!(target_os = "windows") {
+        let script = format!(
+            "Start-Process -Verb Print -FilePath '{}'",
+            path.to_string_lossy().replace('\'', "''")
+        );
+        let mut command = Command::new("powershell");
+        command.args(["-NoProfile", "-Command", &script]);
+        command
+    } else {
+        // Linux has no common print dialog for files; the PDF viewer provides one.
+        let mut command = Command::new("xdg-open");
+        command.arg(path);
+        command
+    };
+    spawn_detached(&mut command)
+}
+
+/// Starts `command` without waiting for it. It is reaped in the background, so it doesn't
+/// linger as a zombie once it exits.
+pub fn spawn_detached(command: &mut Command) -> io::Result<()> {
+    let mut child = command.spawn()?;
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_page_ranges;
+    use std::num::NonZeroUsize;
+
+    #[test]
+    fn test_parse_page_ranges() {
+        let ranges = parse_page_ranges("2-3, 5, 8-").unwrap();
+        let included: Vec<usize> = (1..=10)
+            .filter(|&page| ranges.includes_page(NonZeroUsize::new(page).unwrap()))
+            .collect();
+        assert_eq!(included, [2, 3, 5, 8, 9, 10]);
+        assert!(parse_page_ranges("3-1").is_none());
+        assert!(parse_page_ranges("a").is_none());
+        assert!(parse_page_ranges("").is_none());
+    }
+}
@@ -0,0 +1,62 @@
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Expands variant axes such as `{lang: [en, de], version: [draft, final]}` into
+/// every combination of values, in a stable order.
+pub fn variant_combinations(axes: &BTreeMap<String, Vec<String>>) -> Vec<BTreeMap<String, String>> {
+    let mut combinations = vec![BTreeMap::new()];
+    for (key, values) in axes {
+        if values.is_empty() {
+            continue;
+        }
+        combinations = combinations
+            .into_iter()
+            .flat_map(|combination| {
+                values.iter().map(move |value| {
+                    let mut combination = combination.clone();
+                    combination.insert(key.clone(), value.clone());
+                    combination
+                })
+            })
+            .collect();
+    }
+    combinations
+}
+
+/// A file name suffix for a combination, eg. `en-final`.
+pub fn variant_suffix(combination: &BTreeMap<String, String>) -> String {
+    combination
+        .values()
+        .map(|v| {
+            v.chars()
+                .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// A seed for a new batch of randomized variants, small enough to read off a file name.
+pub fn random_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64 ^ d.as_secs())
+        .unwrap_or_default()
+        % 1_000_000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variant_combinations() {
+        let mut axes = BTreeMap::new();
+        axes.insert("lang".to_string(), vec!["en".to_string(), "de".to_string()]);
+        axes.insert("version".to_string(), vec!["draft".to_string(), "final".to_string()]);
+
+        let combinations = variant_combinations(&axes);
+        let suffixes: Vec<_> = combinations.iter().map(variant_suffix).collect();
+        assert_eq!(suffixes, ["en-draft", "en-final", "de-draft", "de-final"]);
+    }
+}
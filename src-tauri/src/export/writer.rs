@@ -0,0 +1,166 @@
+use crate::ipc::commands::{ensure_disk_space, Error, Result};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use typst::layout::{PageRanges, PagedDocument};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Pdf,
+    /// A zip archive with one SVG per page.
+    Svg,
+    /// A zip archive with one PNG per page.
+    Png,
+}
+
+impl ExportFormat {
+    /// The name of the format, as in the project config.
+    pub fn name(self) -> &'static str {
+        match self {
+            ExportFormat::Pdf => "pdf",
+            ExportFormat::Svg => "svg",
+            ExportFormat::Png => "png",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Pdf => "pdf",
+            ExportFormat::Svg | ExportFormat::Png => "zip",
+        }
+    }
+}
+
+const PNG_PPI: f32 = 144.0;
+
+pub(super) fn zip_error(e: zip::result::ZipError) -> Error {
+    Error::IO(std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))
+}
+
+/// Appends the format's extension unless `path` already ends in it, so `thesis.v2`
+/// becomes `thesis.v2.pdf` rather than being written without one.
+pub fn with_extension(path: &Path, format: ExportFormat) -> PathBuf {
+    let extension = format.extension();
+    if path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case(extension))
+    {
+        return path.to_path_buf();
+    }
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(extension);
+    PathBuf::from(name)
+}
+
+/// The default export of the file at `source`, next to it: `main.pdf`, or `main.svg.zip`
+/// and `main.png.zip` for `main.typ`.
+pub fn output_beside(source: &Path, format: ExportFormat) -> PathBuf {
+    source.with_extension(match format {
+        ExportFormat::Pdf => "pdf",
+        ExportFormat::Svg => "svg.zip",
+        ExportFormat::Png => "png.zip",
+    })
+}
+
+pub fn write_document(doc: &PagedDocument, format: ExportFormat, path: &Path) -> Result<PathBuf> {
+    match format {
+        ExportFormat::Pdf => write_pdf(doc, path),
+        ExportFormat::Svg => write_svg_zip(doc, path),
+        ExportFormat::Png => write_png_zip(doc, path),
+    }
+}
+
+pub fn write_pdf(doc: &PagedDocument, path: &Path) -> Result<PathBuf> {
+    write_pdf_pages(doc, path, None)
+}
+
+/// Writes only the pages in `page_ranges` if given.
+pub fn write_pdf_pages(
+    doc: &PagedDocument,
+    path: &Path,
+    page_ranges: Option<PageRanges>,
+) -> Result<PathBuf> {
+    let pdf = pdf_bytes(doc, page_ranges)?;
+    let path = with_extension(path, ExportFormat::Pdf);
+    ensure_disk_space(&path, pdf.len() as u64)?;
+    std::fs::write(&path, pdf).map_err(Into::<Error>::into)?;
+    Ok(path)
+}
+
+/// The document as a PDF, only the pages in `page_ranges` if given.
+pub fn pdf_bytes(doc: &PagedDocument, page_ranges: Option<PageRanges>) -> Result<Vec<u8>> {
+    let options = typst_pdf::PdfOptions {
+        page_ranges,
+        ..Default::default()
+    };
+    typst_pdf::pdf(doc, &options).map_err(|_| Error::Unknown)
+}
+
+fn write_zip(path: &Path, files: Vec<(String, Vec<u8>)>) -> Result<()> {
+    ensure_disk_space(path, files.iter().map(|(_, d)| d.len() as u64).sum())?;
+
+    let file = std::fs::File::create(path).map_err(Into::<Error>::into)?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::FileOptions::default()
+        .compression_method(zip::CompressionMethod::Stored);
+
+    for (filename, data) in files {
+        zip.start_file(filename, options).map_err(zip_error)?;
+        zip.write_all(&data).map_err(Into::<Error>::into)?;
+    }
+
+    zip.finish().map_err(zip_error)?;
+    Ok(())
+}
+
+pub fn write_svg_zip(doc: &PagedDocument, path: &Path) -> Result<PathBuf> {
+    let path = with_extension(path, ExportFormat::Svg);
+    let files = doc
+        .pages
+        .iter()
+        .enumerate()
+        .map(|(i, page)| (format!("page_{:02}.svg", i + 1), typst_svg::svg(page).into_bytes()))
+        .collect();
+    write_zip(&path, files)?;
+    Ok(path)
+}
+
+pub fn write_png_zip(doc: &PagedDocument, path: &Path) -> Result<PathBuf> {
+    let path = with_extension(path, ExportFormat::Png);
+    let scale = PNG_PPI / 72.0;
+    let files = doc
+        .pages
+        .par_iter()
+        .enumerate()
+        .filter_map(|(i, page)| {
+            let pixmap = typst_render::render(page, scale);
+            pixmap
+                .encode_png()
+                .ok()
+                .map(|data| (format!("page_{:02}.png", i + 1), data))
+        })
+        .collect();
+    write_zip(&path, files)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_extension() {
+        let pdf = |path: &str| with_extension(Path::new(path), ExportFormat::Pdf);
+        assert_eq!(pdf("out/thesis"), Path::new("out/thesis.pdf"));
+        assert_eq!(pdf("out/thesis.pdf"), Path::new("out/thesis.pdf"));
+        assert_eq!(pdf("out/thesis.PDF"), Path::new("out/thesis.PDF"));
+        assert_eq!(pdf("out/thesis.v2"), Path::new("out/thesis.v2.pdf"));
+        assert_eq!(
+            with_extension(Path::new("pages.svg"), ExportFormat::Svg),
+            Path::new("pages.svg.zip")
+        );
+    }
+}
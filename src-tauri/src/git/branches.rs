@@ -0,0 +1,170 @@
+use crate::git::repository_of;
+use git2::build::CheckoutBuilder;
+use git2::{Branch, BranchType, Repository, StatusOptions};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct GitBranch {
+    pub name: String,
+    /// Whether the branch is checked out.
+    pub current: bool,
+    /// The remote branch it tracks, eg. `origin/main`.
+    pub upstream: Option<String>,
+    /// The summary of its latest commit.
+    pub summary: Option<String>,
+}
+
+fn repository(root: &Path) -> Result<Repository, git2::Error> {
+    repository_of(root)
+        .map(|(repo, _)| repo)
+        .ok_or_else(|| git2::Error::from_str("the project is not in a git repository"))
+}
+
+/// The local branches of the repository containing `root`, by name. Empty outside a
+/// repository.
+pub fn list_branches(root: &Path) -> Result<Vec<GitBranch>, git2::Error> {
+    let Some((repo, _)) = repository_of(root) else {
+        return Ok(vec![]);
+    };
+    let mut branches = repo
+        .branches(Some(BranchType::Local))?
+        .map(|branch| {
+            let (branch, _) = branch?;
+            let upstream = branch
+                .upstream()
+                .ok()
+                .and_then(|upstream| upstream.name().ok().flatten().map(String::from));
+            Ok(GitBranch {
+                name: branch_name(&branch)?,
+                current: branch.is_head(),
+                upstream,
+                summary: branch
+                    .get()
+                    .peel_to_commit()
+                    .ok()
+                    .and_then(|commit| commit.summary().map(String::from)),
+            })
+        })
+        .collect::<Result<Vec<_>, git2::Error>>()?;
+    branches.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(branches)
+}
+
+fn branch_name(branch: &Branch) -> Result<String, git2::Error> {
+    branch
+        .name()?
+        .map(String::from)
+        .ok_or_else(|| git2::Error::from_str("branch name is not valid UTF-8"))
+}
+
+/// The tracked files, relative to the working directory, whose changes a checkout could
+/// overwrite: modified, staged or conflicted ones. Untracked files are left out.
+pub fn uncommitted_changes(root: &Path) -> Result<Vec<PathBuf>, git2::Error> {
+    let repo = repository(root)?;
+    let mut options = StatusOptions::new();
+    options.include_untracked(false).include_ignored(false);
+    let statuses = repo.statuses(Some(&mut options))?;
+    Ok(statuses
+        .iter()
+        .filter_map(|entry| entry.path().map(PathBuf::from))
+        .collect())
+}
+
+/// Checks out the local branch `name`. Refuses to overwrite changed or untracked files,
+/// which callers should check for with [`uncommitted_changes`] first.
+pub fn checkout_branch(root: &Path, name: &str) -> Result<(), git2::Error> {
+    let repo = repository(root)?;
+    let branch = repo.find_branch(name, BranchType::Local)?;
+    let reference = branch.get();
+    let tree = reference.peel_to_tree()?;
+    repo.checkout_tree(tree.as_object(), Some(CheckoutBuilder::new().safe()))?;
+    let reference_name = reference
+        .name()
+        .ok_or_else(|| git2::Error::from_str("branch name is not valid UTF-8"))?;
+    repo.set_head(reference_name)
+}
+
+/// Creates the branch `name` at the current commit and checks it out, which leaves the
+/// files as they are.
+pub fn create_branch(root: &Path, name: &str) -> Result<(), git2::Error> {
+    if !Branch::name_is_valid(name)? {
+        return Err(git2::Error::from_str(&format!(
+            "{:?} is not a valid branch name",
+            name
+        )));
+    }
+    let repo = repository(root)?;
+    let head = repo.head()?.peel_to_commit()?;
+    let branch = repo.branch(name, &head, false)?;
+    let reference_name = branch
+        .get()
+        .name()
+        .ok_or_else(|| git2::Error::from_str("branch name is not valid UTF-8"))?;
+    repo.set_head(reference_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Signature;
+    use std::fs;
+
+    fn commit_all(repo: &Repository, message: &str) {
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_branches() {
+        let dir =
+            std::env::temp_dir().join(format!("typstudio-git-branches-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let repo = Repository::init(&dir).unwrap();
+        let main = dir.join("main.typ");
+        fs::write(&main, "Submitted").unwrap();
+        commit_all(&repo, "Submit");
+        let submitted = repo.head().unwrap().shorthand().unwrap().to_string();
+
+        create_branch(&dir, "revisions").unwrap();
+        assert!(create_branch(&dir, "no..dots").is_err());
+        fs::write(&main, "Revised").unwrap();
+        assert_eq!(
+            uncommitted_changes(&dir).unwrap(),
+            [PathBuf::from("main.typ")]
+        );
+        commit_all(&repo, "Revise");
+        assert!(uncommitted_changes(&dir).unwrap().is_empty());
+
+        let branches = list_branches(&dir).unwrap();
+        let names: Vec<(&str, bool)> = branches
+            .iter()
+            .map(|branch| (branch.name.as_str(), branch.current))
+            .collect();
+        assert!(names.contains(&("revisions", true)));
+        assert!(names.contains(&(submitted.as_str(), false)));
+
+        checkout_branch(&dir, &submitted).unwrap();
+        assert_eq!(fs::read_to_string(&main).unwrap(), "Submitted");
+        fs::write(&main, "Unsaved work").unwrap();
+        assert!(checkout_branch(&dir, "revisions").is_err());
+        assert_eq!(fs::read_to_string(&main).unwrap(), "Unsaved work");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
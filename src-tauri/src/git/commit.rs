@@ -0,0 +1,223 @@
+use crate::git::{repository_of, workdir_path};
+use git2::{ErrorCode, Index, IndexEntry, IndexTime, Oid, Repository, Signature};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The author of commits made for users without a git identity.
+const FALLBACK_NAME: &str = "Typstudio";
+const FALLBACK_EMAIL: &str = "typstudio@localhost";
+
+/// How many file names a generated commit message lists.
+const MESSAGE_FILES: usize = 3;
+
+/// Creates a repository in `root`. Refuses if `root` already is in one, as a nested
+/// repository would hide the project from the outer one.
+pub fn init_repository(root: &Path) -> Result<(), git2::Error> {
+    if let Some((repo, _)) = repository_of(root) {
+        let workdir = repo.workdir().unwrap_or(repo.path());
+        return Err(git2::Error::from_str(&format!(
+            "the project is already in the git repository {}",
+            workdir.display()
+        )));
+    }
+    Repository::init(root)?;
+    Ok(())
+}
+
+/// Commits the current content of `paths`, absolute files of the repository containing
+/// `root`, with a message naming them. Returns `None` outside a repository, and when the
+/// files match the last commit.
+///
+/// The tree is the last commit's with only `paths` changed, built in memory, so changes
+/// the user staged aren't committed along and the repository's index isn't locked.
+pub fn commit_files(root: &Path, paths: &[PathBuf]) -> Result<Option<Oid>, git2::Error> {
+    let Some((repo, _)) = repository_of(root) else {
+        return Ok(None);
+    };
+    let parent = match repo.head() {
+        Ok(head) => Some(head.peel_to_commit()?),
+        Err(e) if e.code() == ErrorCode::UnbornBranch || e.code() == ErrorCode::NotFound => None,
+        Err(e) => return Err(e),
+    };
+
+    let mut index = Index::new()?;
+    if let Some(parent) = &parent {
+        index.read_tree(&parent.tree()?)?;
+    }
+    let mut relatives = vec![];
+    for path in paths {
+        let Some(relative) = workdir_path(&repo, path) else {
+            continue;
+        };
+        match fs::read(path) {
+            Ok(content) => {
+                let mode = index
+                    .get_path(&relative, 0)
+                    .map_or(FILE_MODE, |entry| entry.mode);
+                let blob = repo.blob(&content)?;
+                index.add(&index_entry(&relative, blob, mode, content.len()))?;
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                // Fails for paths the last commit doesn't have either.
+                let _ = index.remove_path(&relative);
+            }
+            Err(e) => return Err(git2::Error::from_str(&e.to_string())),
+        }
+        relatives.push(relative);
+    }
+    let tree_id = index.write_tree_to(&repo)?;
+    let parent_tree = parent.as_ref().map(|parent| parent.tree_id());
+    if relatives.is_empty() || parent_tree == Some(tree_id) {
+        return Ok(None);
+    }
+
+    let tree = repo.find_tree(tree_id)?;
+    let signature = match repo.signature() {
+        Ok(signature) => signature,
+        Err(_) => Signature::now(FALLBACK_NAME, FALLBACK_EMAIL)?,
+    };
+    let parents: Vec<_> = parent.iter().collect();
+    let message = commit_message(&relatives);
+    let oid = repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        &message,
+        &tree,
+        &parents,
+    )?;
+    if let Err(e) = stage_committed(&repo, &index, &relatives) {
+        log::debug!("not staging the committed files: {}", e);
+    }
+    Ok(Some(oid))
+}
+
+/// The mode of regular files, for files new to the repository.
+const FILE_MODE: u32 = 0o100644;
+
+fn index_entry(relative: &Path, id: Oid, mode: u32, size: usize) -> IndexEntry {
+    IndexEntry {
+        ctime: IndexTime::new(0, 0),
+        mtime: IndexTime::new(0, 0),
+        dev: 0,
+        ino: 0,
+        mode,
+        uid: 0,
+        gid: 0,
+        file_size: size as u32,
+        id,
+        flags: 0,
+        flags_extended: 0,
+        path: relative.to_string_lossy().replace('\\', "/").into_bytes(),
+    }
+}
+
+/// Updates the committed files in the repository's index too, so they don't show up as
+/// staged changes. Skipped while another program, eg. git, holds the index.
+fn stage_committed(
+    repo: &Repository,
+    committed: &Index,
+    relatives: &[PathBuf],
+) -> Result<(), git2::Error> {
+    let mut index = repo.index()?;
+    for relative in relatives {
+        match committed.get_path(relative, 0) {
+            Some(entry) => index.add(&entry)?,
+            None => {
+                let _ = index.remove_path(relative);
+            }
+        }
+    }
+    index.write()
+}
+
+/// Eg. `Update main.typ` or `Update main.typ, refs.bib and 2 other files`.
+fn commit_message(paths: &[PathBuf]) -> String {
+    let names: Vec<String> = paths
+        .iter()
+        .map(|path| path.to_string_lossy().replace('\\', "/"))
+        .collect();
+    let listed = match names.len() {
+        0..=MESSAGE_FILES => {
+            let (last, rest) = names.split_last().expect("paths are not empty");
+            if rest.is_empty() {
+                last.clone()
+            } else {
+                format!("{} and {}", rest.join(", "), last)
+            }
+        }
+        n => format!(
+            "{} and {} other files",
+            names[..MESSAGE_FILES - 1].join(", "),
+            n - (MESSAGE_FILES - 1)
+        ),
+    };
+    format!("Update {}", listed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_commit_message() {
+        let paths = |names: &[&str]| names.iter().map(PathBuf::from).collect::<Vec<_>>();
+        assert_eq!(commit_message(&paths(&["main.typ"])), "Update main.typ");
+        assert_eq!(
+            commit_message(&paths(&["main.typ", "refs.bib", "chapters/one.typ"])),
+            "Update main.typ, refs.bib and chapters/one.typ"
+        );
+        assert_eq!(
+            commit_message(&paths(&["a.typ", "b.typ", "c.typ", "d.typ", "e.typ"])),
+            "Update a.typ, b.typ and 3 other files"
+        );
+    }
+
+    #[test]
+    fn test_commit_files() {
+        let dir = std::env::temp_dir().join(format!("typstudio-git-commit-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let main = dir.join("main.typ");
+        fs::write(&main, "= Draft").unwrap();
+        fs::write(dir.join("notes.typ"), "Not saved by the editor").unwrap();
+
+        init_repository(&dir).unwrap();
+        assert!(init_repository(&dir.join("chapters")).is_err());
+        let saved = [main.clone()];
+        let first = commit_files(&dir, &saved).unwrap().unwrap();
+        assert_eq!(commit_files(&dir, &saved).unwrap(), None);
+
+        fs::write(&main, "= Thesis").unwrap();
+        let second = commit_files(&dir, &saved).unwrap().unwrap();
+        let repo = Repository::open(&dir).unwrap();
+        let commit = repo.find_commit(second).unwrap();
+        assert_eq!(commit.summary(), Some("Update main.typ"));
+        assert_eq!(commit.parent_id(0).unwrap(), first);
+        assert!(commit
+            .tree()
+            .unwrap()
+            .get_path(Path::new("notes.typ"))
+            .is_err());
+
+        // Files the user staged aren't committed along.
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("notes.typ")).unwrap();
+        index.write().unwrap();
+        fs::write(&main, "= Thesis, again").unwrap();
+        let third = commit_files(&dir, &saved).unwrap().unwrap();
+        let tree = repo.find_commit(third).unwrap().tree().unwrap();
+        assert!(tree.get_path(Path::new("notes.typ")).is_err());
+        let statuses = repo.statuses(None).unwrap();
+        let status = |name: &str| {
+            statuses
+                .iter()
+                .find(|s| s.path() == Some(name))
+                .map(|s| s.status())
+        };
+        assert_eq!(status("main.typ"), None);
+        assert_eq!(status("notes.typ"), Some(git2::Status::INDEX_NEW));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
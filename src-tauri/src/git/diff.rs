@@ -0,0 +1,97 @@
+use crate::git::{blob_at, repository_of};
+use git2::{DiffHunk, DiffOptions, Patch};
+use serde::Serialize;
+use std::path::Path;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GitHunkKind {
+    Added,
+    Modified,
+    Deleted,
+}
+
+/// A changed range of lines of the working copy, for the markers of the editor gutter.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct GitHunk {
+    pub kind: GitHunkKind,
+    /// 1-based, inclusive lines of the working copy. Lines deleted from HEAD sit below
+    /// `start`, which is 0 for the top of the file, with `end` equal to it.
+    pub start: usize,
+    pub end: usize,
+    /// The lines of HEAD the hunk replaces or deletes.
+    pub removed: usize,
+}
+
+/// The hunks between the file at `path` in HEAD and `content`, its working copy or
+/// unsaved buffer. Empty for a file outside a repository or not in HEAD.
+pub fn file_hunks(path: &Path, content: &str) -> Result<Vec<GitHunk>, git2::Error> {
+    let Some((repo, relative)) = repository_of(path) else {
+        return Ok(vec![]);
+    };
+    let hunks = match blob_at(&repo, "HEAD", &relative)? {
+        Some(blob) => diff_hunks(blob.content(), content.as_bytes())?,
+        None => vec![],
+    };
+    Ok(hunks)
+}
+
+/// The line hunks between two versions of a text, without context lines.
+pub fn diff_hunks(old: &[u8], new: &[u8]) -> Result<Vec<GitHunk>, git2::Error> {
+    let mut options = DiffOptions::new();
+    options.context_lines(0);
+    let patch = Patch::from_buffers(old, None, new, None, Some(&mut options))?;
+    (0..patch.num_hunks())
+        .map(|i| patch.hunk(i).map(|(hunk, _)| to_hunk(&hunk)))
+        .collect()
+}
+
+fn to_hunk(hunk: &DiffHunk) -> GitHunk {
+    let start = hunk.new_start() as usize;
+    let lines = hunk.new_lines() as usize;
+    let removed = hunk.old_lines() as usize;
+    let kind = match (removed, lines) {
+        (0, _) => GitHunkKind::Added,
+        (_, 0) => GitHunkKind::Deleted,
+        _ => GitHunkKind::Modified,
+    };
+    GitHunk {
+        kind,
+        start,
+        end: if lines == 0 { start } else { start + lines - 1 },
+        removed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hunk(kind: GitHunkKind, start: usize, end: usize, removed: usize) -> GitHunk {
+        GitHunk {
+            kind,
+            start,
+            end,
+            removed,
+        }
+    }
+
+    #[test]
+    fn test_diff_hunks() {
+        let old = "one\ntwo\nthree\nfour\nfive\nsix\n";
+        let new = "zero\none\nTWO\nthree\nfive\nsix\nseven\neight\n";
+        assert_eq!(
+            diff_hunks(old.as_bytes(), new.as_bytes()).unwrap(),
+            vec![
+                hunk(GitHunkKind::Added, 1, 1, 0),
+                hunk(GitHunkKind::Modified, 3, 3, 1),
+                hunk(GitHunkKind::Deleted, 4, 4, 1),
+                hunk(GitHunkKind::Added, 7, 8, 0),
+            ]
+        );
+
+        let hunks = diff_hunks(b"one\ntwo\n", b"two\n").unwrap();
+        assert_eq!(hunks, vec![hunk(GitHunkKind::Deleted, 0, 0, 1)]);
+        assert!(diff_hunks(old.as_bytes(), old.as_bytes()).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,161 @@
+use crate::git::{blob_at, repository_of};
+use git2::{Commit, ErrorCode, Oid, Repository, Sort};
+use serde::Serialize;
+use std::path::Path;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GitCommit {
+    pub hash: String,
+    pub short_hash: String,
+    pub author: String,
+    pub email: String,
+    /// Seconds since the Unix epoch.
+    pub date: i64,
+    pub summary: String,
+    pub message: String,
+}
+
+impl GitCommit {
+    fn new(commit: &Commit) -> Self {
+        let hash = commit.id().to_string();
+        let author = commit.author();
+        Self {
+            short_hash: hash[..7].to_string(),
+            hash,
+            author: author.name().unwrap_or_default().to_string(),
+            email: author.email().unwrap_or_default().to_string(),
+            date: commit.time().seconds(),
+            summary: commit.summary().unwrap_or_default().to_string(),
+            message: commit.message().unwrap_or_default().to_string(),
+        }
+    }
+}
+
+/// The latest `limit` commits that changed `path`, a file or folder, newest first. Empty
+/// outside a repository and before the first commit.
+pub fn path_log(path: &Path, limit: usize) -> Result<Vec<GitCommit>, git2::Error> {
+    let Some((repo, relative)) = repository_of(path) else {
+        return Ok(vec![]);
+    };
+    let mut walk = repo.revwalk()?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+    match repo.head() {
+        Err(e) if matches!(e.code(), ErrorCode::UnbornBranch | ErrorCode::NotFound) => {
+            return Ok(vec![]);
+        }
+        result => result?,
+    };
+    walk.push_head()?;
+
+    let mut commits = vec![];
+    for id in walk {
+        if commits.len() >= limit {
+            break;
+        }
+        let commit = repo.find_commit(id?)?;
+        if relative.as_os_str().is_empty() || changes_path(&repo, &commit, &relative)? {
+            commits.push(GitCommit::new(&commit));
+        }
+    }
+    Ok(commits)
+}
+
+/// Whether `commit` changed the entry at `relative` compared to its first parent.
+fn changes_path(repo: &Repository, commit: &Commit, relative: &Path) -> Result<bool, git2::Error> {
+    let entry_id = |commit: &Commit| -> Result<Option<Oid>, git2::Error> {
+        Ok(commit
+            .tree()?
+            .get_path(relative)
+            .ok()
+            .map(|entry| entry.id()))
+    };
+    let id = entry_id(commit)?;
+    let parent_id = match commit.parent_ids().next() {
+        Some(parent) => entry_id(&repo.find_commit(parent)?)?,
+        None => None,
+    };
+    Ok(id != parent_id)
+}
+
+/// The content of the file at `path` in revision `rev`, eg. a commit hash or `HEAD~2`, or
+/// `None` if the file isn't in it.
+pub fn file_at(path: &Path, rev: &str) -> Result<Option<String>, git2::Error> {
+    let Some((repo, relative)) = repository_of(path) else {
+        return Ok(None);
+    };
+    let content = blob_at(&repo, rev, &relative)?
+        .map(|blob| String::from_utf8_lossy(blob.content()).into_owned());
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Signature;
+    use std::fs;
+
+    fn commit(repo: &Repository, message: &str) {
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.update_all(["*"], None).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&Commit> = parent.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_path_log() {
+        let dir = std::env::temp_dir().join(format!("typstudio-git-log-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let repo = Repository::init(&dir).unwrap();
+        let chapter = dir.join("chapter.typ");
+        assert!(path_log(&dir, 10).unwrap().is_empty());
+
+        fs::write(&chapter, "= One").unwrap();
+        commit(&repo, "Add the chapter");
+        fs::write(dir.join("main.typ"), "#include \"chapter.typ\"").unwrap();
+        commit(&repo, "Add main");
+        fs::write(&chapter, "= Two").unwrap();
+        commit(&repo, "Rename the chapter");
+
+        let summaries = |commits: Vec<GitCommit>| -> Vec<String> {
+            commits.into_iter().map(|commit| commit.summary).collect()
+        };
+        assert_eq!(
+            summaries(path_log(&chapter, 10).unwrap()),
+            ["Rename the chapter", "Add the chapter"]
+        );
+        assert_eq!(
+            summaries(path_log(&dir, 2).unwrap()),
+            ["Rename the chapter", "Add main"]
+        );
+
+        let first = path_log(&chapter, 10).unwrap().pop().unwrap();
+        assert_eq!(
+            file_at(&chapter, &first.hash).unwrap().as_deref(),
+            Some("= One")
+        );
+        assert_eq!(file_at(&chapter, "HEAD").unwrap().as_deref(), Some("= Two"));
+        assert_eq!(file_at(&dir.join("main.typ"), "HEAD~2").unwrap(), None);
+
+        fs::remove_file(&chapter).unwrap();
+        assert_eq!(
+            file_at(&chapter, "HEAD~1").unwrap().as_deref(),
+            Some("= One")
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,13 @@
+mod branches;
+mod commit;
+mod diff;
+mod log;
+mod repository;
+mod status;
+
+pub use branches::*;
+pub use commit::*;
+pub use diff::*;
+pub use log::*;
+pub use repository::*;
+pub use status::*;
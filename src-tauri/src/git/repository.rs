@@ -0,0 +1,47 @@
+use git2::{Blob, ErrorCode, Repository};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The repository containing `path`, and the path of `path` in its working directory.
+/// The path may not exist, eg. for a file deleted since it was committed.
+pub fn repository_of(path: &Path) -> Option<(Repository, PathBuf)> {
+    let existing = path.ancestors().find(|ancestor| ancestor.exists())?;
+    let repo = Repository::discover(existing).ok()?;
+    let relative = workdir_path(&repo, path)?;
+    Some((repo, relative))
+}
+
+/// The path of `path` in the working directory of `repo`, or `None` if it is outside.
+pub fn workdir_path(repo: &Repository, path: &Path) -> Option<PathBuf> {
+    let existing = path.ancestors().find(|ancestor| ancestor.exists())?;
+    let workdir = fs::canonicalize(repo.workdir()?).ok()?;
+    let path = fs::canonicalize(existing)
+        .ok()?
+        .join(path.strip_prefix(existing).ok()?);
+    Some(path.strip_prefix(&workdir).ok()?.to_path_buf())
+}
+
+/// The blob of the file at `relative` in revision `rev`, or `None` if the revision or
+/// the file doesn't exist, eg. before the first commit.
+pub fn blob_at<'r>(
+    repo: &'r Repository,
+    rev: &str,
+    relative: &Path,
+) -> Result<Option<Blob<'r>>, git2::Error> {
+    let missing = |e: git2::Error| match e.code() {
+        ErrorCode::NotFound | ErrorCode::UnbornBranch => Ok(None),
+        _ => Err(e),
+    };
+    let tree = match repo
+        .revparse_single(rev)
+        .and_then(|object| object.peel_to_tree())
+    {
+        Ok(tree) => tree,
+        Err(e) => return missing(e),
+    };
+    let entry = match tree.get_path(relative) {
+        Ok(entry) => entry,
+        Err(e) => return missing(e),
+    };
+    Ok(entry.to_object(repo)?.into_blob().ok())
+}
@@ -0,0 +1,155 @@
+use crate::git::repository_of;
+use git2::{Status, StatusOptions};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GitFileStatus {
+    Modified,
+    Added,
+    Deleted,
+    Renamed,
+    Untracked,
+    Ignored,
+    Conflicted,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct GitStatusEntry {
+    /// Relative to the project root.
+    pub path: PathBuf,
+    pub status: GitFileStatus,
+    /// Whether the change is in the index, ready to commit.
+    pub staged: bool,
+}
+
+/// The changed, untracked, ignored and conflicted files under `root`. Empty outside a
+/// repository. Ignored folders are reported as a whole, untracked ones file by file.
+pub fn project_status(root: &Path) -> Result<Vec<GitStatusEntry>, git2::Error> {
+    let Some((repo, prefix)) = repository_of(root) else {
+        return Ok(vec![]);
+    };
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(true)
+        .recurse_ignored_dirs(false)
+        .renames_head_to_index(true);
+    if !prefix.as_os_str().is_empty() {
+        options.pathspec(&prefix);
+    }
+
+    let statuses = repo.statuses(Some(&mut options))?;
+    Ok(statuses
+        .iter()
+        .filter_map(|entry| {
+            let (status, staged) = file_status(entry.status())?;
+            let path = Path::new(std::str::from_utf8(entry.path_bytes()).ok()?);
+            Some(GitStatusEntry {
+                path: path.strip_prefix(&prefix).ok()?.to_path_buf(),
+                status,
+                staged,
+            })
+        })
+        .collect())
+}
+
+/// The badge of a file with the given flags, and whether its change is staged.
+fn file_status(status: Status) -> Option<(GitFileStatus, bool)> {
+    let staged = status.intersects(
+        Status::INDEX_NEW
+            | Status::INDEX_MODIFIED
+            | Status::INDEX_DELETED
+            | Status::INDEX_RENAMED
+            | Status::INDEX_TYPECHANGE,
+    );
+    let file_status = if status.is_conflicted() {
+        GitFileStatus::Conflicted
+    } else if status.is_ignored() {
+        GitFileStatus::Ignored
+    } else if status.is_index_new() {
+        GitFileStatus::Added
+    } else if status.is_wt_new() {
+        GitFileStatus::Untracked
+    } else if status.is_index_deleted() || status.is_wt_deleted() {
+        GitFileStatus::Deleted
+    } else if status.is_index_renamed() || status.is_wt_renamed() {
+        GitFileStatus::Renamed
+    } else if status.intersects(
+        Status::INDEX_MODIFIED
+            | Status::WT_MODIFIED
+            | Status::INDEX_TYPECHANGE
+            | Status::WT_TYPECHANGE,
+    ) {
+        GitFileStatus::Modified
+    } else {
+        return None;
+    };
+    Some((file_status, staged))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::{Repository, Signature};
+    use std::fs;
+
+    #[test]
+    fn test_project_status() {
+        let dir = std::env::temp_dir().join(format!("typstudio-git-status-{}", std::process::id()));
+        let root = dir.join("thesis");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(dir.join("outside.typ"), "").unwrap();
+        fs::write(root.join("main.typ"), "= Thesis").unwrap();
+        fs::write(root.join("staged.typ"), "").unwrap();
+        fs::write(root.join(".gitignore"), "out/\n").unwrap();
+
+        let repo = Repository::init(&dir).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("thesis/main.typ")).unwrap();
+        index.add_path(Path::new("thesis/.gitignore")).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])
+            .unwrap();
+        index.add_path(Path::new("thesis/staged.typ")).unwrap();
+        index.write().unwrap();
+
+        fs::write(root.join("main.typ"), "= Changed").unwrap();
+        fs::create_dir_all(root.join("out")).unwrap();
+        fs::write(root.join("out/main.pdf"), "").unwrap();
+        fs::create_dir_all(root.join("chapters")).unwrap();
+        fs::write(root.join("chapters/new.typ"), "").unwrap();
+
+        let mut status = project_status(&root).unwrap();
+        status.sort_by(|a, b| a.path.cmp(&b.path));
+        let entry = |path: &str, status, staged| GitStatusEntry {
+            path: PathBuf::from(path),
+            status,
+            staged,
+        };
+        assert_eq!(
+            status,
+            vec![
+                entry("chapters/new.typ", GitFileStatus::Untracked, false),
+                entry("main.typ", GitFileStatus::Modified, false),
+                entry("out/", GitFileStatus::Ignored, false),
+                entry("staged.typ", GitFileStatus::Added, true),
+            ]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_project_status_outside_repository() {
+        let dir = std::env::temp_dir().join(format!("typstudio-no-git-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        // The temporary folder itself may be in a repository, eg. in a container.
+        if Repository::discover(&dir).is_err() {
+            assert!(project_status(&dir).unwrap().is_empty());
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,34 @@
+use super::{Error, Result};
+use crate::actions::{apply_action, document_actions, ActionEdit, DocumentAction};
+use crate::project::ProjectManager;
+use std::sync::Arc;
+use tauri::{Runtime, State, WebviewWindow};
+
+/// The document actions for the toolbar: the built-in ones and the project's.
+#[tauri::command]
+pub async fn document_actions_list<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<Vec<DocumentAction>> {
+    let project = project_manager.get_project(&window);
+    Ok(document_actions(project.as_deref()))
+}
+
+/// The edit that action `id` makes to the selection `start..end` of `text`, in
+/// characters.
+#[tauri::command]
+pub async fn document_action_apply<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    id: String,
+    text: String,
+    start: usize,
+    end: usize,
+) -> Result<ActionEdit> {
+    let project = project_manager.get_project(&window);
+    let action = document_actions(project.as_deref())
+        .into_iter()
+        .find(|action| action.id == id)
+        .ok_or(Error::Unknown)?;
+    Ok(apply_action(&action.effect, &text, start, end))
+}
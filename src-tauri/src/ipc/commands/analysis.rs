@@ -0,0 +1,61 @@
+use super::Result;
+use crate::analysis::{
+    lint_source, ContinuationProvider, ContinuationRequest, HeuristicProvider, Lint,
+    ModelProvider,
+};
+use crate::settings::app_settings;
+use std::path::PathBuf;
+use typst::syntax::{FileId, Source, VirtualPath};
+
+/// Runs the source lints on the editor content. Returned ranges are character offsets,
+/// matching `TypstSourceDiagnostic`.
+#[tauri::command]
+pub async fn typst_lint(path: PathBuf, content: String) -> Result<Vec<Lint>> {
+    let id = FileId::new(None, VirtualPath::new(&path));
+    let source = Source::new(id, content);
+    let text = source.text();
+
+    let lints = lint_source(&source)
+        .into_iter()
+        .map(|mut lint| {
+            let start = text[..lint.range.start].chars().count();
+            let size = text[lint.range.clone()].chars().count();
+            lint.range = start..start + size;
+            lint
+        })
+        .collect();
+
+    Ok(lints)
+}
+
+/// Suggests ghost text at the cursor (a character offset). The configured model
+/// endpoint is asked first; the syntax heuristics are the fallback.
+#[tauri::command]
+pub async fn typst_suggest_continuation(
+    path: PathBuf,
+    content: String,
+    offset: usize,
+) -> Result<Option<String>> {
+    let offset = content
+        .char_indices()
+        .nth(offset)
+        .map_or(content.len(), |(i, _)| i);
+    let request = ContinuationRequest {
+        path: path.to_string_lossy().to_string(),
+        source: Source::new(FileId::new(None, VirtualPath::new(&path)), content),
+        offset,
+    };
+
+    let mut providers: Vec<Box<dyn ContinuationProvider>> = vec![];
+    if let Some(endpoint) = app_settings().continuation_endpoint.filter(|e| !e.is_empty()) {
+        providers.push(Box::new(ModelProvider::new(endpoint)));
+    }
+    providers.push(Box::new(HeuristicProvider));
+
+    for provider in &providers {
+        if let Some(text) = provider.suggest(&request).await {
+            return Ok(Some(text));
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,401 @@
+use super::{ensure_disk_space, fs_error, Error, Result};
+use crate::analysis::is_remote_url;
+use crate::convert::{import_latex_tree, pandoc_to_typst, LatexImportReport};
+use crate::ipc::commands::project_path;
+use crate::ipc::events::emit_to_window;
+use crate::ipc::FileDropImportEvent;
+use crate::project::{Project, ProjectManager};
+use log::info;
+use reqwest::header::CONTENT_TYPE;
+use serde::Serialize;
+use siphasher::sip128::{Hasher128, SipHasher};
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{Runtime, State, WebviewWindow};
+
+#[derive(Serialize, Debug)]
+pub struct AssetsMirrorResponse {
+    path: PathBuf,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ImportedAsset {
+    path: PathBuf,
+    /// Markup that loads the asset, ready to paste into a document.
+    snippet: String,
+}
+
+fn image_extension(content_type: Option<&str>, url: &str) -> Option<&'static str> {
+    let mime = content_type
+        .and_then(|c| c.split(';').next())
+        .map(|c| c.trim().to_ascii_lowercase());
+    let from_mime = match mime.as_deref() {
+        Some("image/png") => Some("png"),
+        Some("image/jpeg") | Some("image/jpg") => Some("jpg"),
+        Some("image/gif") => Some("gif"),
+        Some("image/svg+xml") => Some("svg"),
+        Some("image/webp") => Some("webp"),
+        _ => None,
+    };
+    from_mime.or_else(|| {
+        let path = url.split(['?', '#']).next().unwrap_or(url);
+        match Path::new(path)
+            .extension()?
+            .to_str()?
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "png" => Some("png"),
+            "jpg" | "jpeg" => Some("jpg"),
+            "gif" => Some("gif"),
+            "svg" => Some("svg"),
+            "webp" => Some("webp"),
+            _ => None,
+        }
+    })
+}
+
+/// Remote images larger than this aren't mirrored.
+const MAX_MIRROR_SIZE: u64 = 50 * 1024 * 1024;
+
+/// Writes the body of `response` to `path` as it arrives, up to `MAX_MIRROR_SIZE`.
+/// Returns a hash of the body.
+async fn download_to(response: &mut reqwest::Response, path: &Path) -> Result<String> {
+    let mut file = File::create(path).map_err(|e| fs_error(e, path))?;
+    let mut hasher = SipHasher::new();
+    let mut size = 0;
+    while let Some(chunk) = response.chunk().await? {
+        size += chunk.len() as u64;
+        if size > MAX_MIRROR_SIZE {
+            return Err(Error::DownloadTooLarge {
+                limit: MAX_MIRROR_SIZE,
+            });
+        }
+        hasher.write(&chunk);
+        file.write_all(&chunk).map_err(|e| fs_error(e, path))?;
+    }
+    Ok(hex::encode(&hasher.finish128().as_bytes()[..8]))
+}
+
+/// Downloads a remote image into the project's `assets` directory. The file is named
+/// after a hash of its content, so mirroring the same image twice is a no-op.
+#[tauri::command]
+pub async fn assets_mirror_url<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    url: String,
+) -> Result<AssetsMirrorResponse> {
+    let (project, dir) = project_path(&window, &project_manager, PathBuf::from("assets"))?;
+    if !project.config.read().unwrap().allow_network {
+        return Err(Error::NetworkDisabled);
+    }
+    if !is_remote_url(&url) {
+        return Err(Error::UnsupportedFormat);
+    }
+
+    let mut response = reqwest::get(&url)
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(Into::<Error>::into)?;
+    let length = response.content_length();
+    if length.is_some_and(|length| length > MAX_MIRROR_SIZE) {
+        return Err(Error::DownloadTooLarge {
+            limit: MAX_MIRROR_SIZE,
+        });
+    }
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned);
+    let extension = image_extension(content_type.as_deref(), &url).ok_or(Error::UnsupportedFormat)?;
+
+    fs::create_dir_all(&dir).map_err(Into::<Error>::into)?;
+    ensure_disk_space(&dir, length.unwrap_or(MAX_MIRROR_SIZE))?;
+    // The name depends on the content, so the download goes to a temporary file first.
+    let download = dir.join(format!(".{}.tmp", crate::net::random_token()?));
+    let hash = match download_to(&mut response, &download).await {
+        Ok(hash) => hash,
+        Err(e) => {
+            let _ = fs::remove_file(&download);
+            return Err(e);
+        }
+    };
+    let name = format!("{}.{}", hash, extension);
+    let path = dir.join(&name);
+    if path.exists() {
+        let _ = fs::remove_file(&download);
+    } else {
+        fs::rename(&download, &path).map_err(|e| fs_error(e, &path))?;
+    }
+
+    info!("mirrored {} to {:?}", url, path);
+    Ok(AssetsMirrorResponse {
+        path: PathBuf::from(format!("assets/{}", name)),
+    })
+}
+
+/// Image formats typst can't load that we convert to PNG on import.
+const CONVERTIBLE_IMAGES: [&str; 5] = ["bmp", "ico", "tif", "tiff", "dib"];
+
+/// A markup snippet that loads `path` (project-absolute) with the matching function.
+fn asset_snippet(path: &str) -> String {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    match extension.as_str() {
+        "csv" => format!("#let data = csv(\"{}\")", path),
+        "json" => format!("#let data = json(\"{}\")", path),
+        "yaml" | "yml" => format!("#let data = yaml(\"{}\")", path),
+        "toml" => format!("#let data = toml(\"{}\")", path),
+        "xml" => format!("#let data = xml(\"{}\")", path),
+        "bib" => format!("#bibliography(\"{}\")", path),
+        "txt" => format!("#raw(read(\"{}\"))", path),
+        "typ" => format!("#include \"{}\"", path),
+        _ => format!("#image(\"{}\")", path),
+    }
+}
+
+/// Picks a free name in `dir`, appending "-2", "-3", ... to the stem. If a file with
+/// the same name and content already exists, that file is reused.
+fn unique_path(dir: &Path, stem: &str, extension: &str, content: Option<&[u8]>) -> PathBuf {
+    for n in 1.. {
+        let name = match n {
+            1 => format!("{}.{}", stem, extension),
+            n => format!("{}-{}.{}", stem, n, extension),
+        };
+        let path = dir.join(name);
+        if !path.exists() {
+            return path;
+        }
+        if let Some(content) = content {
+            if fs::read(&path).is_ok_and(|existing| existing == content) {
+                return path;
+            }
+        }
+    }
+    unreachable!()
+}
+
+/// A project path with a leading slash and forward slashes, as typst expects.
+fn typst_path(project: &Project, path: &Path) -> Result<String> {
+    let relative = path
+        .strip_prefix(&project.root)
+        .map_err(|_| Error::UnrelatedPath)?
+        .to_string_lossy()
+        .replace('\\', "/");
+    Ok(format!("/{}", relative))
+}
+
+/// Copies `external_path` into the project directory `dir` (absolute). Files already in
+/// the project are referenced in place.
+pub(crate) fn import_asset(
+    project: &Project,
+    external_path: &Path,
+    dir: &Path,
+    convert: bool,
+) -> Result<ImportedAsset> {
+    let stem = external_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or(Error::UnsupportedFormat)?;
+    let extension = external_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+
+    let canonical = fs::canonicalize(external_path).map_err(|e| fs_error(e, external_path))?;
+    let path = if canonical.starts_with(&project.root) {
+        canonical
+    } else {
+        let bytes = fs::read(external_path).map_err(|e| fs_error(e, external_path))?;
+        fs::create_dir_all(dir).map_err(|e| fs_error(e, dir))?;
+        ensure_disk_space(dir, bytes.len() as u64)?;
+
+        if convert && CONVERTIBLE_IMAGES.contains(&extension.as_str()) {
+            let image = image::load_from_memory(&bytes).map_err(|_| Error::UnsupportedFormat)?;
+            let path = unique_path(dir, stem, "png", None);
+            image
+                .save_with_format(&path, image::ImageFormat::Png)
+                .map_err(|_| Error::UnsupportedFormat)?;
+            path
+        } else {
+            let path = unique_path(dir, stem, &extension, Some(&bytes));
+            if !path.exists() {
+                fs::write(&path, &bytes).map_err(|e| fs_error(e, &path))?;
+            }
+            path
+        }
+    };
+
+    let typst_path = typst_path(project, &path)?;
+    info!("imported {:?} as {}", external_path, typst_path);
+    Ok(ImportedAsset {
+        snippet: asset_snippet(&typst_path),
+        path: PathBuf::from(typst_path.trim_start_matches('/')),
+    })
+}
+
+/// Copies a file from anywhere on disk into `target_dir` of the project (`assets` by
+/// default). Images typst can't load are converted to PNG unless `convert` is false.
+#[tauri::command]
+pub async fn project_import_asset<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    external_path: PathBuf,
+    target_dir: Option<PathBuf>,
+    convert: Option<bool>,
+) -> Result<ImportedAsset> {
+    let target_dir = target_dir.unwrap_or_else(|| PathBuf::from("assets"));
+    let (project, dir) = project_path(&window, &project_manager, &target_dir)?;
+    import_asset(&project, &external_path, &dir, convert.unwrap_or(true))
+}
+
+/// Converts a Markdown file from anywhere on disk to Typst and saves it next to the
+/// other sources in `target_dir` of the project (the root by default).
+#[tauri::command]
+pub async fn import_markdown_file<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    external_path: PathBuf,
+    target_dir: Option<PathBuf>,
+) -> Result<ImportedAsset> {
+    let (project, dir) = project_path(&window, &project_manager, target_dir.unwrap_or_default())?;
+    let stem = external_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or(Error::UnsupportedFormat)?;
+    let markdown = fs::read_to_string(&external_path).map_err(|e| fs_error(e, &external_path))?;
+    let typst = crate::convert::markdown_to_typst(&markdown) + "\n";
+
+    fs::create_dir_all(&dir).map_err(|e| fs_error(e, &dir))?;
+    let path = unique_path(&dir, stem, "typ", Some(typst.as_bytes()));
+    ensure_disk_space(&dir, typst.len() as u64)?;
+    fs::write(&path, &typst).map_err(|e| fs_error(e, &path))?;
+
+    let typst_path = typst_path(&project, &path)?;
+    info!("imported {:?} as {}", external_path, typst_path);
+    Ok(ImportedAsset {
+        snippet: asset_snippet(&typst_path),
+        path: PathBuf::from(typst_path.trim_start_matches('/')),
+    })
+}
+
+/// Converts a Word document to Typst with pandoc and saves it in `target_dir` of the
+/// project (the root by default). Its images are extracted next to it, into a directory
+/// named after the converted file.
+#[tauri::command]
+pub async fn import_docx<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    path: PathBuf,
+    target_dir: Option<PathBuf>,
+) -> Result<ImportedAsset> {
+    let (project, dir) = project_path(&window, &project_manager, target_dir.unwrap_or_default())?;
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or(Error::UnsupportedFormat)?;
+    fs::create_dir_all(&dir).map_err(|e| fs_error(e, &dir))?;
+    let output = unique_path(&dir, stem, "typ", None);
+    let media_dir = match output.file_stem() {
+        Some(stem) => PathBuf::from(format!("{}-media", stem.to_string_lossy())),
+        None => return Err(Error::UnsupportedFormat),
+    };
+
+    // pandoc runs in the target directory, so it needs the absolute input path.
+    let input = fs::canonicalize(&path).map_err(|e| fs_error(e, &path))?;
+    let target = output.clone();
+    let run = tokio::task::spawn_blocking(move || pandoc_to_typst(&input, &target, &media_dir))
+        .await
+        .map_err(|_| Error::Unknown)?
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => Error::PandocNotFound,
+            _ => e.into(),
+        })?;
+    if !run.success {
+        log::error!("pandoc failed to convert {:?}: {}", path, run.log);
+        let _ = fs::remove_file(&output);
+        return Err(Error::Conversion(run.log.trim().to_string()));
+    }
+
+    let typst_path = typst_path(&project, &output)?;
+    info!("imported {:?} as {}", path, typst_path);
+    Ok(ImportedAsset {
+        snippet: asset_snippet(&typst_path),
+        path: PathBuf::from(typst_path.trim_start_matches('/')),
+    })
+}
+
+/// Imports the LaTeX project in `dir` into `target_dir` of the project, a directory named
+/// after `dir` by default. Returns what was converted and copied, and what needs manual
+/// attention.
+#[tauri::command]
+pub async fn import_latex_project<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    dir: PathBuf,
+    target_dir: Option<PathBuf>,
+) -> Result<LatexImportReport> {
+    let target_dir = match target_dir {
+        Some(target_dir) => target_dir,
+        None => PathBuf::from(dir.file_name().ok_or(Error::UnsupportedFormat)?),
+    };
+    let (project, target) = project_path(&window, &project_manager, &target_dir)?;
+
+    let report =
+        tokio::task::spawn_blocking(move || import_latex_tree(&dir, &target, &project.root))
+            .await
+            .map_err(|_| Error::Unknown)??;
+    info!(
+        "imported LaTeX project: {} converted, {} copied, {} skipped",
+        report.converted.len(),
+        report.copied.len(),
+        report.skipped.len()
+    );
+    Ok(report)
+}
+
+/// Imports files dropped onto the window: sources and bibliographies go to the project
+/// root, everything else to `assets`. Emits `file_drop_import` with the snippets so the
+/// editor can insert them at the cursor.
+pub fn import_dropped_files<R: Runtime>(
+    window: &WebviewWindow<R>,
+    project_manager: &ProjectManager<R>,
+    paths: &[PathBuf],
+) {
+    let Some(project) = project_manager.get_project(window) else {
+        return;
+    };
+
+    let mut assets = vec![];
+    for path in paths.iter().filter(|p| p.is_file()) {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        let dir = match extension.as_deref() {
+            Some("typ") | Some("bib") => project.root.clone(),
+            _ => project.root.join("assets"),
+        };
+        match import_asset(&project, path, &dir, true) {
+            Ok(asset) => assets.push(asset),
+            Err(e) => log::warn!("unable to import dropped file {:?}: {}", path, e),
+        }
+    }
+
+    if !assets.is_empty() {
+        let snippet = assets
+            .iter()
+            .map(|a| a.snippet.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        emit_to_window(window, "file_drop_import", FileDropImportEvent { assets, snippet });
+    }
+}
@@ -0,0 +1,45 @@
+use super::{project, project_path, Result};
+use crate::ipc::AutosavedEvent;
+use crate::project::{autosave_project, ProjectManager};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::{Runtime, State, WebviewWindow};
+
+/// Reports an editor buffer with unsaved changes, for files that aren't compiled.
+#[tauri::command]
+pub async fn autosave_mark_dirty<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    path: PathBuf,
+    content: String,
+) -> Result<()> {
+    let (project, absolute_path) = project_path(&window, &project_manager, &path)?;
+    project.dirty_buffers.record(absolute_path, content);
+    Ok(())
+}
+
+/// Drops a buffer's unsaved changes, eg. when the editor reverts them.
+#[tauri::command]
+pub async fn autosave_mark_clean<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    path: PathBuf,
+) -> Result<()> {
+    let (project, absolute_path) = project_path(&window, &project_manager, &path)?;
+    project.dirty_buffers.clear(&absolute_path);
+    Ok(())
+}
+
+/// Saves the project's dirty buffers right away.
+#[tauri::command]
+pub async fn autosave_now<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<AutosavedEvent> {
+    let project = project(&window, &project_manager)?;
+    let event = autosave_project(&project);
+    if !event.paths.is_empty() {
+        project.auto_exporter.saved(&window, &project);
+    }
+    Ok(event)
+}
@@ -1,13 +1,18 @@
 use super::Error;
 use super::Result;
-use crate::ipc::commands::project_path;
+use crate::compiler::{capped_tile_scale, tile_bounds, tile_page};
+use crate::document::Rect;
+use crate::ipc::commands::{project, project_path};
 use crate::project::ProjectManager;
-use arboard::Clipboard;
+use arboard::{Clipboard, ImageData};
 use chrono::Local;
 use log::info;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use siphasher::sip128::{Hasher128, SipHasher};
 use std::fs;
+use std::borrow::Cow;
 use std::fs::File;
+use std::hash::Hasher;
 use std::io::BufWriter;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -16,41 +21,177 @@ use tauri::Runtime;
 #[derive(Serialize, Debug)]
 pub struct ClipboardPasteResponse {
     path: PathBuf,
+    /// A figure that shows the pasted image, ready to insert.
+    snippet: String,
 }
 
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PasteNaming {
+    /// Name after the paste time, eg. `2024-05-01 13.37.00.png`.
+    #[default]
+    Date,
+    /// Name after a hash of the pixels, so pasting the same image twice reuses the file.
+    Hash,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct ClipboardPasteOptions {
+    /// Project directory for pasted images, `assets` by default.
+    dir: Option<PathBuf>,
+    naming: PasteNaming,
+    /// Spend more time on PNG compression and drop the alpha channel of opaque images.
+    recompress: bool,
+}
+
+/// Writes the clipboard image into the project as PNG. The clipboard backend decodes
+/// platform formats (TIFF and HEIC on macOS, DIB/BMP on Windows, PNG on Linux) to RGBA.
 #[tauri::command]
 pub async fn clipboard_paste<R: Runtime>(
     window: tauri::WebviewWindow<R>,
     project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
+    options: Option<ClipboardPasteOptions>,
 ) -> Result<ClipboardPasteResponse> {
-    let now = Local::now();
-    let (_, path) = project_path(&window, &project_manager, PathBuf::from("assets"))?;
-
-    let now_format = now.format("%Y-%m-%d %H:%M:%S.png");
-
-    fs::create_dir_all(&path).map_err(Into::<Error>::into)?;
-    let path = path.join(now_format.to_string());
+    let options = options.unwrap_or_default();
+    let dir = options.dir.unwrap_or_else(|| PathBuf::from("assets"));
+    let (_, path) = project_path(&window, &project_manager, &dir)?;
 
     // TODO: Better error handling
     let mut clipboard = Clipboard::new().map_err(|_| Error::Unknown)?;
     let data = clipboard.get_image().map_err(|_| Error::Unknown)?;
 
+    let name = match options.naming {
+        PasteNaming::Date => Local::now().format("%Y-%m-%d %H.%M.%S.png").to_string(),
+        PasteNaming::Hash => {
+            let mut hasher = SipHasher::new();
+            hasher.write(&data.bytes);
+            format!("{}.png", hex::encode(&hasher.finish128().as_bytes()[..8]))
+        }
+    };
+
+    fs::create_dir_all(&path).map_err(Into::<Error>::into)?;
+    let path = path.join(&name);
+
+    let opaque = data.bytes.chunks_exact(4).all(|px| px[3] == u8::MAX);
+    let (color, pixels) = if options.recompress && opaque {
+        let rgb: Vec<u8> = data
+            .bytes
+            .chunks_exact(4)
+            .flat_map(|px| [px[0], px[1], px[2]])
+            .collect();
+        (png::ColorType::Rgb, rgb)
+    } else {
+        (png::ColorType::Rgba, data.bytes.into_owned())
+    };
+
     let file = File::create(&path).map_err(Into::<Error>::into)?;
     let ref mut w = BufWriter::new(file);
     let mut encoder = png::Encoder::new(w, data.width as u32, data.height as u32);
-    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_color(color);
     encoder.set_depth(png::BitDepth::Eight);
+    if options.recompress {
+        encoder.set_compression(png::Compression::Best);
+    }
 
     let mut writer = encoder.write_header().map_err(|_| Error::Unknown)?;
     writer
-        .write_image_data(&*data.bytes)
+        .write_image_data(&pixels)
         .map_err(|_| Error::Unknown)?;
 
     info!(
         "wrote {}x{} image from clipboard to {:?}",
         data.width, data.height, path
     );
+    let relative = dir.join(&name).to_string_lossy().replace('\\', "/");
+    let relative = relative.trim_start_matches('/').to_string();
     Ok(ClipboardPasteResponse {
-        path: PathBuf::from(format!("assets/{}", now_format)),
+        snippet: format!("#figure(image(\"/{}\"))", relative),
+        path: PathBuf::from(relative),
     })
 }
+
+/// Renders a page of the last compiled document at `scale` pixels per point and puts it
+/// on the clipboard as an image. `rect`, in points, copies only that region of the page,
+/// which is all that is rasterized. The scale is lowered if the image would be larger
+/// than [`MAX_TILE_SIDE`](crate::compiler::MAX_TILE_SIDE) on a side.
+#[tauri::command]
+pub async fn copy_page_image<R: Runtime>(
+    window: tauri::WebviewWindow<R>,
+    project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
+    page: usize,
+    scale: f32,
+    rect: Option<Rect>,
+) -> Result<()> {
+    let project = project(&window, &project_manager)?;
+    let pixmap = {
+        let cache = project.cache.read().unwrap();
+        let doc = cache.document.as_ref().ok_or(Error::Unknown)?;
+        let page = doc.pages.get(page).ok_or(Error::Unknown)?;
+        let size = page.frame.size();
+        let (width, height) = (size.x.to_pt(), size.y.to_pt());
+        let rect = rect.unwrap_or(Rect {
+            x: 0.0,
+            y: 0.0,
+            width,
+            height,
+        });
+        let scale = capped_tile_scale(rect.width, rect.height, scale.clamp(0.1, 16.0));
+        let (rect, _, _) = tile_bounds(rect, width, height, scale).ok_or(Error::InvalidRange)?;
+        typst_render::render(&tile_page(page, rect), scale)
+    };
+
+    let (width, height) = (pixmap.width(), pixmap.height());
+    let rgba: Vec<u8> = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+
+    let mut clipboard = Clipboard::new().map_err(|_| Error::Unknown)?;
+    clipboard
+        .set_image(ImageData {
+            width: width as usize,
+            height: height as usize,
+            bytes: Cow::Owned(rgba),
+        })
+        .map_err(|_| Error::Unknown)?;
+    Ok(())
+}
+
+/// Cleans up text pasted from a PDF before it is inserted into the editor.
+#[tauri::command]
+pub async fn clean_pasted_text(text: String) -> String {
+    crate::convert::clean_pasted_text(&text)
+}
+
+/// Converts pasted LaTeX, such as math or a `figure` copied from Overleaf, to Typst markup.
+#[tauri::command]
+pub async fn convert_latex_snippet(text: String) -> String {
+    crate::convert::latex_to_typst(&text)
+}
+
+/// Converts pasted Markdown to Typst markup.
+#[tauri::command]
+pub async fn convert_markdown_snippet(text: String) -> String {
+    crate::convert::markdown_to_typst(&text)
+}
+
+/// Converts tabular clipboard content (an HTML table, or tab-separated text from a
+/// spreadsheet) into `#table(...)` markup. Returns `None` if the clipboard holds no table.
+#[tauri::command]
+pub async fn clipboard_paste_table() -> Result<Option<String>> {
+    use crate::convert::{parse_html_table, parse_tsv, table_markup};
+
+    let mut clipboard = Clipboard::new().map_err(|_| Error::Unknown)?;
+    let table = clipboard
+        .get()
+        .html()
+        .ok()
+        .and_then(|html| parse_html_table(&html))
+        .or_else(|| clipboard.get_text().ok().and_then(|text| parse_tsv(&text)));
+    Ok(table.map(|table| table_markup(&table)))
+}
@@ -0,0 +1,167 @@
+use super::{project, project_path, Error, Result};
+use crate::collab::{CollabEdit, CollabSession, CollabSessionInfo, RemoteChanges};
+use crate::ipc::events::emit_to_window;
+use crate::ipc::CollabRemoteChangeEvent;
+use crate::project::{
+    is_project_internal_path, normalize_project_path, Project, ProjectManager,
+};
+use std::path::Path;
+use std::sync::Arc;
+use tauri::{Runtime, State, WebviewWindow};
+
+/// Puts the files peers changed into the editor's buffers and the world, like edits in
+/// the editor. The session only reports files opened here; files outside the project or
+/// in `.typstudio` are ignored, so a peer can't write the project's config.
+fn remote_changes<R: Runtime>(project: &Arc<Project>, window: &WebviewWindow<R>) -> RemoteChanges {
+    let project = Arc::downgrade(project);
+    let window = window.clone();
+    Box::new(move |changes| {
+        let Some(project) = project.upgrade() else {
+            return;
+        };
+        for (path, content) in changes {
+            if !is_shareable(&path) {
+                log::warn!("ignoring a collaboration change to {}", path);
+                continue;
+            }
+            let Some(absolute) = project.resolve(Path::new(&path)) else {
+                log::warn!(
+                    "ignoring a collaboration change outside the project: {}",
+                    path
+                );
+                continue;
+            };
+            if project
+                .single_file
+                .as_ref()
+                .is_some_and(|file| *file != absolute)
+            {
+                continue;
+            }
+            project.dirty_buffers.record(absolute, content.clone());
+            let _ = project
+                .world
+                .lock()
+                .unwrap()
+                .slot_update(&path, Some(content.clone()));
+            emit_to_window(
+                &window,
+                "collab_remote_change",
+                CollabRemoteChangeEvent { path, content },
+            );
+        }
+    })
+}
+
+/// Whether `path` may be shared in a session: a file of the project outside `.typstudio`.
+fn is_shareable(path: &str) -> bool {
+    normalize_project_path(Path::new(path))
+        .is_some_and(|relative| !is_project_internal_path(&relative))
+}
+
+fn start<R: Runtime>(
+    window: &WebviewWindow<R>,
+    project: &Project,
+    session: Arc<CollabSession>,
+) -> CollabSessionInfo {
+    let info = session.info();
+    if let Some(previous) = project.collab.lock().unwrap().replace(session) {
+        previous.stop();
+    }
+    emit_to_window(window, "collab_session", Some(info.clone()));
+    info
+}
+
+fn session(project: &Project) -> Result<Arc<CollabSession>> {
+    project
+        .collab
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| Error::Collab("no collaboration session".to_string()))
+}
+
+/// Shares the project in a collaboration session others join with the returned link,
+/// on `port` or any free one. A previous session is left.
+#[tauri::command]
+pub async fn collab_host<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    port: Option<u16>,
+) -> Result<CollabSessionInfo> {
+    let project = project(&window, &project_manager)?;
+    let session = CollabSession::host(port, remote_changes(&project, &window)).await?;
+    Ok(start(&window, &project, session))
+}
+
+/// Joins the session at `url`, a link from `collab_host`.
+#[tauri::command]
+pub async fn collab_join<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    url: String,
+) -> Result<CollabSessionInfo> {
+    let project = project(&window, &project_manager)?;
+    let session = CollabSession::join(&url, remote_changes(&project, &window))
+        .await
+        .map_err(Error::Collab)?;
+    Ok(start(&window, &project, session))
+}
+
+#[tauri::command]
+pub async fn collab_leave<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<()> {
+    let project = project(&window, &project_manager)?;
+    if let Some(session) = project.collab.lock().unwrap().take() {
+        session.stop();
+    }
+    emit_to_window(&window, "collab_session", None::<CollabSessionInfo>);
+    Ok(())
+}
+
+/// The session the project is shared in, if any.
+#[tauri::command]
+pub async fn collab_session<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<Option<CollabSessionInfo>> {
+    let project = project(&window, &project_manager)?;
+    let info = project
+        .collab
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|session| session.info());
+    Ok(info)
+}
+
+/// Shares a file opened in the editor with `content`. Returns its content in the
+/// session, which the editor should show: a peer may have shared it first.
+#[tauri::command]
+pub async fn collab_open_file<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    path: String,
+    content: String,
+) -> Result<String> {
+    let (project, _) = project_path(&window, &project_manager, &path)?;
+    if !is_shareable(&path) {
+        return Err(Error::Collab(format!("{} can't be shared", path)));
+    }
+    Ok(session(&project)?.open(&path, &content))
+}
+
+/// Applies edits made in the editor, offsets in UTF-16 code units, and sends them to the
+/// peers. Returns the new content of the file.
+#[tauri::command]
+pub async fn collab_apply<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    path: String,
+    edits: Vec<CollabEdit>,
+) -> Result<String> {
+    let (project, _) = project_path(&window, &project_manager, &path)?;
+    Ok(session(&project)?.apply_local(&path, &edits))
+}
@@ -0,0 +1,104 @@
+use super::{project, project_path, Error, Result};
+use crate::project::{normalize_project_path, Project, ProjectManager, ReviewComment};
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{Runtime, State, WebviewWindow};
+
+/// The text of a project file as the editor has it, unsaved changes included.
+fn current_content(project: &Project, path: &Path) -> Option<String> {
+    let absolute = project.resolve(path)?;
+    project
+        .dirty_buffers
+        .get(&absolute)
+        .or_else(|| fs::read_to_string(&absolute).ok())
+}
+
+/// The review comments of the project, or of the file at `path`, each moved to where its
+/// text is now. Comments whose text is gone are marked `orphaned`.
+#[tauri::command]
+pub async fn comments_list<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    path: Option<PathBuf>,
+) -> Result<Vec<ReviewComment>> {
+    let project = project(&window, &project_manager)?;
+    let path = match path {
+        Some(path) => Some(normalize_project_path(&path).ok_or(Error::UnrelatedPath)?),
+        None => None,
+    };
+    let comments = project.comments.update(|comments, _| {
+        let mut contents: HashMap<PathBuf, Option<String>> = HashMap::new();
+        let mut changed = false;
+        let mut listed = vec![];
+        for comment in comments.iter_mut() {
+            let relative = normalize_project_path(&comment.path).unwrap_or_default();
+            if path.as_ref().is_some_and(|path| *path != relative) {
+                continue;
+            }
+            let content = contents
+                .entry(relative)
+                .or_insert_with_key(|relative| current_content(&project, relative));
+            changed |= match content {
+                Some(content) => comment.reanchor(content),
+                None => !std::mem::replace(&mut comment.orphaned, true),
+            };
+            listed.push(comment.clone());
+        }
+        (listed, changed)
+    })?;
+    Ok(comments)
+}
+
+/// Comments on the character range `range` of the file at `path`.
+#[tauri::command]
+pub async fn comments_add<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    path: PathBuf,
+    range: Range<usize>,
+    author: String,
+    body: String,
+) -> Result<ReviewComment> {
+    let (project, _) = project_path(&window, &project_manager, &path)?;
+    let relative = normalize_project_path(&path).ok_or(Error::UnrelatedPath)?;
+    let content = current_content(&project, &relative).ok_or(Error::UnrelatedPath)?;
+    let comment = project.comments.update(|comments, next_id| {
+        let id = *next_id + 1;
+        let path = Path::new("/").join(&relative);
+        match ReviewComment::new(id, path, &content, range, author, body) {
+            Some(comment) => {
+                *next_id = id;
+                comments.push(comment.clone());
+                (Some(comment), true)
+            }
+            None => (None, false),
+        }
+    })?;
+    comment.ok_or(Error::InvalidRange)
+}
+
+/// Marks a comment as resolved, or open again if `resolved` is false.
+#[tauri::command]
+pub async fn comments_resolve<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    id: u64,
+    resolved: Option<bool>,
+) -> Result<ReviewComment> {
+    let project = project(&window, &project_manager)?;
+    let resolved = resolved.unwrap_or(true);
+    let comment = project.comments.update(|comments, _| {
+        match comments.iter_mut().find(|comment| comment.id == id) {
+            Some(comment) => {
+                let changed = comment.resolved != resolved;
+                comment.resolved = resolved;
+                (Some(comment.clone()), changed)
+            }
+            None => (None, false),
+        }
+    })?;
+    comment.ok_or(Error::Unknown)
+}
@@ -0,0 +1,63 @@
+use super::{Error, Result};
+use crate::compiler::{detached_engine, SnippetWorld};
+use crate::docs::{
+    doc_entry, doc_example_source, list_symbols, search_docs, DocEntry, DocSearchHit, SymbolEntry,
+};
+use crate::project::ProjectManager;
+use std::sync::Arc;
+use tauri::{Runtime, State, WebviewWindow};
+use typst::layout::Abs;
+
+/// Searches the offline documentation of the standard library.
+#[tauri::command]
+pub async fn docs_search(query: String) -> Result<Vec<DocSearchHit>> {
+    tokio::task::spawn_blocking(move || search_docs(&query))
+        .await
+        .map_err(|_| Error::Unknown)
+}
+
+/// The documentation of a definition, eg. `text` or `calc.pow`.
+#[tauri::command]
+pub async fn docs_get(symbol: String) -> Result<Option<DocEntry>> {
+    tokio::task::spawn_blocking(move || doc_entry(&symbol))
+        .await
+        .map_err(|_| Error::Unknown)
+}
+
+/// Lists the symbols of the `sym` and `emoji` modules matching `query`, for the symbol
+/// picker.
+#[tauri::command]
+pub async fn typst_list_symbols(query: String) -> Result<Vec<SymbolEntry>> {
+    tokio::task::spawn_blocking(move || list_symbols(&query))
+        .await
+        .map_err(|_| Error::Unknown)
+}
+
+/// Compiles the `index`th example of a definition's documentation without access to any
+/// files and renders it as SVG, like the examples of the official documentation.
+#[tauri::command]
+pub async fn docs_render_example<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    symbol: String,
+    index: usize,
+) -> Result<String> {
+    let code = doc_example_source(&symbol, index).ok_or(Error::Unknown)?;
+    // The open project's fonts are reused rather than searched again.
+    let engine = match project_manager.get_project(&window) {
+        Some(project) => project
+            .world
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .engine(),
+        None => detached_engine(),
+    };
+    tokio::task::spawn_blocking(move || {
+        let document = SnippetWorld::new(engine, code)
+            .compile()
+            .map_err(Error::Compile)?;
+        Ok(typst_svg::svg_merged(&document, Abs::zero()))
+    })
+    .await
+    .map_err(|_| Error::Unknown)?
+}
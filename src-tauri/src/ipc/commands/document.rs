@@ -0,0 +1,233 @@
+use super::{ensure_disk_space, project, Error, Result};
+use crate::appdata::{read_app_json, write_app_json, SUBMISSION_PROFILES_FILE};
+use crate::compiler::{InputsWorld, RevisionWorld};
+use crate::document::{
+    check_submission, compare_documents, find_text, font_report, resolve_bookmarks, Bookmark,
+    BookmarkAnchor, FontReport, PageComparison, ResolvedBookmark, SubmissionCheck,
+    SubmissionProfile, TextHit,
+};
+use crate::project::{statistics_csv, Project, ProjectManager, ProjectWorld};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::{Runtime, State, WebviewWindow};
+use typst::layout::PagedDocument;
+use typst::World;
+
+/// Searches the rendered text of the last compiled document, like a PDF viewer's find.
+#[tauri::command]
+pub async fn document_find_text<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    query: String,
+    case_sensitive: Option<bool>,
+) -> Result<Vec<TextHit>> {
+    let project = project(&window, &project_manager)?;
+    let cache = project.cache.read().unwrap();
+    let doc = cache.document.as_ref().ok_or(Error::Unknown)?;
+    Ok(find_text(doc, &query, case_sensitive.unwrap_or(false)))
+}
+
+fn submission_profiles() -> Result<BTreeMap<String, SubmissionProfile>> {
+    Ok(read_app_json(SUBMISSION_PROFILES_FILE)?.unwrap_or_default())
+}
+
+#[tauri::command]
+pub async fn submission_profiles_list() -> Result<BTreeMap<String, SubmissionProfile>> {
+    submission_profiles()
+}
+
+/// Creates or replaces a profile, or removes it if `profile` is `None`.
+#[tauri::command]
+pub async fn submission_profile_save(
+    name: String,
+    profile: Option<SubmissionProfile>,
+) -> Result<()> {
+    let mut profiles = submission_profiles()?;
+    match profile {
+        Some(profile) => profiles.insert(name, profile),
+        None => profiles.remove(&name),
+    };
+    write_app_json(SUBMISSION_PROFILES_FILE, &profiles).map_err(Into::into)
+}
+
+/// Checks the last compiled document against a submission profile.
+#[tauri::command]
+pub async fn submission_check<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    profile: String,
+) -> Result<Vec<SubmissionCheck>> {
+    let profile = submission_profiles()?
+        .remove(&profile)
+        .ok_or(Error::Unknown)?;
+    let project = project(&window, &project_manager)?;
+    let cache = project.cache.read().unwrap();
+    let doc = cache.document.as_ref().ok_or(Error::Unknown)?;
+    Ok(check_submission(doc, &profile))
+}
+
+/// The font faces and Unicode ranges the last compiled document uses, and the faces
+/// of the project's font files it doesn't, to help subset or remove them.
+#[tauri::command]
+pub async fn document_font_report<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<FontReport> {
+    let project = project(&window, &project_manager)?;
+    tokio::task::spawn_blocking(move || -> Result<FontReport> {
+        let files = super::fs::file_index(&project);
+        let engine = project.world.lock().unwrap().engine();
+        let cache = project.cache.read().unwrap();
+        let doc = cache.document.as_ref().ok_or(Error::Unknown)?;
+        Ok(font_report(doc, &engine, &project.root, &files))
+    })
+    .await
+    .map_err(|_| Error::Unknown)?
+}
+
+/// The bookmarks at their positions in the last compiled document, or without positions
+/// if nothing compiled yet.
+fn resolved_bookmarks(project: &Project) -> Vec<ResolvedBookmark> {
+    let bookmarks = project.config.read().unwrap().bookmarks.clone();
+    match project.cache.read().unwrap().document.as_ref() {
+        Some(doc) => resolve_bookmarks(doc, &bookmarks),
+        None => bookmarks
+            .into_iter()
+            .map(|bookmark| ResolvedBookmark {
+                bookmark,
+                position: None,
+            })
+            .collect(),
+    }
+}
+
+#[tauri::command]
+pub async fn preview_bookmarks_list<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<Vec<ResolvedBookmark>> {
+    let project = project(&window, &project_manager)?;
+    Ok(resolved_bookmarks(&project))
+}
+
+/// Adds a bookmark anchored to a label or heading, replacing any bookmark of the same
+/// name, and saves it in the project config.
+#[tauri::command]
+pub async fn preview_bookmark_add<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    name: String,
+    anchor: BookmarkAnchor,
+) -> Result<Vec<ResolvedBookmark>> {
+    let project = project(&window, &project_manager)?;
+    {
+        let mut config = project.config.write().unwrap();
+        let bookmark = Bookmark { name, anchor };
+        match config.bookmarks.iter_mut().find(|b| b.name == bookmark.name) {
+            Some(existing) => *existing = bookmark,
+            None => config.bookmarks.push(bookmark),
+        }
+    }
+    project.save_config()?;
+    Ok(resolved_bookmarks(&project))
+}
+
+#[tauri::command]
+pub async fn preview_bookmark_remove<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    name: String,
+) -> Result<Vec<ResolvedBookmark>> {
+    let project = project(&window, &project_manager)?;
+    project
+        .config
+        .write()
+        .unwrap()
+        .bookmarks
+        .retain(|bookmark| bookmark.name != name);
+    project.save_config()?;
+    Ok(resolved_bookmarks(&project))
+}
+
+/// Writes the project's daily writing statistics between `from` and `to` (inclusive,
+/// `YYYY-MM-DD`) to `dest` as CSV.
+#[tauri::command]
+pub async fn stats_export<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    from: Option<String>,
+    to: Option<String>,
+    dest: PathBuf,
+) -> Result<PathBuf> {
+    let project = project(&window, &project_manager)?;
+    project.statistics.flush()?;
+    let csv = statistics_csv(&project.statistics.days(), from.as_deref(), to.as_deref());
+    ensure_disk_space(&dest, csv.len() as u64)?;
+    std::fs::write(&dest, csv)?;
+    Ok(dest)
+}
+
+/// The revision of `preview_compare` with the editor's unsaved buffers.
+const REVISION_WORKING: &str = "working";
+/// The revision of `preview_compare` with the files as saved on disk.
+const REVISION_DISK: &str = "disk";
+
+fn compile_document(world: &dyn World) -> Result<PagedDocument> {
+    typst::compile::<PagedDocument>(world)
+        .output
+        .map_err(|diagnostics| {
+            let messages: Vec<_> = diagnostics.iter().map(|d| d.message.to_string()).collect();
+            Error::Compile(messages.join("; "))
+        })
+}
+
+/// Compiles the project's main file at `rev`: `working`, `disk` or a git revision.
+fn compile_revision(project: &Project, rev: &str) -> Result<PagedDocument> {
+    let mut world = project.world.lock().unwrap_or_else(|e| e.into_inner());
+    let inputs = {
+        let config = project.config.read().unwrap();
+        if !world.is_main_set() {
+            config
+                .apply_main(project, &mut world)
+                .map_err(|_| Error::Compile("no main file is configured".to_string()))?;
+        }
+        project.preview_inputs.read().unwrap().to_inputs(&config.toggles)
+    };
+    match rev {
+        REVISION_WORKING => compile_document(&InputsWorld::new(&*world, &inputs)),
+        REVISION_DISK => {
+            let mut disk = ProjectWorld::with_engine(project.root.clone(), world.engine());
+            disk.set_roots(project.roots.read().unwrap().clone());
+            disk.set_main(Some(world.main()));
+            drop(world);
+            compile_document(&InputsWorld::new(&disk, &inputs))
+        }
+        rev => {
+            let revision = RevisionWorld::new(&*world, &project.root, rev)?;
+            compile_document(&InputsWorld::new(&revision, &inputs))
+        }
+    }
+}
+
+/// Compiles the document at two revisions and compares their pages, for a preview of
+/// what changed in the output. A revision is `working` for the editor's buffers, `disk`
+/// for the saved files, or a git revision such as `HEAD` or a commit hash. `rev_b`
+/// defaults to `working`.
+#[tauri::command]
+pub async fn preview_compare<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    rev_a: String,
+    rev_b: Option<String>,
+) -> Result<Vec<PageComparison>> {
+    let project = project(&window, &project_manager)?;
+    let rev_b = rev_b.unwrap_or_else(|| REVISION_WORKING.to_string());
+    tokio::task::spawn_blocking(move || {
+        let old = compile_revision(&project, &rev_a)?;
+        let new = compile_revision(&project, &rev_b)?;
+        Ok(compare_documents(&old, &new))
+    })
+    .await
+    .map_err(|_| Error::Unknown)?
+}
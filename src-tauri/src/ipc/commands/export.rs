@@ -0,0 +1,286 @@
+use super::{Error, Result};
+use crate::appdata::set_commands_approved;
+use crate::compiler::{compile_with_prelude, toggle_inputs, SEED_INPUT};
+use crate::export::{
+    merge_inputs, merge_name, project_main_path, random_seed, read_merge_records, unique_name,
+    variant_combinations, variant_suffix, with_extension, write_pdf, ExportFormat, ExportJobs,
+    ExportTask, Violation, EXPORT_HOOK_COMMANDS,
+};
+use crate::project::{ProjectConfig, ProjectManager};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::{Runtime, State, WebviewWindow};
+
+/// Lists every combination of the variant axes defined in the project config.
+#[tauri::command]
+pub async fn export_list_variants<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<Vec<BTreeMap<String, String>>> {
+    let project = super::project(&window, &project_manager)?;
+    let axes = project.config.read().unwrap().variants.clone();
+    Ok(variant_combinations(&axes))
+}
+
+fn main_stem(config: &ProjectConfig) -> String {
+    config
+        .main
+        .as_ref()
+        .and_then(|m| m.file_stem())
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "document".to_string())
+}
+
+/// Exports one file per variant combination into `dir`, named after the main file
+/// and the variant values (eg. `main-en-final.pdf`). `toggles` overrides the project's
+/// toggle defaults for every variant, and `seed` is passed to every variant as
+/// `sys.inputs.seed`. Returns the export job id.
+#[tauri::command]
+pub async fn export_variants<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    export_jobs: State<'_, Arc<ExportJobs>>,
+    dir: PathBuf,
+    format: ExportFormat,
+    toggles: Option<BTreeMap<String, bool>>,
+    seed: Option<u64>,
+) -> Result<u64> {
+    let project = super::project(&window, &project_manager)?;
+    let (stem, axes, mut shared) = {
+        let config = project.config.read().unwrap();
+        let toggles = toggle_inputs(&config.toggles, &toggles.unwrap_or_default());
+        (main_stem(&config), config.variants.clone(), toggles)
+    };
+    if let Some(seed) = seed {
+        shared.insert(SEED_INPUT.to_string(), seed.to_string());
+    }
+
+    let tasks = variant_combinations(&axes)
+        .into_iter()
+        .map(|inputs| {
+            let suffix = variant_suffix(&inputs);
+            let name = if suffix.is_empty() {
+                stem.clone()
+            } else {
+                format!("{}-{}", stem, suffix)
+            };
+            let mut inputs = inputs;
+            for (key, value) in &shared {
+                inputs.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+            ExportTask {
+                output: with_extension(&dir.join(&name), format),
+                name,
+                inputs,
+                format,
+            }
+        })
+        .collect();
+
+    Ok(export_jobs.spawn(window, project, tasks))
+}
+
+/// Exports `count` randomized versions of the document into `dir`, each compiled with
+/// its own `sys.inputs.seed` counting up from `base_seed` (random if not given) and
+/// named after it, eg. `main-seed-1042.pdf`. Returns the export job id.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn export_seeded<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    export_jobs: State<'_, Arc<ExportJobs>>,
+    dir: PathBuf,
+    format: ExportFormat,
+    count: u64,
+    base_seed: Option<u64>,
+    toggles: Option<BTreeMap<String, bool>>,
+) -> Result<u64> {
+    let project = super::project(&window, &project_manager)?;
+    let (stem, toggles) = {
+        let config = project.config.read().unwrap();
+        let toggles = toggle_inputs(&config.toggles, &toggles.unwrap_or_default());
+        (main_stem(&config), toggles)
+    };
+
+    let base_seed = base_seed.unwrap_or_else(random_seed);
+    let tasks = (0..count)
+        .map(|i| {
+            let seed = base_seed.wrapping_add(i);
+            let name = format!("{}-seed-{}", stem, seed);
+            let mut inputs = toggles.clone();
+            inputs.insert(SEED_INPUT.to_string(), seed.to_string());
+            ExportTask {
+                output: with_extension(&dir.join(&name), format),
+                name,
+                inputs,
+                format,
+            }
+        })
+        .collect();
+
+    Ok(export_jobs.spawn(window, project, tasks))
+}
+
+/// Mail merge: exports one file per record of the CSV or JSON file at `data` into `dir`.
+/// `mapping` maps `sys.inputs` names to columns, and every column is passed under its
+/// own name without one. Files are named by filling the `{column}` placeholders of
+/// `name`, eg. `invoice-{number}`, or numbered after the main file. A record that fails
+/// to compile does not stop the others. Returns the export job id.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn export_mail_merge<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    export_jobs: State<'_, Arc<ExportJobs>>,
+    data: PathBuf,
+    dir: PathBuf,
+    format: ExportFormat,
+    mapping: Option<BTreeMap<String, String>>,
+    name: Option<String>,
+    toggles: Option<BTreeMap<String, bool>>,
+) -> Result<u64> {
+    let project = super::project(&window, &project_manager)?;
+    let (stem, toggles) = {
+        let config = project.config.read().unwrap();
+        let toggles = toggle_inputs(&config.toggles, &toggles.unwrap_or_default());
+        (main_stem(&config), toggles)
+    };
+
+    let records = read_merge_records(&data).map_err(|e| {
+        log::error!("failed to read mail merge data {:?}: {}", data, e);
+        match e.kind() {
+            io::ErrorKind::InvalidData => Error::UnsupportedFormat,
+            _ => e.into(),
+        }
+    })?;
+    let mapping = mapping.unwrap_or_default();
+    let mut taken = HashSet::new();
+    let tasks = records
+        .iter()
+        .enumerate()
+        .map(|(index, record)| {
+            let name = merge_name(name.as_deref(), record, &stem, index);
+            let name = unique_name(name, &mut taken);
+            let mut inputs = merge_inputs(record, &mapping);
+            for (key, value) in &toggles {
+                inputs.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+            ExportTask {
+                output: with_extension(&dir.join(&name), format),
+                name,
+                inputs,
+                format,
+            }
+        })
+        .collect();
+
+    Ok(export_jobs.spawn(window, project, tasks))
+}
+
+/// Turns the automatic exports after every save on or off and saves it to the project
+/// config. Turning them on exports right away. Returns the files they write.
+#[tauri::command]
+pub async fn export_auto_toggle<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    enabled: bool,
+) -> Result<Vec<PathBuf>> {
+    let project = super::project(&window, &project_manager)?;
+    project.config.write().unwrap().auto_export.enabled = enabled;
+    project.save_config()?;
+    project.auto_exporter.spawn(&window, &project);
+
+    let Some(main) = project_main_path(&project) else {
+        return Ok(vec![]);
+    };
+    let roots = project.roots.read().unwrap().clone();
+    let config = project.config.read().unwrap();
+    Ok(config
+        .auto_export
+        .targets(&roots, &main)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
+        .into_iter()
+        .map(|(_, path)| path)
+        .collect())
+}
+
+/// Allows or disallows the export hooks of the project config to run their current
+/// commands after exports. Stored outside the project, and a hook whose command changes
+/// needs approving again.
+#[tauri::command]
+pub async fn export_hooks_approve<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    approved: bool,
+) -> Result<()> {
+    let project = super::project(&window, &project_manager)?;
+    let commands: Vec<String> = project
+        .config
+        .read()
+        .unwrap()
+        .export_hooks
+        .iter()
+        .map(|hook| hook.command.clone())
+        .collect();
+    set_commands_approved(
+        &project.root,
+        EXPORT_HOOK_COMMANDS,
+        commands.iter().map(String::as_str),
+        approved,
+    )?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn export_job_cancel(export_jobs: State<'_, Arc<ExportJobs>>, job_id: u64) -> Result<()> {
+    export_jobs.cancel(job_id);
+    Ok(())
+}
+
+#[derive(Serialize, Debug)]
+pub struct AnonymousExport {
+    /// The written PDF, or `None` if violations blocked the export.
+    pub path: Option<PathBuf>,
+    pub violations: Vec<Violation>,
+}
+
+/// Exports a double-blind PDF: compiles with the project's anonymize inputs and author
+/// placeholders, strips the PDF metadata, and refuses to write the file if forbidden
+/// patterns such as acknowledgments remain, unless `force` is set.
+#[tauri::command]
+pub async fn export_anonymous<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    path: PathBuf,
+    force: Option<bool>,
+) -> Result<AnonymousExport> {
+    let project = super::project(&window, &project_manager)?;
+    let config = project.config.read().unwrap().anonymize.clone();
+
+    let doc_project = project.clone();
+    let export = tokio::task::spawn_blocking(move || -> Result<AnonymousExport> {
+        let mut doc = compile_with_prelude(&doc_project, &config.inputs, Some(config.prelude()))
+            .map_err(|e| {
+                log::error!("anonymous export failed to compile: {}", e);
+                Error::Unknown
+            })?;
+        doc.info = Default::default();
+
+        let violations = config.violations(&doc);
+        let path = if violations.is_empty() || force.unwrap_or(false) {
+            Some(write_pdf(&doc, &path)?)
+        } else {
+            None
+        };
+        Ok(AnonymousExport { path, violations })
+    })
+    .await
+    .map_err(|_| Error::Unknown)??;
+    if let Some(path) = &export.path {
+        super::record_export(&window, &project, path, "pdf");
+    }
+    Ok(export)
+}
@@ -1,16 +1,22 @@
-use super::{Error, Result};
+use super::{fs_error, Error, FileConflict, Result};
 use crate::ipc::commands::project_path;
-use crate::project::ProjectManager;
+use crate::project::{
+    is_history_path, normalize_project_path, within_roots, FileStamp, Project, ProjectManager,
+};
+use crate::search::fuzzy_rank;
 use enumset::EnumSetType;
 use serde::Serialize;
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::fs;
 use std::fs::{File, OpenOptions};
+use std::io;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tauri::{Runtime, State, WebviewWindow};
-use ignore::WalkBuilder;
+use ignore::overrides::OverrideBuilder;
+use ignore::{Walk, WalkBuilder};
 
 #[derive(Serialize, Debug)]
 pub struct FileItem {
@@ -36,7 +42,7 @@ pub async fn fs_read_file_binary<R: Runtime>(
     path: PathBuf,
 ) -> Result<Vec<u8>> {
     let (_, path) = project_path(&window, &project_manager, path)?;
-    fs::read(path).map_err(Into::into)
+    fs::read(&path).map_err(|e| fs_error(e, &path))
 }
 
 #[tauri::command]
@@ -45,8 +51,13 @@ pub async fn fs_read_file_text<R: Runtime>(
     project_manager: State<'_, Arc<ProjectManager<R>>>,
     path: PathBuf,
 ) -> Result<String> {
-    let (_, path) = project_path(&window, &project_manager, path)?;
-    fs::read_to_string(path).map_err(Into::into)
+    let (project, path) = project_path(&window, &project_manager, path)?;
+    let (stamp, content) = FileStamp::read(&path).map_err(|e| fs_error(e, &path))?;
+    let content = String::from_utf8(content)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        .map_err(Into::<Error>::into)?;
+    project.stamps.set(path, stamp);
+    Ok(content)
 }
 
 #[tauri::command]
@@ -60,14 +71,14 @@ pub async fn fs_create_file<R: Runtime>(
     // Not sure if there's a scenario where this condition is not met
     // unless the project is located at `/`
     if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).map_err(Into::<Error>::into)?;
+        fs::create_dir_all(parent).map_err(|e| fs_error(e, parent))?;
     }
     OpenOptions::new()
         .read(true)
         .write(true)
         .create_new(true)
         .open(&*path)
-        .map_err(Into::<Error>::into)?;
+        .map_err(|e| fs_error(e, &path))?;
     Ok(())
 }
 
@@ -79,23 +90,53 @@ pub async fn fs_write_file_binary<R: Runtime>(
     content: Vec<u8>,
 ) -> Result<()> {
     let (_, path) = project_path(&window, &project_manager, path)?;
-    fs::write(path, content).map_err(Into::into)
+    fs::write(&path, content).map_err(|e| fs_error(e, &path))
 }
 
+/// Writes text to a file. Unless `force` is set, the write is refused with a
+/// [`FileConflict`] if the file changed on disk since it was last read or written.
 #[tauri::command]
 pub async fn fs_write_file_text<R: Runtime>(
     window: WebviewWindow<R>,
     project_manager: State<'_, Arc<ProjectManager<R>>>,
     path: PathBuf,
     content: String,
+    force: Option<bool>,
 ) -> Result<()> {
     let (project, absolute_path) = project_path(&window, &project_manager, &path)?;
+    if !force.unwrap_or(false) {
+        if let Some(known) = project.stamps.get(&absolute_path) {
+            if let Ok((current, disk)) = FileStamp::read(&absolute_path) {
+                if current.differs(&known) {
+                    return Err(Error::Conflict(Box::new(FileConflict {
+                        path,
+                        disk: String::from_utf8_lossy(&disk).into_owned(),
+                        local: content,
+                    })));
+                }
+            }
+        }
+    }
+
     if let Some(parent) = absolute_path.parent() {
-        fs::create_dir_all(parent).map_err(Into::<Error>::into)?;
+        fs::create_dir_all(parent).map_err(|e| fs_error(e, parent))?;
+    }
+    File::create(&absolute_path)
+        .and_then(|mut f| f.write_all(content.as_bytes()))
+        .map_err(|e| fs_error(e, &absolute_path))?;
+
+    let modified = fs::metadata(&absolute_path).and_then(|m| m.modified()).ok();
+    project.dirty_buffers.clear(&absolute_path);
+    if let Err(e) = project.history.record(&absolute_path, content.as_bytes()) {
+        log::warn!("failed to snapshot {:?}: {}", absolute_path, e);
+    }
+    project
+        .stamps
+        .set(absolute_path.clone(), FileStamp::new(content.as_bytes(), modified));
+    if project.config.read().unwrap().auto_commit {
+        let project = project.clone();
+        tokio::task::spawn_blocking(move || project.auto_commit(&[absolute_path]));
     }
-    let _ = File::create(&absolute_path)
-        .map(|mut f| f.write_all(content.as_bytes()))
-        .map_err(Into::<Error>::into)?;
 
     let world = project.world.lock().unwrap_or_else(|e| {
         log::warn!("Project world mutex poisoned, recovering: {}", e);
@@ -104,35 +145,67 @@ pub async fn fs_write_file_text<R: Runtime>(
     let _ = world
         .slot_update(&path, Some(content))
         .map_err(Into::<Error>::into)?;
+    drop(world);
+    project.auto_exporter.saved(&window, &project);
 
     Ok(())
 }
 
+/// Lists a directory merged across the project's roots. An entry in an earlier root
+/// hides one of the same name in a later root.
 #[tauri::command]
 pub async fn fs_list_dir<R: Runtime>(
     window: WebviewWindow<R>,
     project_manager: State<'_, Arc<ProjectManager<R>>>,
     path: PathBuf,
 ) -> Result<Vec<FileItem>> {
-    let (_, path) = project_path(&window, &project_manager, path)?;
-    let list = fs::read_dir(path).map_err(Into::<Error>::into)?;
+    let project = super::project(&window, &project_manager)?;
+    let relative = normalize_project_path(&path).ok_or(Error::UnrelatedPath)?;
+    let roots = project.roots.read().unwrap().clone();
+    let dirs: Vec<PathBuf> = roots
+        .iter()
+        .map(|root| root.join(&relative))
+        .filter(|dir| dir.is_dir() && within_roots(&roots, dir))
+        .collect();
+    if dirs.is_empty() {
+        // Reports why the directory of the project itself can't be read.
+        fs::read_dir(project.root.join(&relative)).map_err(Into::<Error>::into)?;
+    }
 
     let mut files: Vec<FileItem> = vec![];
-    list.into_iter().for_each(|entry| {
-        if let Ok(entry) = entry {
-            if let (Ok(file_type), Ok(name)) = (entry.file_type(), entry.file_name().into_string())
-            {
-                // File should only be directory or file.
-                // Symlinks should be resolved in project_path.
-                let t = if file_type.is_dir() {
-                    FileType::Directory
-                } else {
-                    FileType::File
-                };
-                files.push(FileItem { name, file_type: t });
+    if let Some(file) = &project.single_file {
+        if relative.as_os_str().is_empty() {
+            if let Some(name) = file.file_name().and_then(|name| name.to_str()) {
+                files.push(FileItem {
+                    name: name.to_string(),
+                    file_type: FileType::File,
+                });
             }
         }
-    });
+        return Ok(files);
+    }
+    for dir in dirs {
+        let list = fs::read_dir(dir).map_err(Into::<Error>::into)?;
+        list.into_iter().for_each(|entry| {
+            if let Ok(entry) = entry {
+                if let (Ok(file_type), Ok(name)) =
+                    (entry.file_type(), entry.file_name().into_string())
+                {
+                    if files.iter().any(|file| file.name == name) {
+                        return;
+                    }
+                    // File should only be directory or file.
+                    // Symlinks should be resolved in project_path.
+                    let t = if file_type.is_dir() {
+                        FileType::Directory
+                    } else {
+                        FileType::File
+                    };
+                    files.push(FileItem { name, file_type: t });
+                }
+            }
+        });
+    }
 
     files.sort_by(|a, b| {
         if a.file_type == FileType::Directory && b.file_type == FileType::File {
@@ -147,21 +220,84 @@ pub async fn fs_list_dir<R: Runtime>(
     Ok(files)
 }
 
+/// Moves a file or directory to the system trash, or deletes it for good if
+/// `permanent` is set.
 #[tauri::command]
 pub async fn fs_delete_file<R: Runtime>(
     window: WebviewWindow<R>,
     project_manager: State<'_, Arc<ProjectManager<R>>>,
     path: PathBuf,
+    permanent: Option<bool>,
 ) -> Result<()> {
-    let (_, abs_path) = project_path(&window, &project_manager, path)?;
+    let (project, abs_path) = project_path(&window, &project_manager, path)?;
+    project.stamps.remove(&abs_path);
+    if !permanent.unwrap_or(false) {
+        // Don't fall back to deleting for good, the user has to ask for that.
+        trash::delete(&abs_path).map_err(|e| fs_error(io::Error::other(e), &abs_path))?;
+        project.trashed.lock().unwrap().push(abs_path);
+        return Ok(());
+    }
     if abs_path.is_dir() {
-        fs::remove_dir_all(&abs_path).map_err(Into::<Error>::into)?;
+        fs::remove_dir_all(&abs_path).map_err(|e| fs_error(e, &abs_path))?;
     } else {
-        fs::remove_file(&abs_path).map_err(Into::<Error>::into)?;
+        fs::remove_file(&abs_path).map_err(|e| fs_error(e, &abs_path))?;
     }
     Ok(())
 }
 
+/// Restores the most recently trashed file of the project and returns its project path.
+#[cfg(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+))]
+#[tauri::command]
+pub async fn fs_undo_delete<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<Option<PathBuf>> {
+    let project = super::project(&window, &project_manager)?;
+    let Some(path) = project.trashed.lock().unwrap().last().cloned() else {
+        return Ok(None);
+    };
+    // The deletion stays undoable until it's restored, or gone from the trash.
+    let forget = || {
+        let mut trashed = project.trashed.lock().unwrap();
+        if let Some(index) = trashed.iter().rposition(|trashed| *trashed == path) {
+            trashed.remove(index);
+        }
+    };
+
+    let items = trash::os_limited::list().map_err(|e| fs_error(io::Error::other(e), &path))?;
+    let Some(item) = items
+        .into_iter()
+        .filter(|item| item.original_path() == path)
+        .max_by_key(|item| item.time_deleted)
+    else {
+        forget();
+        return Ok(None);
+    };
+    trash::os_limited::restore_all([item]).map_err(|e| fs_error(io::Error::other(e), &path))?;
+    forget();
+
+    Ok(path
+        .strip_prefix(&project.root)
+        .ok()
+        .map(|relative| Path::new("/").join(relative)))
+}
+
+/// The trash on this platform can't be listed, so deletions can't be undone from here.
+#[cfg(not(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+)))]
+#[tauri::command]
+pub async fn fs_undo_delete<R: Runtime>(
+    _window: WebviewWindow<R>,
+    _project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<Option<PathBuf>> {
+    Err(Error::UnsupportedPlatform)
+}
+
 #[tauri::command]
 pub async fn fs_rename_file<R: Runtime>(
     window: WebviewWindow<R>,
@@ -169,11 +305,104 @@ pub async fn fs_rename_file<R: Runtime>(
     old_path: PathBuf,
     new_path: PathBuf,
 ) -> Result<()> {
-    let (_, old_abs) = project_path(&window, &project_manager, &old_path)?;
+    let (project, old_abs) = project_path(&window, &project_manager, &old_path)?;
     let (_, new_abs) = project_path(&window, &project_manager, &new_path)?;
-    fs::rename(&old_abs, &new_abs).map_err(Into::<Error>::into)?;
+    fs::rename(&old_abs, &new_abs).map_err(|e| fs_error(e, &new_abs))?;
+    project.stamps.remove(&old_abs);
+    Ok(())
+}
+
+/// Copies a file or directory. Symlinks are skipped rather than followed so that a
+/// copy can never pull in content from outside the project.
+fn copy_recursive(src: &Path, dest: &Path) -> Result<()> {
+    let metadata = fs::symlink_metadata(src).map_err(|e| fs_error(e, src))?;
+    if metadata.is_dir() {
+        fs::create_dir(dest).map_err(|e| fs_error(e, dest))?;
+        for entry in fs::read_dir(src).map_err(|e| fs_error(e, src))? {
+            let entry = entry.map_err(|e| fs_error(e, src))?;
+            copy_recursive(&entry.path(), &dest.join(entry.file_name()))?;
+        }
+    } else if metadata.is_file() {
+        // Don't overwrite, the frontend decides on names.
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(dest)
+            .map_err(|e| fs_error(e, dest))?;
+        fs::copy(src, dest).map_err(|e| fs_error(e, dest))?;
+    }
     Ok(())
 }
+
+/// `path` with symlinks resolved, for a path that may not exist yet.
+fn canonicalize_new(path: &Path) -> Option<PathBuf> {
+    let existing = path.ancestors().find(|p| p.symlink_metadata().is_ok())?;
+    let rest = path.strip_prefix(existing).ok()?;
+    Some(fs::canonicalize(existing).ok()?.join(rest))
+}
+
+fn copy_in_project(project: &Project, src: &Path, dest: &Path) -> Result<()> {
+    let canonical = fs::canonicalize(src).map_err(|e| fs_error(e, src))?;
+    if !within_roots(&project.roots.read().unwrap(), &canonical) {
+        return Err(Error::UnrelatedPath);
+    }
+    // Copying a directory into itself would never terminate.
+    let dest_canonical = canonicalize_new(dest).ok_or(Error::UnrelatedPath)?;
+    if dest_canonical.starts_with(&canonical) {
+        return Err(Error::UnrelatedPath);
+    }
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).map_err(|e| fs_error(e, parent))?;
+    }
+    copy_recursive(src, dest)
+}
+
+/// Picks the first free sibling name of `path` in the form "name copy.typ",
+/// "name copy 2.typ", ...
+fn duplicate_path(absolute: &Path, relative: &Path) -> Option<PathBuf> {
+    let stem = relative.file_stem()?.to_string_lossy().to_string();
+    let extension = relative.extension().map(|e| e.to_string_lossy().to_string());
+    (1..)
+        .map(|n| {
+            let name = match n {
+                1 => format!("{} copy", stem),
+                n => format!("{} copy {}", stem, n),
+            };
+            match &extension {
+                Some(ext) => format!("{}.{}", name, ext),
+                None => name,
+            }
+        })
+        .find(|name| !absolute.with_file_name(name).exists())
+        .map(|name| relative.with_file_name(name))
+}
+
+#[tauri::command]
+pub async fn fs_copy_file<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    src: PathBuf,
+    dest: PathBuf,
+) -> Result<()> {
+    let (project, src_abs) = project_path(&window, &project_manager, &src)?;
+    let (_, dest_abs) = project_path(&window, &project_manager, &dest)?;
+    copy_in_project(&project, &src_abs, &dest_abs)
+}
+
+/// Copies a file or directory next to itself and returns the project path of the copy.
+#[tauri::command]
+pub async fn fs_duplicate<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    path: PathBuf,
+) -> Result<PathBuf> {
+    let (project, abs_path) = project_path(&window, &project_manager, &path)?;
+    let dest = duplicate_path(&abs_path, &path).ok_or(Error::UnrelatedPath)?;
+    let (_, dest_abs) = project_path(&window, &project_manager, &dest)?;
+    copy_in_project(&project, &abs_path, &dest_abs)?;
+    Ok(dest)
+}
+
 #[tauri::command]
 pub async fn fs_reveal_path<R: Runtime>(
     window: WebviewWindow<R>,
@@ -192,20 +421,29 @@ pub async fn fs_reveal_path<R: Runtime>(
     Ok(())
 }
 
-#[tauri::command]
-pub async fn fs_search_files<R: Runtime>(
-    window: WebviewWindow<R>,
-    project_manager: State<'_, Arc<ProjectManager<R>>>,
-) -> Result<Vec<String>> {
-    let project = super::project(&window, &project_manager)?;
-    let root = project.root.clone();
+/// Builds a walker over the project that respects `.gitignore`, `.nomedia` markers and
+/// the project's configured ignore patterns.
+fn project_walker(project: &Project) -> Walk {
+    let mut overrides = OverrideBuilder::new(&project.root);
+    for pattern in project.config.read().unwrap().ignore.iter() {
+        if let Err(e) = overrides.add(&format!("!{}", pattern)) {
+            log::warn!("invalid ignore pattern {:?}: {}", pattern, e);
+        }
+    }
 
-    let mut files = Vec::new();
-    let walker = WalkBuilder::new(&root)
+    let single_file = project.single_file.clone();
+    let mut builder = WalkBuilder::new(&project.root);
+    builder
         .hidden(false)
         .git_ignore(true)
         .require_git(false)
-        .filter_entry(|entry| {
+        .filter_entry(move |entry| {
+            if is_history_path(entry.path()) {
+                return false;
+            }
+            if let Some(file) = &single_file {
+                return entry.depth() == 0 || entry.path() == file;
+            }
             if entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
                 let nomedia = entry.path().join(".nomedia");
                 if nomedia.exists() {
@@ -213,10 +451,21 @@ pub async fn fs_search_files<R: Runtime>(
                 }
             }
             true
-        })
-        .build();
+        });
+    if let Ok(overrides) = overrides.build() {
+        builder.overrides(overrides);
+    }
+    builder.build()
+}
+
+/// Returns the cached file index of the project, walking the project if needed.
+pub(crate) fn file_index(project: &Project) -> Arc<Vec<String>> {
+    if let Some(index) = project.file_index.read().unwrap().as_ref() {
+        return index.clone();
+    }
 
-    for entry in walker {
+    let mut files = Vec::new();
+    for entry in project_walker(project) {
         let entry = match entry {
             Ok(e) => e,
             Err(_) => continue,
@@ -228,7 +477,7 @@ pub async fn fs_search_files<R: Runtime>(
             continue;
         }
 
-        if let Ok(relative_path) = path.strip_prefix(&root) {
+        if let Ok(relative_path) = path.strip_prefix(&project.root) {
             if let Some(path_str) = relative_path.to_str() {
                 if !path_str.is_empty() {
                     files.push(path_str.to_string());
@@ -237,5 +486,112 @@ pub async fn fs_search_files<R: Runtime>(
         }
     }
 
-    Ok(files)
+    let index = Arc::new(files);
+    *project.file_index.write().unwrap() = Some(index.clone());
+    index
+}
+
+#[tauri::command]
+pub async fn fs_search_files<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<Vec<String>> {
+    let project = super::project(&window, &project_manager)?;
+    Ok(file_index(&project).as_ref().clone())
+}
+
+#[derive(Serialize, Debug)]
+pub struct QuickOpenResult {
+    pub path: String,
+    pub score: i64,
+    /// Character indices of the matched characters in `path`.
+    pub positions: Vec<usize>,
+}
+
+/// Fuzzy-matches the project file index and returns the best `limit` results.
+#[tauri::command]
+pub async fn fs_quick_open<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    query: String,
+    limit: usize,
+) -> Result<Vec<QuickOpenResult>> {
+    let project = super::project(&window, &project_manager)?;
+    let index = file_index(&project);
+
+    Ok(fuzzy_rank(&query, index.as_slice(), limit)
+        .into_iter()
+        .map(|(i, m)| QuickOpenResult {
+            path: index[i].clone(),
+            score: m.score,
+            positions: m.positions,
+        })
+        .collect())
+}
+
+#[derive(Serialize, Debug)]
+pub struct FileTreeNode {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub file_type: FileType,
+    pub children: Option<Vec<FileTreeNode>>,
+}
+
+fn sort_nodes(nodes: &mut [FileTreeNode]) {
+    nodes.sort_by(|a, b| match (a.file_type, b.file_type) {
+        (FileType::Directory, FileType::File) => Ordering::Less,
+        (FileType::File, FileType::Directory) => Ordering::Greater,
+        _ => a.name.cmp(&b.name),
+    });
+}
+
+fn build_tree(dir: &Path, entries: &HashMap<PathBuf, Vec<(String, FileType)>>) -> Vec<FileTreeNode> {
+    let mut nodes: Vec<FileTreeNode> = entries
+        .get(dir)
+        .map(|children| {
+            children
+                .iter()
+                .map(|(name, file_type)| FileTreeNode {
+                    name: name.clone(),
+                    file_type: *file_type,
+                    children: match file_type {
+                        FileType::Directory => Some(build_tree(&dir.join(name), entries)),
+                        FileType::File => None,
+                    },
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    sort_nodes(&mut nodes);
+    nodes
+}
+
+/// Returns the whole project tree in one call, filtered like `fs_search_files`.
+#[tauri::command]
+pub async fn fs_tree<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<Vec<FileTreeNode>> {
+    let project = super::project(&window, &project_manager)?;
+
+    let mut entries: HashMap<PathBuf, Vec<(String, FileType)>> = HashMap::new();
+    for entry in project_walker(&project).flatten() {
+        let Ok(relative) = entry.path().strip_prefix(&project.root) else {
+            continue;
+        };
+        let (Some(parent), Some(name)) = (relative.parent(), relative.file_name()) else {
+            continue;
+        };
+        let file_type = if entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
+            FileType::Directory
+        } else {
+            FileType::File
+        };
+        entries
+            .entry(parent.to_path_buf())
+            .or_default()
+            .push((name.to_string_lossy().to_string(), file_type));
+    }
+
+    Ok(build_tree(Path::new(""), &entries))
 }
@@ -0,0 +1,144 @@
+use super::Error;
+use serde::Serialize;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileAccessKind {
+    PermissionDenied,
+    ReadOnly,
+    Locked,
+    DiskFull,
+    PathTooLong,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecoveryAction {
+    Retry,
+    SaveAs,
+    RevealInFileManager,
+    CheckPermissions,
+    CloseOtherApplications,
+    FreeDiskSpace,
+    ShortenPath,
+}
+
+/// An IO failure the user can act upon, serialized with suggested recovery actions.
+#[derive(Serialize, Debug)]
+#[serde(tag = "kind", rename = "file_access")]
+pub struct FileAccessError {
+    pub reason: FileAccessKind,
+    pub path: PathBuf,
+    pub message: String,
+    pub recovery: Vec<RecoveryAction>,
+}
+
+#[cfg(unix)]
+fn classify_os_error(code: i32) -> Option<FileAccessKind> {
+    match code {
+        1 | 13 => Some(FileAccessKind::PermissionDenied),
+        30 => Some(FileAccessKind::ReadOnly),
+        16 | 26 => Some(FileAccessKind::Locked),
+        28 => Some(FileAccessKind::DiskFull),
+        #[cfg(target_os = "linux")]
+        122 => Some(FileAccessKind::DiskFull),
+        #[cfg(target_os = "linux")]
+        36 => Some(FileAccessKind::PathTooLong),
+        #[cfg(target_os = "macos")]
+        69 => Some(FileAccessKind::DiskFull),
+        #[cfg(target_os = "macos")]
+        63 => Some(FileAccessKind::PathTooLong),
+        _ => None,
+    }
+}
+
+#[cfg(windows)]
+fn classify_os_error(code: i32) -> Option<FileAccessKind> {
+    match code {
+        5 => Some(FileAccessKind::PermissionDenied),
+        19 => Some(FileAccessKind::ReadOnly),
+        32 | 33 => Some(FileAccessKind::Locked),
+        39 | 112 => Some(FileAccessKind::DiskFull),
+        111 | 206 => Some(FileAccessKind::PathTooLong),
+        _ => None,
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn classify_os_error(_code: i32) -> Option<FileAccessKind> {
+    None
+}
+
+fn recovery(kind: FileAccessKind) -> Vec<RecoveryAction> {
+    use RecoveryAction::*;
+    match kind {
+        FileAccessKind::PermissionDenied => vec![CheckPermissions, RevealInFileManager, SaveAs],
+        FileAccessKind::ReadOnly => vec![SaveAs, RevealInFileManager],
+        FileAccessKind::Locked => vec![CloseOtherApplications, Retry],
+        FileAccessKind::DiskFull => vec![FreeDiskSpace, Retry, SaveAs],
+        FileAccessKind::PathTooLong => vec![ShortenPath, SaveAs],
+    }
+}
+
+fn describe(kind: FileAccessKind) -> &'static str {
+    match kind {
+        FileAccessKind::PermissionDenied => "permission denied",
+        FileAccessKind::ReadOnly => "the file system is read-only",
+        FileAccessKind::Locked => "the file is locked by another process",
+        FileAccessKind::DiskFull => "there is not enough space on the disk",
+        FileAccessKind::PathTooLong => "the path is too long",
+    }
+}
+
+/// Classifies an IO error on `path`, falling back to [`Error::IO`] for failures
+/// without a known recovery.
+pub fn fs_error(error: io::Error, path: &Path) -> Error {
+    let kind = error.raw_os_error().and_then(classify_os_error).or_else(|| {
+        (error.kind() == io::ErrorKind::PermissionDenied).then_some(FileAccessKind::PermissionDenied)
+    });
+    match kind {
+        Some(kind) => Error::FileAccess(Box::new(FileAccessError {
+            reason: kind,
+            path: path.to_path_buf(),
+            message: describe(kind).to_string(),
+            recovery: recovery(kind),
+        })),
+        None => Error::IO(error),
+    }
+}
+
+/// Extra space kept free on top of what an operation needs, since file systems
+/// rarely let the last few megabytes be used.
+const DISK_SPACE_MARGIN: u64 = 16 * 1024 * 1024;
+
+/// Checks that the volume containing `dest` can take `required` more bytes before
+/// anything is written, so large outputs fail cleanly instead of being truncated.
+pub fn ensure_disk_space(dest: &Path, required: u64) -> Result<(), Error> {
+    let Some(existing) = dest.ancestors().find(|p| p.exists()) else {
+        return Ok(());
+    };
+    let available = match fs2::available_space(existing) {
+        Ok(available) => available,
+        Err(e) => {
+            log::warn!("unable to query available space for {:?}: {}", existing, e);
+            return Ok(());
+        }
+    };
+
+    if available < required.saturating_add(DISK_SPACE_MARGIN) {
+        return Err(Error::InsufficientSpace {
+            path: dest.to_path_buf(),
+            required,
+            available,
+        });
+    }
+    if available < required.saturating_mul(4) {
+        log::warn!(
+            "writing {} bytes to {:?} leaves little space ({} bytes available)",
+            required, dest, available
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use super::{project, Error, Result};
+use crate::appdata::set_commands_approved;
+use crate::project::{FigureGenerator, GeneratorRun, ProjectManager, GENERATOR_COMMANDS};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use tauri::{Runtime, State, WebviewWindow};
+
+#[derive(Serialize, Debug)]
+pub struct GeneratorStatus {
+    #[serde(flatten)]
+    pub generator: FigureGenerator,
+    pub stale: bool,
+    /// Whether the user approved the generator's command, without which it doesn't run.
+    pub approved: bool,
+}
+
+#[tauri::command]
+pub async fn generators_list<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<Vec<GeneratorStatus>> {
+    let project = project(&window, &project_manager)?;
+    let generators = project.config.read().unwrap().generators.clone();
+    Ok(generators
+        .into_iter()
+        .map(|generator| GeneratorStatus {
+            stale: generator.is_stale(&project.root),
+            approved: generator.is_approved(&project.root),
+            generator,
+        })
+        .collect())
+}
+
+/// Allows or disallows the project's generators to run their current commands. Stored
+/// outside the project, and a generator whose command changes needs approving again.
+#[tauri::command]
+pub async fn generators_approve<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    approved: bool,
+) -> Result<()> {
+    let project = project(&window, &project_manager)?;
+    let commands: Vec<String> = project
+        .config
+        .read()
+        .unwrap()
+        .generators
+        .iter()
+        .map(FigureGenerator::command_text)
+        .collect();
+    set_commands_approved(
+        &project.root,
+        GENERATOR_COMMANDS,
+        commands.iter().map(String::as_str),
+        approved,
+    )?;
+    Ok(())
+}
+
+/// Runs the generator that produces `output`.
+#[tauri::command]
+pub async fn generators_run<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    output: PathBuf,
+) -> Result<GeneratorRun> {
+    let project = project(&window, &project_manager)?;
+    let generator = project
+        .config
+        .read()
+        .unwrap()
+        .generators
+        .iter()
+        .find(|g| g.output == output)
+        .cloned()
+        .ok_or(Error::UnrelatedPath)?;
+
+    let root = project.root.clone();
+    tokio::task::spawn_blocking(move || generator.run(&root))
+        .await
+        .map_err(|_| Error::Unknown)?
+        .map_err(Into::into)
+}
+
+/// Runs every approved generator whose output is missing or older than its script or
+/// inputs.
+#[tauri::command]
+pub async fn generators_run_stale<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<Vec<GeneratorRun>> {
+    let project = project(&window, &project_manager)?;
+    let generators = project.config.read().unwrap().generators.clone();
+
+    let root = project.root.clone();
+    tokio::task::spawn_blocking(move || {
+        generators
+            .iter()
+            .filter(|g| g.is_approved(&root) && g.is_stale(&root))
+            .map(|g| g.run(&root))
+            .collect::<std::io::Result<Vec<_>>>()
+    })
+    .await
+    .map_err(|_| Error::Unknown)?
+    .map_err(Into::into)
+}
+
+/// Enables rerunning generators when their script or inputs change. Each run emits
+/// a `generator_finished` event. Off by default, since it runs project-defined commands.
+#[tauri::command]
+pub async fn generators_watch<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    enabled: bool,
+) -> Result<()> {
+    let project = project(&window, &project_manager)?;
+    project.watch_generators.store(enabled, Ordering::Relaxed);
+    Ok(())
+}
@@ -1,9 +1,169 @@
-use super::{Result, Error, project_path};
+use super::{Result, Error, project, project_path};
+use crate::git::{
+    checkout_branch, commit_files, create_branch, file_at, file_hunks, init_repository,
+    list_branches, path_log, project_status, uncommitted_changes, GitBranch, GitCommit,
+    GitFileStatus, GitHunk, GitStatusEntry,
+};
 use git2::Repository;
 use tauri::{Runtime, State, WebviewWindow};
-use crate::project::ProjectManager;
+use crate::project::{ProjectManager, HISTORY_DIR};
 use std::sync::Arc;
 
+/// The git status of every changed, untracked, ignored or conflicted file of the
+/// project, for the badges of the file tree. Updates arrive as `git_status` events.
+#[tauri::command]
+pub async fn git_status<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<Vec<GitStatusEntry>> {
+    let project = project(&window, &project_manager)?;
+    Ok(project_status(&project.root)?)
+}
+
+/// The changed lines of a file against HEAD, for the gutter markers of the editor. Diffs
+/// `content`, the unsaved buffer, if given, and the file on disk otherwise.
+#[tauri::command]
+pub async fn git_diff_file<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    path: String,
+    content: Option<String>,
+) -> Result<Vec<GitHunk>> {
+    let (_, full_path) = project_path(&window, &project_manager, &path)?;
+    let content = match content {
+        Some(content) => content,
+        None => std::fs::read_to_string(&full_path)?,
+    };
+    Ok(file_hunks(&full_path, &content)?)
+}
+
+/// How many commits `git_log` returns by default.
+const LOG_LIMIT: usize = 100;
+
+/// The latest commits that changed `path`, or the project if not given, newest first.
+#[tauri::command]
+pub async fn git_log<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    path: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<GitCommit>> {
+    let full_path = match path {
+        Some(path) => project_path(&window, &project_manager, &path)?.1,
+        None => project(&window, &project_manager)?.root.clone(),
+    };
+    Ok(path_log(&full_path, limit.unwrap_or(LOG_LIMIT))?)
+}
+
+/// The content of a file at a revision, eg. a commit hash of `git_log`, or `None` if the
+/// file didn't exist in it.
+#[tauri::command]
+pub async fn git_show_file_at<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    path: String,
+    rev: String,
+) -> Result<Option<String>> {
+    let (_, full_path) = project_path(&window, &project_manager, &path)?;
+    Ok(file_at(&full_path, &rev)?)
+}
+
+/// The local branches of the project's repository.
+#[tauri::command]
+pub async fn git_branches<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<Vec<GitBranch>> {
+    let project = project(&window, &project_manager)?;
+    Ok(list_branches(&project.root)?)
+}
+
+/// Switches to the local branch `branch`. Refused while the editor has unsaved buffers
+/// or tracked files have uncommitted changes, as the checkout would replace them.
+#[tauri::command]
+pub async fn git_checkout<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    branch: String,
+) -> Result<()> {
+    let project = project(&window, &project_manager)?;
+    let unsaved = project.dirty_buffers.paths();
+    if !unsaved.is_empty() {
+        return Err(Error::UnsavedChanges(unsaved));
+    }
+    let uncommitted = uncommitted_changes(&project.root)?;
+    if !uncommitted.is_empty() {
+        return Err(Error::UncommittedChanges(uncommitted));
+    }
+    checkout_branch(&project.root, &branch)?;
+    // The watcher reports the changed files, but drop the world's copies right away.
+    project.world.lock().unwrap().clear_slots();
+    Ok(())
+}
+
+/// Creates the branch `name` at the current commit and switches to it. The files stay
+/// as they are, unsaved and uncommitted changes included.
+#[tauri::command]
+pub async fn git_create_branch<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    name: String,
+) -> Result<()> {
+    let project = project(&window, &project_manager)?;
+    create_branch(&project.root, &name)?;
+    Ok(())
+}
+
+/// Creates a repository in the project folder and commits its files, eg. for a new
+/// project. The local history is ignored unless the project has a `.gitignore` already.
+/// Refused if the project already is in a repository.
+#[tauri::command]
+pub async fn git_init<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<()> {
+    let project = project(&window, &project_manager)?;
+    if project.single_file.is_some() {
+        return Err(Error::SingleFile);
+    }
+    init_repository(&project.root)?;
+    let gitignore = project.root.join(".gitignore");
+    if !gitignore.exists() {
+        std::fs::write(&gitignore, format!("{}/\n", HISTORY_DIR))?;
+    }
+    let files = project_status(&project.root)?
+        .into_iter()
+        .filter(|entry| entry.status == GitFileStatus::Untracked)
+        .map(|entry| project.root.join(entry.path))
+        .collect::<Vec<_>>();
+    commit_files(&project.root, &files)?;
+    Ok(())
+}
+
+/// Whether the project commits every file the editor saves.
+#[tauri::command]
+pub async fn git_auto_commit<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<bool> {
+    let project = project(&window, &project_manager)?;
+    let enabled = project.config.read().unwrap().auto_commit;
+    Ok(enabled)
+}
+
+/// Turns committing saved files on or off and saves it to the project config.
+#[tauri::command]
+pub async fn git_set_auto_commit<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    enabled: bool,
+) -> Result<()> {
+    let project = project(&window, &project_manager)?;
+    project.config.write().unwrap().auto_commit = enabled;
+    project.save_config()?;
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn git_read_original_file<R: Runtime>(
     window: WebviewWindow<R>,
@@ -0,0 +1,88 @@
+use super::{project_path, Error, Result};
+use crate::project::{unified_diff, FileStamp, ProjectManager, Snapshot};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::{Runtime, State, WebviewWindow};
+
+/// The saved versions of a file, newest first.
+#[tauri::command]
+pub async fn history_list<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    path: PathBuf,
+) -> Result<Vec<Snapshot>> {
+    let (project, absolute_path) = project_path(&window, &project_manager, &path)?;
+    project
+        .history
+        .snapshots(&absolute_path)
+        .map_err(Into::into)
+}
+
+#[tauri::command]
+pub async fn history_read<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    path: PathBuf,
+    id: String,
+) -> Result<String> {
+    let (project, absolute_path) = project_path(&window, &project_manager, &path)?;
+    let content = project.history.read(&absolute_path, &id)?;
+    Ok(String::from_utf8_lossy(&content).into_owned())
+}
+
+/// A unified diff from a saved version to the file as it is on disk.
+#[tauri::command]
+pub async fn history_diff<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    path: PathBuf,
+    id: String,
+) -> Result<String> {
+    let (project, absolute_path) = project_path(&window, &project_manager, &path)?;
+    let old = project.history.read(&absolute_path, &id)?;
+    let current = match fs::read(&absolute_path) {
+        Ok(current) => current,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => vec![],
+        Err(e) => return Err(e.into()),
+    };
+    let name = path.display();
+    Ok(unified_diff(
+        &format!("{} ({})", name, &id[..id.len().min(8)]),
+        &format!("{} (current)", name),
+        &String::from_utf8_lossy(&old),
+        &String::from_utf8_lossy(&current),
+    ))
+}
+
+/// Writes a saved version back to the file, like a save. The current content is
+/// snapshotted first, so the restore can itself be undone. Open editors pick the change
+/// up from the watcher.
+#[tauri::command]
+pub async fn history_restore<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    path: PathBuf,
+    id: String,
+) -> Result<()> {
+    let (project, absolute_path) = project_path(&window, &project_manager, &path)?;
+    let content = project.history.read(&absolute_path, &id)?;
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        if let Ok(current) = fs::read(&absolute_path) {
+            project.history.record(&absolute_path, &current)?;
+        }
+        if let Some(parent) = absolute_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&absolute_path, &content)?;
+        let modified = fs::metadata(&absolute_path).and_then(|m| m.modified()).ok();
+        project
+            .stamps
+            .set(absolute_path.clone(), FileStamp::new(&content, modified));
+        project.dirty_buffers.clear(&absolute_path);
+        project.history.record(&absolute_path, &content)?;
+        Ok(())
+    })
+    .await
+    .map_err(|_| Error::Unknown)?
+}
@@ -1,22 +1,70 @@
+mod actions;
+mod analysis;
+mod assets;
+mod autosave;
 mod clipboard;
+mod collab;
+mod comments;
+mod docs;
+mod document;
+mod export;
 mod fs;
+mod fs_error;
+mod generators;
 mod git;
+mod history;
+mod palette;
 mod typst;
 mod playground;
+mod preview_server;
+mod recent;
+mod recovery;
+mod roots;
+mod search;
+mod session;
+mod settings;
+mod snippets;
+mod workspace;
 
 pub use self::typst::*;
+pub use actions::*;
+pub use analysis::*;
+pub use assets::*;
+pub use autosave::*;
 pub use clipboard::*;
+pub use collab::*;
+pub use comments::*;
+pub use docs::*;
+pub use document::*;
+pub use export::*;
 pub use fs::*;
+pub use fs_error::*;
+pub use generators::*;
 pub use git::*;
+pub use history::*;
+pub use palette::*;
 pub use playground::*;
+pub use preview_server::*;
+pub use recent::*;
+pub use recovery::*;
+pub use roots::*;
+pub use search::*;
+pub use session::*;
+pub use settings::*;
+pub use snippets::*;
+pub use workspace::*;
 
-use crate::project::{Project, ProjectManager};
+use crate::appdata::add_recent_project;
+use crate::menu::recent_projects_changed;
+use crate::ipc::events::emit_to_window;
+use crate::ipc::ProjectModel;
+use crate::project::{Project, ProjectConfigError, ProjectManager, WorkspaceEditError};
 use ::typst::diag::FileError;
 use serde::{Serialize, Serializer};
 use std::io;
-use std::path::{Component, Path, PathBuf};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tauri::{Runtime, State, WebviewWindow, Emitter};
+use tauri::{Manager, Runtime, State, WebviewWindow};
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -32,6 +80,69 @@ pub enum Error {
     Open(#[from] opener::OpenError),
     #[error("the provided path does not belong to the project")]
     UnrelatedPath,
+    #[error("only the opened file is available without a project")]
+    SingleFile,
+    #[error("git error: {}", .0.message())]
+    Git(#[from] git2::Error),
+    #[error("save the changes to {} first", display_paths(.0))]
+    UnsavedChanges(Vec<PathBuf>),
+    #[error("commit the changes to {} first", display_paths(.0))]
+    UncommittedChanges(Vec<PathBuf>),
+    #[error("collaboration error: {0}")]
+    Collab(String),
+    #[error("network error occurred")]
+    Network(#[from] reqwest::Error),
+    #[error("network access is disabled for this project")]
+    NetworkDisabled,
+    #[error("the download is larger than {limit} bytes")]
+    DownloadTooLarge { limit: u64 },
+    #[error("unsupported file format")]
+    UnsupportedFormat,
+    #[error("not supported on this platform")]
+    UnsupportedPlatform,
+    #[error("pandoc is not installed")]
+    PandocNotFound,
+    #[error("conversion failed: {0}")]
+    Conversion(String),
+    #[error("invalid search pattern")]
+    InvalidPattern,
+    #[error("invalid edit range")]
+    InvalidRange,
+    #[error("invalid page range")]
+    InvalidPageRange,
+    #[error("compilation failed: {0}")]
+    Compile(String),
+    #[error("{0}")]
+    WorkspaceEdit(#[from] WorkspaceEditError),
+    #[error("failed to save the project config")]
+    ProjectConfig(#[from] ProjectConfigError),
+    #[error("the file was modified on disk")]
+    Conflict(Box<FileConflict>),
+    #[error("{}", .0.message)]
+    FileAccess(Box<FileAccessError>),
+    #[error("not enough disk space to write {path:?}: {required} bytes required, {available} bytes available")]
+    InsufficientSpace {
+        path: PathBuf,
+        required: u64,
+        available: u64,
+    },
+}
+
+fn display_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Both versions of a file that changed on disk after the editor loaded it.
+#[derive(Serialize, Debug)]
+#[serde(tag = "kind", rename = "conflict")]
+pub struct FileConflict {
+    pub path: PathBuf,
+    pub disk: String,
+    pub local: String,
 }
 
 impl Serialize for Error {
@@ -39,7 +150,11 @@ impl Serialize for Error {
     where
         S: Serializer,
     {
-        serializer.serialize_str(self.to_string().as_ref())
+        match self {
+            Error::Conflict(conflict) => conflict.serialize(serializer),
+            Error::FileAccess(error) => error.serialize(serializer),
+            _ => serializer.serialize_str(self.to_string().as_ref()),
+        }
     }
 }
 
@@ -62,25 +177,61 @@ pub fn project_path<R: Runtime, P: AsRef<Path>>(
     let project = project_manager
         .get_project(window)
         .ok_or(Error::UnknownProject)?;
-    let root_len = project.root.as_os_str().len();
-    let mut out = project.root.to_path_buf();
-    for component in path.as_ref().components() {
-        match component {
-            Component::Prefix(_) => {}
-            Component::RootDir => {}
-            Component::CurDir => {}
-            Component::ParentDir => {
-                out.pop();
-                if out.as_os_str().len() < root_len {
-                    return Err(Error::UnrelatedPath);
-                }
-            }
-            Component::Normal(_) => out.push(component),
-        }
-    }
+    let out = project.resolve(path.as_ref()).ok_or(if project.single_file.is_some() {
+        Error::SingleFile
+    } else {
+        Error::UnrelatedPath
+    })?;
     Ok((project, out))
 }
 
+/// The project of the calling window, eg. for a tab whose project was opened before its
+/// page loaded.
+#[tauri::command]
+pub async fn project_current<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<Option<ProjectModel>> {
+    Ok(project_manager
+        .get_project(&window)
+        .map(|project| ProjectModel::new(&project)))
+}
+
+/// Opens a new window, as a tab on macOS, showing the project at `path` if given.
+#[tauri::command]
+pub async fn window_new_tab<R: Runtime>(window: WebviewWindow<R>, path: Option<PathBuf>) -> Result<()> {
+    crate::window::open_project_window(window.app_handle(), path, true).map_err(|e| {
+        log::error!("Failed to open window: {}", e);
+        Error::Unknown
+    })?;
+    Ok(())
+}
+
+/// Opens the project at `path` in a window of its own, with its own menu state, compile
+/// events and watcher. If a window already shows the project, that window is focused.
+#[tauri::command]
+pub async fn open_project_in_new_window<R: Runtime>(window: WebviewWindow<R>, path: PathBuf) -> Result<()> {
+    if let Some(existing) = crate::window::project_window(window.app_handle(), &path) {
+        existing.set_focus().map_err(|_| Error::Unknown)?;
+        return Ok(());
+    }
+    crate::window::open_project_window(window.app_handle(), Some(path), false).map_err(|e| {
+        log::error!("Failed to open window: {}", e);
+        Error::Unknown
+    })?;
+    Ok(())
+}
+
+/// The location of the `typstudio://` link this window was opened for, once its project
+/// has loaded.
+#[tauri::command]
+pub async fn deep_link_take_location<R: Runtime>(
+    window: WebviewWindow<R>,
+    pending: State<'_, crate::deeplink::PendingLocations>,
+) -> Result<Option<TypstJump>> {
+    Ok(pending.take(window.label()))
+}
+
 #[tauri::command]
 pub async fn open_project<R: Runtime>(
     window: WebviewWindow<R>,
@@ -89,7 +240,7 @@ pub async fn open_project<R: Runtime>(
 ) -> Result<()> {
     use crate::ipc::LoadingProgressEvent;
     
-    let _ = window.emit("loading_progress", LoadingProgressEvent {
+    emit_to_window(&window, "loading_progress", LoadingProgressEvent {
         stage: "Initializing".to_string(),
         progress: 10,
         message: Some("Opening project...".to_string()),
@@ -102,16 +253,16 @@ pub async fn open_project<R: Runtime>(
     
     let window_clone = window.clone();
     let progress_callback = Box::new(move |stage: String, progress: u32| {
-        let _ = window_clone.emit("loading_progress", LoadingProgressEvent {
+        emit_to_window(&window_clone, "loading_progress", LoadingProgressEvent {
             stage: "Loading fonts".to_string(),
             progress,
             message: Some(stage),
         });
     });
 
-    let project = Arc::new(Project::load_from_path(path, Some(progress_callback)));
+    let project = Arc::new(Project::open(path.clone(), Some(progress_callback)));
     
-    let _ = window.emit("loading_progress", LoadingProgressEvent {
+    emit_to_window(&window, "loading_progress", LoadingProgressEvent {
         stage: "Finalizing".to_string(),
         progress: 95,
         message: Some("Finalizing...".to_string()),
@@ -120,8 +271,12 @@ pub async fn open_project<R: Runtime>(
     tokio::time::sleep(std::time::Duration::from_millis(50)).await;
     
     project_manager.set_project(&window, Some(project));
+    match add_recent_project(&path) {
+        Ok(projects) => recent_projects_changed(&window, &projects),
+        Err(e) => log::warn!("Failed to update recent projects: {}", e),
+    }
     
-    let _ = window.emit("loading_progress", LoadingProgressEvent {
+    emit_to_window(&window, "loading_progress", LoadingProgressEvent {
         stage: "Ready".to_string(),
         progress: 100,
         message: Some("Ready".to_string()),
@@ -0,0 +1,73 @@
+use super::{generators_run, project, workspace_edit_undo, Error, Result};
+use crate::ipc::events::emit_to_window;
+use crate::menu::{menu_context, run_menu_action};
+use crate::palette::{palette_action, palette_commands, PaletteAction, PaletteCommand};
+use crate::project::ProjectManager;
+use serde_json::Value;
+use std::sync::Arc;
+use tauri::{Manager, Runtime, State, WebviewWindow};
+
+/// Lists every action the command palette can run, with its current enablement.
+#[tauri::command]
+pub async fn commands_list<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<Vec<PaletteCommand>> {
+    let project = project_manager.get_project(&window);
+    let context = menu_context(window.app_handle());
+    Ok(palette_commands(project.as_deref(), &context))
+}
+
+/// Runs a palette command. Toggles take an optional `{ "value": bool }`, flipping the
+/// toggle without one, and return the new value; generators return their run, and undoing
+/// a refactor the files it reverted.
+#[tauri::command]
+pub async fn commands_execute<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    id: String,
+    args: Option<Value>,
+) -> Result<Value> {
+    match palette_action(&id).ok_or(Error::Unknown)? {
+        PaletteAction::Menu(id) => {
+            run_menu_action(window.app_handle(), window.clone(), &id);
+            Ok(Value::Null)
+        }
+        PaletteAction::Toggle(name) => {
+            let project = project(&window, &project_manager)?;
+            let default = *project
+                .config
+                .read()
+                .unwrap()
+                .toggles
+                .get(&name)
+                .ok_or(Error::Unknown)?;
+            let mut preview = project.preview_inputs.write().unwrap();
+            let current = preview.toggles.get(&name).copied().unwrap_or(default);
+            let value = args
+                .as_ref()
+                .and_then(|args| args.get("value"))
+                .and_then(Value::as_bool)
+                .unwrap_or(!current);
+            preview.toggles.insert(name, value);
+            drop(preview);
+            project.world.lock().unwrap().bump_revision();
+            // The preview shows the document with the toggle flipped.
+            emit_to_window(&window, "trigger_compile", ());
+            Ok(Value::Bool(value))
+        }
+        PaletteAction::Document(action) => {
+            // The editor owns the selection, so it applies the action itself.
+            emit_to_window(&window, "document_action", action);
+            Ok(Value::Null)
+        }
+        PaletteAction::UndoWorkspaceEdit => {
+            let undone = workspace_edit_undo(window, project_manager).await?;
+            serde_json::to_value(undone).map_err(|_| Error::Unknown)
+        }
+        PaletteAction::Generator(output) => {
+            let run = generators_run(window, project_manager, output).await?;
+            serde_json::to_value(run).map_err(|_| Error::Unknown)
+        }
+    }
+}
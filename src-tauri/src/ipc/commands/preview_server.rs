@@ -0,0 +1,45 @@
+use super::{project, Result};
+use crate::preview_server::{PreviewServer, PreviewServerInfo};
+use crate::project::ProjectManager;
+use std::sync::Arc;
+use tauri::{Runtime, State, WebviewWindow};
+
+/// Serves the preview of the project to browsers on this machine, or on the local network
+/// with `lan`, on `port` or any free one, and returns the link of the viewer. A running
+/// server is replaced.
+#[tauri::command]
+pub async fn preview_server_start<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    port: Option<u16>,
+    lan: Option<bool>,
+) -> Result<PreviewServerInfo> {
+    let project = project(&window, &project_manager)?;
+    // Frees the port before binding it again.
+    project.preview_server.lock().unwrap().take();
+    let server = PreviewServer::start(&project, port, lan.unwrap_or(false)).await?;
+    let info = server.info();
+    *project.preview_server.lock().unwrap() = Some(server);
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn preview_server_stop<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<()> {
+    let project = project(&window, &project_manager)?;
+    project.preview_server.lock().unwrap().take();
+    Ok(())
+}
+
+/// The running preview server of the project, if any.
+#[tauri::command]
+pub async fn preview_server_info<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<Option<PreviewServerInfo>> {
+    let project = project(&window, &project_manager)?;
+    let info = project.preview_server.lock().unwrap().as_ref().map(PreviewServer::info);
+    Ok(info)
+}
@@ -0,0 +1,125 @@
+use super::{project, Error, Result};
+use crate::appdata::{
+    add_recent_project, import_recent_projects, pin_recent_project, recent_projects,
+    remove_recent_project, RecentProject,
+};
+use crate::export::run_export_hooks;
+use crate::ipc::events::emit_to_window;
+use crate::menu::{open_export, rebuild_menu, recent_projects_changed};
+use crate::project::{add_recent_export, recent_exports, Project, ProjectManager, RecentExport};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{Runtime, State, WebviewWindow};
+
+/// Pinned projects first, then the most recently opened.
+#[tauri::command]
+pub async fn recent_projects_list() -> Result<Vec<RecentProject>> {
+    recent_projects().map_err(Into::into)
+}
+
+#[tauri::command]
+pub async fn recent_projects_add<R: Runtime>(
+    window: WebviewWindow<R>,
+    path: PathBuf,
+) -> Result<Vec<RecentProject>> {
+    let path = std::fs::canonicalize(&path).unwrap_or(path);
+    let projects = add_recent_project(&path)?;
+    recent_projects_changed(&window, &projects);
+    Ok(projects)
+}
+
+#[tauri::command]
+pub async fn recent_projects_remove<R: Runtime>(
+    window: WebviewWindow<R>,
+    path: PathBuf,
+) -> Result<Vec<RecentProject>> {
+    let projects = remove_recent_project(&path)?;
+    recent_projects_changed(&window, &projects);
+    Ok(projects)
+}
+
+/// Pinned projects stay listed first and are kept when the list is cleared.
+#[tauri::command]
+pub async fn recent_projects_pin<R: Runtime>(
+    window: WebviewWindow<R>,
+    path: PathBuf,
+    pinned: bool,
+) -> Result<Vec<RecentProject>> {
+    let projects = pin_recent_project(&path, pinned)?;
+    recent_projects_changed(&window, &projects);
+    Ok(projects)
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LegacyRecentProject {
+    path: PathBuf,
+    /// Unix time in milliseconds.
+    last_opened: i64,
+}
+
+/// Adds the recent projects the frontend kept in local storage before the backend owned
+/// the list. Projects already listed are left as they are.
+#[tauri::command]
+pub async fn recent_projects_import<R: Runtime>(
+    window: WebviewWindow<R>,
+    projects: Vec<LegacyRecentProject>,
+) -> Result<Vec<RecentProject>> {
+    let imported: Vec<(PathBuf, i64)> = projects
+        .into_iter()
+        .map(|p| (p.path, p.last_opened))
+        .collect();
+    let projects = import_recent_projects(&imported)?;
+    recent_projects_changed(&window, &projects);
+    Ok(projects)
+}
+
+/// Remembers a successful export of the window's project, updates Recent Exports and
+/// runs the project's export hooks. Failing to record it doesn't fail the export.
+pub fn record_export<R: Runtime>(
+    window: &WebviewWindow<R>,
+    project: &Project,
+    path: &Path,
+    format: &str,
+) {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    match add_recent_export(&project.root, &path, format) {
+        Ok(exports) => {
+            emit_to_window(window, "recent_exports_changed", exports);
+            if let Err(e) = rebuild_menu(window) {
+                log::error!("Failed to rebuild menu: {}", e);
+            }
+        }
+        Err(e) => log::warn!("Failed to update recent exports: {}", e),
+    }
+    run_export_hooks(window, project, &path, format);
+}
+
+/// The project's latest exports, newest first.
+#[tauri::command]
+pub async fn recent_exports_list<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<Vec<RecentExport>> {
+    let project = project(&window, &project_manager)?;
+    recent_exports(&project.root).map_err(Into::into)
+}
+
+/// Opens one of the project's recent exports with the system viewer, or shows it in the
+/// file manager if `reveal` is set.
+#[tauri::command]
+pub async fn recent_exports_open<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    path: PathBuf,
+    reveal: Option<bool>,
+) -> Result<()> {
+    let project = project(&window, &project_manager)?;
+    let exports = recent_exports(&project.root)?;
+    let export = exports
+        .iter()
+        .find(|e| e.path == path)
+        .ok_or(Error::UnrelatedPath)?;
+    open_export(&export.path, reveal.unwrap_or(false))?;
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use super::{project, project_path, Error, Result};
+use crate::project::{ProjectManager, RecoverableBuffer};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::{Runtime, State, WebviewWindow};
+
+/// Unsaved buffers journaled before the app last quit or crashed, with a diff against
+/// the file on disk. Buffers that are dirty in this session are left out.
+#[tauri::command]
+pub async fn recover_unsaved_changes<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<Vec<RecoverableBuffer>> {
+    let project = project(&window, &project_manager)?;
+    tokio::task::spawn_blocking(move || -> Result<Vec<RecoverableBuffer>> {
+        let buffers = project.dirty_buffers.recovery.recoverable(&project.root)?;
+        Ok(buffers
+            .into_iter()
+            .filter(|buffer| {
+                let relative = buffer.path.strip_prefix("/").unwrap_or(&buffer.path);
+                !project.dirty_buffers.contains(&project.root.join(relative))
+            })
+            .collect())
+    })
+    .await
+    .map_err(|_| Error::Unknown)?
+}
+
+/// Drops the journaled changes of `paths` once they were restored or declined.
+#[tauri::command]
+pub async fn recover_discard<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    paths: Vec<PathBuf>,
+) -> Result<()> {
+    for path in paths {
+        let (project, absolute_path) = project_path(&window, &project_manager, &path)?;
+        if !project.dirty_buffers.contains(&absolute_path) {
+            project.dirty_buffers.discard_journal(&absolute_path);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use super::{project, Result};
+use crate::ipc::events::emit_to_window;
+use crate::ipc::FSRefreshEvent;
+use crate::project::{attached_roots, save_attached_roots, Project, ProjectManager};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::{Runtime, State, WebviewWindow};
+
+/// Saves and applies the changed attached folders, then reloads the file tree.
+fn roots_changed<R: Runtime>(
+    window: &WebviewWindow<R>,
+    project_manager: &ProjectManager<R>,
+    project: &Project,
+    attached: &[PathBuf],
+) -> Result<Vec<PathBuf>> {
+    save_attached_roots(&project.root, attached)?;
+    project.config.read().unwrap().apply(project);
+    project_manager.refresh_watches();
+    emit_to_window(window, "fs_refresh", FSRefreshEvent { path: PathBuf::new() });
+    Ok(project.roots.read().unwrap().clone())
+}
+
+/// The folders files are looked up in: the project's own, then the attached ones.
+#[tauri::command]
+pub async fn project_roots<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<Vec<PathBuf>> {
+    let project = project(&window, &project_manager)?;
+    let roots = project.roots.read().unwrap().clone();
+    Ok(roots)
+}
+
+/// Attaches a folder to the project, eg. a shared styles repository. Its files appear
+/// wherever the project has none of the same path. The only way to attach one, as
+/// projects can't attach folders themselves.
+#[tauri::command]
+pub async fn project_attach_root<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    path: PathBuf,
+) -> Result<Vec<PathBuf>> {
+    let project = project(&window, &project_manager)?;
+    let path = fs::canonicalize(path)?;
+    if !fs::metadata(&path)?.is_dir() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "not a directory").into());
+    }
+    let mut attached = attached_roots(&project.root)?;
+    if path != project.root && !attached.contains(&path) {
+        attached.push(path);
+    }
+    roots_changed(&window, &project_manager, &project, &attached)
+}
+
+#[tauri::command]
+pub async fn project_detach_root<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    path: PathBuf,
+) -> Result<Vec<PathBuf>> {
+    let project = project(&window, &project_manager)?;
+    let mut attached = attached_roots(&project.root)?;
+    attached.retain(|root| {
+        let resolved = fs::canonicalize(root).unwrap_or_else(|_| root.clone());
+        *root != path && resolved != path
+    });
+    roots_changed(&window, &project_manager, &project, &attached)
+}
@@ -0,0 +1,259 @@
+use super::{project_path, Error, Result};
+use crate::ipc::events::emit_to_window;
+use crate::ipc::{SearchFinishedEvent, SearchResultEvent};
+use crate::project::{FileWrite, Project, ProjectManager};
+use crate::search::{
+    build_matcher, is_probably_binary, replace_text, search_text, PathFilter, Replacement,
+    SearchOptions, MAX_SEARCH_FILE_SIZE,
+};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tauri::{Runtime, State, WebviewWindow};
+
+const DEFAULT_MAX_RESULTS: usize = 10_000;
+
+/// Searches all project files in parallel. Matches are streamed per file through
+/// `search_result` events, followed by a single `search_finished` event. Starting a
+/// new search cancels the previous one.
+#[tauri::command]
+pub async fn search_project<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    query: String,
+    options: SearchOptions,
+) -> Result<u64> {
+    let project = super::project(&window, &project_manager)?;
+    let matcher = build_matcher(&query, &options).map_err(|_| Error::InvalidPattern)?;
+    let filter = PathFilter::new(&options.include, &options.exclude).map_err(|_| Error::InvalidPattern)?;
+    let max_results = options.max_results.unwrap_or(DEFAULT_MAX_RESULTS);
+
+    let search_id = project.current_search_id.fetch_add(1, Ordering::SeqCst) + 1;
+    let index = super::fs::file_index(&project);
+
+    tokio::task::spawn_blocking(move || {
+        let total = AtomicUsize::new(0);
+        let files = AtomicUsize::new(0);
+        let truncated = AtomicBool::new(false);
+        let cancelled = || project.current_search_id.load(Ordering::Relaxed) != search_id;
+
+        index.par_iter().filter(|p| filter.matches(p)).for_each(|relative| {
+            if cancelled() || truncated.load(Ordering::Relaxed) {
+                return;
+            }
+            let path = project.root.join(relative);
+            if fs::metadata(&path).map(|m| m.len() > MAX_SEARCH_FILE_SIZE).unwrap_or(true) {
+                return;
+            }
+            let Ok(content) = fs::read(&path) else {
+                return;
+            };
+            if is_probably_binary(&content) {
+                return;
+            }
+            let Ok(text) = String::from_utf8(content) else {
+                return;
+            };
+
+            let remaining = max_results.saturating_sub(total.load(Ordering::Relaxed));
+            let matches = search_text(&text, &matcher, remaining);
+            if matches.is_empty() {
+                return;
+            }
+            if total.fetch_add(matches.len(), Ordering::Relaxed) + matches.len() >= max_results {
+                truncated.store(true, Ordering::Relaxed);
+            }
+            files.fetch_add(1, Ordering::Relaxed);
+            emit_to_window(
+                &window,
+                "search_result",
+                SearchResultEvent {
+                    search_id,
+                    path: format!("/{}", relative),
+                    matches,
+                },
+            );
+        });
+
+        emit_to_window(
+            &window,
+            "search_finished",
+            SearchFinishedEvent {
+                search_id,
+                files: files.load(Ordering::Relaxed),
+                matches: total.load(Ordering::Relaxed),
+                truncated: truncated.load(Ordering::Relaxed),
+                cancelled: cancelled(),
+            },
+        );
+    });
+
+    Ok(search_id)
+}
+
+/// Cancels the running project search, if any.
+#[tauri::command]
+pub async fn search_cancel<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<()> {
+    let project = super::project(&window, &project_manager)?;
+    project.current_search_id.fetch_add(1, Ordering::SeqCst);
+    Ok(())
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReplacePreviewFile {
+    path: String,
+    replacements: Vec<Replacement>,
+}
+
+struct PlannedReplace {
+    path: String,
+    content: String,
+    replacements: Vec<Replacement>,
+    /// Whether the file has unsaved changes, which the replacements apply to.
+    buffered: bool,
+}
+
+/// Computes the replacements for every matching project file without writing anything.
+/// Files with unsaved changes are replaced in the editor's text, not in the file.
+fn plan_replace(
+    project: &Project,
+    query: &str,
+    replacement: &str,
+    options: &SearchOptions,
+) -> Result<Vec<PlannedReplace>> {
+    let matcher = build_matcher(query, options).map_err(|_| Error::InvalidPattern)?;
+    let filter = PathFilter::new(&options.include, &options.exclude).map_err(|_| Error::InvalidPattern)?;
+    let index = super::fs::file_index(project);
+
+    let mut planned: Vec<PlannedReplace> = index
+        .par_iter()
+        .filter(|p| filter.matches(p))
+        .filter_map(|relative| {
+            let path = project.root.join(relative);
+            let buffer = project.dirty_buffers.get(&path);
+            let buffered = buffer.is_some();
+            let text = match buffer {
+                Some(text) => text,
+                None => {
+                    if fs::metadata(&path).map(|m| m.len() > MAX_SEARCH_FILE_SIZE).unwrap_or(true) {
+                        return None;
+                    }
+                    let content = fs::read(&path).ok()?;
+                    if is_probably_binary(&content) {
+                        return None;
+                    }
+                    String::from_utf8(content).ok()?
+                }
+            };
+            let (content, replacements) = replace_text(&text, &matcher, replacement, options.regex);
+            (!replacements.is_empty()).then(|| PlannedReplace {
+                path: format!("/{}", relative),
+                content,
+                replacements,
+                buffered,
+            })
+        })
+        .collect();
+    planned.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(planned)
+}
+
+/// Dry run of `search_replace_apply`, listing each replacement per file.
+#[tauri::command]
+pub async fn search_replace_preview<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    query: String,
+    replacement: String,
+    options: SearchOptions,
+) -> Result<Vec<ReplacePreviewFile>> {
+    let project = super::project(&window, &project_manager)?;
+    let planned = tokio::task::spawn_blocking(move || plan_replace(&project, &query, &replacement, &options))
+        .await
+        .map_err(|_| Error::Unknown)??;
+
+    Ok(planned
+        .into_iter()
+        .map(|p| ReplacePreviewFile {
+            path: p.path,
+            replacements: p.replacements,
+        })
+        .collect())
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReplacedBuffer {
+    path: String,
+    content: String,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct ReplaceApplied {
+    /// Files written to disk, which open editors should reload.
+    written: Vec<String>,
+    /// Files with unsaved changes, replaced in their buffer only. Editors should take
+    /// the new content, which is saved like any other edit.
+    buffers: Vec<ReplacedBuffer>,
+}
+
+/// Replaces all matches, writing files as a single undoable workspace edit. If `paths`
+/// is given, only those files are changed. Files with unsaved changes are replaced in
+/// their buffer instead, so the unsaved changes are kept; undoing those is left to the
+/// editor. The world is updated already.
+#[tauri::command]
+pub async fn search_replace_apply<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    query: String,
+    replacement: String,
+    options: SearchOptions,
+    paths: Option<Vec<String>>,
+) -> Result<ReplaceApplied> {
+    let project = super::project(&window, &project_manager)?;
+    let label = format!("Replace \"{}\" with \"{}\"", query, replacement);
+
+    let planned = {
+        let project = project.clone();
+        tokio::task::spawn_blocking(move || plan_replace(&project, &query, &replacement, &options))
+            .await
+            .map_err(|_| Error::Unknown)??
+    };
+
+    let mut applied = ReplaceApplied::default();
+    let mut writes = vec![];
+    for p in planned {
+        if paths.as_ref().is_some_and(|paths| !paths.contains(&p.path)) {
+            continue;
+        }
+        let (_, absolute) = project_path(&window, &project_manager, &p.path)?;
+        if p.buffered {
+            project.dirty_buffers.record(absolute, p.content.clone());
+            let world = project.world.lock().unwrap();
+            if let Err(e) = world.slot_update(&p.path, Some(p.content.clone())) {
+                log::warn!("unable to update slot for {}: {:?}", p.path, e);
+            }
+            applied.buffers.push(ReplacedBuffer {
+                path: p.path,
+                content: p.content,
+            });
+        } else {
+            applied.written.push(p.path.clone());
+            writes.push(FileWrite {
+                path: PathBuf::from(&p.path),
+                absolute,
+                content: p.content,
+            });
+        }
+    }
+
+    if !writes.is_empty() {
+        project.journal.apply(&project, label, writes)?;
+    }
+    Ok(applied)
+}
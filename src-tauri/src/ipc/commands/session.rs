@@ -0,0 +1,26 @@
+use super::{project, Result};
+use crate::project::{ProjectManager, ProjectSession};
+use std::sync::Arc;
+use tauri::{Runtime, State, WebviewWindow};
+
+/// The session saved for the window's project, or an empty one.
+#[tauri::command]
+pub async fn session_get<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<ProjectSession> {
+    let project = project(&window, &project_manager)?;
+    ProjectSession::load(&project.root).map_err(Into::into)
+}
+
+/// Saves the editor state of the window's project, eg. whenever a file is opened and
+/// before the window closes.
+#[tauri::command]
+pub async fn session_save<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    session: ProjectSession,
+) -> Result<()> {
+    let project = project(&window, &project_manager)?;
+    session.save(&project.root).map_err(Into::into)
+}
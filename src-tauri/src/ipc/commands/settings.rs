@@ -0,0 +1,63 @@
+use super::{Error, Result};
+use crate::appdata::{export_bundle, import_bundle, SettingsBundle};
+use crate::ipc::long_operations::{
+    long_operation_settings, reload_long_operation_settings, set_long_operation_settings,
+    LongOperationSettings,
+};
+use crate::settings::{app_settings, reload_app_settings, set_app_settings, AppSettings};
+use crate::snippets::reload_user_snippets;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, Runtime};
+
+#[tauri::command]
+pub async fn settings_get() -> Result<AppSettings> {
+    Ok(app_settings())
+}
+
+/// Saves the settings and sends them to every window as `settings_changed`.
+#[tauri::command]
+pub async fn settings_set<R: Runtime>(app: AppHandle<R>, settings: AppSettings) -> Result<()> {
+    set_app_settings(settings.clone())?;
+    let _ = app.emit("settings_changed", settings);
+    Ok(())
+}
+
+/// Writes the user's settings, keybindings, snippets, templates and export profiles
+/// to a single JSON file. Secrets are left out.
+#[tauri::command]
+pub async fn settings_export(path: PathBuf) -> Result<()> {
+    let bundle = export_bundle()?;
+    let json = serde_json::to_string_pretty(&bundle)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    fs::write(&path, json).map_err(Into::<Error>::into)
+}
+
+/// Imports a file written by `settings_export`, returning the imported sections.
+#[tauri::command]
+pub async fn settings_import<R: Runtime>(app: AppHandle<R>, path: PathBuf) -> Result<Vec<String>> {
+    let json = fs::read_to_string(&path)?;
+    let bundle: SettingsBundle = serde_json::from_str(&json)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let sections = import_bundle(bundle)?;
+    if sections.iter().any(|section| section == "snippets") {
+        reload_user_snippets();
+    }
+    if sections.iter().any(|section| section == "notifications") {
+        reload_long_operation_settings()?;
+    }
+    let _ = app.emit("settings_changed", reload_app_settings()?);
+    Ok(sections)
+}
+
+#[tauri::command]
+pub async fn long_operation_settings_get() -> Result<LongOperationSettings> {
+    Ok(long_operation_settings())
+}
+
+/// Sets when and how the user is told that a long compile or export finished.
+#[tauri::command]
+pub async fn long_operation_settings_set(settings: LongOperationSettings) -> Result<()> {
+    set_long_operation_settings(settings).map_err(Into::into)
+}
@@ -0,0 +1,95 @@
+use super::{project, Error, Result};
+use crate::project::ProjectManager;
+use crate::snippets::{
+    all_snippets, save_user_snippets, unique_snippet_id, user_snippets, Snippet, SnippetEntry,
+    SnippetScope,
+};
+use std::sync::Arc;
+use tauri::{Runtime, State, WebviewWindow};
+
+/// Changes the snippets of `scope` with `f` and saves them.
+fn edit_snippets<R: Runtime, T>(
+    window: &WebviewWindow<R>,
+    project_manager: &State<'_, Arc<ProjectManager<R>>>,
+    scope: SnippetScope,
+    f: impl FnOnce(&mut Vec<Snippet>) -> Result<T>,
+) -> Result<T> {
+    match scope {
+        SnippetScope::User => {
+            let mut snippets = user_snippets()?;
+            let result = f(&mut snippets)?;
+            save_user_snippets(&snippets)?;
+            Ok(result)
+        }
+        SnippetScope::Project => {
+            let project = project(window, project_manager)?;
+            let result = f(&mut project.config.write().unwrap().snippets)?;
+            project.save_config()?;
+            Ok(result)
+        }
+    }
+}
+
+/// The user's snippets and the open project's, with their placeholders.
+#[tauri::command]
+pub async fn snippets_list<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<Vec<SnippetEntry>> {
+    let project = project_manager.get_project(&window);
+    Ok(all_snippets(project.as_deref()))
+}
+
+#[tauri::command]
+pub async fn snippets_create<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    scope: SnippetScope,
+    prefix: String,
+    description: Option<String>,
+    body: String,
+) -> Result<SnippetEntry> {
+    let snippet = edit_snippets(&window, &project_manager, scope, |snippets| {
+        let snippet = Snippet {
+            id: unique_snippet_id(snippets, &prefix),
+            prefix,
+            description: description.unwrap_or_default(),
+            body,
+        };
+        snippets.push(snippet.clone());
+        Ok(snippet)
+    })?;
+    Ok(SnippetEntry::new(snippet, scope))
+}
+
+/// Replaces the snippet with the id of `snippet`.
+#[tauri::command]
+pub async fn snippets_update<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    scope: SnippetScope,
+    snippet: Snippet,
+) -> Result<SnippetEntry> {
+    edit_snippets(&window, &project_manager, scope, |snippets| {
+        let existing = snippets
+            .iter_mut()
+            .find(|s| s.id == snippet.id)
+            .ok_or(Error::Unknown)?;
+        *existing = snippet.clone();
+        Ok(())
+    })?;
+    Ok(SnippetEntry::new(snippet, scope))
+}
+
+#[tauri::command]
+pub async fn snippets_delete<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    scope: SnippetScope,
+    id: String,
+) -> Result<()> {
+    edit_snippets(&window, &project_manager, scope, |snippets| {
+        snippets.retain(|snippet| snippet.id != id);
+        Ok(())
+    })
+}
@@ -1,25 +1,116 @@
-use super::{Error, Result};
-use crate::compiler::{CompileRequest, Compiler};
-use crate::ipc::commands::project;
-use crate::ipc::model::TypstRenderResponse;
-use crate::project::ProjectManager;
+use super::{ensure_disk_space, Error, Result};
+use crate::analysis::top_level_imports;
+use crate::compiler::{
+    compile_with_inputs, detached_engine, project_diagnostics, render_page, render_tile_png,
+    tile_bounds, toggle_inputs, CompileRequest, Compiler, InputsWorld, PreviewDecorations,
+    PreviewTheme, SnippetWorld, VisiblePages, SEED_INPUT,
+};
+use crate::document::{
+    document_text, page_links, positioned_text, sync_map, PageLink, PositionedText, Rect,
+    SyncAnchor, TextFormat,
+};
+use crate::export::{
+    compile_html, open_print_dialog, parse_page_ranges, pdf_bytes, write_epub, write_html,
+    write_pdf, write_pdf_pages, write_png_zip, write_svg_zip, HtmlExport,
+};
+use crate::ipc::commands::{project, project_path, record_export};
+use crate::engine::TypstEngine;
+use crate::ipc::model::{
+    ProjectDiagnosticsEvent, TypstFilePreview, TypstRenderResponse, TypstSnippetResponse, TypstTileResponse,
+};
+use crate::project::{Project, ProjectManager, ProjectWorld};
+use crate::snippets::{all_snippets, snippet_matches, SnippetEntry};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
 use log::debug;
 use serde::Serialize;
 use serde_repr::Serialize_repr;
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tauri::Runtime;
+use typst::layout::PagedDocument;
+use typst::syntax::{FileId, VirtualPath};
 use typst::World;
 use typst_ide::{Completion, CompletionKind};
 
+/// A place in a source file. Lines and columns are 1-based.
 #[derive(Serialize, Debug)]
 pub struct TypstJump {
     filepath: String,
+    /// The start of the clicked syntax node.
     start: Option<(usize, usize)>,
+    /// The end of the clicked syntax node.
     end: Option<(usize, usize)>,
+    /// Where exactly the click was, within `start` and `end`.
+    cursor: Option<(usize, usize)>,
     text: Option<String>,
     offset: Option<usize>,
     node_kind: Option<String>,
+    /// The element around the click, for highlighting all of it.
+    element: Option<TypstJumpElement>,
+}
+
+impl TypstJump {
+    /// A jump to a 1-based line and column of `filepath`.
+    pub fn at(filepath: String, line: usize, column: usize) -> Self {
+        Self {
+            filepath,
+            start: Some((line, column)),
+            end: Some((line, column)),
+            cursor: Some((line, column)),
+            text: None,
+            offset: None,
+            node_kind: None,
+            element: None,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TypstElementKind {
+    Heading,
+    Figure,
+    Equation,
+    Text,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TypstJumpElement {
+    kind: TypstElementKind,
+    start: (usize, usize),
+    end: (usize, usize),
+}
+
+/// The innermost heading, figure or equation around `offset` with its range, or else the
+/// text at `offset`.
+fn enclosing_element(
+    source: &typst::syntax::Source,
+    offset: usize,
+) -> Option<(TypstElementKind, std::ops::Range<usize>)> {
+    use typst::syntax::{ast, LinkedNode, Side, SyntaxKind};
+    let leaf = LinkedNode::new(source.root()).leaf_at(offset, Side::After)?;
+    let mut node = Some(&leaf);
+    while let Some(current) = node {
+        let kind = match current.kind() {
+            SyntaxKind::Heading => Some(TypstElementKind::Heading),
+            SyntaxKind::Equation => Some(TypstElementKind::Equation),
+            SyntaxKind::FuncCall => current
+                .get()
+                .cast::<ast::FuncCall>()
+                .filter(|call| {
+                    matches!(call.callee(), ast::Expr::Ident(ident) if ident.as_str() == "figure")
+                })
+                .map(|_| TypstElementKind::Figure),
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            return Some((kind, current.range()));
+        }
+        node = current.parent();
+    }
+    (leaf.kind() == SyntaxKind::Text).then(|| (TypstElementKind::Text, leaf.range()))
 }
 
 #[derive(Serialize_repr, Debug)]
@@ -31,6 +122,7 @@ pub enum TypstCompletionKind {
     Constant = 4,
     Symbol = 5,
     Type = 6,
+    Snippet = 7,
 }
 
 #[derive(Serialize, Debug)]
@@ -70,12 +162,17 @@ impl From<Completion> for TypstCompletion {
 pub async fn typst_compile<R: Runtime>(
     window: tauri::WebviewWindow<R>,
     compiler: tauri::State<'_, Arc<Compiler<R>>>,
+    project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
     path: PathBuf,
     content: String,
     main_path: Option<PathBuf>,
     request_id: u64,
 ) -> Result<()> {
     log::info!("[Compile] path={:?}, main_path={:?}, request_id={}", path, main_path, request_id);
+    // The compiled content is the editor's buffer, which autosave keeps safe.
+    if let Ok((project, absolute_path)) = project_path(&window, &project_manager, &path) {
+        project.dirty_buffers.record(absolute_path, content.clone());
+    }
     compiler.update(CompileRequest {
         path,
         content,
@@ -87,44 +184,402 @@ pub async fn typst_compile<R: Runtime>(
     Ok(())
 }
 
+/// Sets the app theme exposed to preview compiles through `sys.inputs`, or clears it.
+/// Exports never see these inputs. Takes effect on the next compile.
+#[tauri::command]
+pub async fn typst_set_preview_theme<R: Runtime>(
+    window: tauri::WebviewWindow<R>,
+    project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
+    theme: Option<PreviewTheme>,
+) -> Result<()> {
+    let project = project(&window, &project_manager)?;
+    project.preview_inputs.write().unwrap().theme = theme;
+    project.world.lock().unwrap().bump_revision();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn typst_get_preview_decorations<R: Runtime>(
+    window: tauri::WebviewWindow<R>,
+    project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
+) -> Result<PreviewDecorations> {
+    let project = project(&window, &project_manager)?;
+    let decorations = project.preview_decorations.read().unwrap().clone();
+    Ok(decorations)
+}
+
+/// Sets the guides drawn over the preview pages. They are returned as an overlay with
+/// each rendered page, so the pages are only rerendered, not recompiled.
+#[tauri::command]
+pub async fn typst_set_preview_decorations<R: Runtime>(
+    window: tauri::WebviewWindow<R>,
+    project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
+    decorations: PreviewDecorations,
+) -> Result<()> {
+    let project = project(&window, &project_manager)?;
+    *project.preview_decorations.write().unwrap() = decorations.clone();
+    crate::ipc::events::emit_to_window(&window, "preview_decorations_changed", decorations);
+    project.render_queue.reschedule(&project, Arc::new(window));
+    Ok(())
+}
+
+/// Renders the preview pages with inverted colors, keeping the colors of images, or
+/// back in their own colors. Only the rendering changes, so nothing is recompiled.
+#[tauri::command]
+pub async fn typst_set_dark_preview<R: Runtime>(
+    window: tauri::WebviewWindow<R>,
+    project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
+    enabled: bool,
+) -> Result<()> {
+    let project = project(&window, &project_manager)?;
+    project
+        .renderer
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .set_dark(enabled);
+    project.render_queue.reschedule(&project, Arc::new(window));
+    Ok(())
+}
+
+#[derive(Serialize, Debug)]
+pub struct PreviewToggle {
+    name: String,
+    default: bool,
+    /// The value used by the preview.
+    value: bool,
+}
+
+/// Lists the toggles defined in the project config with their current preview values.
+#[tauri::command]
+pub async fn typst_list_toggles<R: Runtime>(
+    window: tauri::WebviewWindow<R>,
+    project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
+) -> Result<Vec<PreviewToggle>> {
+    let project = project(&window, &project_manager)?;
+    let config = project.config.read().unwrap();
+    let preview = project.preview_inputs.read().unwrap();
+    Ok(config
+        .toggles
+        .iter()
+        .map(|(name, &default)| PreviewToggle {
+            name: name.clone(),
+            default,
+            value: preview.toggles.get(name).copied().unwrap_or(default),
+        })
+        .collect())
+}
+
+/// Overrides a toggle for the preview, or resets it to the default if `value` is `None`.
+/// Takes effect on the next compile.
+#[tauri::command]
+pub async fn typst_set_toggle<R: Runtime>(
+    window: tauri::WebviewWindow<R>,
+    project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
+    name: String,
+    value: Option<bool>,
+) -> Result<()> {
+    let project = project(&window, &project_manager)?;
+    {
+        let mut preview = project.preview_inputs.write().unwrap();
+        match value {
+            Some(value) => preview.toggles.insert(name, value),
+            None => preview.toggles.remove(&name),
+        };
+    }
+    project.world.lock().unwrap().bump_revision();
+    Ok(())
+}
+
+/// Sets the seed passed to randomized documents as `sys.inputs.seed`, or clears it.
+/// Takes effect on the next compile.
+#[tauri::command]
+pub async fn typst_set_seed<R: Runtime>(
+    window: tauri::WebviewWindow<R>,
+    project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
+    seed: Option<u64>,
+) -> Result<()> {
+    let project = project(&window, &project_manager)?;
+    project.preview_inputs.write().unwrap().seed = seed;
+    project.world.lock().unwrap().bump_revision();
+    Ok(())
+}
+
+/// Turns timing the preview compiles on or off. Each timed compile emits a
+/// `compile_profile` event. Timing slows compiles down a little, so it is off by default.
+#[tauri::command]
+pub async fn typst_profile<R: Runtime>(
+    window: tauri::WebviewWindow<R>,
+    project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
+    enabled: bool,
+) -> Result<()> {
+    let project = project(&window, &project_manager)?;
+    project
+        .profile_compiles
+        .store(enabled, std::sync::atomic::Ordering::Relaxed);
+    Ok(())
+}
+
+/// Renders one page of the last compiled document.
 #[tauri::command]
 pub async fn typst_render<R: Runtime>(
     window: tauri::WebviewWindow<R>,
     project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
     page: usize,
     scale: f32,
-    nonce: u32,
 ) -> Result<TypstRenderResponse> {
-    let project = project_manager
-        .get_project(&window)
-        .ok_or(Error::UnknownProject)?;
+    let project = project(&window, &project_manager)?;
+    render_page(&project, page, scale).ok_or(Error::Unknown)
+}
+
+/// The links of a page of the last compiled document, so the preview can open them
+/// when clicked. References and other internal links lead to a position.
+#[tauri::command]
+pub async fn typst_page_links<R: Runtime>(
+    window: tauri::WebviewWindow<R>,
+    project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
+    page: usize,
+) -> Result<Vec<PageLink>> {
+    let project = project(&window, &project_manager)?;
+    let cache = project.cache.read().unwrap();
+    let doc = cache.document.as_ref().ok_or(Error::Unknown)?;
+    Ok(page_links(doc, page))
+}
+
+/// The text runs of a page of the last compiled document with their boxes and the
+/// source ranges they come from, eg. to select and copy text in the preview.
+#[tauri::command]
+pub async fn typst_page_text<R: Runtime>(
+    window: tauri::WebviewWindow<R>,
+    project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
+    page: usize,
+) -> Result<Vec<PositionedText>> {
+    let project = project(&window, &project_manager)?;
+    let world = project.world.lock().unwrap_or_else(|e| e.into_inner());
+    let cache = project.cache.read().unwrap();
+    let doc = cache.document.as_ref().ok_or(Error::Unknown)?;
+    let page = doc.pages.get(page).ok_or(Error::Unknown)?;
+    Ok(positioned_text(page, &*world))
+}
+
+/// Maps the lines of the file at `path` to where their text is in the last compiled
+/// document, sorted by line, for keeping editor and preview scrolled together.
+#[tauri::command]
+pub async fn typst_sync_map<R: Runtime>(
+    window: tauri::WebviewWindow<R>,
+    project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
+    path: PathBuf,
+) -> Result<Vec<SyncAnchor>> {
+    let project = project(&window, &project_manager)?;
+    let world = project.world.lock().unwrap_or_else(|e| e.into_inner());
+    let cache = project.cache.read().unwrap();
+    let doc = cache.document.as_ref().ok_or(Error::Unknown)?;
+    let id = FileId::new(None, VirtualPath::new(&path));
+    Ok(sync_map(doc, &*world, id))
+}
 
-    let (width, height) = {
+/// Rasterizes only `rect` of a page, in points from its top left corner, at `scale` pixels
+/// per point, eg. the part of a poster on screen at 400%. Tiles over 4096 pixels on a
+/// side are refused.
+#[tauri::command]
+pub async fn typst_render_tile<R: Runtime>(
+    window: tauri::WebviewWindow<R>,
+    project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
+    page: usize,
+    rect: Rect,
+    scale: f32,
+) -> Result<TypstTileResponse> {
+    let project = project(&window, &project_manager)?;
+    tokio::task::spawn_blocking(move || -> Result<TypstTileResponse> {
         let cache = project.cache.read().unwrap();
         let doc = cache.document.as_ref().ok_or(Error::Unknown)?;
         let p = doc.pages.get(page).ok_or(Error::Unknown)?;
-        (
-            (p.frame.width().to_pt() * scale as f64) as u32,
-            (p.frame.height().to_pt() * scale as f64) as u32,
-        )
-    };
-    
-    let svg = {
+        let size = p.frame.size();
+        let (rect, width, height) = tile_bounds(rect, size.x.to_pt(), size.y.to_pt(), scale)
+            .ok_or(Error::InvalidRange)?;
+        let png = render_tile_png(p, rect, scale).map_err(|e| {
+            log::error!("failed to encode tile of page {}: {}", page, e);
+            Error::Unknown
+        })?;
+        Ok(TypstTileResponse {
+            image: format!("data:image/png;base64,{}", STANDARD.encode(png)),
+            rect,
+            width,
+            height,
+            version: cache.version,
+        })
+    })
+    .await
+    .map_err(|_| Error::Unknown)?
+}
+
+/// The last compiled document as a PDF, for a preview shown by a PDF viewer.
+#[tauri::command]
+pub async fn preview_pdf_bytes<R: Runtime>(
+    window: tauri::WebviewWindow<R>,
+    project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
+) -> Result<tauri::ipc::Response> {
+    let project = project(&window, &project_manager)?;
+    let pdf = tokio::task::spawn_blocking(move || {
         let cache = project.cache.read().unwrap();
         let doc = cache.document.as_ref().ok_or(Error::Unknown)?;
-        let p = doc.pages.get(page).ok_or(Error::Unknown)?;
-        
-        let mut renderer = project.renderer.lock().unwrap_or_else(|e| e.into_inner());
-        let (svg, _was_changed) = renderer.render_page(page, p);
-        svg
+        pdf_bytes(doc, None)
+    })
+    .await
+    .map_err(|_| Error::Unknown)??;
+    Ok(tauri::ipc::Response::new(pdf))
+}
+
+/// Reports the pages the preview shows, `first` to `last`, at `scale`. They are rendered
+/// first, then their neighbors, each sent in a `page_rendered` event. Pages still waiting
+/// for an earlier range are dropped, and the range is rendered again after compiles.
+#[tauri::command]
+pub async fn typst_render_visible<R: Runtime>(
+    window: tauri::WebviewWindow<R>,
+    project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
+    first: usize,
+    last: usize,
+    scale: f32,
+) -> Result<()> {
+    let project = project(&window, &project_manager)?;
+    let visible = VisiblePages { first, last, scale };
+    project
+        .render_queue
+        .schedule(&project, Arc::new(window), visible);
+    Ok(())
+}
+
+/// The version of the document the preview renders from. Compile events and render
+/// responses stamped with an older version are stale.
+#[tauri::command]
+pub async fn typst_current_version<R: Runtime>(
+    window: tauri::WebviewWindow<R>,
+    project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
+) -> Result<u64> {
+    let project = project(&window, &project_manager)?;
+    let version = project.cache.read().unwrap().version;
+    Ok(version)
+}
+
+/// Every diagnostic of the latest preview compile grouped by file, also emitted as
+/// `project_diagnostics`. Before the first preview compile, the main file is compiled in
+/// the background on a snapshot of the world, so the preview's main file stays as it is.
+#[tauri::command]
+pub async fn diagnostics_project<R: Runtime>(
+    window: tauri::WebviewWindow<R>,
+    project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
+) -> Result<ProjectDiagnosticsEvent> {
+    let project = project(&window, &project_manager)?;
+    let latest = project.cache.read().unwrap().problems.clone();
+    let problems = match latest {
+        Some(problems) => problems,
+        None => {
+            let mut world = project.world.lock().unwrap_or_else(|e| e.into_inner()).snapshot();
+            let config = project.config.read().unwrap();
+            if config.main.is_some() || !world.is_main_set() {
+                config
+                    .apply_main(&project, &mut world)
+                    .map_err(|_| Error::Compile("no main file is configured".to_string()))?;
+            }
+            let inputs = project.preview_inputs.read().unwrap().to_inputs(&config.toggles);
+            drop(config);
+
+            tokio::task::spawn_blocking(move || {
+                let inputs_world = InputsWorld::new(&world, &inputs);
+                let result = typst::compile::<PagedDocument>(&inputs_world);
+                let errors = result.output.as_ref().err();
+                let errors = errors.map_or(&[][..], |errors| errors.as_slice());
+                ProjectDiagnosticsEvent::new(
+                    world.revision(),
+                    project_diagnostics(&inputs_world, errors.iter().chain(&result.warnings)),
+                )
+            })
+            .await
+            .map_err(|_| Error::Unknown)?
+        }
     };
-    
-    Ok(TypstRenderResponse {
-        image: svg,
-        width,
-        height,
-        nonce,
+    crate::ipc::events::emit_to_window(&window, "project_diagnostics", problems.clone());
+    Ok(problems)
+}
+
+/// Compiles `code` on its own, eg. an equation under the cursor, and renders it cropped
+/// to its content. It has the fonts and preview inputs of the project and the top-level
+/// imports of the file at `path`, which relative paths in the snippet resolve against.
+/// `inline` snippets are rendered without a margin, to sit within a line of text.
+#[tauri::command]
+pub async fn typst_render_snippet<R: Runtime>(
+    window: tauri::WebviewWindow<R>,
+    project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
+    code: String,
+    inline: bool,
+    path: Option<PathBuf>,
+) -> Result<TypstSnippetResponse> {
+    let project = project(&window, &project_manager)?;
+    let inputs = {
+        let config = project.config.read().unwrap();
+        project.preview_inputs.read().unwrap().to_inputs(&config.toggles)
+    };
+    let world = project.world.lock().unwrap_or_else(|e| {
+        log::warn!("Project world mutex poisoned, recovering: {}", e);
+        e.into_inner()
+    });
+
+    let vpath = VirtualPath::new(path.as_deref().unwrap_or(Path::new("/snippet.typ")));
+    let imports = match &path {
+        Some(_) => world
+            .source(FileId::new(None, vpath.clone()))
+            .map(|source| top_level_imports(&source))
+            .unwrap_or_default(),
+        None => vec![],
+    };
+    let margin = if inline { "0pt" } else { "4pt" };
+    let code = format!(
+        "{}\n#set page(width: auto, height: auto, margin: {}, fill: none)\n{}",
+        imports.join("\n"),
+        margin,
+        code
+    );
+
+    let document = SnippetWorld::new(world.engine(), code)
+        .with_files(&*world, vpath)
+        .with_library(TypstEngine::library_with_inputs(&inputs))
+        .compile()
+        .map_err(Error::Compile)?;
+    let page = document.pages.first().ok_or(Error::Unknown)?;
+    Ok(TypstSnippetResponse {
+        image: typst_svg::svg(page),
+        width: page.frame.width().to_pt(),
+        height: page.frame.height().to_pt(),
+    })
+}
+
+/// Compiles a single file for a quick look, without opening it as a project: nothing is
+/// watched, remembered or written. The file's directory is the root, so files it reads
+/// must be within it.
+#[tauri::command]
+pub async fn preview_file(path: PathBuf) -> Result<TypstFilePreview> {
+    let path = std::fs::canonicalize(&path)?;
+    let (root, name) = match (path.parent(), path.file_name()) {
+        (Some(root), Some(name)) => (root.to_path_buf(), PathBuf::from(name)),
+        _ => return Err(Error::UnrelatedPath),
+    };
+    tokio::task::spawn_blocking(move || {
+        let mut world = ProjectWorld::with_engine(root, detached_engine());
+        world.set_main_path(VirtualPath::new(&name));
+        let result = typst::compile::<PagedDocument>(&world);
+        let doc = result.output.map_err(|diagnostics| {
+            let messages: Vec<_> = diagnostics.iter().map(|d| d.message.to_string()).collect();
+            Error::Compile(messages.join("; "))
+        })?;
+        let first = doc.pages.first().ok_or(Error::Unknown)?;
+        Ok(TypstFilePreview {
+            width: first.frame.width().to_pt(),
+            height: first.frame.height().to_pt(),
+            page_svgs: doc.pages.iter().map(typst_svg::svg).collect(),
+            warnings: result.warnings.iter().map(|w| w.message.to_string()).collect(),
+        })
     })
+    .await
+    .map_err(|_| Error::Unknown)?
 }
 
 #[tauri::command]
@@ -154,17 +609,65 @@ pub async fn typst_autocomplete<R: Runtime>(
 
     let source = world.source(source_id).map_err(Into::<Error>::into)?;
 
+    // Where typst has nothing to offer, snippets still complete the word being typed.
     let (completed_offset, completions) =
-        typst_ide::autocomplete(&*world, None, &source, offset, explicit)
-            .ok_or_else(|| Error::Unknown)?;
+        typst_ide::autocomplete(&*world, None, &source, offset, explicit).unwrap_or_else(|| {
+            let word = content[..offset]
+                .trim_end_matches(|c: char| c.is_alphanumeric() || c == '_' || c == '-');
+            (word.len(), vec![])
+        });
+
+    let mut completions: Vec<TypstCompletion> =
+        completions.into_iter().map(TypstCompletion::from).collect();
+    completions.extend(snippet_completions(
+        &all_snippets(Some(&*project)),
+        &content,
+        completed_offset,
+        offset,
+        explicit,
+    ));
 
     let completed_char_offset = content[..completed_offset].chars().count();
     Ok(TypstCompleteResponse {
         offset: completed_char_offset,
-        completions: completions.into_iter().map(TypstCompletion::from).collect(),
+        completions,
     })
 }
 
+/// The snippets matching the text between `completed_offset` and `offset`, which they
+/// replace. A `#` before that text isn't inserted twice.
+fn snippet_completions(
+    snippets: &[SnippetEntry],
+    content: &str,
+    completed_offset: usize,
+    offset: usize,
+    explicit: bool,
+) -> Vec<TypstCompletion> {
+    let typed = &content[completed_offset..offset];
+    if typed.is_empty() && !explicit {
+        return vec![];
+    }
+    let after_hash = content[..completed_offset].ends_with('#');
+    snippets
+        .iter()
+        .map(|entry| &entry.snippet)
+        .filter(|snippet| snippet_matches(snippet, typed))
+        .map(|snippet| {
+            let body = if after_hash {
+                snippet.body.strip_prefix('#').unwrap_or(&snippet.body)
+            } else {
+                &snippet.body
+            };
+            TypstCompletion {
+                kind: TypstCompletionKind::Snippet,
+                label: snippet.prefix.clone(),
+                apply: Some(body.to_string()),
+                detail: (!snippet.description.is_empty()).then(|| snippet.description.clone()),
+            }
+        })
+        .collect()
+}
+
 fn find_precise_position(
     frame: &typst::layout::Frame,
     target_span: typst::syntax::Span,
@@ -199,6 +702,20 @@ fn find_precise_position(
     None
 }
 
+/// How far around shapes and images a click still hits them, eg. for thin lines.
+const HIT_TOLERANCE: f64 = 2.0;
+
+/// Whether `point` is within `size` from the origin, or `HIT_TOLERANCE` around it.
+/// Sizes may be negative, as for lines drawn up or left.
+fn hits_box(point: typst::layout::Point, size: typst::layout::Size) -> bool {
+    let within = |value: f64, extent: f64| {
+        value >= extent.min(0.0) - HIT_TOLERANCE && value <= extent.max(0.0) + HIT_TOLERANCE
+    };
+    within(point.x.to_pt(), size.x.to_pt()) && within(point.y.to_pt(), size.y.to_pt())
+}
+
+/// The source span of the topmost text, shape or image at `click`. Groups, eg. of an
+/// equation or a rotated box, are searched in their own coordinates.
 fn find_precise_jump(
     frame: &typst::layout::Frame,
     click: typst::layout::Point,
@@ -221,10 +738,23 @@ fn find_precise_jump(
                 }
             }
             FrameItem::Group(group) => {
-                if let Some(res) = find_precise_jump(&group.frame, rel_click) {
+                let Some(inverse) = group.transform.invert() else {
+                    continue;
+                };
+                if let Some(res) = find_precise_jump(&group.frame, rel_click.transform(inverse)) {
                     return Some(res);
                 }
             }
+            FrameItem::Shape(shape, span) => {
+                if !span.is_detached() && hits_box(rel_click, shape.geometry.bbox_size()) {
+                    return Some((*span, 0));
+                }
+            }
+            FrameItem::Image(_, size, span) => {
+                if !span.is_detached() && hits_box(rel_click, *size) {
+                    return Some((*span, 0));
+                }
+            }
             _ => {}
         }
     }
@@ -279,8 +809,17 @@ pub async fn typst_jump<R: Runtime>(
     let offset = range.start + span_offset as usize;
 
     let lines = source.lines();
-    let line = lines.byte_to_line(offset).ok_or(Error::Unknown)?;
-    let column = lines.byte_to_column(offset).ok_or(Error::Unknown)?;
+    let position = |offset: usize| -> Option<(usize, usize)> {
+        Some((lines.byte_to_line(offset)? + 1, lines.byte_to_column(offset)? + 1))
+    };
+    let cursor = position(offset).ok_or(Error::Unknown)?;
+    let element = enclosing_element(&source, offset).and_then(|(kind, range)| {
+        Some(TypstJumpElement {
+            kind,
+            start: position(range.start)?,
+            end: position(range.end)?,
+        })
+    });
 
     let path = source.id().vpath().as_rootless_path().to_string_lossy().to_string();
     let filepath = if path.starts_with("/") { path } else { format!("/{}", path) };
@@ -306,11 +845,13 @@ pub async fn typst_jump<R: Runtime>(
 
     Ok(Some(TypstJump {
         filepath,
-        start: Some((line + 1, column + 1)),
-        end: Some((line + 1, column + 1)),
+        start: position(range.start),
+        end: position(range.end),
+        cursor: Some(cursor),
         text: Some(snippet),
         offset: Some(offset),
         node_kind,
+        element,
     }))
 }
 
@@ -511,10 +1052,17 @@ pub async fn typst_delete_package(
     Ok(())
 }
 
+const PACKAGE_DOWNLOAD_ESTIMATE: u64 = 32 * 1024 * 1024;
+
 #[tauri::command]
 pub async fn typst_install_package(spec: String) -> Result<()> {
     use std::process::Command;
 
+    // Package sizes are unknown before downloading, so require room for a large one.
+    if let Some(cache_dir) = get_package_cache_dir() {
+        ensure_disk_space(&cache_dir, PACKAGE_DOWNLOAD_ESTIMATE)?;
+    }
+
     let output = Command::new("typst")
         .args(["init", &format!("@{}", spec.trim_start_matches('@')), "/dev/null"])
         .output()
@@ -534,66 +1082,108 @@ pub async fn typst_install_package(spec: String) -> Result<()> {
     Ok(())
 }
 
+/// The `sys.inputs` of an export with the given toggle overrides and the preview's seed.
+fn export_inputs(
+    project: &Project,
+    toggles: Option<BTreeMap<String, bool>>,
+) -> BTreeMap<String, String> {
+    let config = project.config.read().unwrap();
+    let mut inputs = toggle_inputs(&config.toggles, &toggles.unwrap_or_default());
+    if let Some(seed) = project.preview_inputs.read().unwrap().seed {
+        inputs.insert(SEED_INPUT.to_string(), seed.to_string());
+    }
+    inputs
+}
+
+/// Runs `f` on the document as it should be exported with the given toggle overrides and
+/// the preview's seed.
+/// That's the preview document if it was compiled with the same inputs, otherwise the
+/// project is compiled again without the preview-only inputs.
+fn with_export_document<T>(
+    project: &Project,
+    toggles: Option<BTreeMap<String, bool>>,
+    f: impl FnOnce(&PagedDocument) -> Result<T>,
+) -> Result<T> {
+    let export_inputs = export_inputs(project, toggles);
+    let preview_inputs = {
+        let config = project.config.read().unwrap();
+        let preview = project.preview_inputs.read().unwrap();
+        preview.to_inputs(&config.toggles)
+    };
+
+    if export_inputs == preview_inputs {
+        let cache = project.cache.read().unwrap();
+        let doc = cache.document.as_ref().ok_or(Error::Unknown)?;
+        return f(doc);
+    }
+
+    let doc = compile_with_inputs(project, &export_inputs).map_err(|e| {
+        log::error!("export compile failed: {}", e);
+        Error::Unknown
+    })?;
+    f(&doc)
+}
+
 #[tauri::command]
 pub async fn export_pdf<R: Runtime>(
     window: tauri::WebviewWindow<R>,
     project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
     path: String,
+    toggles: Option<BTreeMap<String, bool>>,
 ) -> Result<()> {
     let project = project_manager
         .get_project(&window)
         .ok_or(Error::UnknownProject)?;
 
-    let cache = project.cache.read().unwrap();
-    let doc = cache.document.as_ref().ok_or(Error::Unknown)?;
+    let path = with_export_document(&project, toggles, |doc| write_pdf(doc, Path::new(&path)))?;
+    record_export(&window, &project, &path, "pdf");
 
-    let options = typst_pdf::PdfOptions::default();
-    let pdf = typst_pdf::pdf(doc, &options).map_err(|_| Error::Unknown)?;
-    
-    let mut path_buf = PathBuf::from(&path);
-    if path_buf.extension().is_none() {
-        path_buf.set_extension("pdf");
-    }
-    
-    std::fs::write(&path_buf, pdf).map_err(Into::<Error>::into)?;
-    
     Ok(())
 }
 
+/// Writes the document, or the pages selected by `pages` (eg. `1-3, 5`), to a temporary
+/// PDF and opens the platform's print dialog for it.
+#[tauri::command]
+pub async fn print_document<R: Runtime>(
+    window: tauri::WebviewWindow<R>,
+    project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
+    pages: Option<String>,
+    toggles: Option<BTreeMap<String, bool>>,
+) -> Result<()> {
+    let project = project(&window, &project_manager)?;
+    let page_ranges = match pages.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(pages) => Some(parse_page_ranges(pages).ok_or(Error::InvalidPageRange)?),
+    };
+
+    // The print dialog reads the file after this returns, so it is left for the OS to
+    // clean up with the rest of the temporary directory.
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let path = std::env::temp_dir().join(format!("typstudio-print-{}.pdf", stamp));
+    let path = with_export_document(&project, toggles, |doc| {
+        write_pdf_pages(doc, &path, page_ranges)
+    })?;
+    open_print_dialog(&path)?;
+    Ok(())
+}
 
 #[tauri::command]
 pub async fn export_svg<R: Runtime>(
     window: tauri::WebviewWindow<R>,
     project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
     path: String,
+    toggles: Option<BTreeMap<String, bool>>,
 ) -> Result<()> {
     let project = project_manager
         .get_project(&window)
         .ok_or(Error::UnknownProject)?;
 
-    let cache = project.cache.read().unwrap();
-    let doc = cache.document.as_ref().ok_or(Error::Unknown)?;
-
-    let mut path_buf = PathBuf::from(&path);
-    if path_buf.extension().is_none() {
-        path_buf.set_extension("zip");
-    }
-
-    let file = std::fs::File::create(&path_buf).map_err(Into::<Error>::into)?;
-    let mut zip = zip::ZipWriter::new(file);
-    let options = zip::write::FileOptions::default()
-        .compression_method(zip::CompressionMethod::Stored);
+    let path = with_export_document(&project, toggles, |doc| write_svg_zip(doc, Path::new(&path)))?;
+    record_export(&window, &project, &path, "svg");
 
-    for (i, page) in doc.pages.iter().enumerate() {
-        let svg = typst_svg::svg(page);
-        let filename = format!("page_{:02}.svg", i + 1);
-        zip.start_file(filename, options).map_err(|e| Error::IO(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())))?;
-        use std::io::Write;
-        zip.write_all(svg.as_bytes()).map_err(Into::<Error>::into)?;
-    }
-
-    zip.finish().map_err(|e| Error::IO(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())))?;
-    
     Ok(())
 }
 
@@ -602,51 +1192,91 @@ pub async fn export_png<R: Runtime>(
     window: tauri::WebviewWindow<R>,
     project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
     path: String,
+    toggles: Option<BTreeMap<String, bool>>,
 ) -> Result<()> {
-    use rayon::prelude::*;
-    
     let project = project_manager
         .get_project(&window)
         .ok_or(Error::UnknownProject)?;
 
-    let pages: Vec<_> = {
-        let cache = project.cache.read().unwrap();
-        let doc = cache.document.as_ref().ok_or(Error::Unknown)?;
-        doc.pages.clone()
-    };
+    let path = with_export_document(&project, toggles, |doc| write_png_zip(doc, Path::new(&path)))?;
+    record_export(&window, &project, &path, "png");
 
-    let mut path_buf = PathBuf::from(&path);
-    if path_buf.extension().is_none() {
-        path_buf.set_extension("zip");
+    Ok(())
+}
+
+/// Exports a standalone HTML file with typst's HTML backend. Embedded images are moved
+/// into a directory next to the file unless `inline_assets` is set. The backend supports
+/// fewer elements than PDF export, so its diagnostics are returned instead of failing,
+/// and `path` is `None` if the document did not compile.
+#[tauri::command]
+pub async fn export_html<R: Runtime>(
+    window: tauri::WebviewWindow<R>,
+    project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
+    path: String,
+    inline_assets: Option<bool>,
+    toggles: Option<BTreeMap<String, bool>>,
+) -> Result<HtmlExport> {
+    let project = project(&window, &project_manager)?;
+    let inputs = export_inputs(&project, toggles);
+
+    let (html, diagnostics) = compile_html(&project, &inputs);
+    let path = match html {
+        Some(html) => Some(write_html(
+            &html,
+            Path::new(&path),
+            inline_assets.unwrap_or(true),
+        )?),
+        None => None,
+    };
+    if let Some(path) = &path {
+        record_export(&window, &project, path, "html");
     }
+    Ok(HtmlExport { path, diagnostics })
+}
 
-    let ppi = 144.0;
-    let scale = ppi / 72.0;
+/// Exports an EPUB book built from the HTML export, with a chapter per top-level
+/// heading and the metadata and fonts configured in the project. Like `export_html`,
+/// returns the diagnostics of the HTML backend.
+#[tauri::command]
+pub async fn export_epub<R: Runtime>(
+    window: tauri::WebviewWindow<R>,
+    project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
+    path: String,
+    toggles: Option<BTreeMap<String, bool>>,
+) -> Result<HtmlExport> {
+    let project = project(&window, &project_manager)?;
+    let inputs = export_inputs(&project, toggles);
+    let config = project.config.read().unwrap().epub.clone();
 
-    let rendered: Vec<(usize, Vec<u8>)> = pages
-        .par_iter()
-        .enumerate()
-        .filter_map(|(i, page)| {
-            let pixmap = typst_render::render(page, scale);
-            pixmap.encode_png().ok().map(|data| (i, data))
-        })
-        .collect();
-
-    let file = std::fs::File::create(&path_buf).map_err(Into::<Error>::into)?;
-    let mut zip = zip::ZipWriter::new(file);
-    let options = zip::write::FileOptions::default()
-        .compression_method(zip::CompressionMethod::Stored);
-
-    for (i, data) in rendered {
-        let filename = format!("page_{:02}.png", i + 1);
-        zip.start_file(filename, options).map_err(|e| Error::IO(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())))?;
-        use std::io::Write;
-        zip.write_all(&data).map_err(Into::<Error>::into)?;
+    let (html, diagnostics) = compile_html(&project, &inputs);
+    let path = match html {
+        Some(html) => Some(write_epub(&html, &config, &project.root, Path::new(&path))?),
+        None => None,
+    };
+    if let Some(path) = &path {
+        record_export(&window, &project, path, "epub");
     }
-    
-    zip.finish().map_err(|e| Error::IO(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())))?;
-    
-    Ok(())
+    Ok(HtmlExport { path, diagnostics })
+}
+
+/// The document's text as plain text or Markdown, eg. to paste an abstract into a web
+/// form. It is also written to `path` if given.
+#[tauri::command]
+pub async fn export_text<R: Runtime>(
+    window: tauri::WebviewWindow<R>,
+    project_manager: tauri::State<'_, Arc<ProjectManager<R>>>,
+    format: TextFormat,
+    path: Option<String>,
+    toggles: Option<BTreeMap<String, bool>>,
+) -> Result<String> {
+    let project = project(&window, &project_manager)?;
+    let text = with_export_document(&project, toggles, |doc| Ok(document_text(doc, format)))?;
+    if let Some(path) = path {
+        ensure_disk_space(Path::new(&path), text.len() as u64)?;
+        std::fs::write(&path, &text).map_err(Into::<Error>::into)?;
+        record_export(&window, &project, Path::new(&path), "text");
+    }
+    Ok(text)
 }
 
 #[tauri::command]
@@ -664,39 +1294,63 @@ pub async fn typst_get_document_sources<R: Runtime>(
     Ok(sources)
 }
 
-#[derive(serde::Deserialize)]
-pub struct RecentProjectInfo {
-    path: String,
-    name: String,
-    #[serde(default)]
-    _last_opened: Option<u64>,
+/// Tells the menu whether the editor has unsaved changes, which enables Save.
+#[tauri::command]
+pub async fn menu_set_dirty<R: Runtime>(window: tauri::WebviewWindow<R>, dirty: bool) {
+    crate::menu::update_menu_context(&window, |context| context.dirty = dirty);
 }
 
+/// Rebuilds the menu for the window's project, eg. once it opened or closed a project.
 #[tauri::command]
-pub async fn update_menu_state<R: Runtime>(
-    window: tauri::WebviewWindow<R>,
-    projects: Vec<RecentProjectInfo>,
-    is_project_open: bool,
-) -> Result<()> {
-    use tauri::Manager;
-    use crate::menu::{build_menu, RecentProject};
-    
-    let recent_projects: Vec<RecentProject> = projects.into_iter().map(|p| RecentProject {
-        name: p.name,
-        path: p.path,
-    }).collect();
-    
-    match build_menu(window.app_handle(), &recent_projects, is_project_open) {
-        Ok(menu) => {
-            if let Err(e) = window.app_handle().set_menu(menu) {
-                log::error!("Failed to set app menu: {}", e);
-                return Err(Error::Unknown);
-            }
-        }
-        Err(e) => {
-            log::error!("Failed to build menu: {}", e);
-            return Err(Error::Unknown);
-        }
+pub async fn update_menu_state<R: Runtime>(window: tauri::WebviewWindow<R>) -> Result<()> {
+    crate::menu::rebuild_menu(&window).map_err(|e| {
+        log::error!("Failed to rebuild menu: {}", e);
+        Error::Unknown
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{enclosing_element, hits_box, TypstElementKind};
+    use typst::layout::{Abs, Point, Size};
+    use typst::syntax::Source;
+
+    #[test]
+    fn test_enclosing_element() {
+        let text = "= Intro\n\nSome text\n\n#figure(image(\"a.png\"), caption: [Cap])\n\n$ x + y $";
+        let source = Source::detached(text);
+        let at = |needle: &str| text.find(needle).unwrap();
+
+        assert_eq!(
+            enclosing_element(&source, at("Intro")),
+            Some((TypstElementKind::Heading, 0..7))
+        );
+        let figure = at("figure")..at("\n\n$");
+        assert_eq!(
+            enclosing_element(&source, at("a.png")),
+            Some((TypstElementKind::Figure, figure))
+        );
+        let equation = at("$ x")..text.len();
+        assert_eq!(
+            enclosing_element(&source, at("y $")),
+            Some((TypstElementKind::Equation, equation))
+        );
+        let (kind, _) = enclosing_element(&source, at("Some")).unwrap();
+        assert_eq!(kind, TypstElementKind::Text);
+    }
+
+    #[test]
+    fn test_hits_box() {
+        let point = |x, y| Point::new(Abs::pt(x), Abs::pt(y));
+        let size = Size::new(Abs::pt(10.0), Abs::pt(10.0));
+        assert!(hits_box(point(5.0, 5.0), size));
+        // Within the tolerance around the box, but not beyond.
+        assert!(hits_box(point(-1.5, 11.5), size));
+        assert!(!hits_box(point(-3.0, 5.0), size));
+
+        // A line drawn to the left has a negative width.
+        let line = Size::new(Abs::pt(-20.0), Abs::zero());
+        assert!(hits_box(point(-10.0, 1.0), line));
+        assert!(!hits_box(point(5.0, 0.0), line));
     }
-    Ok(())
 }
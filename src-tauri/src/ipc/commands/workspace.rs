@@ -0,0 +1,122 @@
+use super::{Error, Result};
+use crate::ipc::commands::project_path;
+use crate::project::{FileWrite, ProjectManager};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::{Runtime, State, WebviewWindow};
+
+/// A replacement of a character range.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct WorkspaceFileEdit {
+    pub path: PathBuf,
+    pub edits: Vec<TextEdit>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct WorkspaceUndoResponse {
+    label: String,
+    paths: Vec<PathBuf>,
+}
+
+/// Applies character-range edits to `content`. Edits must not overlap.
+pub fn apply_text_edits(content: &str, edits: &[TextEdit]) -> Result<String> {
+    let to_byte = |offset: usize| {
+        content
+            .char_indices()
+            .nth(offset)
+            .map(|(i, _)| i)
+            .or_else(|| (offset == content.chars().count()).then_some(content.len()))
+    };
+
+    let mut ranges = edits
+        .iter()
+        .map(|edit| {
+            let start = to_byte(edit.range.start).ok_or(Error::InvalidRange)?;
+            let end = to_byte(edit.range.end).ok_or(Error::InvalidRange)?;
+            if start > end {
+                return Err(Error::InvalidRange);
+            }
+            Ok((start..end, edit.text.as_str()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    ranges.sort_by_key(|(range, _)| range.start);
+    if ranges.windows(2).any(|w| w[0].0.end > w[1].0.start) {
+        return Err(Error::InvalidRange);
+    }
+
+    let mut out = content.to_string();
+    for (range, text) in ranges.into_iter().rev() {
+        out.replace_range(range, text);
+    }
+    Ok(out)
+}
+
+/// Applies edits to several files as one undoable batch. Files are edited on disk, so
+/// the frontend should reload the returned paths if they are open. Changes of the same
+/// file are merged, so the file is written and journaled once; their edits may not
+/// overlap either.
+#[tauri::command]
+pub async fn workspace_edit_apply<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+    label: String,
+    changes: Vec<WorkspaceFileEdit>,
+) -> Result<Vec<PathBuf>> {
+    let project = super::project(&window, &project_manager)?;
+    let mut files: Vec<(PathBuf, WorkspaceFileEdit)> = vec![];
+    for change in changes {
+        let (_, absolute) = project_path(&window, &project_manager, &change.path)?;
+        match files.iter_mut().find(|(other, _)| *other == absolute) {
+            Some((_, file)) => file.edits.extend(change.edits),
+            None => files.push((absolute, change)),
+        }
+    }
+
+    let mut writes = vec![];
+    for (absolute, change) in files {
+        let content = match fs::read_to_string(&absolute) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        writes.push(FileWrite {
+            content: apply_text_edits(&content, &change.edits)?,
+            path: change.path,
+            absolute,
+        });
+    }
+
+    let paths = writes.iter().map(|w| w.path.clone()).collect();
+    project.journal.apply(&project, label, writes)?;
+    Ok(paths)
+}
+
+/// Reverts the last batch applied by the backend.
+#[tauri::command]
+pub async fn workspace_edit_undo<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<WorkspaceUndoResponse> {
+    let project = super::project(&window, &project_manager)?;
+    let (label, paths) = project.journal.undo(&project)?;
+    Ok(WorkspaceUndoResponse { label, paths })
+}
+
+/// Label of the edit `workspace_edit_undo` would revert, if any.
+#[tauri::command]
+pub async fn workspace_edit_peek<R: Runtime>(
+    window: WebviewWindow<R>,
+    project_manager: State<'_, Arc<ProjectManager<R>>>,
+) -> Result<Option<String>> {
+    let project = super::project(&window, &project_manager)?;
+    Ok(project.journal.peek())
+}
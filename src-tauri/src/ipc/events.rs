@@ -1,18 +1,15 @@
-use crate::ipc::TypstCompileEvent;
+use crate::compiler::EventSink;
 use serde::Serialize;
-use tauri::{Runtime, WebviewWindow, Emitter};
+use serde_json::Value;
+use tauri::{Emitter, EventTarget, Runtime, WebviewWindow};
 
-#[derive(Serialize, Clone, Debug)]
-#[serde(tag = "event", content = "payload")]
-pub enum BackendEvent {
-    #[serde(rename = "typst_compile")]
-    Compile(TypstCompileEvent),
+/// Emits `event` to `window` only; a plain `emit` would reach every window.
+pub fn emit_to_window<R: Runtime, S: Serialize + Clone>(window: &WebviewWindow<R>, event: &str, payload: S) {
+    let _ = window.emit_to(EventTarget::webview_window(window.label()), event, payload);
 }
 
-pub fn emit_event<R: Runtime>(window: &WebviewWindow<R>, event: BackendEvent) {
-    let _ = match &event {
-        BackendEvent::Compile(payload) => window.emit("typst_compile", payload),
-    };
-    // Also emit a generic "backend_event" for single-listener setups if needed
-    let _ = window.emit("backend_event", event);
+impl<R: Runtime> EventSink for WebviewWindow<R> {
+    fn send(&self, event: &str, payload: Value) {
+        emit_to_window(self, event, payload);
+    }
 }
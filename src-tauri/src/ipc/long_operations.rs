@@ -0,0 +1,130 @@
+use crate::appdata::{read_app_json, write_app_json, NOTIFICATIONS_FILE};
+use crate::export::spawn_detached;
+use crate::ipc::events::emit_to_window;
+use crate::ipc::LongOperationFinishedEvent;
+use log::warn;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::process::Command;
+use std::sync::RwLock;
+use std::time::Instant;
+use tauri::{Runtime, WebviewWindow};
+use tauri_plugin_notification::NotificationExt;
+
+/// How the user is told about compiles and exports that took a while.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct LongOperationSettings {
+    /// Operations shorter than this finish silently.
+    pub threshold_secs: f64,
+    /// Shows a system notification, unless the window has focus.
+    pub notification: bool,
+    pub sound: bool,
+}
+
+impl Default for LongOperationSettings {
+    fn default() -> Self {
+        Self {
+            threshold_secs: 10.0,
+            notification: false,
+            sound: false,
+        }
+    }
+}
+
+static SETTINGS: Lazy<RwLock<LongOperationSettings>> = Lazy::new(|| {
+    let settings = read_app_json(NOTIFICATIONS_FILE).unwrap_or_else(|e| {
+        warn!("failed to read {}: {}", NOTIFICATIONS_FILE, e);
+        None
+    });
+    RwLock::new(settings.unwrap_or_default())
+});
+
+pub fn long_operation_settings() -> LongOperationSettings {
+    SETTINGS.read().unwrap().clone()
+}
+
+pub fn set_long_operation_settings(settings: LongOperationSettings) -> io::Result<()> {
+    write_app_json(NOTIFICATIONS_FILE, &settings)?;
+    *SETTINGS.write().unwrap() = settings;
+    Ok(())
+}
+
+/// Rereads the settings file, eg. after importing a settings bundle.
+pub fn reload_long_operation_settings() -> io::Result<LongOperationSettings> {
+    let settings: LongOperationSettings = read_app_json(NOTIFICATIONS_FILE)?.unwrap_or_default();
+    *SETTINGS.write().unwrap() = settings.clone();
+    Ok(settings)
+}
+
+/// Plays the platform's alert sound, for when notifications are off.
+fn play_sound() {
+    let result = if cfg!(target_os = "macos") {
+        spawn_detached(Command::new("afplay").arg("/System/Library/Sounds/Glass.aiff"))
+    } else if cfg!(target_os = "windows") {
+        spawn_detached(Command::new("powershell").args([
+            "-NoProfile",
+            "-Command",
+            "[System.Media.SystemSounds]::Asterisk.Play()",
+        ]))
+    } else {
+        spawn_detached(Command::new("canberra-gtk-play").args(["--id", "complete"]))
+    };
+    if let Err(e) = result {
+        warn!("failed to play the completion sound: {}", e);
+    }
+}
+
+/// Emits `long_operation_finished` if the operation that began at `started` took longer
+/// than the configured threshold, and notifies the user as configured.
+pub fn report_long_operation<R: Runtime>(
+    window: &WebviewWindow<R>,
+    operation: &str,
+    started: Instant,
+    success: bool,
+) {
+    let duration = started.elapsed();
+    let settings = long_operation_settings();
+    if duration.as_secs_f64() < settings.threshold_secs {
+        return;
+    }
+
+    emit_to_window(
+        window,
+        "long_operation_finished",
+        LongOperationFinishedEvent {
+            operation: operation.to_string(),
+            duration_ms: duration.as_millis() as u64,
+            success,
+        },
+    );
+
+    let focused = window.is_focused().unwrap_or(false);
+    if settings.notification && !focused {
+        let outcome = if success { "finished" } else { "failed" };
+        let mut notification = window
+            .notification()
+            .builder()
+            .title(format!("{} {}", capitalize(operation), outcome))
+            .body(format!("Took {:.1} seconds", duration.as_secs_f64()));
+        if settings.sound {
+            notification = notification.sound("default");
+        }
+        match notification.show() {
+            Ok(()) => return,
+            Err(e) => warn!("failed to show a notification: {}", e),
+        }
+    }
+    if settings.sound {
+        play_sound();
+    }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
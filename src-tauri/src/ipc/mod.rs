@@ -1,5 +1,6 @@
 pub mod commands;
 pub mod events;
+pub mod long_operations;
 
 mod model;
 pub use model::*;
@@ -1,9 +1,17 @@
+use crate::analysis::Explanation;
+use crate::compiler::{FileDiagnostics, Hotspot, StageTiming};
+use crate::document::{BudgetOverrun, PageChanges, Rect, ResolvedBookmark};
+use crate::ipc::commands::ImportedAsset;
+use crate::project::Project;
+use crate::search::SearchMatch;
 use serde::Serialize;
 use std::ops::Range;
 use std::path::PathBuf;
 
 #[derive(Serialize, Clone, Debug)]
 pub struct TypstCompileEvent {
+    /// The world revision that was compiled. Events with an older version are stale.
+    pub version: u64,
     pub document: Option<TypstDocument>,
     pub diagnostics: Option<Vec<TypstSourceDiagnostic>>,
 }
@@ -17,7 +25,7 @@ pub struct TypstDocument {
     pub page_svgs: Vec<String>,
 }
 
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum TypstDiagnosticSeverity {
     Error,
@@ -30,6 +38,30 @@ pub struct TypstSourceDiagnostic {
     pub severity: TypstDiagnosticSeverity,
     pub message: String,
     pub hints: Vec<String>,
+    /// What a common error means and how to fix it.
+    pub explanation: Option<Explanation>,
+}
+
+/// Every diagnostic of a compile of the main file, by file, so that problems show up in
+/// files that aren't open.
+#[derive(Serialize, Clone, Debug)]
+pub struct ProjectDiagnosticsEvent {
+    /// The world revision that was compiled.
+    pub version: u64,
+    pub errors: usize,
+    pub warnings: usize,
+    pub files: Vec<FileDiagnostics>,
+}
+
+impl ProjectDiagnosticsEvent {
+    pub fn new(version: u64, files: Vec<FileDiagnostics>) -> Self {
+        Self {
+            version,
+            errors: files.iter().map(|f| f.errors).sum(),
+            warnings: files.iter().map(|f| f.warnings).sum(),
+            files,
+        }
+    }
 }
 
 #[derive(Serialize, Clone, Debug)]
@@ -37,7 +69,56 @@ pub struct TypstRenderResponse {
     pub image: String,
     pub width: u32,
     pub height: u32,
-    pub nonce: u32,
+    /// The version of the document the page was rendered from.
+    pub version: u64,
+    /// An SVG of the same size with the preview decorations, if any are enabled.
+    pub overlay: Option<String>,
+}
+
+/// A page rendered by the preview's render queue.
+#[derive(Serialize, Clone, Debug)]
+pub struct TypstPageRenderedEvent {
+    pub page: usize,
+    #[serde(flatten)]
+    pub render: TypstRenderResponse,
+}
+
+/// A raster image of part of a page, for zoom levels too high to render the whole page.
+#[derive(Serialize, Clone, Debug)]
+pub struct TypstTileResponse {
+    /// A `data:image/png` URL.
+    pub image: String,
+    /// The part of the page rendered, in points, clamped to the page.
+    pub rect: Rect,
+    pub width: u32,
+    pub height: u32,
+    /// The version of the document the tile was rendered from.
+    pub version: u64,
+}
+
+/// A standalone rendering of a snippet, cropped to its content. Sizes are in points.
+#[derive(Serialize, Clone, Debug)]
+pub struct TypstSnippetResponse {
+    pub image: String,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// A file compiled on its own for a quick look. Sizes are of the first page, in points.
+#[derive(Serialize, Clone, Debug)]
+pub struct TypstFilePreview {
+    pub page_svgs: Vec<String>,
+    pub width: f64,
+    pub height: f64,
+    pub warnings: Vec<String>,
+}
+
+/// Files written by autosave, and those skipped because they changed on disk. Paths
+/// are project-relative.
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct AutosavedEvent {
+    pub paths: Vec<PathBuf>,
+    pub conflicts: Vec<PathBuf>,
 }
 
 #[derive(Serialize, Clone, Debug)]
@@ -48,6 +129,17 @@ pub struct ProjectChangeEvent {
 #[derive(Serialize, Clone, Debug)]
 pub struct ProjectModel {
     pub root: PathBuf,
+    /// Set for an implicit project opened from a lone file.
+    pub single_file: Option<PathBuf>,
+}
+
+impl ProjectModel {
+    pub fn new(project: &Project) -> Self {
+        Self {
+            root: project.root.clone(),
+            single_file: project.single_file.clone(),
+        }
+    }
 }
 
 #[derive(Serialize, Clone, Debug)]
@@ -55,9 +147,193 @@ pub struct FSRefreshEvent {
     pub path: PathBuf,
 }
 
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FSChangeKind {
+    Created,
+    Modified,
+    Deleted,
+    Renamed,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct FSChange {
+    pub kind: FSChangeKind,
+    pub path: PathBuf,
+    /// The previous path, set for [`FSChangeKind::Renamed`].
+    pub from: Option<PathBuf>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct FSChangedEvent {
+    pub changes: Vec<FSChange>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GitStatusEvent {
+    pub files: Vec<crate::git::GitStatusEntry>,
+}
+
 #[derive(Serialize, Clone, Debug)]
 pub struct LoadingProgressEvent {
     pub stage: String,
     pub progress: u32,
     pub message: Option<String>,
 }
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SearchResultEvent {
+    pub search_id: u64,
+    pub path: String,
+    pub matches: Vec<SearchMatch>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SearchFinishedEvent {
+    pub search_id: u64,
+    pub files: usize,
+    pub matches: usize,
+    pub truncated: bool,
+    pub cancelled: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ExportProgressEvent {
+    pub job_id: u64,
+    pub index: usize,
+    pub total: usize,
+    pub name: String,
+    pub output: Option<PathBuf>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ExportFinishedEvent {
+    pub job_id: u64,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub cancelled: bool,
+}
+
+#[derive(Serialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoExportStatus {
+    Running,
+    Exported,
+    Failed,
+}
+
+/// Emitted when an automatic export after a save starts and ends.
+#[derive(Serialize, Clone, Debug)]
+pub struct AutoExportEvent {
+    pub status: AutoExportStatus,
+    /// The written files once exported.
+    pub outputs: Vec<PathBuf>,
+    pub error: Option<String>,
+}
+
+impl AutoExportEvent {
+    pub fn new(status: AutoExportStatus) -> Self {
+        Self {
+            status,
+            outputs: vec![],
+            error: None,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportHookStream {
+    Stdout,
+    Stderr,
+}
+
+/// Emitted for every line an export hook prints.
+#[derive(Serialize, Clone, Debug)]
+pub struct ExportHookOutputEvent {
+    /// Tells apart the runs of the same command.
+    pub run_id: u64,
+    pub command: String,
+    pub stream: ExportHookStream,
+    pub line: String,
+}
+
+/// Emitted when an export hook exits, or fails to start with `error`.
+#[derive(Serialize, Clone, Debug)]
+pub struct ExportHookFinishedEvent {
+    pub run_id: u64,
+    pub command: String,
+    /// The exported file the hook ran for.
+    pub export: PathBuf,
+    pub success: bool,
+    pub code: Option<i32>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct FileDropImportEvent {
+    pub assets: Vec<ImportedAsset>,
+    /// The snippets of all assets, one per line, to insert at the cursor.
+    pub snippet: String,
+}
+
+/// Emitted after each compile when the project has a page budget.
+#[derive(Serialize, Clone, Debug)]
+pub struct PageBudgetEvent {
+    pub pages: usize,
+    pub overruns: Vec<BudgetOverrun>,
+}
+
+/// Emitted after each compile when the project has bookmarks, with their new positions.
+#[derive(Serialize, Clone, Debug)]
+pub struct PreviewBookmarksEvent {
+    pub bookmarks: Vec<ResolvedBookmark>,
+}
+
+/// Emitted after each compile that changed the document, with the regions to highlight.
+#[derive(Serialize, Clone, Debug)]
+pub struct PreviewChangesEvent {
+    pub version: u64,
+    pub pages: Vec<PageChanges>,
+}
+
+/// Emitted after each preview compile while profiling is enabled.
+#[derive(Serialize, Clone, Debug)]
+pub struct CompileProfileEvent {
+    pub version: u64,
+    /// From the request to the document being ready, including the page previews.
+    pub total_ms: f64,
+    /// Typst's compile alone.
+    pub compile_ms: f64,
+    /// Time per kind of Typst timing scope, slowest first, eg. `eval` or `layout`.
+    pub stages: Vec<StageTiming>,
+    /// The slowest scopes with a place in the sources, eg. calls of a slow function.
+    pub hotspots: Vec<Hotspot>,
+    pub cache: CompileCacheInfo,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct CompileCacheInfo {
+    /// Files the compile read, which decide whether later edits recompile.
+    pub files_accessed: usize,
+    /// Page previews rendered again, and those reused since their page didn't change.
+    pub pages_rendered: usize,
+    pub pages_reused: usize,
+}
+
+/// Emitted when a compile or export took longer than the configured threshold.
+#[derive(Serialize, Clone, Debug)]
+pub struct LongOperationFinishedEvent {
+    /// `compile` or `export`.
+    pub operation: String,
+    pub duration_ms: u64,
+    pub success: bool,
+}
+
+/// Emitted when a collaboration peer changed a file, with its new content.
+#[derive(Serialize, Clone, Debug)]
+pub struct CollabRemoteChangeEvent {
+    pub path: String,
+    pub content: String,
+}
@@ -0,0 +1,353 @@
+mod actions;
+mod analysis;
+mod appdata;
+pub mod cli;
+mod collab;
+mod compiler;
+mod convert;
+mod deeplink;
+mod docs;
+mod document;
+mod engine;
+mod export;
+mod git;
+mod ipc;
+mod menu;
+mod net;
+mod palette;
+mod preview_server;
+mod project;
+mod search;
+mod settings;
+mod snippets;
+#[cfg(test)]
+mod tests;
+mod window;
+
+/// Internals exposed to the benchmarks in `benches/`.
+#[cfg(feature = "bench")]
+pub mod bench {
+    pub use crate::compiler::IncrementalRenderer;
+    pub use crate::engine::TypstEngine;
+    pub use crate::project::ProjectWorld;
+}
+
+use crate::compiler::Compiler;
+use crate::export::ExportJobs;
+
+
+use crate::project::{Project, ProjectManager};
+use env_logger::Env;
+use log::info;
+use std::path::Path;
+use std::sync::Arc;
+use tauri::Manager;
+use tauri::Wry;
+use tauri_plugin_deep_link::DeepLinkExt;
+use window_vibrancy::{apply_vibrancy, NSVisualEffectMaterial};
+
+/// Runs the app until its last window closes. Expects to run inside a Tokio runtime,
+/// which the project watcher and compiler spawn their tasks on.
+pub fn run() {
+    env_logger::init_from_env(Env::default().default_filter_or("info"));
+    info!("initializing typstudio");
+
+    let project_manager = Arc::new(ProjectManager::<Wry>::new());
+    if let Ok(watcher) = ProjectManager::init_watcher(project_manager.clone()) {
+        project_manager.set_watcher(watcher);
+    }
+    tauri::Builder::default()
+        // A second launch, eg. for a link or a file on Windows and Linux, hands its
+        // arguments to this instance instead. Links go on to `on_open_url`.
+        .plugin(tauri_plugin_single_instance::init(|app, argv, cwd| {
+            let args = argv.iter().skip(1).map(|arg| Path::new(&cwd).join(arg));
+            if let Some(path) = window::path_argument(args) {
+                window::open_path(app, path);
+            } else if let Some(window) = app.get_webview_window("main") {
+                let _ = window.set_focus();
+            }
+        }))
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
+        // .menu(menu::build_menu(&[]))
+        // .on_menu_event(handle_menu_event)
+        .manage(project_manager.clone())
+        .manage(Arc::new(ExportJobs::new()))
+        .manage(menu::MenuState::default())
+        .manage(menu::FocusedWindow::default())
+        .manage(deeplink::PendingLocations::default())
+        .setup(move |app| {
+            let handle = app.handle();
+            let menu = menu::build_menu(handle, &[], &[], false, &actions::document_actions(None))?;
+            app.set_menu(menu)?;
+            app.on_menu_event(|app, event| {
+                menu::handle_menu_event(app, event);
+            });
+
+            let compiler = Arc::new(Compiler::new(project_manager.clone(), app.handle().clone()));
+            app.manage(compiler);
+            project::spawn_autosave(project_manager.clone());
+
+            // Installed bundles register the scheme themselves; this covers development
+            // builds and AppImages.
+            #[cfg(any(windows, target_os = "linux"))]
+            if let Err(e) = app.deep_link().register_all() {
+                log::warn!("Failed to register {}:// links: {}", deeplink::SCHEME, e);
+            }
+            let link_handle = app.handle().clone();
+            app.deep_link().on_open_url(move |event| {
+                for url in event.urls() {
+                    deeplink::handle_deep_link(&link_handle, &url);
+                }
+            });
+            let launch_links = app.deep_link().get_current().ok().flatten().unwrap_or_default();
+
+            // The page picks the project up through `project_current` once it loads.
+            if let Some(path) = window::launch_path() {
+                info!("opening {:?} from the command line", path);
+                window::open_path(app.handle(), path);
+            } else if !launch_links.is_empty() {
+                for url in &launch_links {
+                    deeplink::handle_deep_link(app.handle(), url);
+                }
+            } else if settings::app_settings().reopen_last_project {
+                if let (Some(path), Some(window)) =
+                    (appdata::last_opened_project(), app.get_webview_window("main"))
+                {
+                    info!("reopening {:?}", path);
+                    tauri::async_runtime::spawn_blocking(move || {
+                        let project = Arc::new(Project::open(path, None));
+                        project_manager.set_project(&window, Some(project));
+                    });
+                }
+            }
+
+            #[cfg(target_os = "macos")]
+            if let Some(window) = app.get_webview_window("main") {
+                apply_vibrancy(&window, NSVisualEffectMaterial::Sidebar, None, None)
+                    .expect("Unsupported platform! 'apply_vibrancy' is only supported on macOS");
+            } else {
+                println!("Error: Could not find window labeled 'main'");
+            }
+
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Focused(true) = event {
+                if let Some(webview) = window.app_handle().get_webview_window(window.label()) {
+                    menu::window_focused(&webview);
+                }
+            }
+            // Leaving the window is a natural point to save, as is closing it.
+            if let tauri::WindowEvent::Focused(false) | tauri::WindowEvent::CloseRequested { .. } = event {
+                let app = window.app_handle();
+                if settings::app_settings().autosave_interval_secs.is_some() {
+                    if let Some(webview) = app.get_webview_window(window.label()) {
+                        let project_manager = app.state::<Arc<ProjectManager<Wry>>>();
+                        if let Some(project) = project_manager.get_project(&webview) {
+                            project::autosave_window(&webview, &project);
+                        }
+                    }
+                }
+            }
+            if let tauri::WindowEvent::Destroyed = event {
+                menu::window_destroyed(window.app_handle(), window.label());
+                let project_manager = window.app_handle().state::<Arc<ProjectManager<Wry>>>();
+                project_manager.remove_window(window.label());
+                let compiler = window.app_handle().state::<Arc<Compiler<Wry>>>();
+                compiler.remove_window(window.label());
+            }
+            if let tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) = event {
+                let app = window.app_handle();
+                if let Some(webview) = app.get_webview_window(window.label()) {
+                    let project_manager = app.state::<Arc<ProjectManager<Wry>>>().inner().clone();
+                    let paths = paths.clone();
+                    tauri::async_runtime::spawn_blocking(move || {
+                        ipc::commands::import_dropped_files(&webview, &project_manager, &paths);
+                    });
+                }
+            }
+        })
+        .invoke_handler(tauri::generate_handler![
+            ipc::commands::fs_list_dir,
+            ipc::commands::fs_read_file_binary,
+            ipc::commands::fs_read_file_text,
+            ipc::commands::fs_create_file,
+            ipc::commands::fs_write_file_binary,
+            ipc::commands::fs_write_file_text,
+            ipc::commands::fs_delete_file,
+            ipc::commands::fs_rename_file,
+            ipc::commands::fs_copy_file,
+            ipc::commands::fs_duplicate,
+            ipc::commands::fs_undo_delete,
+            ipc::commands::fs_reveal_path,
+            ipc::commands::fs_search_files,
+            ipc::commands::fs_tree,
+            ipc::commands::fs_quick_open,
+            ipc::commands::git_read_original_file,
+            ipc::commands::git_status,
+            ipc::commands::git_diff_file,
+            ipc::commands::git_log,
+            ipc::commands::git_show_file_at,
+            ipc::commands::git_branches,
+            ipc::commands::git_checkout,
+            ipc::commands::git_create_branch,
+            ipc::commands::git_init,
+            ipc::commands::git_auto_commit,
+            ipc::commands::git_set_auto_commit,
+            ipc::commands::typst_compile,
+            ipc::commands::typst_render,
+            ipc::commands::typst_render_visible,
+            ipc::commands::typst_render_tile,
+            ipc::commands::typst_page_links,
+            ipc::commands::typst_page_text,
+            ipc::commands::typst_sync_map,
+            ipc::commands::preview_pdf_bytes,
+            ipc::commands::typst_current_version,
+            ipc::commands::diagnostics_project,
+            ipc::commands::typst_autocomplete,
+            ipc::commands::typst_render_snippet,
+            ipc::commands::preview_file,
+            ipc::commands::typst_jump,
+            ipc::commands::typst_jump_from_cursor,
+            ipc::commands::typst_list_packages,
+            ipc::commands::typst_delete_package,
+            ipc::commands::typst_install_package,
+            ipc::commands::typst_get_document_sources,
+            ipc::commands::typst_lint,
+            ipc::commands::typst_suggest_continuation,
+            ipc::commands::clipboard_paste,
+            ipc::commands::copy_page_image,
+            ipc::commands::clean_pasted_text,
+            ipc::commands::convert_latex_snippet,
+            ipc::commands::convert_markdown_snippet,
+            ipc::commands::clipboard_paste_table,
+            ipc::commands::assets_mirror_url,
+            ipc::commands::project_import_asset,
+            ipc::commands::import_markdown_file,
+            ipc::commands::import_latex_project,
+            ipc::commands::import_docx,
+            ipc::commands::open_project,
+            ipc::commands::project_current,
+            ipc::commands::project_roots,
+            ipc::commands::project_attach_root,
+            ipc::commands::project_detach_root,
+            ipc::commands::window_new_tab,
+            ipc::commands::open_project_in_new_window,
+            ipc::commands::deep_link_take_location,
+            ipc::commands::session_get,
+            ipc::commands::session_save,
+            ipc::commands::autosave_mark_dirty,
+            ipc::commands::autosave_mark_clean,
+            ipc::commands::autosave_now,
+            ipc::commands::recover_unsaved_changes,
+            ipc::commands::recover_discard,
+            ipc::commands::history_list,
+            ipc::commands::history_read,
+            ipc::commands::history_diff,
+            ipc::commands::history_restore,
+            ipc::commands::recent_projects_list,
+            ipc::commands::recent_projects_add,
+            ipc::commands::recent_projects_remove,
+            ipc::commands::recent_projects_pin,
+            ipc::commands::recent_projects_import,
+            ipc::commands::recent_exports_list,
+            ipc::commands::recent_exports_open,
+            ipc::commands::create_playground,
+            ipc::commands::export_pdf,
+            ipc::commands::print_document,
+            ipc::commands::export_svg,
+            ipc::commands::export_png,
+            ipc::commands::export_html,
+            ipc::commands::export_epub,
+            ipc::commands::export_text,
+            ipc::commands::update_menu_state,
+            ipc::commands::menu_set_dirty,
+            ipc::commands::workspace_edit_apply,
+            ipc::commands::workspace_edit_undo,
+            ipc::commands::workspace_edit_peek,
+            ipc::commands::search_project,
+            ipc::commands::search_cancel,
+            ipc::commands::search_replace_preview,
+            ipc::commands::search_replace_apply,
+            ipc::commands::settings_get,
+            ipc::commands::settings_set,
+            ipc::commands::settings_export,
+            ipc::commands::settings_import,
+            ipc::commands::long_operation_settings_get,
+            ipc::commands::long_operation_settings_set,
+            ipc::commands::export_list_variants,
+            ipc::commands::export_variants,
+            ipc::commands::export_seeded,
+            ipc::commands::export_mail_merge,
+            ipc::commands::export_job_cancel,
+            ipc::commands::export_auto_toggle,
+            ipc::commands::export_hooks_approve,
+            ipc::commands::export_anonymous,
+            ipc::commands::document_find_text,
+            ipc::commands::submission_profiles_list,
+            ipc::commands::submission_profile_save,
+            ipc::commands::submission_check,
+            ipc::commands::document_font_report,
+            ipc::commands::preview_bookmarks_list,
+            ipc::commands::preview_bookmark_add,
+            ipc::commands::preview_bookmark_remove,
+            ipc::commands::preview_compare,
+            ipc::commands::comments_list,
+            ipc::commands::comments_add,
+            ipc::commands::comments_resolve,
+            ipc::commands::collab_host,
+            ipc::commands::collab_join,
+            ipc::commands::collab_leave,
+            ipc::commands::collab_session,
+            ipc::commands::collab_open_file,
+            ipc::commands::collab_apply,
+            ipc::commands::preview_server_start,
+            ipc::commands::preview_server_stop,
+            ipc::commands::preview_server_info,
+            ipc::commands::stats_export,
+            ipc::commands::docs_search,
+            ipc::commands::docs_get,
+            ipc::commands::docs_render_example,
+            ipc::commands::typst_list_symbols,
+            ipc::commands::commands_list,
+            ipc::commands::commands_execute,
+            ipc::commands::document_actions_list,
+            ipc::commands::document_action_apply,
+            ipc::commands::snippets_list,
+            ipc::commands::snippets_create,
+            ipc::commands::snippets_update,
+            ipc::commands::snippets_delete,
+            ipc::commands::typst_set_preview_theme,
+            ipc::commands::typst_set_dark_preview,
+            ipc::commands::typst_get_preview_decorations,
+            ipc::commands::typst_set_preview_decorations,
+            ipc::commands::typst_list_toggles,
+            ipc::commands::typst_set_toggle,
+            ipc::commands::typst_set_seed,
+            ipc::commands::typst_profile,
+            ipc::commands::generators_list,
+            ipc::commands::generators_approve,
+            ipc::commands::generators_run,
+            ipc::commands::generators_run_stale,
+            ipc::commands::generators_watch
+        ])
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            // Files opened through a file association or the Dock on macOS.
+            #[cfg(target_os = "macos")]
+            if let tauri::RunEvent::Opened { urls } = event {
+                for url in urls {
+                    if let Ok(path) = url.to_file_path() {
+                        window::open_path(app, path);
+                    }
+                }
+            }
+            #[cfg(not(target_os = "macos"))]
+            let _ = (app, event);
+        });
+}
@@ -1,18 +1,135 @@
-use crate::project::{Project, ProjectManager};
+use crate::actions::{document_actions, DocumentAction};
+use crate::ipc::events::emit_to_window;
+use crate::appdata::{add_recent_project, clear_recent_projects, recent_projects, RecentProject};
+use crate::project::{recent_exports, Project, ProjectManager, RecentExport};
+use muda::accelerator::Accelerator;
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
-use std::sync::Arc;
-use tauri::menu::{Menu, MenuBuilder, SubmenuBuilder, MenuEvent};
-use tauri::{AppHandle, Manager, Runtime, State, Emitter};
+use std::sync::{Arc, Mutex};
+use tauri::menu::{Menu, MenuBuilder, MenuItemKind, SubmenuBuilder, MenuEvent};
+use tauri::{AppHandle, Manager, Runtime, State, Emitter, WebviewWindow};
 use tauri_plugin_dialog::DialogExt;
 use tauri_plugin_opener::OpenerExt;
 
-pub struct RecentProject {
-    pub name: String,
-    pub path: String,
+/// What the enablement of menu items depends on.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MenuContext {
+    pub project_open: bool,
+    /// Whether the editor has unsaved changes.
+    pub dirty: bool,
+    /// Whether the project compiled successfully since it was opened.
+    pub compiled: bool,
+    /// Whether the project is in a git repository.
+    pub git: bool,
 }
 
-pub fn build_menu<R: Runtime>(handle: &AppHandle<R>, recent_projects: &[RecentProject], is_project_open: bool) -> tauri::Result<Menu<R>> {
+impl MenuContext {
+    /// The context of a window showing `project`, before any edits.
+    pub fn for_project(project: Option<&Project>) -> Self {
+        let Some(project) = project else {
+            return Self::default();
+        };
+        Self {
+            project_open: true,
+            dirty: false,
+            compiled: project.cache.read().unwrap().document.is_some(),
+            git: git2::Repository::discover(&project.root).is_ok(),
+        }
+    }
+
+    /// Whether the item `id` is enabled, or `None` for items that always are.
+    pub fn enabled(&self, id: &str) -> Option<bool> {
+        let enabled = match id {
+            "file_save" | "file_save_all" => self.project_open && self.dirty,
+            "file_export_pdf" | "file_export_svg" | "file_export_png" | "file_export_html"
+            | "file_export_epub" | "file_export_markdown" | "file_print" => {
+                self.project_open && self.compiled
+            }
+            "view_diff" => self.project_open && self.git,
+            "file_new_file" | "file_close_project" | "view_toggle_sidebar"
+            | "view_toggle_preview" | "packages_install" => self.project_open,
+            id if id.starts_with(ACTION_MENU_PREFIX) => self.project_open,
+            _ => return None,
+        };
+        Some(enabled)
+    }
+}
+
+/// The [`MenuContext`] of each window by label, managed as app state. The menu is shared
+/// by all windows and shows the context of the window it acts on.
+#[derive(Default)]
+pub struct MenuState(Mutex<HashMap<String, MenuContext>>);
+
+/// The context of the window the menu acts on.
+pub fn menu_context<R: Runtime>(app: &AppHandle<R>) -> MenuContext {
+    let Some(window) = menu_target_window(app) else {
+        return MenuContext::default();
+    };
+    app.try_state::<MenuState>()
+        .and_then(|state| state.0.lock().unwrap().get(window.label()).copied())
+        .unwrap_or_default()
+}
+
+/// Updates `window`'s menu context and, if that changed it and the menu acts on the
+/// window, the enablement of the menu's items.
+pub fn update_menu_context<R: Runtime>(
+    window: &WebviewWindow<R>,
+    update: impl FnOnce(&mut MenuContext),
+) {
+    let app = window.app_handle();
+    let Some(state) = app.try_state::<MenuState>() else {
+        return;
+    };
+    let context = {
+        let mut contexts = state.0.lock().unwrap();
+        let context = contexts.entry(window.label().to_string()).or_default();
+        let before = *context;
+        update(context);
+        if *context == before {
+            return;
+        }
+        *context
+    };
+    let shown = menu_target_window(app).is_some_and(|target| target.label() == window.label());
+    if let Some(menu) = app.menu().filter(|_| shown) {
+        apply_menu_context(&menu.items().unwrap_or_default(), &context);
+    }
+}
+
+/// Forgets the menu context of a closed window.
+pub fn window_destroyed<R: Runtime>(app: &AppHandle<R>, label: &str) {
+    if let Some(state) = app.try_state::<MenuState>() {
+        state.0.lock().unwrap().remove(label);
+    }
+}
+
+fn apply_menu_context<R: Runtime>(items: &[MenuItemKind<R>], context: &MenuContext) {
+    for item in items {
+        match item {
+            MenuItemKind::MenuItem(item) => {
+                if let Some(enabled) = context.enabled(item.id().as_ref()) {
+                    let _ = item.set_enabled(enabled);
+                }
+            }
+            MenuItemKind::Submenu(submenu) => {
+                apply_menu_context(&submenu.items().unwrap_or_default(), context)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Menu ids of document actions are the action's id with this prefix.
+pub const ACTION_MENU_PREFIX: &str = "action_";
+
+pub fn build_menu<R: Runtime>(
+    handle: &AppHandle<R>,
+    recent_projects: &[RecentProject],
+    recent_exports: &[RecentExport],
+    is_project_open: bool,
+    actions: &[DocumentAction],
+) -> tauri::Result<Menu<R>> {
     use tauri::menu::{MenuItemBuilder, CheckMenuItemBuilder};
 
     let app_menu = SubmenuBuilder::new(handle, "Typstudio")
@@ -46,10 +163,30 @@ pub fn build_menu<R: Runtime>(handle: &AppHandle<R>, recent_projects: &[RecentPr
         .text("file_clear_recent", "Clear Recent")
         .build()?;
 
+    let mut exports_menu_builder = SubmenuBuilder::new(handle, "Recent Exports");
+    if recent_exports.is_empty() {
+        exports_menu_builder = exports_menu_builder.item(
+            &MenuItemBuilder::with_id("file_recent_export_none", "No Recent Exports")
+                .enabled(false)
+                .build(handle)?
+        );
+    } else {
+        for (i, export) in recent_exports.iter().enumerate() {
+            let export_sub = SubmenuBuilder::new(handle, export.name())
+                .text(format!("file_recent_export_open_{}", i), "Open")
+                .text(format!("file_recent_export_reveal_{}", i), "Reveal in File Manager")
+                .build()?;
+            exports_menu_builder = exports_menu_builder.item(&export_sub);
+        }
+    }
+    let exports_sub = exports_menu_builder.build()?;
+
     let export_menu = SubmenuBuilder::new(handle, "Export")
         .item(&MenuItemBuilder::with_id("file_export_pdf", "Export as PDF...").enabled(is_project_open).build(handle)?)
         .item(&MenuItemBuilder::with_id("file_export_svg", "Export as SVG (Zip)...").enabled(is_project_open).build(handle)?)
         .item(&MenuItemBuilder::with_id("file_export_png", "Export as PNG (Zip)...").enabled(is_project_open).build(handle)?)
+        .separator()
+        .item(&exports_sub)
         .build()?;
 
     let file_menu = if is_project_open {
@@ -93,6 +230,33 @@ pub fn build_menu<R: Runtime>(handle: &AppHandle<R>, recent_projects: &[RecentPr
         .select_all()
         .build()?;
 
+    let mut format_menu_builder = SubmenuBuilder::new(handle, "Format");
+    let mut group = None;
+    for action in actions {
+        if group.is_some_and(|group| group != action.group.as_str()) {
+            format_menu_builder = format_menu_builder.separator();
+        }
+        group = Some(action.group.as_str());
+        let mut item = MenuItemBuilder::with_id(
+            format!("{}{}", ACTION_MENU_PREFIX, action.id),
+            &action.title,
+        );
+        if let Some(keybinding) = &action.keybinding {
+            // Tauri drops an accelerator it can't parse without a word, so say which.
+            match keybinding.parse::<Accelerator>() {
+                Ok(_) => item = item.accelerator(keybinding),
+                Err(e) => log::warn!(
+                    "ignoring keybinding {:?} of action {}: {}",
+                    keybinding,
+                    action.id,
+                    e
+                ),
+            }
+        }
+        format_menu_builder = format_menu_builder.item(&item.build(handle)?);
+    }
+    let format_menu = format_menu_builder.build()?;
+
     let view_menu = SubmenuBuilder::new(handle, "View")
         .item(&MenuItemBuilder::with_id("view_toggle_sidebar", "Toggle Sidebar").accelerator("CmdOrCtrl+B").enabled(is_project_open).build(handle)?)
         .item(&MenuItemBuilder::with_id("view_toggle_preview", "Toggle Preview").accelerator("CmdOrCtrl+\\").enabled(is_project_open).build(handle)?)
@@ -105,57 +269,145 @@ pub fn build_menu<R: Runtime>(handle: &AppHandle<R>, recent_projects: &[RecentPr
         .item(&MenuItemBuilder::with_id("packages_install", "Install Package...").enabled(is_project_open).build(handle)?)
         .build()?;
 
+    let window_menu = SubmenuBuilder::new(handle, "Window")
+        .item(&MenuItemBuilder::with_id("window_new_window", "New Window").accelerator("CmdOrCtrl+Shift+N").build(handle)?)
+        .item(&MenuItemBuilder::with_id("window_new_tab", "New Tab").accelerator("CmdOrCtrl+T").build(handle)?)
+        .separator()
+        .minimize()
+        .maximize()
+        .close_window()
+        .build()?;
+    // Lets macOS add its tab items, such as Show Tab Bar and Merge All Windows.
+    #[cfg(target_os = "macos")]
+    window_menu.set_as_windows_menu_for_nsapp()?;
+
     let help_menu = SubmenuBuilder::new(handle, "Help")
         .text("help_documentation", "Typst Documentation")
         .text("help_typstudio", "Typstudio Help")
         .build()?;
 
     let menu = MenuBuilder::new(handle)
-        .items(&[&app_menu, &file_menu, &edit_menu, &view_menu, &packages_menu, &help_menu])
+        .items(&[&app_menu, &file_menu, &edit_menu, &format_menu, &view_menu, &packages_menu, &window_menu, &help_menu])
         .build()?;
 
+    let context = MenuContext {
+        project_open: is_project_open,
+        ..menu_context(handle)
+    };
+    apply_menu_context(&menu.items()?, &context);
+
     Ok(menu)
 }
 
-pub fn handle_menu_event<R: Runtime>(app: &AppHandle<R>, event: MenuEvent) {
-    let id = event.id.as_ref();
-    
-    // Logic similar to before, but getting window might be different.
-    // In v2, MenuEvent doesn't carry the window directly unless it's a WindowMenu?
-    // But we are setting app-wide menu.
-    // We can get the main window from app handle.
-    
-    // Use the first available window if "main" is not found
-    let window = app.get_webview_window("main")
-        .or_else(|| app.webview_windows().values().next().cloned());
+/// The label of the window that last had focus, managed as app state.
+#[derive(Default)]
+pub struct FocusedWindow(Mutex<Option<String>>);
 
-    if window.is_none() { 
-        log::warn!("No window found for menu event");
-        return; 
+/// Records that `window` gained focus and makes the menu show its context, starting from
+/// its project's for a window the menu hasn't seen yet.
+pub fn window_focused<R: Runtime>(window: &WebviewWindow<R>) {
+    let app = window.app_handle();
+    if let Some(state) = app.try_state::<FocusedWindow>() {
+        *state.0.lock().unwrap() = Some(window.label().to_string());
+    }
+    let Some(state) = app.try_state::<MenuState>() else {
+        return;
+    };
+    let context = *state
+        .0
+        .lock()
+        .unwrap()
+        .entry(window.label().to_string())
+        .or_insert_with(|| {
+            let project = app
+                .try_state::<Arc<ProjectManager<R>>>()
+                .and_then(|project_manager| project_manager.get_project(window));
+            MenuContext::for_project(project.as_deref())
+        });
+    if let Some(menu) = app.menu() {
+        apply_menu_context(&menu.items().unwrap_or_default(), &context);
+    }
+}
+
+/// The window menu items act on: the focused one, else the one focused last, since the
+/// app menu can take focus from it on some platforms.
+pub fn menu_target_window<R: Runtime>(app: &AppHandle<R>) -> Option<WebviewWindow<R>> {
+    let windows = app.webview_windows();
+    if let Some(window) = windows.values().find(|w| w.is_focused().unwrap_or(false)) {
+        return Some(window.clone());
+    }
+    let last = app
+        .try_state::<FocusedWindow>()
+        .and_then(|state| state.0.lock().unwrap().clone());
+    last.and_then(|label| windows.get(&label).cloned())
+        .or_else(|| windows.get("main").cloned())
+        .or_else(|| windows.values().next().cloned())
+}
+
+/// Rebuilds the menu for `window`'s project, eg. after the recent projects changed.
+pub fn rebuild_menu<R: Runtime>(window: &WebviewWindow<R>) -> tauri::Result<()> {
+    let recent = recent_projects().unwrap_or_else(|e| {
+        log::warn!("Failed to read recent projects: {}", e);
+        vec![]
+    });
+    let project = window
+        .try_state::<Arc<ProjectManager<R>>>()
+        .and_then(|project_manager| project_manager.get_project(window));
+    let exports = project
+        .as_ref()
+        .map(|project| {
+            recent_exports(&project.root).unwrap_or_else(|e| {
+                log::warn!("Failed to read recent exports: {}", e);
+                vec![]
+            })
+        })
+        .unwrap_or_default();
+    let actions = document_actions(project.as_deref());
+    let menu = build_menu(window.app_handle(), &recent, &exports, project.is_some(), &actions)?;
+    window.app_handle().set_menu(menu)?;
+    Ok(())
+}
+
+/// Opens an exported file with the system viewer, or shows it in the file manager.
+pub fn open_export(path: &std::path::Path, reveal: bool) -> Result<(), opener::OpenError> {
+    if reveal {
+        opener::reveal(path)
+    } else {
+        opener::open(path)
+    }
+}
+
+/// Tells every window about the new list of recent projects and updates Open Recent.
+pub fn recent_projects_changed<R: Runtime>(window: &WebviewWindow<R>, projects: &[RecentProject]) {
+    let _ = window.app_handle().emit("recent_projects_changed", projects);
+    if let Err(e) = rebuild_menu(window) {
+        log::error!("Failed to rebuild menu: {}", e);
     }
-    let window = window.unwrap();
+}
+
+pub fn handle_menu_event<R: Runtime>(app: &AppHandle<R>, event: MenuEvent) {
+    let Some(window) = menu_target_window(app) else {
+        log::warn!("No window found for menu event");
+        return;
+    };
+    run_menu_action(app, window, event.id.as_ref());
+}
 
+/// Runs the action of a menu item for `window`, eg. when chosen from the command palette.
+pub fn run_menu_action<R: Runtime>(app: &AppHandle<R>, window: WebviewWindow<R>, id: &str) {
     match id {
         "file_new_file" => {
-            let _ = window.emit("menu_new_file", ());
+            emit_to_window(&window, "menu_new_file", ());
         }
         "file_new_project" => {
-            let app_handle = app.clone();
             tauri::async_runtime::spawn(async move {
-                // ... same logic
                  use crate::ipc::commands::create_playground;
-                 // Assuming create_playground is available
                  match create_playground().await {
                     Ok(path) => {
                          let path = fs::canonicalize(&path).unwrap_or(PathBuf::from(path));
-                          let project = Arc::new(Project::load_from_path(path, None));
-                         let project_manager: State<'_, Arc<ProjectManager<R>>> = app_handle.state();
-                         let w = app_handle.get_webview_window("main")
-                             .or_else(|| app_handle.webview_windows().values().next().cloned());
-                         
-                         if let Some(w) = w {
-                             project_manager.set_project(&w, Some(project));
-                         }
+                         let project = Arc::new(Project::load_from_path(path, None));
+                         let project_manager: State<'_, Arc<ProjectManager<R>>> = window.state();
+                         project_manager.set_project(&window, Some(project));
                     }
                     Err(e) => log::error!("Failed: {:?}", e),
                  }
@@ -169,17 +421,25 @@ pub fn handle_menu_event<R: Runtime>(app: &AppHandle<R>, event: MenuEvent) {
                       if let Ok(path) = path.into_path() {
                           let path = fs::canonicalize(&path).unwrap_or(path);
                           let project_manager: State<'_, Arc<ProjectManager<R>>> = window_clone.state();
-                           let project = Arc::new(Project::load_from_path(path, None));
+                           let project = Arc::new(Project::load_from_path(path.clone(), None));
                           project_manager.set_project(&window_clone, Some(project));
+                          match add_recent_project(&path) {
+                              Ok(projects) => recent_projects_changed(&window_clone, &projects),
+                              Err(e) => log::error!("Failed to update recent projects: {}", e),
+                          }
                       }
                  }
              });
         }
-        "file_save" => { let _ = window.emit("menu_save", ()); }
-        "file_save_all" => { let _ = window.emit("menu_save_all", ()); }
-        "file_export_pdf" => { let _ = window.emit("menu_export_pdf", ()); }
-        "file_export_svg" => { let _ = window.emit("menu_export_svg", ()); }
-        "file_export_png" => { let _ = window.emit("menu_export_png", ()); }
+        "file_save" => { emit_to_window(&window, "menu_save", ()); }
+        "file_save_all" => { emit_to_window(&window, "menu_save_all", ()); }
+        "file_export_pdf" => { emit_to_window(&window, "menu_export_pdf", ()); }
+        "file_export_svg" => { emit_to_window(&window, "menu_export_svg", ()); }
+        "file_export_png" => { emit_to_window(&window, "menu_export_png", ()); }
+        "file_export_html" => { emit_to_window(&window, "menu_export_html", ()); }
+        "file_export_epub" => { emit_to_window(&window, "menu_export_epub", ()); }
+        "file_export_markdown" => { emit_to_window(&window, "menu_export_markdown", ()); }
+        "file_print" => { emit_to_window(&window, "menu_print", ()); }
         "file_close_project" => {
              let project_manager: State<'_, Arc<ProjectManager<R>>> = window.state();
              project_manager.set_project(&window, None);
@@ -187,15 +447,51 @@ pub fn handle_menu_event<R: Runtime>(app: &AppHandle<R>, event: MenuEvent) {
         // ... exports ...
         // file_recent_item_ ...
         id if id.starts_with("file_recent_item_") => {
-             if let Ok(index) = id.strip_prefix("file_recent_item_").unwrap_or("0").parse::<usize>() {
-                  let _ = window.emit("menu_open_recent", index);
+             let index = id["file_recent_item_".len()..].parse::<usize>().ok();
+             let recent = recent_projects().unwrap_or_default();
+             if let Some(project) = index.and_then(|index| recent.get(index)) {
+                  emit_to_window(&window, "menu_open_recent", &project.path);
+             }
+        }
+        id if id.starts_with("file_recent_export_") => {
+             let (reveal, index) = if let Some(index) = id.strip_prefix("file_recent_export_reveal_") {
+                  (true, index)
+             } else if let Some(index) = id.strip_prefix("file_recent_export_open_") {
+                  (false, index)
+             } else {
+                  return;
+             };
+             let project = app.state::<Arc<ProjectManager<R>>>().get_project(&window);
+             let exports = project
+                  .map(|project| recent_exports(&project.root).unwrap_or_default())
+                  .unwrap_or_default();
+             if let Some(export) = index.parse::<usize>().ok().and_then(|index| exports.get(index)) {
+                  if let Err(e) = open_export(&export.path, reveal) {
+                       log::error!("Failed to open export {:?}: {}", export.path, e);
+                  }
+             }
+        }
+        "file_clear_recent" => match clear_recent_projects() {
+             Ok(projects) => recent_projects_changed(&window, &projects),
+             Err(e) => log::error!("Failed to clear recent projects: {}", e),
+        },
+        "view_toggle_sidebar" => { emit_to_window(&window, "toggle_sidebar", ()); }
+        "view_toggle_preview" => { emit_to_window(&window, "toggle_preview", ()); }
+        "view_diff" => { emit_to_window(&window, "menu_view_diff", ()); }
+        "packages_install" => { emit_to_window(&window, "show_install_package", ()); }
+        id if id.starts_with(ACTION_MENU_PREFIX) => {
+             emit_to_window(&window, "document_action", &id[ACTION_MENU_PREFIX.len()..]);
+        }
+        "window_new_window" => {
+             if let Err(e) = crate::window::open_project_window(app, None, false) {
+                  log::error!("Failed to open window: {}", e);
+             }
+        }
+        "window_new_tab" => {
+             if let Err(e) = crate::window::open_project_window(app, None, true) {
+                  log::error!("Failed to open tab: {}", e);
              }
         }
-        "file_clear_recent" => { let _ = window.emit("menu_clear_recent", ()); }
-        "view_toggle_sidebar" => { let _ = window.emit("toggle_sidebar", ()); }
-        "view_toggle_preview" => { let _ = window.emit("toggle_preview", ()); }
-        "view_diff" => { let _ = window.emit("menu_view_diff", ()); }
-        "packages_install" => { let _ = window.emit("show_install_package", ()); }
         "help_documentation" => {
              let _ = app.opener().open_url("https://typst.app/docs/", None::<&str>);
         }
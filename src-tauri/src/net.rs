@@ -0,0 +1,23 @@
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+
+/// The address of this machine on the local network, for links other devices open, or
+/// the loopback address without a network. Connecting a UDP socket picks the interface
+/// of the default route without sending anything.
+pub fn lan_address() -> IpAddr {
+    UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .and_then(|socket| {
+            socket.connect((Ipv4Addr::new(192, 0, 2, 1), 9))?;
+            socket.local_addr()
+        })
+        .map(|address| address.ip())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST))
+}
+
+/// A hard to guess token for links that give access to this machine, eg. a shared
+/// session: 128 bits from the system's secure random number generator.
+pub fn random_token() -> io::Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(io::Error::other)?;
+    Ok(hex::encode(bytes))
+}
@@ -0,0 +1,195 @@
+use crate::actions::document_actions;
+use crate::menu::MenuContext;
+use crate::project::Project;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// An action of the native menu, runnable from the command palette as well.
+struct MenuAction {
+    id: &'static str,
+    title: &'static str,
+    category: &'static str,
+    keybinding: Option<&'static str>,
+    needs_project: bool,
+}
+
+const fn action(
+    id: &'static str,
+    title: &'static str,
+    category: &'static str,
+    keybinding: Option<&'static str>,
+    needs_project: bool,
+) -> MenuAction {
+    MenuAction {
+        id,
+        title,
+        category,
+        keybinding,
+        needs_project,
+    }
+}
+
+#[rustfmt::skip]
+const MENU_ACTIONS: &[MenuAction] = &[
+    action("file_new_file", "New File", "File", Some("CmdOrCtrl+N"), true),
+    action("file_new_project", "New Project", "File", None, false),
+    action("file_open_project", "Open Project...", "File", Some("CmdOrCtrl+O"), false),
+    action("file_save", "Save", "File", Some("CmdOrCtrl+S"), true),
+    action("file_save_all", "Save All", "File", None, true),
+    action("file_export_pdf", "Export as PDF...", "Export", None, true),
+    action("file_export_svg", "Export as SVG (Zip)...", "Export", None, true),
+    action("file_export_png", "Export as PNG (Zip)...", "Export", None, true),
+    action("file_export_html", "Export as HTML...", "Export", None, true),
+    action("file_export_epub", "Export as EPUB...", "Export", None, true),
+    action("file_export_markdown", "Export as Markdown...", "Export", None, true),
+    action("file_print", "Print...", "Export", None, true),
+    action("file_close_project", "Close Project", "File", None, true),
+    action("file_clear_recent", "Clear Recent Projects", "File", None, false),
+    action("view_toggle_sidebar", "Toggle Sidebar", "View", Some("CmdOrCtrl+B"), true),
+    action("view_toggle_preview", "Toggle Preview", "View", Some("CmdOrCtrl+\\"), true),
+    action("view_diff", "View Diff", "View", None, true),
+    action("packages_install", "Install Package...", "Packages", None, true),
+    action("window_new_window", "New Window", "Window", Some("CmdOrCtrl+Shift+N"), false),
+    action("window_new_tab", "New Tab", "Window", Some("CmdOrCtrl+T"), false),
+    action("help_documentation", "Typst Documentation", "Help", None, false),
+    action("help_typstudio", "Typstudio Help", "Help", None, false),
+];
+
+/// An entry of the command palette.
+#[derive(Serialize, Clone, Debug)]
+pub struct PaletteCommand {
+    pub id: String,
+    pub title: String,
+    pub category: String,
+    pub enabled: bool,
+    /// An accelerator such as `CmdOrCtrl+S`.
+    pub keybinding: Option<String>,
+}
+
+/// What running a palette command does.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaletteAction {
+    /// Runs the menu item with this id.
+    Menu(String),
+    /// Flips a preview toggle of the project config.
+    Toggle(String),
+    /// Runs the figure generator of this output.
+    Generator(PathBuf),
+    /// Applies a document action in the editor.
+    Document(String),
+    /// Reverts the last multi-file edit, eg. a rename or a replace-all.
+    UndoWorkspaceEdit,
+}
+
+const TOGGLE_PREFIX: &str = "toggle:";
+const GENERATOR_PREFIX: &str = "generator:";
+const DOCUMENT_PREFIX: &str = "action:";
+const UNDO_WORKSPACE_EDIT: &str = "refactor:undo";
+
+/// Lists every action the backend can run: the menu's actions, enabled like their menu
+/// items, the document actions, then the open project's refactors, toggles and figure
+/// generators.
+pub fn palette_commands(project: Option<&Project>, context: &MenuContext) -> Vec<PaletteCommand> {
+    let mut commands: Vec<PaletteCommand> = MENU_ACTIONS
+        .iter()
+        .map(|action| PaletteCommand {
+            id: action.id.into(),
+            title: action.title.into(),
+            category: action.category.into(),
+            enabled: (project.is_some() || !action.needs_project)
+                && context.enabled(action.id).unwrap_or(true),
+            keybinding: action.keybinding.map(Into::into),
+        })
+        .collect();
+    for action in document_actions(project) {
+        commands.push(PaletteCommand {
+            id: format!("{}{}", DOCUMENT_PREFIX, action.id),
+            title: action.title,
+            category: "Format".into(),
+            enabled: project.is_some(),
+            keybinding: action.keybinding,
+        });
+    }
+
+    let Some(project) = project else {
+        return commands;
+    };
+    if let Some(label) = project.journal.peek() {
+        commands.push(PaletteCommand {
+            id: UNDO_WORKSPACE_EDIT.into(),
+            title: format!("Undo {}", label),
+            category: "Refactor".into(),
+            enabled: true,
+            keybinding: None,
+        });
+    }
+    let config = project.config.read().unwrap();
+    let preview = project.preview_inputs.read().unwrap();
+    for (name, &default) in &config.toggles {
+        let value = preview.toggles.get(name).copied().unwrap_or(default);
+        commands.push(PaletteCommand {
+            id: format!("{}{}", TOGGLE_PREFIX, name),
+            title: format!("Turn {} {}", name, if value { "Off" } else { "On" }),
+            category: "Toggles".into(),
+            enabled: true,
+            keybinding: None,
+        });
+    }
+    for generator in &config.generators {
+        let output = generator.output.to_string_lossy();
+        commands.push(PaletteCommand {
+            id: format!("{}{}", GENERATOR_PREFIX, output),
+            title: format!("Regenerate {}", output),
+            category: "Generators".into(),
+            enabled: true,
+            keybinding: None,
+        });
+    }
+    commands
+}
+
+/// Resolves a palette command id. Returns `None` for unknown menu ids.
+pub fn palette_action(id: &str) -> Option<PaletteAction> {
+    if id == UNDO_WORKSPACE_EDIT {
+        return Some(PaletteAction::UndoWorkspaceEdit);
+    }
+    if let Some(name) = id.strip_prefix(TOGGLE_PREFIX) {
+        return Some(PaletteAction::Toggle(name.into()));
+    }
+    if let Some(output) = id.strip_prefix(GENERATOR_PREFIX) {
+        return Some(PaletteAction::Generator(output.into()));
+    }
+    if let Some(action) = id.strip_prefix(DOCUMENT_PREFIX) {
+        return Some(PaletteAction::Document(action.into()));
+    }
+    MENU_ACTIONS
+        .iter()
+        .any(|action| action.id == id)
+        .then(|| PaletteAction::Menu(id.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{palette_action, PaletteAction};
+
+    #[test]
+    fn test_palette_action() {
+        assert_eq!(
+            palette_action("file_save"),
+            Some(PaletteAction::Menu("file_save".into()))
+        );
+        assert_eq!(
+            palette_action("toggle:solutions"),
+            Some(PaletteAction::Toggle("solutions".into()))
+        );
+        assert_eq!(
+            palette_action("generator:figures/plot.svg"),
+            Some(PaletteAction::Generator("figures/plot.svg".into()))
+        );
+        assert_eq!(
+            palette_action("refactor:undo"),
+            Some(PaletteAction::UndoWorkspaceEdit)
+        );
+        assert_eq!(palette_action("file_format_disk"), None);
+    }
+}
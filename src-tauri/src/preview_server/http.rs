@@ -0,0 +1,154 @@
+//! Just enough HTTP/1.1 for the preview server: `GET` requests without a body, answered
+//! with a whole response or an event stream, one request per connection.
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Requests with a longer head are refused.
+const MAX_HEAD: usize = 8 * 1024;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    /// Without the query.
+    pub path: String,
+}
+
+/// Parses the request line of a request head.
+pub fn parse_request(head: &str) -> Option<Request> {
+    let line = head.lines().next()?;
+    let mut parts = line.split(' ');
+    let method = parts.next()?.to_string();
+    let target = parts.next()?;
+    if !parts.next()?.starts_with("HTTP/1.") || !target.starts_with('/') {
+        return None;
+    }
+    let path = target.split(['?', '#']).next()?.to_string();
+    Some(Request { method, path })
+}
+
+/// Reads the head of a request, up to the blank line.
+pub async fn read_request<S: AsyncRead + Unpin>(stream: &mut S) -> Option<Request> {
+    let mut head = Vec::new();
+    let mut buffer = [0; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).await.ok()?;
+        if read == 0 || head.len() + read > MAX_HEAD {
+            return None;
+        }
+        head.extend_from_slice(&buffer[..read]);
+    }
+    parse_request(std::str::from_utf8(&head).ok()?)
+}
+
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+    pub location: Option<String>,
+}
+
+impl Response {
+    pub fn ok(content_type: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: 200,
+            content_type,
+            body: body.into(),
+            location: None,
+        }
+    }
+
+    /// A permanent redirect to `location`.
+    pub fn redirect(location: String) -> Self {
+        Self {
+            location: Some(location),
+            ..Self::error(301)
+        }
+    }
+
+    pub fn error(status: u16) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: reason(status).as_bytes().to_vec(),
+            location: None,
+        }
+    }
+
+    /// The response as sent, head and body.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let location = self.location.as_ref().map_or(String::new(), |location| {
+            format!("Location: {}\r\n", location)
+        });
+        let mut bytes = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n{}Cache-Control: no-store\r\nConnection: close\r\n\r\n",
+            self.status,
+            reason(self.status),
+            self.content_type,
+            self.body.len(),
+            location
+        )
+        .into_bytes();
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+}
+
+/// The head of a response that sends server-sent events until the connection closes.
+pub const EVENT_STREAM_HEAD: &str = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-store\r\nConnection: keep-alive\r\n\r\n";
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        301 => "Moved Permanently",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request() {
+        let request = parse_request("GET /abc/page/2.svg?v=7 HTTP/1.1\r\nHost: x\r\n\r\n");
+        assert_eq!(
+            request,
+            Some(Request {
+                method: "GET".to_string(),
+                path: "/abc/page/2.svg".to_string(),
+            })
+        );
+        assert_eq!(parse_request("GET abc HTTP/1.1\r\n\r\n"), None);
+        assert_eq!(parse_request("GET /abc SMTP\r\n\r\n"), None);
+        assert_eq!(parse_request(""), None);
+    }
+
+    #[tokio::test]
+    async fn test_read_request() {
+        let mut input: &[u8] = b"GET / HTTP/1.1\r\nHost: tablet\r\n\r\n";
+        let request = read_request(&mut input).await.unwrap();
+        assert_eq!(request.path, "/");
+
+        let mut truncated: &[u8] = b"GET / HTTP/1.1\r\nHost: tab";
+        assert_eq!(read_request(&mut truncated).await, None);
+    }
+
+    #[test]
+    fn test_response() {
+        let bytes = Response::ok("text/plain", "hi").to_bytes();
+        let text = String::from_utf8(bytes).unwrap();
+        assert!(text.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(text.contains("Content-Length: 2\r\n"));
+        assert!(text.ends_with("\r\n\r\nhi"));
+
+        let bytes = Response::redirect("/abc/".to_string()).to_bytes();
+        let text = String::from_utf8(bytes).unwrap();
+        assert!(text.starts_with("HTTP/1.1 301 Moved Permanently\r\n"));
+        assert!(text.contains("\r\nLocation: /abc/\r\n"));
+    }
+}
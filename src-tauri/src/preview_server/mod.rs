@@ -0,0 +1,4 @@
+mod http;
+mod server;
+
+pub use server::*;
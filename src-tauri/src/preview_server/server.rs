@@ -0,0 +1,170 @@
+use super::http::{read_request, Response, EVENT_STREAM_HEAD};
+use crate::project::Project;
+use log::{debug, info};
+use serde::Serialize;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use tokio::task::{JoinHandle, JoinSet};
+
+const VIEWER: &str = include_str!("viewer.html");
+
+/// Pixels per point of the PNG pages, sharp on high density tablet screens.
+const PNG_SCALE: f32 = 3.0;
+
+/// How often an idle event stream is written to, to notice viewers that went away.
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// How long a connection may take to send its request head.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PreviewServerInfo {
+    /// The viewer page, eg. `http://192.168.1.20:4322/3f2a…/`.
+    pub url: String,
+}
+
+/// Serves the pages of a project's latest compiled document to browsers on this
+/// machine, or on the local network, eg. a tablet next to the screen. The viewer reloads
+/// the pages after every compile. Stops when dropped.
+pub struct PreviewServer {
+    info: PreviewServerInfo,
+    task: JoinHandle<()>,
+}
+
+impl PreviewServer {
+    /// Serves `project` on `port`, or any free port, of the loopback interface, or of
+    /// every network interface with `lan`. Paths start with a random token, so only
+    /// those given the link can see the document.
+    pub async fn start(project: &Arc<Project>, port: Option<u16>, lan: bool) -> io::Result<Self> {
+        let (bind, host) = if lan {
+            (Ipv4Addr::UNSPECIFIED, crate::net::lan_address())
+        } else {
+            (Ipv4Addr::LOCALHOST, Ipv4Addr::LOCALHOST.into())
+        };
+        let listener = TcpListener::bind(SocketAddr::from((bind, port.unwrap_or(0)))).await?;
+        let port = listener.local_addr()?.port();
+        let token = crate::net::random_token()?;
+        let info = PreviewServerInfo {
+            url: format!("http://{}:{}/{}/", host, port, token),
+        };
+        info!("serving the preview of {:?} on port {}", project.root, port);
+
+        let project = Arc::downgrade(project);
+        let prefix: Arc<str> = format!("/{}", token).into();
+        let task = tokio::spawn(async move {
+            // Dropping the set when the server stops closes its connections too.
+            let mut connections = JoinSet::new();
+            loop {
+                tokio::select! {
+                    accepted = listener.accept() => {
+                        let Ok((stream, address)) = accepted else { continue };
+                        debug!("preview viewer connected from {}", address);
+                        connections.spawn(serve(project.clone(), prefix.clone(), stream));
+                    }
+                    Some(_) = connections.join_next() => {}
+                }
+            }
+        });
+        Ok(Self { info, task })
+    }
+
+    pub fn info(&self) -> PreviewServerInfo {
+        self.info.clone()
+    }
+}
+
+impl Drop for PreviewServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn serve(project: Weak<Project>, prefix: Arc<str>, mut stream: TcpStream) {
+    let response = match tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut stream)).await {
+        Err(_) => Response::error(408),
+        Ok(None) => Response::error(400),
+        Ok(Some(request)) if request.method != "GET" => Response::error(405),
+        Ok(Some(request)) => match request.path.strip_prefix(&*prefix) {
+            // The viewer's links are relative to the directory of the token.
+            Some("") => Response::redirect(format!("{}/", prefix)),
+            Some("/events") => {
+                if let Some(project) = project.upgrade() {
+                    let versions = project.document_versions.subscribe();
+                    drop(project);
+                    let _ = stream_events(&mut stream, versions).await;
+                }
+                return;
+            }
+            Some(route) => respond(project, route.to_string()).await,
+            None => Response::error(404),
+        },
+    };
+    let _ = stream.write_all(&response.to_bytes()).await;
+}
+
+async fn respond(project: Weak<Project>, route: String) -> Response {
+    let Some(project) = project.upgrade() else {
+        return Response::error(503);
+    };
+    tokio::task::spawn_blocking(move || route_response(&project, &route))
+        .await
+        .unwrap_or_else(|_| Response::error(500))
+}
+
+fn route_response(project: &Project, route: &str) -> Response {
+    if route == "/" {
+        return Response::ok("text/html; charset=utf-8", VIEWER);
+    }
+    let cache = project.cache.read().unwrap();
+    if route == "/pages" {
+        let pages = cache.document.as_ref().map_or(0, |doc| doc.pages.len());
+        let body = serde_json::json!({ "version": cache.version, "pages": pages });
+        return Response::ok("application/json", body.to_string());
+    }
+
+    let Some((index, format)) = route
+        .strip_prefix("/page/")
+        .and_then(|page| page.split_once('.'))
+    else {
+        return Response::error(404);
+    };
+    let page = index
+        .parse::<usize>()
+        .ok()
+        .and_then(|index| cache.document.as_ref()?.pages.get(index).cloned());
+    drop(cache);
+    match (page, format) {
+        (Some(page), "svg") => Response::ok("image/svg+xml", typst_svg::svg(&page)),
+        (Some(page), "png") => match typst_render::render(&page, PNG_SCALE).encode_png() {
+            Ok(png) => Response::ok("image/png", png),
+            Err(_) => Response::error(500),
+        },
+        _ => Response::error(404),
+    }
+}
+
+/// Sends the document version as an event after every compile, until the viewer leaves.
+async fn stream_events(
+    stream: &mut TcpStream,
+    mut versions: watch::Receiver<u64>,
+) -> io::Result<()> {
+    stream.write_all(EVENT_STREAM_HEAD.as_bytes()).await?;
+    let mut keep_alive = tokio::time::interval(KEEP_ALIVE);
+    loop {
+        tokio::select! {
+            changed = versions.changed() => {
+                if changed.is_err() {
+                    return Ok(());
+                }
+                let version = *versions.borrow_and_update();
+                stream.write_all(format!("data: {}\n\n", version).as_bytes()).await?;
+            }
+            _ = keep_alive.tick() => stream.write_all(b": keep-alive\n\n").await?,
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use crate::ipc::AutosavedEvent;
+use crate::ipc::events::emit_to_window;
+use crate::project::{FileStamp, Project, ProjectManager, RecoveryJournal};
+use crate::settings::app_settings;
+use log::{info, warn};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{Runtime, WebviewWindow};
+
+/// How often the autosave service checks whether saving is due.
+const AUTOSAVE_TICK: Duration = Duration::from_secs(1);
+
+/// Editor buffers with changes not yet on disk, keyed by absolute path. Each is also
+/// journaled for crash recovery until it is saved or cleared.
+pub struct DirtyBuffers {
+    buffers: Mutex<BTreeMap<PathBuf, String>>,
+    /// Buffers changed since they were last journaled.
+    unjournaled: Mutex<BTreeSet<PathBuf>>,
+    /// Held while journaling, so an entry isn't written back after it was discarded.
+    journaling: Mutex<()>,
+    pub recovery: RecoveryJournal,
+}
+
+impl DirtyBuffers {
+    pub fn new(recovery: RecoveryJournal) -> Self {
+        Self {
+            buffers: Mutex::default(),
+            unjournaled: Mutex::default(),
+            journaling: Mutex::default(),
+            recovery,
+        }
+    }
+
+    /// Records an edit, journaled on the next autosave tick.
+    pub fn record(&self, path: PathBuf, content: String) {
+        self.buffers.lock().unwrap().insert(path.clone(), content);
+        self.unjournaled.lock().unwrap().insert(path);
+    }
+
+    /// Journals the buffers changed since the last call. Done on the autosave tick rather
+    /// than for every edit, so typing doesn't sync a file to disk per keystroke.
+    pub fn journal(&self) {
+        let _journaling = self.journaling.lock().unwrap();
+        let paths = std::mem::take(&mut *self.unjournaled.lock().unwrap());
+        for path in paths {
+            let Some(content) = self.get(&path) else {
+                continue;
+            };
+            if let Err(e) = self.recovery.write(&path, &content) {
+                warn!("failed to journal {:?}: {}", path, e);
+                self.unjournaled.lock().unwrap().insert(path);
+            }
+        }
+    }
+
+    /// Forgets a buffer, eg. once the editor saved it itself.
+    pub fn clear(&self, path: &Path) {
+        let _journaling = self.journaling.lock().unwrap();
+        self.buffers.lock().unwrap().remove(path);
+        self.recovery.discard(path);
+    }
+
+    /// Removes the journal entry of a buffer that was saved or recovered.
+    pub fn discard_journal(&self, path: &Path) {
+        let _journaling = self.journaling.lock().unwrap();
+        self.recovery.discard(path);
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.buffers.lock().unwrap().contains_key(path)
+    }
+
+    /// The unsaved content of the file at `path`.
+    pub fn get(&self, path: &Path) -> Option<String> {
+        self.buffers.lock().unwrap().get(path).cloned()
+    }
+
+    /// The files with unsaved changes.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.buffers.lock().unwrap().keys().cloned().collect()
+    }
+
+    fn take(&self) -> BTreeMap<PathBuf, String> {
+        std::mem::take(&mut *self.buffers.lock().unwrap())
+    }
+
+    /// Puts back a buffer that wasn't saved, unless a newer version was recorded.
+    fn restore(&self, path: PathBuf, content: String) {
+        self.buffers.lock().unwrap().entry(path).or_insert(content);
+    }
+}
+
+/// Writes the project's dirty buffers that differ from the disk. Files changed on disk
+/// since the editor read them, or existing files it never read, are left alone and
+/// stay dirty, as saving them would be a conflict.
+pub fn autosave_project(project: &Project) -> AutosavedEvent {
+    let mut event = AutosavedEvent::default();
+    let relative =
+        |path: &Path| Path::new("/").join(path.strip_prefix(&project.root).unwrap_or(path));
+    for (path, content) in project.dirty_buffers.take() {
+        let stamp = FileStamp::new(content.as_bytes(), None);
+        // Read right before writing, as the file may have changed since the last save.
+        if let Ok((current, _)) = FileStamp::read(&path) {
+            if !current.differs(&stamp) {
+                project.stamps.set(path.clone(), current);
+                project.dirty_buffers.discard_journal(&path);
+                continue;
+            }
+            let known = project.stamps.get(&path);
+            if known.map_or(true, |known| current.differs(&known)) {
+                event.conflicts.push(relative(&path));
+                project.dirty_buffers.restore(path, content);
+                continue;
+            }
+        }
+        if let Err(e) = fs::write(&path, &content) {
+            warn!("failed to autosave {:?}: {}", path, e);
+            project.dirty_buffers.restore(path, content);
+            continue;
+        }
+        let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+        project
+            .stamps
+            .set(path.clone(), FileStamp::new(content.as_bytes(), modified));
+        project.dirty_buffers.discard_journal(&path);
+        if let Err(e) = project.history.record(&path, content.as_bytes()) {
+            warn!("failed to snapshot {:?}: {}", path, e);
+        }
+        // Like an explicit save, so the automatic export waits for a compile of it.
+        let world = project.world.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = world.slot_update(relative(&path), Some(content)) {
+            warn!("failed to update {:?} after autosaving it: {:?}", path, e);
+        }
+        drop(world);
+        event.paths.push(relative(&path));
+    }
+    event
+}
+
+/// Autosaves the project of `window` and tells the window what was written.
+pub fn autosave_window<R: Runtime>(window: &WebviewWindow<R>, project: &Arc<Project>) {
+    let event = autosave_project(project);
+    if !event.paths.is_empty() {
+        project.auto_exporter.saved(window, project);
+    }
+    if !event.paths.is_empty() || !event.conflicts.is_empty() {
+        info!("autosaved {:?} of {:?}", event.paths, project.root);
+        emit_to_window(window, "autosaved", event);
+    }
+}
+
+/// Journals the dirty buffers of all windows every tick, and saves them at the interval
+/// configured in the settings.
+pub fn spawn_autosave<R: Runtime>(project_manager: Arc<ProjectManager<R>>) {
+    tokio::spawn(async move {
+        let mut last_save = Instant::now();
+        loop {
+            tokio::time::sleep(AUTOSAVE_TICK).await;
+            let save = app_settings()
+                .autosave_interval_secs
+                .is_some_and(|interval| last_save.elapsed() >= Duration::from_secs(interval));
+            if save {
+                last_save = Instant::now();
+            }
+            let project_manager = project_manager.clone();
+            let _ = tokio::task::spawn_blocking(move || {
+                for (window, project) in project_manager.windows() {
+                    project.dirty_buffers.journal();
+                    if save {
+                        autosave_window(&window, &project);
+                    }
+                }
+            })
+            .await;
+        }
+    });
+}
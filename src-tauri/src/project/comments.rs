@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+use siphasher::sip::SipHasher;
+use std::fs;
+use std::hash::Hasher;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+pub const COMMENTS_FILE: &str = ".typstudio/comments.json";
+
+/// How many characters of the commented text are kept to show with the comment.
+const EXCERPT_CHARS: usize = 80;
+
+/// Feedback on a range of a file, eg. from an advisor.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReviewComment {
+    pub id: u64,
+    /// The project path of the file, eg. `/chapters/one.typ`.
+    pub path: PathBuf,
+    /// The character range of the commented text, as of the last time it was found.
+    pub range: Range<usize>,
+    /// The start of the commented text.
+    pub excerpt: String,
+    /// The hash of the whole commented text, to find it again once edits moved it.
+    pub hash: String,
+    pub author: String,
+    pub body: String,
+    /// Seconds since the Unix epoch.
+    pub created: i64,
+    #[serde(default)]
+    pub resolved: bool,
+    /// Whether the commented text was changed or deleted, so the comment lost its place.
+    #[serde(default)]
+    pub orphaned: bool,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct CommentsFile {
+    next_id: u64,
+    comments: Vec<ReviewComment>,
+}
+
+fn text_hash(text: &str) -> String {
+    let mut hasher = SipHasher::new();
+    hasher.write(text.as_bytes());
+    format!("{:016x}", hasher.finish())
+}
+
+/// The byte range of the character range `range` of `content`.
+fn byte_range(content: &str, range: &Range<usize>) -> Option<Range<usize>> {
+    let to_byte = |offset: usize| {
+        content
+            .char_indices()
+            .nth(offset)
+            .map(|(i, _)| i)
+            .or_else(|| (offset == content.chars().count()).then_some(content.len()))
+    };
+    let (start, end) = (to_byte(range.start)?, to_byte(range.end)?);
+    (start <= end).then_some(start..end)
+}
+
+impl ReviewComment {
+    /// A comment on the character range `range` of `content`, the file at `path`.
+    pub fn new(
+        id: u64,
+        path: PathBuf,
+        content: &str,
+        range: Range<usize>,
+        author: String,
+        body: String,
+    ) -> Option<Self> {
+        let text = &content[byte_range(content, &range)?];
+        Some(Self {
+            id,
+            path,
+            range,
+            excerpt: text.chars().take(EXCERPT_CHARS).collect(),
+            hash: text_hash(text),
+            author,
+            body,
+            created: chrono::Utc::now().timestamp(),
+            resolved: false,
+            orphaned: false,
+        })
+    }
+
+    /// Moves the comment to where its text is in `content` now, the occurrence nearest
+    /// to its last place if there are several, or marks it orphaned. Returns whether the
+    /// comment changed.
+    pub fn reanchor(&mut self, content: &str) -> bool {
+        let before = (self.range.clone(), self.orphaned);
+        let anchored = byte_range(content, &self.range)
+            .is_some_and(|bytes| text_hash(&content[bytes]) == self.hash);
+        if anchored {
+            self.orphaned = false;
+            return before != (self.range.clone(), self.orphaned);
+        }
+
+        let chars = self.range.end - self.range.start;
+        let found = content
+            .match_indices(self.excerpt.as_str())
+            .filter_map(|(start, _)| {
+                let start = content[..start].chars().count();
+                let range = start..start + chars;
+                let bytes = byte_range(content, &range)?;
+                (text_hash(&content[bytes]) == self.hash).then_some(range)
+            })
+            .min_by_key(|range| range.start.abs_diff(self.range.start));
+        match found {
+            Some(range) => {
+                self.range = range;
+                self.orphaned = false;
+            }
+            None => self.orphaned = true,
+        }
+        before != (self.range.clone(), self.orphaned)
+    }
+}
+
+/// The review comments of a project, kept in `.typstudio/comments.json` so they travel
+/// with the project, eg. through git or a shared folder.
+pub struct ReviewComments {
+    file: PathBuf,
+    /// Serializes updates of the file.
+    lock: Mutex<()>,
+}
+
+impl ReviewComments {
+    pub fn new(root: &Path) -> Self {
+        Self::with_file(root.join(COMMENTS_FILE))
+    }
+
+    pub fn with_file(file: PathBuf) -> Self {
+        Self {
+            file,
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn read(&self) -> io::Result<CommentsFile> {
+        match fs::read(&self.file) {
+            Ok(json) => serde_json::from_slice(&json)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(CommentsFile::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn write(&self, comments: &CommentsFile) -> io::Result<()> {
+        let json = serde_json::to_vec_pretty(comments)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if let Some(parent) = self.file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.file, json)
+    }
+
+    pub fn list(&self) -> io::Result<Vec<ReviewComment>> {
+        let _lock = self.lock.lock().unwrap();
+        Ok(self.read()?.comments)
+    }
+
+    /// Changes the comments with `f`, which returns whether anything changed. The file
+    /// is only written then.
+    pub fn update<T>(
+        &self,
+        f: impl FnOnce(&mut Vec<ReviewComment>, &mut u64) -> (T, bool),
+    ) -> io::Result<T> {
+        let _lock = self.lock.lock().unwrap();
+        let mut file = self.read()?;
+        let (result, changed) = f(&mut file.comments, &mut file.next_id);
+        if changed {
+            self.write(&file)?;
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(content: &str, range: Range<usize>) -> ReviewComment {
+        let path = PathBuf::from("/main.typ");
+        ReviewComment::new(
+            1,
+            path,
+            content,
+            range,
+            "Advisor".into(),
+            "Cite this".into(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_reanchor() {
+        let content = "Prior work showed the effect. We confirm it.";
+        let mut comment = comment(content, 0..28);
+        assert_eq!(comment.excerpt, "Prior work showed the effect");
+        assert!(!comment.reanchor(content));
+
+        let moved = "= Related work\nPrior work showed the effect. We confirm it.";
+        assert!(comment.reanchor(moved));
+        assert_eq!(comment.range, 15..43);
+        assert!(!comment.orphaned);
+
+        assert!(comment.reanchor("Prior work showed no effect."));
+        assert!(comment.orphaned);
+        assert_eq!(comment.range, 15..43);
+    }
+
+    #[test]
+    fn test_reanchor_nearest() {
+        let content = "ä ok. ok. ok.";
+        let mut comment = comment(content, 6..8);
+        assert!(!comment.reanchor("ä ok. ok. ok. ok."));
+        assert!(comment.reanchor("ok. ok."));
+        assert_eq!(comment.range, 4..6);
+        assert!(
+            ReviewComment::new(2, "/a.typ".into(), content, 5..40, "".into(), "".into()).is_none()
+        );
+    }
+}
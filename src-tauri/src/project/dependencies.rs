@@ -0,0 +1,33 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+use typst::syntax::FileId;
+
+/// The files each compile target (main file) read during its last successful compile.
+#[derive(Default)]
+pub struct TargetDependencies {
+    targets: RwLock<HashMap<FileId, HashSet<FileId>>>,
+}
+
+impl TargetDependencies {
+    pub fn set(&self, target: FileId, files: HashSet<FileId>) {
+        self.targets.write().unwrap().insert(target, files);
+    }
+
+    /// Forgets the dependencies of a target, eg. after a failed compile that may not
+    /// have reached every include.
+    pub fn invalidate(&self, target: FileId) {
+        self.targets.write().unwrap().remove(&target);
+    }
+
+    /// Whether an edit to `file` may change the output of `target`. Targets without
+    /// recorded dependencies are always affected.
+    pub fn affects(&self, target: FileId, file: FileId) -> bool {
+        if target == file {
+            return true;
+        }
+        match self.targets.read().unwrap().get(&target) {
+            Some(files) => files.contains(&file),
+            None => true,
+        }
+    }
+}
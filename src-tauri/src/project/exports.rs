@@ -0,0 +1,85 @@
+use crate::appdata::{project_app_file, read_app_json, write_app_json};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Older exports are forgotten.
+pub const MAX_RECENT_EXPORTS: usize = 5;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RecentExport {
+    /// Absolute path of the exported file or directory.
+    pub path: PathBuf,
+    /// The export command's format, eg. `pdf` or `epub`.
+    pub format: String,
+    /// Unix time in milliseconds.
+    pub exported_at: i64,
+}
+
+impl RecentExport {
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map_or_else(
+                || self.path.to_string_lossy(),
+                |name| name.to_string_lossy(),
+            )
+            .to_string()
+    }
+}
+
+/// Moves `export` to the front of the list, replacing an earlier export to the same path.
+fn record_export(exports: &mut Vec<RecentExport>, export: RecentExport) {
+    exports.retain(|e| e.path != export.path);
+    exports.insert(0, export);
+    exports.truncate(MAX_RECENT_EXPORTS);
+}
+
+/// The project's latest exports, newest first. Like the session, they are kept in the
+/// app config directory rather than the project.
+pub fn recent_exports(root: &Path) -> io::Result<Vec<RecentExport>> {
+    Ok(read_app_json(&project_app_file("exports", root))?.unwrap_or_default())
+}
+
+pub fn add_recent_export(root: &Path, path: &Path, format: &str) -> io::Result<Vec<RecentExport>> {
+    let mut exports = recent_exports(root)?;
+    record_export(
+        &mut exports,
+        RecentExport {
+            path: path.to_path_buf(),
+            format: format.to_string(),
+            exported_at: chrono::Utc::now().timestamp_millis(),
+        },
+    );
+    write_app_json(&project_app_file("exports", root), &exports)?;
+    Ok(exports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{record_export, RecentExport, MAX_RECENT_EXPORTS};
+    use std::path::PathBuf;
+
+    fn export(path: &str, exported_at: i64) -> RecentExport {
+        RecentExport {
+            path: PathBuf::from(path),
+            format: "pdf".into(),
+            exported_at,
+        }
+    }
+
+    #[test]
+    fn test_record_export() {
+        let mut exports = vec![];
+        for i in 0..MAX_RECENT_EXPORTS + 2 {
+            record_export(&mut exports, export(&format!("/out/{}.pdf", i), i as i64));
+        }
+        assert_eq!(exports.len(), MAX_RECENT_EXPORTS);
+        assert_eq!(exports[0].name(), format!("{}.pdf", MAX_RECENT_EXPORTS + 1));
+
+        record_export(&mut exports, export("/out/3.pdf", 100));
+        assert_eq!(exports.len(), MAX_RECENT_EXPORTS);
+        assert_eq!(exports[0].exported_at, 100);
+        assert_eq!(exports.iter().filter(|e| e.name() == "3.pdf").count(), 1);
+    }
+}
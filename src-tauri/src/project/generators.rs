@@ -0,0 +1,158 @@
+use crate::appdata::is_command_approved;
+use crate::export::run_with_timeout;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+/// An asset produced by a script, eg. `figures/plot1.svg` from `scripts/plot1.py`.
+/// Paths are relative to the project root.
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
+pub struct FigureGenerator {
+    pub output: PathBuf,
+    pub script: PathBuf,
+    /// Program and arguments to run, eg. `["python3", "scripts/plot1.py"]`. Inferred from
+    /// the script's extension if empty.
+    #[serde(default)]
+    pub command: Vec<String>,
+    /// Data files the script reads, so changing them marks the output stale.
+    #[serde(default)]
+    pub inputs: Vec<PathBuf>,
+}
+
+/// The kind of the commands of generators, for [`is_command_approved`].
+pub const GENERATOR_COMMANDS: &str = "generator";
+
+/// How long a script may run before it is killed, eg. one waiting for input.
+const GENERATOR_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GeneratorRun {
+    pub output: PathBuf,
+    pub success: bool,
+    /// Combined stdout and stderr of the script, in the order it printed them.
+    pub log: String,
+    /// Whether the script was killed for running longer than allowed.
+    pub timed_out: bool,
+}
+
+/// Resolves a project-relative path, refusing paths that leave the project.
+fn resolve(root: &Path, path: &Path) -> Option<PathBuf> {
+    let mut out = root.to_path_buf();
+    for component in path.components() {
+        match component {
+            Component::Normal(c) => out.push(c),
+            Component::RootDir | Component::CurDir => {}
+            Component::ParentDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(out)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl FigureGenerator {
+    /// The script and its inputs, relative to the project root.
+    pub fn sources(&self) -> impl Iterator<Item = &PathBuf> {
+        std::iter::once(&self.script).chain(self.inputs.iter())
+    }
+
+    /// Whether `path` (relative to the project root) is the script or one of its inputs.
+    pub fn depends_on(&self, path: &Path) -> bool {
+        let strip = |p: &Path| p.strip_prefix("/").unwrap_or(p).to_path_buf();
+        let path = strip(path);
+        self.sources().any(|source| strip(source) == path)
+    }
+
+    /// The output is stale if it is missing or older than the script or any input.
+    pub fn is_stale(&self, root: &Path) -> bool {
+        let Some(output) = resolve(root, &self.output).and_then(|p| modified(&p)) else {
+            return true;
+        };
+        self.sources()
+            .filter_map(|source| resolve(root, source).and_then(|p| modified(&p)))
+            .any(|source| source > output)
+    }
+
+    /// The command as approved by the user, which changes with any of its arguments.
+    pub fn command_text(&self) -> String {
+        serde_json::to_string(&self.command_line()).unwrap_or_default()
+    }
+
+    /// Whether the user allowed the project at `root` to run this generator's command.
+    pub fn is_approved(&self, root: &Path) -> bool {
+        is_command_approved(root, GENERATOR_COMMANDS, &self.command_text())
+    }
+
+    fn command_line(&self) -> Vec<String> {
+        if !self.command.is_empty() {
+            return self.command.clone();
+        }
+        let script = self.script.to_string_lossy().trim_start_matches('/').to_string();
+        let interpreter = match self.script.extension().and_then(|e| e.to_str()) {
+            Some("py") => Some("python3"),
+            Some("r") | Some("R") => Some("Rscript"),
+            Some("jl") => Some("julia"),
+            Some("js") | Some("mjs") => Some("node"),
+            Some("sh") => Some("sh"),
+            _ => None,
+        };
+        match interpreter {
+            Some(interpreter) => vec![interpreter.to_string(), script],
+            None => vec![format!("./{}", script)],
+        }
+    }
+
+    /// Runs the generator in the project root. The output path is passed in the
+    /// `TYPSTUDIO_OUTPUT` environment variable for scripts that don't hardcode it.
+    /// Refused unless the user approved its command, as any project can define one.
+    /// A script still running after `GENERATOR_TIMEOUT` is killed, failing the run.
+    pub fn run(&self, root: &Path) -> io::Result<GeneratorRun> {
+        if !self.is_approved(root) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("the generator for {:?} is not approved", self.output),
+            ));
+        }
+        let invalid = || io::Error::new(io::ErrorKind::InvalidInput, "path outside of project");
+        let output = resolve(root, &self.output).ok_or_else(invalid)?;
+        resolve(root, &self.script).ok_or_else(invalid)?;
+
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let command_line = self.command_line();
+        let (program, args) = command_line.split_first().ok_or_else(invalid)?;
+        let mut log = String::new();
+        let result = run_with_timeout(
+            Command::new(program)
+                .args(args)
+                .current_dir(root)
+                .env("TYPSTUDIO_OUTPUT", &output),
+            GENERATOR_TIMEOUT,
+            |_, line| {
+                log.push_str(&line);
+                log.push('\n');
+            },
+        );
+        let (success, timed_out) = match result {
+            Ok(status) => (status.success(), false),
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => {
+                log.push_str(&format!("{}\n", e));
+                (false, true)
+            }
+            Err(e) => return Err(e),
+        };
+        Ok(GeneratorRun {
+            output: self.output.clone(),
+            success,
+            log,
+            timed_out,
+        })
+    }
+}
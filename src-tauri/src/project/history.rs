@@ -0,0 +1,299 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use siphasher::sip128::{Hasher128, SipHasher};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::hash::Hasher;
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+/// Where snapshots are kept, relative to the project root.
+pub const HISTORY_DIR: &str = ".typstudio/history";
+
+/// Older snapshots of a file are dropped.
+const MAX_SNAPSHOTS: usize = 100;
+
+/// The oldest snapshots of the project are dropped once their contents add up to more,
+/// eg. for large generated files saved often. The latest snapshot of each file is kept.
+const MAX_HISTORY_SIZE: u64 = 64 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Hash of the content, which also names the stored copy.
+    pub id: String,
+    /// Unix time in milliseconds.
+    pub timestamp: i64,
+    pub size: u64,
+}
+
+/// Snapshots per project-relative path, oldest first.
+type HistoryIndex = BTreeMap<PathBuf, Vec<Snapshot>>;
+
+/// Whether `path` is in a history directory, which the file tree and watcher ignore.
+pub fn is_history_path(path: &Path) -> bool {
+    let history: Vec<Component> = Path::new(HISTORY_DIR).components().collect();
+    let components: Vec<Component> = path.components().collect();
+    components.windows(history.len()).any(|w| w == history)
+}
+
+/// The size of the distinct contents of the index.
+fn history_size(index: &HistoryIndex) -> u64 {
+    let mut seen = HashSet::new();
+    index
+        .values()
+        .flatten()
+        .filter(|snapshot| seen.insert(snapshot.id.as_str()))
+        .map(|snapshot| snapshot.size)
+        .sum()
+}
+
+/// Drops the oldest snapshots of any file until the index fits in `max_size`, keeping
+/// the latest snapshot of each file. Returns the dropped snapshots.
+fn trim_to_size(index: &mut HistoryIndex, max_size: u64) -> Vec<Snapshot> {
+    let mut dropped = vec![];
+    while history_size(index) > max_size {
+        let oldest = index
+            .iter()
+            .filter(|(_, snapshots)| snapshots.len() > 1)
+            .min_by_key(|(_, snapshots)| snapshots[0].timestamp)
+            .map(|(path, _)| path.clone());
+        let Some(path) = oldest else {
+            break;
+        };
+        dropped.push(index.get_mut(&path).unwrap().remove(0));
+    }
+    dropped
+}
+
+/// Adds a snapshot unless the content matches the latest one, and drops the oldest
+/// snapshots over the limits. Returns the snapshot and the ids no longer referenced.
+fn record_snapshot(
+    index: &mut HistoryIndex,
+    path: &Path,
+    snapshot: Snapshot,
+    max_size: u64,
+) -> (Option<Snapshot>, Vec<String>) {
+    let snapshots = index.entry(path.to_path_buf()).or_default();
+    if snapshots.last().is_some_and(|last| last.id == snapshot.id) {
+        return (None, vec![]);
+    }
+    snapshots.push(snapshot.clone());
+    let excess = snapshots.len().saturating_sub(MAX_SNAPSHOTS);
+    let mut dropped: Vec<Snapshot> = snapshots.drain(..excess).collect();
+    dropped.extend(trim_to_size(index, max_size));
+
+    let referenced: HashSet<&str> = index
+        .values()
+        .flatten()
+        .map(|snapshot| snapshot.id.as_str())
+        .collect();
+    let mut unreferenced: Vec<String> = dropped
+        .into_iter()
+        .map(|snapshot| snapshot.id)
+        .filter(|id| !referenced.contains(id.as_str()))
+        .collect();
+    unreferenced.sort();
+    unreferenced.dedup();
+    (Some(snapshot), unreferenced)
+}
+
+/// Compressed copies of project files as they were saved, stored by content so
+/// identical versions share one copy. Works whether or not the project uses git.
+pub struct LocalHistory {
+    root: PathBuf,
+    dir: PathBuf,
+    /// Serializes updates of the index.
+    lock: Mutex<()>,
+}
+
+impl LocalHistory {
+    pub fn new(root: &Path) -> Self {
+        Self::with_dir(root, root.join(HISTORY_DIR))
+    }
+
+    /// A history of the files under `root` kept in `dir`, eg. outside a lone file's folder.
+    pub fn with_dir(root: &Path, dir: PathBuf) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            dir,
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn dir(&self) -> PathBuf {
+        self.dir.clone()
+    }
+
+    fn object_path(&self, id: &str) -> io::Result<PathBuf> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid snapshot id",
+            ));
+        }
+        Ok(self.dir().join("objects").join(format!("{}.gz", id)))
+    }
+
+    /// The index key of an absolute path, eg. `/chapters/intro.typ`.
+    fn key(&self, path: &Path) -> PathBuf {
+        Path::new("/").join(path.strip_prefix(&self.root).unwrap_or(path))
+    }
+
+    fn read_index(&self) -> io::Result<HistoryIndex> {
+        match fs::read(self.dir().join("index.json")) {
+            Ok(json) => serde_json::from_slice(&json)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(HistoryIndex::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn write_index(&self, index: &HistoryIndex) -> io::Result<()> {
+        let json =
+            serde_json::to_vec(index).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let tmp = self.dir().join(".index.json.tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, self.dir().join("index.json"))
+    }
+
+    /// Snapshots the saved `content` of the file at the absolute `path`. Returns `None`
+    /// if it matches the latest snapshot.
+    pub fn record(&self, path: &Path, content: &[u8]) -> io::Result<Option<Snapshot>> {
+        let mut hasher = SipHasher::new();
+        hasher.write(content);
+        let id = hex::encode(hasher.finish128().as_bytes());
+
+        let _guard = self.lock.lock().unwrap();
+        let object = self.object_path(&id)?;
+        if !object.exists() {
+            fs::create_dir_all(self.dir().join("objects"))?;
+            // Keeps the history out of git, whatever the project's own `.gitignore` says.
+            let gitignore = self.dir().join(".gitignore");
+            if !gitignore.exists() {
+                fs::write(&gitignore, "*\n")?;
+            }
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(content)?;
+            fs::write(&object, encoder.finish()?)?;
+        }
+
+        let mut index = self.read_index()?;
+        let snapshot = Snapshot {
+            id,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            size: content.len() as u64,
+        };
+        let (snapshot, unreferenced) =
+            record_snapshot(&mut index, &self.key(path), snapshot, MAX_HISTORY_SIZE);
+        if snapshot.is_some() {
+            self.write_index(&index)?;
+        }
+        for id in unreferenced {
+            let _ = fs::remove_file(self.object_path(&id)?);
+        }
+        Ok(snapshot)
+    }
+
+    /// The snapshots of the file at the absolute `path`, newest first.
+    pub fn snapshots(&self, path: &Path) -> io::Result<Vec<Snapshot>> {
+        let mut snapshots = self
+            .read_index()?
+            .remove(&self.key(path))
+            .unwrap_or_default();
+        snapshots.reverse();
+        Ok(snapshots)
+    }
+
+    /// The content of a snapshot of the file at the absolute `path`.
+    pub fn read(&self, path: &Path, id: &str) -> io::Result<Vec<u8>> {
+        if !self
+            .snapshots(path)?
+            .iter()
+            .any(|snapshot| snapshot.id == id)
+        {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "no such snapshot"));
+        }
+        let mut content = vec![];
+        GzDecoder::new(fs::File::open(self.object_path(id)?)?).read_to_end(&mut content)?;
+        Ok(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        is_history_path, record_snapshot, HistoryIndex, Snapshot, MAX_HISTORY_SIZE, MAX_SNAPSHOTS,
+    };
+    use std::path::Path;
+
+    fn snapshot(id: &str) -> Snapshot {
+        Snapshot {
+            id: id.into(),
+            timestamp: 0,
+            size: 0,
+        }
+    }
+
+    fn record(
+        index: &mut HistoryIndex,
+        path: &Path,
+        snapshot: Snapshot,
+    ) -> (Option<Snapshot>, Vec<String>) {
+        record_snapshot(index, path, snapshot, MAX_HISTORY_SIZE)
+    }
+
+    fn sized(id: &str, timestamp: i64, size: u64) -> Snapshot {
+        Snapshot {
+            id: id.into(),
+            timestamp,
+            size,
+        }
+    }
+
+    #[test]
+    fn test_record_snapshot_trims_to_size() {
+        let mut index = HistoryIndex::new();
+        let (main, data) = (Path::new("/main.typ"), Path::new("/data.csv"));
+        record_snapshot(&mut index, main, sized("a", 1, 10), 100);
+        record_snapshot(&mut index, data, sized("b", 2, 60), 100);
+        record_snapshot(&mut index, main, sized("c", 3, 10), 100);
+        let (_, unreferenced) = record_snapshot(&mut index, data, sized("d", 4, 60), 100);
+
+        // The oldest snapshot of any file goes first, but each file keeps its latest.
+        assert_eq!(unreferenced, ["a", "b"]);
+        assert_eq!(index[main], [sized("c", 3, 10)]);
+        assert_eq!(index[data], [sized("d", 4, 60)]);
+
+        let (_, unreferenced) = record_snapshot(&mut index, data, sized("e", 5, 200), 100);
+        assert_eq!(unreferenced, ["d"]);
+        assert_eq!(index[data], [sized("e", 5, 200)]);
+    }
+
+    #[test]
+    fn test_record_snapshot() {
+        let mut index = HistoryIndex::new();
+        let main = Path::new("/main.typ");
+        let (recorded, _) = record(&mut index, main, snapshot("a0"));
+        assert!(recorded.is_some());
+        assert!(record(&mut index, main, snapshot("a0")).0.is_none());
+
+        record(&mut index, Path::new("/copy.typ"), snapshot("a0"));
+        for i in 1..MAX_SNAPSHOTS {
+            record(&mut index, main, snapshot(&format!("b{}", i)));
+        }
+        // `a0` is dropped from main.typ but still referenced by copy.typ.
+        let (_, unreferenced) = record(&mut index, main, snapshot("c0"));
+        assert!(unreferenced.is_empty());
+        assert_eq!(index[main].len(), MAX_SNAPSHOTS);
+        let (_, unreferenced) = record(&mut index, main, snapshot("c1"));
+        assert_eq!(unreferenced, vec!["b1".to_string()]);
+
+        assert!(is_history_path(Path::new(
+            "/p/.typstudio/history/objects/a0.gz"
+        )));
+        assert!(!is_history_path(Path::new("/p/.typstudio/project.json")));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::project::{FileStamp, Project};
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use thiserror::Error;
+
+/// Number of applied batches kept for undo.
+const JOURNAL_CAPACITY: usize = 50;
+
+#[derive(Error, Debug)]
+pub enum WorkspaceEditError {
+    #[error("io error")]
+    IO(#[from] io::Error),
+    #[error("{0:?} was modified after the edit was applied")]
+    Modified(PathBuf),
+    #[error("there is no workspace edit to undo")]
+    Empty,
+}
+
+/// A full-content write of a single project file.
+#[derive(Clone, Debug)]
+pub struct FileWrite {
+    /// Project-relative path, as used by the world (eg. `/chapters/intro.typ`).
+    pub path: PathBuf,
+    pub absolute: PathBuf,
+    pub content: String,
+}
+
+struct JournalEntry {
+    path: PathBuf,
+    absolute: PathBuf,
+    /// Content before the edit, or `None` if the edit created the file.
+    before: Option<String>,
+    after: FileStamp,
+}
+
+struct JournalBatch {
+    label: String,
+    entries: Vec<JournalEntry>,
+}
+
+/// Records multi-file edits applied by the backend (refactors, replace-all, ...) so
+/// they can be reverted outside of the editor's own undo stack.
+#[derive(Default)]
+pub struct WorkspaceJournal {
+    batches: Mutex<VecDeque<JournalBatch>>,
+}
+
+impl WorkspaceJournal {
+    /// Writes all files, rolling back already written ones if any write fails, and
+    /// records the batch for [`WorkspaceJournal::undo`].
+    pub fn apply(
+        &self,
+        project: &Project,
+        label: impl Into<String>,
+        writes: Vec<FileWrite>,
+    ) -> Result<(), WorkspaceEditError> {
+        let mut entries: Vec<JournalEntry> = vec![];
+        for write in writes {
+            let before = match fs::read_to_string(&write.absolute) {
+                Ok(content) => Some(content),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                Err(e) => {
+                    rollback(project, &entries);
+                    return Err(e.into());
+                }
+            };
+            let after = match write_file(project, &write.path, &write.absolute, &write.content) {
+                Ok(after) => after,
+                Err(e) => {
+                    rollback(project, &entries);
+                    return Err(e.into());
+                }
+            };
+            entries.push(JournalEntry {
+                path: write.path,
+                after,
+                absolute: write.absolute,
+                before,
+            });
+        }
+
+        let mut batches = self.batches.lock().unwrap();
+        batches.push_back(JournalBatch {
+            label: label.into(),
+            entries,
+        });
+        while batches.len() > JOURNAL_CAPACITY {
+            batches.pop_front();
+        }
+        Ok(())
+    }
+
+    /// Reverts the most recent batch, provided none of its files changed since.
+    /// Returns the label and the restored paths.
+    pub fn undo(&self, project: &Project) -> Result<(String, Vec<PathBuf>), WorkspaceEditError> {
+        let mut batches = self.batches.lock().unwrap();
+        let batch = batches.back().ok_or(WorkspaceEditError::Empty)?;
+
+        for entry in &batch.entries {
+            let (current, _) = FileStamp::read(&entry.absolute)?;
+            if current.differs(&entry.after) {
+                return Err(WorkspaceEditError::Modified(entry.path.clone()));
+            }
+        }
+
+        let batch = batches.pop_back().unwrap();
+        rollback(project, &batch.entries);
+        Ok((
+            batch.label,
+            batch.entries.into_iter().map(|e| e.path).collect(),
+        ))
+    }
+
+    /// Label of the batch that would be reverted next.
+    pub fn peek(&self) -> Option<String> {
+        self.batches.lock().unwrap().back().map(|b| b.label.clone())
+    }
+}
+
+/// Writes the file and updates the world, returning the stamp of the written file. The
+/// project's stamps are left alone: they track what the editor loaded, so an editor that
+/// didn't reload the file yet still gets a conflict when saving over the edit.
+fn write_file(
+    project: &Project,
+    path: &Path,
+    absolute: &Path,
+    content: &str,
+) -> io::Result<FileStamp> {
+    if let Some(parent) = absolute.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(absolute, content)?;
+    let modified = fs::metadata(absolute).and_then(|m| m.modified()).ok();
+
+    let world = project.world.lock().unwrap_or_else(|e| e.into_inner());
+    if let Err(e) = world.slot_update(path, Some(content.to_string())) {
+        log::warn!("unable to update slot for {:?}: {:?}", path, e);
+    }
+    Ok(FileStamp::new(content.as_bytes(), modified))
+}
+
+fn rollback(project: &Project, entries: &[JournalEntry]) {
+    for entry in entries.iter().rev() {
+        let result = match &entry.before {
+            Some(content) => write_file(project, &entry.path, &entry.absolute, content).map(|_| ()),
+            None => fs::remove_file(&entry.absolute),
+        };
+        if let Err(e) = result {
+            log::error!("unable to restore {:?}: {:?}", entry.absolute, e);
+        }
+    }
+}
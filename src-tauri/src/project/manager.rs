@@ -1,23 +1,68 @@
-use crate::ipc::{FSRefreshEvent, ProjectChangeEvent, ProjectModel};
-use crate::project::{is_project_config_file, Project, ProjectConfig};
+use crate::git::project_status;
+use crate::ipc::{
+    FSChange, FSChangeKind, FSChangedEvent, FSRefreshEvent, GitStatusEvent, ProjectChangeEvent,
+    ProjectModel,
+};
+use crate::ipc::events::emit_to_window;
+use crate::menu::{update_menu_context, MenuContext};
+use crate::project::{
+    is_history_path, is_project_config_file, strip_roots, FigureGenerator, Project, ProjectConfig,
+};
 use log::{debug, error, info, trace, warn};
-use notify::event::ModifyKind;
+use notify::event::{ModifyKind, RenameMode};
 use notify::{Config, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex, RwLock};
-use tauri::{Runtime, WebviewWindow, Emitter};
+use std::time::{Duration, Instant};
+use tauri::{Runtime, WebviewWindow};
 use tokio::sync::mpsc::channel;
 
-#[derive(Clone, Copy, Debug)]
+/// Quiet period after which a burst of watcher events is flushed.
+const FS_DEBOUNCE: Duration = Duration::from_millis(150);
+/// Upper bound on how long a continuous burst (eg. `git checkout`) is held back.
+const FS_DEBOUNCE_MAX: Duration = Duration::from_secs(1);
+/// Quiet period before the git status is read, so a series of saves reads it once.
+const GIT_STATUS_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Keeps a project to one git status at a time. Refreshes asked for while one runs are
+/// folded into a single rerun once it's done.
+#[derive(Default)]
+pub struct GitStatusRefresh {
+    running: AtomicBool,
+    requested: AtomicBool,
+}
+
+impl GitStatusRefresh {
+    /// Asks for a refresh. Returns whether the caller has to start the run, as none is
+    /// running yet.
+    fn request(&self) -> bool {
+        self.requested.store(true, Ordering::SeqCst);
+        !self.running.swap(true, Ordering::SeqCst)
+    }
+
+    /// Ends a run. Returns whether to run again for a refresh asked for meanwhile.
+    fn finish(&self) -> bool {
+        self.running.store(false, Ordering::SeqCst);
+        self.requested.load(Ordering::SeqCst) && !self.running.swap(true, Ordering::SeqCst)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum FSHandleKind {
     Refresh,
     Reload,
 }
 
+/// The project of each window, by window label.
+type Projects<R> = HashMap<String, (WebviewWindow<R>, Arc<Project>)>;
+
 pub struct ProjectManager<R: Runtime> {
-    projects: RwLock<HashMap<String, (WebviewWindow<R>, Arc<Project>)>>,
+    projects: RwLock<Projects<R>>,
     watcher: Mutex<Option<Box<dyn Watcher + Send + Sync>>>,
+    /// The roots being watched, those of all windows' projects, and how.
+    watched: Mutex<HashMap<PathBuf, RecursiveMode>>,
 }
 
 impl<R: Runtime> ProjectManager<R> {
@@ -39,9 +84,27 @@ impl<R: Runtime> ProjectManager<R> {
 
         tokio::spawn(async move {
             while let Some(res) = rx.recv().await {
-                match res {
-                    Ok(event) => project_manager.handle_fs_event(event),
+                let mut batch = vec![];
+                let mut push = |res: notify::Result<notify::Event>| match res {
+                    Ok(event) => batch.push(event),
                     Err(e) => error!("watch error {:?}", e),
+                };
+                push(res);
+
+                let deadline = Instant::now() + FS_DEBOUNCE_MAX;
+                loop {
+                    let wait = FS_DEBOUNCE.min(deadline.saturating_duration_since(Instant::now()));
+                    match tokio::time::timeout(wait, rx.recv()).await {
+                        Ok(Some(res)) => push(res),
+                        _ => break,
+                    }
+                }
+
+                if project_manager.handle_fs_events(batch) {
+                    // Off this task, as watching waits for the watcher's event thread, which
+                    // may be blocked sending to this task.
+                    let project_manager = project_manager.clone();
+                    tokio::task::spawn_blocking(move || project_manager.refresh_watches());
                 }
             }
         });
@@ -54,45 +117,249 @@ impl<R: Runtime> ProjectManager<R> {
         *inner = Some(watcher);
     }
 
+    /// Forgets the project of a closed window. A tab moved to another window keeps its
+    /// label, so it keeps its project.
+    pub fn remove_window(&self, label: &str) {
+        let mut projects = self.projects.write().unwrap();
+        if let Some((_, old)) = projects.remove(label) {
+            // Other windows, eg. tabs, may show the same project and keep sharing it.
+            if !projects.values().any(|(_, project)| Arc::ptr_eq(project, &old)) {
+                old.stop_sharing();
+            }
+            self.update_watches(&projects);
+            if let Err(e) = old.statistics.flush() {
+                warn!("failed to save statistics of {:?}: {}", old.root, e);
+            }
+            info!("window {} closed, released project {:?}", label, old.root);
+        }
+    }
+
+    /// The windows showing a project, with their projects.
+    pub fn windows(&self) -> Vec<(WebviewWindow<R>, Arc<Project>)> {
+        self.projects.read().unwrap().values().cloned().collect()
+    }
+
     pub fn get_project(&self, window: &WebviewWindow<R>) -> Option<Arc<Project>> {
         self.projects.read().unwrap().get(window.label()).map(|(_, p)| p.clone())
     }
 
+    /// Watches the roots of all projects, including attached folders, and stops watching
+    /// the rest. Windows, including macOS tabs, can show the same project, and the watcher
+    /// watches each root once.
+    fn update_watches(&self, projects: &Projects<R>) {
+        let roots = Self::roots(projects);
+        let mut watcher = self.watcher.lock().unwrap();
+        let Some(watcher) = watcher.as_mut() else {
+            return;
+        };
+        let mut watched = self.watched.lock().unwrap();
+        for (root, mode) in watched.iter() {
+            if roots.get(root) != Some(mode) {
+                let _ = watcher.unwatch(root);
+            }
+        }
+        for (root, mode) in &roots {
+            if watched.get(root) != Some(mode) {
+                if let Err(e) = watcher.watch(root, *mode) {
+                    warn!("unable to watch {:?}: {}", root, e);
+                }
+            }
+        }
+        *watched = roots;
+    }
+
+    /// The roots to watch. A single-file project only watches the file's directory, not
+    /// its subdirectories; it is watched rather than the file, which editors may replace
+    /// when saving.
+    fn roots(projects: &Projects<R>) -> HashMap<PathBuf, RecursiveMode> {
+        let mut roots = HashMap::new();
+        for (_, project) in projects.values() {
+            if project.single_file.is_some() {
+                roots
+                    .entry(project.root.clone())
+                    .or_insert(RecursiveMode::NonRecursive);
+                continue;
+            }
+            for root in project.roots.read().unwrap().iter() {
+                roots.insert(root.clone(), RecursiveMode::Recursive);
+            }
+        }
+        roots
+    }
+
+    /// Updates the watcher after the roots of a project changed.
+    pub fn refresh_watches(&self) {
+        self.update_watches(&self.projects.read().unwrap());
+    }
+
     pub fn set_project(&self, window: &WebviewWindow<R>, project: Option<Arc<Project>>) {
         let mut projects = self.projects.write().unwrap();
-        let model = project.as_ref().map(|p| ProjectModel {
-            root: p.root.clone(),
-        });
+        let model = project.as_deref().map(ProjectModel::new);
+        let menu_context = MenuContext::for_project(project.as_deref());
+        // A replaced project leaves its collaboration session and stops serving its preview.
+        if let Some((_, previous)) = projects.get(window.label()) {
+            previous.stop_sharing();
+        }
         match project {
             None => {
-                if let Some((_, old)) = projects.remove(window.label()) {
-                    let mut guard = self.watcher.lock().unwrap();
-                    if let Some(watcher) = guard.as_mut() {
-                        let _ = watcher.unwatch(&old.root);
-                    }
-                }
+                projects.remove(window.label());
             }
             Some(p) => {
                 p.config.read().unwrap().apply(&*p);
+                projects.insert(window.label().to_string(), (window.clone(), p));
+            }
+        };
+        self.update_watches(&projects);
 
-                let root = &p.root.clone();
-                let mut guard = self.watcher.lock().unwrap();
-                if let Some((_, old)) = projects.insert(window.label().to_string(), (window.clone(), p)) {
-                    if let Some(watcher) = guard.as_mut() {
-                        let _ = watcher.unwatch(&old.root);
-                    }
+        update_menu_context(window, |context| *context = menu_context);
+
+        info!("project set for window {}: {:?}", window.label(), model);
+        emit_to_window(window, "project_changed", ProjectChangeEvent { project: model });
+    }
+
+    /// Handles a batch of watcher events. Returns whether the watched roots are outdated,
+    /// eg. as a reloaded project config attached a folder.
+    fn handle_fs_events(&self, events: Vec<notify::Event>) -> bool {
+        let mut handled = vec![];
+        let mut changes = vec![];
+        for event in events {
+            if event.paths.iter().all(|path| is_history_path(path)) {
+                continue;
+            }
+            changes.extend(Self::fs_changes(&event));
+            if let Some(opt) = Self::fs_handle_kind(&event) {
+                if !handled.contains(&opt) {
+                    handled.push(opt);
+                }
+            }
+        }
+
+        let projects = self.projects.read().unwrap();
+        for (path, kind) in handled {
+            for (window, project) in projects.values() {
+                if strip_roots(&project.roots.read().unwrap(), &path).is_some() {
+                    self.handle_project_fs_event(project, window, &path, kind);
                 }
-                if let Some(watcher) = guard.as_mut() {
-                    let _ = watcher.watch(root, RecursiveMode::Recursive);
+            }
+        }
+
+        let changes = coalesce_changes(changes);
+        for (window, project) in projects.values() {
+            let roots = project.roots.read().unwrap().clone();
+            let relative = |p: &Path| strip_roots(&roots, p).map(Path::to_path_buf);
+            let changes: Vec<FSChange> = changes
+                .iter()
+                // A single-file project only hears of its file, not of the files next to it.
+                .filter(|change| {
+                    let file = project.single_file.as_ref();
+                    file.map_or(true, |file| change.path == *file)
+                })
+                .filter_map(|change| {
+                    Some(FSChange {
+                        kind: change.kind,
+                        path: relative(&change.path)?,
+                        from: match &change.from {
+                            Some(from) => Some(relative(from)?),
+                            None => None,
+                        },
+                    })
+                })
+                .collect();
+            if changes.iter().any(|c| c.kind != FSChangeKind::Modified) {
+                *project.file_index.write().unwrap() = None;
+            }
+            if project.watch_generators.load(Ordering::Relaxed) {
+                Self::rerun_generators(project, window, &changes);
+            }
+            if !changes.is_empty() {
+                Self::refresh_git_status(project, window);
+                emit_to_window(window, "fs_changed", FSChangedEvent { changes });
+            }
+        }
+
+        Self::roots(&projects) != *self.watched.lock().unwrap()
+    }
+
+    /// Sends the project's git status in the background, as any change of its files, or of
+    /// the repository itself, may alter it.
+    fn refresh_git_status(project: &Arc<Project>, window: &WebviewWindow<R>) {
+        if !project.git_status_refresh.request() {
+            return;
+        }
+        let project = project.clone();
+        let window = window.clone();
+        tokio::task::spawn_blocking(move || loop {
+            std::thread::sleep(GIT_STATUS_DEBOUNCE);
+            project.git_status_refresh.requested.store(false, Ordering::SeqCst);
+            match project_status(&project.root) {
+                Ok(files) => emit_to_window(&window, "git_status", GitStatusEvent { files }),
+                Err(e) => debug!("unable to read the git status of {:?}: {}", project.root, e),
+            }
+            if !project.git_status_refresh.finish() {
+                break;
+            }
+        });
+    }
+
+    /// Reruns the approved generators whose script or inputs are among `changes`, in the
+    /// background.
+    fn rerun_generators(project: &Arc<Project>, window: &WebviewWindow<R>, changes: &[FSChange]) {
+        let generators: Vec<FigureGenerator> = project
+            .config
+            .read()
+            .unwrap()
+            .generators
+            .iter()
+            .filter(|g| changes.iter().any(|c| g.depends_on(&c.path)))
+            .cloned()
+            .collect();
+        if generators.is_empty() {
+            return;
+        }
+
+        let root = project.root.clone();
+        let window = window.clone();
+        tokio::task::spawn_blocking(move || {
+            for generator in generators.into_iter().filter(|g| g.is_approved(&root)) {
+                match generator.run(&root) {
+                    Ok(run) => {
+                        emit_to_window(&window, "generator_finished", run);
+                    }
+                    Err(e) => warn!("unable to run generator for {:?}: {}", generator.output, e),
                 }
             }
-        };
+        });
+    }
 
-        info!("project set for window {}: {:?}", window.label(), model);
-        let _ = window.emit("project_changed", ProjectChangeEvent { project: model });
+    fn fs_changes(event: &notify::Event) -> Vec<FSChange> {
+        let change = |kind, path: &PathBuf| FSChange {
+            kind,
+            path: normalize_path(path),
+            from: None,
+        };
+        let Some(path) = event.paths.first() else {
+            return vec![];
+        };
+        match event.kind {
+            EventKind::Create(_) => vec![change(FSChangeKind::Created, path)],
+            EventKind::Remove(_) => vec![change(FSChangeKind::Deleted, path)],
+            EventKind::Modify(ModifyKind::Name(mode)) => match mode {
+                RenameMode::Both if event.paths.len() >= 2 => vec![FSChange {
+                    kind: FSChangeKind::Renamed,
+                    path: normalize_path(&event.paths[1]),
+                    from: Some(normalize_path(path)),
+                }],
+                RenameMode::From => vec![change(FSChangeKind::Deleted, path)],
+                RenameMode::To => vec![change(FSChangeKind::Created, path)],
+                _ if path.exists() => vec![change(FSChangeKind::Created, path)],
+                _ => vec![change(FSChangeKind::Deleted, path)],
+            },
+            EventKind::Modify(ModifyKind::Data(_)) => vec![change(FSChangeKind::Modified, path)],
+            _ => vec![],
+        }
     }
 
-    fn handle_fs_event(&self, event: notify::Event) {
+    fn fs_handle_kind(event: &notify::Event) -> Option<(PathBuf, FSHandleKind)> {
         let opt = match event.kind {
             EventKind::Create(_) | EventKind::Remove(_) => event.paths[0]
                 .parent()
@@ -107,16 +374,7 @@ impl<R: Runtime> ProjectManager<R> {
             _ => None,
         };
 
-        if let Some((path, kind)) = opt {
-            let path = path.canonicalize().unwrap_or(path);
-            let projects = self.projects.read().unwrap();
-
-            for (window, project) in projects.values() {
-                if path.starts_with(&project.root) {
-                    self.handle_project_fs_event(project, window, &path, kind);
-                }
-            }
-        }
+        opt.map(|(path, kind)| (path.canonicalize().unwrap_or(path), kind))
     }
 
     fn handle_project_fs_event(
@@ -135,17 +393,19 @@ impl<R: Runtime> ProjectManager<R> {
         match kind {
             // Refreshes the explorer view
             FSHandleKind::Refresh => {
-                if let Ok(relative) = path.strip_prefix(&project.root) {
+                if let Some(relative) = strip_roots(&project.roots.read().unwrap(), path) {
                     let event = FSRefreshEvent {
                         path: relative.to_path_buf(),
                     };
-                    let _ = window.emit("fs_refresh", &event);
+                    emit_to_window(window, "fs_refresh", &event);
                 }
             }
             // Reloads the file content, eg. project config or project source files
             FSHandleKind::Reload => {
-                if let Ok(relative) = path.strip_prefix(&project.root) {
-                    if is_project_config_file(relative) {
+                let roots = project.roots.read().unwrap().clone();
+                if let Some(relative) = strip_roots(&roots, path) {
+                    // Attached folders may have a config of their own, which doesn't apply.
+                    if is_project_config_file(relative) && path.starts_with(&project.root) {
                         if let Ok(config) = ProjectConfig::read_from_file(path) {
                             debug!("updating project config for {:?}: {:?}", project, config);
                             let mut config_write = project.config.write().unwrap();
@@ -176,6 +436,48 @@ impl<R: Runtime> ProjectManager<R> {
         Self {
             projects: RwLock::new(HashMap::new()),
             watcher: Mutex::new(None),
+            watched: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+/// Canonicalizes a path that may no longer exist by resolving its parent instead.
+fn normalize_path(path: &Path) -> PathBuf {
+    if let Ok(path) = path.canonicalize() {
+        return path;
+    }
+    match (path.parent().and_then(|p| p.canonicalize().ok()), path.file_name()) {
+        (Some(parent), Some(name)) => parent.join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Merges repeated changes to the same path within a debounced burst, eg. an editor
+/// writing a temporary file and deleting it again collapses to nothing.
+fn coalesce_changes(changes: Vec<FSChange>) -> Vec<FSChange> {
+    let mut out: Vec<FSChange> = vec![];
+    for change in changes {
+        let previous = if change.kind == FSChangeKind::Renamed {
+            None
+        } else {
+            out.iter().position(|c| c.path == change.path && c.kind != FSChangeKind::Renamed)
+        };
+        let Some(index) = previous else {
+            out.push(change);
+            continue;
+        };
+        let merged = match (out[index].kind, change.kind) {
+            (FSChangeKind::Created, FSChangeKind::Deleted) => None,
+            (FSChangeKind::Created, _) => Some(FSChangeKind::Created),
+            (FSChangeKind::Deleted, FSChangeKind::Created) => Some(FSChangeKind::Modified),
+            (_, kind) => Some(kind),
+        };
+        match merged {
+            Some(kind) => out[index].kind = kind,
+            None => {
+                out.remove(index);
+            }
         }
     }
+    out
 }
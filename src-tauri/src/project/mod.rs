@@ -1,7 +1,31 @@
 mod project;
 mod world;
 mod manager;
+mod stamps;
+mod dependencies;
+mod journal;
+mod generators;
+mod statistics;
+mod session;
+mod autosave;
+mod exports;
+mod history;
+mod recovery;
+mod roots;
+mod comments;
 
 pub use project::*;
 pub use world::*;
 pub use manager::*;
+pub use stamps::*;
+pub use dependencies::*;
+pub use journal::*;
+pub use generators::*;
+pub use statistics::*;
+pub use session::*;
+pub use autosave::*;
+pub use exports::*;
+pub use history::*;
+pub use recovery::*;
+pub use roots::*;
+pub use comments::*;
@@ -1,36 +1,143 @@
-use crate::compiler::IncrementalRenderer;
-use crate::project::ProjectWorld;
-use log::debug;
+use crate::actions::DocumentAction;
+use crate::collab::CollabSession;
+use crate::preview_server::PreviewServer;
+use crate::appdata::project_app_dir;
+use crate::compiler::{
+    IncrementalRenderer, PreviewDecorations, PreviewInputs, ProjectDiagnosticsEvent, RenderQueue,
+};
+use crate::document::{Bookmark, PageBudget};
+use crate::export::{AnonymizeConfig, AutoExportConfig, AutoExporter, EpubConfig, ExportHook};
+use crate::git::commit_files;
+use crate::snippets::Snippet;
+use crate::project::{
+    attached_roots, project_roots, resolve_project_path, DirtyBuffers, FigureGenerator, FileStamps,
+    GitStatusRefresh, LocalHistory, ProjectStatistics, ProjectWorld, RecoveryJournal,
+    ReviewComments, TargetDependencies, WorkspaceJournal,
+};
+use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fmt::{Debug, Formatter};
 use std::path::{Path, PathBuf};
-use std::sync::atomic::AtomicU64;
-use std::sync::{Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::sync::{Arc, Mutex, RwLock};
 use std::{fs, io};
 use thiserror::Error;
+use tokio::sync::watch;
 use typst::diag::{FileError, FileResult};
 use typst::layout::PagedDocument;
 use typst::syntax::VirtualPath;
 
+const PROJECT_DATA_DIR: &str = ".typstudio";
 const PATH_PROJECT_CONFIG_FILE: &str = ".typstudio/project.json";
 
 pub struct Project {
     pub root: PathBuf,
+    /// `root`, then the folders attached to the project.
+    pub roots: RwLock<Vec<PathBuf>>,
     pub world: Mutex<ProjectWorld>,
     pub cache: RwLock<ProjectCache>,
     pub config: RwLock<ProjectConfig>,
     pub current_compile_request_id: AtomicU64,
     pub renderer: Mutex<IncrementalRenderer>,
+    pub render_queue: RenderQueue,
+    pub stamps: FileStamps,
+    pub dependencies: TargetDependencies,
+    pub journal: WorkspaceJournal,
+    /// Project-relative paths of all files, cached until the watcher sees files added or removed.
+    pub file_index: RwLock<Option<Arc<Vec<String>>>>,
+    /// Id of the latest project search; older searches stop once superseded.
+    pub current_search_id: AtomicU64,
+    /// `sys.inputs` for preview compiles only, such as the app's theme.
+    pub preview_inputs: RwLock<PreviewInputs>,
+    /// Guides laid over the preview pages, such as grid paper.
+    pub preview_decorations: RwLock<PreviewDecorations>,
+    /// Paths moved to the trash by `fs_delete_file`, most recent last.
+    pub trashed: Mutex<Vec<PathBuf>>,
+    /// Whether figure generators rerun when their script or inputs change.
+    pub watch_generators: AtomicBool,
+    /// Whether preview compiles are timed and reported in `compile_profile` events.
+    pub profile_compiles: AtomicBool,
+    /// The thread of the last compile that timed out, left running as Typst can't be
+    /// interrupted. Compiles wait for it to exit, so runaway compiles don't pile up.
+    pub abandoned_compile: Mutex<Option<std::thread::JoinHandle<()>>>,
+    pub git_status_refresh: GitStatusRefresh,
+    pub statistics: ProjectStatistics,
+    pub dirty_buffers: DirtyBuffers,
+    /// Snapshots of files as they are saved.
+    pub history: LocalHistory,
+    pub comments: ReviewComments,
+    /// The collaboration session the project is shared in.
+    pub collab: Mutex<Option<Arc<CollabSession>>>,
+    /// The version of every document stored in `cache`, for viewers outside the window.
+    pub document_versions: watch::Sender<u64>,
+    pub preview_server: Mutex<Option<PreviewServer>>,
+    pub auto_exporter: AutoExporter,
+    /// The file of an implicit project for a lone file, the only one fs commands reach.
+    pub single_file: Option<PathBuf>,
 }
 
 #[derive(Default)]
 pub struct ProjectCache {
     pub document: Option<PagedDocument>,
+    /// The world revision `document` was compiled from.
+    pub version: u64,
+    /// The `project_diagnostics` event of the latest preview compile.
+    pub problems: Option<ProjectDiagnosticsEvent>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Hash)]
 pub struct ProjectConfig {
     pub main: Option<PathBuf>,
+    /// Allows commands such as `assets_mirror_url` to download remote files into the project.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_network: bool,
+    /// Gitignore-style patterns hidden from the file tree and project-wide file listings.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+    /// Build matrix axes injected into `sys.inputs`, eg. `{"lang": ["en", "de"]}`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variants: BTreeMap<String, Vec<String>>,
+    /// Scripts that produce figures, eg. plots from data files.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub generators: Vec<FigureGenerator>,
+    /// Settings for `export_anonymous`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub anonymize: AnonymizeConfig,
+    /// Page limits checked after every compile.
+    #[serde(default, skip_serializing_if = "PageBudget::is_empty")]
+    pub page_budget: PageBudget,
+    /// Boolean `sys.inputs` the preview and export profiles can flip, with their
+    /// defaults, eg. `{"show_solutions": false}`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub toggles: BTreeMap<String, bool>,
+    /// Preview bookmarks, anchored to labels and headings.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bookmarks: Vec<Bookmark>,
+    /// Book metadata for `export_epub`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub epub: EpubConfig,
+    /// Document actions of the project, shown with the built-in ones.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<DocumentAction>,
+    /// Snippets of the project, offered with the user's own.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub snippets: Vec<Snippet>,
+    /// Commits every file the user saves, for a version history without using git.
+    /// Autosaves aren't committed, so the history has no commit per second of typing.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub auto_commit: bool,
+    /// Exports written after every save, eg. to keep a PDF viewer up to date.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub auto_export: AutoExportConfig,
+    /// Shell commands run after every successful export, eg. to upload the PDF, once the
+    /// user approved them. Auto-exports don't run them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub export_hooks: Vec<ExportHook>,
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
 }
 
 #[derive(Error, Debug)]
@@ -53,7 +160,14 @@ impl ProjectConfig {
     }
 
     pub fn apply(&self, project: &Project) {
+        let attached = attached_roots(&project.root).unwrap_or_else(|e| {
+            warn!("unable to read the attached folders of {:?}: {}", project.root, e);
+            vec![]
+        });
+        let roots = project_roots(&project.root, &attached);
+        *project.roots.write().unwrap() = roots.clone();
         let mut world = project.world.lock().unwrap();
+        world.set_roots(roots);
         match self.apply_main(project, &mut world) {
             Ok(_) => debug!(
                 "applied main source configuration for project {:?}",
@@ -85,23 +199,145 @@ impl Default for ProjectConfig {
     fn default() -> Self {
         Self {
             main: Some(PathBuf::from("/main.typ")),
+            allow_network: false,
+            ignore: vec![],
+            variants: BTreeMap::new(),
+            generators: vec![],
+            anonymize: AnonymizeConfig::default(),
+            page_budget: PageBudget::default(),
+            toggles: BTreeMap::new(),
+            bookmarks: vec![],
+            epub: EpubConfig::default(),
+            actions: vec![],
+            snippets: vec![],
+            auto_commit: false,
+            auto_export: AutoExportConfig::default(),
+            export_hooks: vec![],
         }
     }
 }
 
 impl Project {
+    /// Opens the project at `path`, or an implicit project if it is a file.
+    pub fn open(path: PathBuf, progress: Option<Box<dyn Fn(String, u32) + Send>>) -> Self {
+        if path.is_file() {
+            Self::load_single_file(path, progress)
+        } else {
+            Self::load_from_path(path, progress)
+        }
+    }
+
     pub fn load_from_path(path: PathBuf, progress: Option<Box<dyn Fn(String, u32) + Send>>) -> Self {
         let path = fs::canonicalize(&path).unwrap_or(path);
         let config =
             ProjectConfig::read_from_file(path.join(PATH_PROJECT_CONFIG_FILE)).unwrap_or_default();
+        let history = LocalHistory::new(&path);
+        Self::load(path, config, history, progress)
+    }
+
+    /// An implicit project rooted at the file's directory, with the file as main. Nothing
+    /// is written to the directory: the config stays in memory and the history is kept in
+    /// the app config directory. Only the file is watched and reachable by commands;
+    /// the compiler still resolves imports and images next to it, like `typst compile`.
+    pub fn load_single_file(file: PathBuf, progress: Option<Box<dyn Fn(String, u32) + Send>>) -> Self {
+        let file = fs::canonicalize(&file).unwrap_or(file);
+        let root = file.parent().map_or_else(|| PathBuf::from("/"), Path::to_path_buf);
+        let config = ProjectConfig {
+            main: file.file_name().map(|name| Path::new("/").join(name)),
+            ..ProjectConfig::default()
+        };
+        let history = match project_app_dir("history", &file) {
+            Some(dir) => LocalHistory::with_dir(&root, dir),
+            None => LocalHistory::new(&root),
+        };
+        let mut project = Self::load(root, config, history, progress);
+        if let Some(dir) = project_app_dir("comments", &file) {
+            project.comments = ReviewComments::with_file(dir.join("comments.json"));
+        }
+        project.single_file = Some(file);
+        project
+    }
 
+    fn load(
+        path: PathBuf,
+        config: ProjectConfig,
+        history: LocalHistory,
+        progress: Option<Box<dyn Fn(String, u32) + Send>>,
+    ) -> Self {
         Self {
             world: ProjectWorld::new(path.clone(), progress).into(),
             cache: RwLock::new(Default::default()),
             config: RwLock::new(config),
-            root: path,
+            root: path.clone(),
+            roots: RwLock::new(vec![path.clone()]),
             current_compile_request_id: AtomicU64::new(0),
             renderer: Mutex::new(IncrementalRenderer::new()),
+            render_queue: RenderQueue::default(),
+            stamps: FileStamps::default(),
+            dependencies: TargetDependencies::default(),
+            journal: WorkspaceJournal::default(),
+            file_index: RwLock::new(None),
+            current_search_id: AtomicU64::new(0),
+            preview_inputs: RwLock::new(PreviewInputs::default()),
+            preview_decorations: RwLock::new(PreviewDecorations::default()),
+            trashed: Mutex::new(Vec::new()),
+            watch_generators: AtomicBool::new(false),
+            profile_compiles: AtomicBool::new(false),
+            abandoned_compile: Mutex::new(None),
+            git_status_refresh: GitStatusRefresh::default(),
+            statistics: ProjectStatistics::load(&path),
+            dirty_buffers: DirtyBuffers::new(RecoveryJournal::new(&path)),
+            comments: ReviewComments::new(&path),
+            collab: Mutex::new(None),
+            document_versions: watch::channel(0).0,
+            preview_server: Mutex::new(None),
+            auto_exporter: AutoExporter::default(),
+            history,
+            single_file: None,
+        }
+    }
+}
+
+impl Project {
+    /// The absolute path of a project path such as `/styles/thesis.typ`, in the first root
+    /// that has it. Returns `None` if the path leaves the project, or is not the file of a
+    /// single-file project.
+    pub fn resolve(&self, path: &Path) -> Option<PathBuf> {
+        resolve_project_path(&self.roots.read().unwrap(), path)
+            .filter(|out| self.single_file.as_ref().map_or(true, |file| file == out))
+    }
+
+    /// Writes the current config to `.typstudio/project.json`. The config of a single-file
+    /// project only lives in memory.
+    pub fn save_config(&self) -> Result<(), ProjectConfigError> {
+        if self.single_file.is_some() {
+            return Ok(());
+        }
+        let path = self.root.join(PATH_PROJECT_CONFIG_FILE);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        self.config.read().unwrap().write_to_file(path)
+    }
+
+    /// Leaves the project's collaboration session and stops serving its preview, which
+    /// otherwise keep their ports and the project alive.
+    pub fn stop_sharing(&self) {
+        if let Some(session) = self.collab.lock().unwrap().take() {
+            session.stop();
+        }
+        self.preview_server.lock().unwrap().take();
+    }
+
+    /// Commits the saved `paths` if the project enabled `auto_commit`.
+    pub fn auto_commit(&self, paths: &[PathBuf]) {
+        if !self.config.read().unwrap().auto_commit || self.single_file.is_some() {
+            return;
+        }
+        match commit_files(&self.root, paths) {
+            Ok(Some(oid)) => info!("committed {:?} as {}", paths, oid),
+            Ok(None) => {}
+            Err(e) => warn!("failed to commit {:?}: {}", paths, e),
         }
     }
 }
@@ -115,3 +351,29 @@ impl Debug for Project {
 pub fn is_project_config_file(relative: &Path) -> bool {
     relative.as_os_str() == PATH_PROJECT_CONFIG_FILE
 }
+
+/// Whether a normalized project path is in `.typstudio`, the app's own files of the
+/// project, which are never written on behalf of others.
+pub fn is_project_internal_path(relative: &Path) -> bool {
+    relative.starts_with(PROJECT_DATA_DIR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_skips_defaults() {
+        let json = serde_json::to_string(&ProjectConfig::default()).unwrap();
+        assert_eq!(json, r#"{"main":"/main.typ"}"#);
+
+        let config = ProjectConfig {
+            allow_network: true,
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(json, r#"{"main":"/main.typ","allow_network":true}"#);
+        let read: ProjectConfig = serde_json::from_str(&json).unwrap();
+        assert!(read.allow_network && read.ignore.is_empty());
+    }
+}
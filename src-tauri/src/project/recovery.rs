@@ -0,0 +1,184 @@
+use crate::appdata::project_app_dir;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use similar::TextDiff;
+use siphasher::sip128::{Hasher128, SipHasher};
+use std::fs;
+use std::hash::Hasher;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// An unsaved editor buffer as written to the recovery directory.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    /// Absolute path of the file the buffer belongs to.
+    pub path: PathBuf,
+    pub content: String,
+    /// Unix time in milliseconds.
+    pub journaled_at: i64,
+}
+
+/// A journaled buffer that differs from the file on disk.
+#[derive(Serialize, Debug, Clone)]
+pub struct RecoverableBuffer {
+    /// Project-relative path, eg. `/chapters/intro.typ`.
+    pub path: PathBuf,
+    pub content: String,
+    pub journaled_at: i64,
+    /// Unified diff from the file on disk to the buffer. A missing file diffs as empty.
+    pub diff: String,
+    pub missing: bool,
+}
+
+/// Keeps a copy of every unsaved buffer on disk until it is saved or discarded, so
+/// the changes survive a crash. Entries are written on the autosave tick, losing at most
+/// a second of edits, and removed only once the file itself was written.
+pub struct RecoveryJournal {
+    dir: Option<PathBuf>,
+}
+
+impl RecoveryJournal {
+    /// The journal of the project at `root`, kept in the app config directory.
+    pub fn new(root: &Path) -> Self {
+        Self::with_dir(project_app_dir("recovery", root))
+    }
+
+    pub fn with_dir(dir: Option<PathBuf>) -> Self {
+        Self { dir }
+    }
+
+    fn entry_path(&self, path: &Path) -> Option<PathBuf> {
+        let mut hasher = SipHasher::new();
+        hasher.write(path.to_string_lossy().as_bytes());
+        let name = hex::encode(&hasher.finish128().as_bytes()[..8]);
+        Some(self.dir.as_ref()?.join(format!("{}.json", name)))
+    }
+
+    pub fn write(&self, path: &Path, content: &str) -> io::Result<()> {
+        let (Some(dir), Some(entry_path)) = (&self.dir, self.entry_path(path)) else {
+            return Ok(());
+        };
+        fs::create_dir_all(dir)?;
+        let entry = JournalEntry {
+            path: path.to_path_buf(),
+            content: content.to_string(),
+            journaled_at: chrono::Utc::now().timestamp_millis(),
+        };
+        let json = serde_json::to_vec(&entry)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        // Synced before the rename so a crash leaves either the old entry or the new one.
+        let tmp = entry_path.with_extension("tmp");
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(&json)?;
+        file.sync_all()?;
+        fs::rename(&tmp, entry_path)
+    }
+
+    pub fn discard(&self, path: &Path) {
+        let Some(entry_path) = self.entry_path(path) else {
+            return;
+        };
+        if let Err(e) = fs::remove_file(&entry_path) {
+            if e.kind() != io::ErrorKind::NotFound {
+                warn!("failed to discard recovery entry for {:?}: {}", path, e);
+            }
+        }
+    }
+
+    /// All journaled buffers. Unreadable entries, eg. from an interrupted write, are skipped.
+    pub fn entries(&self) -> io::Result<Vec<JournalEntry>> {
+        let Some(dir) = &self.dir else {
+            return Ok(vec![]);
+        };
+        let read_dir = match fs::read_dir(dir) {
+            Ok(read_dir) => read_dir,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+        let mut entries = vec![];
+        for file in read_dir {
+            let path = file?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                match fs::read(&path).map(|json| serde_json::from_slice::<JournalEntry>(&json)) {
+                    Ok(Ok(entry)) => entries.push(entry),
+                    _ => warn!("skipping unreadable recovery entry {:?}", path),
+                }
+            }
+        }
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(entries)
+    }
+
+    /// The journaled buffers of the project at `root` that differ from the disk. Entries
+    /// that match the disk are stale and removed.
+    pub fn recoverable(&self, root: &Path) -> io::Result<Vec<RecoverableBuffer>> {
+        let mut buffers = vec![];
+        for entry in self.entries()? {
+            let disk = match fs::read_to_string(&entry.path) {
+                Ok(disk) => Some(disk),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                Err(e) => return Err(e),
+            };
+            if disk.as_deref() == Some(entry.content.as_str()) {
+                self.discard(&entry.path);
+                continue;
+            }
+            let path = Path::new("/").join(entry.path.strip_prefix(root).unwrap_or(&entry.path));
+            buffers.push(RecoverableBuffer {
+                diff: unified_diff(
+                    &format!("{} (on disk)", path.display()),
+                    &format!("{} (unsaved)", path.display()),
+                    disk.as_deref().unwrap_or(""),
+                    &entry.content,
+                ),
+                missing: disk.is_none(),
+                path,
+                content: entry.content,
+                journaled_at: entry.journaled_at,
+            });
+        }
+        Ok(buffers)
+    }
+}
+
+/// A unified diff of two versions of a file, with `old_label` and `new_label` as headers.
+pub fn unified_diff(old_label: &str, new_label: &str, old: &str, new: &str) -> String {
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(old_label, new_label)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RecoveryJournal;
+    use std::fs;
+
+    #[test]
+    fn test_recoverable() {
+        let dir = std::env::temp_dir().join(format!("typstudio-recovery-{}", std::process::id()));
+        let root = dir.join("project");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("main.typ"), "= Intro\nHello\n").unwrap();
+        fs::write(root.join("saved.typ"), "done\n").unwrap();
+
+        let journal = RecoveryJournal::with_dir(Some(dir.join("journal")));
+        journal
+            .write(&root.join("main.typ"), "= Intro\nHello, world\n")
+            .unwrap();
+        journal.write(&root.join("saved.typ"), "done\n").unwrap();
+        journal.write(&root.join("new.typ"), "new\n").unwrap();
+
+        let buffers = journal.recoverable(&root).unwrap();
+        assert_eq!(buffers.len(), 2);
+        assert_eq!(buffers[0].path.to_str(), Some("/main.typ"));
+        assert!(buffers[0].diff.contains("-Hello\n+Hello, world\n"));
+        assert!(buffers[1].missing);
+        assert_eq!(journal.entries().unwrap().len(), 2);
+
+        journal.discard(&root.join("main.typ"));
+        assert_eq!(journal.entries().unwrap().len(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,251 @@
+use crate::appdata::{project_app_file, read_app_json, write_app_json};
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// The folders attached to the project at `root`, eg. a shared styles repository. Kept
+/// in the app config directory rather than `project.json`, so a downloaded project
+/// can't make its files reach into other folders of the user.
+pub fn attached_roots(root: &Path) -> io::Result<Vec<PathBuf>> {
+    Ok(read_app_json(&project_app_file("roots", root))?.unwrap_or_default())
+}
+
+pub fn save_attached_roots(root: &Path, attached: &[PathBuf]) -> io::Result<()> {
+    write_app_json(&project_app_file("roots", root), &attached)
+}
+
+/// The roots of a project: its own folder, then the attached folders in the order of
+/// `attached`. Missing ones and duplicates are left out.
+pub fn project_roots(root: &Path, attached: &[PathBuf]) -> Vec<PathBuf> {
+    let mut roots = vec![root.to_path_buf()];
+    for folder in attached {
+        let Ok(folder) = root.join(folder).canonicalize() else {
+            continue;
+        };
+        if folder.is_dir() && !roots.contains(&folder) {
+            roots.push(folder);
+        }
+    }
+    roots
+}
+
+/// Normalizes a project path such as `/chapters/../intro.typ` to a relative one.
+/// Returns `None` if it leaves the project or names no file in it.
+pub fn normalize_project_path(path: &Path) -> Option<PathBuf> {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    return None;
+                }
+            }
+            Component::Normal(name) if is_plain_name(name) => out.push(name),
+            Component::Normal(_) => return None,
+        }
+    }
+    Some(out)
+}
+
+/// Whether `name` names a file in its folder. On Windows, device names such as `NUL` and
+/// alternate data streams such as `main.typ:stream` don't.
+fn is_plain_name(name: &OsStr) -> bool {
+    if cfg!(windows) {
+        let name = name.to_string_lossy();
+        !name.contains(':') && !is_device_name(&name)
+    } else {
+        true
+    }
+}
+
+/// Whether `name` is reserved for a device on Windows, with any extension.
+fn is_device_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or(name).trim_end_matches(' ');
+    let stem = stem.to_ascii_uppercase();
+    match stem.as_bytes() {
+        b"CON" | b"PRN" | b"AUX" | b"NUL" => true,
+        [b'C', b'O', b'M', digit] | [b'L', b'P', b'T', digit] => (b'1'..=b'9').contains(digit),
+        _ => false,
+    }
+}
+
+/// Resolves a normalized relative path against the first root that has it. Paths found
+/// in no root, eg. of files yet to be created, belong to the first root.
+pub fn resolve_in_roots(roots: &[PathBuf], relative: &Path) -> PathBuf {
+    roots
+        .iter()
+        .map(|root| root.join(relative))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| roots[0].join(relative))
+}
+
+/// Resolves a project path like [`resolve_in_roots`], if it stays inside the roots both
+/// lexically and once symlinks are followed. A link out of the project is refused; the
+/// folder it points to can be attached instead.
+pub fn resolve_project_path(roots: &[PathBuf], path: &Path) -> Option<PathBuf> {
+    let relative = normalize_project_path(path)?;
+    let resolved = resolve_in_roots(roots, &relative);
+    within_roots(roots, &resolved).then_some(resolved)
+}
+
+/// Whether `path` lies inside one of `roots` once symlinks are followed. For a path yet
+/// to be created, its closest existing ancestor decides; a dangling link never does.
+pub fn within_roots(roots: &[PathBuf], path: &Path) -> bool {
+    let Some(existing) = path.ancestors().find(|p| p.symlink_metadata().is_ok()) else {
+        return false;
+    };
+    let Ok(real) = existing.canonicalize() else {
+        return false;
+    };
+    roots
+        .iter()
+        .any(|root| real.starts_with(fs::canonicalize(root).unwrap_or_else(|_| root.clone())))
+}
+
+/// The path of an absolute `path` relative to the first root containing it.
+pub fn strip_roots<'a>(roots: &[PathBuf], path: &'a Path) -> Option<&'a Path> {
+    roots.iter().find_map(|root| path.strip_prefix(root).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        is_device_name, normalize_project_path, project_roots, resolve_in_roots,
+        resolve_project_path, strip_roots,
+    };
+    use proptest::prelude::*;
+    use std::fs;
+    use std::path::{Component, Path, PathBuf};
+    use std::sync::OnceLock;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_resolve_in_roots() {
+        let dir = TempDir::new().unwrap();
+        let thesis = dir.path().join("thesis");
+        let styles = dir.path().join("styles");
+        fs::create_dir_all(&thesis).unwrap();
+        fs::create_dir_all(&styles).unwrap();
+        fs::write(thesis.join("main.typ"), "").unwrap();
+        fs::write(styles.join("main.typ"), "").unwrap();
+        fs::write(styles.join("style.typ"), "").unwrap();
+
+        let thesis = thesis.canonicalize().unwrap();
+        let attached = [styles.clone(), dir.path().join("missing")];
+        let roots = project_roots(&thesis, &attached);
+        assert_eq!(roots.len(), 2);
+        let styles = &roots[1];
+
+        assert_eq!(
+            resolve_in_roots(&roots, Path::new("main.typ")),
+            thesis.join("main.typ")
+        );
+        assert_eq!(
+            resolve_in_roots(&roots, Path::new("style.typ")),
+            styles.join("style.typ")
+        );
+        assert_eq!(
+            resolve_in_roots(&roots, Path::new("new.typ")),
+            thesis.join("new.typ")
+        );
+        assert_eq!(
+            strip_roots(&roots, &styles.join("style.typ")),
+            Some(Path::new("style.typ"))
+        );
+
+        assert_eq!(
+            normalize_project_path(Path::new("/chapters/../intro.typ")),
+            Some(PathBuf::from("intro.typ"))
+        );
+        assert_eq!(normalize_project_path(Path::new("/../secret")), None);
+    }
+
+    #[test]
+    fn test_is_device_name() {
+        assert!(is_device_name("NUL"));
+        assert!(is_device_name("con.typ"));
+        assert!(is_device_name("Com1 .txt"));
+        assert!(!is_device_name("COM0"));
+        assert!(!is_device_name("console.typ"));
+        assert!(!is_device_name("main.typ"));
+    }
+
+    /// A project with a folder and a file, next to a secret outside of it. On Unix, `link`
+    /// points to the outside folder and `dangling` to a missing file in it.
+    fn sandbox() -> &'static Path {
+        static ROOT: OnceLock<(TempDir, PathBuf)> = OnceLock::new();
+        let (_, root) = ROOT.get_or_init(|| {
+            let dir = TempDir::new().unwrap();
+            let root = dir.path().join("project");
+            let outside = dir.path().join("outside");
+            fs::create_dir_all(root.join("chapters")).unwrap();
+            fs::create_dir_all(&outside).unwrap();
+            fs::write(root.join("main.typ"), "").unwrap();
+            fs::write(outside.join("secret.typ"), "").unwrap();
+            #[cfg(unix)]
+            {
+                let _ = std::os::unix::fs::symlink(&outside, root.join("link"));
+                let _ = std::os::unix::fs::symlink(outside.join("missing"), root.join("dangling"));
+            }
+            let root = root.canonicalize().unwrap();
+            (dir, root)
+        });
+        root
+    }
+
+    /// Path segments an attacker might try: parent and current folders, separators and
+    /// drives of other platforms, links, unicode and plain names.
+    fn segment() -> impl Strategy<Value = String> {
+        prop_oneof![
+            Just("..".to_string()),
+            Just(".".to_string()),
+            Just(String::new()),
+            Just("...".to_string()),
+            Just("..\\..".to_string()),
+            Just("C:".to_string()),
+            Just("~".to_string()),
+            Just("link".to_string()),
+            Just("dangling".to_string()),
+            Just("chapters".to_string()),
+            "[a-z]{1,8}(\\.typ)?",
+            "\\PC{1,6}",
+        ]
+    }
+
+    fn project_path() -> impl Strategy<Value = String> {
+        (any::<bool>(), prop::collection::vec(segment(), 0..8)).prop_map(|(absolute, segments)| {
+            let path = segments.join("/");
+            if absolute {
+                format!("/{}", path)
+            } else {
+                path
+            }
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_normalized_paths_stay_relative(path in project_path()) {
+            if let Some(relative) = normalize_project_path(Path::new(&path)) {
+                prop_assert!(relative.components().all(|c| matches!(c, Component::Normal(_))));
+            }
+        }
+
+        #[test]
+        fn test_resolved_paths_stay_in_project(path in project_path()) {
+            let root = sandbox();
+            let roots = [root.to_path_buf()];
+            if let Some(resolved) = resolve_project_path(&roots, Path::new(&path)) {
+                let relative = resolved.strip_prefix(root).unwrap();
+                prop_assert!(relative.components().all(|c| matches!(c, Component::Normal(_))));
+                let first = relative.components().next().map(|c| c.as_os_str().to_owned());
+                prop_assert!(
+                    cfg!(not(unix)) || !matches!(first.as_deref().and_then(|f| f.to_str()), Some("link" | "dangling")),
+                    "{:?} escaped through a link", path
+                );
+            }
+        }
+    }
+}
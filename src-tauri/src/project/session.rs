@@ -0,0 +1,49 @@
+use crate::appdata::{project_app_file, read_app_json, write_app_json};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Where the editor was in a file. Offsets are in characters.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct OpenFile {
+    pub path: PathBuf,
+    pub cursor: Option<usize>,
+    /// The editor's scroll offset in pixels.
+    pub scroll_top: Option<f64>,
+}
+
+/// The editor state of a project, restored when it is opened again. It is kept in the
+/// app config directory, like the statistics, so it doesn't end up in version control.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct ProjectSession {
+    pub open_files: Vec<OpenFile>,
+    pub active_file: Option<PathBuf>,
+    pub preview_file: Option<PathBuf>,
+    /// The 0-based page the preview was scrolled to.
+    pub preview_page: Option<usize>,
+}
+
+impl ProjectSession {
+    pub fn load(root: &Path) -> io::Result<Self> {
+        Ok(read_app_json(&project_app_file("session", root))?.unwrap_or_default())
+    }
+
+    /// Saves the session, leaving out files that no longer exist.
+    pub fn save(mut self, root: &Path) -> io::Result<()> {
+        let exists = |path: &Path| {
+            let relative = path.strip_prefix("/").unwrap_or(path);
+            root.join(relative).exists()
+        };
+        self.open_files.retain(|file| exists(&file.path));
+        if self
+            .active_file
+            .as_deref()
+            .is_some_and(|path| !exists(path))
+        {
+            self.active_file = None;
+        }
+        write_app_json(&project_app_file("session", root), &self)
+    }
+}
@@ -0,0 +1,57 @@
+use siphasher::sip128::{Hasher128, SipHasher};
+use std::collections::HashMap;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Identifies the on-disk state of a file at the time the editor loaded it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileStamp {
+    pub modified: Option<SystemTime>,
+    pub hash: u128,
+}
+
+impl FileStamp {
+    pub fn new(content: &[u8], modified: Option<SystemTime>) -> Self {
+        let mut hasher = SipHasher::new();
+        hasher.write(content);
+        Self {
+            modified,
+            hash: hasher.finish128().as_u128(),
+        }
+    }
+
+    /// Reads the file and returns its stamp together with the content.
+    pub fn read(path: &Path) -> std::io::Result<(Self, Vec<u8>)> {
+        let content = std::fs::read(path)?;
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        Ok((Self::new(&content, modified), content))
+    }
+
+    /// Whether the file changed relative to `other`. The modification time alone is not
+    /// trusted since some tools rewrite files without changing their content.
+    pub fn differs(&self, other: &FileStamp) -> bool {
+        self.hash != other.hash
+    }
+}
+
+/// Stamps of files as last read or written by the editor, keyed by absolute path.
+#[derive(Default)]
+pub struct FileStamps {
+    stamps: Mutex<HashMap<PathBuf, FileStamp>>,
+}
+
+impl FileStamps {
+    pub fn get(&self, path: &Path) -> Option<FileStamp> {
+        self.stamps.lock().unwrap().get(path).copied()
+    }
+
+    pub fn set(&self, path: PathBuf, stamp: FileStamp) {
+        self.stamps.lock().unwrap().insert(path, stamp);
+    }
+
+    pub fn remove(&self, path: &Path) {
+        self.stamps.lock().unwrap().remove(path);
+    }
+}
@@ -0,0 +1,166 @@
+use crate::appdata::{project_app_file, read_app_json, write_app_json};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Compiles further apart than this are separate sessions; the time between them
+/// doesn't count as time in the project.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// How often the word count is refreshed and the store written to disk.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct DayStatistics {
+    /// Words in the document at the end of the day.
+    pub words: usize,
+    pub compiles: u64,
+    /// Time spent editing, measured between compiles. Fractional, as compiles while
+    /// typing are often less than a second apart.
+    pub active_secs: f64,
+}
+
+struct StatisticsState {
+    /// Keyed by local date, eg. `2024-05-31`.
+    days: BTreeMap<String, DayStatistics>,
+    last_activity: Option<Instant>,
+    last_flush: Option<Instant>,
+    dirty: bool,
+}
+
+/// Daily writing statistics of a project, stored in the app config directory rather
+/// than the project so they don't end up in version control.
+pub struct ProjectStatistics {
+    file: String,
+    state: Mutex<StatisticsState>,
+}
+
+impl ProjectStatistics {
+    pub fn load(root: &Path) -> Self {
+        let file = project_app_file("statistics", root);
+        let days = read_app_json(&file).unwrap_or_else(|e| {
+            warn!("failed to read {}: {}", file, e);
+            None
+        });
+        Self {
+            file,
+            state: Mutex::new(StatisticsState {
+                days: days.unwrap_or_default(),
+                last_activity: None,
+                last_flush: None,
+                dirty: false,
+            }),
+        }
+    }
+
+    /// Records a successful compile. `words` counts the words of the document; it is only
+    /// called when the statistics are due to be written.
+    pub fn record_compile(&self, words: impl FnOnce() -> usize) {
+        let now = Instant::now();
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        let mut state = self.state.lock().unwrap();
+
+        let active = state
+            .last_activity
+            .map(|last| now - last)
+            .filter(|&gap| gap < IDLE_TIMEOUT)
+            .unwrap_or_default();
+        state.last_activity = Some(now);
+        state.dirty = true;
+
+        let due = !state
+            .last_flush
+            .is_some_and(|last| now - last < FLUSH_INTERVAL);
+        // A new day starts with the words of the last one until they are counted again.
+        let words_before = state.days.values().next_back().map_or(0, |day| day.words);
+        let day = state.days.entry(today).or_insert_with(|| DayStatistics {
+            words: words_before,
+            ..Default::default()
+        });
+        day.compiles += 1;
+        day.active_secs += active.as_secs_f64();
+        if due {
+            day.words = words();
+            state.last_flush = Some(now);
+            if let Err(e) = write_app_json(&self.file, &state.days) {
+                warn!("failed to write {}: {}", self.file, e);
+            }
+            state.dirty = false;
+        }
+    }
+
+    /// Writes unsaved statistics to disk.
+    pub fn flush(&self) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.dirty {
+            write_app_json(&self.file, &state.days)?;
+            state.dirty = false;
+        }
+        Ok(())
+    }
+
+    pub fn days(&self) -> BTreeMap<String, DayStatistics> {
+        self.state.lock().unwrap().days.clone()
+    }
+}
+
+/// Renders the days between `from` and `to` (inclusive, `YYYY-MM-DD`) as CSV. Words
+/// written are relative to the previous recorded day.
+pub fn statistics_csv(
+    days: &BTreeMap<String, DayStatistics>,
+    from: Option<&str>,
+    to: Option<&str>,
+) -> String {
+    let mut csv = String::from("date,words,words_written,compiles,minutes\n");
+    let mut previous: Option<usize> = None;
+    for (date, day) in days {
+        let written = day.words as i64 - previous.unwrap_or(day.words) as i64;
+        previous = Some(day.words);
+        if from.is_some_and(|from| date.as_str() < from) || to.is_some_and(|to| date.as_str() > to)
+        {
+            continue;
+        }
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{}",
+            date,
+            day.words,
+            written,
+            day.compiles,
+            (day.active_secs / 60.0) as u64
+        );
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{statistics_csv, DayStatistics};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_statistics_csv() {
+        let day = |words, compiles, active_secs| DayStatistics {
+            words,
+            compiles,
+            active_secs,
+        };
+        let days = BTreeMap::from([
+            ("2024-05-01".to_string(), day(1000, 10, 3600.0)),
+            ("2024-05-02".to_string(), day(1500, 4, 600.0)),
+            ("2024-05-04".to_string(), day(1400, 2, 90.5)),
+        ]);
+        assert_eq!(
+            statistics_csv(&days, Some("2024-05-02"), None),
+            "date,words,words_written,compiles,minutes\n\
+             2024-05-02,1500,500,4,10\n\
+             2024-05-04,1400,-100,2,1\n"
+        );
+    }
+}
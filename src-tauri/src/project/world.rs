@@ -1,10 +1,11 @@
-use crate::engine::TypstEngine;
-use chrono::Datelike;
+use crate::engine::{today, TypstEngine};
+use crate::project::resolve_project_path;
 use typst::utils::LazyHash;
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, RwLock};
 use typst::diag::{FileError, FileResult, PackageError, PackageResult};
 use typst::foundations::{Bytes, Datetime};
@@ -16,12 +17,16 @@ use typst::World;
 use typst_ide::IdeWorld;
 
 pub struct ProjectWorld {
-    root: PathBuf,
+    /// The project's folder, then any attached ones.
+    roots: RwLock<Vec<PathBuf>>,
     engine: Arc<TypstEngine>,
 
     slots: RwLock<HashMap<FileId, PathSlot>>,
 
     main: Option<FileId>,
+
+    /// Bumped whenever a file of the world changes.
+    revision: AtomicU64,
 }
 
 impl ProjectWorld {
@@ -36,13 +41,7 @@ impl ProjectWorld {
         let mut slots = self.slots.write().unwrap();
         
         if let Entry::Vacant(_) = &slots.entry(id) {
-            let buf;
-            let mut root = &self.root;
-            if let Some(spec) = id.package() {
-                buf = Self::prepare_package(spec)?;
-                root = &buf;
-            }
-            let path = id.vpath().resolve(root).ok_or(FileError::AccessDenied)?;
+            let path = self.resolve(id)?;
             slots.insert(id, PathSlot {
                 id,
                 path,
@@ -52,8 +51,21 @@ impl ProjectWorld {
         }
         
         let slot = slots.get(&id).unwrap();
-        
-        if let Some(ref content_str) = content {
+        self.revision.fetch_add(1, Ordering::SeqCst);
+
+        // A change on disk is read right away, so the source is reparsed incrementally like
+        // an edit and memoized results for the rest of the file stay valid. Other files are
+        // read again on their next use.
+        let content = match content {
+            Some(content) => Some(content),
+            None => self.take_or_read(&vpath, None).ok(),
+        };
+        let Some(content_str) = content else {
+            *slot.buffer.write().unwrap() = None;
+            *slot.source.write().unwrap() = None;
+            return Ok(id);
+        };
+        {
             let bytes = Bytes::new(content_str.as_bytes().to_vec());
             *slot.buffer.write().unwrap() = Some(Ok(bytes));
             
@@ -71,10 +83,8 @@ impl ProjectWorld {
         Ok(id)
     }
 
+    /// Loaded files are kept, as switching between files doesn't change them.
     pub fn set_main(&mut self, id: Option<FileId>) {
-        if self.main != id {
-            self.clear_slots();
-        }
         self.main = id
     }
 
@@ -104,6 +114,19 @@ impl ProjectWorld {
     pub fn clear_slots(&self) {
         let mut slots = self.slots.write().unwrap();
         slots.clear();
+        self.revision.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Increases with every change of the world's files, so a document can be stamped
+    /// with the revision it was compiled from.
+    pub fn revision(&self) -> u64 {
+        self.revision.load(Ordering::SeqCst)
+    }
+
+    /// Marks documents compiled so far as outdated although no file changed, eg. after
+    /// the preview inputs changed.
+    pub fn bump_revision(&self) {
+        self.revision.fetch_add(1, Ordering::SeqCst);
     }
     
     pub fn get_main_path(&self) -> Option<String> {
@@ -113,12 +136,47 @@ impl ProjectWorld {
         })
     }
 
+    /// A copy of the world sharing its fonts and loaded files, to compile without holding
+    /// the project's lock.
+    pub fn snapshot(&self) -> ProjectWorld {
+        let slots = self.slots.read().unwrap();
+        Self {
+            roots: RwLock::new(self.roots.read().unwrap().clone()),
+            engine: self.engine.clone(),
+            slots: RwLock::new(slots.iter().map(|(id, slot)| (*id, slot.clone())).collect()),
+            main: self.main,
+            revision: AtomicU64::new(self.revision()),
+        }
+    }
+
+    /// Keeps the files a compile of `snapshot` loaded, unless a file changed since it was
+    /// taken, which they may be outdated by.
+    pub fn absorb(&self, snapshot: &ProjectWorld) {
+        let mut slots = self.slots.write().unwrap();
+        if snapshot.revision() != self.revision() {
+            return;
+        }
+        for (id, slot) in snapshot.slots.read().unwrap().iter() {
+            slots.entry(*id).or_insert_with(|| slot.clone());
+        }
+    }
+
+    pub fn engine(&self) -> Arc<TypstEngine> {
+        self.engine.clone()
+    }
+
     pub fn new(root: PathBuf, progress: Option<Box<dyn Fn(String, u32) + Send>>) -> Self {
+        Self::with_engine(root, Arc::new(TypstEngine::new(progress)))
+    }
+
+    /// A world sharing the fonts of `engine`, eg. to compile a file outside any project.
+    pub fn with_engine(root: PathBuf, engine: Arc<TypstEngine>) -> Self {
         Self {
-            root,
-            engine: Arc::new(TypstEngine::new(progress)),
+            roots: RwLock::new(vec![root]),
+            engine,
             slots: RwLock::new(HashMap::new()),
             main: None,
+            revision: AtomicU64::new(0),
         }
     }
 
@@ -127,10 +185,31 @@ impl ProjectWorld {
             return Ok(content);
         }
 
-        let path = vpath.resolve(&self.root).ok_or(FileError::AccessDenied)?;
+        let path = self.resolve(FileId::new(None, vpath.clone()))?;
         fs::read_to_string(&path).map_err(|e| FileError::from_io(e, &path))
     }
 
+    /// The file of `id`: in its package, or in the first of the project's roots that has it.
+    fn resolve(&self, id: FileId) -> FileResult<PathBuf> {
+        if let Some(spec) = id.package() {
+            let root = Self::prepare_package(spec)?;
+            return id.vpath().resolve(&root).ok_or(FileError::AccessDenied);
+        }
+        resolve_project_path(&self.roots.read().unwrap(), id.vpath().as_rootless_path())
+            .ok_or(FileError::AccessDenied)
+    }
+
+    /// Sets the folders paths are resolved against, the project's own first. Loaded files
+    /// are dropped if they changed, as a path may now resolve to another root.
+    pub fn set_roots(&self, roots: Vec<PathBuf>) {
+        let mut current = self.roots.write().unwrap();
+        if *current != roots {
+            *current = roots;
+            drop(current);
+            self.clear_slots();
+        }
+    }
+
     fn prepare_package(spec: &PackageSpec) -> PackageResult<PathBuf> {
         let subdir = format!(
             "typst/packages/{}/{}/{}",
@@ -179,13 +258,7 @@ impl World for ProjectWorld {
         drop(slots);
         
         let mut slots = self.slots.write().unwrap();
-        let buf;
-        let mut root = &self.root;
-        if let Some(spec) = id.package() {
-            buf = Self::prepare_package(spec)?;
-            root = &buf;
-        }
-        let path = id.vpath().resolve(root).ok_or(FileError::AccessDenied)?;
+        let path = self.resolve(id)?;
         
         let slot = slots.entry(id).or_insert_with(|| PathSlot {
             id,
@@ -204,13 +277,7 @@ impl World for ProjectWorld {
         drop(slots);
         
         let mut slots = self.slots.write().unwrap();
-        let buf;
-        let mut root = &self.root;
-        if let Some(spec) = id.package() {
-            buf = Self::prepare_package(spec)?;
-            root = &buf;
-        }
-        let path = id.vpath().resolve(root).ok_or(FileError::AccessDenied)?;
+        let path = self.resolve(id)?;
         
         let slot = slots.entry(id).or_insert_with(|| PathSlot {
             id,
@@ -222,25 +289,11 @@ impl World for ProjectWorld {
     }
 
     fn font(&self, id: usize) -> Option<Font> {
-        let slot = &self.engine.fonts[id];
-        slot.font
-            .get_or_init(|| {
-                let data = fs::read(&slot.path).map(|v| Bytes::new(v)).ok()?;
-                Font::new(data, slot.index)
-            })
-            .clone()
+        self.engine.font(id)
     }
 
     fn today(&self, offset: Option<i64>) -> Option<Datetime> {
-        let dt = match offset {
-            None => chrono::Local::now().naive_local(),
-            Some(o) => (chrono::Utc::now() + chrono::Duration::try_hours(o)?).naive_utc(),
-        };
-        Datetime::from_ymd(
-            dt.year(),
-            dt.month().try_into().ok()?,
-            dt.day().try_into().ok()?,
-        )
+        today(offset)
     }
 }
 
@@ -251,6 +304,17 @@ struct PathSlot {
     buffer: RwLock<Option<FileResult<Bytes>>>,
 }
 
+impl Clone for PathSlot {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            path: self.path.clone(),
+            source: RwLock::new(self.source.read().unwrap().clone()),
+            buffer: RwLock::new(self.buffer.read().unwrap().clone()),
+        }
+    }
+}
+
 impl PathSlot {
     fn source(&self) -> FileResult<Source> {
         let guard = self.source.read().unwrap();
@@ -300,6 +364,7 @@ impl IdeWorld for ProjectWorld {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::BTreeMap;
     use std::path::PathBuf;
 
     #[test]
@@ -331,4 +396,41 @@ Hello World"#;
         
         assert!(result.output.is_ok(), "Compilation failed");
     }
+
+    #[test]
+    fn test_files_survive_main_changes_and_follow_disk() {
+        let dir = std::env::temp_dir().join(format!("typstudio-world-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.typ"), "A").unwrap();
+        let engine = Arc::new(TypstEngine::embedded());
+        let mut world = ProjectWorld::with_engine(dir.clone(), engine.clone());
+
+        let a = world.slot_update("a.typ", Some("edited".to_string())).unwrap();
+        world.set_main_path(VirtualPath::new("b.typ"));
+        assert_eq!(world.source(a).unwrap().text(), "edited");
+
+        fs::write(dir.join("a.typ"), "changed on disk").unwrap();
+        world.slot_update("a.typ", None).unwrap();
+        assert_eq!(world.source(a).unwrap().text(), "changed on disk");
+
+        // Files a snapshot loads are kept, unless the world changed meanwhile.
+        fs::write(dir.join("c.typ"), "C").unwrap();
+        let c = FileId::new(None, VirtualPath::new("c.typ"));
+        let snapshot = world.snapshot();
+        assert_eq!(snapshot.source(a).unwrap().text(), "changed on disk");
+        snapshot.source(c).unwrap();
+        world.absorb(&snapshot);
+        assert!(world.slots.read().unwrap().contains_key(&c));
+        let snapshot = world.snapshot();
+        world.slot_update("a.typ", Some("newer".to_string())).unwrap();
+        world.absorb(&snapshot);
+        assert_eq!(world.source(a).unwrap().text(), "newer");
+
+        let inputs = BTreeMap::from([("lang".to_string(), "en".to_string())]);
+        assert!(Arc::ptr_eq(
+            &engine.library_for_inputs(&inputs),
+            &engine.library_for_inputs(&inputs)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
@@ -0,0 +1,161 @@
+//! fzf-style fuzzy matching for quick open.
+
+const SCORE_MATCH: i64 = 16;
+const SCORE_GAP_START: i64 = -3;
+const SCORE_GAP_EXTENSION: i64 = -1;
+const BONUS_BOUNDARY: i64 = 8;
+const BONUS_CAMEL: i64 = 7;
+const BONUS_CONSECUTIVE: i64 = 4;
+const BONUS_FIRST_CHAR_MULTIPLIER: i64 = 2;
+const BONUS_FILENAME: i64 = 10;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// Character indices of the matched characters in the candidate.
+    pub positions: Vec<usize>,
+}
+
+fn bonus(prev: Option<char>, current: char) -> i64 {
+    match prev {
+        None | Some('/' | '\\') => BONUS_BOUNDARY + 1,
+        Some('_' | '-' | '.' | ' ') => BONUS_BOUNDARY,
+        Some(p) if p.is_lowercase() && current.is_uppercase() => BONUS_CAMEL,
+        Some(p) if !p.is_numeric() && current.is_numeric() => BONUS_CAMEL,
+        _ => 0,
+    }
+}
+
+fn eq(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// Matches `query` against `candidate` like fzf's v1 algorithm: find the first
+/// occurrence of the query as a subsequence, then shrink it by scanning backwards
+/// from its end. Whitespace in the query is ignored.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+    let chars: Vec<char> = candidate.chars().collect();
+    if query.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: vec![],
+        });
+    }
+
+    // Forward pass: the end of the first subsequence occurrence.
+    let mut qi = 0;
+    let mut end = None;
+    for (i, &c) in chars.iter().enumerate() {
+        if eq(c, query[qi]) {
+            qi += 1;
+            if qi == query.len() {
+                end = Some(i);
+                break;
+            }
+        }
+    }
+    let end = end?;
+
+    // Backward pass: the latest start that still matches, which yields the tightest window.
+    let mut qi = query.len();
+    let mut start = end;
+    for i in (0..=end).rev() {
+        if eq(chars[i], query[qi - 1]) {
+            qi -= 1;
+            if qi == 0 {
+                start = i;
+                break;
+            }
+        }
+    }
+
+    // Scoring pass over the window, greedily taking matches left to right.
+    let filename_start = chars
+        .iter()
+        .rposition(|&c| c == '/' || c == '\\')
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    let mut positions = Vec::with_capacity(query.len());
+    let mut score = 0;
+    let mut qi = 0;
+    let mut in_gap = false;
+    let mut consecutive = 0;
+    for i in start..=end {
+        if qi < query.len() && eq(chars[i], query[qi]) {
+            let prev = i.checked_sub(1).map(|p| chars[p]);
+            let mut b = bonus(prev, chars[i]);
+            if qi == 0 {
+                b *= BONUS_FIRST_CHAR_MULTIPLIER;
+            }
+            if consecutive > 0 {
+                b = b.max(BONUS_CONSECUTIVE);
+            }
+            if i >= filename_start {
+                b += BONUS_FILENAME / query.len() as i64;
+            }
+            score += SCORE_MATCH + b;
+            positions.push(i);
+            consecutive += 1;
+            in_gap = false;
+            qi += 1;
+        } else {
+            score += if in_gap {
+                SCORE_GAP_EXTENSION
+            } else {
+                SCORE_GAP_START
+            };
+            consecutive = 0;
+            in_gap = true;
+        }
+    }
+
+    Some(FuzzyMatch { score, positions })
+}
+
+/// Scores all candidates and returns the best `limit` as `(index, match)`, ordered
+/// by descending score, then by shorter candidates.
+pub fn fuzzy_rank<S: AsRef<str>>(
+    query: &str,
+    candidates: &[S],
+    limit: usize,
+) -> Vec<(usize, FuzzyMatch)> {
+    let mut results: Vec<(usize, FuzzyMatch)> = candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| fuzzy_match(query, c.as_ref()).map(|m| (i, m)))
+        .collect();
+    results.sort_by(|(a_index, a), (b_index, b)| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| candidates[*a_index].as_ref().len().cmp(&candidates[*b_index].as_ref().len()))
+    });
+    results.truncate(limit);
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_match_positions() {
+        let m = fuzzy_match("mtp", "main.typ").unwrap();
+        assert_eq!(m.positions, vec![0, 5, 7]);
+        assert!(fuzzy_match("xyz", "main.typ").is_none());
+    }
+
+    #[test]
+    fn test_fuzzy_rank_prefers_boundaries_and_filenames() {
+        let candidates = [
+            "chapters/introduction.typ",
+            "assets/intro-image.png",
+            "chapters/appendix/notes.typ",
+            "intro.typ",
+        ];
+        let ranked = fuzzy_rank("intro", &candidates, 10);
+        assert_eq!(ranked.len(), 3);
+        assert_eq!(candidates[ranked[0].0], "intro.typ");
+        assert!(ranked.iter().all(|(i, _)| candidates[*i] != "chapters/appendix/notes.typ"));
+    }
+}
@@ -0,0 +1,5 @@
+mod fuzzy;
+mod text;
+
+pub use fuzzy::*;
+pub use text::*;
@@ -0,0 +1,214 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+/// Files larger than this are skipped by project search.
+pub const MAX_SEARCH_FILE_SIZE: u64 = 4 * 1024 * 1024;
+const MAX_CONTEXT_CHARS: usize = 200;
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct SearchOptions {
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    /// Glob patterns a file must match, eg. `chapters/**`. Empty matches everything.
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub max_results: Option<usize>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SearchMatch {
+    /// Zero-based line index.
+    pub line: usize,
+    /// Zero-based character column of the match start within the line.
+    pub column: usize,
+    /// Length of the match in characters.
+    pub length: usize,
+    /// Character range of the match within the whole file.
+    pub range: std::ops::Range<usize>,
+    /// The line containing the match, shortened around the match if very long.
+    pub context: String,
+    /// Offset of the match start within `context`, in characters.
+    pub context_column: usize,
+}
+
+pub fn build_matcher(query: &str, options: &SearchOptions) -> Result<Regex, regex::Error> {
+    let pattern = if options.regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    let pattern = if options.whole_word {
+        format!(r"\b(?:{})\b", pattern)
+    } else {
+        pattern
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .multi_line(true)
+        .build()
+}
+
+/// Compiles include and exclude globs into a path filter.
+pub struct PathFilter {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+impl PathFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, globset::Error> {
+        let build = |patterns: &[String]| -> Result<GlobSet, globset::Error> {
+            let mut builder = GlobSetBuilder::new();
+            for pattern in patterns {
+                builder.add(Glob::new(pattern)?);
+            }
+            builder.build()
+        };
+        Ok(Self {
+            include: if include.is_empty() {
+                None
+            } else {
+                Some(build(include)?)
+            },
+            exclude: build(exclude)?,
+        })
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        self.include.as_ref().map_or(true, |i| i.is_match(path)) && !self.exclude.is_match(path)
+    }
+}
+
+pub fn is_probably_binary(content: &[u8]) -> bool {
+    content.iter().take(8192).any(|&b| b == 0)
+}
+
+/// Finds all matches of `matcher` in `text`. Matches spanning lines are reported at
+/// the line they start on.
+pub fn search_text(text: &str, matcher: &Regex, limit: usize) -> Vec<SearchMatch> {
+    let mut matches = vec![];
+    let mut line = 0;
+    let mut line_start = 0;
+    let mut scanned = 0;
+    let mut char_offset = 0;
+
+    for m in matcher.find_iter(text) {
+        if matches.len() >= limit {
+            break;
+        }
+        if m.start() == m.end() {
+            continue;
+        }
+
+        char_offset += text[scanned..m.start()].chars().count();
+        for (i, b) in text[scanned..m.start()].bytes().enumerate() {
+            if b == b'\n' {
+                line += 1;
+                line_start = scanned + i + 1;
+            }
+        }
+        scanned = m.start();
+
+        let line_end = text[line_start..].find('\n').map_or(text.len(), |i| line_start + i);
+        let line_text = text[line_start..line_end].trim_end_matches('\r');
+        let column = text[line_start..m.start()].chars().count();
+        let length = m.as_str().chars().count();
+
+        let (context, context_column) = shorten_context(line_text, column, length);
+        matches.push(SearchMatch {
+            line,
+            column,
+            length,
+            range: char_offset..char_offset + length,
+            context,
+            context_column,
+        });
+    }
+
+    matches
+}
+
+fn shorten_context(line: &str, column: usize, length: usize) -> (String, usize) {
+    let chars: Vec<char> = line.chars().collect();
+    if chars.len() <= MAX_CONTEXT_CHARS {
+        return (line.to_string(), column);
+    }
+    let before = MAX_CONTEXT_CHARS.saturating_sub(length.min(MAX_CONTEXT_CHARS)) / 2;
+    let start = column.saturating_sub(before);
+    let end = (start + MAX_CONTEXT_CHARS).min(chars.len());
+    (chars[start..end].iter().collect(), column - start)
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Replacement {
+    pub line: usize,
+    pub column: usize,
+    /// Character range of the replaced text in the original file.
+    pub range: std::ops::Range<usize>,
+    pub original: String,
+    pub replacement: String,
+}
+
+/// Replaces every match in `text`. With `expand`, `$1`/`${name}` in `replacement`
+/// refer to capture groups; otherwise it is inserted literally.
+pub fn replace_text(
+    text: &str,
+    matcher: &Regex,
+    replacement: &str,
+    expand: bool,
+) -> (String, Vec<Replacement>) {
+    let mut out = String::with_capacity(text.len());
+    let mut replacements = vec![];
+    let mut last = 0;
+    let mut line = 0;
+    let mut line_start = 0;
+    let mut char_offset = 0;
+
+    for caps in matcher.captures_iter(text) {
+        let m = caps.get(0).unwrap();
+        if m.start() == m.end() {
+            continue;
+        }
+
+        let skipped = &text[last..m.start()];
+        out.push_str(skipped);
+        char_offset += skipped.chars().count();
+        for (i, b) in skipped.bytes().enumerate() {
+            if b == b'\n' {
+                line += 1;
+                line_start = last + i + 1;
+            }
+        }
+
+        let mut replaced = String::new();
+        if expand {
+            caps.expand(replacement, &mut replaced);
+        } else {
+            replaced.push_str(replacement);
+        }
+        out.push_str(&replaced);
+
+        let length = m.as_str().chars().count();
+        replacements.push(Replacement {
+            line,
+            column: text[line_start..m.start()].chars().count(),
+            range: char_offset..char_offset + length,
+            original: m.as_str().to_string(),
+            replacement: replaced,
+        });
+
+        char_offset += length;
+        for (i, b) in m.as_str().bytes().enumerate() {
+            if b == b'\n' {
+                line += 1;
+                line_start = m.start() + i + 1;
+            }
+        }
+        last = m.end();
+    }
+    out.push_str(&text[last..]);
+
+    (out, replacements)
+}
@@ -0,0 +1,96 @@
+use crate::appdata::{read_app_json, write_app_json, SETTINGS_FILE};
+use log::warn;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct EditorSettings {
+    pub font_family: String,
+    pub font_size: f64,
+    pub tab_size: u32,
+    pub word_wrap: bool,
+    pub line_numbers: bool,
+    pub minimap: bool,
+}
+
+impl Default for EditorSettings {
+    fn default() -> Self {
+        Self {
+            font_family: "JetBrains Mono, monospace".into(),
+            font_size: 14.0,
+            tab_size: 2,
+            word_wrap: true,
+            line_numbers: true,
+            minimap: false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct PreviewSettings {
+    /// How long the compiler waits for further edits before compiling.
+    pub compile_debounce_ms: u64,
+    /// Scrolls the preview to the cursor as it moves.
+    pub follow_cursor: bool,
+    /// Compiles when a file is opened, not only when it is edited.
+    pub compile_on_open: bool,
+    /// Gives up on preview compiles running longer, eg. stuck in an endless loop.
+    pub compile_timeout_secs: Option<u64>,
+}
+
+impl Default for PreviewSettings {
+    fn default() -> Self {
+        Self {
+            compile_debounce_ms: 0,
+            follow_cursor: true,
+            compile_on_open: true,
+            compile_timeout_secs: Some(30),
+        }
+    }
+}
+
+/// The user's preferences, shared by the frontend and the backend.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct AppSettings {
+    pub editor: EditorSettings,
+    pub preview: PreviewSettings,
+    /// Saves modified files after this many seconds, or never if unset.
+    pub autosave_interval_secs: Option<u64>,
+    /// Where export dialogs open when a project has no previous export.
+    pub default_export_dir: Option<PathBuf>,
+    /// Opens the last project on launch, restoring its session.
+    pub reopen_last_project: bool,
+    /// Local model server used for ghost text, eg. `http://localhost:8080/complete`.
+    pub continuation_endpoint: Option<String>,
+}
+
+static SETTINGS: Lazy<RwLock<AppSettings>> = Lazy::new(|| {
+    let settings = read_app_json(SETTINGS_FILE).unwrap_or_else(|e| {
+        warn!("failed to read {}: {}", SETTINGS_FILE, e);
+        None
+    });
+    RwLock::new(settings.unwrap_or_default())
+});
+
+pub fn app_settings() -> AppSettings {
+    SETTINGS.read().unwrap().clone()
+}
+
+pub fn set_app_settings(settings: AppSettings) -> io::Result<()> {
+    write_app_json(SETTINGS_FILE, &settings)?;
+    *SETTINGS.write().unwrap() = settings;
+    Ok(())
+}
+
+/// Rereads the settings file, eg. after importing a settings bundle.
+pub fn reload_app_settings() -> io::Result<AppSettings> {
+    let settings: AppSettings = read_app_json(SETTINGS_FILE)?.unwrap_or_default();
+    *SETTINGS.write().unwrap() = settings.clone();
+    Ok(settings)
+}
@@ -0,0 +1,188 @@
+use crate::appdata::{read_app_json, write_app_json, SNIPPETS_FILE};
+use crate::project::Project;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::sync::Mutex;
+
+/// Where a snippet is stored: in the user's app data or in the project config.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SnippetScope {
+    User,
+    Project,
+}
+
+/// Code inserted by typing its prefix, offered with the completions.
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Snippet {
+    pub id: String,
+    /// The word that triggers the snippet, eg. `fig`.
+    pub prefix: String,
+    #[serde(default)]
+    pub description: String,
+    /// Typst code with `${name}` placeholders, which the editor turns into tab stops
+    /// like those of the built-in completions.
+    pub body: String,
+}
+
+/// A placeholder of a snippet's body. Offsets are in characters and span `${...}`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct SnippetPlaceholder {
+    /// The tab stop, counting from 1 in the order of the body.
+    pub index: usize,
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SnippetEntry {
+    #[serde(flatten)]
+    pub snippet: Snippet,
+    pub scope: SnippetScope,
+    pub placeholders: Vec<SnippetPlaceholder>,
+}
+
+impl SnippetEntry {
+    pub fn new(snippet: Snippet, scope: SnippetScope) -> Self {
+        Self {
+            placeholders: placeholders(&snippet.body),
+            snippet,
+            scope,
+        }
+    }
+}
+
+/// The `${name}` placeholders of `body`. An unclosed `${` is left as text.
+pub fn placeholders(body: &str) -> Vec<SnippetPlaceholder> {
+    let chars: Vec<char> = body.chars().collect();
+    let mut placeholders = vec![];
+    let mut i = 0;
+    while i + 1 < chars.len() {
+        if chars[i] == '$' && chars[i + 1] == '{' {
+            if let Some(close) = chars[i + 2..].iter().position(|&c| c == '}') {
+                let end = i + 2 + close + 1;
+                placeholders.push(SnippetPlaceholder {
+                    index: placeholders.len() + 1,
+                    name: chars[i + 2..end - 1].iter().collect(),
+                    start: i,
+                    end,
+                });
+                i = end;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    placeholders
+}
+
+/// The user's snippets, read once instead of on every completion and document action.
+/// Saving the snippets replaces them, importing them clears them.
+static USER_SNIPPETS: Lazy<Mutex<Option<Vec<SnippetEntry>>>> = Lazy::new(|| Mutex::new(None));
+
+fn user_entries(snippets: &[Snippet]) -> Vec<SnippetEntry> {
+    snippets
+        .iter()
+        .map(|snippet| SnippetEntry::new(snippet.clone(), SnippetScope::User))
+        .collect()
+}
+
+fn cached_user_snippets() -> io::Result<Vec<SnippetEntry>> {
+    let mut cached = USER_SNIPPETS.lock().unwrap();
+    if let Some(entries) = &*cached {
+        return Ok(entries.clone());
+    }
+    let snippets: Vec<Snippet> = read_app_json(SNIPPETS_FILE)?.unwrap_or_default();
+    Ok(cached.insert(user_entries(&snippets)).clone())
+}
+
+pub fn user_snippets() -> io::Result<Vec<Snippet>> {
+    Ok(cached_user_snippets()?
+        .into_iter()
+        .map(|entry| entry.snippet)
+        .collect())
+}
+
+pub fn save_user_snippets(snippets: &[Snippet]) -> io::Result<()> {
+    write_app_json(SNIPPETS_FILE, &snippets)?;
+    *USER_SNIPPETS.lock().unwrap() = Some(user_entries(snippets));
+    Ok(())
+}
+
+/// Drops the cached snippets, so the file is read again, eg. after importing a settings
+/// bundle.
+pub fn reload_user_snippets() {
+    *USER_SNIPPETS.lock().unwrap() = None;
+}
+
+/// The user's snippets followed by the open project's.
+pub fn all_snippets(project: Option<&Project>) -> Vec<SnippetEntry> {
+    let mut entries = cached_user_snippets().unwrap_or_else(|e| {
+        log::warn!("unable to read snippets: {}", e);
+        vec![]
+    });
+    if let Some(project) = project {
+        let config = project.config.read().unwrap();
+        entries.extend(
+            config
+                .snippets
+                .iter()
+                .map(|snippet| SnippetEntry::new(snippet.clone(), SnippetScope::Project)),
+        );
+    }
+    entries
+}
+
+/// An id derived from `prefix` that none of `snippets` has yet.
+pub fn unique_snippet_id(snippets: &[Snippet], prefix: &str) -> String {
+    let base: String = prefix
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    let base = match base.trim_matches('-') {
+        "" => "snippet",
+        base => base,
+    };
+    let taken = |id: &str| snippets.iter().any(|s| s.id == id);
+    let mut id = base.to_string();
+    let mut n = 2;
+    while taken(&id) {
+        id = format!("{}-{}", base, n);
+        n += 1;
+    }
+    id
+}
+
+/// Whether the snippet is offered for `typed`, the word before the cursor. Nothing
+/// typed offers all snippets.
+pub fn snippet_matches(snippet: &Snippet, typed: &str) -> bool {
+    snippet
+        .prefix
+        .to_lowercase()
+        .starts_with(&typed.trim_start_matches('#').to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{placeholders, unique_snippet_id, Snippet};
+
+    #[test]
+    fn test_placeholders() {
+        let found = placeholders("#figure(${image}, caption: [${}]) ${open");
+        assert_eq!(found.len(), 2);
+        assert_eq!((found[0].index, found[0].name.as_str()), (1, "image"));
+        assert_eq!((found[0].start, found[0].end), (8, 16));
+        assert_eq!((found[1].index, found[1].name.as_str()), (2, ""));
+
+        let existing = vec![Snippet {
+            id: "fig".into(),
+            prefix: "fig".into(),
+            description: String::new(),
+            body: String::new(),
+        }];
+        assert_eq!(unique_snippet_id(&existing, "fig"), "fig-2");
+        assert_eq!(unique_snippet_id(&existing, "#"), "snippet");
+    }
+}
@@ -0,0 +1,129 @@
+//! Golden tests of the render pipeline. Every `tests/golden/<case>.typ` is compiled with
+//! the embedded fonts only, and its pages are rendered to SVG by the incremental renderer
+//! and to pixels by `typst-render`. The hashes of both are compared to `<case>.golden`, so
+//! changes to the renderer, the fonts or a Typst update can't alter the output unnoticed.
+//!
+//! After an intended change, or for a new case, record the output with
+//! `UPDATE_GOLDENS=1 cargo test golden` and commit the `.golden` files. A missing golden
+//! fails the test otherwise, so a case can't pass without anything to compare to.
+
+use crate::compiler::IncrementalRenderer;
+use crate::engine::TypstEngine;
+use crate::project::ProjectWorld;
+use once_cell::sync::Lazy;
+use siphasher::sip128::{Hasher128, SipHasher};
+use std::fs;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use typst::layout::PagedDocument;
+use typst::syntax::VirtualPath;
+
+const UPDATE_ENV: &str = "UPDATE_GOLDENS";
+
+/// Pixels per point of the rendered images. Low, as only their hash is compared.
+const PIXEL_PER_PT: f32 = 1.0;
+
+static ENGINE: Lazy<Arc<TypstEngine>> = Lazy::new(|| Arc::new(TypstEngine::embedded()));
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn hash(data: &[u8]) -> String {
+    let mut hasher = SipHasher::new();
+    hasher.write(data);
+    hex::encode(hasher.finish128().as_bytes())
+}
+
+/// Compiles `<case>.typ` and describes its pages, one line each, in the golden format.
+/// The SVG of every page is also written to `out`, to compare by eye when a case fails.
+fn render_case(case: &str, out: &Path) -> Result<String, String> {
+    let mut world = ProjectWorld::with_engine(golden_dir(), ENGINE.clone());
+    world.set_main_path(VirtualPath::new(format!("{}.typ", case)));
+    let document = typst::compile::<PagedDocument>(&world)
+        .output
+        .map_err(|errors| format!("{} failed to compile: {:?}", case, errors))?;
+
+    let mut renderer = IncrementalRenderer::new();
+    let mut golden = format!("pages {}\n", document.pages.len());
+    for (i, page) in document.pages.iter().enumerate() {
+        let (svg, _) = renderer.render_page(i, page);
+        // Rendering the unchanged page again must come from the cache, unaltered.
+        if renderer.render_page(i, page) != (svg.clone(), false) {
+            return Err(format!(
+                "{} page {} rendered differently from the cache",
+                case,
+                i + 1
+            ));
+        }
+        let _ = fs::write(out.join(format!("{}-{}.svg", case, i + 1)), &svg);
+
+        let pixmap = typst_render::render(page, PIXEL_PER_PT);
+        golden.push_str(&format!(
+            "page {} {:.2}x{:.2}pt svg {} png {}x{} {}\n",
+            i + 1,
+            page.frame.width().to_pt(),
+            page.frame.height().to_pt(),
+            hash(svg.as_bytes()),
+            pixmap.width(),
+            pixmap.height(),
+            hash(pixmap.data()),
+        ));
+    }
+    Ok(golden)
+}
+
+#[test]
+fn test_render_goldens() {
+    let update = std::env::var_os(UPDATE_ENV).is_some();
+    let out = std::env::temp_dir().join(format!("typstudio-golden-{}", std::process::id()));
+    fs::create_dir_all(&out).unwrap();
+
+    let mut cases: Vec<String> = fs::read_dir(golden_dir())
+        .unwrap()
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != "typ" {
+                return None;
+            }
+            Some(path.file_stem()?.to_str()?.to_string())
+        })
+        .collect();
+    cases.sort();
+    assert!(!cases.is_empty(), "no golden cases in {:?}", golden_dir());
+
+    let mut failures = vec![];
+    for case in &cases {
+        let actual = match render_case(case, &out) {
+            Ok(actual) => actual,
+            Err(e) => {
+                failures.push(e);
+                continue;
+            }
+        };
+        let golden_path = golden_dir().join(format!("{}.golden", case));
+        match fs::read_to_string(&golden_path) {
+            Ok(expected) if expected == actual => {}
+            _ if update => fs::write(&golden_path, actual).unwrap(),
+            Ok(expected) => failures.push(format!(
+                "{} differs from its golden\n--- expected\n{}--- actual\n{}",
+                case, expected, actual
+            )),
+            Err(_) => failures.push(format!(
+                "{} has no golden {:?}\n--- actual\n{}",
+                case, golden_path, actual
+            )),
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{}\n\nThe rendered pages are in {:?}. If the change is intended, rerun with {}=1 \
+         and commit the goldens.",
+        failures.join("\n\n"),
+        out,
+        UPDATE_ENV
+    );
+    let _ = fs::remove_dir_all(&out);
+}
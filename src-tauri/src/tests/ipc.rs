@@ -0,0 +1,242 @@
+use super::Harness;
+use crate::ipc::commands::{
+    diagnostics_project, export_pdf, project_path, search_replace_apply, typst_autocomplete,
+    typst_compile,
+};
+use crate::project::{autosave_project, FileStamp};
+use crate::search::SearchOptions;
+use proptest::prelude::*;
+use proptest::test_runner::{Config, TestRunner};
+use std::fs;
+use std::path::PathBuf;
+
+fn main_content(harness: &Harness) -> String {
+    fs::read_to_string(harness.root.join("main.typ")).unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_compile_emits_document() {
+    let harness = Harness::open("basic");
+    assert_eq!(harness.events("project_changed").len(), 1);
+
+    typst_compile(
+        harness.window.clone(),
+        harness.state(),
+        harness.project_manager(),
+        PathBuf::from("/main.typ"),
+        main_content(&harness),
+        None,
+        1,
+    )
+    .await
+    .unwrap();
+
+    let event = harness.wait_for("typst_compile", 1).await;
+    assert_eq!(event["document"]["pages"], 1);
+    assert!(event["diagnostics"].is_null());
+    assert!(event["version"].as_u64().unwrap() > 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_compile_reports_diagnostics() {
+    let harness = Harness::open("basic");
+    let content = main_content(&harness) + "\n#unknown-function()\n";
+
+    typst_compile(
+        harness.window.clone(),
+        harness.state(),
+        harness.project_manager(),
+        PathBuf::from("/main.typ"),
+        content,
+        None,
+        1,
+    )
+    .await
+    .unwrap();
+
+    let event = harness.wait_for("typst_compile", 1).await;
+    assert!(event["document"].is_null());
+    let diagnostics = event["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics[0]["severity"], "error");
+    assert!(diagnostics[0]["message"]
+        .as_str()
+        .unwrap()
+        .contains("unknown variable"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_project_diagnostics_cover_other_files() {
+    let harness = Harness::open("basic");
+    let template = "#let title(body) = unknown-function()\n";
+    fs::write(harness.root.join("template.typ"), template).unwrap();
+
+    typst_compile(
+        harness.window.clone(),
+        harness.state(),
+        harness.project_manager(),
+        PathBuf::from("/main.typ"),
+        main_content(&harness),
+        None,
+        1,
+    )
+    .await
+    .unwrap();
+
+    let event = harness.wait_for("project_diagnostics", 1).await;
+    assert_eq!(event["errors"], 1);
+    assert_eq!(event["files"][0]["path"], "template.typ");
+    assert_eq!(event["files"][0]["diagnostics"][0]["start"][0], 1);
+
+    let problems = diagnostics_project(harness.window.clone(), harness.project_manager())
+        .await
+        .unwrap();
+    assert_eq!(problems.errors, 1);
+    assert_eq!(harness.events("project_diagnostics").len(), 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_autocomplete() {
+    let harness = Harness::open("basic");
+    let content = "#pageb".to_string();
+
+    let response = typst_autocomplete(
+        harness.window.clone(),
+        harness.project_manager(),
+        PathBuf::from("/main.typ"),
+        content,
+        6,
+        true,
+    )
+    .await
+    .unwrap();
+
+    let response = serde_json::to_value(response).unwrap();
+    let labels: Vec<&str> = response["completions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|completion| completion["label"].as_str())
+        .collect();
+    assert!(labels.contains(&"pagebreak"), "{:?}", labels);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_export_pdf() {
+    let harness = Harness::open("basic");
+    let out = harness.root.join("out.pdf");
+
+    export_pdf(
+        harness.window.clone(),
+        harness.project_manager(),
+        out.to_string_lossy().to_string(),
+        None,
+    )
+    .await
+    .unwrap();
+
+    assert!(fs::read(&out).unwrap().starts_with(b"%PDF"));
+    let exports = harness.wait_for("recent_exports_changed", 1).await;
+    assert_eq!(exports[0]["format"], "pdf");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_project_path_stays_in_project() {
+    let harness = Harness::open("basic");
+    let project_manager = harness.project_manager();
+    let mut runner = TestRunner::new(Config {
+        cases: 128,
+        ..Config::default()
+    });
+
+    let paths = "(/?(\\.\\.|\\.|~|[a-z]{1,4}(\\.typ)?|\\PC{1,3})){0,6}";
+    runner
+        .run(&paths, |path| {
+            if let Ok((_, resolved)) = project_path(&harness.window, &project_manager, &path) {
+                prop_assert!(resolved.starts_with(&harness.root), "{:?} -> {:?}", path, resolved);
+            }
+            Ok(())
+        })
+        .unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_autosave_keeps_conflicting_buffers() {
+    let harness = Harness::open("basic");
+    let project = harness
+        .project_manager()
+        .get_project(&harness.window)
+        .unwrap();
+    let main = harness.root.join("main.typ");
+    let (stamp, _) = FileStamp::read(&main).unwrap();
+
+    // The editor never read the file, so it can't know the buffer is based on it.
+    project.dirty_buffers.record(main.clone(), "= Edited".to_string());
+    let event = autosave_project(&project);
+    assert_eq!(event.conflicts, [PathBuf::from("/main.typ")]);
+    assert!(project.dirty_buffers.contains(&main));
+
+    project.stamps.set(main.clone(), stamp);
+    fs::write(&main, "= Changed elsewhere").unwrap();
+    assert_eq!(autosave_project(&project).conflicts.len(), 1);
+    assert_eq!(main_content(&harness), "= Changed elsewhere");
+
+    let (stamp, _) = FileStamp::read(&main).unwrap();
+    project.stamps.set(main.clone(), stamp);
+    let event = autosave_project(&project);
+    assert_eq!(event.paths, [PathBuf::from("/main.typ")]);
+    assert_eq!(main_content(&harness), "= Edited");
+    assert!(!project.dirty_buffers.contains(&main));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_buffers_are_journaled_on_tick() {
+    let harness = Harness::open("basic");
+    let project = harness
+        .project_manager()
+        .get_project(&harness.window)
+        .unwrap();
+    let main = harness.root.join("main.typ");
+    let journaled = || project.dirty_buffers.recovery.entries().unwrap();
+
+    project.dirty_buffers.record(main.clone(), "= Draft".to_string());
+    project.dirty_buffers.record(main.clone(), "= Draft 2".to_string());
+    assert!(journaled().is_empty());
+    project.dirty_buffers.journal();
+    assert_eq!(journaled()[0].content, "= Draft 2");
+
+    project.dirty_buffers.clear(&main);
+    project.dirty_buffers.journal();
+    assert!(journaled().is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_replace_keeps_unsaved_changes() {
+    let harness = Harness::open("basic");
+    let project = harness
+        .project_manager()
+        .get_project(&harness.window)
+        .unwrap();
+    let main = harness.root.join("main.typ");
+    let saved = main_content(&harness);
+    let draft = saved.replace("#title[Fixture]", "#title[Fixture draft]");
+    project.dirty_buffers.record(main.clone(), draft.clone());
+
+    search_replace_apply(
+        harness.window.clone(),
+        harness.project_manager(),
+        "Fixture".to_string(),
+        "Thesis".to_string(),
+        SearchOptions::default(),
+        None,
+    )
+    .await
+    .unwrap();
+
+    // The draft is replaced in the buffer; the file and its stamp are left alone.
+    assert_eq!(
+        project.dirty_buffers.get(&main).unwrap(),
+        draft.replace("Fixture", "Thesis")
+    );
+    assert_eq!(main_content(&harness), saved);
+    assert!(project.stamps.get(&main).is_none());
+}
@@ -0,0 +1,146 @@
+//! End-to-end tests of the IPC commands, run against Tauri's mock runtime. Fixture
+//! projects live in `tests/fixtures` and are copied before each test opens them. The
+//! golden tests of the render pipeline use the documents in `tests/golden`.
+
+mod golden;
+mod ipc;
+
+use crate::compiler::Compiler;
+use crate::export::ExportJobs;
+use crate::menu::{FocusedWindow, MenuState};
+use crate::project::{Project, ProjectManager};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::test::{mock_builder, mock_context, noop_assets, MockRuntime};
+use tauri::{App, Listener, Manager, State, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+use walkdir::WalkDir;
+
+/// Events recorded for every harness.
+const RECORDED_EVENTS: [&str; 6] = [
+    "project_changed",
+    "typst_compile",
+    "project_diagnostics",
+    "preview_changes",
+    "recent_exports_changed",
+    "fs_changed",
+];
+
+/// How long `Harness::wait_for` waits, which includes loading the fonts for a compile.
+const EVENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A fixture project opened in the window of a mock app, with the events emitted to the
+/// window recorded.
+pub struct Harness {
+    pub app: App<MockRuntime>,
+    pub window: WebviewWindow<MockRuntime>,
+    /// The copy of the fixture, removed once the harness is dropped.
+    pub root: PathBuf,
+    events: Arc<Mutex<Vec<(String, Value)>>>,
+}
+
+impl Harness {
+    /// Opens a copy of the fixture project `name`. Needs a multi-threaded Tokio runtime,
+    /// which the compiler runs on.
+    pub fn open(name: &str) -> Self {
+        let root = copy_fixture(name);
+        let project_manager = Arc::new(ProjectManager::<MockRuntime>::new());
+        let app = mock_builder()
+            .manage(project_manager.clone())
+            .manage(Arc::new(ExportJobs::new()))
+            .manage(MenuState::default())
+            .manage(FocusedWindow::default())
+            .build(mock_context(noop_assets()))
+            .expect("failed to build the mock app");
+        app.manage(Arc::new(Compiler::new(
+            project_manager.clone(),
+            app.handle().clone(),
+        )));
+        let window = WebviewWindowBuilder::new(&app, "main", WebviewUrl::default())
+            .build()
+            .expect("failed to open the mock window");
+
+        let harness = Self {
+            app,
+            window,
+            root,
+            events: Default::default(),
+        };
+        for event in RECORDED_EVENTS {
+            let events = harness.events.clone();
+            harness.window.listen(event, move |e| {
+                let payload = serde_json::from_str(e.payload()).unwrap_or(Value::Null);
+                events.lock().unwrap().push((event.to_string(), payload));
+            });
+        }
+
+        let project = Project::load_from_path(harness.root.clone(), None);
+        project_manager.set_project(&harness.window, Some(Arc::new(project)));
+        harness
+    }
+
+    /// Managed state, to pass to commands.
+    pub fn state<T: Send + Sync + 'static>(&self) -> State<'_, T> {
+        self.app.state::<T>()
+    }
+
+    pub fn project_manager(&self) -> State<'_, Arc<ProjectManager<MockRuntime>>> {
+        self.state()
+    }
+
+    /// The payloads of `event` emitted to the window so far, oldest first.
+    pub fn events(&self, event: &str) -> Vec<Value> {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(name, _)| name == event)
+            .map(|(_, payload)| payload.clone())
+            .collect()
+    }
+
+    /// Waits until `event` was emitted `count` times and returns the last payload.
+    pub async fn wait_for(&self, event: &str, count: usize) -> Value {
+        let started = std::time::Instant::now();
+        while started.elapsed() < EVENT_TIMEOUT {
+            if let Some(payload) = self.events(event).get(count - 1) {
+                return payload.clone();
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("{} was not emitted {} times within {:?}", event, count, EVENT_TIMEOUT);
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+/// Copies `tests/fixtures/<name>` to a directory of its own, so tests can change it.
+fn copy_fixture(name: &str) -> PathBuf {
+    static COPIES: AtomicUsize = AtomicUsize::new(0);
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    let root = std::env::temp_dir().join(format!(
+        "typstudio-fixture-{}-{}-{}",
+        name,
+        std::process::id(),
+        COPIES.fetch_add(1, Ordering::Relaxed)
+    ));
+    for entry in WalkDir::new(&fixture) {
+        let entry = entry.expect("unreadable fixture");
+        let target = root.join(entry.path().strip_prefix(&fixture).unwrap());
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target).unwrap();
+        } else {
+            fs::copy(entry.path(), &target).unwrap();
+        }
+    }
+    root.canonicalize().unwrap()
+}
@@ -0,0 +1,133 @@
+use crate::appdata::add_recent_project;
+use crate::menu::recent_projects_changed;
+use crate::project::{Project, ProjectManager};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, Runtime, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+
+/// Windows sharing this identifier are grouped as tabs on macOS, which also adds the
+/// native tab items, such as Merge All Windows, to the Window menu.
+pub const TABBING_IDENTIFIER: &str = "typstudio";
+
+static NEXT_WINDOW: AtomicU64 = AtomicU64::new(1);
+
+/// Opens a window set up like the main one, showing the project at `path` if given.
+/// With `tab`, it joins the focused window as a tab where the platform supports it.
+pub fn open_project_window<R: Runtime>(
+    app: &AppHandle<R>,
+    path: Option<PathBuf>,
+    tab: bool,
+) -> tauri::Result<WebviewWindow<R>> {
+    let label = loop {
+        let label = format!("window-{}", NEXT_WINDOW.fetch_add(1, Ordering::Relaxed));
+        if app.get_webview_window(&label).is_none() {
+            break label;
+        }
+    };
+
+    let builder = WebviewWindowBuilder::new(app, &label, WebviewUrl::default())
+        .title("Typstudio")
+        .inner_size(1200.0, 800.0)
+        .min_inner_size(800.0, 600.0)
+        .resizable(true);
+    // A window of its own gets a unique identifier so macOS never merges it into a tab.
+    #[cfg(target_os = "macos")]
+    let builder = builder
+        .tabbing_identifier(if tab { TABBING_IDENTIFIER } else { label.as_str() })
+        .title_bar_style(tauri::TitleBarStyle::Overlay)
+        .hidden_title(true)
+        .transparent(true);
+    let window = builder.build()?;
+
+    #[cfg(target_os = "macos")]
+    {
+        use window_vibrancy::{apply_vibrancy, NSVisualEffectMaterial};
+        if let Err(e) = apply_vibrancy(&window, NSVisualEffectMaterial::Sidebar, None, None) {
+            log::warn!("Failed to apply vibrancy: {}", e);
+        }
+    }
+
+    if let Some(path) = path {
+        // Loading searches fonts, so it happens off the main thread. The window picks
+        // the project up from `project_changed` or, if that came first, `project_current`.
+        let window = window.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            let path = std::fs::canonicalize(&path).unwrap_or(path);
+            let project = Arc::new(Project::open(path.clone(), None));
+            window
+                .state::<Arc<ProjectManager<R>>>()
+                .set_project(&window, Some(project));
+            match add_recent_project(&path) {
+                Ok(projects) => recent_projects_changed(&window, &projects),
+                Err(e) => log::warn!("Failed to update recent projects: {}", e),
+            }
+        });
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    let _ = tab;
+
+    Ok(window)
+}
+
+/// The window already showing the project at `path`, or the lone file at `path`, if any.
+pub fn project_window<R: Runtime>(app: &AppHandle<R>, path: &Path) -> Option<WebviewWindow<R>> {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    app.state::<Arc<ProjectManager<R>>>()
+        .windows()
+        .into_iter()
+        .find(|(_, project)| match &project.single_file {
+            Some(file) => *file == path,
+            None => project.root == path,
+        })
+        .map(|(window, _)| window)
+}
+
+/// Opens a project folder or a lone `.typ` file handed to the app by the system, eg.
+/// through a file association. A window already showing it is focused; otherwise the
+/// main window takes it if it has no project yet, and a new window if it does. Returns
+/// the window the project is shown in.
+pub fn open_path<R: Runtime>(app: &AppHandle<R>, path: PathBuf) -> Option<WebviewWindow<R>> {
+    // Relative and symlinked paths are recorded like the paths of other windows.
+    let path = std::fs::canonicalize(&path).unwrap_or(path);
+    if let Some(window) = project_window(app, &path) {
+        let _ = window.set_focus();
+        return Some(window);
+    }
+    let project_manager = app.state::<Arc<ProjectManager<R>>>().inner().clone();
+    match app.get_webview_window("main") {
+        Some(window) if project_manager.get_project(&window).is_none() => {
+            let main = window.clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                let project = Arc::new(Project::open(path.clone(), None));
+                project_manager.set_project(&window, Some(project));
+                match add_recent_project(&path) {
+                    Ok(projects) => recent_projects_changed(&window, &projects),
+                    Err(e) => log::warn!("Failed to update recent projects: {}", e),
+                }
+            });
+            Some(main)
+        }
+        _ => match open_project_window(app, Some(path), false) {
+            Ok(window) => Some(window),
+            Err(e) => {
+                log::error!("Failed to open window: {}", e);
+                None
+            }
+        },
+    }
+}
+
+/// The first `.typ` file or folder among the app's launch arguments, as passed by file
+/// associations and "Open With" on Windows and Linux.
+pub fn launch_path() -> Option<PathBuf> {
+    path_argument(std::env::args_os().skip(1).map(PathBuf::from))
+}
+
+/// The first `.typ` file or folder among `args`.
+pub fn path_argument(mut args: impl Iterator<Item = PathBuf>) -> Option<PathBuf> {
+    args.find(|path| {
+        path.is_dir() || (path.is_file() && path.extension().is_some_and(|ext| ext == "typ"))
+    })
+}
@@ -0,0 +1,5 @@
+#import "template.typ": title
+
+#title[Fixture]
+
+This project is opened by the IPC tests.
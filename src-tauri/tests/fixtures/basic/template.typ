@@ -0,0 +1 @@
+#let title(body) = align(center, text(size: 18pt, weight: "bold", body))
@@ -0,0 +1,19 @@
+#set page(width: 12cm, height: auto, margin: 1cm)
+#set text(font: "Libertinus Serif", size: 10pt)
+#show raw: set text(font: "DejaVu Sans Mono")
+
+```rust
+fn main() {
+    let words = ["golden", "tests"];
+    for word in words.iter() {
+        println!("{word}");
+    }
+}
+```
+
+```typ
+#let greet(name) = [Hello, *#name*!]
+#greet("world")
+```
+
+Inline `raw` and a #raw("longer raw call", lang: "py") in the text.
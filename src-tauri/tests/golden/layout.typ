@@ -0,0 +1,29 @@
+#set page(paper: "a6", margin: 1cm, numbering: "1")
+#set text(font: "Libertinus Serif", size: 10pt)
+
+#columns(2)[
+  #for i in range(1, 13) [
+    Paragraph #i fills the columns and flows onto the next page. \
+  ]
+]
+
+#pagebreak()
+
+#table(
+  columns: (1fr, auto, auto),
+  align: (left, center, right),
+  table.header[*Item*][*Count*][*Price*],
+  [Apples], [3], [1.20],
+  [Pears], [12], [4.80],
+  table.cell(colspan: 2)[*Total*], [6.00],
+)
+
+#grid(
+  columns: 3,
+  gutter: 4pt,
+  rect(width: 100%, height: 1cm, fill: red.lighten(60%)),
+  circle(radius: 0.5cm, stroke: 2pt + blue),
+  polygon.regular(vertices: 6, size: 1cm, fill: gradient.linear(green, yellow)),
+)
+
+#place(bottom + right, box(stroke: (dash: "dashed"), inset: 4pt)[placed])
@@ -0,0 +1,13 @@
+#set page(width: 12cm, height: auto, margin: 1cm)
+#set text(font: "New Computer Modern", size: 11pt)
+#set math.equation(numbering: "(1)")
+
+The roots of $a x^2 + b x + c = 0$ are
+$ x_(1,2) = (-b plus.minus sqrt(b^2 - 4 a c)) / (2 a) $
+
+A sum, an integral and a matrix:
+$ sum_(k=1)^n k = (n (n + 1)) / 2 quad integral_0^infinity e^(-x^2) dif x = sqrt(pi) / 2 $
+$ mat(1, 2; 3, 4) vec(x, y) = vec(5, 6) $
+
+Cases and attachments: $f(x) = cases(x "if" x >= 0, -x "otherwise")$,
+$limits(lim)_(n -> infinity) (1 + 1/n)^n = e$.
@@ -0,0 +1,22 @@
+#set page(width: 12cm, height: auto, margin: 1cm)
+#set text(font: "Libertinus Serif", size: 11pt)
+#set par(justify: true)
+#set heading(numbering: "1.1")
+
+= Introduction
+Typstudio renders *strong*, _emphasized_ and `inline code` text, with
+#smallcaps[small capitals], #underline[underlines] and #strike[strikes].
+Long paragraphs are justified and hyphenated, which exercises the line
+breaker on every run of the suite.
+
+== Lists
+- A bullet list
+  - with a nested item
+- and another item
+
++ A numbered list
++ continues here
+
+/ Term: and its description.
+
+#quote(block: true)[A block quote, set apart from the text.]
@@ -0,0 +1,64 @@
+import type { CancellationToken, editor, IRange, Range } from "monaco-editor";
+import * as monaco from "monaco-editor";
+
+import { createFile, type TypstSourceDiagnostic } from "../ipc";
+
+const CREATE_FILE_COMMAND = "typst.createFile";
+
+// The latest diagnostics of each model, whose explanations carry the quick fixes.
+const diagnosticsByModel = new Map<string, TypstSourceDiagnostic[]>();
+
+export const setModelDiagnostics = (model: editor.ITextModel, diagnostics: TypstSourceDiagnostic[]) => {
+  diagnosticsByModel.set(model.uri.toString(), diagnostics);
+};
+
+monaco.editor.registerCommand(CREATE_FILE_COMMAND, (_accessor, path: string) => createFile(`/${path}`));
+
+/** Offers the quick fixes of the explained diagnostics under the cursor. */
+export class TypstCodeActionProvider implements monaco.languages.CodeActionProvider {
+  provideCodeActions(
+    model: editor.ITextModel,
+    range: Range,
+    _context: monaco.languages.CodeActionContext,
+    _token: CancellationToken
+  ): monaco.languages.CodeActionList {
+    const actions: monaco.languages.CodeAction[] = [];
+    for (const diagnostic of diagnosticsByModel.get(model.uri.toString()) ?? []) {
+      const start = model.getPositionAt(diagnostic.range.start);
+      const end = model.getPositionAt(diagnostic.range.end);
+      const fixRange: IRange = {
+        startLineNumber: start.lineNumber,
+        startColumn: start.column,
+        endLineNumber: end.lineNumber,
+        endColumn: end.column,
+      };
+      if (!monaco.Range.areIntersectingOrTouching(fixRange, range)) continue;
+
+      for (const fix of diagnostic.explanation?.fixes ?? []) {
+        if (fix.kind === "replace") {
+          actions.push({
+            title: `Replace with \`${fix.text}\``,
+            kind: "quickfix",
+            isPreferred: true,
+            edit: {
+              edits: [
+                {
+                  resource: model.uri,
+                  textEdit: { range: fixRange, text: fix.text },
+                  versionId: model.getVersionId(),
+                },
+              ],
+            },
+          });
+        } else {
+          actions.push({
+            title: `Create \`${fix.path}\``,
+            kind: "quickfix",
+            command: { id: CREATE_FILE_COMMAND, title: "Create file", arguments: [fix.path] },
+          });
+        }
+      }
+    }
+    return { actions, dispose: () => {} };
+  }
+}
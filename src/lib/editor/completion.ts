@@ -58,6 +58,9 @@ export class TypstCompletionProvider implements languages.CompletionItemProvider
           case TypstCompletionKind.Type:
             kind = languages.CompletionItemKind.Class;
             break;
+          case TypstCompletionKind.Snippet:
+            kind = languages.CompletionItemKind.Snippet;
+            break;
         }
 
         let count = 0;
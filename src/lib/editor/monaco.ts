@@ -8,6 +8,7 @@ import bibtex from "./lang/bibtex.json";
 import typstConfig from "./lang/typst-config.json";
 import typstTm from "./lang/typst-tm.json";
 
+import { TypstCodeActionProvider } from "$lib/editor/code-actions";
 import { TypstCompletionProvider } from "$lib/editor/completion";
 
 type IMonarchLanguage = monaco.languages.IMonarchLanguage;
@@ -128,6 +129,7 @@ export const initMonaco = (async () => {
   monaco.languages.setMonarchTokensProvider("bibtex", bibtex as IMonarchLanguage);
 
   monaco.languages.registerCompletionItemProvider("typst", new TypstCompletionProvider());
+  monaco.languages.registerCodeActionProvider("typst", new TypstCodeActionProvider());
 
   monaco.editor.defineTheme("notion-light", notionLightTheme);
   monaco.editor.setTheme("notion-light");
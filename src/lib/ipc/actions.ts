@@ -0,0 +1,27 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export type ActionEffect =
+  | { kind: "wrap"; before: string; after: string }
+  | { kind: "insert"; text: string; cursor: number | null }
+  | { kind: "heading_level"; delta: number };
+
+export interface DocumentAction {
+  id: string;
+  title: string;
+  group: string;
+  keybinding: string | null;
+  effect: ActionEffect;
+}
+
+export interface ActionEdit {
+  start: number;
+  end: number;
+  text: string;
+  selection: [number, number];
+}
+
+export const listDocumentActions = (): Promise<DocumentAction[]> =>
+  invoke<DocumentAction[]>("document_actions_list");
+
+export const applyDocumentAction = (id: string, text: string, start: number, end: number): Promise<ActionEdit> =>
+  invoke<ActionEdit>("document_action_apply", { id, text, start, end });
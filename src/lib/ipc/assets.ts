@@ -0,0 +1,50 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export interface AssetsMirrorResponse {
+  path: string;
+}
+
+export const mirrorUrl = (url: string): Promise<AssetsMirrorResponse> =>
+  invoke<AssetsMirrorResponse>("assets_mirror_url", { url });
+
+export interface ImportedAsset {
+  path: string;
+  snippet: string;
+}
+
+export const importAsset = (
+  externalPath: string,
+  targetDir?: string,
+  convert = true
+): Promise<ImportedAsset> =>
+  invoke<ImportedAsset>("project_import_asset", { externalPath, targetDir, convert });
+
+export const importMarkdownFile = (
+  externalPath: string,
+  targetDir?: string
+): Promise<ImportedAsset> => invoke<ImportedAsset>("import_markdown_file", { externalPath, targetDir });
+
+export const importDocx = (path: string, targetDir?: string): Promise<ImportedAsset> =>
+  invoke<ImportedAsset>("import_docx", { path, targetDir });
+
+export interface ConvertedFile {
+  source: string;
+  output: string;
+  unsupported: string[];
+}
+
+export interface LatexImportReport {
+  main: string | null;
+  converted: ConvertedFile[];
+  copied: string[];
+  skipped: string[];
+  conflicts: string[];
+}
+
+export const importLatexProject = (dir: string, targetDir?: string): Promise<LatexImportReport> =>
+  invoke<LatexImportReport>("import_latex_project", { dir, targetDir });
+
+export interface FileDropImportEvent {
+  assets: ImportedAsset[];
+  snippet: string;
+}
@@ -0,0 +1,14 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** Payload of the `autosaved` event. Paths are project-relative. */
+export interface AutosavedEvent {
+  paths: string[];
+  conflicts: string[];
+}
+
+export const markDirty = (path: string, content: string): Promise<void> =>
+  invoke("autosave_mark_dirty", { path, content });
+
+export const markClean = (path: string): Promise<void> => invoke("autosave_mark_clean", { path });
+
+export const autosaveNow = (): Promise<AutosavedEvent> => invoke<AutosavedEvent>("autosave_now");
@@ -1,8 +1,31 @@
 import { invoke } from "@tauri-apps/api/core";
+import type { Rect } from "./document";
 
 export interface ClipboardPasteResponse {
   path: string;
+  snippet: string;
 }
 
-export const paste = async (): Promise<ClipboardPasteResponse> =>
-  invoke<ClipboardPasteResponse>("clipboard_paste");
+export interface ClipboardPasteOptions {
+  dir?: string;
+  naming?: "date" | "hash";
+  recompress?: boolean;
+}
+
+export const paste = async (options?: ClipboardPasteOptions): Promise<ClipboardPasteResponse> =>
+  invoke<ClipboardPasteResponse>("clipboard_paste", { options });
+
+export const cleanPastedText = (text: string): Promise<string> =>
+  invoke<string>("clean_pasted_text", { text });
+
+export const convertLatexSnippet = (text: string): Promise<string> =>
+  invoke<string>("convert_latex_snippet", { text });
+
+export const convertMarkdownSnippet = (text: string): Promise<string> =>
+  invoke<string>("convert_markdown_snippet", { text });
+
+export const pasteTable = (): Promise<string | null> =>
+  invoke<string | null>("clipboard_paste_table");
+
+export const copyPageImage = (page: number, scale = 2, rect?: Rect): Promise<void> =>
+  invoke("copy_page_image", { page, scale, rect });
@@ -0,0 +1,39 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export interface CollabSessionInfo {
+  /** The link peers join with, eg. `ws://192.168.1.20:4321/3f2a…`. */
+  url: string;
+  hosting: boolean;
+}
+
+/** A replacement of a range of a shared file, in UTF-16 code units like the editor's. */
+export interface CollabEdit {
+  start: number;
+  end: number;
+  text: string;
+}
+
+/** Emitted as `collab_remote_change` when a peer changed a file. */
+export interface CollabRemoteChangeEvent {
+  path: string;
+  content: string;
+}
+
+export const hostCollabSession = (port?: number): Promise<CollabSessionInfo> =>
+  invoke<CollabSessionInfo>("collab_host", { port });
+
+export const joinCollabSession = (url: string): Promise<CollabSessionInfo> =>
+  invoke<CollabSessionInfo>("collab_join", { url });
+
+export const leaveCollabSession = (): Promise<void> => invoke<void>("collab_leave");
+
+export const getCollabSession = (): Promise<CollabSessionInfo | null> =>
+  invoke<CollabSessionInfo | null>("collab_session");
+
+/** Shares an opened file; returns its content in the session, which may be a peer's. */
+export const collabOpenFile = (path: string, content: string): Promise<string> =>
+  invoke<string>("collab_open_file", { path, content });
+
+/** Applies and sends edits made in the editor; returns the new content. */
+export const collabApply = (path: string, edits: CollabEdit[]): Promise<string> =>
+  invoke<string>("collab_apply", { path, edits });
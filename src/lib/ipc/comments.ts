@@ -0,0 +1,35 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** Feedback on a range of a file, stored in `.typstudio/comments.json`. */
+export interface ReviewComment {
+  id: number;
+  /** The project path of the file, eg. `/chapters/one.typ`. */
+  path: string;
+  /** The character range of the commented text. */
+  range: { start: number; end: number };
+  /** The start of the commented text. */
+  excerpt: string;
+  hash: string;
+  author: string;
+  body: string;
+  /** Seconds since the Unix epoch. */
+  created: number;
+  resolved: boolean;
+  /** Whether the commented text was changed or deleted. */
+  orphaned: boolean;
+}
+
+/** The comments of the project, or of `path`, moved to where their text is now. */
+export const listComments = (path?: string): Promise<ReviewComment[]> =>
+  invoke<ReviewComment[]>("comments_list", { path });
+
+export const addComment = (
+  path: string,
+  range: { start: number; end: number },
+  author: string,
+  body: string
+): Promise<ReviewComment> =>
+  invoke<ReviewComment>("comments_add", { path, range, author, body });
+
+export const resolveComment = (id: number, resolved = true): Promise<ReviewComment> =>
+  invoke<ReviewComment>("comments_resolve", { id, resolved });
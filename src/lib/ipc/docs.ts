@@ -0,0 +1,53 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export type DocKind = "function" | "type" | "module" | "constant";
+
+export interface DocParam {
+  name: string;
+  types: string[];
+  docs: string;
+  default: string | null;
+  positional: boolean;
+  named: boolean;
+  required: boolean;
+  variadic: boolean;
+}
+
+export interface DocEntry {
+  path: string;
+  kind: DocKind;
+  signature: string | null;
+  summary: string;
+  docs: string;
+  params: DocParam[];
+  returns: string[];
+  examples: string[];
+}
+
+export interface DocSearchHit {
+  path: string;
+  kind: DocKind;
+  summary: string;
+}
+
+export const searchDocs = (query: string): Promise<DocSearchHit[]> =>
+  invoke<DocSearchHit[]>("docs_search", { query });
+
+export const getDoc = (symbol: string): Promise<DocEntry | null> =>
+  invoke<DocEntry | null>("docs_get", { symbol });
+
+export const renderDocExample = (symbol: string, index: number): Promise<string> =>
+  invoke<string>("docs_render_example", { symbol, index });
+
+export interface SymbolEntry {
+  name: string;
+  module: "sym" | "emoji";
+  value: string;
+  codepoints: string[];
+  markup: string;
+  math: string;
+  shorthand: string | null;
+}
+
+export const listSymbols = (query = ""): Promise<SymbolEntry[]> =>
+  invoke<SymbolEntry[]>("typst_list_symbols", { query });
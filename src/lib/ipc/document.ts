@@ -0,0 +1,149 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export interface Rect {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+export interface TextHit {
+  page: number;
+  rects: Rect[];
+}
+
+export const findDocumentText = (query: string, caseSensitive = false): Promise<TextHit[]> =>
+  invoke<TextHit[]>("document_find_text", { query, caseSensitive });
+
+export interface SubmissionProfile {
+  max_pages?: number | null;
+  paper?: string | null;
+  min_font_size?: number | null;
+  anonymous_names?: string[];
+  embedded_fonts?: boolean;
+}
+
+export interface SubmissionCheck {
+  rule: string;
+  passed: boolean;
+  message: string;
+  pages: number[];
+}
+
+export const listSubmissionProfiles = (): Promise<Record<string, SubmissionProfile>> =>
+  invoke<Record<string, SubmissionProfile>>("submission_profiles_list");
+
+export const saveSubmissionProfile = (name: string, profile: SubmissionProfile | null): Promise<void> =>
+  invoke("submission_profile_save", { name, profile });
+
+export const checkSubmission = (profile: string): Promise<SubmissionCheck[]> =>
+  invoke<SubmissionCheck[]>("submission_check", { profile });
+
+export interface FontFace {
+  family: string;
+  weight: number;
+  style: "normal" | "italic" | "oblique";
+}
+
+export interface FontUsage extends FontFace {
+  /** The file the face was loaded from, or null for the built-in fonts. */
+  path: string | null;
+  characters: number;
+  glyphs: number;
+  /** Inclusive code point ranges. */
+  ranges: [number, number][];
+  /** The ranges for font subsetters, eg. `U+0020-007E,U+00E9`. */
+  unicodes: string;
+  pages: number[];
+}
+
+export interface ProjectFontFile extends FontFace {
+  path: string;
+  used: boolean;
+}
+
+export interface FontReport {
+  fonts: FontUsage[];
+  project_fonts: ProjectFontFile[];
+}
+
+export const fontReport = (): Promise<FontReport> => invoke<FontReport>("document_font_report");
+
+export interface BudgetOverrun {
+  section: string | null;
+  pages: number;
+  limit: number;
+}
+
+export interface PageBudgetEvent {
+  pages: number;
+  overruns: BudgetOverrun[];
+}
+
+export type BookmarkAnchor = { label: string } | { heading: string };
+
+export interface ResolvedBookmark {
+  name: string;
+  anchor: BookmarkAnchor;
+  position: { page: number; x: number; y: number } | null;
+}
+
+export interface PreviewBookmarksEvent {
+  bookmarks: ResolvedBookmark[];
+}
+
+export const listBookmarks = (): Promise<ResolvedBookmark[]> =>
+  invoke<ResolvedBookmark[]>("preview_bookmarks_list");
+
+export const addBookmark = (name: string, anchor: BookmarkAnchor): Promise<ResolvedBookmark[]> =>
+  invoke<ResolvedBookmark[]>("preview_bookmark_add", { name, anchor });
+
+export const removeBookmark = (name: string): Promise<ResolvedBookmark[]> =>
+  invoke<ResolvedBookmark[]>("preview_bookmark_remove", { name });
+
+export interface PageChanges {
+  page: number;
+  rects: Rect[];
+}
+
+export interface PreviewChangesEvent {
+  version: number;
+  pages: PageChanges[];
+}
+
+export const exportStatistics = (dest: string, from?: string, to?: string): Promise<string> =>
+  invoke<string>("stats_export", { dest, from: from ?? null, to: to ?? null });
+
+export type PageCompareStatus = "unchanged" | "changed" | "added" | "removed";
+
+export interface PageComparison {
+  page: number;
+  status: PageCompareStatus;
+  /** The changed regions of the newer page, from its layout, in points. */
+  rects: Rect[];
+  /** The share of pixels that differ, from 0 to 1. */
+  pixel_difference: number;
+  pixel_bounds: Rect | null;
+}
+
+/**
+ * Compiles the document at two revisions and compares their pages. A revision is
+ * `working` (the editor's buffers), `disk` (the saved files) or a git revision.
+ */
+export const comparePreview = (revA: string, revB = "working"): Promise<PageComparison[]> =>
+  invoke<PageComparison[]>("preview_compare", { revA, revB });
+
+export interface PreviewServerInfo {
+  /** The viewer page for browsers on the local network. */
+  url: string;
+}
+
+/** Serves the preview to browsers on the local network, eg. a tablet. */
+/** Serves the preview on this machine, or to other devices on the local network with `lan`. */
+export const startPreviewServer = (port?: number, lan = false): Promise<PreviewServerInfo> =>
+  invoke<PreviewServerInfo>("preview_server_start", { port, lan });
+
+export const stopPreviewServer = (): Promise<void> => invoke<void>("preview_server_stop");
+
+export const getPreviewServer = (): Promise<PreviewServerInfo | null> =>
+  invoke<PreviewServerInfo | null>("preview_server_info");
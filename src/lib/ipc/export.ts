@@ -0,0 +1,129 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export type ExportFormat = "pdf" | "svg" | "png";
+
+export interface ExportProgressEvent {
+  job_id: number;
+  index: number;
+  total: number;
+  name: string;
+  output: string | null;
+  error: string | null;
+}
+
+export interface ExportFinishedEvent {
+  job_id: number;
+  succeeded: number;
+  failed: number;
+  cancelled: boolean;
+}
+
+export const listVariants = (): Promise<Record<string, string>[]> =>
+  invoke<Record<string, string>[]>("export_list_variants");
+
+export const exportVariants = (
+  dir: string,
+  format: ExportFormat,
+  toggles?: Record<string, boolean>,
+  seed?: number
+): Promise<number> => invoke<number>("export_variants", { dir, format, toggles, seed });
+
+export const exportSeeded = (
+  dir: string,
+  format: ExportFormat,
+  count: number,
+  baseSeed?: number,
+  toggles?: Record<string, boolean>
+): Promise<number> => invoke<number>("export_seeded", { dir, format, count, baseSeed, toggles });
+
+export const exportMailMerge = (
+  data: string,
+  dir: string,
+  format: ExportFormat,
+  mapping?: Record<string, string>,
+  name?: string,
+  toggles?: Record<string, boolean>
+): Promise<number> =>
+  invoke<number>("export_mail_merge", { data, dir, format, mapping, name, toggles });
+
+/** Payload of the `auto_export` event, sent when an export after a save starts and ends. */
+export interface AutoExportEvent {
+  status: "running" | "exported" | "failed";
+  outputs: string[];
+  error: string | null;
+}
+
+/** Turns exporting after every save on or off. Resolves to the files it writes. */
+export const toggleAutoExport = (enabled: boolean): Promise<string[]> =>
+  invoke<string[]>("export_auto_toggle", { enabled });
+
+/** Payload of the `export_hook_output` event, one per line a hook prints. */
+export interface ExportHookOutputEvent {
+  run_id: number;
+  command: string;
+  stream: "stdout" | "stderr";
+  line: string;
+}
+
+/** Payload of the `export_hook_finished` event. `error` is set if the hook didn't start. */
+export interface ExportHookFinishedEvent {
+  run_id: number;
+  command: string;
+  export: string;
+  success: boolean;
+  code: number | null;
+  error: string | null;
+}
+
+/** Approves the current commands of the project's export hooks, or revokes them. */
+export const approveExportHooks = (approved: boolean): Promise<void> =>
+  invoke("export_hooks_approve", { approved });
+
+export const cancelExportJob = (jobId: number): Promise<void> =>
+  invoke("export_job_cancel", { jobId });
+
+export interface Violation {
+  pattern: string;
+  page: number;
+  text: string;
+}
+
+export interface AnonymousExport {
+  path: string | null;
+  violations: Violation[];
+}
+
+export const exportAnonymous = (path: string, force = false): Promise<AnonymousExport> =>
+  invoke<AnonymousExport>("export_anonymous", { path, force });
+
+export interface ExportDiagnostic {
+  severity: "error" | "warning";
+  message: string;
+  hints: string[];
+  path: string | null;
+}
+
+export interface HtmlExport {
+  path: string | null;
+  diagnostics: ExportDiagnostic[];
+}
+
+export const exportHtml = (
+  path: string,
+  inlineAssets = true,
+  toggles?: Record<string, boolean>
+): Promise<HtmlExport> => invoke<HtmlExport>("export_html", { path, inlineAssets, toggles });
+
+export type TextFormat = "plain" | "markdown";
+
+export const exportText = (
+  format: TextFormat,
+  path?: string,
+  toggles?: Record<string, boolean>
+): Promise<string> => invoke<string>("export_text", { format, path, toggles });
+
+export const exportEpub = (path: string, toggles?: Record<string, boolean>): Promise<HtmlExport> =>
+  invoke<HtmlExport>("export_epub", { path, toggles });
+
+export const printDocument = (pages?: string, toggles?: Record<string, boolean>): Promise<void> =>
+  invoke("print_document", { pages, toggles });
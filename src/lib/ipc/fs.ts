@@ -13,6 +13,18 @@ export interface FSRefreshEvent {
   path: string;
 }
 
+export type FSChangeKind = "created" | "modified" | "deleted" | "renamed";
+
+export interface FSChange {
+  kind: FSChangeKind;
+  path: string;
+  from: string | null;
+}
+
+export interface FSChangedEvent {
+  changes: FSChange[];
+}
+
 export interface ProjectChangeEvent {
   project: Project | null;
 }
@@ -25,21 +37,80 @@ export const readFileText = (path: string): Promise<string> =>
 
 export const createFile = (path: string): Promise<never> => invoke("fs_create_file", { path });
 
-export const writeFileText = (path: string, content: string): Promise<string> =>
-  invoke("fs_write_file_text", { path, content });
+export interface FileConflict {
+  kind: "conflict";
+  path: string;
+  disk: string;
+  local: string;
+}
+
+export const isFileConflict = (e: unknown): e is FileConflict =>
+  typeof e === "object" && e !== null && (e as FileConflict).kind === "conflict";
+
+export type FileAccessReason = "permission_denied" | "read_only" | "locked" | "disk_full" | "path_too_long";
+
+export type RecoveryAction =
+  | "retry"
+  | "save_as"
+  | "reveal_in_file_manager"
+  | "check_permissions"
+  | "close_other_applications"
+  | "free_disk_space"
+  | "shorten_path";
+
+export interface FileAccessError {
+  kind: "file_access";
+  reason: FileAccessReason;
+  path: string;
+  message: string;
+  recovery: RecoveryAction[];
+}
+
+export const isFileAccessError = (e: unknown): e is FileAccessError =>
+  typeof e === "object" && e !== null && (e as FileAccessError).kind === "file_access";
+
+export const writeFileText = (path: string, content: string, force?: boolean): Promise<string> =>
+  invoke("fs_write_file_text", { path, content, force });
 
 export const listDir = (path: string): Promise<FileItem[]> =>
   invoke<FileItem[]>("fs_list_dir", { path });
 
-export const deleteFile = (path: string): Promise<void> =>
-  invoke("fs_delete_file", { path });
+export const deleteFile = (path: string, permanent = false): Promise<void> =>
+  invoke("fs_delete_file", { path, permanent });
+
+// Restores the last trashed file; resolves to its path, or null if there was nothing to restore.
+export const undoDelete = (): Promise<string | null> =>
+  invoke<string | null>("fs_undo_delete");
 
 export const renameFile = (oldPath: string, newPath: string): Promise<void> =>
   invoke("fs_rename_file", { oldPath, newPath });
 
+export const copyFile = (src: string, dest: string): Promise<void> =>
+  invoke("fs_copy_file", { src, dest });
+
+export const duplicateFile = (path: string): Promise<string> =>
+  invoke<string>("fs_duplicate", { path });
+
 export const revealPath = (path: string): Promise<void> =>
   invoke("fs_reveal_path", { path });
 
 
 export const searchFiles = (): Promise<string[]> =>
   invoke<string[]>("fs_search_files");
+
+export interface FileTreeNode {
+  name: string;
+  type: FileType;
+  children: FileTreeNode[] | null;
+}
+
+export const fileTree = (): Promise<FileTreeNode[]> => invoke<FileTreeNode[]>("fs_tree");
+
+export interface QuickOpenResult {
+  path: string;
+  score: number;
+  positions: number[];
+}
+
+export const quickOpen = (query: string, limit: number): Promise<QuickOpenResult[]> =>
+  invoke<QuickOpenResult[]>("fs_quick_open", { query, limit });
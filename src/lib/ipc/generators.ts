@@ -0,0 +1,38 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export interface FigureGenerator {
+  output: string;
+  script: string;
+  command: string[];
+  inputs: string[];
+}
+
+export interface GeneratorStatus extends FigureGenerator {
+  stale: boolean;
+  /** Generators only run once the user approved their command. */
+  approved: boolean;
+}
+
+export interface GeneratorRun {
+  output: string;
+  success: boolean;
+  log: string;
+  /** The script was killed for running too long. */
+  timed_out: boolean;
+}
+
+export const listGenerators = (): Promise<GeneratorStatus[]> =>
+  invoke<GeneratorStatus[]>("generators_list");
+
+/** Approves the current commands of the project's generators, or revokes them. */
+export const approveGenerators = (approved: boolean): Promise<void> =>
+  invoke("generators_approve", { approved });
+
+export const runGenerator = (output: string): Promise<GeneratorRun> =>
+  invoke<GeneratorRun>("generators_run", { output });
+
+export const runStaleGenerators = (): Promise<GeneratorRun[]> =>
+  invoke<GeneratorRun[]>("generators_run_stale");
+
+export const watchGenerators = (enabled: boolean): Promise<void> =>
+  invoke("generators_watch", { enabled });
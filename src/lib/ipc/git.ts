@@ -8,3 +8,92 @@ export async function getOriginalFileContent(path: string): Promise<string> {
     return "";
   }
 }
+
+export type GitFileStatus =
+  | "modified"
+  | "added"
+  | "deleted"
+  | "renamed"
+  | "untracked"
+  | "ignored"
+  | "conflicted";
+
+export interface GitStatusEntry {
+  /** Relative to the project root; ignored folders end with `/`. */
+  path: string;
+  status: GitFileStatus;
+  /** Whether the change is in the index, ready to commit. */
+  staged: boolean;
+}
+
+export interface GitStatusEvent {
+  files: GitStatusEntry[];
+}
+
+/** The git status of the project's changed, untracked, ignored or conflicted files. */
+export const getGitStatus = (): Promise<GitStatusEntry[]> =>
+  invoke<GitStatusEntry[]>("git_status");
+
+export type GitHunkKind = "added" | "modified" | "deleted";
+
+export interface GitHunk {
+  kind: GitHunkKind;
+  /** 1-based, inclusive lines of the working copy. Deleted lines sit below `start`, 0 for the top. */
+  start: number;
+  end: number;
+  /** The lines of HEAD the hunk replaces or deletes. */
+  removed: number;
+}
+
+/** The changed lines of `path` against HEAD, of the unsaved `content` if given. */
+export const gitDiffFile = (path: string, content?: string): Promise<GitHunk[]> =>
+  invoke<GitHunk[]>("git_diff_file", { path, content });
+
+export interface GitCommit {
+  hash: string;
+  short_hash: string;
+  author: string;
+  email: string;
+  /** Seconds since the Unix epoch. */
+  date: number;
+  summary: string;
+  message: string;
+}
+
+/** The latest commits that changed `path`, or the whole project, newest first. */
+export const gitLog = (path?: string, limit?: number): Promise<GitCommit[]> =>
+  invoke<GitCommit[]>("git_log", { path, limit });
+
+/** The content of `path` at `rev`, eg. a commit hash, or null if the file didn't exist then. */
+export const gitShowFileAt = (path: string, rev: string): Promise<string | null> =>
+  invoke<string | null>("git_show_file_at", { path, rev });
+
+export interface GitBranch {
+  name: string;
+  /** Whether the branch is checked out. */
+  current: boolean;
+  /** The remote branch it tracks, eg. `origin/main`. */
+  upstream: string | null;
+  /** The summary of its latest commit. */
+  summary: string | null;
+}
+
+/** The local branches of the project's repository. */
+export const gitBranches = (): Promise<GitBranch[]> => invoke<GitBranch[]>("git_branches");
+
+/** Switches to `branch`; fails while files have unsaved or uncommitted changes. */
+export const gitCheckout = (branch: string): Promise<void> =>
+  invoke<void>("git_checkout", { branch });
+
+/** Creates the branch `name` at the current commit and switches to it. */
+export const gitCreateBranch = (name: string): Promise<void> =>
+  invoke<void>("git_create_branch", { name });
+
+/** Creates a repository in the project folder and commits its files. */
+export const gitInit = (): Promise<void> => invoke<void>("git_init");
+
+/** Whether the project commits every file the editor saves. */
+export const gitAutoCommit = (): Promise<boolean> => invoke<boolean>("git_auto_commit");
+
+export const gitSetAutoCommit = (enabled: boolean): Promise<void> =>
+  invoke<void>("git_set_auto_commit", { enabled });
@@ -0,0 +1,22 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** A saved version of a file in the local history. */
+export interface Snapshot {
+  id: string;
+  timestamp: number;
+  size: number;
+}
+
+/** Newest first. */
+export const listSnapshots = (path: string): Promise<Snapshot[]> =>
+  invoke<Snapshot[]>("history_list", { path });
+
+export const readSnapshot = (path: string, id: string): Promise<string> =>
+  invoke<string>("history_read", { path, id });
+
+/** A unified diff from the snapshot to the current file. */
+export const diffSnapshot = (path: string, id: string): Promise<string> =>
+  invoke<string>("history_diff", { path, id });
+
+export const restoreSnapshot = (path: string, id: string): Promise<void> =>
+  invoke<void>("history_restore", { path, id });
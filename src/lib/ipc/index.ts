@@ -1,3 +1,22 @@
 export * from "./fs";
 export * from "./typst";
 export * from "./git";
+export * from "./comments";
+export * from "./collab";
+export * from "./history";
+export * from "./assets";
+export * from "./workspace";
+export * from "./search";
+export * from "./settings";
+export * from "./export";
+export * from "./document";
+export * from "./generators";
+export * from "./docs";
+export * from "./palette";
+export * from "./actions";
+export * from "./snippets";
+export * from "./recent";
+export * from "./recovery";
+export * from "./window";
+export * from "./session";
+export * from "./autosave";
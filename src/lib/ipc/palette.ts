@@ -0,0 +1,14 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export interface PaletteCommand {
+  id: string;
+  title: string;
+  category: string;
+  enabled: boolean;
+  keybinding: string | null;
+}
+
+export const listCommands = (): Promise<PaletteCommand[]> => invoke<PaletteCommand[]>("commands_list");
+
+export const executeCommand = (id: string, args?: Record<string, unknown>): Promise<unknown> =>
+  invoke("commands_execute", { id, args });
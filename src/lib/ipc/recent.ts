@@ -0,0 +1,40 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** Payload item of the `recent_projects_changed` event. */
+export interface RecentProject {
+  path: string;
+  name: string;
+  last_opened: number;
+  pinned: boolean;
+}
+
+export const listRecentProjects = (): Promise<RecentProject[]> =>
+  invoke<RecentProject[]>("recent_projects_list");
+
+export const addRecentProject = (path: string): Promise<RecentProject[]> =>
+  invoke<RecentProject[]>("recent_projects_add", { path });
+
+export const removeRecentProject = (path: string): Promise<RecentProject[]> =>
+  invoke<RecentProject[]>("recent_projects_remove", { path });
+
+export const pinRecentProject = (path: string, pinned: boolean): Promise<RecentProject[]> =>
+  invoke<RecentProject[]>("recent_projects_pin", { path, pinned });
+
+/** Adds projects of an older list; those already listed are left as they are. */
+export const importRecentProjects = (
+  projects: { path: string; last_opened: number }[]
+): Promise<RecentProject[]> => invoke<RecentProject[]>("recent_projects_import", { projects });
+
+/** Payload item of the `recent_exports_changed` event, newest first. */
+export interface RecentExport {
+  path: string;
+  format: string;
+  exported_at: number;
+}
+
+export const listRecentExports = (): Promise<RecentExport[]> =>
+  invoke<RecentExport[]>("recent_exports_list");
+
+/** Opens a recent export with the system viewer, or shows it in the file manager. */
+export const openRecentExport = (path: string, reveal = false): Promise<void> =>
+  invoke<void>("recent_exports_open", { path, reveal });
@@ -0,0 +1,18 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** An unsaved buffer journaled before the app last quit or crashed. */
+export interface RecoverableBuffer {
+  path: string;
+  content: string;
+  journaled_at: number;
+  /** Unified diff from the file on disk to the buffer. */
+  diff: string;
+  /** Whether the file no longer exists on disk. */
+  missing: boolean;
+}
+
+export const recoverUnsavedChanges = (): Promise<RecoverableBuffer[]> =>
+  invoke<RecoverableBuffer[]>("recover_unsaved_changes");
+
+export const discardRecovered = (paths: string[]): Promise<void> =>
+  invoke<void>("recover_discard", { paths });
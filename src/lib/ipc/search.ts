@@ -0,0 +1,73 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export interface SearchOptions {
+  regex?: boolean;
+  case_sensitive?: boolean;
+  whole_word?: boolean;
+  include?: string[];
+  exclude?: string[];
+  max_results?: number;
+}
+
+export interface SearchMatch {
+  line: number;
+  column: number;
+  length: number;
+  range: { start: number; end: number };
+  context: string;
+  context_column: number;
+}
+
+export interface SearchResultEvent {
+  search_id: number;
+  path: string;
+  matches: SearchMatch[];
+}
+
+export interface SearchFinishedEvent {
+  search_id: number;
+  files: number;
+  matches: number;
+  truncated: boolean;
+  cancelled: boolean;
+}
+
+export const searchProject = (query: string, options: SearchOptions): Promise<number> =>
+  invoke<number>("search_project", { query, options });
+
+export const cancelSearch = (): Promise<void> => invoke("search_cancel");
+
+export interface Replacement {
+  line: number;
+  column: number;
+  range: { start: number; end: number };
+  original: string;
+  replacement: string;
+}
+
+export interface ReplacePreviewFile {
+  path: string;
+  replacements: Replacement[];
+}
+
+export const replacePreview = (
+  query: string,
+  replacement: string,
+  options: SearchOptions
+): Promise<ReplacePreviewFile[]> =>
+  invoke<ReplacePreviewFile[]>("search_replace_preview", { query, replacement, options });
+
+export interface ReplaceApplied {
+  /** Files written to disk, which open editors should reload. */
+  written: string[];
+  /** Files with unsaved changes, replaced in their buffer only. */
+  buffers: { path: string; content: string }[];
+}
+
+export const replaceApply = (
+  query: string,
+  replacement: string,
+  options: SearchOptions,
+  paths?: string[]
+): Promise<ReplaceApplied> =>
+  invoke<ReplaceApplied>("search_replace_apply", { query, replacement, options, paths });
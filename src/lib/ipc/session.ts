@@ -0,0 +1,18 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export interface OpenFile {
+  path: string;
+  cursor: number | null;
+  scroll_top: number | null;
+}
+
+export interface ProjectSession {
+  open_files: OpenFile[];
+  active_file: string | null;
+  preview_file: string | null;
+  preview_page: number | null;
+}
+
+export const getSession = (): Promise<ProjectSession> => invoke<ProjectSession>("session_get");
+
+export const saveSession = (session: ProjectSession): Promise<void> => invoke("session_save", { session });
@@ -0,0 +1,54 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export interface EditorSettings {
+  font_family: string;
+  font_size: number;
+  tab_size: number;
+  word_wrap: boolean;
+  line_numbers: boolean;
+  minimap: boolean;
+}
+
+export interface PreviewSettings {
+  compile_debounce_ms: number;
+  follow_cursor: boolean;
+  compile_on_open: boolean;
+  compile_timeout_secs: number | null;
+}
+
+/** Payload of the `settings_changed` event. */
+export interface AppSettings {
+  editor: EditorSettings;
+  preview: PreviewSettings;
+  autosave_interval_secs: number | null;
+  default_export_dir: string | null;
+  reopen_last_project: boolean;
+  continuation_endpoint: string | null;
+}
+
+export const getSettings = (): Promise<AppSettings> => invoke<AppSettings>("settings_get");
+
+export const setSettings = (settings: AppSettings): Promise<void> => invoke("settings_set", { settings });
+
+export const exportSettings = (path: string): Promise<void> => invoke("settings_export", { path });
+
+export const importSettings = (path: string): Promise<string[]> =>
+  invoke<string[]>("settings_import", { path });
+
+export interface LongOperationSettings {
+  threshold_secs: number;
+  notification: boolean;
+  sound: boolean;
+}
+
+export interface LongOperationFinishedEvent {
+  operation: "compile" | "export";
+  duration_ms: number;
+  success: boolean;
+}
+
+export const getLongOperationSettings = (): Promise<LongOperationSettings> =>
+  invoke<LongOperationSettings>("long_operation_settings_get");
+
+export const setLongOperationSettings = (settings: LongOperationSettings): Promise<void> =>
+  invoke("long_operation_settings_set", { settings });
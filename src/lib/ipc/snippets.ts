@@ -0,0 +1,37 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export type SnippetScope = "user" | "project";
+
+export interface Snippet {
+  id: string;
+  prefix: string;
+  description: string;
+  body: string;
+}
+
+export interface SnippetPlaceholder {
+  index: number;
+  name: string;
+  start: number;
+  end: number;
+}
+
+export interface SnippetEntry extends Snippet {
+  scope: SnippetScope;
+  placeholders: SnippetPlaceholder[];
+}
+
+export const listSnippets = (): Promise<SnippetEntry[]> => invoke<SnippetEntry[]>("snippets_list");
+
+export const createSnippet = (
+  scope: SnippetScope,
+  prefix: string,
+  body: string,
+  description?: string
+): Promise<SnippetEntry> => invoke<SnippetEntry>("snippets_create", { scope, prefix, description, body });
+
+export const updateSnippet = (scope: SnippetScope, snippet: Snippet): Promise<SnippetEntry> =>
+  invoke<SnippetEntry>("snippets_update", { scope, snippet });
+
+export const deleteSnippet = (scope: SnippetScope, id: string): Promise<void> =>
+  invoke("snippets_delete", { scope, id });
@@ -1,6 +1,8 @@
 import { invoke } from "@tauri-apps/api/core";
 
 export interface TypstCompileEvent {
+  /** The world revision that was compiled. Events with an older version are stale. */
+  version: number;
   document: TypstDocument | null;
   diagnostics: TypstSourceDiagnostic[] | null;
 }
@@ -20,13 +22,27 @@ export interface TypstSourceDiagnostic {
   severity: TypstDiagnosticSeverity;
   message: string;
   hints: string[];
+  explanation: TypstErrorExplanation | null;
+}
+
+/** A fix for a diagnostic. `replace` replaces the diagnostic's range. */
+export type TypstQuickFix = { kind: "create_file"; path: string } | { kind: "replace"; text: string };
+
+export interface TypstErrorExplanation {
+  summary: string;
+  suggestions: string[];
+  links: string[];
+  fixes: TypstQuickFix[];
 }
 
 export interface TypstRenderResponse {
   image: string;
   width: number;
   height: number;
-  nonce: number;
+  /** The version of the document the page was rendered from. */
+  version: number;
+  /** An SVG of the page's size with the preview decorations, if any are enabled. */
+  overlay?: string | null;
 }
 
 export enum TypstCompletionKind {
@@ -36,6 +52,7 @@ export enum TypstCompletionKind {
   Constant = 4,
   Symbol = 5,
   Type = 6,
+  Snippet = 7,
 }
 
 export interface TypstCompletion {
@@ -53,8 +70,136 @@ export interface TypstCompleteResponse {
 export const compile = (path: string, content: string, requestId: number, mainPath?: string): Promise<TypstRenderResponse> =>
   invoke<TypstRenderResponse>("typst_compile", { path, content, mainPath, requestId });
 
-export const render = (page: number, scale: number, nonce: number): Promise<TypstRenderResponse> =>
-  invoke<TypstRenderResponse>("typst_render", { page, scale, nonce });
+export const render = (page: number, scale: number): Promise<TypstRenderResponse> =>
+  invoke<TypstRenderResponse>("typst_render", { page, scale });
+
+export interface TypstPageRenderedEvent extends TypstRenderResponse {
+  page: number;
+}
+
+/**
+ * Reports the pages the preview shows, `first` to `last` inclusive. They are rendered
+ * first, then their neighbors, each arriving in a `page_rendered` event.
+ */
+export const renderVisible = (first: number, last: number, scale: number): Promise<void> =>
+  invoke("typst_render_visible", { first, last, scale });
+
+/** Where a link leads: a URL, or a page index and a point in pt. */
+export type LinkTarget =
+  | { kind: "url"; url: string }
+  | { kind: "position"; page: number; x: number; y: number };
+
+export interface PageLink {
+  /** The clickable area, in points on the page. */
+  rect: { x: number; y: number; width: number; height: number };
+  target: LinkTarget;
+}
+
+export const pageLinks = (page: number): Promise<PageLink[]> =>
+  invoke<PageLink[]>("typst_page_links", { page });
+
+export interface PositionedText {
+  text: string;
+  /** In points on the page. */
+  bounds: { x: number; y: number; width: number; height: number };
+  /** One box per character of `text`. */
+  boxes: { x: number; y: number; width: number; height: number }[];
+  size: number;
+  /** The file and byte range the text comes from. */
+  source: { path: string; range: { start: number; end: number } } | null;
+}
+
+/** The text runs of a page, eg. to select and copy text in the preview. */
+export const pageText = (page: number): Promise<PositionedText[]> =>
+  invoke<PositionedText[]>("typst_page_text", { page });
+
+/** Where the text of lines `start_line` to `end_line` (1-based) starts in the document. */
+export interface SyncAnchor {
+  start_line: number;
+  end_line: number;
+  page: number;
+  /** In points from the top of the page. */
+  y: number;
+}
+
+/** Maps the lines of a file to the document, sorted by line, for scroll sync. */
+export const syncMap = (path: string): Promise<SyncAnchor[]> =>
+  invoke<SyncAnchor[]>("typst_sync_map", { path });
+
+export interface TypstTileResponse {
+  /** A `data:image/png` URL. */
+  image: string;
+  /** The part of the page rendered, in points, clamped to the page. */
+  rect: { x: number; y: number; width: number; height: number };
+  width: number;
+  height: number;
+  version: number;
+}
+
+/**
+ * Rasterizes only `rect` of a page, in points from its top left corner, at `scale`
+ * pixels per point. For zoom levels too high to render the whole page.
+ */
+export const renderTile = (
+  page: number,
+  rect: { x: number; y: number; width: number; height: number },
+  scale: number
+): Promise<TypstTileResponse> => invoke<TypstTileResponse>("typst_render_tile", { page, rect, scale });
+
+/** The last compiled document as a PDF, eg. for pdf.js. */
+export const previewPdfBytes = (): Promise<ArrayBuffer> => invoke<ArrayBuffer>("preview_pdf_bytes");
+
+/** The version of the document the preview renders from. */
+export const getCurrentVersion = (): Promise<number> => invoke<number>("typst_current_version");
+
+export interface ProjectDiagnostic {
+  severity: TypstDiagnosticSeverity;
+  message: string;
+  hints: string[];
+  /** Line and column (1-indexed), null without a place in the file. */
+  start: [number, number] | null;
+  end: [number, number] | null;
+}
+
+export interface FileDiagnostics {
+  /** Relative to the project root, or prefixed with the package for package files. */
+  path: string | null;
+  errors: number;
+  warnings: number;
+  diagnostics: ProjectDiagnostic[];
+}
+
+/** Payload of the `project_diagnostics` event, sent after each compile. */
+export interface ProjectDiagnosticsEvent {
+  version: number;
+  errors: number;
+  warnings: number;
+  files: FileDiagnostics[];
+}
+
+/** Compiles the main file and returns the diagnostics of every file of the project. */
+export const projectDiagnostics = (): Promise<ProjectDiagnosticsEvent> =>
+  invoke<ProjectDiagnosticsEvent>("diagnostics_project");
+
+export interface TypstSnippetResponse {
+  image: string;
+  width: number;
+  height: number;
+}
+
+export const renderSnippet = (code: string, inline: boolean, path?: string): Promise<TypstSnippetResponse> =>
+  invoke<TypstSnippetResponse>("typst_render_snippet", { code, inline, path });
+
+export interface TypstFilePreview {
+  page_svgs: string[];
+  width: number;
+  height: number;
+  warnings: string[];
+}
+
+/** Compiles a single file for a quick look, without opening it as a project. */
+export const previewFile = (path: string): Promise<TypstFilePreview> =>
+  invoke<TypstFilePreview>("preview_file", { path });
 
 export const autocomplete = (
   path: string,
@@ -66,11 +211,22 @@ export const autocomplete = (
 
 export interface TypstJump {
   filepath: string;
-  start: [number, number] | null; // line, column (1-indexed)
+  /** The start of the clicked syntax node, as line and column (1-indexed). */
+  start: [number, number] | null;
   end: [number, number] | null;
+  /** Where exactly the click was. */
+  cursor?: [number, number] | null;
   text?: string;
   offset?: number;
   node_kind?: string;
+  /** The heading, figure, equation or text around the click. */
+  element?: TypstJumpElement | null;
+}
+
+export interface TypstJumpElement {
+  kind: "heading" | "figure" | "equation" | "text";
+  start: [number, number];
+  end: [number, number];
 }
 
 export const jump = (page: number, x: number, y: number): Promise<TypstJump | null> =>
@@ -109,3 +265,93 @@ export const jumpFromCursor = (
 
 export const getDocumentSources = (): Promise<string[]> =>
   invoke<string[]>("typst_get_document_sources");
+
+export type TypstLintFix = { kind: "mirror_url"; url: string };
+
+export interface TypstLint {
+  range: { start: number; end: number };
+  message: string;
+  fix: TypstLintFix | null;
+}
+
+export const lint = (path: string, content: string): Promise<TypstLint[]> =>
+  invoke<TypstLint[]>("typst_lint", { path, content });
+
+export interface PreviewTheme {
+  dark: boolean;
+  accent?: string;
+}
+
+export const setPreviewTheme = (theme: PreviewTheme | null): Promise<void> =>
+  invoke("typst_set_preview_theme", { theme });
+
+/** Renders the preview pages with inverted colors, keeping the colors of images. */
+export const setDarkPreview = (enabled: boolean): Promise<void> =>
+  invoke("typst_set_dark_preview", { enabled });
+
+/** Guides drawn over the preview pages only. Lengths are in millimeters. */
+export interface PreviewDecorations {
+  grid_mm: number | null;
+  margin_ruler: boolean;
+  columns: number;
+  column_gutter_mm: number;
+  color: string;
+}
+
+export const getPreviewDecorations = (): Promise<PreviewDecorations> =>
+  invoke<PreviewDecorations>("typst_get_preview_decorations");
+
+export const setPreviewDecorations = (decorations: PreviewDecorations): Promise<void> =>
+  invoke("typst_set_preview_decorations", { decorations });
+
+export interface PreviewToggle {
+  name: string;
+  default: boolean;
+  value: boolean;
+}
+
+export const listToggles = (): Promise<PreviewToggle[]> =>
+  invoke<PreviewToggle[]>("typst_list_toggles");
+
+export const setToggle = (name: string, value: boolean | null): Promise<void> =>
+  invoke("typst_set_toggle", { name, value });
+
+export const setSeed = (seed: number | null): Promise<void> =>
+  invoke("typst_set_seed", { seed });
+
+export interface StageTiming {
+  name: string;
+  duration_ms: number;
+  count: number;
+}
+
+export interface Hotspot {
+  name: string;
+  path: string;
+  line: number;
+  duration_ms: number;
+  count: number;
+}
+
+/** Payload of the `compile_profile` event, sent after each compile while profiling. */
+export interface CompileProfileEvent {
+  version: number;
+  total_ms: number;
+  compile_ms: number;
+  stages: StageTiming[];
+  hotspots: Hotspot[];
+  cache: {
+    files_accessed: number;
+    pages_rendered: number;
+    pages_reused: number;
+  };
+}
+
+/** Times every preview compile, reported through `compile_profile` events. */
+export const setCompileProfiling = (enabled: boolean): Promise<void> =>
+  invoke("typst_profile", { enabled });
+
+export const suggestContinuation = (path: string, content: string, offset: number): Promise<string | null> =>
+  invoke<string | null>("typst_suggest_continuation", { path, content, offset });
+
+export const setMenuDirty = (dirty: boolean): Promise<void> => invoke("menu_set_dirty", { dirty });
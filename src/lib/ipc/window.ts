@@ -0,0 +1,27 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { Project } from "../stores";
+import type { TypstJump } from "./typst";
+
+/** The project of this window, which a new tab may have before its page loaded. */
+export const currentProject = (): Promise<Project | null> => invoke<Project | null>("project_current");
+
+/** Opens a new window, as a tab on macOS, showing the project at `path` if given. */
+export const openTab = (path?: string): Promise<void> => invoke("window_new_tab", { path });
+
+/** Opens the project at `path` in a window of its own, or focuses the window showing it. */
+export const openProjectInNewWindow = (path: string): Promise<void> =>
+  invoke("open_project_in_new_window", { path });
+
+/** The location of the `typstudio://` link this window was opened for, taken once its project loaded. */
+export const takeDeepLinkLocation = (): Promise<TypstJump | null> =>
+  invoke<TypstJump | null>("deep_link_take_location");
+
+/** The folders files are looked up in: the project's own, then the attached ones. */
+export const listProjectRoots = (): Promise<string[]> => invoke<string[]>("project_roots");
+
+/** Attaches a folder, eg. a shared styles repository, whose files appear wherever the project has none. */
+export const attachProjectRoot = (path: string): Promise<string[]> =>
+  invoke<string[]>("project_attach_root", { path });
+
+export const detachProjectRoot = (path: string): Promise<string[]> =>
+  invoke<string[]>("project_detach_root", { path });
@@ -0,0 +1,25 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export interface TextEdit {
+  range: { start: number; end: number };
+  text: string;
+}
+
+export interface WorkspaceFileEdit {
+  path: string;
+  edits: TextEdit[];
+}
+
+export interface WorkspaceUndoResponse {
+  label: string;
+  paths: string[];
+}
+
+export const applyWorkspaceEdit = (label: string, changes: WorkspaceFileEdit[]): Promise<string[]> =>
+  invoke<string[]>("workspace_edit_apply", { label, changes });
+
+export const undoWorkspaceEdit = (): Promise<WorkspaceUndoResponse> =>
+  invoke<WorkspaceUndoResponse>("workspace_edit_undo");
+
+export const peekWorkspaceEdit = (): Promise<string | null> =>
+  invoke<string | null>("workspace_edit_peek");
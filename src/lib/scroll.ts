@@ -1,5 +1,6 @@
 
 import type { editor } from "monaco-editor";
+import type { SyncAnchor } from "./ipc";
 
 export interface PreviewPosition {
     page: number;
@@ -201,7 +202,7 @@ export const getPreviewToEditorTarget = async (
         const sameFileResults = validResults.filter(r => r.filepath === filepath);
         if (sameFileResults.length === 0) return null;
         
-        const lines = sameFileResults.map(r => r.start![0]);
+        const lines = sameFileResults.map(r => (r.cursor ?? r.start!)[0]);
         const sum = lines.reduce((a, b) => a + b, 0);
         
         return {
@@ -222,3 +223,31 @@ export const getPreviewToEditorTargetLine = async (
     const target = await getPreviewToEditorTarget(container, pagesContainer, effectiveScale);
     return target?.line ?? null;
 }
+
+/** The preview position of `line` in a sync map: the last anchor starting at or before it. */
+export const syncPreviewPosition = (map: SyncAnchor[], line: number): PreviewPosition | null => {
+    let low = 0;
+    let high = map.length - 1;
+    let found: SyncAnchor | null = null;
+    while (low <= high) {
+        const mid = (low + high) >> 1;
+        if (map[mid].start_line <= line) {
+            found = map[mid];
+            low = mid + 1;
+        } else {
+            high = mid - 1;
+        }
+    }
+    return found ? { page: found.page, x: 0, y: found.y } : null;
+};
+
+/** The line of a sync map closest above a preview position. */
+export const syncLine = (map: SyncAnchor[], page: number, y: number): number | null => {
+    let best: SyncAnchor | null = null;
+    for (const anchor of map) {
+        const before = anchor.page < page || (anchor.page === page && anchor.y <= y);
+        const closer = !best || anchor.page > best.page || (anchor.page === best.page && anchor.y > best.y);
+        if (before && closer) best = anchor;
+    }
+    return best ? best.start_line : null;
+};
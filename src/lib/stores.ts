@@ -1,15 +1,29 @@
 import { writable } from "svelte/store";
 import { getCurrentWindow } from "@tauri-apps/api/window";
-import type { TypstSourceDiagnostic } from "./ipc";
+import { listen } from "@tauri-apps/api/event";
+import type { GitStatusEntry, TypstSourceDiagnostic } from "./ipc";
+import {
+  addRecentProject,
+  importRecentProjects,
+  listRecentProjects,
+  pinRecentProject,
+  removeRecentProject,
+  type RecentProject,
+} from "./ipc/recent";
 
 const TYPST_EXTENSIONS = [".typ"];
 
 export interface Project {
   root: string;
+  /** Set for an implicit project opened from a lone `.typ` file. */
+  single_file?: string | null;
 }
 
 export const project = writable<Project | null>(null);
 
+/** The git status of the project's files, by project path such as `/main.typ`. */
+export const gitStatus = writable<Map<string, GitStatusEntry>>(new Map());
+
 export interface OutlineItem {
   type: "heading" | "figure" | "table" | "list" | "include";
   level: number;
@@ -237,68 +251,51 @@ const createShell = () => {
 
 export const shell = createShell();
 
-export interface RecentProject {
-  path: string;
-  name: string;
-  lastOpened: number;
-}
-
-const RECENT_PROJECTS_KEY = "typstudio_recent_projects";
-const MAX_RECENT_PROJECTS = 10;
+/** Where the list was kept before the backend owned it. */
+const LEGACY_RECENT_PROJECTS_KEY = "typstudio_recent_projects";
 
-const loadRecentProjects = (): RecentProject[] => {
+/** Moves the list kept in local storage to the backend, once. */
+const importLegacyRecentProjects = async () => {
+  const stored = localStorage.getItem(LEGACY_RECENT_PROJECTS_KEY);
+  if (!stored) return;
+  let projects: { path: string; lastOpened: number }[];
   try {
-    const stored = localStorage.getItem(RECENT_PROJECTS_KEY);
-    if (stored) {
-      return JSON.parse(stored);
-    }
-  } catch (e) {
-    console.error("Failed to load recent projects:", e);
-  }
-  return [];
-};
-
-const saveRecentProjects = (projects: RecentProject[]) => {
-  try {
-    localStorage.setItem(RECENT_PROJECTS_KEY, JSON.stringify(projects));
-  } catch (e) {
-    console.error("Failed to save recent projects:", e);
+    projects = JSON.parse(stored);
+  } catch {
+    localStorage.removeItem(LEGACY_RECENT_PROJECTS_KEY);
+    return;
   }
+  await importRecentProjects(
+    projects.map((p) => ({ path: p.path, last_opened: p.lastOpened }))
+  );
+  localStorage.removeItem(LEGACY_RECENT_PROJECTS_KEY);
 };
 
 const createRecentProjects = () => {
-  const { subscribe, set, update } = writable<RecentProject[]>(loadRecentProjects());
+  const { subscribe, set } = writable<RecentProject[]>([]);
+
+  // The backend owns the list; it changes when projects open and through the menu.
+  importLegacyRecentProjects()
+    .catch((e) => console.error("Failed to import recent projects:", e))
+    .then(listRecentProjects)
+    .then(set)
+    .catch((e) => console.error("Failed to load recent projects:", e));
+  listen<RecentProject[]>("recent_projects_changed", ({ payload }) => set(payload));
 
   return {
     subscribe,
     addProject(path: string) {
-      update((projects) => {
-        const name = path.split("/").pop() || path;
-        const filtered = projects.filter((p) => p.path !== path);
-        const newProjects = [
-          { path, name, lastOpened: Date.now() },
-          ...filtered,
-        ].slice(0, MAX_RECENT_PROJECTS);
-        saveRecentProjects(newProjects);
-        return newProjects;
-      });
+      addRecentProject(path).then(set).catch(console.error);
     },
     removeProject(path: string) {
-      update((projects) => {
-        const newProjects = projects.filter((p) => p.path !== path);
-        saveRecentProjects(newProjects);
-        return newProjects;
-      });
+      removeRecentProject(path).then(set).catch(console.error);
     },
-    clear() {
-      set([]);
-      console.log("Recent projects cleared");
-      saveRecentProjects([]);
+    pinProject(path: string, pinned: boolean) {
+      pinRecentProject(path, pinned).then(set).catch(console.error);
     },
   };
 };
 
-
 export const recentProjects = createRecentProjects();
 
 export const pendingScroll = writable<{